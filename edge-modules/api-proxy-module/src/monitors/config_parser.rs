@@ -302,6 +302,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn hierarchy_routes_require_client_certificate() {
+        // The token server behind auth_request accepts every request, so only the certificate
+        // check keeps devices outside the hierarchy from reading it.
+        let template = include_str!("../../templates/nginx_default_config.conf");
+        let lines: Vec<&str> = template.lines().map(str::trim).collect();
+
+        let mut locations = 0;
+        for (i, line) in lines.iter().enumerate() {
+            if line.starts_with("location") && line.contains("/iotedge/hierarchy") {
+                locations += 1;
+                assert_eq!(
+                    &lines[i + 1..i + 4],
+                    &["if ($ssl_client_verify != SUCCESS) {", "return 403;", "}"],
+                    "{} does not check the client certificate first",
+                    line
+                );
+            }
+        }
+
+        assert_eq!(locations, 3);
+    }
+
     #[test]
     fn env_var_tests() {
         //All environment variable tests are grouped in one test.
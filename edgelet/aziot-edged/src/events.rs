@@ -0,0 +1,129 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use edgelet_core::{EventBus, ModuleEvent, ModuleRuntime};
+
+// Delay before subscribing again after the module runtime's event stream ends.
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(5);
+
+/// Publishes the module runtime's events to `events` for clients of the management API.
+/// When the stream ends, for example because the runtime restarted, edged subscribes again
+/// and asks for the events it may have missed.
+pub(crate) async fn forward(runtime: impl ModuleRuntime, events: EventBus) {
+    let mut replay = Replay::default();
+
+    loop {
+        match runtime.events(replay.subscribe()).await {
+            Ok(mut stream) => {
+                while let Some(event) = stream.recv().await {
+                    if replay.is_new(&event) {
+                        events.publish(event);
+                    }
+                }
+
+                log::info!("Module event stream ended; subscribing again...");
+            }
+
+            Err(err) => log::warn!("Failed to subscribe to module events: {err}"),
+        }
+
+        tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+    }
+}
+
+/// Tells the events that the runtime replays after subscribing again from new ones. Event
+/// times have a resolution of one second, so the events published at the latest time are
+/// remembered, and only exact copies of them are dropped from the replay.
+#[derive(Debug, Default)]
+struct Replay {
+    last_time: Option<DateTime<Utc>>,
+
+    /// The events published at `last_time`.
+    published: Vec<ModuleEvent>,
+
+    /// The events published at `last_time` that have not been replayed yet, while replaying.
+    replaying: Option<Vec<ModuleEvent>>,
+}
+
+impl Replay {
+    /// Starts a replay, and returns the time to ask the runtime for events since.
+    fn subscribe(&mut self) -> Option<DateTime<Utc>> {
+        self.replaying = self.last_time.map(|_| self.published.clone());
+
+        self.last_time
+    }
+
+    fn is_new(&mut self, event: &ModuleEvent) -> bool {
+        let time = *event.time();
+
+        if let Some(last_time) = self.last_time
+            && let Some(replaying) = &mut self.replaying
+        {
+            if time < last_time {
+                return false;
+            }
+
+            if time > last_time {
+                // The replay has caught up.
+                self.replaying = None;
+            } else if let Some(index) = replaying.iter().position(|replayed| replayed == event) {
+                replaying.swap_remove(index);
+                return false;
+            }
+        }
+
+        match self.last_time {
+            Some(last_time) if time < last_time => {}
+            Some(last_time) if time == last_time => self.published.push(event.clone()),
+            _ => {
+                self.last_time = Some(time);
+                self.published = vec![event.clone()];
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use edgelet_core::EventKind;
+
+    use super::*;
+
+    fn event(secs: i64, kind: EventKind, module: &str) -> ModuleEvent {
+        ModuleEvent::new(DateTime::from_timestamp(secs, 0).unwrap(), kind)
+            .with_module(Some(module.to_string()))
+    }
+
+    #[test]
+    fn events_in_the_same_second_are_kept() {
+        let mut replay = Replay::default();
+        assert_eq!(replay.subscribe(), None);
+
+        let died = event(10, EventKind::Died, "m");
+        let started = event(10, EventKind::Started, "m");
+        let stopped = event(10, EventKind::Stopped, "n");
+        assert!(replay.is_new(&event(9, EventKind::Created, "m")));
+        assert!(replay.is_new(&died));
+        assert!(replay.is_new(&started));
+
+        // After subscribing again, the events already published are dropped from the replay,
+        // but a new one in the same second is not.
+        assert_eq!(
+            replay.subscribe(),
+            Some(DateTime::from_timestamp(10, 0).unwrap())
+        );
+        assert!(!replay.is_new(&event(9, EventKind::Created, "m")));
+        assert!(!replay.is_new(&started));
+        assert!(replay.is_new(&stopped));
+        assert!(!replay.is_new(&died));
+        assert!(replay.is_new(&event(11, EventKind::Started, "n")));
+
+        // Once the replay has caught up, repeated events are new.
+        assert!(replay.is_new(&event(11, EventKind::Started, "n")));
+    }
+}
@@ -1,20 +1,26 @@
 // Copyright (c) Microsoft. All rights reserved.
 
 mod error;
+mod events;
 mod management;
+mod metrics;
 mod provision;
+mod restart;
 mod watchdog;
 mod workload_manager;
 
 use std::sync::atomic;
 
-use edgelet_core::{ModuleRuntime, WatchdogAction, module::ModuleAction};
+use edgelet_core::{EventBus, ModuleRuntime, RestartTracker, WatchdogAction, module::ModuleAction};
 use edgelet_docker::{ImagePruneData, MakeModuleRuntime};
 use edgelet_image_cleanup::image_gc;
-use edgelet_settings::RuntimeSettings;
+use edgelet_settings::{RuntimeBackend, RuntimeSettings};
 
 use crate::{error::Error as EdgedError, workload_manager::WorkloadManager};
 
+/// The modules that were stopped on request, which edged does not restart.
+const STOPPED_MODULES_FILENAME: &str = "stopped_modules.json";
+
 #[tokio::main]
 async fn main() {
     let version = edgelet_core::version_with_source_version();
@@ -42,7 +48,6 @@ async fn main() {
     }
 }
 
-#[allow(clippy::too_many_lines)]
 async fn run() -> Result<(), EdgedError> {
     let settings = edgelet_settings::docker::Settings::new().map_err(EdgedError::settings_err)?;
 
@@ -96,17 +101,103 @@ async fn run() -> Result<(), EdgedError> {
     let image_use_data = ImagePruneData::new(&gc_dir, gc_settings.clone())
         .map_err(|err| EdgedError::from_err("Failed to set up image garbage collection", err))?;
 
-    let runtime = edgelet_docker::DockerModuleRuntime::make_runtime(
-        &settings,
-        create_socket_channel_snd.clone(),
-        image_use_data.clone(),
+    let restart_tracker = RestartTracker::load(
+        settings.watchdog().restart_backoff().clone(),
+        settings.homedir().join(STOPPED_MODULES_FILENAME),
+    );
+
+    let context = RunContext {
+        settings,
+        cache_dir,
+        identity_client,
+        device_info,
+        create_socket_channel_snd,
+        create_socket_channel_rcv,
+        gc_settings,
+        image_use_data,
+        restart_tracker,
+    };
+
+    log::info!(
+        "Using {} module runtime",
+        context.settings.runtime_backend()
+    );
+
+    match context.settings.runtime_backend() {
+        RuntimeBackend::Moby => {
+            let runtime =
+                make_runtime::<edgelet_docker::DockerModuleRuntime<http_common::Connector>>(
+                    &context,
+                )
+                .await?;
+            run_with_runtime(context, runtime).await
+        }
+
+        RuntimeBackend::Cri => {
+            let runtime = make_runtime::<edgelet_cri::CriModuleRuntime>(&context).await?;
+            run_with_runtime(context, runtime).await
+        }
+    }
+}
+
+/// State set up before the module runtime is initialized.
+struct RunContext {
+    settings: edgelet_settings::docker::Settings,
+    cache_dir: std::path::PathBuf,
+    identity_client: aziot_identity_client_async::Client,
+    device_info: aziot_identity_common::AzureIoTSpec,
+    create_socket_channel_snd: tokio::sync::mpsc::UnboundedSender<ModuleAction>,
+    create_socket_channel_rcv: tokio::sync::mpsc::UnboundedReceiver<ModuleAction>,
+    gc_settings: edgelet_settings::base::image::ImagePruneSettings,
+    image_use_data: ImagePruneData,
+    restart_tracker: RestartTracker,
+}
+
+async fn make_runtime<R>(context: &RunContext) -> Result<R::ModuleRuntime, EdgedError>
+where
+    R: MakeModuleRuntime<Settings = edgelet_settings::docker::Settings>,
+{
+    R::make_runtime(
+        &context.settings,
+        context.create_socket_channel_snd.clone(),
+        context.image_use_data.clone(),
+        context.restart_tracker.clone(),
     )
     .await
-    .map_err(|err| EdgedError::from_err("Failed to initialize module runtime", err))?;
+    .map_err(|err| EdgedError::from_err("Failed to initialize module runtime", err))
+}
+
+#[allow(clippy::too_many_lines)]
+async fn run_with_runtime<M>(context: RunContext, runtime: M) -> Result<(), EdgedError>
+where
+    M: ModuleRuntime<Config = edgelet_settings::DockerConfig> + Clone + Send + Sync + 'static,
+{
+    let RunContext {
+        settings,
+        cache_dir,
+        identity_client,
+        device_info,
+        create_socket_channel_snd,
+        create_socket_channel_rcv,
+        gc_settings,
+        image_use_data,
+        restart_tracker,
+    } = context;
 
     let (watchdog_tx, watchdog_rx) =
         tokio::sync::mpsc::unbounded_channel::<edgelet_core::WatchdogAction>();
 
+    let events = EventBus::default();
+
+    let (key_rotation_tx, key_rotation_rx) =
+        tokio::sync::mpsc::unbounded_channel::<edgelet_core::EncryptionKeyRotation>();
+
+    let (image_prune_tx, image_prune_rx) =
+        tokio::sync::mpsc::unbounded_channel::<edgelet_core::ImagePruneRequest>();
+
+    let (cert_inventory_tx, cert_inventory_rx) =
+        tokio::sync::mpsc::unbounded_channel::<edgelet_core::CertInventoryRequest>();
+
     // Keep track of running tasks to determine when all server tasks have shut down.
     // Workload and management API each have one task, so start with 2 tasks total.
     let tasks = atomic::AtomicUsize::new(2);
@@ -120,6 +211,9 @@ async fn run() -> Result<(), EdgedError> {
         tasks.clone(),
         create_socket_channel_snd,
         watchdog_tx.clone(),
+        &events,
+        key_rotation_rx,
+        cert_inventory_rx,
         settings.iotedge_max_requests().workload,
     )
     .await?;
@@ -152,6 +246,10 @@ async fn run() -> Result<(), EdgedError> {
         &settings,
         runtime.clone(),
         watchdog_tx.clone(),
+        events.clone(),
+        key_rotation_tx,
+        image_prune_tx,
+        cert_inventory_tx,
         tasks.clone(),
         settings.iotedge_max_requests().management,
     )
@@ -170,6 +268,7 @@ async fn run() -> Result<(), EdgedError> {
         runtime.clone(),
         &identity_client,
         watchdog_rx,
+        &events,
     );
 
     let edge_agent_bootstrap: String = settings.agent().config().image().to_string();
@@ -178,13 +277,44 @@ async fn run() -> Result<(), EdgedError> {
         gc_settings.clone(),
         &runtime,
         image_use_data,
+        image_prune_rx,
     );
 
+    let restarts = restart::run(
+        settings.agent().name().to_string(),
+        runtime.clone(),
+        restart_tracker,
+    );
+
+    let event_forwarder = events::forward(runtime.clone(), events.clone());
+
+    let metrics_listener = {
+        let listen = settings.metrics().listen();
+        let runtime = runtime.clone();
+
+        async move {
+            match listen {
+                Some(listen) => metrics::serve(listen, runtime).await,
+                None => std::future::pending().await,
+            }
+        }
+    };
+
     tokio::select! {
         watchdog_finished = watchdog => {
             log::info!("watchdog finished");
             shutdown_reason = watchdog_finished?;
         },
+        () = restarts => {
+            return Err(EdgedError::new("module restart policy enforcement stopped unexpectedly"));
+        },
+        () = event_forwarder => {
+            return Err(EdgedError::new("module event forwarding stopped unexpectedly"));
+        },
+        metrics_finished = metrics_listener => {
+            metrics_finished?;
+            return Err(EdgedError::new("module metrics listener stopped unexpectedly"));
+        },
         image_gc_finished = image_gc => {
             let err_msg = "image garbage collection stopped unexpectedly";
             image_gc_finished.map_err(|e| EdgedError::from_err(err_msg, e))?;
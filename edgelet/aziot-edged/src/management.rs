@@ -8,6 +8,10 @@ pub(crate) async fn start<M>(
     settings: &impl edgelet_settings::RuntimeSettings,
     runtime: M,
     sender: tokio::sync::mpsc::UnboundedSender<edgelet_core::WatchdogAction>,
+    events: edgelet_core::EventBus,
+    key_rotation: tokio::sync::mpsc::UnboundedSender<edgelet_core::EncryptionKeyRotation>,
+    image_prune: tokio::sync::mpsc::UnboundedSender<edgelet_core::ImagePruneRequest>,
+    cert_inventory: tokio::sync::mpsc::UnboundedSender<edgelet_core::CertInventoryRequest>,
     tasks: std::sync::Arc<std::sync::atomic::AtomicUsize>,
     max_requests: usize,
 ) -> Result<tokio::sync::oneshot::Sender<()>, EdgedError>
@@ -24,6 +28,10 @@ where
         settings.endpoints().aziot_identityd_url(),
         runtime,
         sender,
+        events,
+        key_rotation,
+        image_prune,
+        cert_inventory,
     )
     .map_err(|err| EdgedError::from_err("Invalid Identity Service URL", err))?;
 
@@ -0,0 +1,86 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::net::SocketAddr;
+
+use http_body_util::Full;
+use hyper::body::Bytes;
+use hyper::{Method, Request, Response, StatusCode};
+
+use edgelet_core::ModuleRuntime;
+
+use crate::error::Error as EdgedError;
+
+const METRICS_PATH: &str = "/metrics";
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Serves module metrics in the Prometheus text format at `/metrics` on `listen`. Returns only if
+/// the listener cannot be set up.
+pub(crate) async fn serve<M>(listen: SocketAddr, runtime: M) -> Result<(), EdgedError>
+where
+    M: ModuleRuntime + Clone + Send + Sync + 'static,
+{
+    let listener = tokio::net::TcpListener::bind(listen)
+        .await
+        .map_err(|err| EdgedError::from_err(format!("Failed to listen on {listen}"), err))?;
+
+    log::info!("Serving module metrics on http://{listen}{METRICS_PATH}");
+
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(err) => {
+                log::warn!("Failed to accept metrics connection: {err}");
+                continue;
+            }
+        };
+
+        let runtime = runtime.clone();
+        let service = hyper::service::service_fn(move |req| {
+            let runtime = runtime.clone();
+            async move { Ok::<_, std::convert::Infallible>(respond(&runtime, &req).await) }
+        });
+
+        tokio::spawn(async move {
+            if let Err(err) = hyper::server::conn::http1::Builder::new()
+                .serve_connection(hyper_util::rt::TokioIo::new(stream), service)
+                .await
+            {
+                log::debug!("Metrics connection from {peer} failed: {err}");
+            }
+        });
+    }
+}
+
+async fn respond<M, B>(runtime: &M, req: &Request<B>) -> Response<Full<Bytes>>
+where
+    M: ModuleRuntime,
+{
+    if req.uri().path() != METRICS_PATH {
+        return status(StatusCode::NOT_FOUND);
+    }
+    if req.method() != Method::GET {
+        return status(StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    match runtime.module_metrics().await {
+        Ok(metrics) => Response::builder()
+            .status(StatusCode::OK)
+            .header(hyper::header::CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)
+            .body(Full::new(Bytes::from(edgelet_core::metrics::prometheus(
+                &metrics,
+            ))))
+            .expect("cannot fail to build hyper response"),
+
+        Err(err) => {
+            log::warn!("Failed to collect module metrics: {err:?}");
+            status(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+fn status(status: StatusCode) -> Response<Full<Bytes>> {
+    Response::builder()
+        .status(status)
+        .body(Full::default())
+        .expect("cannot fail to build hyper response")
+}
@@ -0,0 +1,78 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::time::{Duration, SystemTime};
+
+use edgelet_core::{Module, ModuleRuntime, RestartDecision, RestartTracker};
+
+// Restart policies are checked more often than the watchdog runs so that the back-off delays,
+// which start at a few seconds, are honored reasonably closely.
+const RESTART_CHECK_PERIOD: Duration = Duration::from_secs(5);
+
+/// Restarts modules that have exited according to their restart policies. edgeAgent is
+/// excluded, as it is always kept running by the watchdog.
+pub(crate) async fn run(
+    agent_name: String,
+    runtime: impl ModuleRuntime<Config = edgelet_settings::DockerConfig>,
+    restart_tracker: RestartTracker,
+) {
+    let mut timer = tokio::time::interval(RESTART_CHECK_PERIOD);
+    timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        timer.tick().await;
+
+        let modules = match runtime.list_with_details().await {
+            Ok(modules) => modules,
+            Err(err) => {
+                log::warn!("Failed to list modules for restart policy check: {err}");
+
+                continue;
+            }
+        };
+
+        for (module, state) in modules {
+            let name = module.name();
+
+            if name == agent_name {
+                continue;
+            }
+
+            let policy = edgelet_core::restart::restart_policy(
+                module.config().create_options().labels.as_ref(),
+            );
+
+            match restart_tracker.evaluate(name, policy, &state, SystemTime::now().into()) {
+                RestartDecision::None | RestartDecision::Wait(_) => (),
+
+                RestartDecision::Restart => {
+                    log::info!(
+                        "Module {name} is {} with restart policy {policy}; restarting (attempt {})...",
+                        state.status(),
+                        state.restart_count() + 1
+                    );
+
+                    // Unhealthy modules are still running, so they need to be stopped first.
+                    let result = if state.status().is_running() {
+                        runtime.restart(name).await
+                    } else {
+                        runtime.start(name).await
+                    };
+
+                    // Failed attempts count towards the back-off as well.
+                    if let Err(err) = result {
+                        log::warn!("Failed to restart module {name}: {err}");
+                    }
+
+                    restart_tracker.record_restart(name);
+                }
+
+                RestartDecision::GiveUp => {
+                    log::warn!(
+                        "Module {name} has been restarted {} times without staying up; it will not be restarted again until started explicitly",
+                        state.restart_count()
+                    );
+                }
+            }
+        }
+    }
+}
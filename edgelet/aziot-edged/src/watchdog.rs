@@ -8,9 +8,10 @@ use crate::error::Error as EdgedError;
 pub(crate) async fn run_until_shutdown(
     settings: edgelet_settings::docker::Settings,
     device_info: &aziot_identity_common::AzureIoTSpec,
-    runtime: edgelet_docker::DockerModuleRuntime<http_common::Connector>,
+    runtime: impl ModuleRuntime<Config = edgelet_settings::DockerConfig>,
     identity_client: &aziot_identity_client_async::Client,
     mut action_rx: tokio::sync::mpsc::UnboundedReceiver<edgelet_core::WatchdogAction>,
+    events: &edgelet_core::EventBus,
 ) -> Result<edgelet_core::WatchdogAction, EdgedError> {
     // Run the watchdog every 60 seconds while waiting for any running task to send a
     // watchdog action.
@@ -51,7 +52,16 @@ pub(crate) async fn run_until_shutdown(
                 log::info!("{action}");
 
                 if let edgelet_core::WatchdogAction::EdgeCaRenewal = action {
-                    restart_modules(&settings, &runtime).await;
+                    events.publish(edgelet_core::ModuleEvent::new(
+                        std::time::SystemTime::now().into(),
+                        edgelet_core::EventKind::EdgeCaRenewed,
+                    ));
+
+                    if settings.edge_ca_rollover().live_reload() {
+                        restart_opted_out_modules(&settings, &runtime).await;
+                    } else {
+                        restart_modules(&settings, &runtime).await;
+                    }
                 } else {
                     log::info!("Watchdog stopped");
 
@@ -76,7 +86,7 @@ pub(crate) async fn run_until_shutdown(
 async fn watchdog(
     settings: &edgelet_settings::docker::Settings,
     device_info: &aziot_identity_common::AzureIoTSpec,
-    runtime: &edgelet_docker::DockerModuleRuntime<http_common::Connector>,
+    runtime: &impl ModuleRuntime<Config = edgelet_settings::DockerConfig>,
     identity_client: &aziot_identity_client_async::Client,
 ) -> Result<(), EdgedError> {
     log::info!("Watchdog checking Edge runtime status");
@@ -101,6 +111,17 @@ async fn watchdog(
                 log::info!("Started Edge runtime module {agent_name}");
             }
 
+            edgelet_core::ModuleStatus::Unhealthy => {
+                log::info!("Edge runtime status is {agent_status}, restarting module now...");
+
+                runtime
+                    .restart(agent_name)
+                    .await
+                    .map_err(|err| EdgedError::from_err("Failed to restart Edge runtime", err))?;
+
+                log::info!("Restarted Edge runtime module {agent_name}");
+            }
+
             edgelet_core::ModuleStatus::Dead | edgelet_core::ModuleStatus::Unknown => {
                 log::info!(
                     "Edge runtime status is {agent_status}, removing and recreating module..."
@@ -123,14 +144,15 @@ async fn watchdog(
 
 async fn restart_modules(
     settings: &edgelet_settings::docker::Settings,
-    runtime: &edgelet_docker::DockerModuleRuntime<http_common::Connector>,
+    runtime: &impl ModuleRuntime<Config = edgelet_settings::DockerConfig>,
 ) {
     let agent_name = settings.agent().name();
 
     // Check if edgeAgent is running. If edgeAgent does not exist or is not running,
-    // return and let the periodic watchdog create and start it.
+    // return and let the periodic watchdog create and start it. An unhealthy edgeAgent is
+    // still running, and its modules still need certificates from the new Edge CA.
     if let Ok((_, agent_status)) = runtime.get(agent_name).await {
-        if agent_status.status() != &edgelet_core::ModuleStatus::Running {
+        if !agent_status.status().is_running() {
             log::info!("Agent not running; skipping module restart");
 
             return;
@@ -177,10 +199,51 @@ async fn restart_modules(
     }
 }
 
+/// Restarts the modules that opted out of live reload, one at a time. Other modules are told
+/// about the renewal over the workload API and keep running.
+async fn restart_opted_out_modules(
+    settings: &edgelet_settings::docker::Settings,
+    runtime: &impl ModuleRuntime<Config = edgelet_settings::DockerConfig>,
+) {
+    let restart_modules = settings.edge_ca_rollover().restart_modules();
+
+    if restart_modules.is_empty() {
+        log::info!("Edge CA renewal does not require any module restarts");
+
+        return;
+    }
+
+    let Ok(modules) = runtime.list().await else {
+        log::warn!("Failed to list modules");
+
+        return;
+    };
+
+    // Names like '$edgeHub' in settings match the module names without the '$'.
+    for name in restart_modules {
+        let Some(module) = modules
+            .iter()
+            .find(|module| module.name() == name.trim_start_matches('$'))
+        else {
+            log::info!("Edge CA renewal skipped restart of {name}, which is not deployed");
+
+            continue;
+        };
+
+        let module_name = module.name();
+
+        if let Err(err) = runtime.restart(module_name).await {
+            log::warn!("Edge CA renewal failed to restart {module_name}: {err}");
+        } else {
+            log::info!("Edge CA renewal restarted {module_name}");
+        }
+    }
+}
+
 async fn create_and_start_agent(
     settings: &edgelet_settings::docker::Settings,
     device_info: &aziot_identity_common::AzureIoTSpec,
-    runtime: &edgelet_docker::DockerModuleRuntime<http_common::Connector>,
+    runtime: &impl ModuleRuntime<Config = edgelet_settings::DockerConfig>,
     identity_client: &aziot_identity_client_async::Client,
 ) -> Result<(), EdgedError> {
     let agent_name = settings.agent().name();
@@ -192,11 +255,13 @@ async fn create_and_start_agent(
 
     log::info!("Creating and starting Edge runtime module {agent_name}...");
 
-    if let edgelet_settings::module::ImagePullPolicy::OnCreate = agent_spec.image_pull_policy() {
-        edgelet_core::ModuleRegistry::pull(runtime.registry(), agent_spec.config())
-            .await
-            .map_err(|err| EdgedError::from_err("Failed to pull Edge runtime module", err))?;
-    }
+    edgelet_core::get_image(
+        runtime.registry(),
+        agent_spec.config(),
+        agent_spec.image_pull_policy(),
+    )
+    .await
+    .map_err(|err| EdgedError::from_err("Failed to pull Edge runtime module", err))?;
 
     runtime
         .create(agent_spec)
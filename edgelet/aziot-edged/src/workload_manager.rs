@@ -32,6 +32,9 @@ where
         tasks: std::sync::Arc<std::sync::atomic::AtomicUsize>,
         create_socket_channel_snd: tokio::sync::mpsc::UnboundedSender<ModuleAction>,
         renewal_tx: tokio::sync::mpsc::UnboundedSender<edgelet_core::WatchdogAction>,
+        events: &edgelet_core::EventBus,
+        key_rotation: tokio::sync::mpsc::UnboundedReceiver<edgelet_core::EncryptionKeyRotation>,
+        cert_inventory: tokio::sync::mpsc::UnboundedReceiver<edgelet_core::CertInventoryRequest>,
         max_requests: usize,
     ) -> Result<(WorkloadManager<M>, tokio::sync::oneshot::Sender<()>), EdgedError> {
         let shutdown_senders: HashMap<String, tokio::sync::oneshot::Sender<()>> = HashMap::new();
@@ -43,9 +46,14 @@ where
 
         let service =
             edgelet_http_workload::Service::new(settings, runtime, renewal_tx, device_info)
-                .map_err(|err| EdgedError::from_err("Invalid service endpoint", err))?;
+                .map_err(|err| EdgedError::from_err("Failed to create workload API", err))?;
 
         service.check_edge_ca().await.map_err(EdgedError::new)?;
+        service.check_master_keys().await.map_err(EdgedError::new)?;
+
+        tokio::spawn(service.clone().forget_deleted_modules(events.clone()));
+        tokio::spawn(service.clone().rotate_encryption_keys(key_rotation));
+        tokio::spawn(service.clone().list_certs(cert_inventory));
 
         let home_dir = settings.homedir().to_path_buf();
 
@@ -164,6 +164,7 @@ fi
 %attr(600, root, root) %{aziot_confdir}/config.toml.edge.template
 %attr(400, %{iotedge_user}, %{iotedge_group}) %{iotedge_confdir}/config.toml.default
 %attr(700, %{iotedge_user}, %{iotedge_group}) %dir %{iotedge_confdir}/config.d
+%attr(755, root, root) %dir %{iotedge_confdir}/checks.d
 %config(noreplace) %{_sysconfdir}/logrotate.d/%{name}
 
 # man
@@ -42,6 +42,17 @@ where
 }
 
 impl ApiError {
+    /// Converts the `errorDetail` of a progress stream, falling back to its JSON text if it is
+    /// not structured as expected.
+    pub fn from_error_detail(detail: serde_json::Value) -> Self {
+        let fallback_msg = detail.to_string();
+
+        serde_json::from_value(detail).unwrap_or(ApiError {
+            code: hyper::StatusCode::INTERNAL_SERVER_ERROR,
+            message: fallback_msg,
+        })
+    }
+
     async fn try_from_response(value: hyper::Response<Incoming>) -> anyhow::Result<Self> {
         let (parts, body) = value.into_parts();
         let error_bytes = body.collect().await?.to_bytes();
@@ -102,6 +113,29 @@ pub trait DockerApi {
         platform: &'a str,
     ) -> BoxFutureResult<'a, ()>;
 
+    fn image_pull<'a>(
+        &'a self,
+        from_image: &'a str,
+        tag: &'a str,
+        platform: &'a str,
+        x_registry_auth: &'a str,
+    ) -> BoxFutureResult<'a, Incoming>;
+
+    /// Loads images from a tarball in the format written by `docker save`, or an OCI image
+    /// layout. Returns the names of the loaded images, or their IDs for images without a name.
+    fn image_load(
+        &self,
+        quiet: bool,
+        body: BoxBody<Bytes, Infallible>,
+    ) -> BoxFutureResult<'_, Vec<String>>;
+
+    fn image_tag<'a>(
+        &'a self,
+        name: &'a str,
+        repo: &'a str,
+        tag: &'a str,
+    ) -> BoxFutureResult<'a, ()>;
+
     fn images_list<'a>(
         &'a self,
         all: bool,
@@ -121,7 +155,17 @@ pub trait DockerApi {
         name: &'a str,
         platform: &'a str,
         body: models::ContainerCreateBody,
-    ) -> BoxFutureResult<'a, ()>;
+    ) -> BoxFutureResult<'a, models::ContainerCreateResponse>;
+
+    /// Streams the output of a container from when it is called. Unless the container has a TTY,
+    /// the stream is multiplexed in the same format as `container_logs`.
+    fn container_attach<'a>(
+        &'a self,
+        id: &'a str,
+        stream: bool,
+        stdout: bool,
+        stderr: bool,
+    ) -> BoxFutureResult<'a, Incoming>;
 
     fn container_delete<'a>(
         &'a self,
@@ -137,6 +181,14 @@ pub trait DockerApi {
         size: bool,
     ) -> BoxFutureResult<'a, models::ContainerInspectResponse>;
 
+    /// Like `container_inspect`, but returns the whole response instead of the fields that
+    /// edgelet reads.
+    fn container_inspect_raw<'a>(
+        &'a self,
+        id: &'a str,
+        size: bool,
+    ) -> BoxFutureResult<'a, serde_json::Value>;
+
     fn container_list<'a>(
         &'a self,
         all: bool,
@@ -145,6 +197,11 @@ pub trait DockerApi {
         filters: &'a str,
     ) -> BoxFutureResult<'a, Vec<models::ContainerSummary>>;
 
+    fn container_prune<'a>(
+        &'a self,
+        filters: &'a str,
+    ) -> BoxFutureResult<'a, models::PruneResponse>;
+
     fn container_restart<'a>(
         &'a self,
         id: &'a str,
@@ -158,6 +215,11 @@ pub trait DockerApi {
         one_shot: bool,
     ) -> BoxFutureResult<'a, serde_json::Value>;
     fn container_stop<'a>(&'a self, id: &'a str, timeout: Option<i32>) -> BoxFutureResult<'a, ()>;
+    fn container_wait<'a>(
+        &'a self,
+        id: &'a str,
+        condition: &'a str,
+    ) -> BoxFutureResult<'a, models::ContainerWaitResponse>;
     fn container_top<'a>(
         &'a self,
         id: &'a str,
@@ -178,10 +240,25 @@ pub trait DockerApi {
 
     fn network_create(&self, network_config: models::NetworkConfig) -> BoxFutureResult<'_, ()>;
 
+    fn system_events<'a>(
+        &'a self,
+        since: &'a str,
+        until: &'a str,
+        filters: &'a str,
+    ) -> BoxFutureResult<'a, Incoming>;
+
     fn network_list<'a>(
         &'a self,
         filters: &'a str,
-    ) -> BoxFutureResult<'a, Vec<serde::de::IgnoredAny>>;
+    ) -> BoxFutureResult<'a, Vec<models::NetworkSummary>>;
+
+    fn network_inspect<'a>(
+        &'a self,
+        id: &'a str,
+        verbose: bool,
+    ) -> BoxFutureResult<'a, serde_json::Value>;
+
+    fn volume_prune<'a>(&'a self, filters: &'a str) -> BoxFutureResult<'a, models::PruneResponse>;
 }
 
 macro_rules! api_call {
@@ -295,6 +372,83 @@ where
         ok : [OK]
     }
 
+    api_call! {
+        image_pull : post "/images/create" -> Incoming ;
+        query : [
+            "fromImage" = (from_image: &'a str),
+            "tag" = (tag: &'a str),
+            "platform" = (platform: &'a str)
+        ] ;
+        header : [
+            "x-registry-auth" = (x_registry_auth: &'a str)
+        ] ;
+        ok : [OK] ;
+        and_then(response) : { Ok(response.into_body()) }
+    }
+
+    fn image_load(
+        &self,
+        quiet: bool,
+        body: BoxBody<Bytes, Infallible>,
+    ) -> BoxFutureResult<'_, Vec<String>> {
+        Box::pin(async move {
+            let query = url::form_urlencoded::Serializer::new(String::new())
+                .append_pair("quiet", &quiet.to_string())
+                .finish();
+            let uri = (self.configuration.uri_composer)(
+                &self.configuration.base_path,
+                &format!("/images/load?{query}"),
+            )?;
+
+            let mut builder =
+                hyper::Request::post(&uri).header(hyper::header::CONTENT_TYPE, "application/x-tar");
+            if let Some(agent) = &self.configuration.user_agent {
+                builder = builder.header(hyper::header::USER_AGENT, agent);
+            }
+            let request = builder.body(body)?;
+
+            // Docker responds only after it has read the whole tarball, so unlike other calls
+            // this one is not bounded by a timeout.
+            let response = self.client.request(request).await?;
+
+            if response.status() != hyper::StatusCode::OK {
+                return Err(anyhow::anyhow!(
+                    ApiError::try_from_response(response).await?
+                ));
+            }
+
+            let response_bytes = response.into_body().collect().await?.to_bytes();
+            let mut loaded = vec![];
+            for info in serde_json::Deserializer::from_slice(&response_bytes)
+                .into_iter::<models::CreateImageInfo>()
+            {
+                let info = info?;
+                if let Some(detail) = info.error_detail {
+                    return Err(anyhow::anyhow!(ApiError::from_error_detail(detail)));
+                }
+
+                // e.g. "Loaded image: ubuntu:24.04" or "Loaded image ID: sha256:..."
+                if let Some(image) = info.stream.as_deref().and_then(|stream| {
+                    stream
+                        .trim()
+                        .strip_prefix("Loaded image: ")
+                        .or_else(|| stream.trim().strip_prefix("Loaded image ID: "))
+                }) {
+                    loaded.push(image.to_owned());
+                }
+            }
+
+            Ok(loaded)
+        })
+    }
+
+    api_call! {
+        image_tag : post "/images/{name}/tag" ;
+        path : [ name: &'a str ] ;
+        query : [ "repo" = (repo: &'a str), "tag" = (tag: &'a str) ] ;
+        ok : [CREATED]
+    }
+
     api_call! {
         images_list : get "/images/json" -> Vec<models::ImageSummary> ;
         query : [ "all" = (all: bool), "filters" = (filters: &'a str), "digests" = (digests: bool)] ;
@@ -302,12 +456,24 @@ where
     }
 
     api_call! {
-        container_create : post "/containers/create" ;
+        container_create : post "/containers/create" -> models::ContainerCreateResponse ;
         query : [ "name" = (name: &'a str), "platform" = (platform: &'a str) ] ;
         body : models::ContainerCreateBody ;
         ok : [CREATED]
     }
 
+    api_call! {
+        container_attach : post "/containers/{id}/attach" -> Incoming ;
+        path : [ id: &'a str ] ;
+        query : [
+            "stream" = (stream: bool),
+            "stdout" = (stdout: bool),
+            "stderr" = (stderr: bool)
+        ] ;
+        ok : [OK] ;
+        and_then(response) : { Ok(response.into_body()) }
+    }
+
     api_call! {
         container_delete : delete "/containers/{id}" ;
         path : [ id: &'a str ] ;
@@ -329,6 +495,13 @@ where
         ok : [OK]
     }
 
+    api_call! {
+        container_inspect_raw : get "/containers/{id}/json" -> serde_json::Value ;
+        path : [ id: &'a str ] ;
+        query : [ "size" = (size: bool) ] ;
+        ok : [OK]
+    }
+
     api_call! {
         container_list : get "/containers/json" -> Vec<models::ContainerSummary> ;
         query : [
@@ -340,6 +513,12 @@ where
         ok : [OK]
     }
 
+    api_call! {
+        container_prune : post "/containers/prune" -> models::PruneResponse ;
+        query : [ "filters" = (filters: &'a str) ] ;
+        ok : [OK]
+    }
+
     api_call! {
         container_start : post "/containers/{id}/start" ;
         path : [ id: &'a str ] ;
@@ -361,6 +540,15 @@ where
         ok : [NO_CONTENT, NOT_MODIFIED]
     }
 
+    // Docker sends the response headers as soon as it starts waiting, so the timeout does not
+    // limit how long the container can run.
+    api_call! {
+        container_wait : post "/containers/{id}/wait" -> models::ContainerWaitResponse ;
+        path : [ id: &'a str ] ;
+        query : [ "condition" = (condition: &'a str) ] ;
+        ok : [OK]
+    }
+
     api_call! {
         container_top : get "/containers/{id}/top" -> models::ContainerTopResponse ;
         path : [ id: &'a str ] ;
@@ -375,7 +563,31 @@ where
     }
 
     api_call! {
-        network_list : get "/networks" -> Vec<serde::de::IgnoredAny> ;
+        system_events : get "/events" -> Incoming ;
+        query : [
+            "since" = (since: &'a str),
+            "until" = (until: &'a str),
+            "filters" = (filters: &'a str)
+        ] ;
+        ok : [OK] ;
+        and_then(response) : { Ok(response.into_body()) }
+    }
+
+    api_call! {
+        network_list : get "/networks" -> Vec<models::NetworkSummary> ;
+        query : [ "filters" = (filters: &'a str) ] ;
+        ok : [OK]
+    }
+
+    api_call! {
+        network_inspect : get "/networks/{id}" -> serde_json::Value ;
+        path : [ id: &'a str ] ;
+        query : [ "verbose" = (verbose: bool) ] ;
+        ok : [OK]
+    }
+
+    api_call! {
+        volume_prune : post "/volumes/prune" -> models::PruneResponse ;
         query : [ "filters" = (filters: &'a str) ] ;
         ok : [OK]
     }
@@ -415,14 +627,7 @@ where
                 })??;
 
             if let Some(detail) = last.remove("errorDetail") {
-                let fallback_msg = serde_json::to_string(&detail)?;
-                Err(anyhow::anyhow!(
-                    serde_json::from_value(detail)
-                        .unwrap_or(ApiError {
-                            code: hyper::StatusCode::INTERNAL_SERVER_ERROR,
-                            message: fallback_msg
-                        })
-                ))
+                Err(anyhow::anyhow!(ApiError::from_error_detail(detail)))
             } else {
                 Ok(())
             }
@@ -450,6 +655,8 @@ where
 mod tests {
     use edgelet_test_utils::JsonConnector;
 
+    use http_body_util::combinators::BoxBody;
+
     use super::{ApiError, DockerApi, DockerApiClient};
 
     #[tokio::test]
@@ -518,6 +725,45 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn image_load_error() {
+        let payload =
+            serde_json::to_string(&serde_json::json!({"errorDetail":{"message":"unexpected EOF"}}))
+                .unwrap();
+        let client = DockerApiClient::new(JsonConnector::ok(&payload));
+        assert_eq!(
+            client
+                .image_load(true, BoxBody::new(http_body_util::Empty::new()))
+                .await
+                .unwrap_err()
+                .downcast::<ApiError>()
+                .unwrap(),
+            ApiError {
+                code: hyper::StatusCode::INTERNAL_SERVER_ERROR,
+                message: r#"{"message":"unexpected EOF"}"#.to_owned()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn image_load_names() {
+        let payload = [
+            serde_json::json!({"stream":"Loaded image: ubuntu:24.04\n"}),
+            serde_json::json!({"stream":"Loaded image ID: sha256:f9a33e4c293f\n"}),
+        ]
+        .iter()
+        .map(ToString::to_string)
+        .collect::<String>();
+        let client = DockerApiClient::new(JsonConnector::ok(&payload));
+        assert_eq!(
+            client
+                .image_load(true, BoxBody::new(http_body_util::Empty::new()))
+                .await
+                .unwrap(),
+            ["ubuntu:24.04", "sha256:f9a33e4c293f"]
+        );
+    }
+
     #[tokio::test]
     async fn images_list_null_repo_tags() {
         let payload = format!(
@@ -540,7 +786,39 @@ mod tests {
             .unwrap()
         );
         let client = DockerApiClient::new(JsonConnector::ok(&payload));
-        _ = client.images_list(false, "", false).await.unwrap();
+        let images = client.images_list(false, "", false).await.unwrap();
+        assert_eq!(Some(180_383_211), images[0].size);
+    }
+
+    #[tokio::test]
+    async fn container_wait_exit_code() {
+        let payload = serde_json::to_string(&serde_json::json!({
+            "StatusCode": 1,
+            "Error": null,
+        }))
+        .unwrap();
+        let client = DockerApiClient::new(JsonConnector::ok(&payload));
+        let response = client.container_wait("foo", "not-running").await.unwrap();
+        assert_eq!(1, response.status_code);
+        assert!(response.error.is_none());
+    }
+
+    #[tokio::test]
+    async fn network_list_names() {
+        let payload = serde_json::to_string(&serde_json::json!([
+            { "Name": "azure-iot-edge", "Id": "f2de39df4171", "Driver": "bridge" },
+            { "Name": "host", "Id": "e8af1a7f2a3b", "Driver": "host" },
+        ]))
+        .unwrap();
+        let client = DockerApiClient::new(JsonConnector::ok(&payload));
+        let networks = client.network_list("").await.unwrap();
+        assert_eq!(
+            vec![Some("azure-iot-edge"), Some("host")],
+            networks
+                .iter()
+                .map(|network| network.name.as_deref())
+                .collect::<Vec<_>>()
+        );
     }
 
     #[tokio::test]
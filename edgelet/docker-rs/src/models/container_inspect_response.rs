@@ -28,6 +28,30 @@ pub struct ContainerInspectResponseState {
     pub started_at: Option<String>,
     #[serde(rename = "FinishedAt", skip_serializing_if = "Option::is_none")]
     pub finished_at: Option<String>,
+    #[serde(rename = "Health", skip_serializing_if = "Option::is_none")]
+    pub health: Option<Health>,
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct Health {
+    #[serde(rename = "Status", skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(rename = "FailingStreak", skip_serializing_if = "Option::is_none")]
+    pub failing_streak: Option<i64>,
+    #[serde(rename = "Log", skip_serializing_if = "Option::is_none")]
+    pub log: Option<Vec<HealthcheckResult>>,
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct HealthcheckResult {
+    #[serde(rename = "Start", skip_serializing_if = "Option::is_none")]
+    pub start: Option<String>,
+    #[serde(rename = "End", skip_serializing_if = "Option::is_none")]
+    pub end: Option<String>,
+    #[serde(rename = "ExitCode", skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i64>,
+    #[serde(rename = "Output", skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
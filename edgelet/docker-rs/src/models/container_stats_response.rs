@@ -0,0 +1,73 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct ContainerStatsResponse {
+    #[serde(rename = "name", skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(rename = "id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(rename = "cpu_stats", skip_serializing_if = "Option::is_none")]
+    pub cpu_stats: Option<ContainerCpuStats>,
+    #[serde(rename = "precpu_stats", skip_serializing_if = "Option::is_none")]
+    pub precpu_stats: Option<ContainerCpuStats>,
+    #[serde(rename = "memory_stats", skip_serializing_if = "Option::is_none")]
+    pub memory_stats: Option<ContainerMemoryStats>,
+    #[serde(rename = "networks", skip_serializing_if = "Option::is_none")]
+    pub networks: Option<std::collections::BTreeMap<String, ContainerNetworkStats>>,
+    #[serde(rename = "blkio_stats", skip_serializing_if = "Option::is_none")]
+    pub blkio_stats: Option<ContainerBlkioStats>,
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct ContainerCpuStats {
+    #[serde(rename = "cpu_usage", skip_serializing_if = "Option::is_none")]
+    pub cpu_usage: Option<ContainerCpuUsage>,
+    #[serde(rename = "system_cpu_usage", skip_serializing_if = "Option::is_none")]
+    pub system_cpu_usage: Option<u64>,
+    #[serde(rename = "online_cpus", skip_serializing_if = "Option::is_none")]
+    pub online_cpus: Option<u32>,
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct ContainerCpuUsage {
+    #[serde(rename = "total_usage", skip_serializing_if = "Option::is_none")]
+    pub total_usage: Option<u64>,
+    #[serde(rename = "percpu_usage", skip_serializing_if = "Option::is_none")]
+    pub percpu_usage: Option<Vec<u64>>,
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct ContainerMemoryStats {
+    #[serde(rename = "usage", skip_serializing_if = "Option::is_none")]
+    pub usage: Option<u64>,
+    #[serde(rename = "limit", skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+    /// Raw cgroup memory statistics. Their names differ between cgroup v1 and v2.
+    #[serde(rename = "stats", default)]
+    pub stats: std::collections::BTreeMap<String, u64>,
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct ContainerNetworkStats {
+    #[serde(rename = "rx_bytes", skip_serializing_if = "Option::is_none")]
+    pub rx_bytes: Option<u64>,
+    #[serde(rename = "tx_bytes", skip_serializing_if = "Option::is_none")]
+    pub tx_bytes: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct ContainerBlkioStats {
+    #[serde(
+        rename = "io_service_bytes_recursive",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub io_service_bytes_recursive: Option<Vec<ContainerBlkioStatEntry>>,
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct ContainerBlkioStatEntry {
+    #[serde(rename = "op", skip_serializing_if = "Option::is_none")]
+    pub op: Option<String>,
+    #[serde(rename = "value", skip_serializing_if = "Option::is_none")]
+    pub value: Option<u64>,
+}
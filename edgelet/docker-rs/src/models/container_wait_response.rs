@@ -0,0 +1,15 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct ContainerWaitResponse {
+    #[serde(rename = "StatusCode")]
+    pub status_code: i64,
+    #[serde(rename = "Error", skip_serializing_if = "Option::is_none")]
+    pub error: Option<ContainerWaitExitError>,
+}
+
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct ContainerWaitExitError {
+    #[serde(rename = "Message", skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
@@ -4,6 +4,20 @@
 pub struct HostConfig {
     #[serde(rename = "Memory", skip_serializing_if = "Option::is_none")]
     pub memory: Option<i64>,
+    #[serde(rename = "MemorySwap", skip_serializing_if = "Option::is_none")]
+    pub memory_swap: Option<i64>,
+    #[serde(rename = "CpuShares", skip_serializing_if = "Option::is_none")]
+    pub cpu_shares: Option<i64>,
+    #[serde(rename = "NanoCpus", skip_serializing_if = "Option::is_none")]
+    pub nano_cpus: Option<i64>,
+    #[serde(rename = "CpuPeriod", skip_serializing_if = "Option::is_none")]
+    pub cpu_period: Option<i64>,
+    #[serde(rename = "CpuQuota", skip_serializing_if = "Option::is_none")]
+    pub cpu_quota: Option<i64>,
+    #[serde(rename = "PidsLimit", skip_serializing_if = "Option::is_none")]
+    pub pids_limit: Option<i64>,
+    #[serde(rename = "Ulimits", skip_serializing_if = "Option::is_none")]
+    pub ulimits: Option<Vec<ResourcesUlimits>>,
     #[serde(rename = "Binds", skip_serializing_if = "Option::is_none")]
     pub binds: Option<Vec<String>>,
     #[serde(rename = "PortBindings", skip_serializing_if = "Option::is_none")]
@@ -30,6 +44,16 @@ pub struct HostConfigPortBindings {
     pub other_properties: std::collections::BTreeMap<String, serde_json::Value>,
 }
 
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct ResourcesUlimits {
+    #[serde(rename = "Name", skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(rename = "Soft", skip_serializing_if = "Option::is_none")]
+    pub soft: Option<i64>,
+    #[serde(rename = "Hard", skip_serializing_if = "Option::is_none")]
+    pub hard: Option<i64>,
+}
+
 #[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
 pub struct Mount {
     #[serde(rename = "Target", skip_serializing_if = "Option::is_none")]
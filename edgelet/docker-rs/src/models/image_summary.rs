@@ -6,4 +6,6 @@ pub struct ImageSummary {
     pub id: String,
     #[serde(rename = "RepoTags")]
     pub repo_tags: Option<Vec<String>>,
+    #[serde(rename = "Size", default)]
+    pub size: Option<u64>,
 }
@@ -29,9 +29,18 @@ pub use self::container_create_body::{
     ContainerCreateBody, ContainerCreateBodyNetworkingConfig, EndpointSettings,
 };
 
+mod container_create_response;
+pub use self::container_create_response::ContainerCreateResponse;
+
 mod container_inspect_response;
 pub use self::container_inspect_response::{
-    ContainerInspectResponse, ContainerInspectResponseState, MountPoint,
+    ContainerInspectResponse, ContainerInspectResponseState, Health, HealthcheckResult, MountPoint,
+};
+
+mod container_stats_response;
+pub use self::container_stats_response::{
+    ContainerBlkioStatEntry, ContainerBlkioStats, ContainerCpuStats, ContainerCpuUsage,
+    ContainerMemoryStats, ContainerNetworkStats, ContainerStatsResponse,
 };
 
 mod container_summary;
@@ -40,8 +49,17 @@ pub use self::container_summary::ContainerSummary;
 mod container_top_response;
 pub use self::container_top_response::ContainerTopResponse;
 
+mod container_wait_response;
+pub use self::container_wait_response::{ContainerWaitExitError, ContainerWaitResponse};
+
+mod create_image_info;
+pub use self::create_image_info::{CreateImageInfo, ProgressDetail};
+
+mod event_message;
+pub use self::event_message::{EventActor, EventMessage};
+
 mod host_config;
-pub use self::host_config::{HostConfig, HostConfigPortBindings};
+pub use self::host_config::{HostConfig, HostConfigPortBindings, ResourcesUlimits};
 
 mod image_summary;
 pub use self::image_summary::ImageSummary;
@@ -52,5 +70,11 @@ pub use self::ipam::Ipam;
 mod network_config;
 pub use self::network_config::NetworkConfig;
 
+mod network_summary;
+pub use self::network_summary::NetworkSummary;
+
+mod prune_response;
+pub use self::prune_response::PruneResponse;
+
 mod system_info;
 pub use self::system_info::SystemInfo;
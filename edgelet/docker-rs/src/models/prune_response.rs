@@ -0,0 +1,9 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+/// The response of `POST /containers/prune` and `POST /volumes/prune`. The lists of deleted
+/// objects are not needed.
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct PruneResponse {
+    #[serde(rename = "SpaceReclaimed", skip_serializing_if = "Option::is_none")]
+    pub space_reclaimed: Option<u64>,
+}
@@ -4,4 +4,6 @@
 pub struct SystemInfo {
     #[serde(rename = "ServerVersion", skip_serializing_if = "Option::is_none")]
     pub server_version: Option<String>,
+    #[serde(rename = "DockerRootDir", skip_serializing_if = "Option::is_none")]
+    pub docker_root_dir: Option<String>,
 }
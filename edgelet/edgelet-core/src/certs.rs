@@ -0,0 +1,109 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! The inventory of the certificates on the device, for `iotedge certs list`.
+
+use std::time::Duration;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A request for the certificates that the workload API knows about. They are sent back on the
+/// channel, or why they could not be listed.
+pub type CertInventoryRequest = tokio::sync::oneshot::Sender<Result<Vec<CertInfo>, String>>;
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CertKind {
+    EdgeCa,
+    TrustBundle,
+    ManifestTrustBundle,
+    DeviceIdentity,
+    ModuleServer,
+    ModuleIdentity,
+}
+
+impl std::fmt::Display for CertKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            CertKind::EdgeCa => "Edge CA",
+            CertKind::TrustBundle => "trust bundle",
+            CertKind::ManifestTrustBundle => "manifest trust bundle",
+            CertKind::DeviceIdentity => "device identity",
+            CertKind::ModuleServer => "module server",
+            CertKind::ModuleIdentity => "module identity",
+        })
+    }
+}
+
+/// A certificate on the device. Trust bundles have one entry for each of their certificates.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CertInfo {
+    pub kind: CertKind,
+
+    /// The ID of the certificate in the certificates service.
+    pub cert_id: String,
+
+    /// The module the certificate was issued to, for module certificates.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub module_id: Option<String>,
+
+    pub subject: String,
+
+    #[serde(default)]
+    pub subject_alt_names: Vec<String>,
+
+    /// The issuer of the certificate, followed by the issuers of the rest of its chain.
+    pub issuers: Vec<String>,
+
+    /// The type and size of the certificate's key, like "RSA 2048" or "EC P-256".
+    pub key_type: String,
+
+    pub not_before: DateTime<Utc>,
+    pub not_after: DateTime<Utc>,
+}
+
+impl CertInfo {
+    /// Whether the certificate has expired at `now`, or expires within `threshold` of it.
+    pub fn expires_within(&self, threshold: Duration, now: DateTime<Utc>) -> bool {
+        TimeDelta::from_std(threshold)
+            .ok()
+            .and_then(|threshold| now.checked_add_signed(threshold))
+            .is_none_or(|deadline| self.not_after <= deadline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::{DateTime, TimeDelta};
+
+    use super::{CertInfo, CertKind};
+
+    #[test]
+    fn expires_within() {
+        let now = DateTime::from_timestamp(1_000_000, 0).unwrap();
+
+        let cert = CertInfo {
+            kind: CertKind::ModuleServer,
+            cert_id: "aziot-edged/module/edgeHub:1:server".to_string(),
+            module_id: Some("$edgeHub".to_string()),
+            subject: "CN=edgeHub".to_string(),
+            subject_alt_names: vec!["DNS:edgehub".to_string()],
+            issuers: vec!["CN=aziot-edge CA test-device".to_string()],
+            key_type: "EC P-256".to_string(),
+            not_before: now - TimeDelta::days(60),
+            not_after: now + TimeDelta::days(30),
+        };
+
+        assert!(!cert.expires_within(Duration::from_hours(24 * 29), now));
+        assert!(cert.expires_within(Duration::from_hours(24 * 30), now));
+
+        // Expired certificates are within any threshold.
+        assert!(cert.expires_within(Duration::ZERO, now + TimeDelta::days(31)));
+
+        // So is any certificate, for a threshold too long to add to `now`.
+        assert!(cert.expires_within(Duration::MAX, now));
+    }
+}
@@ -0,0 +1,229 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! The latest results of `iotedge check` runs, so that monitoring can follow them.
+//!
+//! `iotedge check --watch` reports the results of each run to edged, which keeps the last result
+//! of every check in a [`CheckHealth`] along with when it last changed.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Warning,
+    Ignored,
+    Skipped,
+    Failed,
+    Fatal,
+}
+
+impl CheckStatus {
+    /// Whether the status fails `iotedge check`.
+    pub fn is_failure(self) -> bool {
+        matches!(self, CheckStatus::Failed | CheckStatus::Fatal)
+    }
+}
+
+impl fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CheckStatus::Ok => "ok",
+            CheckStatus::Warning => "warning",
+            CheckStatus::Ignored => "ignored",
+            CheckStatus::Skipped => "skipped",
+            CheckStatus::Failed => "failed",
+            CheckStatus::Fatal => "fatal",
+        })
+    }
+}
+
+/// The result of one check in one run.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckReport {
+    pub id: String,
+
+    pub description: String,
+
+    pub status: CheckStatus,
+
+    /// The error and its causes, for warnings and failures.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub details: Vec<String>,
+}
+
+/// The last result of a check.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckRecord {
+    #[serde(flatten)]
+    pub report: CheckReport,
+
+    /// When the check last ran.
+    pub last_run: DateTime<Utc>,
+
+    /// When the check entered its current status.
+    pub since: DateTime<Utc>,
+
+    /// The status before the last change, if the status has changed since edged started.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_status: Option<CheckStatus>,
+}
+
+/// A change in the status of a check, or its first result.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckTransition {
+    pub id: String,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<CheckStatus>,
+
+    pub to: CheckStatus,
+
+    pub time: DateTime<Utc>,
+}
+
+impl fmt::Display for CheckTransition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.from {
+            Some(from) => write!(f, "check {} went from {from} to {}", self.id, self.to),
+            None => write!(f, "check {} is {}", self.id, self.to),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct CheckHealth {
+    records: Arc<Mutex<BTreeMap<String, CheckRecord>>>,
+}
+
+impl CheckHealth {
+    /// Records the results of a run at `time`, and returns the checks whose status changed.
+    pub fn record(&self, reports: Vec<CheckReport>, time: DateTime<Utc>) -> Vec<CheckTransition> {
+        let mut records = self.records.lock().expect("check health lock poisoned");
+        let mut transitions = Vec::new();
+
+        for report in reports {
+            let previous = records.get(&report.id);
+            let from = previous.map(|record| record.report.status);
+
+            let record = match previous {
+                Some(previous) if previous.report.status == report.status => CheckRecord {
+                    report,
+                    last_run: time,
+                    since: previous.since,
+                    previous_status: previous.previous_status,
+                },
+                _ => {
+                    let transition = CheckTransition {
+                        id: report.id.clone(),
+                        from,
+                        to: report.status,
+                        time,
+                    };
+                    if report.status.is_failure() {
+                        log::warn!("{transition}");
+                    } else {
+                        log::info!("{transition}");
+                    }
+                    transitions.push(transition);
+
+                    CheckRecord {
+                        report,
+                        last_run: time,
+                        since: time,
+                        previous_status: from,
+                    }
+                }
+            };
+
+            records.insert(record.report.id.clone(), record);
+        }
+
+        transitions
+    }
+
+    /// The last result of every check that has been reported, ordered by ID.
+    pub fn records(&self) -> Vec<CheckRecord> {
+        let records = self.records.lock().expect("check health lock poisoned");
+
+        records.values().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+
+    use super::{CheckHealth, CheckReport, CheckStatus};
+
+    fn report(id: &str, status: CheckStatus) -> CheckReport {
+        CheckReport {
+            id: id.to_string(),
+            description: format!("{id} works"),
+            status,
+            details: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn transitions() {
+        let health = CheckHealth::default();
+        let start = Utc::now();
+
+        // The first result of every check is a transition.
+        let transitions = health.record(
+            vec![
+                report("container-connect-upstream-mqtt", CheckStatus::Ok),
+                report("container-local-time", CheckStatus::Ok),
+            ],
+            start,
+        );
+        assert_eq!(2, transitions.len());
+        assert_eq!(None, transitions[0].from);
+
+        // Unchanged results only update when the check last ran.
+        let later = start + Duration::minutes(5);
+        let transitions = health.record(
+            vec![
+                report("container-connect-upstream-mqtt", CheckStatus::Failed),
+                report("container-local-time", CheckStatus::Ok),
+            ],
+            later,
+        );
+        assert_eq!(1, transitions.len());
+        assert_eq!(
+            "check container-connect-upstream-mqtt went from ok to failed",
+            transitions[0].to_string()
+        );
+
+        let records = health.records();
+        assert_eq!("container-connect-upstream-mqtt", records[0].report.id);
+        assert_eq!(CheckStatus::Failed, records[0].report.status);
+        assert_eq!(Some(CheckStatus::Ok), records[0].previous_status);
+        assert_eq!(later, records[0].since);
+
+        assert_eq!("container-local-time", records[1].report.id);
+        assert_eq!(None, records[1].previous_status);
+        assert_eq!(start, records[1].since);
+        assert_eq!(later, records[1].last_run);
+
+        // The previous status is kept until the status changes again.
+        let transitions = health.record(
+            vec![report(
+                "container-connect-upstream-mqtt",
+                CheckStatus::Failed,
+            )],
+            later + Duration::minutes(5),
+        );
+        assert!(transitions.is_empty());
+        assert_eq!(Some(CheckStatus::Ok), health.records()[0].previous_status);
+    }
+}
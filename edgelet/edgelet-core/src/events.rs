@@ -0,0 +1,223 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! Module lifecycle events.
+//!
+//! Events reported by the module runtime and by edged itself are published to an [`EventBus`],
+//! which keeps a bounded history so that clients can ask for events since a point in time
+//! before following new ones.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, mpsc};
+
+/// Events reported by a module runtime. The stream ends when the connection to the runtime
+/// is lost.
+pub type EventStream = mpsc::Receiver<ModuleEvent>;
+
+const DEFAULT_HISTORY: usize = 1000;
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EventKind {
+    Created,
+    Started,
+    Stopped,
+    Died,
+    OomKilled,
+    ImagePulled,
+    Removed,
+    EdgeCaRenewed,
+    IdentityDeleted,
+}
+
+impl fmt::Display for EventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            EventKind::Created => "created",
+            EventKind::Started => "started",
+            EventKind::Stopped => "stopped",
+            EventKind::Died => "died",
+            EventKind::OomKilled => "oom-killed",
+            EventKind::ImagePulled => "image-pulled",
+            EventKind::Removed => "removed",
+            EventKind::EdgeCaRenewed => "edge-ca-renewed",
+            EventKind::IdentityDeleted => "identity-deleted",
+        })
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModuleEvent {
+    time: DateTime<Utc>,
+
+    #[serde(rename = "type")]
+    kind: EventKind,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    module: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    image: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    exit_code: Option<i64>,
+}
+
+impl ModuleEvent {
+    pub fn new(time: DateTime<Utc>, kind: EventKind) -> Self {
+        ModuleEvent {
+            time,
+            kind,
+            module: None,
+            image: None,
+            exit_code: None,
+        }
+    }
+
+    pub fn time(&self) -> &DateTime<Utc> {
+        &self.time
+    }
+
+    pub fn kind(&self) -> EventKind {
+        self.kind
+    }
+
+    pub fn module(&self) -> Option<&str> {
+        self.module.as_deref()
+    }
+
+    #[must_use]
+    pub fn with_module(mut self, module: Option<String>) -> Self {
+        self.module = module;
+        self
+    }
+
+    pub fn image(&self) -> Option<&str> {
+        self.image.as_deref()
+    }
+
+    #[must_use]
+    pub fn with_image(mut self, image: Option<String>) -> Self {
+        self.image = image;
+        self
+    }
+
+    pub fn exit_code(&self) -> Option<i64> {
+        self.exit_code
+    }
+
+    #[must_use]
+    pub fn with_exit_code(mut self, exit_code: Option<i64>) -> Self {
+        self.exit_code = exit_code;
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct EventBus {
+    sender: broadcast::Sender<ModuleEvent>,
+    history: Arc<Mutex<VecDeque<ModuleEvent>>>,
+    capacity: usize,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        EventBus::new(DEFAULT_HISTORY)
+    }
+}
+
+impl EventBus {
+    /// Creates a bus that remembers up to `capacity` past events.
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+
+        EventBus {
+            sender,
+            history: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    pub fn publish(&self, event: ModuleEvent) {
+        let mut history = self.history.lock().expect("event history lock poisoned");
+
+        if history.len() == self.capacity {
+            history.pop_front();
+        }
+        if self.capacity > 0 {
+            history.push_back(event.clone());
+        }
+
+        // Sending fails only if nobody is subscribed, which is fine.
+        let _ = self.sender.send(event);
+    }
+
+    /// Returns the remembered events at or after `since`, along with a receiver for events
+    /// published afterwards. No event is both returned and received.
+    pub fn subscribe(
+        &self,
+        since: Option<DateTime<Utc>>,
+    ) -> (Vec<ModuleEvent>, broadcast::Receiver<ModuleEvent>) {
+        let history = self.history.lock().expect("event history lock poisoned");
+
+        let past = history
+            .iter()
+            .filter(|event| since.is_none_or(|since| event.time >= since))
+            .cloned()
+            .collect();
+
+        (past, self.sender.subscribe())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(secs: i64, kind: EventKind) -> ModuleEvent {
+        ModuleEvent::new(DateTime::from_timestamp(secs, 0).unwrap(), kind)
+            .with_module(Some("m".to_string()))
+    }
+
+    #[test]
+    fn serialize() {
+        let event = event(0, EventKind::OomKilled).with_exit_code(Some(137));
+
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({
+                "time": "1970-01-01T00:00:00Z",
+                "type": "oom-killed",
+                "module": "m",
+                "exitCode": 137,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn subscribe_since() {
+        let bus = EventBus::new(2);
+
+        bus.publish(event(1, EventKind::Created));
+        bus.publish(event(2, EventKind::Started));
+        bus.publish(event(3, EventKind::Died));
+
+        // Only the last 2 events are remembered.
+        let (past, _) = bus.subscribe(None);
+        assert_eq!(
+            past,
+            [event(2, EventKind::Started), event(3, EventKind::Died)]
+        );
+
+        let (past, mut receiver) = bus.subscribe(DateTime::from_timestamp(3, 0));
+        assert_eq!(past, [event(3, EventKind::Died)]);
+
+        bus.publish(event(4, EventKind::Removed));
+        assert_eq!(receiver.recv().await.unwrap(), event(4, EventKind::Removed));
+    }
+}
@@ -1,20 +1,39 @@
 // Copyright (c) Microsoft. All rights reserved.
 
+pub mod certs;
+pub mod check_health;
 pub mod error;
+pub mod events;
+pub mod image_use;
+pub mod metrics;
 pub mod module;
+pub mod policy;
+pub mod prune;
+pub mod pull;
+pub mod restart;
 
 mod parse_since;
 mod virtualization;
 
 use std::sync::LazyLock;
 
+pub use certs::{CertInfo, CertInventoryRequest, CertKind};
+pub use check_health::{CheckHealth, CheckRecord, CheckReport, CheckStatus, CheckTransition};
 pub use error::Error;
+pub use events::{EventBus, EventKind, EventStream, ModuleEvent};
+pub use image_use::ImageUse;
+pub use metrics::ModuleMetrics;
 pub use module::{
-    DiskInfo, LogOptions, LogTail, Module, ModuleAction, ModuleOperation, ModuleRegistry,
-    ModuleRuntime, ModuleRuntimeErrorReason, ModuleRuntimeState, ModuleStatus, ProvisioningInfo,
-    RegistryOperation, RuntimeOperation, SystemInfo, SystemResources,
+    DiskInfo, HealthStatus, LogOptions, LogStream, LogTail, Module, ModuleAction, ModuleHealth,
+    ModuleOperation, ModuleRegistry, ModuleRuntime, ModuleRuntimeErrorReason, ModuleRuntimeState,
+    ModuleStatus, ProvisioningInfo, RegistryOperation, RuntimeOperation, SystemInfo,
+    SystemResources,
 };
 pub use parse_since::parse_since;
+pub use policy::{PolicyRule, PolicyViolation, PolicyViolations};
+pub use prune::{ImagePruneReport, ImagePruneRequest, PrunedImage};
+pub use pull::{PullProgress, PullProgressBus, PullStatus, get_image};
+pub use restart::{RestartDecision, RestartTracker};
 
 use std::path::{Path, PathBuf};
 
@@ -79,3 +98,7 @@ impl std::fmt::Display for WatchdogAction {
         }
     }
 }
+
+/// A request to rotate the master key that the workload API encrypts module data with. The
+/// new key version, or why the rotation failed, is sent back on the channel.
+pub type EncryptionKeyRotation = tokio::sync::oneshot::Sender<Result<u32, String>>;
@@ -0,0 +1,248 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! Per-module resource metrics.
+//!
+//! Values that a module runtime cannot report, such as network counters on CRI runtimes, are
+//! left unset and omitted from both the JSON and the Prometheus representation.
+
+use std::fmt::Write;
+
+use serde::Serialize;
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ModuleMetrics {
+    name: String,
+
+    /// Total CPU time consumed by the module.
+    cpu_usage_seconds: f64,
+    /// CPU usage as a percentage of a single core, over the runtime's sampling interval. Not
+    /// reported by runtimes that take a single sample.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cpu_percent: Option<f64>,
+
+    memory_usage_bytes: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    memory_limit_bytes: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    network_rx_bytes: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    network_tx_bytes: Option<u64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    block_read_bytes: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    block_write_bytes: Option<u64>,
+}
+
+impl ModuleMetrics {
+    pub fn new(name: String) -> Self {
+        ModuleMetrics {
+            name,
+            ..Default::default()
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn cpu_usage_seconds(&self) -> f64 {
+        self.cpu_usage_seconds
+    }
+
+    pub fn cpu_percent(&self) -> Option<f64> {
+        self.cpu_percent
+    }
+
+    #[must_use]
+    pub fn with_cpu(mut self, usage_seconds: f64, percent: Option<f64>) -> Self {
+        self.cpu_usage_seconds = usage_seconds;
+        self.cpu_percent = percent;
+        self
+    }
+
+    pub fn memory_usage_bytes(&self) -> u64 {
+        self.memory_usage_bytes
+    }
+
+    pub fn memory_limit_bytes(&self) -> Option<u64> {
+        self.memory_limit_bytes
+    }
+
+    #[must_use]
+    pub fn with_memory(mut self, usage_bytes: u64, limit_bytes: Option<u64>) -> Self {
+        self.memory_usage_bytes = usage_bytes;
+        self.memory_limit_bytes = limit_bytes;
+        self
+    }
+
+    pub fn network_rx_bytes(&self) -> Option<u64> {
+        self.network_rx_bytes
+    }
+
+    pub fn network_tx_bytes(&self) -> Option<u64> {
+        self.network_tx_bytes
+    }
+
+    #[must_use]
+    pub fn with_network(mut self, rx_bytes: u64, tx_bytes: u64) -> Self {
+        self.network_rx_bytes = Some(rx_bytes);
+        self.network_tx_bytes = Some(tx_bytes);
+        self
+    }
+
+    pub fn block_read_bytes(&self) -> Option<u64> {
+        self.block_read_bytes
+    }
+
+    pub fn block_write_bytes(&self) -> Option<u64> {
+        self.block_write_bytes
+    }
+
+    #[must_use]
+    pub fn with_block_io(mut self, read_bytes: u64, write_bytes: u64) -> Self {
+        self.block_read_bytes = Some(read_bytes);
+        self.block_write_bytes = Some(write_bytes);
+        self
+    }
+}
+
+/// Renders `metrics` in the Prometheus text exposition format.
+pub fn prometheus(metrics: &[ModuleMetrics]) -> String {
+    #[allow(clippy::cast_precision_loss)]
+    let families: [(&str, &str, &str, fn(&ModuleMetrics) -> Option<f64>); 8] = [
+        (
+            "iotedge_module_cpu_seconds_total",
+            "counter",
+            "Total CPU time consumed by the module.",
+            |m| Some(m.cpu_usage_seconds),
+        ),
+        (
+            "iotedge_module_cpu_percent",
+            "gauge",
+            "CPU usage of the module as a percentage of a single core.",
+            |m| m.cpu_percent,
+        ),
+        (
+            "iotedge_module_memory_usage_bytes",
+            "gauge",
+            "Memory used by the module.",
+            |m| Some(m.memory_usage_bytes as f64),
+        ),
+        (
+            "iotedge_module_memory_limit_bytes",
+            "gauge",
+            "Memory limit of the module.",
+            |m| m.memory_limit_bytes.map(|v| v as f64),
+        ),
+        (
+            "iotedge_module_network_receive_bytes_total",
+            "counter",
+            "Bytes received by the module over the network.",
+            |m| m.network_rx_bytes.map(|v| v as f64),
+        ),
+        (
+            "iotedge_module_network_transmit_bytes_total",
+            "counter",
+            "Bytes sent by the module over the network.",
+            |m| m.network_tx_bytes.map(|v| v as f64),
+        ),
+        (
+            "iotedge_module_block_read_bytes_total",
+            "counter",
+            "Bytes read by the module from block devices.",
+            |m| m.block_read_bytes.map(|v| v as f64),
+        ),
+        (
+            "iotedge_module_block_write_bytes_total",
+            "counter",
+            "Bytes written by the module to block devices.",
+            |m| m.block_write_bytes.map(|v| v as f64),
+        ),
+    ];
+
+    let mut output = String::new();
+
+    for (name, kind, help, value) in families {
+        let samples: Vec<_> = metrics
+            .iter()
+            .filter_map(|m| value(m).map(|value| (m.name(), value)))
+            .collect();
+
+        if samples.is_empty() {
+            continue;
+        }
+
+        // Writing to a String cannot fail.
+        let _ = writeln!(output, "# HELP {name} {help}");
+        let _ = writeln!(output, "# TYPE {name} {kind}");
+        for (module, value) in samples {
+            let _ = writeln!(
+                output,
+                "{name}{{module=\"{}\"}} {value}",
+                escape_label(module)
+            );
+        }
+    }
+
+    output
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serialize_omits_unknown_values() {
+        let metrics = ModuleMetrics::new("m".to_string())
+            .with_cpu(1.5, None)
+            .with_memory(1024, None);
+
+        assert_eq!(
+            serde_json::to_value(&metrics).unwrap(),
+            serde_json::json!({
+                "name": "m",
+                "cpu_usage_seconds": 1.5,
+                "memory_usage_bytes": 1024,
+            })
+        );
+    }
+
+    #[test]
+    fn prometheus_format() {
+        let metrics = [
+            ModuleMetrics::new("edgeAgent".to_string())
+                .with_cpu(2.5, Some(10.0))
+                .with_memory(2048, Some(4096))
+                .with_network(10, 20)
+                .with_block_io(30, 40),
+            ModuleMetrics::new("odd\"name".to_string()).with_memory(1024, None),
+        ];
+
+        let output = prometheus(&metrics);
+
+        assert!(output.contains(
+            "# TYPE iotedge_module_cpu_seconds_total counter\n\
+             iotedge_module_cpu_seconds_total{module=\"edgeAgent\"} 2.5\n\
+             iotedge_module_cpu_seconds_total{module=\"odd\\\"name\"} 0\n"
+        ));
+        assert!(output.contains("iotedge_module_memory_limit_bytes{module=\"edgeAgent\"} 4096\n"));
+        assert!(!output.contains("iotedge_module_memory_limit_bytes{module=\"odd"));
+        assert!(
+            output.contains("iotedge_module_block_write_bytes_total{module=\"edgeAgent\"} 40\n")
+        );
+    }
+
+    #[test]
+    fn prometheus_empty() {
+        assert_eq!(prometheus(&[]), "");
+    }
+}
@@ -6,8 +6,9 @@ use std::fmt;
 use std::time::Duration;
 
 use anyhow::Context;
+use bytes::Bytes;
 use chrono::prelude::*;
-use hyper::body::Incoming;
+use http_body_util::combinators::BoxBody;
 use nix::sys::utsname::UtsName;
 use serde::{Deserialize, Serialize};
 
@@ -15,6 +16,8 @@ use aziotctl_common::host_info::{DmiInfo, OsInfo};
 use edgelet_settings::module::Settings as ModuleSpec;
 
 use crate::error::Error;
+use crate::events::EventStream;
+use crate::metrics::ModuleMetrics;
 
 #[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 #[serde(rename_all = "lowercase")]
@@ -25,6 +28,15 @@ pub enum ModuleStatus {
     Stopped,
     Failed,
     Dead,
+    /// The module is running, but its health check is failing.
+    Unhealthy,
+}
+
+impl ModuleStatus {
+    /// Whether the module's container is running, whether or not it is healthy.
+    pub fn is_running(self) -> bool {
+        matches!(self, ModuleStatus::Running | ModuleStatus::Unhealthy)
+    }
 }
 
 impl fmt::Display for ModuleStatus {
@@ -61,6 +73,12 @@ pub struct ModuleRuntimeState {
     finished_at: Option<DateTime<Utc>>,
     image_id: Option<String>,
     pid: Option<i32>,
+    #[serde(default)]
+    restart_count: u32,
+    #[serde(default)]
+    next_retry_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    health: Option<ModuleHealth>,
 }
 
 impl ModuleRuntimeState {
@@ -123,6 +141,89 @@ impl ModuleRuntimeState {
         self.pid = pid;
         self
     }
+
+    /// Number of consecutive restarts edged has performed under the module's restart policy.
+    pub fn restart_count(&self) -> u32 {
+        self.restart_count
+    }
+
+    #[must_use]
+    pub fn with_restart_count(mut self, restart_count: u32) -> Self {
+        self.restart_count = restart_count;
+        self
+    }
+
+    /// Time at which edged will next attempt to restart the module, if a restart is pending.
+    pub fn next_retry_at(&self) -> Option<&DateTime<Utc>> {
+        self.next_retry_at.as_ref()
+    }
+
+    #[must_use]
+    pub fn with_next_retry_at(mut self, next_retry_at: Option<DateTime<Utc>>) -> Self {
+        self.next_retry_at = next_retry_at;
+        self
+    }
+
+    /// Result of the module's health check, if the module has one.
+    pub fn health(&self) -> Option<&ModuleHealth> {
+        self.health.as_ref()
+    }
+
+    #[must_use]
+    pub fn with_health(mut self, health: Option<ModuleHealth>) -> Self {
+        self.health = health;
+        self
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HealthStatus {
+    #[default]
+    Starting,
+    Healthy,
+    Unhealthy,
+}
+
+impl fmt::Display for HealthStatus {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HealthStatus::Starting => write!(formatter, "starting"),
+            HealthStatus::Healthy => write!(formatter, "healthy"),
+            HealthStatus::Unhealthy => write!(formatter, "unhealthy"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ModuleHealth {
+    status: HealthStatus,
+    failing_streak: u32,
+    last_output: Option<String>,
+}
+
+impl ModuleHealth {
+    pub fn new(status: HealthStatus, failing_streak: u32, last_output: Option<String>) -> Self {
+        ModuleHealth {
+            status,
+            failing_streak,
+            last_output,
+        }
+    }
+
+    pub fn status(&self) -> HealthStatus {
+        self.status
+    }
+
+    /// Number of consecutive failed health check probes.
+    pub fn failing_streak(&self) -> u32 {
+        self.failing_streak
+    }
+
+    /// Output of the most recent health check probe.
+    pub fn last_output(&self) -> Option<&str> {
+        self.last_output.as_deref()
+    }
 }
 
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
@@ -244,6 +345,23 @@ pub trait ModuleRegistry {
 
     async fn pull(&self, config: &Self::Config) -> anyhow::Result<()>;
     async fn remove(&self, name: &str) -> anyhow::Result<()>;
+
+    /// Whether the image of `config` is present on the device.
+    async fn image_exists(&self, config: &Self::Config) -> anyhow::Result<bool>;
+
+    /// Loads the image of `config` from its offline bundle. Returns `false` if it has none.
+    async fn load_bundle(&self, config: &Self::Config) -> anyhow::Result<bool>;
+
+    /// Loads the images of a `docker save` tarball or an OCI image layout directory, and
+    /// returns their names, or their IDs for images without a name.
+    async fn import(&self, path: &std::path::Path) -> anyhow::Result<Vec<String>>;
+
+    /// The images deployed through IoT Edge that image garbage collection tracks, with what
+    /// they were used for.
+    async fn image_use(&self) -> anyhow::Result<Vec<crate::ImageUse>>;
+
+    /// Where the progress of pulls is reported.
+    fn pull_progress(&self) -> &crate::PullProgressBus;
 }
 
 #[derive(Debug, Eq, PartialEq, Serialize)]
@@ -449,6 +567,13 @@ impl DiskInfo {
     }
 }
 
+/// Body of a module's log stream.
+///
+/// Regardless of the module runtime backend, each frame uses the Docker multiplexed stream
+/// format: an 8-byte header (stream type, 3 padding bytes, big-endian payload length)
+/// followed by the payload.
+pub type LogStream = BoxBody<Bytes, Box<dyn std::error::Error + Send + Sync>>;
+
 pub trait ProvisioningResult {
     fn device_id(&self) -> &str;
     fn hub_name(&self) -> &str;
@@ -468,14 +593,32 @@ pub trait ModuleRuntime {
     async fn remove(&self, id: &str) -> anyhow::Result<()>;
     async fn system_info(&self) -> anyhow::Result<SystemInfo>;
     async fn system_resources(&self) -> anyhow::Result<SystemResources>;
+    async fn module_metrics(&self) -> anyhow::Result<Vec<ModuleMetrics>>;
     async fn list(&self) -> anyhow::Result<Vec<Self::Module>>;
     async fn list_with_details(&self) -> anyhow::Result<Vec<(Self::Module, ModuleRuntimeState)>>;
     async fn list_images(&self) -> anyhow::Result<std::collections::HashMap<String, String>>;
-    async fn logs(&self, id: &str, options: &LogOptions) -> anyhow::Result<Incoming>;
+
+    /// The sizes in bytes of the images on the device, by image ID.
+    async fn image_sizes(&self) -> anyhow::Result<std::collections::HashMap<String, u64>>;
+
+    async fn logs(&self, id: &str, options: &LogOptions) -> anyhow::Result<LogStream>;
     async fn remove_all(&self) -> anyhow::Result<()>;
     async fn stop_all(&self, wait_before_kill: Option<Duration>) -> anyhow::Result<()>;
     async fn module_top(&self, id: &str) -> anyhow::Result<Vec<i32>>;
 
+    /// The directory where the runtime stores images, containers and volumes.
+    async fn data_root(&self) -> anyhow::Result<std::path::PathBuf>;
+
+    /// Removes stopped containers that IoT Edge did not create. Returns the bytes reclaimed.
+    async fn prune_containers(&self) -> anyhow::Result<u64>;
+
+    /// Removes anonymous volumes that no container uses. Returns the bytes reclaimed.
+    async fn prune_volumes(&self) -> anyhow::Result<u64>;
+
+    /// Subscribes to lifecycle events of modules, starting from `since` if the runtime can
+    /// replay past events.
+    async fn events(&self, since: Option<DateTime<Utc>>) -> anyhow::Result<EventStream>;
+
     fn registry(&self) -> &Self::ModuleRegistry;
 
     fn error_code(error: &anyhow::Error) -> hyper::StatusCode;
@@ -504,6 +647,7 @@ impl fmt::Display for ModuleOperation {
 // Useful for error contexts
 #[derive(Clone, Debug)]
 pub enum RegistryOperation {
+    ImportImage(String),
     PullImage(String),
     RemoveImage(String),
 }
@@ -511,6 +655,7 @@ pub enum RegistryOperation {
 impl fmt::Display for RegistryOperation {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            RegistryOperation::ImportImage(path) => write!(f, "import image from {path:?}"),
             RegistryOperation::PullImage(name) => write!(f, "pull image {name:?}"),
             RegistryOperation::RemoveImage(name) => write!(f, "remove image {name:?}"),
         }
@@ -523,10 +668,15 @@ pub enum RuntimeOperation {
     CreateModule(String),
     GetModule(String),
     GetModuleLogs(String),
+    GetEvents,
+    GetDataRoot,
+    GetModuleMetrics,
     GetSupportBundle,
     Init,
     ListImages,
     ListModules,
+    PruneContainers,
+    PruneVolumes,
     RemoveModule(String),
     RestartModule(String),
     StartModule(String),
@@ -544,10 +694,15 @@ impl fmt::Display for RuntimeOperation {
             RuntimeOperation::GetModuleLogs(name) => {
                 write!(f, "get logs for module {name:?}")
             }
+            RuntimeOperation::GetEvents => write!(f, "get module events"),
+            RuntimeOperation::GetDataRoot => write!(f, "get runtime data root"),
+            RuntimeOperation::GetModuleMetrics => write!(f, "get module metrics"),
             RuntimeOperation::GetSupportBundle => write!(f, "get support bundle"),
             RuntimeOperation::Init => write!(f, "initialize module runtime"),
             RuntimeOperation::ListModules => write!(f, "list modules"),
             RuntimeOperation::ListImages => write!(f, "list images"),
+            RuntimeOperation::PruneContainers => write!(f, "prune containers"),
+            RuntimeOperation::PruneVolumes => write!(f, "prune volumes"),
             RuntimeOperation::RemoveModule(name) => write!(f, "remove module {name:?}"),
             RuntimeOperation::RestartModule(name) => write!(f, "restart module {name:?}"),
             RuntimeOperation::StartModule(name) => write!(f, "start module {name:?}"),
@@ -578,9 +733,20 @@ mod tests {
             ("stopped", ModuleStatus::Stopped),
             ("failed", ModuleStatus::Failed),
             ("dead", ModuleStatus::Dead),
+            ("unhealthy", ModuleStatus::Unhealthy),
         ]
     }
 
+    #[test]
+    fn module_status_is_running() {
+        let running: Vec<_> = get_inputs()
+            .into_iter()
+            .filter(|(_, status)| status.is_running())
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(running, ["running", "unhealthy"]);
+    }
+
     #[test]
     fn module_status_ser() {
         let inputs = get_inputs();
@@ -0,0 +1,144 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! Violations of the `[container_policy]` rules, which module runtimes report when they refuse
+//! to create a module.
+
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyRule {
+    Privileged,
+    HostNetwork,
+    HostPid,
+    HostIpc,
+    HostUserns,
+    HostCgroupns,
+    BindMount,
+    VolumesFrom,
+    Device,
+    DeviceCgroupRule,
+    Capability,
+    SeccompProfile,
+    #[serde(rename = "apparmor_profile")]
+    AppArmorProfile,
+    Registry,
+}
+
+impl fmt::Display for PolicyRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PolicyRule::Privileged => "privileged mode",
+            PolicyRule::HostNetwork => "host network mode",
+            PolicyRule::HostPid => "host PID mode",
+            PolicyRule::HostIpc => "host IPC mode",
+            PolicyRule::HostUserns => "host user namespace mode",
+            PolicyRule::HostCgroupns => "host cgroup namespace mode",
+            PolicyRule::BindMount => "bind mount",
+            PolicyRule::VolumesFrom => "volumes from container",
+            PolicyRule::Device => "device",
+            PolicyRule::DeviceCgroupRule => "device cgroup rule",
+            PolicyRule::Capability => "capability",
+            PolicyRule::SeccompProfile => "seccomp profile",
+            PolicyRule::AppArmorProfile => "AppArmor profile",
+            PolicyRule::Registry => "registry",
+        })
+    }
+}
+
+/// A rule that module `module` breaks, with the value that breaks it, like the host path of a
+/// bind mount. Modes such as privileged mode have no value.
+#[derive(Clone, Debug, Eq, PartialEq, serde::Serialize)]
+pub struct PolicyViolation {
+    rule: PolicyRule,
+    value: Option<String>,
+    module: String,
+}
+
+impl PolicyViolation {
+    pub fn new(module: String, rule: PolicyRule, value: Option<String>) -> Self {
+        PolicyViolation {
+            rule,
+            value,
+            module,
+        }
+    }
+
+    pub fn rule(&self) -> PolicyRule {
+        self.rule
+    }
+
+    pub fn value(&self) -> Option<&str> {
+        self.value.as_deref()
+    }
+
+    pub fn module(&self) -> &str {
+        &self.module
+    }
+}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.value {
+            Some(value) => write!(f, "{} {value:?} is not allowed", self.rule),
+            None => write!(f, "{} is not allowed", self.rule),
+        }
+    }
+}
+
+/// The ways in which a module's create options break the container policy. Module runtimes
+/// return it as the root cause of a failed `RuntimeOperation::CreateModule`, so that clients of
+/// the management API are told each rule that the module breaks.
+#[derive(Debug, thiserror::Error)]
+#[error("module {module:?} violates the container policy: {}", join(.violations))]
+pub struct PolicyViolations {
+    module: String,
+    violations: Vec<PolicyViolation>,
+}
+
+impl PolicyViolations {
+    pub fn new(module: String, violations: Vec<PolicyViolation>) -> Self {
+        PolicyViolations { module, violations }
+    }
+
+    pub fn module(&self) -> &str {
+        &self.module
+    }
+
+    pub fn violations(&self) -> &[PolicyViolation] {
+        &self.violations
+    }
+}
+
+fn join(violations: &[PolicyViolation]) -> String {
+    violations
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn violations_are_serialized() {
+        let violations = [
+            PolicyViolation::new("m".to_owned(), PolicyRule::Privileged, None),
+            PolicyViolation::new(
+                "m".to_owned(),
+                PolicyRule::AppArmorProfile,
+                Some("unconfined".to_owned()),
+            ),
+        ];
+
+        assert_eq!(
+            serde_json::to_value(violations).unwrap(),
+            serde_json::json!([
+                { "rule": "privileged", "value": null, "module": "m" },
+                { "rule": "apparmor_profile", "value": "unconfined", "module": "m" },
+            ])
+        );
+    }
+}
@@ -0,0 +1,49 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! Image garbage collection run on demand rather than on its schedule.
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A request to remove unused images now. The report of the run, or why it failed, is sent
+/// back on `response`.
+#[derive(Debug)]
+pub struct ImagePruneRequest {
+    /// How long an image must have been unused to be removed. Unset to use the configured
+    /// `image_age_cleanup_threshold`.
+    pub older_than: Option<Duration>,
+
+    /// Only report the images that would be removed.
+    pub dry_run: bool,
+
+    pub response: tokio::sync::oneshot::Sender<Result<ImagePruneReport, String>>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImagePruneReport {
+    pub dry_run: bool,
+
+    /// The images that were removed, or would be removed by a dry run.
+    pub images: Vec<PrunedImage>,
+
+    /// The sum of the sizes of `images`. Images share layers, so less space may be freed.
+    pub space_reclaimed: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrunedImage {
+    pub id: String,
+
+    /// Size of the image in bytes, if the runtime reported it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+
+    pub last_used: DateTime<Utc>,
+
+    /// Why the image qualified for removal.
+    pub reason: String,
+}
@@ -0,0 +1,392 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! Image pulls and their progress.
+//!
+//! Module runtimes report the progress of each pull to a [`PullProgressBus`], which remembers
+//! the latest progress of every layer of the pulls still in progress, so that clients that
+//! subscribe in the middle of a pull see where it stands.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+use edgelet_settings::module::ImagePullPolicy;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::ModuleRegistry;
+
+const CHANNEL_CAPACITY: usize = 256;
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PullStatus {
+    /// The pull of the image started.
+    Started,
+    /// A layer is being downloaded.
+    Downloading,
+    /// A layer finished downloading.
+    Downloaded,
+    /// A downloaded layer is being extracted.
+    Extracting,
+    /// A layer is ready.
+    Extracted,
+    /// A layer was already present and is not downloaded.
+    AlreadyExists,
+    /// The pull failed with an error that may be transient and is tried again.
+    Retrying,
+    /// The pull of the image finished.
+    Completed,
+    /// The pull of the image failed.
+    Failed,
+}
+
+impl PullStatus {
+    fn is_final(self) -> bool {
+        matches!(self, PullStatus::Completed | PullStatus::Failed)
+    }
+}
+
+impl fmt::Display for PullStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PullStatus::Started => "started",
+            PullStatus::Downloading => "downloading",
+            PullStatus::Downloaded => "downloaded",
+            PullStatus::Extracting => "extracting",
+            PullStatus::Extracted => "extracted",
+            PullStatus::AlreadyExists => "already exists",
+            PullStatus::Retrying => "retrying",
+            PullStatus::Completed => "completed",
+            PullStatus::Failed => "failed",
+        })
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PullProgress {
+    time: DateTime<Utc>,
+
+    image: String,
+
+    /// The layer that this progress is about. Unset for progress of the whole image.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    layer: Option<String>,
+
+    status: PullStatus,
+
+    /// Bytes of the layer done so far.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    current: Option<u64>,
+
+    /// Size of the layer in bytes, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    total: Option<u64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+}
+
+impl PullProgress {
+    pub fn new(image: impl Into<String>, status: PullStatus) -> Self {
+        PullProgress {
+            time: Utc::now(),
+            image: image.into(),
+            layer: None,
+            status,
+            current: None,
+            total: None,
+            message: None,
+        }
+    }
+
+    pub fn time(&self) -> &DateTime<Utc> {
+        &self.time
+    }
+
+    pub fn image(&self) -> &str {
+        &self.image
+    }
+
+    pub fn layer(&self) -> Option<&str> {
+        self.layer.as_deref()
+    }
+
+    #[must_use]
+    pub fn with_layer(mut self, layer: Option<String>) -> Self {
+        self.layer = layer;
+        self
+    }
+
+    pub fn status(&self) -> PullStatus {
+        self.status
+    }
+
+    pub fn current(&self) -> Option<u64> {
+        self.current
+    }
+
+    pub fn total(&self) -> Option<u64> {
+        self.total
+    }
+
+    #[must_use]
+    pub fn with_bytes(mut self, current: Option<u64>, total: Option<u64>) -> Self {
+        self.current = current;
+        self.total = total;
+        self
+    }
+
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    #[must_use]
+    pub fn with_message(mut self, message: Option<String>) -> Self {
+        self.message = message;
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PullProgressBus {
+    sender: broadcast::Sender<PullProgress>,
+    /// The latest progress of each layer of each pull in progress. Progress of the whole image
+    /// is kept under the empty layer name.
+    in_progress: Arc<Mutex<BTreeMap<String, BTreeMap<String, PullProgress>>>>,
+}
+
+impl Default for PullProgressBus {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+
+        PullProgressBus {
+            sender,
+            in_progress: Arc::default(),
+        }
+    }
+}
+
+impl PullProgressBus {
+    pub fn report(&self, progress: PullProgress) {
+        let mut in_progress = self
+            .in_progress
+            .lock()
+            .expect("pull progress lock poisoned");
+
+        if progress.status.is_final() {
+            in_progress.remove(&progress.image);
+        } else {
+            in_progress
+                .entry(progress.image.clone())
+                .or_default()
+                .insert(progress.layer.clone().unwrap_or_default(), progress.clone());
+        }
+
+        // Sending fails only if nobody is subscribed, which is fine.
+        let _ = self.sender.send(progress);
+    }
+
+    /// Returns the latest progress of the pulls in progress, along with a receiver for
+    /// progress reported afterwards.
+    pub fn subscribe(&self) -> (Vec<PullProgress>, broadcast::Receiver<PullProgress>) {
+        let in_progress = self
+            .in_progress
+            .lock()
+            .expect("pull progress lock poisoned");
+
+        let current = in_progress
+            .values()
+            .flat_map(BTreeMap::values)
+            .cloned()
+            .collect();
+
+        (current, self.sender.subscribe())
+    }
+}
+
+/// Gets the image of a module before it is created, as its pull policy allows.
+pub async fn get_image<R>(
+    registry: &R,
+    config: &R::Config,
+    policy: ImagePullPolicy,
+) -> anyhow::Result<()>
+where
+    R: ModuleRegistry + ?Sized,
+{
+    match policy {
+        ImagePullPolicy::OnCreate => registry.pull(config).await,
+
+        ImagePullPolicy::PreferLocal | ImagePullPolicy::OfflineBundle => {
+            if registry.image_exists(config).await? {
+                log::debug!("Using image that is already present as per pull policy");
+                return Ok(());
+            }
+
+            if registry.load_bundle(config).await? && registry.image_exists(config).await? {
+                return Ok(());
+            }
+
+            if policy == ImagePullPolicy::PreferLocal {
+                registry.pull(config).await
+            } else {
+                Err(anyhow::anyhow!(
+                    "image is not present, and no offline bundle has it"
+                ))
+            }
+        }
+
+        ImagePullPolicy::Never => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progress(layer: &str, status: PullStatus, current: u64) -> PullProgress {
+        PullProgress::new("ubuntu:24.04", status)
+            .with_layer(Some(layer.to_string()))
+            .with_bytes(Some(current), Some(100))
+    }
+
+    #[test]
+    fn serialize() {
+        let progress = PullProgress {
+            time: DateTime::from_timestamp(0, 0).unwrap(),
+            ..progress("a1b2c3", PullStatus::AlreadyExists, 100)
+        };
+
+        assert_eq!(
+            serde_json::to_value(&progress).unwrap(),
+            serde_json::json!({
+                "time": "1970-01-01T00:00:00Z",
+                "image": "ubuntu:24.04",
+                "layer": "a1b2c3",
+                "status": "already-exists",
+                "current": 100,
+                "total": 100,
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn subscribe() {
+        let bus = PullProgressBus::default();
+
+        bus.report(PullProgress::new("ubuntu:24.04", PullStatus::Started));
+        bus.report(progress("a", PullStatus::Downloading, 10));
+        bus.report(progress("b", PullStatus::Downloading, 20));
+        bus.report(progress("a", PullStatus::Downloading, 30));
+
+        // Only the latest progress of each layer is remembered.
+        let (current, mut receiver) = bus.subscribe();
+        let current: Vec<_> = current
+            .iter()
+            .map(|progress| (progress.layer(), progress.status(), progress.current()))
+            .collect();
+        assert_eq!(
+            current,
+            [
+                (None, PullStatus::Started, None),
+                (Some("a"), PullStatus::Downloading, Some(30)),
+                (Some("b"), PullStatus::Downloading, Some(20)),
+            ]
+        );
+
+        bus.report(PullProgress::new("ubuntu:24.04", PullStatus::Completed));
+        assert_eq!(
+            receiver.recv().await.unwrap().status(),
+            PullStatus::Completed
+        );
+
+        // Finished pulls are forgotten.
+        let (current, _) = bus.subscribe();
+        assert!(current.is_empty());
+    }
+
+    /// A registry that has the images in `present`, and can load those in `bundled`.
+    #[derive(Default)]
+    struct TestRegistry {
+        present: Mutex<Vec<&'static str>>,
+        bundled: Vec<&'static str>,
+        pulls: Mutex<Vec<&'static str>>,
+        progress: PullProgressBus,
+    }
+
+    #[async_trait::async_trait]
+    impl ModuleRegistry for TestRegistry {
+        type Config = &'static str;
+
+        async fn pull(&self, config: &Self::Config) -> anyhow::Result<()> {
+            self.pulls.lock().unwrap().push(config);
+            self.present.lock().unwrap().push(config);
+            Ok(())
+        }
+
+        async fn remove(&self, _name: &str) -> anyhow::Result<()> {
+            unimplemented!()
+        }
+
+        async fn image_exists(&self, config: &Self::Config) -> anyhow::Result<bool> {
+            Ok(self.present.lock().unwrap().contains(config))
+        }
+
+        async fn load_bundle(&self, config: &Self::Config) -> anyhow::Result<bool> {
+            if !self.bundled.contains(config) {
+                return Ok(false);
+            }
+
+            self.present.lock().unwrap().push(config);
+            Ok(true)
+        }
+
+        async fn import(&self, _path: &std::path::Path) -> anyhow::Result<Vec<String>> {
+            unimplemented!()
+        }
+
+        async fn image_use(&self) -> anyhow::Result<Vec<crate::ImageUse>> {
+            unimplemented!()
+        }
+
+        fn pull_progress(&self) -> &PullProgressBus {
+            &self.progress
+        }
+    }
+
+    #[tokio::test]
+    async fn image_policy() {
+        let registry = TestRegistry {
+            present: Mutex::new(vec!["present"]),
+            bundled: vec!["bundled"],
+            ..Default::default()
+        };
+
+        for image in ["present", "bundled", "missing"] {
+            get_image(&registry, &image, ImagePullPolicy::Never)
+                .await
+                .unwrap();
+        }
+        assert!(registry.pulls.lock().unwrap().is_empty());
+
+        // Images are pulled only if neither present nor bundled.
+        for image in ["present", "bundled", "missing"] {
+            get_image(&registry, &image, ImagePullPolicy::PreferLocal)
+                .await
+                .unwrap();
+        }
+        assert_eq!(*registry.pulls.lock().unwrap(), ["missing"]);
+
+        get_image(&registry, &"other", ImagePullPolicy::OfflineBundle)
+            .await
+            .unwrap_err();
+        assert_eq!(*registry.pulls.lock().unwrap(), ["missing"]);
+
+        get_image(&registry, &"present", ImagePullPolicy::OnCreate)
+            .await
+            .unwrap();
+        assert_eq!(*registry.pulls.lock().unwrap(), ["missing", "present"]);
+    }
+}
@@ -0,0 +1,516 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! Bookkeeping for restarts that edged performs according to each module's restart policy.
+//!
+//! The restart policy is recorded as a label on the module's container when the module is
+//! created, so that it survives restarts of edged. Modules that were stopped on request are
+//! recorded in a file, so that they stay stopped across restarts of edged as well. The restart
+//! counts and back-off timers are kept in memory only.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use chrono::{DateTime, Utc};
+
+use edgelet_settings::module::RestartPolicy;
+use edgelet_settings::watchdog::RestartBackoff;
+
+use crate::{ModuleRuntimeState, ModuleStatus};
+
+pub const RESTART_POLICY_LABEL_KEY: &str = "net.azure-devices.edge.restart-policy";
+
+/// Reads a module's restart policy from its container labels. Modules created without one
+/// are never restarted by edged.
+pub fn restart_policy(labels: Option<&BTreeMap<String, String>>) -> RestartPolicy {
+    labels
+        .and_then(|labels| labels.get(RESTART_POLICY_LABEL_KEY))
+        .and_then(|policy| policy.parse().ok())
+        .unwrap_or_default()
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RestartDecision {
+    /// The module does not need to be restarted.
+    None,
+    /// The module should be restarted once the given time has passed.
+    Wait(DateTime<Utc>),
+    /// The module should be restarted now.
+    Restart,
+    /// The module has just exceeded the maximum number of consecutive restarts. It is not
+    /// restarted again until it is started explicitly.
+    GiveUp,
+}
+
+#[derive(Debug, Default)]
+struct RestartState {
+    count: u32,
+    next_retry_at: Option<DateTime<Utc>>,
+    stopped: bool,
+    gave_up: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct RestartTracker {
+    backoff: RestartBackoff,
+    modules: Arc<Mutex<HashMap<String, RestartState>>>,
+    stopped_path: Option<PathBuf>,
+}
+
+impl RestartTracker {
+    /// A tracker that keeps everything in memory.
+    pub fn new(backoff: RestartBackoff) -> Self {
+        RestartTracker {
+            backoff,
+            modules: Arc::new(Mutex::new(HashMap::new())),
+            stopped_path: None,
+        }
+    }
+
+    /// A tracker that records the modules stopped on request in the file at `path`, starting
+    /// from the modules recorded there. A missing or unreadable file is treated as empty.
+    pub fn load(backoff: RestartBackoff, path: PathBuf) -> Self {
+        let stopped: BTreeSet<String> = match std::fs::read(&path) {
+            Ok(stopped) => serde_json::from_slice(&stopped).unwrap_or_else(|err| {
+                log::warn!("Ignoring invalid stopped modules {}: {err}", path.display());
+
+                BTreeSet::new()
+            }),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => BTreeSet::new(),
+            Err(err) => {
+                log::warn!("Failed to read stopped modules {}: {err}", path.display());
+
+                BTreeSet::new()
+            }
+        };
+
+        let modules = stopped
+            .into_iter()
+            .map(|name| {
+                let restart = RestartState {
+                    stopped: true,
+                    ..Default::default()
+                };
+                (name, restart)
+            })
+            .collect();
+
+        RestartTracker {
+            backoff,
+            modules: Arc::new(Mutex::new(modules)),
+            stopped_path: Some(path),
+        }
+    }
+
+    /// Adds the restart count and next retry time of module `name` to its runtime state.
+    #[must_use]
+    pub fn annotate(&self, name: &str, state: ModuleRuntimeState) -> ModuleRuntimeState {
+        let modules = self.modules.lock().expect("restart tracker lock poisoned");
+
+        match modules.get(name) {
+            Some(restart) => state
+                .with_restart_count(restart.count)
+                .with_next_retry_at(restart.next_retry_at),
+            None => state,
+        }
+    }
+
+    /// Called when module `name` is started, either by edged or by a client of the management
+    /// API. A module that was stopped or given up on becomes eligible for restarts again.
+    pub fn module_started(&self, name: &str) {
+        let mut modules = self.modules.lock().expect("restart tracker lock poisoned");
+        let restart = modules.entry(name.to_string()).or_default();
+
+        let was_stopped = std::mem::replace(&mut restart.stopped, false);
+        restart.next_retry_at = None;
+
+        if restart.gave_up {
+            restart.gave_up = false;
+            restart.count = 0;
+        }
+
+        if was_stopped {
+            self.save(&modules);
+        }
+    }
+
+    /// Called when module `name` is stopped on request. Stopped modules are not restarted until
+    /// they are started again.
+    pub fn module_stopped(&self, name: &str) {
+        let mut modules = self.modules.lock().expect("restart tracker lock poisoned");
+        let restart = modules.entry(name.to_string()).or_default();
+
+        let was_stopped = std::mem::replace(&mut restart.stopped, true);
+        restart.next_retry_at = None;
+
+        if !was_stopped {
+            self.save(&modules);
+        }
+    }
+
+    pub fn module_removed(&self, name: &str) {
+        let mut modules = self.modules.lock().expect("restart tracker lock poisoned");
+
+        if modules.remove(name).is_some_and(|restart| restart.stopped) {
+            self.save(&modules);
+        }
+    }
+
+    /// Decides whether module `name`, currently in `state`, should be restarted under `policy`.
+    /// The first time a restart becomes necessary, the back-off delay is scheduled from `now`.
+    pub fn evaluate(
+        &self,
+        name: &str,
+        policy: RestartPolicy,
+        state: &ModuleRuntimeState,
+        now: DateTime<Utc>,
+    ) -> RestartDecision {
+        let mut modules = self.modules.lock().expect("restart tracker lock poisoned");
+        let restart = modules.entry(name.to_string()).or_default();
+
+        if *state.status() == ModuleStatus::Running {
+            restart.next_retry_at = None;
+
+            // A module that stays up long enough is no longer considered to be crash looping.
+            let reset_after = chrono::Duration::from_std(self.backoff.reset_after())
+                .unwrap_or(chrono::Duration::MAX);
+            if state
+                .started_at()
+                .and_then(|started_at| started_at.checked_add_signed(reset_after))
+                .is_some_and(|reset_at| reset_at <= now)
+            {
+                restart.count = 0;
+            }
+
+            return RestartDecision::None;
+        }
+
+        let needs_restart = match policy {
+            RestartPolicy::Never => false,
+            RestartPolicy::Always => {
+                matches!(state.status(), ModuleStatus::Stopped | ModuleStatus::Failed)
+            }
+            RestartPolicy::OnFailure => *state.status() == ModuleStatus::Failed,
+            RestartPolicy::OnUnhealthy => {
+                matches!(
+                    state.status(),
+                    ModuleStatus::Failed | ModuleStatus::Unhealthy
+                )
+            }
+        };
+
+        if !needs_restart || restart.stopped {
+            restart.next_retry_at = None;
+            return RestartDecision::None;
+        }
+
+        if restart.gave_up {
+            return RestartDecision::None;
+        }
+
+        if self.backoff.max_restarts() <= restart.count {
+            restart.gave_up = true;
+            restart.next_retry_at = None;
+            return RestartDecision::GiveUp;
+        }
+
+        match restart.next_retry_at {
+            Some(next_retry_at) if next_retry_at <= now => RestartDecision::Restart,
+            Some(next_retry_at) => RestartDecision::Wait(next_retry_at),
+            None => {
+                let delay = chrono::Duration::from_std(self.delay(restart.count))
+                    .unwrap_or(chrono::Duration::MAX);
+                let next_retry_at = now
+                    .checked_add_signed(delay)
+                    .unwrap_or(DateTime::<Utc>::MAX_UTC);
+
+                restart.next_retry_at = Some(next_retry_at);
+
+                if next_retry_at <= now {
+                    RestartDecision::Restart
+                } else {
+                    RestartDecision::Wait(next_retry_at)
+                }
+            }
+        }
+    }
+
+    /// Called after edged has restarted module `name` under its restart policy.
+    pub fn record_restart(&self, name: &str) {
+        let mut modules = self.modules.lock().expect("restart tracker lock poisoned");
+        let restart = modules.entry(name.to_string()).or_default();
+
+        restart.count = restart.count.saturating_add(1);
+        restart.next_retry_at = None;
+    }
+
+    /// Records the modules that are stopped on request. Failures are logged, and the stops stay
+    /// in effect until edged restarts.
+    fn save(&self, modules: &HashMap<String, RestartState>) {
+        let Some(path) = &self.stopped_path else {
+            return;
+        };
+
+        let stopped: BTreeSet<&str> = modules
+            .iter()
+            .filter(|(_, restart)| restart.stopped)
+            .map(|(name, _)| name.as_str())
+            .collect();
+
+        // Replace the file atomically so that a crash never leaves it half-written.
+        let result = serde_json::to_vec(&stopped)
+            .map_err(std::io::Error::from)
+            .and_then(|stopped| {
+                let temp_path = path.with_extension("json.tmp");
+                std::fs::write(&temp_path, stopped)?;
+                std::fs::rename(&temp_path, path)
+            });
+
+        if let Err(err) = result {
+            log::warn!("Failed to save stopped modules {}: {err}", path.display());
+        }
+    }
+
+    fn delay(&self, count: u32) -> std::time::Duration {
+        self.backoff
+            .initial_delay()
+            .saturating_mul(2_u32.saturating_pow(count))
+            .min(self.backoff.max_delay())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use edgelet_settings::watchdog::MaxRetries;
+
+    use super::*;
+
+    fn backoff(max_restarts: MaxRetries) -> RestartBackoff {
+        RestartBackoff::new(
+            Duration::from_secs(10),
+            Duration::from_mins(1),
+            max_restarts,
+            Duration::from_mins(10),
+        )
+    }
+
+    fn tracker(max_restarts: MaxRetries) -> RestartTracker {
+        RestartTracker::new(backoff(max_restarts))
+    }
+
+    fn failed() -> ModuleRuntimeState {
+        ModuleRuntimeState::default()
+            .with_status(ModuleStatus::Failed)
+            .with_exit_code(Some(1))
+    }
+
+    fn restart_after(
+        tracker: &RestartTracker,
+        state: &ModuleRuntimeState,
+        now: DateTime<Utc>,
+    ) -> chrono::Duration {
+        let RestartDecision::Wait(next_retry_at) =
+            tracker.evaluate("m", RestartPolicy::Always, state, now)
+        else {
+            panic!("expected restart to be scheduled");
+        };
+
+        assert_eq!(
+            tracker.evaluate("m", RestartPolicy::Always, state, next_retry_at),
+            RestartDecision::Restart
+        );
+        tracker.record_restart("m");
+
+        next_retry_at - now
+    }
+
+    #[test]
+    fn restart_policy_label() {
+        let mut labels = BTreeMap::new();
+        assert_eq!(restart_policy(None), RestartPolicy::Never);
+        assert_eq!(restart_policy(Some(&labels)), RestartPolicy::Never);
+
+        labels.insert(
+            RESTART_POLICY_LABEL_KEY.to_string(),
+            "on-failure".to_string(),
+        );
+        assert_eq!(restart_policy(Some(&labels)), RestartPolicy::OnFailure);
+    }
+
+    #[test]
+    fn backoff_is_exponential_and_capped() {
+        let tracker = tracker(MaxRetries::Infinite);
+        let now = Utc::now();
+
+        let delays: Vec<_> = (0..5)
+            .map(|_| restart_after(&tracker, &failed(), now).num_seconds())
+            .collect();
+        assert_eq!(delays, [10, 20, 40, 60, 60]);
+
+        let state = tracker.annotate("m", ModuleRuntimeState::default());
+        assert_eq!(state.restart_count(), 5);
+        assert_eq!(state.next_retry_at(), None);
+    }
+
+    #[test]
+    fn gives_up_after_max_restarts() {
+        let tracker = tracker(MaxRetries::Num(2));
+        let now = Utc::now();
+
+        restart_after(&tracker, &failed(), now);
+        restart_after(&tracker, &failed(), now);
+        assert_eq!(
+            tracker.evaluate("m", RestartPolicy::Always, &failed(), now),
+            RestartDecision::GiveUp
+        );
+        assert_eq!(
+            tracker.evaluate("m", RestartPolicy::Always, &failed(), now),
+            RestartDecision::None
+        );
+
+        // Starting the module explicitly makes it eligible for restarts again.
+        tracker.module_started("m");
+        assert!(matches!(
+            tracker.evaluate("m", RestartPolicy::Always, &failed(), now),
+            RestartDecision::Wait(_)
+        ));
+    }
+
+    #[test]
+    fn policy_determines_restart() {
+        let tracker = tracker(MaxRetries::Infinite);
+        let now = Utc::now();
+        let stopped = ModuleRuntimeState::default()
+            .with_status(ModuleStatus::Stopped)
+            .with_exit_code(Some(0));
+
+        assert_eq!(
+            tracker.evaluate("m", RestartPolicy::Never, &failed(), now),
+            RestartDecision::None
+        );
+        assert_eq!(
+            tracker.evaluate("m", RestartPolicy::OnFailure, &stopped, now),
+            RestartDecision::None
+        );
+        assert!(matches!(
+            tracker.evaluate("m", RestartPolicy::OnFailure, &failed(), now),
+            RestartDecision::Wait(_)
+        ));
+        assert!(matches!(
+            tracker.evaluate("n", RestartPolicy::Always, &stopped, now),
+            RestartDecision::Wait(_)
+        ));
+
+        let unhealthy = ModuleRuntimeState::default().with_status(ModuleStatus::Unhealthy);
+        assert_eq!(
+            tracker.evaluate("o", RestartPolicy::OnFailure, &unhealthy, now),
+            RestartDecision::None
+        );
+        assert!(matches!(
+            tracker.evaluate("o", RestartPolicy::OnUnhealthy, &unhealthy, now),
+            RestartDecision::Wait(_)
+        ));
+    }
+
+    #[test]
+    fn stopped_modules_are_not_restarted() {
+        let tracker = tracker(MaxRetries::Infinite);
+        let now = Utc::now();
+
+        tracker.module_stopped("m");
+        assert_eq!(
+            tracker.evaluate("m", RestartPolicy::Always, &failed(), now),
+            RestartDecision::None
+        );
+
+        tracker.module_started("m");
+        assert!(matches!(
+            tracker.evaluate("m", RestartPolicy::Always, &failed(), now),
+            RestartDecision::Wait(_)
+        ));
+    }
+
+    #[test]
+    fn stops_are_persisted() {
+        let path =
+            std::env::temp_dir().join(format!("stopped_modules_{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let backoff = backoff(MaxRetries::Infinite);
+        let now = Utc::now();
+
+        let tracker = RestartTracker::load(backoff.clone(), path.clone());
+        tracker.module_stopped("m");
+        tracker.module_stopped("n");
+        tracker.module_stopped("o");
+        tracker.module_started("n");
+        tracker.module_removed("o");
+
+        // A new tracker, like the one edged creates when it restarts, keeps "m" stopped.
+        let tracker = RestartTracker::load(backoff.clone(), path.clone());
+        assert_eq!(
+            tracker.evaluate("m", RestartPolicy::Always, &failed(), now),
+            RestartDecision::None
+        );
+        assert!(matches!(
+            tracker.evaluate("n", RestartPolicy::Always, &failed(), now),
+            RestartDecision::Wait(_)
+        ));
+        assert!(matches!(
+            tracker.evaluate("o", RestartPolicy::Always, &failed(), now),
+            RestartDecision::Wait(_)
+        ));
+
+        tracker.module_started("m");
+        let tracker = RestartTracker::load(backoff.clone(), path.clone());
+        assert!(matches!(
+            tracker.evaluate("m", RestartPolicy::Always, &failed(), now),
+            RestartDecision::Wait(_)
+        ));
+
+        // An invalid file is treated as empty.
+        std::fs::write(&path, "invalid").unwrap();
+        let tracker = RestartTracker::load(backoff, path.clone());
+        assert!(matches!(
+            tracker.evaluate("m", RestartPolicy::Always, &failed(), now),
+            RestartDecision::Wait(_)
+        ));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn count_resets_after_module_stays_up() {
+        let tracker = tracker(MaxRetries::Infinite);
+        let now = Utc::now();
+
+        restart_after(&tracker, &failed(), now);
+        restart_after(&tracker, &failed(), now);
+
+        let running = ModuleRuntimeState::default()
+            .with_status(ModuleStatus::Running)
+            .with_started_at(Some(now));
+
+        tracker.evaluate("m", RestartPolicy::Always, &running, now);
+        assert_eq!(
+            tracker
+                .annotate("m", ModuleRuntimeState::default())
+                .restart_count(),
+            2
+        );
+
+        tracker.evaluate(
+            "m",
+            RestartPolicy::Always,
+            &running,
+            now + chrono::Duration::minutes(10),
+        );
+        assert_eq!(
+            tracker
+                .annotate("m", ModuleRuntimeState::default())
+                .restart_count(),
+            0
+        );
+    }
+}
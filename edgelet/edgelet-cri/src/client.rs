@@ -0,0 +1,84 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use anyhow::Context;
+use k8s_cri::v1::image_service_client::ImageServiceClient;
+use k8s_cri::v1::runtime_service_client::RuntimeServiceClient;
+use tonic::transport::{Channel, Endpoint, Uri};
+
+use edgelet_core::UrlExt;
+
+use crate::error::Error;
+
+/// gRPC clients for the runtime and image services of a CRI endpoint.
+///
+/// The underlying channels are cheap to clone, so each call clones the client it needs.
+#[derive(Clone)]
+pub struct CriClient {
+    runtime: RuntimeServiceClient<Channel>,
+    image: ImageServiceClient<Channel>,
+}
+
+impl std::fmt::Debug for CriClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CriClient").finish()
+    }
+}
+
+impl CriClient {
+    pub async fn connect(runtime_uri: &url::Url, image_uri: &url::Url) -> anyhow::Result<Self> {
+        let runtime_channel = channel(runtime_uri).await?;
+        let image_channel = if image_uri == runtime_uri {
+            runtime_channel.clone()
+        } else {
+            channel(image_uri).await?
+        };
+
+        Ok(CriClient {
+            runtime: RuntimeServiceClient::new(runtime_channel),
+            image: ImageServiceClient::new(image_channel),
+        })
+    }
+
+    pub fn runtime(&self) -> RuntimeServiceClient<Channel> {
+        self.runtime.clone()
+    }
+
+    pub fn image(&self) -> ImageServiceClient<Channel> {
+        self.image.clone()
+    }
+}
+
+async fn channel(uri: &url::Url) -> anyhow::Result<Channel> {
+    match uri.scheme() {
+        "unix" => {
+            let path = uri.to_uds_file_path().context(Error::Initialization)?;
+
+            // tonic requires an HTTP endpoint even though the connector below ignores it.
+            Endpoint::from_static("http://[::]:50051")
+                .connect_with_connector(tower::service_fn(move |_: Uri| {
+                    let path = path.clone();
+
+                    async move {
+                        let stream = tokio::net::UnixStream::connect(path).await?;
+
+                        Ok::<_, std::io::Error>(hyper_util::rt::TokioIo::new(stream))
+                    }
+                }))
+                .await
+                .with_context(|| format!("could not connect to CRI endpoint {uri}"))
+                .context(Error::Initialization)
+        }
+
+        "http" | "https" => Endpoint::from_shared(uri.to_string())
+            .context(Error::Initialization)?
+            .connect()
+            .await
+            .with_context(|| format!("could not connect to CRI endpoint {uri}"))
+            .context(Error::Initialization),
+
+        scheme => Err(anyhow::anyhow!(
+            "unsupported CRI endpoint scheme {scheme:?}"
+        ))
+        .context(Error::Initialization),
+    }
+}
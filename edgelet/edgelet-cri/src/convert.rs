@@ -0,0 +1,685 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! Translation of Docker create options into CRI pod sandbox and container configs.
+//!
+//! Each module runs as the only container in its own pod sandbox. Network-level options
+//! (port bindings, host networking, hostname) are applied to the sandbox; everything else is
+//! applied to the container. Options that have no CRI equivalent are ignored with a warning.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use docker::models::ContainerCreateBody;
+use k8s_cri::v1::{
+    Capability, ContainerConfig, ContainerMetadata, Device, ImageSpec, Int64Value, KeyValue,
+    LinuxContainerConfig, LinuxContainerResources, LinuxContainerSecurityContext,
+    LinuxPodSandboxConfig, LinuxSandboxSecurityContext, Mount, NamespaceMode, NamespaceOption,
+    PodSandboxConfig, PodSandboxMetadata, PortMapping, Protocol,
+};
+
+use edgelet_docker::DEFAULT_CPU_PERIOD;
+
+use crate::error::Error;
+
+pub(crate) const OWNER_LABEL_KEY: &str = "net.azure-devices.edge.owner";
+pub(crate) const OWNER_LABEL_VALUE: &str = "Microsoft.Azure.Devices.Edge.Agent";
+pub(crate) const MODULE_LABEL_KEY: &str = "net.azure-devices.edge.module";
+pub(crate) const IMAGE_ID_ANNOTATION_KEY: &str = "net.azure-devices.edge.image-id";
+pub(crate) const ORIGINAL_IMAGE_ANNOTATION_KEY: &str = "net.azure-devices.edge.original-image";
+pub(crate) const CREATE_OPTIONS_ANNOTATION_KEY: &str = "net.azure-devices.edge.create-options";
+
+/// Name of the module's log file, relative to its sandbox's log directory.
+pub(crate) fn container_log_path(name: &str) -> String {
+    format!("{name}.log")
+}
+
+/// Directory that holds the log files of the module's sandbox.
+pub(crate) fn sandbox_log_directory(log_directory: &Path, namespace: &str, name: &str) -> String {
+    log_directory
+        .join(format!("{namespace}_{name}"))
+        .to_string_lossy()
+        .into_owned()
+}
+
+pub(crate) fn sandbox_config(
+    name: &str,
+    namespace: &str,
+    attempt: u32,
+    log_directory: &Path,
+    create_options: &ContainerCreateBody,
+) -> Result<PodSandboxConfig, Error> {
+    let host_config = create_options.host_config.as_ref();
+
+    let host_network = host_config
+        .and_then(|host_config| host_config.other_properties.get("NetworkMode"))
+        .and_then(serde_json::Value::as_str)
+        == Some("host");
+
+    let privileged = host_config
+        .and_then(|host_config| host_config.privileged)
+        .unwrap_or_default();
+
+    if create_options.networking_config.is_some() {
+        log::warn!(
+            "Module {name}: NetworkingConfig is not supported by the CRI runtime and will be ignored"
+        );
+    }
+
+    if host_config.is_some_and(|host_config| host_config.extra_hosts.is_some()) {
+        log::warn!(
+            "Module {name}: HostConfig.ExtraHosts is not supported by the CRI runtime and will be ignored"
+        );
+    }
+
+    let mut port_mappings = vec![];
+    if let Some(port_bindings) =
+        host_config.and_then(|host_config| host_config.port_bindings.as_ref())
+    {
+        for (port, bindings) in port_bindings {
+            let (container_port, protocol) = parse_port(port)?;
+
+            for binding in bindings {
+                let host_port = match binding.host_port.as_deref() {
+                    None | Some("") => container_port,
+                    Some(host_port) => host_port.parse().map_err(|_| {
+                        Error::InvalidCreateOptions(format!("invalid host port {host_port:?}"))
+                    })?,
+                };
+
+                let host_ip = binding
+                    .other_properties
+                    .get("HostIp")
+                    .and_then(serde_json::Value::as_str)
+                    .unwrap_or_default()
+                    .to_owned();
+
+                port_mappings.push(PortMapping {
+                    protocol: protocol.into(),
+                    container_port,
+                    host_port,
+                    host_ip,
+                });
+            }
+        }
+    }
+
+    let hostname = if host_network {
+        String::new()
+    } else {
+        create_options
+            .hostname
+            .clone()
+            .unwrap_or_else(|| name.to_owned())
+    };
+
+    Ok(PodSandboxConfig {
+        metadata: Some(PodSandboxMetadata {
+            name: name.to_owned(),
+            uid: name.to_owned(),
+            namespace: namespace.to_owned(),
+            attempt,
+        }),
+        hostname,
+        log_directory: sandbox_log_directory(log_directory, namespace, name),
+        port_mappings,
+        labels: module_labels(name),
+        linux: Some(LinuxPodSandboxConfig {
+            security_context: Some(LinuxSandboxSecurityContext {
+                namespace_options: Some(namespace_options(host_network)),
+                privileged,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }),
+        ..Default::default()
+    })
+}
+
+pub(crate) fn container_config(
+    name: &str,
+    attempt: u32,
+    original_image: &str,
+    volume_directory: &Path,
+    create_options: &ContainerCreateBody,
+) -> Result<ContainerConfig, Error> {
+    let image = create_options
+        .image
+        .clone()
+        .ok_or_else(|| Error::InvalidCreateOptions("image is not set".to_owned()))?;
+
+    let envs = create_options
+        .env
+        .iter()
+        .flatten()
+        .map(|env| {
+            let (key, value) = env.split_once('=').unwrap_or((env, ""));
+            KeyValue {
+                key: key.to_owned(),
+                value: value.to_owned(),
+            }
+        })
+        .collect();
+
+    let host_config = create_options.host_config.clone().unwrap_or_default();
+
+    let mut mounts = vec![];
+    for bind in host_config.binds.iter().flatten() {
+        mounts.push(parse_bind(bind, volume_directory)?);
+    }
+    for mount in host_config.mounts.iter().flatten() {
+        let target = mount
+            .target
+            .clone()
+            .ok_or_else(|| Error::InvalidCreateOptions("mount target is not set".to_owned()))?;
+        let source = mount.source.clone().unwrap_or_default();
+        let host_path = match mount.r#type.as_deref() {
+            None | Some("bind") => source,
+            Some("volume") => volume_path(volume_directory, &source)?,
+            Some(other) => {
+                return Err(Error::InvalidCreateOptions(format!(
+                    "mount type {other:?} is not supported"
+                )));
+            }
+        };
+
+        mounts.push(Mount {
+            container_path: target,
+            host_path,
+            readonly: mount.read_only.unwrap_or_default(),
+            ..Default::default()
+        });
+    }
+
+    let devices = match host_config.other_properties.get("Devices") {
+        Some(devices) => parse_devices(devices)?,
+        None => vec![],
+    };
+
+    let host_network = host_config
+        .other_properties
+        .get("NetworkMode")
+        .and_then(serde_json::Value::as_str)
+        == Some("host");
+
+    let mut security_context = LinuxContainerSecurityContext {
+        privileged: host_config.privileged.unwrap_or_default(),
+        capabilities: Some(Capability {
+            add_capabilities: normalize_capabilities(host_config.cap_add.as_deref()),
+            drop_capabilities: normalize_capabilities(host_config.cap_drop.as_deref()),
+            ..Default::default()
+        }),
+        namespace_options: Some(namespace_options(host_network)),
+        ..Default::default()
+    };
+    if let Some(user) = create_options
+        .other_properties
+        .get("User")
+        .and_then(serde_json::Value::as_str)
+        .filter(|user| !user.is_empty())
+    {
+        let (user, group) = user
+            .split_once(':')
+            .map_or((user, None), |(u, g)| (u, Some(g)));
+        match user.parse() {
+            Ok(uid) => security_context.run_as_user = Some(Int64Value { value: uid }),
+            Err(_) => security_context.run_as_username = user.to_owned(),
+        }
+        if let Some(group) = group {
+            let gid = group.parse().map_err(|_| {
+                Error::InvalidCreateOptions(format!("group {group:?} must be numeric"))
+            })?;
+            security_context.run_as_group = Some(Int64Value { value: gid });
+        }
+    }
+
+    if host_config.pids_limit.is_some() || host_config.ulimits.is_some() {
+        log::warn!(
+            "Module {name}: HostConfig.PidsLimit and HostConfig.Ulimits are not supported by the CRI runtime and will be ignored"
+        );
+    }
+
+    // CRI has no equivalent of NanoCpus, so express it as a quota over the default period.
+    let (cpu_period, cpu_quota) = match host_config.nano_cpus {
+        Some(nano_cpus) if nano_cpus > 0 => (
+            DEFAULT_CPU_PERIOD,
+            nano_cpus * DEFAULT_CPU_PERIOD / 1_000_000_000,
+        ),
+        _ => (
+            host_config.cpu_period.unwrap_or_default(),
+            host_config.cpu_quota.unwrap_or_default(),
+        ),
+    };
+
+    let resources = LinuxContainerResources {
+        memory_limit_in_bytes: host_config.memory.unwrap_or_default(),
+        memory_swap_limit_in_bytes: host_config.memory_swap.unwrap_or_default(),
+        cpu_shares: host_config.cpu_shares.unwrap_or_default(),
+        cpu_period,
+        cpu_quota,
+        ..Default::default()
+    };
+
+    let working_dir = create_options
+        .other_properties
+        .get("WorkingDir")
+        .and_then(serde_json::Value::as_str)
+        .unwrap_or_default()
+        .to_owned();
+
+    let tty = create_options
+        .other_properties
+        .get("Tty")
+        .and_then(serde_json::Value::as_bool)
+        .unwrap_or_default();
+
+    let create_options_json = serde_json::to_string(create_options)
+        .map_err(|err| Error::InvalidCreateOptions(err.to_string()))?;
+
+    let mut annotations = HashMap::new();
+    annotations.insert(
+        ORIGINAL_IMAGE_ANNOTATION_KEY.to_owned(),
+        original_image.to_owned(),
+    );
+    annotations.insert(
+        CREATE_OPTIONS_ANNOTATION_KEY.to_owned(),
+        create_options_json,
+    );
+
+    let mut labels: HashMap<_, _> = create_options
+        .labels
+        .iter()
+        .flatten()
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect();
+    labels.extend(module_labels(name));
+
+    Ok(ContainerConfig {
+        metadata: Some(ContainerMetadata {
+            name: name.to_owned(),
+            attempt,
+        }),
+        image: Some(ImageSpec {
+            image,
+            ..Default::default()
+        }),
+        command: create_options.entrypoint.clone().unwrap_or_default(),
+        args: create_options.cmd.clone().unwrap_or_default(),
+        working_dir,
+        envs,
+        mounts,
+        devices,
+        labels,
+        annotations,
+        log_path: container_log_path(name),
+        tty,
+        linux: Some(LinuxContainerConfig {
+            resources: Some(resources),
+            security_context: Some(security_context),
+        }),
+        ..Default::default()
+    })
+}
+
+/// Labels that identify the sandbox and container of a module.
+pub(crate) fn module_labels(name: &str) -> HashMap<String, String> {
+    let mut labels = HashMap::new();
+    labels.insert(OWNER_LABEL_KEY.to_owned(), OWNER_LABEL_VALUE.to_owned());
+    labels.insert(MODULE_LABEL_KEY.to_owned(), name.to_owned());
+    labels
+}
+
+fn namespace_options(host_network: bool) -> NamespaceOption {
+    NamespaceOption {
+        network: if host_network {
+            NamespaceMode::Node
+        } else {
+            NamespaceMode::Pod
+        }
+        .into(),
+        pid: NamespaceMode::Container.into(),
+        ipc: NamespaceMode::Pod.into(),
+        ..Default::default()
+    }
+}
+
+fn parse_port(port: &str) -> Result<(i32, Protocol), Error> {
+    let (port_number, protocol) = port.split_once('/').unwrap_or((port, "tcp"));
+
+    let port_number = port_number
+        .parse()
+        .map_err(|_| Error::InvalidCreateOptions(format!("invalid container port {port:?}")))?;
+
+    let protocol = match protocol {
+        "tcp" => Protocol::Tcp,
+        "udp" => Protocol::Udp,
+        "sctp" => Protocol::Sctp,
+        _ => {
+            return Err(Error::InvalidCreateOptions(format!(
+                "invalid protocol in container port {port:?}"
+            )));
+        }
+    };
+
+    Ok((port_number, protocol))
+}
+
+fn parse_bind(bind: &str, volume_directory: &Path) -> Result<Mount, Error> {
+    let mut parts = bind.split(':');
+
+    let (source, target) = match (parts.next(), parts.next()) {
+        (Some(source), Some(target)) if !source.is_empty() && !target.is_empty() => {
+            (source, target)
+        }
+        _ => {
+            return Err(Error::InvalidCreateOptions(format!(
+                "invalid bind {bind:?}"
+            )));
+        }
+    };
+
+    let readonly = parts
+        .next()
+        .is_some_and(|options| options.split(',').any(|option| option == "ro"));
+
+    // Binds whose source is not an absolute path refer to named volumes.
+    let host_path = if source.starts_with('/') {
+        source.to_owned()
+    } else {
+        volume_path(volume_directory, source)?
+    };
+
+    Ok(Mount {
+        container_path: target.to_owned(),
+        host_path,
+        readonly,
+        ..Default::default()
+    })
+}
+
+fn volume_path(volume_directory: &Path, volume: &str) -> Result<String, Error> {
+    if volume.is_empty() || volume.contains('/') || volume == "." || volume == ".." {
+        return Err(Error::InvalidCreateOptions(format!(
+            "invalid volume name {volume:?}"
+        )));
+    }
+
+    Ok(volume_directory.join(volume).to_string_lossy().into_owned())
+}
+
+fn parse_devices(devices: &serde_json::Value) -> Result<Vec<Device>, Error> {
+    #[derive(serde::Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct DockerDevice {
+        path_on_host: String,
+        #[serde(default)]
+        path_in_container: String,
+        #[serde(default)]
+        cgroup_permissions: String,
+    }
+
+    let devices: Vec<DockerDevice> = serde_json::from_value(devices.clone())
+        .map_err(|err| Error::InvalidCreateOptions(format!("invalid devices: {err}")))?;
+
+    Ok(devices
+        .into_iter()
+        .map(|device| Device {
+            container_path: if device.path_in_container.is_empty() {
+                device.path_on_host.clone()
+            } else {
+                device.path_in_container
+            },
+            host_path: device.path_on_host,
+            permissions: if device.cgroup_permissions.is_empty() {
+                "rwm".to_owned()
+            } else {
+                device.cgroup_permissions
+            },
+        })
+        .collect())
+}
+
+// Docker accepts capabilities with or without the CAP_ prefix; CRI runtimes expect them without.
+fn normalize_capabilities(capabilities: Option<&[String]>) -> Vec<String> {
+    capabilities
+        .unwrap_or_default()
+        .iter()
+        .map(|capability| {
+            capability
+                .strip_prefix("CAP_")
+                .unwrap_or(capability)
+                .to_owned()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use docker::models::{HostConfig, HostConfigPortBindings};
+
+    use super::*;
+
+    fn create_options(host_config: HostConfig) -> ContainerCreateBody {
+        ContainerCreateBody {
+            image: Some("mcr.microsoft.com/azureiotedge-agent:1.5".to_owned()),
+            host_config: Some(host_config),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn sandbox_config_port_bindings() {
+        let mut port_bindings = BTreeMap::new();
+        port_bindings.insert(
+            "8883/tcp".to_owned(),
+            vec![HostConfigPortBindings {
+                host_port: Some("8883".to_owned()),
+                ..Default::default()
+            }],
+        );
+        port_bindings.insert(
+            "5671/udp".to_owned(),
+            vec![HostConfigPortBindings {
+                host_port: None,
+                ..Default::default()
+            }],
+        );
+
+        let config = sandbox_config(
+            "edgeHub",
+            "azure-iot-edge",
+            0,
+            Path::new("/var/log/pods"),
+            &create_options(HostConfig {
+                port_bindings: Some(port_bindings),
+                ..Default::default()
+            }),
+        )
+        .unwrap();
+
+        assert_eq!(config.hostname, "edgeHub");
+        assert_eq!(config.log_directory, "/var/log/pods/azure-iot-edge_edgeHub");
+        assert_eq!(config.port_mappings.len(), 2);
+        assert_eq!(config.port_mappings[0].container_port, 5671);
+        assert_eq!(config.port_mappings[0].host_port, 5671);
+        assert_eq!(config.port_mappings[0].protocol, i32::from(Protocol::Udp));
+        assert_eq!(config.port_mappings[1].container_port, 8883);
+        assert_eq!(config.port_mappings[1].protocol, i32::from(Protocol::Tcp));
+    }
+
+    #[test]
+    fn sandbox_config_invalid_port() {
+        let mut port_bindings = BTreeMap::new();
+        port_bindings.insert("http/tcp".to_owned(), vec![]);
+
+        let result = sandbox_config(
+            "edgeHub",
+            "azure-iot-edge",
+            0,
+            Path::new("/var/log/pods"),
+            &create_options(HostConfig {
+                port_bindings: Some(port_bindings),
+                ..Default::default()
+            }),
+        );
+
+        assert!(matches!(result, Err(Error::InvalidCreateOptions(_))));
+    }
+
+    #[test]
+    fn sandbox_config_host_network() {
+        let mut other_properties = BTreeMap::new();
+        other_properties.insert("NetworkMode".to_owned(), serde_json::json!("host"));
+
+        let config = sandbox_config(
+            "module",
+            "azure-iot-edge",
+            0,
+            Path::new("/var/log/pods"),
+            &create_options(HostConfig {
+                other_properties,
+                ..Default::default()
+            }),
+        )
+        .unwrap();
+
+        let namespace_options = config
+            .linux
+            .unwrap()
+            .security_context
+            .unwrap()
+            .namespace_options
+            .unwrap();
+        assert_eq!(namespace_options.network, i32::from(NamespaceMode::Node));
+        assert!(config.hostname.is_empty());
+    }
+
+    #[test]
+    fn container_config_translates_create_options() {
+        let mut create_options = create_options(HostConfig {
+            binds: Some(vec![
+                "/etc/iotedge:/etc/iotedge:ro".to_owned(),
+                "edgehub-data:/data".to_owned(),
+            ]),
+            cap_add: Some(vec!["CAP_NET_ADMIN".to_owned()]),
+            cap_drop: Some(vec!["CHOWN".to_owned()]),
+            memory: Some(1024),
+            cpu_shares: Some(512),
+            nano_cpus: Some(1_500_000_000),
+            ..Default::default()
+        });
+        create_options.env = Some(vec!["A=1".to_owned(), "B=x=y".to_owned()]);
+        create_options.entrypoint = Some(vec!["dotnet".to_owned()]);
+        create_options.cmd = Some(vec!["Agent.dll".to_owned()]);
+        create_options
+            .other_properties
+            .insert("User".to_owned(), serde_json::json!("1000:1001"));
+
+        let config = container_config(
+            "edgeAgent",
+            2,
+            "azureiotedge-agent:1.5",
+            Path::new("/var/lib/volumes"),
+            &create_options,
+        )
+        .unwrap();
+
+        assert_eq!(config.metadata.unwrap().attempt, 2);
+        assert_eq!(
+            config.image.unwrap().image,
+            "mcr.microsoft.com/azureiotedge-agent:1.5"
+        );
+        assert_eq!(config.command, vec!["dotnet"]);
+        assert_eq!(config.args, vec!["Agent.dll"]);
+        assert_eq!(config.envs.len(), 2);
+        assert_eq!(config.envs[1].key, "B");
+        assert_eq!(config.envs[1].value, "x=y");
+        assert_eq!(config.log_path, "edgeAgent.log");
+
+        assert_eq!(config.mounts.len(), 2);
+        assert_eq!(config.mounts[0].host_path, "/etc/iotedge");
+        assert!(config.mounts[0].readonly);
+        assert_eq!(config.mounts[1].host_path, "/var/lib/volumes/edgehub-data");
+        assert_eq!(config.mounts[1].container_path, "/data");
+        assert!(!config.mounts[1].readonly);
+
+        assert_eq!(config.labels[OWNER_LABEL_KEY], OWNER_LABEL_VALUE);
+        assert_eq!(config.labels[MODULE_LABEL_KEY], "edgeAgent");
+        assert_eq!(
+            config.annotations[ORIGINAL_IMAGE_ANNOTATION_KEY],
+            "azureiotedge-agent:1.5"
+        );
+        let stored: ContainerCreateBody =
+            serde_json::from_str(&config.annotations[CREATE_OPTIONS_ANNOTATION_KEY]).unwrap();
+        assert_eq!(stored.cmd, create_options.cmd);
+
+        let linux = config.linux.unwrap();
+        let resources = linux.resources.unwrap();
+        assert_eq!(resources.memory_limit_in_bytes, 1024);
+        assert_eq!(resources.cpu_shares, 512);
+        assert_eq!(resources.cpu_period, 100_000);
+        assert_eq!(resources.cpu_quota, 150_000);
+        let security_context = linux.security_context.unwrap();
+        let capabilities = security_context.capabilities.unwrap();
+        assert_eq!(capabilities.add_capabilities, vec!["NET_ADMIN"]);
+        assert_eq!(capabilities.drop_capabilities, vec!["CHOWN"]);
+        assert_eq!(security_context.run_as_user.unwrap().value, 1000);
+        assert_eq!(security_context.run_as_group.unwrap().value, 1001);
+    }
+
+    #[test]
+    fn container_config_devices() {
+        let mut other_properties = BTreeMap::new();
+        other_properties.insert(
+            "Devices".to_owned(),
+            serde_json::json!([{ "PathOnHost": "/dev/ttyUSB0" }]),
+        );
+
+        let config = container_config(
+            "module",
+            0,
+            "image",
+            Path::new("/var/lib/volumes"),
+            &create_options(HostConfig {
+                other_properties,
+                ..Default::default()
+            }),
+        )
+        .unwrap();
+
+        assert_eq!(config.devices.len(), 1);
+        assert_eq!(config.devices[0].host_path, "/dev/ttyUSB0");
+        assert_eq!(config.devices[0].container_path, "/dev/ttyUSB0");
+        assert_eq!(config.devices[0].permissions, "rwm");
+    }
+
+    #[test]
+    fn container_config_rejects_invalid_volume() {
+        let result = container_config(
+            "module",
+            0,
+            "image",
+            Path::new("/var/lib/volumes"),
+            &create_options(HostConfig {
+                binds: Some(vec!["../etc:/data".to_owned()]),
+                ..Default::default()
+            }),
+        );
+
+        assert!(matches!(result, Err(Error::InvalidCreateOptions(_))));
+    }
+
+    #[test]
+    fn container_config_requires_image() {
+        let result = container_config(
+            "module",
+            0,
+            "image",
+            Path::new("/var/lib/volumes"),
+            &ContainerCreateBody::default(),
+        );
+
+        assert!(matches!(result, Err(Error::InvalidCreateOptions(_))));
+    }
+}
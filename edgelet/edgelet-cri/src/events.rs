@@ -0,0 +1,217 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::collections::HashMap;
+
+use chrono::DateTime;
+use k8s_cri::v1::{ContainerEventResponse, ContainerEventType};
+use tokio::sync::mpsc;
+
+use edgelet_core::{EventKind, EventStream, ModuleEvent};
+
+use crate::convert::{
+    MODULE_LABEL_KEY, ORIGINAL_IMAGE_ANNOTATION_KEY, OWNER_LABEL_KEY, OWNER_LABEL_VALUE,
+};
+
+/// Module names of the containers seen so far. Deletion events usually no longer carry the
+/// container's status, so the name has to be remembered from earlier events.
+#[derive(Debug, Default)]
+pub(crate) struct ContainerNames(HashMap<String, (String, Option<String>)>);
+
+/// Converts a CRI container event into module events. A container that stops because it ran
+/// out of memory produces both a `died` and an `oom-killed` event.
+pub(crate) fn module_events(
+    response: ContainerEventResponse,
+    names: &mut ContainerNames,
+) -> Vec<ModuleEvent> {
+    let time = DateTime::from_timestamp_nanos(response.created_at);
+
+    let status = response
+        .containers_statuses
+        .into_iter()
+        .find(|status| status.id == response.container_id);
+
+    if let Some(status) = &status {
+        if status.labels.get(OWNER_LABEL_KEY).map(String::as_str) != Some(OWNER_LABEL_VALUE) {
+            return vec![];
+        }
+
+        if let Some(name) = status.labels.get(MODULE_LABEL_KEY) {
+            let image = status
+                .annotations
+                .get(ORIGINAL_IMAGE_ANNOTATION_KEY)
+                .cloned()
+                .or_else(|| status.image.as_ref().map(|image| image.image.clone()));
+
+            names
+                .0
+                .insert(response.container_id.clone(), (name.clone(), image));
+        }
+    }
+
+    let Ok(kind) = ContainerEventType::try_from(response.container_event_type) else {
+        return vec![];
+    };
+
+    let (module, image) = if kind == ContainerEventType::ContainerDeletedEvent {
+        names.0.remove(&response.container_id)
+    } else {
+        names.0.get(&response.container_id).cloned()
+    }
+    .map_or((None, None), |(name, image)| (Some(name), image));
+
+    // Containers that were never seen with the owner label are not modules.
+    if module.is_none() {
+        return vec![];
+    }
+
+    let event = |kind| {
+        ModuleEvent::new(time, kind)
+            .with_module(module.clone())
+            .with_image(image.clone())
+    };
+
+    match kind {
+        ContainerEventType::ContainerCreatedEvent => vec![event(EventKind::Created)],
+        ContainerEventType::ContainerStartedEvent => vec![event(EventKind::Started)],
+        ContainerEventType::ContainerStoppedEvent => {
+            let exit_code = status.as_ref().map(|status| i64::from(status.exit_code));
+            let oom_killed = status
+                .as_ref()
+                .is_some_and(|status| status.reason == "OOMKilled");
+
+            let mut events = vec![event(EventKind::Died).with_exit_code(exit_code)];
+            if oom_killed {
+                events.push(event(EventKind::OomKilled));
+            }
+
+            events
+        }
+        ContainerEventType::ContainerDeletedEvent => vec![event(EventKind::Removed)],
+    }
+}
+
+/// Forwards the container events of a CRI event stream. CRI does not report image pulls.
+pub(crate) fn stream(mut events: tonic::Streaming<ContainerEventResponse>) -> EventStream {
+    let (sender, receiver) = mpsc::channel(64);
+
+    tokio::spawn(async move {
+        let mut names = ContainerNames::default();
+
+        loop {
+            let response = match events.message().await {
+                Ok(Some(response)) => response,
+                Ok(None) => return,
+                Err(err) => {
+                    log::warn!("Failed to read CRI container events: {err}");
+                    return;
+                }
+            };
+
+            for event in module_events(response, &mut names) {
+                if sender.send(event).await.is_err() {
+                    return;
+                }
+            }
+        }
+    });
+
+    receiver
+}
+
+#[cfg(test)]
+mod tests {
+    use k8s_cri::v1::ContainerStatus;
+
+    use super::*;
+
+    fn response(
+        kind: ContainerEventType,
+        status: Option<ContainerStatus>,
+    ) -> ContainerEventResponse {
+        ContainerEventResponse {
+            container_id: "abc".to_owned(),
+            container_event_type: kind.into(),
+            created_at: 1_704_164_645_000_000_000,
+            containers_statuses: status.into_iter().collect(),
+            ..Default::default()
+        }
+    }
+
+    fn status(labels: &[(&str, &str)]) -> ContainerStatus {
+        ContainerStatus {
+            id: "abc".to_owned(),
+            labels: labels
+                .iter()
+                .map(|(k, v)| ((*k).to_owned(), (*v).to_owned()))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn module_status() -> ContainerStatus {
+        status(&[
+            (OWNER_LABEL_KEY, OWNER_LABEL_VALUE),
+            (MODULE_LABEL_KEY, "tempSensor"),
+        ])
+    }
+
+    #[test]
+    fn lifecycle() {
+        let mut names = ContainerNames::default();
+
+        let events = module_events(
+            response(
+                ContainerEventType::ContainerStartedEvent,
+                Some(module_status()),
+            ),
+            &mut names,
+        );
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind(), EventKind::Started);
+        assert_eq!(events[0].module(), Some("tempSensor"));
+        assert_eq!(events[0].time().timestamp(), 1_704_164_645);
+
+        let oom_killed = ContainerStatus {
+            exit_code: 137,
+            reason: "OOMKilled".to_owned(),
+            ..module_status()
+        };
+        let events = module_events(
+            response(ContainerEventType::ContainerStoppedEvent, Some(oom_killed)),
+            &mut names,
+        );
+        let kinds: Vec<_> = events.iter().map(ModuleEvent::kind).collect();
+        assert_eq!(kinds, [EventKind::Died, EventKind::OomKilled]);
+        assert_eq!(events[0].exit_code(), Some(137));
+
+        // The status of deleted containers is no longer available.
+        let events = module_events(
+            response(ContainerEventType::ContainerDeletedEvent, None),
+            &mut names,
+        );
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind(), EventKind::Removed);
+        assert_eq!(events[0].module(), Some("tempSensor"));
+        assert!(names.0.is_empty());
+    }
+
+    #[test]
+    fn foreign_containers_are_ignored() {
+        let mut names = ContainerNames::default();
+
+        let events = module_events(
+            response(
+                ContainerEventType::ContainerCreatedEvent,
+                Some(status(&[(MODULE_LABEL_KEY, "tempSensor")])),
+            ),
+            &mut names,
+        );
+        assert!(events.is_empty());
+
+        let events = module_events(
+            response(ContainerEventType::ContainerDeletedEvent, None),
+            &mut names,
+        );
+        assert!(events.is_empty());
+    }
+}
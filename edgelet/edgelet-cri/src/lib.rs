@@ -0,0 +1,14 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+mod client;
+mod convert;
+mod error;
+mod events;
+mod logs;
+mod module;
+mod runtime;
+
+pub use client::CriClient;
+pub use error::Error;
+pub use module::CriModule;
+pub use runtime::CriModuleRuntime;
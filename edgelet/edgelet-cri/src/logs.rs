@@ -0,0 +1,392 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! Reading of module logs written by the CRI runtime.
+//!
+//! CRI runtimes write each container's output to a file, one line per record:
+//! `<RFC 3339 timestamp> <stdout|stderr> <P|F> <message>`, where `P` marks a partial record that
+//! is continued on the next line. The records are re-encoded in the Docker multiplexed stream
+//! format so that consumers of [`edgelet_core::LogStream`] need not care which runtime is in use.
+
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use bytes::{BufMut, Bytes, BytesMut};
+use chrono::{DateTime, SecondsFormat, Utc};
+use http_body::Frame;
+use http_body_util::{BodyExt as _, Full};
+use tokio::sync::mpsc;
+
+use edgelet_core::{LogOptions, LogStream, LogTail};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Stream {
+    Stdout,
+    Stderr,
+}
+
+impl Stream {
+    fn header_byte(self) -> u8 {
+        match self {
+            Stream::Stdout => 1,
+            Stream::Stderr => 2,
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) struct Record {
+    pub(crate) timestamp: DateTime<Utc>,
+    pub(crate) stream: Stream,
+    pub(crate) message: Vec<u8>,
+}
+
+impl Record {
+    /// Encodes the record as a single frame of the Docker multiplexed stream format.
+    pub(crate) fn to_frame(&self, timestamps: bool) -> Bytes {
+        let timestamp = timestamps.then(|| {
+            format!(
+                "{} ",
+                self.timestamp.to_rfc3339_opts(SecondsFormat::Nanos, true)
+            )
+        });
+        let timestamp = timestamp.as_deref().unwrap_or_default().as_bytes();
+
+        let len = timestamp.len() + self.message.len() + 1;
+
+        let mut frame = BytesMut::with_capacity(8 + len);
+        frame.put_u8(self.stream.header_byte());
+        frame.put_bytes(0, 3);
+        frame.put_u32(u32::try_from(len).unwrap_or(u32::MAX));
+        frame.put_slice(timestamp);
+        frame.put_slice(&self.message);
+        frame.put_u8(b'\n');
+
+        frame.freeze()
+    }
+}
+
+/// Assembles records from lines of a CRI log file, joining partial lines.
+#[derive(Debug, Default)]
+pub(crate) struct Parser {
+    partial: Option<Record>,
+}
+
+impl Parser {
+    /// Parses one line, without its trailing newline. Returns the record if the line completes
+    /// one. Malformed lines are skipped.
+    pub(crate) fn parse_line(&mut self, line: &[u8]) -> Option<Record> {
+        let mut fields = line.splitn(4, |&b| b == b' ');
+
+        let timestamp = std::str::from_utf8(fields.next()?).ok()?;
+        let timestamp = DateTime::parse_from_rfc3339(timestamp)
+            .ok()?
+            .with_timezone(&Utc);
+
+        let stream = match fields.next()? {
+            b"stdout" => Stream::Stdout,
+            b"stderr" => Stream::Stderr,
+            _ => return None,
+        };
+
+        let is_partial = match fields.next()? {
+            b"P" => true,
+            b"F" => false,
+            _ => return None,
+        };
+
+        let message = fields.next().unwrap_or_default();
+
+        let mut record = match self.partial.take() {
+            Some(mut partial) if partial.stream == stream => {
+                partial.message.extend_from_slice(message);
+                partial
+            }
+            _ => Record {
+                timestamp,
+                stream,
+                message: message.to_vec(),
+            },
+        };
+
+        if is_partial {
+            self.partial = Some(record);
+            None
+        } else {
+            record.message.shrink_to_fit();
+            Some(record)
+        }
+    }
+}
+
+fn in_range(record: &Record, options: &LogOptions) -> bool {
+    let timestamp = record.timestamp.timestamp();
+
+    timestamp >= i64::from(options.since())
+        && options
+            .until()
+            .is_none_or(|until| timestamp <= i64::from(until))
+}
+
+/// Parses the complete lines of `buf`, returning the records that match `options` and the
+/// number of bytes consumed.
+pub(crate) fn parse_records(
+    parser: &mut Parser,
+    buf: &[u8],
+    options: &LogOptions,
+) -> (Vec<Record>, usize) {
+    let mut records = vec![];
+    let mut consumed = 0;
+
+    while let Some(end) = buf[consumed..].iter().position(|&b| b == b'\n') {
+        let line = &buf[consumed..consumed + end];
+        consumed += end + 1;
+
+        if let Some(record) = parser.parse_line(line)
+            && in_range(&record, options)
+        {
+            records.push(record);
+        }
+    }
+
+    (records, consumed)
+}
+
+fn encode(records: &[Record], options: &LogOptions) -> Bytes {
+    let skip = match options.tail() {
+        LogTail::All => 0,
+        LogTail::Num(n) => records
+            .len()
+            .saturating_sub(usize::try_from(*n).unwrap_or(usize::MAX)),
+    };
+
+    let mut body = BytesMut::new();
+    for record in &records[skip..] {
+        body.put(record.to_frame(options.timestamps()));
+    }
+
+    body.freeze()
+}
+
+/// Returns the logs in `path` as a Docker multiplexed stream.
+pub(crate) async fn read(path: PathBuf, options: &LogOptions) -> std::io::Result<LogStream> {
+    let contents = match tokio::fs::read(&path).await {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => vec![],
+        Err(err) => return Err(err),
+    };
+
+    let mut parser = Parser::default();
+    let (records, consumed) = parse_records(&mut parser, &contents, options);
+    let initial = encode(&records, options);
+
+    if !options.follow() {
+        return Ok(Full::new(initial).map_err(Into::into).boxed());
+    }
+
+    // Records are filtered by `until` only up to the time it passes, after which the stream ends.
+    let until = options.until();
+    let options = LogOptions::new()
+        .with_since(options.since())
+        .with_timestamps(options.timestamps());
+    let (sender, receiver) = mpsc::channel(16);
+
+    tokio::spawn(async move {
+        if !initial.is_empty() && sender.send(Ok(initial)).await.is_err() {
+            return;
+        }
+
+        let mut offset = contents.len() as u64;
+        let mut pending = contents[consumed..].to_vec();
+
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+
+            if sender.is_closed() {
+                return;
+            }
+
+            if until.is_some_and(|until| Utc::now().timestamp() > i64::from(until)) {
+                return;
+            }
+
+            match follow(&path, &mut offset, &mut pending).await {
+                Ok(true) => parser = Parser::default(),
+                Ok(false) => (),
+                Err(err) => {
+                    let _ = sender.send(Err(err)).await;
+                    return;
+                }
+            }
+
+            let (records, consumed) = parse_records(&mut parser, &pending, &options);
+            pending.drain(..consumed);
+
+            if !records.is_empty() && sender.send(Ok(encode(&records, &options))).await.is_err() {
+                return;
+            }
+        }
+    });
+
+    Ok(FollowBody { receiver }.map_err(Into::into).boxed())
+}
+
+/// Appends anything written to `path` after `offset` to `pending`. Returns true if the file was
+/// truncated or replaced, in which case it is read again from the start.
+async fn follow(path: &Path, offset: &mut u64, pending: &mut Vec<u8>) -> std::io::Result<bool> {
+    use tokio::io::{AsyncReadExt as _, AsyncSeekExt as _};
+
+    let mut file = match tokio::fs::File::open(path).await {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(err) => return Err(err),
+    };
+
+    let len = file.metadata().await?.len();
+    let rotated = len < *offset;
+    if rotated {
+        *offset = 0;
+        pending.clear();
+    }
+
+    file.seek(std::io::SeekFrom::Start(*offset)).await?;
+    let read = file.read_to_end(pending).await?;
+    *offset += read as u64;
+
+    Ok(rotated)
+}
+
+struct FollowBody {
+    receiver: mpsc::Receiver<std::io::Result<Bytes>>,
+}
+
+impl http_body::Body for FollowBody {
+    type Data = Bytes;
+    type Error = std::io::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        self.receiver
+            .poll_recv(cx)
+            .map(|chunk| chunk.map(|chunk| chunk.map(Frame::data)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_line() {
+        let mut parser = Parser::default();
+
+        let record = parser
+            .parse_line(b"2024-01-02T03:04:05.123456789Z stderr F something failed")
+            .unwrap();
+
+        assert_eq!(record.stream, Stream::Stderr);
+        assert_eq!(record.message, b"something failed");
+        assert_eq!(record.timestamp.timestamp(), 1_704_164_645);
+    }
+
+    #[test]
+    fn parse_line_joins_partial_lines() {
+        let mut parser = Parser::default();
+
+        assert!(
+            parser
+                .parse_line(b"2024-01-02T03:04:05Z stdout P hello ")
+                .is_none()
+        );
+        let record = parser
+            .parse_line(b"2024-01-02T03:04:06Z stdout F world")
+            .unwrap();
+
+        assert_eq!(record.message, b"hello world");
+        assert_eq!(record.timestamp.timestamp(), 1_704_164_645);
+    }
+
+    #[test]
+    fn parse_line_skips_malformed_lines() {
+        let mut parser = Parser::default();
+
+        assert!(parser.parse_line(b"").is_none());
+        assert!(parser.parse_line(b"not a timestamp stdout F x").is_none());
+        assert!(
+            parser
+                .parse_line(b"2024-01-02T03:04:05Z stdin F x")
+                .is_none()
+        );
+        assert!(
+            parser
+                .parse_line(b"2024-01-02T03:04:05Z stdout X x")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn parse_line_empty_message() {
+        let mut parser = Parser::default();
+
+        let record = parser.parse_line(b"2024-01-02T03:04:05Z stdout F").unwrap();
+
+        assert!(record.message.is_empty());
+    }
+
+    #[test]
+    fn to_frame() {
+        let record = Record {
+            timestamp: "2024-01-02T03:04:05Z".parse().unwrap(),
+            stream: Stream::Stdout,
+            message: b"hello".to_vec(),
+        };
+
+        assert_eq!(
+            &record.to_frame(false)[..],
+            b"\x01\x00\x00\x00\x00\x00\x00\x06hello\n"
+        );
+
+        let frame = record.to_frame(true);
+        assert_eq!(&frame[..8], b"\x01\x00\x00\x00\x00\x00\x00\x25");
+        assert_eq!(&frame[8..], b"2024-01-02T03:04:05.000000000Z hello\n");
+    }
+
+    #[test]
+    fn parse_records_filters_and_keeps_incomplete_line() {
+        let buf = b"2024-01-02T03:04:05Z stdout F one\n\
+            2024-01-02T03:04:10Z stdout F two\n\
+            2024-01-02T03:04:15Z stdout F three\n\
+            2024-01-02T03:04:20Z stdout F fou";
+
+        let options = LogOptions::new()
+            .with_since(1_704_164_650)
+            .with_until(1_704_164_655);
+        let mut parser = Parser::default();
+        let (records, consumed) = parse_records(&mut parser, buf, &options);
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].message, b"two");
+        assert_eq!(records[1].message, b"three");
+        assert_eq!(&buf[consumed..], b"2024-01-02T03:04:20Z stdout F fou");
+    }
+
+    #[test]
+    fn encode_applies_tail() {
+        let buf = b"2024-01-02T03:04:05Z stdout F one\n\
+            2024-01-02T03:04:10Z stderr F two\n";
+
+        let options = LogOptions::new().with_tail(LogTail::Num(1));
+        let mut parser = Parser::default();
+        let (records, _) = parse_records(&mut parser, buf, &options);
+
+        assert_eq!(
+            &encode(&records, &options)[..],
+            b"\x02\x00\x00\x00\x00\x00\x00\x04two\n"
+        );
+    }
+}
@@ -0,0 +1,201 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::collections::HashMap;
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use k8s_cri::v1::{ContainerState, ContainerStatus, ContainerStatusRequest};
+
+use edgelet_core::{Module, ModuleOperation, ModuleRuntimeState, ModuleStatus};
+use edgelet_docker::MODULE_TYPE;
+use edgelet_settings::DockerConfig;
+use edgelet_utils::ensure_not_empty;
+
+use crate::client::CriClient;
+use crate::error::Error;
+
+pub struct CriModule {
+    client: CriClient,
+    name: String,
+    container_id: String,
+    config: DockerConfig,
+}
+
+impl std::fmt::Debug for CriModule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CriModule").finish()
+    }
+}
+
+impl CriModule {
+    pub fn new(
+        client: CriClient,
+        name: String,
+        container_id: String,
+        config: DockerConfig,
+    ) -> anyhow::Result<Self> {
+        ensure_not_empty(&name).with_context(|| Error::InvalidModuleName(name.clone()))?;
+
+        Ok(CriModule {
+            client,
+            name,
+            container_id,
+            config,
+        })
+    }
+
+    pub fn container_id(&self) -> &str {
+        &self.container_id
+    }
+}
+
+fn timestamp(nanos: i64) -> Option<DateTime<Utc>> {
+    // CRI runtimes report unset timestamps as 0.
+    if nanos == 0 {
+        None
+    } else {
+        Some(DateTime::from_timestamp_nanos(nanos))
+    }
+}
+
+/// Extracts the container's PID from the verbose `info` of a container status response.
+fn pid(info: &HashMap<String, String>) -> Option<i32> {
+    let info: serde_json::Value = serde_json::from_str(info.get("info")?).ok()?;
+    let pid = info.get("pid")?.as_i64()?;
+
+    // The runtime reports a PID of 0 for containers that are not running.
+    i32::try_from(pid).ok().filter(|&pid| pid != 0)
+}
+
+/// Maps a CRI container status to the module's runtime state. CRI runtimes do not run the
+/// `Healthcheck` of Docker create options, so no health is reported for CRI modules.
+pub fn runtime_state(
+    status: Option<&ContainerStatus>,
+    info: &HashMap<String, String>,
+) -> ModuleRuntimeState {
+    status.map_or_else(ModuleRuntimeState::default, |status| {
+        let module_status = match ContainerState::try_from(status.state) {
+            Ok(ContainerState::ContainerCreated) => ModuleStatus::Stopped,
+            Ok(ContainerState::ContainerRunning) => ModuleStatus::Running,
+            Ok(ContainerState::ContainerExited) => {
+                if status.exit_code == 0 {
+                    ModuleStatus::Stopped
+                } else {
+                    ModuleStatus::Failed
+                }
+            }
+            Ok(ContainerState::ContainerUnknown) | Err(_) => ModuleStatus::Unknown,
+        };
+
+        let exited = status.state == i32::from(ContainerState::ContainerExited);
+
+        ModuleRuntimeState::default()
+            .with_status(module_status)
+            .with_exit_code(exited.then_some(i64::from(status.exit_code)))
+            .with_started_at(timestamp(status.started_at))
+            .with_finished_at(timestamp(status.finished_at))
+            .with_image_id(Some(status.id.clone()))
+            .with_pid(pid(info))
+    })
+}
+
+#[async_trait::async_trait]
+impl Module for CriModule {
+    type Config = DockerConfig;
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn type_(&self) -> &str {
+        MODULE_TYPE
+    }
+
+    fn config(&self) -> &Self::Config {
+        &self.config
+    }
+
+    async fn runtime_state(&self) -> anyhow::Result<ModuleRuntimeState> {
+        let response = self
+            .client
+            .runtime()
+            .container_status(ContainerStatusRequest {
+                container_id: self.container_id.clone(),
+                verbose: true,
+            })
+            .await
+            .context(Error::Cri)
+            .context(Error::ModuleOperation(ModuleOperation::RuntimeState))?
+            .into_inner();
+
+        Ok(runtime_state(response.status.as_ref(), &response.info))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(state: ContainerState, exit_code: i32) -> ContainerStatus {
+        ContainerStatus {
+            id: "abc".to_owned(),
+            state: state.into(),
+            exit_code,
+            started_at: 1_704_164_645_000_000_000,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn runtime_state_running() {
+        let mut info = HashMap::new();
+        info.insert("info".to_owned(), r#"{"pid":1234}"#.to_owned());
+
+        let state = runtime_state(Some(&status(ContainerState::ContainerRunning, 0)), &info);
+
+        assert_eq!(state.status(), &ModuleStatus::Running);
+        assert_eq!(state.pid(), Some(1234));
+        assert_eq!(state.exit_code(), None);
+        assert_eq!(state.image_id(), Some("abc"));
+        assert_eq!(
+            state.started_at().map(DateTime::timestamp),
+            Some(1_704_164_645)
+        );
+        assert_eq!(state.finished_at(), None);
+    }
+
+    #[test]
+    fn runtime_state_exited() {
+        let state = runtime_state(
+            Some(&status(ContainerState::ContainerExited, 0)),
+            &HashMap::new(),
+        );
+        assert_eq!(state.status(), &ModuleStatus::Stopped);
+        assert_eq!(state.exit_code(), Some(0));
+
+        let state = runtime_state(
+            Some(&status(ContainerState::ContainerExited, 137)),
+            &HashMap::new(),
+        );
+        assert_eq!(state.status(), &ModuleStatus::Failed);
+        assert_eq!(state.exit_code(), Some(137));
+        assert_eq!(state.pid(), None);
+    }
+
+    #[test]
+    fn runtime_state_created() {
+        let state = runtime_state(
+            Some(&status(ContainerState::ContainerCreated, 0)),
+            &HashMap::new(),
+        );
+
+        assert_eq!(state.status(), &ModuleStatus::Stopped);
+    }
+
+    #[test]
+    fn runtime_state_missing() {
+        let state = runtime_state(None, &HashMap::new());
+
+        assert_eq!(state.status(), &ModuleStatus::Unknown);
+    }
+}
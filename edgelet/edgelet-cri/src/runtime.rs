@@ -0,0 +1,1476 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Context;
+use k8s_cri::v1::{
+    AuthConfig, Container, ContainerFilter, ContainerState, ContainerStatsRequest,
+    ContainerStatusRequest, CreateContainerRequest, GetEventsRequest, ImageFsInfoRequest,
+    ImageSpec, ImageStatusRequest, ListContainersRequest, ListImagesRequest, ListPodSandboxRequest,
+    PodSandbox, PodSandboxFilter, PodSandboxState, PodSandboxStatusRequest, PullImageRequest,
+    RemoveContainerRequest, RemoveImageRequest, RemovePodSandboxRequest, RunPodSandboxRequest,
+    StartContainerRequest, StopContainerRequest, StopPodSandboxRequest, VersionRequest,
+};
+use sysinfo::{Disks, Process, System};
+use tokio::sync::Mutex;
+use tokio::sync::mpsc::UnboundedSender;
+
+use docker::models::ContainerCreateBody;
+use edgelet_core::{
+    DiskInfo, EventStream, ImageUse, LogOptions, LogStream, Module, ModuleAction, ModuleMetrics,
+    ModuleRegistry, ModuleRuntime, ModuleRuntimeState, PullProgressBus, RegistryOperation,
+    RestartTracker, RuntimeOperation, SystemInfo as CoreSystemInfo, SystemResources,
+};
+use edgelet_docker::{
+    ImagePruneData, ImageUseDetails, ImageVerifier, MODULE_TYPE, MakeModuleRuntime,
+    apply_resource_quotas, check_container_policy, pull_with_retries, restrict_create_options,
+};
+use edgelet_settings::base::policy::ContainerPolicy;
+use edgelet_settings::base::pull::Settings as PullSettings;
+use edgelet_settings::base::quota::ResourceQuotas;
+use edgelet_settings::{CriRuntime, DockerConfig, ModuleSpec, RuntimeSettings, Settings};
+use edgelet_utils::ensure_not_empty;
+
+use crate::client::CriClient;
+use crate::convert::{
+    self, CREATE_OPTIONS_ANNOTATION_KEY, IMAGE_ID_ANNOTATION_KEY, MODULE_LABEL_KEY,
+    ORIGINAL_IMAGE_ANNOTATION_KEY, OWNER_LABEL_KEY, OWNER_LABEL_VALUE,
+};
+use crate::error::Error;
+use crate::events;
+use crate::logs;
+use crate::module::{CriModule, runtime_state};
+
+const CRI_API_VERSION: &str = "v1";
+
+// Same default as `docker stop`.
+const DEFAULT_STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone)]
+pub struct CriModuleRuntime {
+    client: CriClient,
+    settings: CriRuntime,
+    system_resources: Arc<Mutex<System>>,
+    create_socket_channel: UnboundedSender<ModuleAction>,
+    allow_elevated_docker_permissions: bool,
+    resource_quotas: ResourceQuotas,
+    container_policy: ContainerPolicy,
+    content_trust: Option<ImageVerifier>,
+    image_pull: PullSettings,
+    pull_progress: PullProgressBus,
+    additional_info: BTreeMap<String, String>,
+    image_use_data: ImagePruneData,
+    restart_tracker: RestartTracker,
+}
+
+impl std::fmt::Debug for CriModuleRuntime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CriModuleRuntime").finish()
+    }
+}
+
+fn merge_env(cur_env: Option<&[String]>, new_env: &BTreeMap<String, String>) -> Vec<String> {
+    let mut merged_env = BTreeMap::new();
+    merged_env.extend(new_env.iter().map(|(k, v)| (k.as_str(), v.as_str())));
+
+    if let Some(env) = cur_env {
+        merged_env.extend(
+            env.iter()
+                .map(|s| s.split_once('=').unwrap_or((s.as_str(), ""))),
+        );
+    }
+
+    merged_env
+        .iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect()
+}
+
+/// Returns the name by which Docker would refer to a fully-qualified image reference, so that
+/// images can be looked up by the names used in module specs.
+fn familiar_name(image: &str) -> Option<&str> {
+    image
+        .strip_prefix("docker.io/library/")
+        .or_else(|| image.strip_prefix("docker.io/"))
+}
+
+fn image_spec(image: &str) -> ImageSpec {
+    ImageSpec {
+        image: image.to_owned(),
+        ..Default::default()
+    }
+}
+
+fn owner_selector() -> HashMap<String, String> {
+    let mut selector = HashMap::new();
+    selector.insert(OWNER_LABEL_KEY.to_owned(), OWNER_LABEL_VALUE.to_owned());
+    selector
+}
+
+fn module_selector(name: &str) -> HashMap<String, String> {
+    let mut selector = owner_selector();
+    selector.insert(MODULE_LABEL_KEY.to_owned(), name.to_owned());
+    selector
+}
+
+fn module_name(container: &Container) -> Option<&str> {
+    container
+        .labels
+        .get(MODULE_LABEL_KEY)
+        .map(String::as_str)
+        .or_else(|| {
+            container
+                .metadata
+                .as_ref()
+                .map(|metadata| metadata.name.as_str())
+        })
+}
+
+impl CriModuleRuntime {
+    /// Returns the most recently created container of the module, if any.
+    async fn find_container(&self, name: &str) -> anyhow::Result<Option<Container>> {
+        let containers = self
+            .client
+            .runtime()
+            .list_containers(ListContainersRequest {
+                filter: Some(ContainerFilter {
+                    label_selector: module_selector(name),
+                    ..Default::default()
+                }),
+            })
+            .await
+            .context(Error::Cri)?
+            .into_inner()
+            .containers;
+
+        Ok(containers
+            .into_iter()
+            .max_by_key(|container| container.created_at))
+    }
+
+    async fn get_container(
+        &self,
+        name: &str,
+        operation: impl Fn() -> RuntimeOperation,
+    ) -> anyhow::Result<Container> {
+        ensure_not_empty(name).with_context(|| Error::RuntimeOperation(operation()))?;
+
+        self.find_container(name)
+            .await
+            .with_context(|| Error::RuntimeOperation(operation()))?
+            .ok_or_else(|| Error::ModuleNotFound(name.to_owned()))
+            .with_context(|| Error::RuntimeOperation(operation()))
+    }
+
+    async fn find_sandboxes(&self, name: &str) -> anyhow::Result<Vec<PodSandbox>> {
+        let sandboxes = self
+            .client
+            .runtime()
+            .list_pod_sandbox(ListPodSandboxRequest {
+                filter: Some(PodSandboxFilter {
+                    label_selector: module_selector(name),
+                    ..Default::default()
+                }),
+            })
+            .await
+            .context(Error::Cri)?
+            .into_inner()
+            .items;
+
+        Ok(sandboxes)
+    }
+
+    async fn remove_sandbox(&self, sandbox_id: &str) -> anyhow::Result<()> {
+        let mut client = self.client.runtime();
+
+        client
+            .stop_pod_sandbox(StopPodSandboxRequest {
+                pod_sandbox_id: sandbox_id.to_owned(),
+            })
+            .await
+            .context(Error::Cri)?;
+
+        client
+            .remove_pod_sandbox(RemovePodSandboxRequest {
+                pod_sandbox_id: sandbox_id.to_owned(),
+            })
+            .await
+            .context(Error::Cri)?;
+
+        Ok(())
+    }
+
+    async fn image_id(&self, image: &str) -> anyhow::Result<Option<String>> {
+        let response = self
+            .client
+            .image()
+            .image_status(ImageStatusRequest {
+                image: Some(image_spec(image)),
+                verbose: false,
+            })
+            .await
+            .context(Error::Cri)?
+            .into_inner();
+
+        Ok(response.image.map(|image| image.id))
+    }
+
+    /// The size of image `image_id` in bytes, which is only recorded for image garbage
+    /// collection, so errors are not reported.
+    async fn image_size(&self, image_id: &str) -> Option<u64> {
+        let sizes = self.image_sizes().await.ok()?;
+        sizes.get(image_id).copied()
+    }
+
+    /// Creates the module's container in the given sandbox, creating a new sandbox if none is
+    /// given. Returns the ID of the container.
+    async fn create_container(
+        &self,
+        name: &str,
+        attempt: u32,
+        sandbox_id: Option<String>,
+        original_image: &str,
+        create_options: &ContainerCreateBody,
+    ) -> anyhow::Result<String> {
+        let sandbox_config = convert::sandbox_config(
+            name,
+            self.settings.pod_namespace(),
+            attempt,
+            self.settings.log_directory(),
+            create_options,
+        )?;
+
+        let image = create_options.image.as_deref().unwrap_or(original_image);
+        let image_id = self
+            .image_id(image)
+            .await?
+            .ok_or_else(|| Error::InvalidCreateOptions(format!("image {image} is not present")))?;
+
+        let mut container_config = convert::container_config(
+            name,
+            attempt,
+            original_image,
+            self.settings.volume_directory(),
+            create_options,
+        )?;
+        container_config
+            .annotations
+            .insert(IMAGE_ID_ANNOTATION_KEY.to_owned(), image_id.clone());
+
+        let mut client = self.client.runtime();
+
+        let (sandbox_id, created_sandbox) = if let Some(sandbox_id) = sandbox_id {
+            (sandbox_id, false)
+        } else {
+            let sandbox_id = client
+                .run_pod_sandbox(RunPodSandboxRequest {
+                    config: Some(sandbox_config.clone()),
+                    ..Default::default()
+                })
+                .await
+                .context(Error::Cri)?
+                .into_inner()
+                .pod_sandbox_id;
+
+            (sandbox_id, true)
+        };
+
+        let container_id = match client
+            .create_container(CreateContainerRequest {
+                pod_sandbox_id: sandbox_id.clone(),
+                config: Some(container_config),
+                sandbox_config: Some(sandbox_config),
+            })
+            .await
+        {
+            Ok(response) => response.into_inner().container_id,
+            Err(err) => {
+                // Don't leave behind a sandbox without a container.
+                if created_sandbox && let Err(err) = self.remove_sandbox(&sandbox_id).await {
+                    log::warn!("Could not remove pod sandbox of module {name}: {err:?}");
+                }
+
+                return Err(err).context(Error::Cri);
+            }
+        };
+
+        // update image use timestamp for image garbage collection job later
+        self.image_use_data.record_image_use(
+            &image_id,
+            ImageUseDetails {
+                tag: Some(image),
+                module: Some(name),
+                size: None,
+            },
+        )?;
+
+        Ok(container_id)
+    }
+
+    /// Replaces an exited container with a new one created from the same options, since CRI
+    /// containers cannot be restarted. The sandbox is replaced as well if it is no longer ready,
+    /// e.g. after a reboot. Returns the ID of the new container.
+    async fn recreate_container(
+        &self,
+        name: &str,
+        container: &Container,
+    ) -> anyhow::Result<String> {
+        log::debug!("Recreating container of module {name}...");
+
+        let original_image = container
+            .annotations
+            .get(ORIGINAL_IMAGE_ANNOTATION_KEY)
+            .ok_or_else(|| Error::InvalidCreateOptions("original image is not set".to_owned()))?;
+        let create_options: ContainerCreateBody = serde_json::from_str(
+            container
+                .annotations
+                .get(CREATE_OPTIONS_ANNOTATION_KEY)
+                .ok_or_else(|| {
+                    Error::InvalidCreateOptions("create options are not set".to_owned())
+                })?,
+        )
+        .map_err(|err| Error::InvalidCreateOptions(err.to_string()))?;
+
+        let attempt = container
+            .metadata
+            .as_ref()
+            .map_or(0, |metadata| metadata.attempt)
+            .saturating_add(1);
+
+        let mut client = self.client.runtime();
+
+        let sandbox_ready = client
+            .pod_sandbox_status(PodSandboxStatusRequest {
+                pod_sandbox_id: container.pod_sandbox_id.clone(),
+                verbose: false,
+            })
+            .await
+            .ok()
+            .and_then(|response| response.into_inner().status)
+            .is_some_and(|status| status.state == i32::from(PodSandboxState::SandboxReady));
+
+        client
+            .remove_container(RemoveContainerRequest {
+                container_id: container.id.clone(),
+            })
+            .await
+            .context(Error::Cri)?;
+
+        let sandbox_id = if sandbox_ready {
+            Some(container.pod_sandbox_id.clone())
+        } else {
+            self.remove_sandbox(&container.pod_sandbox_id).await?;
+            None
+        };
+
+        self.create_container(name, attempt, sandbox_id, original_image, &create_options)
+            .await
+    }
+
+    async fn start_container(&self, name: &str) -> anyhow::Result<()> {
+        let operation = || RuntimeOperation::StartModule(name.to_owned());
+
+        let container = self.get_container(name, operation).await?;
+
+        let container_id = match ContainerState::try_from(container.state) {
+            Ok(ContainerState::ContainerRunning) => return Ok(()),
+            Ok(ContainerState::ContainerCreated) => container.id,
+            _ => self
+                .recreate_container(name, &container)
+                .await
+                .with_context(|| Error::RuntimeOperation(operation()))?,
+        };
+
+        self.client
+            .runtime()
+            .start_container(StartContainerRequest { container_id })
+            .await
+            .context(Error::Cri)
+            .map_err(|e| {
+                log::warn!("{e:?}");
+                e
+            })
+            .with_context(|| Error::RuntimeOperation(operation()))?;
+
+        Ok(())
+    }
+
+    async fn stop_container(
+        &self,
+        name: &str,
+        wait_before_kill: Option<Duration>,
+    ) -> anyhow::Result<()> {
+        let operation = || RuntimeOperation::StopModule(name.to_owned());
+
+        let container = self.get_container(name, operation).await?;
+
+        let timeout = wait_before_kill.unwrap_or(DEFAULT_STOP_TIMEOUT).as_secs();
+
+        self.client
+            .runtime()
+            .stop_container(StopContainerRequest {
+                container_id: container.id,
+                timeout: i64::try_from(timeout).unwrap_or(i64::MAX),
+            })
+            .await
+            .context(Error::Cri)
+            .map_err(|e| {
+                log::warn!("{e:?}");
+                e
+            })
+            .with_context(|| Error::RuntimeOperation(operation()))?;
+
+        Ok(())
+    }
+
+    /// Stops a module without recording the stop with the restart tracker, for stops that edged
+    /// makes itself rather than on request.
+    async fn stop_module(
+        &self,
+        id: &str,
+        wait_before_kill: Option<Duration>,
+    ) -> anyhow::Result<()> {
+        log::info!("Stopping module {id}...");
+
+        self.create_socket_channel
+            .send(ModuleAction::Stop(id.to_string()))
+            .map_err(|_| {
+                log::error!("Could not notify workload manager, stop of module: {id}");
+                Error::RuntimeOperation(RuntimeOperation::GetModule(id.to_string()))
+            })?;
+
+        self.stop_container(id, wait_before_kill).await
+    }
+
+    fn module_from_container(&self, container: Container) -> anyhow::Result<CriModule> {
+        let name = module_name(&container).unwrap_or("Unknown").to_owned();
+
+        let image = container
+            .annotations
+            .get(ORIGINAL_IMAGE_ANNOTATION_KEY)
+            .cloned()
+            .or_else(|| container.image.as_ref().map(|image| image.image.clone()))
+            .unwrap_or_else(|| name.clone());
+
+        let image_hash = container
+            .annotations
+            .get(IMAGE_ID_ANNOTATION_KEY)
+            .cloned()
+            .unwrap_or(container.image_ref);
+
+        let config = DockerConfig::new(
+            image,
+            ContainerCreateBody {
+                labels: Some(container.labels.into_iter().collect()),
+                ..Default::default()
+            },
+            None,
+            None,
+            self.allow_elevated_docker_permissions,
+        )?
+        .with_image_hash(image_hash);
+
+        CriModule::new(self.client.clone(), name, container.id, config)
+    }
+}
+
+#[async_trait::async_trait]
+impl ModuleRegistry for CriModuleRuntime {
+    type Config = DockerConfig;
+
+    async fn pull(&self, config: &Self::Config) -> anyhow::Result<()> {
+        let mut image = config.image().to_owned();
+
+        match &self.content_trust {
+            Some(content_trust) if content_trust.is_required(&image) => {
+                image = content_trust
+                    .verify(&image, config.auth())
+                    .await
+                    .with_context(|| {
+                        Error::RegistryOperation(RegistryOperation::PullImage(image.clone()))
+                    })?;
+                log::info!("Pulling image via digest {image}...");
+            }
+            _ => log::info!("Pulling image via tag {image}..."),
+        }
+
+        let auth = config.auth().map(|auth| AuthConfig {
+            username: auth.username.clone().unwrap_or_default(),
+            password: auth.password.clone().unwrap_or_default(),
+            server_address: auth.server_address.clone().unwrap_or_default(),
+            ..Default::default()
+        });
+
+        // CRI reports no progress of the layers, so only the start and the outcome of the pull
+        // are reported.
+        let (pinned, auth) = (&image, &auth);
+        let image_ref = pull_with_retries(
+            &self.image_pull,
+            &self.pull_progress,
+            config.image(),
+            move || async move {
+                let response = self
+                    .client
+                    .image()
+                    .pull_image(PullImageRequest {
+                        image: Some(image_spec(pinned)),
+                        auth: auth.clone(),
+                        sandbox_config: None,
+                    })
+                    .await
+                    .context(Error::Cri)?;
+
+                Ok(response.into_inner().image_ref)
+            },
+        )
+        .await
+        .map_err(|e| {
+            log::warn!("{e:?}");
+            e
+        })
+        .with_context(|| Error::RegistryOperation(RegistryOperation::PullImage(image.clone())))?;
+
+        log::info!("Successfully pulled image {image}");
+
+        // Now, get the image_id of the image we just pulled for image garbage collection in future
+        match self.image_id(&image_ref).await {
+            Ok(Some(image_id)) => {
+                let size = self.image_size(&image_id).await;
+                self.image_use_data.record_image_use(
+                    &image_id,
+                    ImageUseDetails {
+                        tag: Some(&image),
+                        module: None,
+                        size,
+                    },
+                )?;
+            }
+            Ok(None) => log::warn!(
+                "Could not retrieve image id. {image} was not added to image garbage collection list and will not be garbage collected"
+            ),
+            Err(e) => log::error!("Could not get status of image {image}: {e}"),
+        }
+
+        Ok(())
+    }
+
+    async fn remove(&self, name: &str) -> anyhow::Result<()> {
+        log::info!("Removing image {name}...");
+
+        ensure_not_empty(name).with_context(|| {
+            Error::RegistryOperation(RegistryOperation::RemoveImage(name.to_string()))
+        })?;
+
+        if let Some(content_trust) = &self.content_trust {
+            content_trust.forget(name);
+        }
+
+        self.client
+            .image()
+            .remove_image(RemoveImageRequest {
+                image: Some(image_spec(name)),
+            })
+            .await
+            .context(Error::Cri)
+            .map_err(|e| {
+                log::warn!("{e:?}");
+                e
+            })
+            .with_context(|| {
+                Error::RegistryOperation(RegistryOperation::RemoveImage(name.to_string()))
+            })?;
+
+        log::info!("Successfully removed image {name}");
+        Ok(())
+    }
+
+    async fn image_exists(&self, config: &Self::Config) -> anyhow::Result<bool> {
+        Ok(self
+            .image_id(config.image())
+            .await
+            .context(Error::RuntimeOperation(RuntimeOperation::ListImages))?
+            .is_some())
+    }
+
+    async fn load_bundle(&self, config: &Self::Config) -> anyhow::Result<bool> {
+        if config.bundle().is_none() && self.image_pull.bundle(config.image()).is_none() {
+            return Ok(false);
+        }
+
+        Err(anyhow::anyhow!(
+            "CRI cannot load images from offline bundles; import the image into the runtime, e.g. with 'ctr images import'"
+        ))
+        .context(Error::RegistryOperation(RegistryOperation::PullImage(
+            config.image().to_owned(),
+        )))
+    }
+
+    async fn import(&self, path: &Path) -> anyhow::Result<Vec<String>> {
+        Err(anyhow::anyhow!(
+            "CRI cannot load images; import them into the runtime, e.g. with 'ctr images import'"
+        ))
+        .context(Error::RegistryOperation(RegistryOperation::ImportImage(
+            path.display().to_string(),
+        )))
+    }
+
+    async fn image_use(&self) -> anyhow::Result<Vec<ImageUse>> {
+        Ok(self.image_use_data.images()?)
+    }
+
+    fn pull_progress(&self) -> &PullProgressBus {
+        &self.pull_progress
+    }
+}
+
+#[async_trait::async_trait]
+impl MakeModuleRuntime for CriModuleRuntime {
+    type Config = DockerConfig;
+    type Settings = Settings;
+    type ModuleRuntime = Self;
+
+    async fn make_runtime(
+        settings: &Settings,
+        create_socket_channel: UnboundedSender<ModuleAction>,
+        image_use_data: ImagePruneData,
+        restart_tracker: RestartTracker,
+    ) -> anyhow::Result<Self::ModuleRuntime> {
+        log::info!("Initializing module runtime...");
+
+        let cri_settings = settings
+            .cri_runtime()
+            .ok_or(Error::Initialization)
+            .context("[cri_runtime] is not configured")?
+            .clone();
+
+        let client = CriClient::connect(cri_settings.uri(), cri_settings.image_uri()).await?;
+
+        let version = client
+            .runtime()
+            .version(VersionRequest {
+                version: CRI_API_VERSION.to_owned(),
+            })
+            .await
+            .context(Error::Cri)
+            .context(Error::RuntimeOperation(RuntimeOperation::Init))?
+            .into_inner();
+        log::info!(
+            "Using CRI runtime {} {} (API {})",
+            version.runtime_name,
+            version.runtime_version,
+            version.runtime_api_version
+        );
+
+        let content_trust = ImageVerifier::from_settings(settings)
+            .await
+            .context(Error::Initialization)?;
+
+        if settings.image_pull().max_bandwidth().is_some() {
+            log::warn!("[image_pull] max_bandwidth is not supported with CRI and is ignored");
+        }
+
+        // to avoid excessive FD usage, we will not allow sysinfo to keep files open.
+        sysinfo::set_open_files_limit(0);
+        let system_resources = System::new_all();
+        log::info!("Successfully initialized module runtime");
+
+        let runtime = Self {
+            client,
+            settings: cri_settings,
+            system_resources: Arc::new(Mutex::new(system_resources)),
+            create_socket_channel,
+            allow_elevated_docker_permissions: settings.allow_elevated_docker_permissions(),
+            resource_quotas: settings.resource_quotas().clone(),
+            container_policy: settings.container_policy().clone(),
+            content_trust,
+            image_pull: settings.image_pull().clone(),
+            pull_progress: PullProgressBus::default(),
+            additional_info: settings.additional_info().clone(),
+            image_use_data,
+            restart_tracker,
+        };
+
+        Ok(runtime)
+    }
+}
+
+#[async_trait::async_trait]
+impl ModuleRuntime for CriModuleRuntime {
+    type Config = DockerConfig;
+    type Module = CriModule;
+    type ModuleRegistry = Self;
+
+    async fn create(&self, mut module: ModuleSpec<Self::Config>) -> anyhow::Result<()> {
+        log::info!("Creating module {}...", module.name());
+
+        // we only want "docker" modules
+        if module.r#type() != MODULE_TYPE {
+            return Err(Error::InvalidModuleType(module.r#type().to_string()).into());
+        }
+
+        let operation = || RuntimeOperation::CreateModule(module.name().to_string());
+
+        if self
+            .find_container(module.name())
+            .await
+            .with_context(|| Error::RuntimeOperation(operation()))?
+            .is_some()
+        {
+            return Err(anyhow::anyhow!("module {} already exists", module.name()))
+                .with_context(|| Error::RuntimeOperation(operation()));
+        }
+
+        check_container_policy(
+            &self.container_policy,
+            module.name(),
+            module.config().image(),
+            module.config().create_options(),
+        )
+        .with_context(|| Error::RuntimeOperation(operation()))?;
+
+        restrict_create_options(
+            self.allow_elevated_docker_permissions,
+            module.config_mut().create_options_mut(),
+        );
+        apply_resource_quotas(
+            &self.resource_quotas,
+            module.name(),
+            module.config_mut().create_options_mut(),
+        )?;
+
+        let mut image = module.config().image().to_owned();
+
+        match &self.content_trust {
+            Some(content_trust) if content_trust.is_required(&image) => {
+                image = content_trust
+                    .pinned(&image, module.config().auth())
+                    .await
+                    .with_context(|| Error::RuntimeOperation(operation()))?;
+                log::info!("Creating image via digest {image}...");
+            }
+            _ => log::info!("Creating image via tag {image}..."),
+        }
+
+        log::debug!("Creating container {} with image {image}...", module.name());
+
+        let mut create_options = module.config().create_options().clone();
+        create_options.image = Some(image.clone());
+        create_options.env = Some(merge_env(create_options.env.as_deref(), module.env()));
+        create_options.labels.get_or_insert_default().insert(
+            edgelet_core::restart::RESTART_POLICY_LABEL_KEY.to_owned(),
+            module.restart_policy().to_string(),
+        );
+
+        // Remove sandboxes left behind by an earlier module of the same name.
+        for sandbox in self
+            .find_sandboxes(module.name())
+            .await
+            .with_context(|| Error::RuntimeOperation(operation()))?
+        {
+            self.remove_sandbox(&sandbox.id)
+                .await
+                .with_context(|| Error::RuntimeOperation(operation()))?;
+        }
+
+        self.create_container(module.name(), 0, None, &image, &create_options)
+            .await
+            .map_err(|e| {
+                log::warn!("{e:?}");
+                e
+            })
+            .with_context(|| Error::RuntimeOperation(operation()))?;
+
+        Ok(())
+    }
+
+    async fn get(&self, id: &str) -> anyhow::Result<(Self::Module, ModuleRuntimeState)> {
+        log::debug!("Getting module {id}...");
+
+        let operation = || RuntimeOperation::GetModule(id.to_owned());
+
+        let container = self.get_container(id, operation).await?;
+
+        let response = self
+            .client
+            .runtime()
+            .container_status(ContainerStatusRequest {
+                container_id: container.id.clone(),
+                verbose: true,
+            })
+            .await
+            .context(Error::Cri)
+            .with_context(|| Error::RuntimeOperation(operation()))?
+            .into_inner();
+
+        let state = self
+            .restart_tracker
+            .annotate(id, runtime_state(response.status.as_ref(), &response.info));
+        let module = self
+            .module_from_container(container)
+            .with_context(|| Error::RuntimeOperation(operation()))?;
+
+        Ok((module, state))
+    }
+
+    async fn start(&self, id: &str) -> anyhow::Result<()> {
+        log::info!("Starting module {id}...");
+
+        ensure_not_empty(id).with_context(|| {
+            Error::RuntimeOperation(RuntimeOperation::StartModule(id.to_owned()))
+        })?;
+
+        let (sender, receiver) = tokio::sync::oneshot::channel::<()>();
+
+        self.create_socket_channel
+            .send(ModuleAction::Start(id.to_string(), sender))
+            .map_err(|_| {
+                log::error!("Could not notify workload manager, start of module: {id}");
+                Error::RuntimeOperation(RuntimeOperation::StartModule(id.to_string()))
+            })?;
+
+        receiver.await.map_err(|_| {
+            log::error!("Could not wait on workload manager response, start of module: {id}");
+            Error::RuntimeOperation(RuntimeOperation::StartModule(id.to_owned()))
+        })?;
+
+        self.start_container(id).await?;
+
+        self.restart_tracker.module_started(id);
+
+        Ok(())
+    }
+
+    async fn stop(&self, id: &str, wait_before_kill: Option<Duration>) -> anyhow::Result<()> {
+        ensure_not_empty(id).with_context(|| {
+            Error::RuntimeOperation(RuntimeOperation::StopModule(id.to_owned()))
+        })?;
+
+        // Record the stop first so that the module is not restarted while it is stopping.
+        self.restart_tracker.module_stopped(id);
+
+        self.stop_module(id, wait_before_kill).await
+    }
+
+    async fn restart(&self, id: &str) -> anyhow::Result<()> {
+        log::info!("Restarting module {id}...");
+
+        self.stop_container(id, None).await.with_context(|| {
+            Error::RuntimeOperation(RuntimeOperation::RestartModule(id.to_owned()))
+        })?;
+
+        self.start_container(id).await.with_context(|| {
+            Error::RuntimeOperation(RuntimeOperation::RestartModule(id.to_owned()))
+        })?;
+
+        self.restart_tracker.module_started(id);
+
+        Ok(())
+    }
+
+    async fn remove(&self, id: &str) -> anyhow::Result<()> {
+        log::info!("Removing module {id}...");
+
+        let operation = || RuntimeOperation::RemoveModule(id.to_owned());
+
+        let container = self.get_container(id, operation).await?;
+        let image_id = container
+            .annotations
+            .get(IMAGE_ID_ANNOTATION_KEY)
+            .cloned()
+            .ok_or(Error::GetImageId())?;
+
+        // Removing the sandbox removes its containers.
+        for sandbox in self
+            .find_sandboxes(id)
+            .await
+            .with_context(|| Error::RuntimeOperation(operation()))?
+        {
+            self.remove_sandbox(&sandbox.id)
+                .await
+                .map_err(|e| {
+                    log::warn!("{e:?}");
+                    e
+                })
+                .with_context(|| Error::RuntimeOperation(operation()))?;
+        }
+
+        // update image use timestamp for image garbage collection job later
+        self.image_use_data.record_image_use(
+            &image_id,
+            ImageUseDetails {
+                module: Some(id),
+                ..Default::default()
+            },
+        )?;
+
+        self.restart_tracker.module_removed(id);
+
+        // Remove the socket to avoid having socket files polluting the home folder.
+        self.create_socket_channel
+            .send(ModuleAction::Remove(id.to_string()))
+            .map_err(|_| {
+                log::error!("Could not notify workload manager, remove of module: {id}");
+                anyhow::anyhow!(Error::RuntimeOperation(RuntimeOperation::GetModule(
+                    id.to_string()
+                )))
+            })
+    }
+
+    async fn system_info(&self) -> anyhow::Result<CoreSystemInfo> {
+        log::info!("Querying system info...");
+
+        let total_memory = {
+            let mut system_resources = self.system_resources.as_ref().lock().await;
+            system_resources.refresh_memory();
+            system_resources.total_memory()
+        };
+
+        let mut system_info = CoreSystemInfo::default();
+
+        let version = self
+            .client
+            .runtime()
+            .version(VersionRequest {
+                version: CRI_API_VERSION.to_owned(),
+            })
+            .await
+            .context(Error::Cri)
+            .context(Error::RuntimeOperation(RuntimeOperation::SystemInfo))?
+            .into_inner();
+        system_info.server_version = Some(format!(
+            "{} {}",
+            version.runtime_name, version.runtime_version
+        ));
+        system_info.total_memory = Some(total_memory);
+        system_info.merge_additional(self.additional_info.clone());
+
+        log::info!("Successfully queried system info");
+        Ok(system_info)
+    }
+
+    async fn system_resources(&self) -> anyhow::Result<SystemResources> {
+        log::info!("Querying system resources...");
+
+        let uptime = nix::sys::sysinfo::sysinfo()?.uptime().as_secs();
+
+        // Get system resources
+        let mut system_resources = self.system_resources.as_ref().lock().await;
+        system_resources.refresh_all();
+
+        let start_time = system_resources
+            .process(sysinfo::Pid::from_u32(process::id()))
+            .map(Process::start_time)
+            .unwrap_or_default();
+
+        let current_time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let used_cpu = system_resources.global_cpu_usage();
+        let total_memory = system_resources.total_memory();
+        let used_memory = system_resources.used_memory();
+
+        let disks = Disks::new_with_refreshed_list()
+            .list()
+            .iter()
+            .map(|disk| {
+                DiskInfo::new(
+                    disk.name().to_string_lossy().into_owned(),
+                    disk.available_space(),
+                    disk.total_space(),
+                    disk.file_system().to_string_lossy().into_owned(),
+                    format!("{:?}", disk.kind()),
+                )
+            })
+            .collect();
+
+        // Report container stats in the subset of the Docker stats format that consumers use.
+        let modules = self.list().await?;
+        let mut container_stats = Vec::with_capacity(modules.len());
+        for module in modules {
+            let stats = self
+                .client
+                .runtime()
+                .container_stats(ContainerStatsRequest {
+                    container_id: module.container_id().to_owned(),
+                })
+                .await
+                .context(Error::Cri)?
+                .into_inner()
+                .stats
+                .unwrap_or_default();
+
+            let cpu_usage = stats
+                .cpu
+                .and_then(|cpu| cpu.usage_core_nano_seconds)
+                .map_or(0, |usage| usage.value);
+            let memory_usage = stats
+                .memory
+                .and_then(|memory| memory.working_set_bytes)
+                .map_or(0, |usage| usage.value);
+
+            container_stats.push(serde_json::json!({
+                "name": format!("/{}", module.name()),
+                "id": module.container_id(),
+                "cpu_stats": { "cpu_usage": { "total_usage": cpu_usage } },
+                "memory_stats": { "usage": memory_usage, "limit": total_memory },
+            }));
+        }
+        let container_stats = serde_json::to_string(&container_stats)
+            .map_err(|_| Error::RuntimeOperation(RuntimeOperation::SystemResources))?;
+
+        Ok(SystemResources::new(
+            uptime,
+            current_time - start_time,
+            used_cpu,
+            used_memory,
+            total_memory,
+            disks,
+            container_stats,
+        ))
+    }
+
+    /// CRI reports neither network nor block I/O statistics, nor memory limits, so those
+    /// metrics are left unset.
+    #[allow(clippy::cast_precision_loss)]
+    async fn module_metrics(&self) -> anyhow::Result<Vec<ModuleMetrics>> {
+        let modules = self.list().await?;
+
+        let mut metrics = Vec::with_capacity(modules.len());
+        for module in modules {
+            let stats = self
+                .client
+                .runtime()
+                .container_stats(ContainerStatsRequest {
+                    container_id: module.container_id().to_owned(),
+                })
+                .await
+                .context(Error::Cri)
+                .context(Error::RuntimeOperation(RuntimeOperation::GetModuleMetrics))?
+                .into_inner()
+                .stats
+                .unwrap_or_default();
+
+            let cpu = stats.cpu.unwrap_or_default();
+            let cpu_usage = cpu.usage_core_nano_seconds.map_or(0, |usage| usage.value);
+            // One core is 10^9 nanocores.
+            let cpu_percent = cpu.usage_nano_cores.map(|usage| usage.value as f64 / 1e7);
+
+            let memory_usage = stats
+                .memory
+                .and_then(|memory| memory.working_set_bytes)
+                .map_or(0, |usage| usage.value);
+
+            metrics.push(
+                ModuleMetrics::new(module.name().to_owned())
+                    .with_cpu(cpu_usage as f64 / 1e9, cpu_percent)
+                    .with_memory(memory_usage, None),
+            );
+        }
+
+        Ok(metrics)
+    }
+
+    async fn list(&self) -> anyhow::Result<Vec<Self::Module>> {
+        log::debug!("Listing modules...");
+
+        let containers = self
+            .client
+            .runtime()
+            .list_containers(ListContainersRequest {
+                filter: Some(ContainerFilter {
+                    label_selector: owner_selector(),
+                    ..Default::default()
+                }),
+            })
+            .await
+            .context(Error::Cri)
+            .context(Error::RuntimeOperation(RuntimeOperation::ListModules))?
+            .into_inner()
+            .containers;
+
+        // Keep only the most recently created container of each module.
+        let mut latest: BTreeMap<String, Container> = BTreeMap::new();
+        for container in containers {
+            let Some(name) = module_name(&container) else {
+                continue;
+            };
+
+            if latest
+                .get(name)
+                .is_none_or(|existing| existing.created_at < container.created_at)
+            {
+                latest.insert(name.to_owned(), container);
+            }
+        }
+
+        let result = latest
+            .into_values()
+            .flat_map(|container| self.module_from_container(container))
+            .collect();
+
+        Ok(result)
+    }
+
+    async fn list_with_details(&self) -> anyhow::Result<Vec<(Self::Module, ModuleRuntimeState)>> {
+        let mut result = Vec::new();
+        for module in self.list().await? {
+            // Note, if error calling just drop module from list
+            match module.runtime_state().await {
+                Ok(state) => {
+                    let state = self.restart_tracker.annotate(module.name(), state);
+                    result.push((module, state));
+                }
+                Err(err) => {
+                    log::warn!(
+                        "error when getting details for {}: {:?}",
+                        module.name(),
+                        err
+                    );
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn list_images(&self) -> anyhow::Result<HashMap<String, String>> {
+        let images = self
+            .client
+            .image()
+            .list_images(ListImagesRequest { filter: None })
+            .await
+            .context(Error::Cri)
+            .map_err(|e| {
+                log::warn!("{e:?}");
+                e
+            })
+            .context(Error::RuntimeOperation(RuntimeOperation::ListImages))?
+            .into_inner()
+            .images;
+
+        let mut result = HashMap::new();
+        for image in images {
+            for tag in image.repo_tags {
+                if let Some(name) = familiar_name(&tag) {
+                    result.insert(name.to_owned(), image.id.clone());
+                }
+                result.insert(tag, image.id.clone());
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn image_sizes(&self) -> anyhow::Result<HashMap<String, u64>> {
+        let images = self
+            .client
+            .image()
+            .list_images(ListImagesRequest { filter: None })
+            .await
+            .context(Error::Cri)
+            .context(Error::RuntimeOperation(RuntimeOperation::ListImages))?
+            .into_inner()
+            .images;
+
+        Ok(images
+            .into_iter()
+            .map(|image| (image.id, image.size))
+            .collect())
+    }
+
+    async fn logs(&self, id: &str, options: &LogOptions) -> anyhow::Result<LogStream> {
+        log::info!("Getting logs for module {id}...");
+
+        let operation = || RuntimeOperation::GetModuleLogs(id.to_owned());
+
+        let container = self.get_container(id, operation).await?;
+
+        let status = self
+            .client
+            .runtime()
+            .container_status(ContainerStatusRequest {
+                container_id: container.id,
+                verbose: false,
+            })
+            .await
+            .context(Error::Cri)
+            .with_context(|| Error::RuntimeOperation(operation()))?
+            .into_inner()
+            .status;
+
+        let log_path = status
+            .map(|status| status.log_path)
+            .filter(|log_path| !log_path.is_empty())
+            .map_or_else(
+                || {
+                    Path::new(&convert::sandbox_log_directory(
+                        self.settings.log_directory(),
+                        self.settings.pod_namespace(),
+                        id,
+                    ))
+                    .join(convert::container_log_path(id))
+                },
+                PathBuf::from,
+            );
+
+        let logs = logs::read(log_path, options)
+            .await
+            .map_err(|e| {
+                log::warn!("{e:?}");
+                e
+            })
+            .with_context(|| Error::RuntimeOperation(operation()))?;
+
+        Ok(logs)
+    }
+
+    async fn remove_all(&self) -> anyhow::Result<()> {
+        let modules = self.list().await?;
+        let mut remove = vec![];
+
+        for module in &modules {
+            remove.push(ModuleRuntime::remove(self, module.name()));
+        }
+
+        for result in futures_util::future::join_all(remove).await {
+            if let Err(err) = result {
+                log::warn!("Failed to remove module: {err:?}");
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn stop_all(&self, wait_before_kill: Option<Duration>) -> anyhow::Result<()> {
+        let modules = self.list().await?;
+        let mut stop = vec![];
+
+        for module in &modules {
+            stop.push(self.stop_module(module.name(), wait_before_kill));
+        }
+
+        for result in futures_util::future::join_all(stop).await {
+            if let Err(err) = result {
+                log::warn!("Failed to stop module: {err:?}");
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn module_top(&self, id: &str) -> anyhow::Result<Vec<i32>> {
+        let operation = || RuntimeOperation::TopModule(id.to_owned());
+
+        let (_, state) = self.get(id).await?;
+        let pid = state
+            .pid()
+            .ok_or_else(|| anyhow::anyhow!("module {id} is not running"))
+            .with_context(|| Error::RuntimeOperation(operation()))?;
+
+        let pids = cgroup_pids(pid)
+            .await
+            .with_context(|| Error::RuntimeOperation(operation()))?;
+
+        Ok(pids)
+    }
+
+    async fn data_root(&self) -> anyhow::Result<PathBuf> {
+        let filesystems = self
+            .client
+            .image()
+            .image_fs_info(ImageFsInfoRequest {})
+            .await
+            .context(Error::Cri)
+            .context(Error::RuntimeOperation(RuntimeOperation::GetDataRoot))?
+            .into_inner()
+            .image_filesystems;
+
+        let data_root = filesystems
+            .into_iter()
+            .find_map(|filesystem| filesystem.fs_id)
+            .map(|fs_id| fs_id.mountpoint)
+            .ok_or_else(|| anyhow::anyhow!("CRI runtime did not report its image filesystem"))
+            .context(Error::RuntimeOperation(RuntimeOperation::GetDataRoot))?;
+
+        Ok(data_root.into())
+    }
+
+    /// Containers that are not modules belong to other CRI clients, such as the kubelet, so
+    /// they are never removed.
+    async fn prune_containers(&self) -> anyhow::Result<u64> {
+        Err(anyhow::anyhow!("not supported by CRI runtimes"))
+            .context(Error::RuntimeOperation(RuntimeOperation::PruneContainers))
+    }
+
+    /// CRI has no volumes of its own; volumes are managed by the CRI client.
+    async fn prune_volumes(&self) -> anyhow::Result<u64> {
+        Err(anyhow::anyhow!("not supported by CRI runtimes"))
+            .context(Error::RuntimeOperation(RuntimeOperation::PruneVolumes))
+    }
+
+    /// CRI runtimes cannot replay past events, so `since` is ignored and only events that occur
+    /// after subscribing are reported.
+    async fn events(
+        &self,
+        _since: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> anyhow::Result<EventStream> {
+        let events = self
+            .client
+            .runtime()
+            .get_container_events(GetEventsRequest {})
+            .await
+            .context(Error::Cri)
+            .context(Error::RuntimeOperation(RuntimeOperation::GetEvents))?
+            .into_inner();
+
+        Ok(events::stream(events))
+    }
+
+    fn registry(&self) -> &Self::ModuleRegistry {
+        self
+    }
+
+    fn error_code(error: &anyhow::Error) -> hyper::StatusCode {
+        let root_cause = error.root_cause();
+
+        if let Some(status) = root_cause.downcast_ref::<tonic::Status>() {
+            match status.code() {
+                tonic::Code::NotFound => hyper::StatusCode::NOT_FOUND,
+                tonic::Code::AlreadyExists => hyper::StatusCode::CONFLICT,
+                tonic::Code::InvalidArgument => hyper::StatusCode::BAD_REQUEST,
+                _ => hyper::StatusCode::INTERNAL_SERVER_ERROR,
+            }
+        } else if let Some(Error::ModuleNotFound(_)) = root_cause.downcast_ref::<Error>() {
+            hyper::StatusCode::NOT_FOUND
+        } else if let Some(edgelet_docker::Error::ResourceQuotaExceeded(..)) =
+            root_cause.downcast_ref::<edgelet_docker::Error>()
+        {
+            hyper::StatusCode::BAD_REQUEST
+        } else if root_cause.is::<edgelet_core::PolicyViolations>() {
+            hyper::StatusCode::FORBIDDEN
+        } else if let Some(edgelet_docker::Error::ImageVerification(..)) =
+            root_cause.downcast_ref::<edgelet_docker::Error>()
+        {
+            hyper::StatusCode::FORBIDDEN
+        } else {
+            hyper::StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+/// Returns the path of the `cgroup.procs` file of the cgroup described by the contents of
+/// `/proc/<pid>/cgroup`.
+fn cgroup_procs_path(cgroup: &str) -> Option<PathBuf> {
+    let mut unified = None;
+
+    for line in cgroup.lines() {
+        let mut fields = line.splitn(3, ':');
+        let (Some(_), Some(controllers), Some(path)) =
+            (fields.next(), fields.next(), fields.next())
+        else {
+            continue;
+        };
+        let path = path.trim_start_matches('/');
+
+        if controllers.is_empty() {
+            unified = Some(Path::new("/sys/fs/cgroup").join(path).join("cgroup.procs"));
+        } else if controllers
+            .split(',')
+            .any(|controller| controller == "pids")
+        {
+            // On hybrid hierarchies, the pids controller is mounted as cgroup v1.
+            return Some(
+                Path::new("/sys/fs/cgroup/pids")
+                    .join(path)
+                    .join("cgroup.procs"),
+            );
+        }
+    }
+
+    unified
+}
+
+async fn cgroup_pids(pid: i32) -> anyhow::Result<Vec<i32>> {
+    let cgroup = tokio::fs::read_to_string(format!("/proc/{pid}/cgroup")).await?;
+    let procs_path = cgroup_procs_path(&cgroup)
+        .ok_or_else(|| anyhow::anyhow!("could not determine cgroup of process {pid}"))?;
+
+    let procs = tokio::fs::read_to_string(&procs_path)
+        .await
+        .with_context(|| format!("could not read {}", procs_path.display()))?;
+
+    let pids = procs
+        .lines()
+        .map(|line| {
+            line.trim()
+                .parse()
+                .with_context(|| format!("invalid process ID {line:?}"))
+        })
+        .collect::<anyhow::Result<_>>()?;
+
+    Ok(pids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cgroup_procs_path_unified() {
+        let cgroup = "0::/kubepods/besteffort/pod1/abc\n";
+
+        assert_eq!(
+            cgroup_procs_path(cgroup).unwrap(),
+            Path::new("/sys/fs/cgroup/kubepods/besteffort/pod1/abc/cgroup.procs")
+        );
+    }
+
+    #[test]
+    fn cgroup_procs_path_v1() {
+        let cgroup = "12:memory:/k8s.io/abc\n\
+            11:pids:/k8s.io/abc\n\
+            10:cpu,cpuacct:/k8s.io/abc\n\
+            0::/\n";
+
+        assert_eq!(
+            cgroup_procs_path(cgroup).unwrap(),
+            Path::new("/sys/fs/cgroup/pids/k8s.io/abc/cgroup.procs")
+        );
+    }
+
+    #[test]
+    fn cgroup_procs_path_invalid() {
+        assert!(cgroup_procs_path("").is_none());
+        assert!(cgroup_procs_path("garbage").is_none());
+    }
+
+    #[test]
+    fn merge_env_prefers_create_options() {
+        let mut env = BTreeMap::new();
+        env.insert("A".to_owned(), "from-spec".to_owned());
+        env.insert("B".to_owned(), "from-spec".to_owned());
+
+        let merged = merge_env(Some(&["B=from-options".to_owned()]), &env);
+
+        assert_eq!(merged, vec!["A=from-spec", "B=from-options"]);
+    }
+
+    #[test]
+    fn familiar_name_strips_default_registry() {
+        assert_eq!(
+            familiar_name("docker.io/library/ubuntu:22.04"),
+            Some("ubuntu:22.04")
+        );
+        assert_eq!(familiar_name("docker.io/user/app:1"), Some("user/app:1"));
+        assert_eq!(
+            familiar_name("mcr.microsoft.com/azureiotedge-agent:1.5"),
+            None
+        );
+    }
+}
@@ -0,0 +1,302 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! Verification of cosign image signatures that carry a signing certificate.
+
+use openssl::x509::X509;
+
+use super::ImageReference;
+
+pub(crate) const SIGNATURE_ANNOTATION: &str = "dev.cosignproject.cosign/signature";
+pub(crate) const CERTIFICATE_ANNOTATION: &str = "dev.sigstore.cosign/certificate";
+pub(crate) const CHAIN_ANNOTATION: &str = "dev.sigstore.cosign/chain";
+
+const SIGNATURE_TYPE: &str = "cosign container image signature";
+
+/// One signature of an image, as stored by cosign in the `sha256-<digest>.sig` tag.
+#[derive(Clone, Debug, Default)]
+pub struct Signature {
+    /// The signed payload, a "simple signing" JSON document.
+    pub payload: Vec<u8>,
+    pub signature: Vec<u8>,
+    /// PEM-encoded signing certificate.
+    pub certificate: Option<Vec<u8>>,
+    /// PEM-encoded intermediate certificates.
+    pub chain: Option<Vec<u8>>,
+}
+
+#[derive(serde::Deserialize)]
+struct Payload {
+    critical: Critical,
+}
+
+#[derive(serde::Deserialize)]
+struct Critical {
+    identity: Identity,
+    image: Image,
+    r#type: String,
+}
+
+#[derive(serde::Deserialize)]
+struct Identity {
+    #[serde(rename = "docker-reference")]
+    docker_reference: String,
+}
+
+#[derive(serde::Deserialize)]
+struct Image {
+    #[serde(rename = "docker-manifest-digest")]
+    docker_manifest_digest: String,
+}
+
+/// Checks that `signature` is a valid signature of `reference` at `digest`, made with a
+/// certificate that chains to one of `ca_certs`.
+pub fn verify(
+    signature: &Signature,
+    reference: &ImageReference,
+    digest: &str,
+    ca_certs: &[X509],
+) -> Result<(), String> {
+    let certificate = signature
+        .certificate
+        .as_deref()
+        .ok_or("signature has no signing certificate")?;
+    let certificate =
+        X509::from_pem(certificate).map_err(|err| format!("invalid signing certificate: {err}"))?;
+
+    let chain = match &signature.chain {
+        Some(chain) => X509::stack_from_pem(chain)
+            .map_err(|err| format!("invalid certificate chain: {err}"))?,
+        None => vec![],
+    };
+
+    verify_chain(&certificate, chain, ca_certs)?;
+
+    let public_key = certificate
+        .public_key()
+        .map_err(|err| format!("invalid signing certificate: {err}"))?;
+    let valid = openssl::sign::Verifier::new(openssl::hash::MessageDigest::sha256(), &public_key)
+        .and_then(|mut verifier| {
+            verifier.update(&signature.payload)?;
+            verifier.verify(&signature.signature)
+        })
+        .map_err(|err| format!("could not check signature: {err}"))?;
+    if !valid {
+        return Err("signature does not match its payload".to_owned());
+    }
+
+    let payload: Payload = serde_json::from_slice(&signature.payload)
+        .map_err(|err| format!("invalid signature payload: {err}"))?;
+
+    if payload.critical.r#type != SIGNATURE_TYPE {
+        return Err(format!(
+            "unexpected signature type {:?}",
+            payload.critical.r#type
+        ));
+    }
+    if payload.critical.image.docker_manifest_digest != digest {
+        return Err(format!(
+            "signature is for digest {}, not {digest}",
+            payload.critical.image.docker_manifest_digest
+        ));
+    }
+
+    let signed_reference = ImageReference::parse(&payload.critical.identity.docker_reference)?;
+    if signed_reference.qualified_name() != reference.qualified_name() {
+        return Err(format!(
+            "signature is for image {}, not {}",
+            payload.critical.identity.docker_reference,
+            reference.name()
+        ));
+    }
+
+    Ok(())
+}
+
+fn verify_chain(certificate: &X509, chain: Vec<X509>, ca_certs: &[X509]) -> Result<(), String> {
+    let error = |err: openssl::error::ErrorStack| format!("could not check certificate: {err}");
+
+    let mut store = openssl::x509::store::X509StoreBuilder::new().map_err(error)?;
+    for ca_cert in ca_certs {
+        store.add_cert(ca_cert.clone()).map_err(error)?;
+    }
+    let store = store.build();
+
+    let mut intermediates = openssl::stack::Stack::new().map_err(error)?;
+    for cert in chain {
+        intermediates.push(cert).map_err(error)?;
+    }
+
+    let mut context = openssl::x509::X509StoreContext::new().map_err(error)?;
+    let result = context
+        .init(&store, certificate, &intermediates, |context| {
+            if context.verify_cert()? {
+                Ok(Ok(()))
+            } else {
+                Ok(Err(context.error().error_string().to_owned()))
+            }
+        })
+        .map_err(error)?;
+
+    result.map_err(|reason| format!("signing certificate is not trusted: {reason}"))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use openssl::asn1::Asn1Time;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::hash::MessageDigest;
+    use openssl::nid::Nid;
+    use openssl::pkey::{PKey, Private};
+    use openssl::x509::{X509, X509NameBuilder};
+
+    use super::*;
+
+    pub(crate) struct TestCa {
+        pub(crate) cert: X509,
+        key: PKey<Private>,
+    }
+
+    fn key() -> PKey<Private> {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        PKey::from_ec_key(EcKey::generate(&group).unwrap()).unwrap()
+    }
+
+    fn cert(common_name: &str, key: &PKey<Private>, issuer: Option<&TestCa>) -> X509 {
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_nid(Nid::COMMONNAME, common_name)
+            .unwrap();
+        let name = name.build();
+
+        let mut builder = X509::builder().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder
+            .set_issuer_name(issuer.map_or(&*name, |issuer| issuer.cert.subject_name()))
+            .unwrap();
+        builder.set_pubkey(key).unwrap();
+        builder
+            .set_not_before(&Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&Asn1Time::days_from_now(1).unwrap())
+            .unwrap();
+        if issuer.is_none() {
+            builder
+                .append_extension(
+                    openssl::x509::extension::BasicConstraints::new()
+                        .critical()
+                        .ca()
+                        .build()
+                        .unwrap(),
+                )
+                .unwrap();
+        }
+        builder
+            .sign(
+                issuer.map_or(key, |issuer| &issuer.key),
+                MessageDigest::sha256(),
+            )
+            .unwrap();
+
+        builder.build()
+    }
+
+    impl TestCa {
+        pub(crate) fn new(common_name: &str) -> Self {
+            let key = key();
+            let cert = cert(common_name, &key, None);
+
+            TestCa { cert, key }
+        }
+
+        /// Signs `image` at `digest` with a new certificate issued by this CA.
+        pub(crate) fn sign(&self, image: &str, digest: &str) -> Signature {
+            let key = key();
+            let cert = cert("signer", &key, Some(self));
+
+            let payload = serde_json::to_vec(&serde_json::json!({
+                "critical": {
+                    "identity": { "docker-reference": image },
+                    "image": { "docker-manifest-digest": digest },
+                    "type": SIGNATURE_TYPE,
+                },
+                "optional": null,
+            }))
+            .unwrap();
+
+            let mut signer = openssl::sign::Signer::new(MessageDigest::sha256(), &key).unwrap();
+            signer.update(&payload).unwrap();
+            let signature = signer.sign_to_vec().unwrap();
+
+            Signature {
+                payload,
+                signature,
+                certificate: Some(cert.to_pem().unwrap()),
+                chain: None,
+            }
+        }
+    }
+
+    const DIGEST: &str = "sha256:0123";
+
+    fn reference() -> ImageReference {
+        ImageReference::parse("contoso.azurecr.io/app:1.0").unwrap()
+    }
+
+    #[test]
+    fn valid_signature() {
+        let ca = TestCa::new("ca");
+        let signature = ca.sign("contoso.azurecr.io/app", DIGEST);
+
+        verify(&signature, &reference(), DIGEST, &[ca.cert]).unwrap();
+    }
+
+    #[test]
+    fn untrusted_certificate() {
+        let ca = TestCa::new("ca");
+        let other_ca = TestCa::new("other");
+        let signature = other_ca.sign("contoso.azurecr.io/app", DIGEST);
+
+        let err = verify(&signature, &reference(), DIGEST, &[ca.cert]).unwrap_err();
+        assert!(
+            err.starts_with("signing certificate is not trusted"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn tampered_payload() {
+        let ca = TestCa::new("ca");
+        let mut signature = ca.sign("contoso.azurecr.io/app", DIGEST);
+        signature.payload = ca.sign("contoso.azurecr.io/app", "sha256:4567").payload;
+
+        let err = verify(&signature, &reference(), DIGEST, &[ca.cert]).unwrap_err();
+        assert_eq!(err, "signature does not match its payload");
+    }
+
+    #[test]
+    fn wrong_digest_or_image() {
+        let ca = TestCa::new("ca");
+
+        let signature = ca.sign("contoso.azurecr.io/app", "sha256:4567");
+        let err = verify(&signature, &reference(), DIGEST, &[ca.cert.clone()]).unwrap_err();
+        assert_eq!(err, "signature is for digest sha256:4567, not sha256:0123");
+
+        let signature = ca.sign("contoso.azurecr.io/other", DIGEST);
+        let err = verify(&signature, &reference(), DIGEST, &[ca.cert]).unwrap_err();
+        assert_eq!(
+            err,
+            "signature is for image contoso.azurecr.io/other, not contoso.azurecr.io/app"
+        );
+    }
+
+    #[test]
+    fn missing_certificate() {
+        let ca = TestCa::new("ca");
+        let mut signature = ca.sign("contoso.azurecr.io/app", DIGEST);
+        signature.certificate = None;
+
+        let err = verify(&signature, &reference(), DIGEST, &[ca.cert]).unwrap_err();
+        assert_eq!(err, "signature has no signing certificate");
+    }
+}
@@ -0,0 +1,394 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! Content trust: module images from registries with configured CA certificates must carry a
+//! cosign signature made with a certificate issued by one of those CAs. Verified images are
+//! pinned to the digest that was verified, so that the container runs exactly what was checked.
+
+mod cosign;
+mod reference;
+pub(crate) mod registry;
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Context;
+use openssl::x509::X509;
+
+use docker::models::AuthConfig;
+use edgelet_settings::{RuntimeSettings, Settings};
+
+pub use cosign::Signature;
+pub use reference::ImageReference;
+pub use registry::{HttpRegistry, Registry};
+
+use crate::error::Error;
+
+#[derive(Clone)]
+pub struct ImageVerifier {
+    /// Trusted signing CAs, by registry.
+    ca_certs: Arc<BTreeMap<String, Vec<X509>>>,
+    registry: Arc<dyn Registry>,
+    /// The pinned reference that each image was last verified as. An entry only lives until
+    /// the image is pulled, imported or removed again, so that modules are never created from a
+    /// digest that the tag no longer resolved to when the device last got the image.
+    verified: Arc<Mutex<BTreeMap<String, String>>>,
+}
+
+impl std::fmt::Debug for ImageVerifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ImageVerifier")
+            .field("registries", &self.ca_certs.keys().collect::<Vec<_>>())
+            .finish_non_exhaustive()
+    }
+}
+
+impl ImageVerifier {
+    pub fn new(ca_certs: BTreeMap<String, Vec<X509>>, registry: Arc<dyn Registry>) -> Self {
+        ImageVerifier {
+            ca_certs: Arc::new(ca_certs),
+            registry,
+            verified: Arc::default(),
+        }
+    }
+
+    /// Builds the verifier configured by `[moby_runtime.content_trust]`, loading its CA
+    /// certificates from certd. Returns `None` if content trust is not configured.
+    pub async fn from_settings(settings: &Settings) -> anyhow::Result<Option<Self>> {
+        let Some(ca_cert_ids) = settings
+            .moby_runtime()
+            .content_trust()
+            .and_then(|content_trust| content_trust.ca_certs())
+            .filter(|ca_certs| !ca_certs.is_empty())
+        else {
+            return Ok(None);
+        };
+
+        let connector = http_common::Connector::new(settings.endpoints().aziot_certd_url())
+            .context(Error::Initialization)?;
+        let cert_client = aziot_cert_client_async::Client::new(
+            aziot_cert_common_http::ApiVersion::V2020_09_01,
+            connector,
+            1,
+        );
+
+        let mut ca_certs = BTreeMap::new();
+
+        for (registry, cert_id) in ca_cert_ids {
+            let pem = cert_client.get_cert(cert_id).await.with_context(|| {
+                format!("could not get content trust CA certificate {cert_id} for {registry}")
+            })?;
+            let certs = X509::stack_from_pem(&pem).with_context(|| {
+                format!("invalid content trust CA certificate {cert_id} for {registry}")
+            })?;
+
+            log::info!("Content trust is enabled for images from {registry}");
+            ca_certs.insert(registry.to_ascii_lowercase(), certs);
+        }
+
+        Ok(Some(ImageVerifier::new(
+            ca_certs,
+            Arc::new(HttpRegistry::new()?),
+        )))
+    }
+
+    /// Whether `image` must be verified before it is used, i.e. whether its registry has CA
+    /// certificates configured. Images that cannot be parsed are always verified, and so
+    /// rejected.
+    pub fn is_required(&self, image: &str) -> bool {
+        match ImageReference::parse(image) {
+            Ok(reference) => self.ca_certs.contains_key(reference.registry()),
+            Err(_) => true,
+        }
+    }
+
+    /// Whether `image` may come from an offline bundle. Bundles carry no signatures, and the
+    /// digest that a module is created from is always verified against the registry, so images
+    /// that must be verified cannot be loaded from bundles.
+    pub fn check_bundle_image(&self, image: &str) -> Result<(), Error> {
+        if self.is_required(image) {
+            Err(Error::ImageVerification(
+                image.to_owned(),
+                "content trust requires the image to be pulled from its registry, so it cannot be loaded from an offline bundle".to_owned(),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// The pinned reference that `image` was last verified as, if any.
+    pub fn verified(&self, image: &str) -> Option<String> {
+        self.verified
+            .lock()
+            .expect("verified images lock poisoned")
+            .get(image)
+            .cloned()
+    }
+
+    /// Drops the pinned reference that `image` was verified as, so that it is verified again
+    /// before it is next used.
+    pub fn forget(&self, image: &str) {
+        self.verified
+            .lock()
+            .expect("verified images lock poisoned")
+            .remove(image);
+    }
+
+    /// The pinned reference of `image`, verifying it first if it has not been verified since it
+    /// was last pulled, imported or removed.
+    pub async fn pinned(&self, image: &str, auth: Option<&AuthConfig>) -> anyhow::Result<String> {
+        match self.verified(image) {
+            Some(pinned) => Ok(pinned),
+            None => self.verify(image, auth).await,
+        }
+    }
+
+    /// Resolves `image` to a digest and checks that the digest carries a trusted signature.
+    /// Returns `image` pinned to that digest. A failed verification also drops the digest that
+    /// `image` was verified as before.
+    pub async fn verify(&self, image: &str, auth: Option<&AuthConfig>) -> anyhow::Result<String> {
+        let failed = |reason: String| Error::ImageVerification(image.to_owned(), reason);
+
+        self.forget(image);
+
+        let reference = ImageReference::parse(image).map_err(failed)?;
+        let ca_certs = self
+            .ca_certs
+            .get(reference.registry())
+            .ok_or_else(|| failed(format!("no CA certificates for {}", reference.registry())))?;
+
+        let digest = self
+            .registry
+            .manifest_digest(&reference, auth)
+            .await
+            .with_context(|| format!("could not resolve digest of {image}"))?;
+        if let Some(expected) = reference.digest()
+            && expected != digest
+        {
+            return Err(failed(format!("registry served digest {digest}")).into());
+        }
+
+        let signatures = self
+            .registry
+            .signatures(&reference, &digest, auth)
+            .await
+            .with_context(|| format!("could not get signatures of {image}"))?;
+        if signatures.is_empty() {
+            return Err(failed(format!("digest {digest} is not signed")).into());
+        }
+
+        let mut reasons = vec![];
+        for signature in &signatures {
+            match cosign::verify(signature, &reference, &digest, ca_certs) {
+                Ok(()) => {
+                    let pinned = reference.pinned(&digest);
+                    log::info!("Verified signature of {image} as {pinned}");
+
+                    self.verified
+                        .lock()
+                        .expect("verified images lock poisoned")
+                        .insert(image.to_owned(), pinned.clone());
+
+                    return Ok(pinned);
+                }
+                Err(reason) => reasons.push(reason),
+            }
+        }
+
+        Err(failed(format!("no trusted signature: {}", reasons.join("; "))).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+
+    use docker::models::AuthConfig;
+
+    use super::cosign::tests::TestCa;
+    use super::{ImageReference, ImageVerifier, Registry, Signature};
+    use crate::Error;
+
+    const DIGEST: &str = "sha256:9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08";
+
+    /// A registry with one image, held in memory.
+    struct TestRegistry {
+        digest: String,
+        signatures: Vec<Signature>,
+    }
+
+    #[async_trait::async_trait]
+    impl Registry for TestRegistry {
+        async fn manifest_digest(
+            &self,
+            _: &ImageReference,
+            _: Option<&AuthConfig>,
+        ) -> anyhow::Result<String> {
+            Ok(self.digest.clone())
+        }
+
+        async fn signatures(
+            &self,
+            _: &ImageReference,
+            digest: &str,
+            _: Option<&AuthConfig>,
+        ) -> anyhow::Result<Vec<Signature>> {
+            if digest == self.digest {
+                Ok(self.signatures.clone())
+            } else {
+                Ok(vec![])
+            }
+        }
+    }
+
+    fn verifier(ca: &TestCa, signatures: Vec<Signature>) -> ImageVerifier {
+        let mut ca_certs = BTreeMap::new();
+        ca_certs.insert("contoso.azurecr.io".to_owned(), vec![ca.cert.clone()]);
+
+        ImageVerifier::new(
+            ca_certs,
+            Arc::new(TestRegistry {
+                digest: DIGEST.to_owned(),
+                signatures,
+            }),
+        )
+    }
+
+    fn assert_rejected(err: &anyhow::Error) {
+        assert!(
+            matches!(
+                err.root_cause().downcast_ref::<Error>(),
+                Some(Error::ImageVerification(..))
+            ),
+            "{err:#}"
+        );
+    }
+
+    #[tokio::test]
+    async fn signed_image() {
+        let ca = TestCa::new("ca");
+        let verifier = verifier(&ca, vec![ca.sign("contoso.azurecr.io/app", DIGEST)]);
+
+        assert!(verifier.is_required("contoso.azurecr.io/app:1.0"));
+        assert!(!verifier.is_required("mcr.microsoft.com/azureiotedge-agent:1.5"));
+        assert_eq!(None, verifier.verified("contoso.azurecr.io/app:1.0"));
+
+        let pinned = verifier
+            .verify("contoso.azurecr.io/app:1.0", None)
+            .await
+            .unwrap();
+        assert_eq!(format!("contoso.azurecr.io/app@{DIGEST}"), pinned);
+        assert_eq!(
+            Some(pinned),
+            verifier.verified("contoso.azurecr.io/app:1.0")
+        );
+
+        // Already pinned to the signed digest.
+        verifier
+            .verify(&format!("contoso.azurecr.io/app@{DIGEST}"), None)
+            .await
+            .unwrap();
+    }
+
+    #[test]
+    fn bundle_images() {
+        let ca = TestCa::new("ca");
+        let verifier = verifier(&ca, vec![ca.sign("contoso.azurecr.io/app", DIGEST)]);
+
+        let err = verifier
+            .check_bundle_image("contoso.azurecr.io/app:1.0")
+            .unwrap_err();
+        assert!(matches!(err, Error::ImageVerification(..)), "{err}");
+
+        verifier
+            .check_bundle_image("mcr.microsoft.com/azureiotedge-agent:1.5")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn unsigned_image() {
+        let ca = TestCa::new("ca");
+        let verifier = verifier(&ca, vec![]);
+
+        let err = verifier
+            .verify("contoso.azurecr.io/app:1.0", None)
+            .await
+            .unwrap_err();
+        assert_rejected(&err);
+        assert_eq!(None, verifier.verified("contoso.azurecr.io/app:1.0"));
+    }
+
+    #[tokio::test]
+    async fn verification_is_forgotten() {
+        let ca = TestCa::new("ca");
+        let verifier = verifier(&ca, vec![ca.sign("contoso.azurecr.io/app", DIGEST)]);
+
+        verifier
+            .verify("contoso.azurecr.io/app:1.0", None)
+            .await
+            .unwrap();
+        verifier.forget("contoso.azurecr.io/app:1.0");
+        assert_eq!(None, verifier.verified("contoso.azurecr.io/app:1.0"));
+
+        // A tag that moved to an unsigned digest is not pinned to the digest it had before.
+        verifier
+            .verify("contoso.azurecr.io/app:1.0", None)
+            .await
+            .unwrap();
+        let moved = ImageVerifier {
+            registry: Arc::new(TestRegistry {
+                digest: "sha256:0123".to_owned(),
+                signatures: vec![],
+            }),
+            ..verifier.clone()
+        };
+        moved
+            .verify("contoso.azurecr.io/app:1.0", None)
+            .await
+            .unwrap_err();
+        assert_eq!(None, verifier.verified("contoso.azurecr.io/app:1.0"));
+    }
+
+    #[tokio::test]
+    async fn untrusted_signature() {
+        let ca = TestCa::new("ca");
+        let other_ca = TestCa::new("other");
+        let verifier = verifier(&ca, vec![other_ca.sign("contoso.azurecr.io/app", DIGEST)]);
+
+        let err = verifier
+            .verify("contoso.azurecr.io/app:1.0", None)
+            .await
+            .unwrap_err();
+        assert_rejected(&err);
+    }
+
+    #[tokio::test]
+    async fn one_trusted_signature_is_enough() {
+        let ca = TestCa::new("ca");
+        let other_ca = TestCa::new("other");
+        let verifier = verifier(
+            &ca,
+            vec![
+                other_ca.sign("contoso.azurecr.io/app", DIGEST),
+                ca.sign("contoso.azurecr.io/app", DIGEST),
+            ],
+        );
+
+        verifier
+            .verify("contoso.azurecr.io/app:1.0", None)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn digest_mismatch() {
+        let ca = TestCa::new("ca");
+        let verifier = verifier(&ca, vec![ca.sign("contoso.azurecr.io/app", DIGEST)]);
+
+        let err = verifier
+            .verify("contoso.azurecr.io/app@sha256:0123", None)
+            .await
+            .unwrap_err();
+        assert_rejected(&err);
+    }
+}
@@ -0,0 +1,168 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::fmt;
+
+/// The registry of images that do not name one.
+const DEFAULT_REGISTRY: &str = "docker.io";
+
+/// The host that serves the registry API of `docker.io`.
+const DEFAULT_REGISTRY_HOST: &str = "registry-1.docker.io";
+
+const DEFAULT_TAG: &str = "latest";
+
+/// A parsed image reference such as `mcr.microsoft.com/azureiotedge-agent:1.5`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ImageReference {
+    /// The image name as written, without tag or digest.
+    name: String,
+    registry: String,
+    repository: String,
+    tag: Option<String>,
+    digest: Option<String>,
+}
+
+impl ImageReference {
+    pub fn parse(image: &str) -> Result<Self, String> {
+        let (rest, digest) = match image.split_once('@') {
+            Some((rest, digest)) => {
+                if !digest.starts_with("sha256:") {
+                    return Err(format!("unsupported digest {digest:?}"));
+                }
+                (rest, Some(digest.to_owned()))
+            }
+            None => (image, None),
+        };
+
+        // A ':' after the last '/' separates the tag. Any other ':' belongs to a registry port.
+        let (name, tag) = match rest.rsplit_once(':') {
+            Some((name, tag)) if !tag.contains('/') => (name, Some(tag.to_owned())),
+            _ => (rest, None),
+        };
+
+        if name.is_empty() {
+            return Err(format!("invalid image reference {image:?}"));
+        }
+
+        let (registry, repository) = match name.split_once('/') {
+            Some((host, repository)) if host.contains(['.', ':']) || host == "localhost" => {
+                (host.to_ascii_lowercase(), repository.to_owned())
+            }
+            Some(_) => (DEFAULT_REGISTRY.to_owned(), name.to_owned()),
+            None => (DEFAULT_REGISTRY.to_owned(), format!("library/{name}")),
+        };
+
+        Ok(ImageReference {
+            name: name.to_owned(),
+            registry,
+            repository,
+            tag,
+            digest,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn registry(&self) -> &str {
+        &self.registry
+    }
+
+    /// The host that serves the registry API.
+    pub fn registry_host(&self) -> &str {
+        if self.registry == DEFAULT_REGISTRY {
+            DEFAULT_REGISTRY_HOST
+        } else {
+            &self.registry
+        }
+    }
+
+    pub fn repository(&self) -> &str {
+        &self.repository
+    }
+
+    /// The tag, which defaults to `latest` when neither a tag nor a digest is given.
+    pub fn tag(&self) -> Option<&str> {
+        match (&self.tag, &self.digest) {
+            (Some(tag), _) => Some(tag),
+            (None, None) => Some(DEFAULT_TAG),
+            (None, Some(_)) => None,
+        }
+    }
+
+    pub fn digest(&self) -> Option<&str> {
+        self.digest.as_deref()
+    }
+
+    /// What to ask the registry for: the digest if there is one, otherwise the tag.
+    pub fn manifest_reference(&self) -> &str {
+        self.digest
+            .as_deref()
+            .or_else(|| self.tag())
+            .unwrap_or(DEFAULT_TAG)
+    }
+
+    /// The fully qualified repository, as recorded in signature payloads.
+    pub fn qualified_name(&self) -> String {
+        format!("{}/{}", self.registry, self.repository)
+    }
+
+    /// This image pinned to `digest`.
+    pub fn pinned(&self, digest: &str) -> String {
+        format!("{}@{digest}", self.name)
+    }
+}
+
+impl fmt::Display for ImageReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)?;
+        if let Some(tag) = &self.tag {
+            write!(f, ":{tag}")?;
+        }
+        if let Some(digest) = &self.digest {
+            write!(f, "@{digest}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ImageReference;
+
+    #[test]
+    fn parse() {
+        let reference = ImageReference::parse("mcr.microsoft.com/azureiotedge-agent:1.5").unwrap();
+        assert_eq!(reference.name(), "mcr.microsoft.com/azureiotedge-agent");
+        assert_eq!(reference.registry(), "mcr.microsoft.com");
+        assert_eq!(reference.repository(), "azureiotedge-agent");
+        assert_eq!(reference.tag(), Some("1.5"));
+        assert_eq!(reference.digest(), None);
+        assert_eq!(reference.manifest_reference(), "1.5");
+
+        let reference = ImageReference::parse("ubuntu").unwrap();
+        assert_eq!(reference.registry(), "docker.io");
+        assert_eq!(reference.registry_host(), "registry-1.docker.io");
+        assert_eq!(reference.repository(), "library/ubuntu");
+        assert_eq!(reference.tag(), Some("latest"));
+        assert_eq!(reference.qualified_name(), "docker.io/library/ubuntu");
+
+        let reference = ImageReference::parse("localhost:5000/team/app@sha256:abcd").unwrap();
+        assert_eq!(reference.registry(), "localhost:5000");
+        assert_eq!(reference.repository(), "team/app");
+        assert_eq!(reference.tag(), None);
+        assert_eq!(reference.digest(), Some("sha256:abcd"));
+        assert_eq!(reference.manifest_reference(), "sha256:abcd");
+        assert_eq!(reference.to_string(), "localhost:5000/team/app@sha256:abcd");
+
+        let reference = ImageReference::parse("contoso.azurecr.io/app:1.0").unwrap();
+        assert_eq!(
+            reference.pinned("sha256:1234"),
+            "contoso.azurecr.io/app@sha256:1234"
+        );
+
+        ImageReference::parse("app@md5:1234").unwrap_err();
+        ImageReference::parse(":1.0").unwrap_err();
+    }
+}
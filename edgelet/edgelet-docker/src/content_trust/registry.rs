@@ -0,0 +1,514 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! A minimal client of the OCI distribution API, covering what signature verification needs.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use anyhow::Context;
+use http_body_util::{BodyExt, Empty};
+use hyper::body::{Bytes, Incoming};
+use hyper_openssl::client::legacy::HttpsConnector;
+use hyper_util::client::legacy::Client;
+use hyper_util::client::legacy::connect::HttpConnector;
+
+use docker::models::AuthConfig;
+
+use super::ImageReference;
+use super::cosign::{CERTIFICATE_ANNOTATION, CHAIN_ANNOTATION, SIGNATURE_ANNOTATION, Signature};
+
+const MANIFEST_MEDIA_TYPES: &str = "application/vnd.oci.image.index.v1+json, application/vnd.oci.image.manifest.v1+json, application/vnd.docker.distribution.manifest.list.v2+json, application/vnd.docker.distribution.manifest.v2+json";
+
+const MAX_REDIRECTS: usize = 5;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Where signatures and digests of images come from.
+#[async_trait::async_trait]
+pub trait Registry: Send + Sync {
+    /// Resolves `reference` to the digest of its manifest.
+    async fn manifest_digest(
+        &self,
+        reference: &ImageReference,
+        auth: Option<&AuthConfig>,
+    ) -> anyhow::Result<String>;
+
+    /// Fetches the cosign signatures of the manifest of `reference` with digest `digest`.
+    async fn signatures(
+        &self,
+        reference: &ImageReference,
+        digest: &str,
+        auth: Option<&AuthConfig>,
+    ) -> anyhow::Result<Vec<Signature>>;
+}
+
+/// A registry reached over HTTPS.
+pub struct HttpRegistry {
+    client: Client<HttpsConnector<HttpConnector>, Empty<Bytes>>,
+}
+
+impl HttpRegistry {
+    pub fn new() -> anyhow::Result<Self> {
+        let connector = HttpsConnector::new().context("could not initialize TLS")?;
+        let client = Client::builder(hyper_util::rt::TokioExecutor::new())
+            .build::<_, Empty<Bytes>>(connector);
+
+        Ok(HttpRegistry { client })
+    }
+
+    /// Starts a series of requests for the repository of `reference`.
+    pub(crate) fn session<'a>(
+        &'a self,
+        reference: &'a ImageReference,
+        auth: Option<&'a AuthConfig>,
+    ) -> Session<'a> {
+        Session {
+            registry: self,
+            reference,
+            auth,
+            authorization: None,
+        }
+    }
+}
+
+/// A manifest, or an index of manifests for different platforms.
+pub(crate) struct Manifest {
+    pub(crate) digest: String,
+    pub(crate) media_type: Option<String>,
+    pub(crate) body: Bytes,
+}
+
+#[async_trait::async_trait]
+impl Registry for HttpRegistry {
+    async fn manifest_digest(
+        &self,
+        reference: &ImageReference,
+        auth: Option<&AuthConfig>,
+    ) -> anyhow::Result<String> {
+        let mut session = self.session(reference, auth);
+        let manifest = session.manifest(reference.manifest_reference()).await?;
+
+        Ok(manifest.digest)
+    }
+
+    async fn signatures(
+        &self,
+        reference: &ImageReference,
+        digest: &str,
+        auth: Option<&AuthConfig>,
+    ) -> anyhow::Result<Vec<Signature>> {
+        #[derive(serde::Deserialize)]
+        struct Manifest {
+            #[serde(default)]
+            layers: Vec<Layer>,
+        }
+
+        #[derive(serde::Deserialize)]
+        struct Layer {
+            digest: String,
+            #[serde(default)]
+            annotations: BTreeMap<String, String>,
+        }
+
+        let mut session = self.session(reference, auth);
+
+        let signature_tag = format!("{}.sig", digest.replace(':', "-"));
+        let Some((_, manifest)) = session
+            .get(
+                &session.url(&format!("manifests/{signature_tag}")),
+                MANIFEST_MEDIA_TYPES,
+            )
+            .await?
+        else {
+            return Ok(vec![]);
+        };
+        let manifest: Manifest =
+            serde_json::from_slice(&manifest).context("invalid signature manifest")?;
+
+        let mut signatures = vec![];
+
+        for layer in manifest.layers {
+            let Some(signature) = layer.annotations.get(SIGNATURE_ANNOTATION) else {
+                continue;
+            };
+            let signature =
+                base64::Engine::decode(&base64::engine::general_purpose::STANDARD, signature)
+                    .context("invalid signature encoding")?;
+
+            let (_, payload) = session
+                .get(&session.url(&format!("blobs/{}", layer.digest)), "*/*")
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("signature payload {} not found", layer.digest))?;
+            anyhow::ensure!(
+                sha256_digest(&payload) == layer.digest,
+                "signature payload does not match its digest {}",
+                layer.digest
+            );
+
+            signatures.push(Signature {
+                payload: payload.to_vec(),
+                signature,
+                certificate: layer
+                    .annotations
+                    .get(CERTIFICATE_ANNOTATION)
+                    .map(|pem| pem.clone().into_bytes()),
+                chain: layer
+                    .annotations
+                    .get(CHAIN_ANNOTATION)
+                    .map(|pem| pem.clone().into_bytes()),
+            });
+        }
+
+        Ok(signatures)
+    }
+}
+
+pub(crate) fn sha256_digest(content: &[u8]) -> String {
+    format!("sha256:{}", hex::encode(openssl::sha::sha256(content)))
+}
+
+/// Requests for one repository, which share the authorization obtained by the first of them.
+pub(crate) struct Session<'a> {
+    registry: &'a HttpRegistry,
+    reference: &'a ImageReference,
+    auth: Option<&'a AuthConfig>,
+    authorization: Option<String>,
+}
+
+impl Session<'_> {
+    /// Fetches the manifest with the given tag or digest.
+    pub(crate) async fn manifest(&mut self, manifest_reference: &str) -> anyhow::Result<Manifest> {
+        let (headers, body) = self
+            .get(
+                &self.url(&format!("manifests/{manifest_reference}")),
+                MANIFEST_MEDIA_TYPES,
+            )
+            .await?
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "manifest {manifest_reference} of {} not found",
+                    self.reference.name()
+                )
+            })?;
+
+        // The digest is computed rather than taken from the response, so that a registry cannot
+        // claim a digest for content it did not serve.
+        let digest = sha256_digest(&body);
+
+        if let Some(claimed) = headers
+            .get("docker-content-digest")
+            .and_then(|value| value.to_str().ok())
+            .filter(|claimed| claimed.starts_with("sha256:"))
+        {
+            anyhow::ensure!(
+                claimed == digest,
+                "registry reported digest {claimed} for {}, but the manifest has digest {digest}",
+                self.reference.name()
+            );
+        }
+
+        let media_type = headers
+            .get(hyper::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| {
+                value
+                    .split(';')
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .to_owned()
+            });
+
+        Ok(Manifest {
+            digest,
+            media_type,
+            body,
+        })
+    }
+
+    /// Starts downloading blob `digest` from byte `offset`. Returns the body, and whether the
+    /// registry honoured the offset; if it did not, the body starts at the beginning of the blob.
+    pub(crate) async fn blob(
+        &mut self,
+        digest: &str,
+        offset: u64,
+    ) -> anyhow::Result<(Incoming, bool)> {
+        let url = self.url(&format!("blobs/{digest}"));
+        let mut response = self
+            .send(&url, "*/*", offset)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("blob {digest} not found"))?;
+
+        let mut resumed = response.status() == hyper::StatusCode::PARTIAL_CONTENT;
+
+        // A partial response that does not start where the earlier download stopped cannot be
+        // appended to it, so download the whole blob instead.
+        if resumed && range_start(response.headers()) != Some(offset) {
+            log::warn!("Registry did not resume blob {digest} at byte {offset}, starting over");
+
+            response = self
+                .send(&url, "*/*", 0)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("blob {digest} not found"))?;
+            resumed = false;
+        }
+
+        Ok((response.into_body(), resumed))
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!(
+            "https://{}/v2/{}/{path}",
+            self.reference.registry_host(),
+            self.reference.repository()
+        )
+    }
+
+    /// Fetches `url`. Returns `None` if the registry does not have it.
+    async fn get(
+        &mut self,
+        url: &str,
+        accept: &str,
+    ) -> anyhow::Result<Option<(hyper::HeaderMap, Bytes)>> {
+        let Some(response) = self.send(url, accept, 0).await? else {
+            return Ok(None);
+        };
+
+        let (parts, body) = response.into_parts();
+        let body = body.collect().await?.to_bytes();
+
+        Ok(Some((parts.headers, body)))
+    }
+
+    /// Requests `url` from byte `offset`, authenticating and following redirects as needed.
+    /// Returns `None` if the registry does not have it.
+    async fn send(
+        &mut self,
+        url: &str,
+        accept: &str,
+        offset: u64,
+    ) -> anyhow::Result<Option<hyper::Response<Incoming>>> {
+        let registry_host = self.reference.registry_host().to_owned();
+        let mut url = url::Url::parse(url)?;
+        let mut authenticated = self.authorization.is_some();
+
+        for _ in 0..=MAX_REDIRECTS {
+            let mut request =
+                hyper::Request::get(url.as_str()).header(hyper::header::ACCEPT, accept);
+            if offset > 0 {
+                request = request.header(hyper::header::RANGE, format!("bytes={offset}-"));
+            }
+
+            // Credentials are only sent to the registry itself, not to where it redirects to.
+            if let Some(authorization) = &self.authorization
+                && url.host_str() == Some(registry_host.as_str())
+            {
+                request = request.header(hyper::header::AUTHORIZATION, authorization);
+            }
+
+            let response = tokio::time::timeout(
+                REQUEST_TIMEOUT,
+                self.registry.client.request(request.body(Empty::new())?),
+            )
+            .await
+            .with_context(|| format!("request to {url} timed out"))?
+            .with_context(|| format!("request to {url} failed"))?;
+
+            let status = response.status();
+
+            if status.is_success() {
+                return Ok(Some(response));
+            } else if status.is_redirection() {
+                let location = response
+                    .headers()
+                    .get(hyper::header::LOCATION)
+                    .and_then(|location| location.to_str().ok())
+                    .ok_or_else(|| anyhow::anyhow!("redirect from {url} has no location"))?;
+                url = url.join(location)?;
+            } else if status == hyper::StatusCode::UNAUTHORIZED && !authenticated {
+                let challenge = response
+                    .headers()
+                    .get(hyper::header::WWW_AUTHENTICATE)
+                    .and_then(|challenge| challenge.to_str().ok())
+                    .ok_or_else(|| anyhow::anyhow!("{url} requires authentication"))?
+                    .to_owned();
+
+                self.authorization = Some(self.authenticate(&challenge).await?);
+                authenticated = true;
+            } else if status == hyper::StatusCode::NOT_FOUND {
+                return Ok(None);
+            } else {
+                anyhow::bail!("registry returned {status} for {url}");
+            }
+        }
+
+        anyhow::bail!("too many redirects for {url}")
+    }
+
+    /// Answers a `WWW-Authenticate` challenge with the value of an `Authorization` header.
+    async fn authenticate(&self, challenge: &str) -> anyhow::Result<String> {
+        #[derive(serde::Deserialize)]
+        struct TokenResponse {
+            token: Option<String>,
+            access_token: Option<String>,
+        }
+
+        let (scheme, params) = parse_challenge(challenge);
+
+        let basic = self.auth.and_then(|auth| {
+            let username = auth.username.as_deref()?;
+            let password = auth.password.as_deref().unwrap_or_default();
+
+            Some(format!(
+                "Basic {}",
+                base64::Engine::encode(
+                    &base64::engine::general_purpose::STANDARD,
+                    format!("{username}:{password}")
+                )
+            ))
+        });
+
+        if scheme.eq_ignore_ascii_case("basic") {
+            return basic.ok_or_else(|| anyhow::anyhow!("registry requires credentials"));
+        }
+        anyhow::ensure!(
+            scheme.eq_ignore_ascii_case("bearer"),
+            "unsupported authentication scheme {scheme:?}"
+        );
+
+        let realm = params
+            .get("realm")
+            .ok_or_else(|| anyhow::anyhow!("authentication challenge has no realm"))?;
+        let mut token_url = url::Url::parse(realm)?;
+        {
+            let mut query = token_url.query_pairs_mut();
+            if let Some(service) = params.get("service") {
+                query.append_pair("service", service);
+            }
+            if let Some(scope) = params.get("scope") {
+                query.append_pair("scope", scope);
+            }
+        }
+
+        let mut request = hyper::Request::get(token_url.as_str());
+        if let Some(basic) = &basic {
+            request = request.header(hyper::header::AUTHORIZATION, basic);
+        }
+
+        let response = tokio::time::timeout(
+            REQUEST_TIMEOUT,
+            self.registry.client.request(request.body(Empty::new())?),
+        )
+        .await
+        .context("token request timed out")?
+        .context("token request failed")?;
+        anyhow::ensure!(
+            response.status().is_success(),
+            "token request returned {}",
+            response.status()
+        );
+
+        let body = response.into_body().collect().await?.to_bytes();
+        let response: TokenResponse =
+            serde_json::from_slice(&body).context("invalid token response")?;
+        let token = response
+            .token
+            .or(response.access_token)
+            .ok_or_else(|| anyhow::anyhow!("token response has no token"))?;
+
+        Ok(format!("Bearer {token}"))
+    }
+}
+
+/// Splits a `WWW-Authenticate` header into its scheme and parameters.
+fn parse_challenge(challenge: &str) -> (&str, BTreeMap<String, String>) {
+    let (scheme, rest) = challenge
+        .trim()
+        .split_once(' ')
+        .unwrap_or((challenge.trim(), ""));
+
+    let mut params = BTreeMap::new();
+    let mut rest = rest.trim_start();
+
+    while let Some((key, value)) = rest.split_once('=') {
+        let key = key
+            .trim()
+            .trim_start_matches(',')
+            .trim()
+            .to_ascii_lowercase();
+
+        let value = if let Some(quoted) = value.strip_prefix('"') {
+            let end = quoted.find('"').unwrap_or(quoted.len());
+            rest = quoted.get(end + 1..).unwrap_or_default();
+            &quoted[..end]
+        } else {
+            let end = value.find(',').unwrap_or(value.len());
+            rest = &value[end..];
+            &value[..end]
+        };
+
+        params.insert(key, value.trim().to_owned());
+        rest = rest.trim_start_matches([',', ' ']);
+    }
+
+    (scheme, params)
+}
+
+/// The first byte of a partial response, from its `Content-Range` header.
+fn range_start(headers: &hyper::HeaderMap) -> Option<u64> {
+    let range = headers
+        .get(hyper::header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .strip_prefix("bytes ")?;
+    let (start, _) = range.split_once('-')?;
+
+    start.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn challenge() {
+        let (scheme, params) = parse_challenge(
+            r#"Bearer realm="https://contoso.azurecr.io/oauth2/token",service="contoso.azurecr.io",scope="repository:app:pull,push""#,
+        );
+        assert_eq!(scheme, "Bearer");
+        assert_eq!(params["realm"], "https://contoso.azurecr.io/oauth2/token");
+        assert_eq!(params["service"], "contoso.azurecr.io");
+        assert_eq!(params["scope"], "repository:app:pull,push");
+
+        let (scheme, params) = parse_challenge(r#"Basic realm="Registry Realm""#);
+        assert_eq!(scheme, "Basic");
+        assert_eq!(params["realm"], "Registry Realm");
+
+        let (_, params) =
+            parse_challenge("Bearer realm=https://auth.example.com/token, service=registry");
+        assert_eq!(params["realm"], "https://auth.example.com/token");
+        assert_eq!(params["service"], "registry");
+    }
+
+    #[test]
+    fn digest() {
+        assert_eq!(
+            sha256_digest(b""),
+            "sha256:e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
+    #[test]
+    fn content_range() {
+        let range = |value: &str| {
+            let mut headers = hyper::HeaderMap::new();
+            headers.insert(hyper::header::CONTENT_RANGE, value.parse().unwrap());
+            range_start(&headers)
+        };
+
+        assert_eq!(range("bytes 1024-4095/4096"), Some(1024));
+        assert_eq!(range("bytes 0-4095/*"), Some(0));
+        assert_eq!(range("bytes */4096"), None);
+        assert_eq!(range("items 1024-4095/4096"), None);
+        assert_eq!(range_start(&hyper::HeaderMap::new()), None);
+    }
+}
@@ -18,6 +18,12 @@ pub enum Error {
     #[error("invalid module type: {0:?}")]
     InvalidModuleType(String),
 
+    #[error("module {0:?} exceeds the device resource quotas: {1}")]
+    ResourceQuotaExceeded(String, String),
+
+    #[error("image {0:?} failed verification: {1}")]
+    ImageVerification(String, String),
+
     #[error("module operation error: {0}")]
     ModuleOperation(ModuleOperation),
 
@@ -36,6 +42,12 @@ pub enum Error {
     #[error("attempted to get image hash but was nonexistent.")]
     GetImageId(),
 
+    #[error("image garbage collection store error: {0}")]
+    ImageUseStore(redb::Error),
+
+    #[error("invalid image garbage collection record: {0}")]
+    ImageUseRecord(serde_json::Error),
+
     #[error("failed to lock for module image garbage collection: {0}")]
     LockError(String),
 
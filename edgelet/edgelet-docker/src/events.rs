@@ -0,0 +1,203 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use chrono::{DateTime, Utc};
+use http_body_util::BodyExt as _;
+use hyper::body::Incoming;
+use tokio::sync::mpsc;
+
+use docker::models::EventMessage;
+use edgelet_core::{EventKind, EventStream, ModuleEvent};
+
+use crate::runtime::{ORIGINAL_IMAGE_LABEL_KEY, OWNER_LABEL_KEY, OWNER_LABEL_VALUE};
+
+/// Filters for the Docker `/events` endpoint that select the events edged reports.
+pub(crate) const EVENT_FILTERS: &str = r#"{"type":["container","image"],"event":["create","start","stop","die","oom","destroy","pull"]}"#;
+
+/// Formats `since` the way Docker expects, as seconds and nanoseconds since the epoch.
+pub(crate) fn docker_timestamp(since: &DateTime<Utc>) -> String {
+    format!(
+        "{}.{:09}",
+        since.timestamp(),
+        since.timestamp_subsec_nanos()
+    )
+}
+
+/// Converts a Docker event into a module event. Returns `None` for events that do not concern
+/// modules, such as events of containers that were not created by IoT Edge.
+pub(crate) fn module_event(message: EventMessage) -> Option<ModuleEvent> {
+    let time = message
+        .time_nano
+        .map_or_else(Utc::now, DateTime::from_timestamp_nanos);
+    let actor = message.actor.unwrap_or_default();
+
+    match message.r#type.as_deref()? {
+        "container" => {
+            if actor.attributes.get(OWNER_LABEL_KEY).map(String::as_str) != Some(OWNER_LABEL_VALUE)
+            {
+                return None;
+            }
+
+            let kind = match message.action.as_deref()? {
+                "create" => EventKind::Created,
+                "start" => EventKind::Started,
+                "stop" => EventKind::Stopped,
+                "die" => EventKind::Died,
+                "oom" => EventKind::OomKilled,
+                "destroy" => EventKind::Removed,
+                _ => return None,
+            };
+
+            let exit_code = if kind == EventKind::Died {
+                actor
+                    .attributes
+                    .get("exitCode")
+                    .and_then(|code| code.parse().ok())
+            } else {
+                None
+            };
+
+            let image = actor
+                .attributes
+                .get(ORIGINAL_IMAGE_LABEL_KEY)
+                .or_else(|| actor.attributes.get("image"))
+                .cloned();
+
+            Some(
+                ModuleEvent::new(time, kind)
+                    .with_module(actor.attributes.get("name").cloned())
+                    .with_image(image)
+                    .with_exit_code(exit_code),
+            )
+        }
+
+        "image" if message.action.as_deref() == Some("pull") => {
+            Some(ModuleEvent::new(time, EventKind::ImagePulled).with_image(actor.id))
+        }
+
+        _ => None,
+    }
+}
+
+/// Reads the newline-delimited JSON event stream returned by Docker.
+pub(crate) fn stream(mut body: Incoming) -> EventStream {
+    let (sender, receiver) = mpsc::channel(64);
+
+    tokio::spawn(async move {
+        let mut pending = Vec::new();
+
+        while let Some(frame) = body.frame().await {
+            let data = match frame {
+                Ok(frame) => match frame.into_data() {
+                    Ok(data) => data,
+                    Err(_) => continue,
+                },
+                Err(err) => {
+                    log::warn!("Failed to read Docker events: {err}");
+                    return;
+                }
+            };
+
+            pending.extend_from_slice(&data);
+
+            while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = pending.drain(..=end).collect();
+
+                let message = match serde_json::from_slice::<EventMessage>(&line) {
+                    Ok(message) => message,
+                    Err(err) => {
+                        log::warn!("Ignoring malformed Docker event: {err}");
+                        continue;
+                    }
+                };
+
+                if let Some(event) = module_event(message)
+                    && sender.send(event).await.is_err()
+                {
+                    return;
+                }
+            }
+        }
+    });
+
+    receiver
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(value: serde_json::Value) -> EventMessage {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn container_event() {
+        let event = module_event(message(serde_json::json!({
+            "Type": "container",
+            "Action": "die",
+            "Actor": {
+                "ID": "abc",
+                "Attributes": {
+                    "exitCode": "137",
+                    "image": "sha256:123",
+                    "name": "tempSensor",
+                    "net.azure-devices.edge.original-image": "mcr.microsoft.com/sensor:1.0",
+                    "net.azure-devices.edge.owner": "Microsoft.Azure.Devices.Edge.Agent",
+                },
+            },
+            "timeNano": 1_704_164_645_000_000_000_i64,
+        })))
+        .unwrap();
+
+        assert_eq!(event.kind(), EventKind::Died);
+        assert_eq!(event.module(), Some("tempSensor"));
+        assert_eq!(event.image(), Some("mcr.microsoft.com/sensor:1.0"));
+        assert_eq!(event.exit_code(), Some(137));
+        assert_eq!(event.time().timestamp(), 1_704_164_645);
+    }
+
+    #[test]
+    fn foreign_container_event_is_ignored() {
+        let event = module_event(message(serde_json::json!({
+            "Type": "container",
+            "Action": "start",
+            "Actor": {
+                "ID": "abc",
+                "Attributes": { "name": "someone-else" },
+            },
+        })));
+
+        assert!(event.is_none());
+    }
+
+    #[test]
+    fn image_pull_event() {
+        let event = module_event(message(serde_json::json!({
+            "Type": "image",
+            "Action": "pull",
+            "Actor": { "ID": "mcr.microsoft.com/azureiotedge-agent:1.5" },
+        })))
+        .unwrap();
+
+        assert_eq!(event.kind(), EventKind::ImagePulled);
+        assert_eq!(event.module(), None);
+        assert_eq!(
+            event.image(),
+            Some("mcr.microsoft.com/azureiotedge-agent:1.5")
+        );
+
+        let event = module_event(message(serde_json::json!({
+            "Type": "image",
+            "Action": "delete",
+            "Actor": { "ID": "sha256:123" },
+        })));
+        assert!(event.is_none());
+    }
+
+    #[test]
+    fn docker_timestamp_format() {
+        let since = DateTime::from_timestamp(1_704_164_645, 5).unwrap();
+
+        assert_eq!(docker_timestamp(&since), "1704164645.000000005");
+    }
+}
@@ -1,22 +1,33 @@
 // Copyright (c) Microsoft. All rights reserved.
 
-use std::io::Write;
-use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
+use std::path::Path;
+use std::sync::Arc;
 use std::time::UNIX_EPOCH;
 use std::{collections::HashMap, collections::HashSet, fs, time::Duration};
 
+use chrono::{DateTime, Utc};
+use redb::{ReadableDatabase, ReadableTable, TableDefinition};
+
+use edgelet_core::ImageUse;
 use edgelet_settings::base::image::ImagePruneSettings;
 
 use crate::Error;
 
+const IMAGE_USE_DB_FILENAME: &str = "image_use.redb";
+
+// Image use was kept in these flat files before it moved to the store. They are imported
+// into the store and then removed.
 const IMAGE_USE_FILENAME: &str = "image_use";
 const TMP_FILENAME: &str = "image_use_tmp";
 
-#[derive(Debug, Clone)]
+// Image ID -> the image's `ImageUse`, as JSON.
+const IMAGES: TableDefinition<&str, &str> = TableDefinition::new("images");
+
+type ImageTable<'txn> = redb::Table<'txn, &'static str, &'static str>;
+
+#[derive(Debug)]
 struct ImagePruneInner {
-    image_use_filepath: String,
-    tmp_filepath: String,
+    db: redb::Database,
     settings: ImagePruneSettings,
 }
 
@@ -24,74 +35,78 @@ struct ImagePruneInner {
 /// The methods associated with this struct are at the heart of the image garbage collection
 /// feature. As such, this struct does not hold any user data, but simply holds information
 /// needed to collect/process state that (eventually) enables unused image garbage collection.
+/// That state is kept in an embedded transactional store, so an update that is interrupted
+/// by a crash is either fully applied or not at all.
 #[derive(Debug, Clone)]
 pub struct ImagePruneData {
-    inner: Arc<Mutex<ImagePruneInner>>,
+    inner: Arc<ImagePruneInner>,
+}
+
+/// What an image was used as, recorded along with the time of use.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ImageUseDetails<'a> {
+    /// The name that the image was pulled, imported or run as.
+    pub tag: Option<&'a str>,
+
+    /// The module that the image was used by.
+    pub module: Option<&'a str>,
+
+    /// Size of the image in bytes.
+    pub size: Option<u64>,
 }
 
 impl ImagePruneData {
     pub fn new(homedir: &Path, settings: ImagePruneSettings) -> Result<Self, Error> {
-        let fp: PathBuf = homedir.join(IMAGE_USE_FILENAME);
-        let tmp_fp: PathBuf = homedir.join(TMP_FILENAME);
-
-        let image_use_filepath = fp
-            .to_str()
-            .ok_or_else(|| Error::FilepathCreationError(IMAGE_USE_FILENAME.into()))?;
-        let tmp_filepath = tmp_fp
-            .to_str()
-            .ok_or_else(|| Error::FilepathCreationError(TMP_FILENAME.into()))?;
-
-        Ok(Self {
-            inner: Arc::new(Mutex::new(ImagePruneInner {
-                image_use_filepath: image_use_filepath.to_string(),
-                tmp_filepath: tmp_filepath.to_string(),
-                settings,
-            })),
-        })
+        let db =
+            redb::Database::create(homedir.join(IMAGE_USE_DB_FILENAME)).map_err(store_error)?;
+
+        let image_use_data = Self {
+            inner: Arc::new(ImagePruneInner { db, settings }),
+        };
+
+        // Opening the table for writing creates it, so that reads never find it missing.
+        image_use_data.write(|_| Ok(()))?;
+
+        image_use_data.import_legacy_file(homedir)?;
+
+        Ok(image_use_data)
     }
 
     /// <summary>
     /// This method takes the `image_id` and adds (if the image is new) OR updates the last-used timestamp associated
-    /// with this `image_id`. This state is maintained for use during image garbage collection.
-    /// This method is (currently) called whenever a new image is pulled, a container is created, or when a container is removed.
-    pub fn record_image_use_timestamp(&self, image_id: &str) -> Result<(), Error> {
-        let guard = self
-            .inner
-            .lock()
-            .expect("Image garbage collection file operation failed");
-
-        // read persistence file into in-mem map
-        // this map now contains all images deployed to the device (through an IoT Edge deployment)
-
-        let mut image_map = match get_images_with_timestamp(guard.image_use_filepath.clone()) {
-            Ok(map) => map,
-            Err(e) => {
-                drop(guard);
-                log::warn!(
-                    "Could not read image garbage collection data. Latest time of use will not be updated for image: {image_id}. Error: {e}"
-                );
-                return Err(e);
+    /// with this `image_id`, along with what the image was used as. This state is maintained for use during image
+    /// garbage collection.
+    /// This method is (currently) called whenever a new image is pulled or imported, a container is created, or when
+    /// a container is removed.
+    pub fn record_image_use(
+        &self,
+        image_id: &str,
+        details: ImageUseDetails<'_>,
+    ) -> Result<(), Error> {
+        let now = Utc::now();
+
+        let res = self.write(|table| {
+            let mut image_use =
+                read_image(table, image_id)?.unwrap_or_else(|| ImageUse::new(image_id, now));
+
+            image_use.last_used = now;
+            image_use.tags.extend(details.tag.map(str::to_string));
+            image_use.modules.extend(details.module.map(str::to_string));
+            if details.size.is_some() {
+                image_use.size = details.size;
             }
-        };
 
-        let current_time = std::time::SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("Could not get EPOCH time");
-
-        image_map.insert(image_id.to_string(), current_time);
-
-        // write entries back to file
-        let res = write_images_with_timestamp(
-            &image_map,
-            guard.tmp_filepath.clone(),
-            guard.image_use_filepath.clone(),
-        );
+            write_image(table, &image_use)
+        });
 
-        if res.is_ok() {
-            log::debug!("Image with ID {image_id} tracked in image garbage collection state.");
+        if let Err(e) = res {
+            log::warn!(
+                "Could not update image garbage collection data. Latest time of use will not be updated for image: {image_id}. Error: {e}"
+            );
+            return Err(e);
         }
 
-        drop(guard);
+        log::debug!("Image with ID {image_id} tracked in image garbage collection state.");
 
         Ok(())
     }
@@ -101,76 +116,237 @@ impl ImagePruneData {
     /// will be deleted by the image garbage collector.
     /// The `in_use_image_ids` is a set of image IDs currently being used on the device [and
     /// contains image_ids that may or may not have been deployed by IoTEdge].
+    /// The returned map holds the time each image was last used (in epoch).
+    /// `older_than` overrides the configured minimum age of the images to delete. With
+    /// `dry_run`, the store is left as it is, so the images are still tracked.
     pub fn prune_images_from_file(
         &self,
         in_use_image_ids: HashSet<String>,
+        older_than: Option<Duration>,
+        dry_run: bool,
     ) -> Result<HashMap<String, Duration>, Error> {
-        let guard = self
-            .inner
-            .lock()
-            .map_err(|e| Error::LockError(e.to_string()))?;
-
-        let settings = guard.settings.clone();
-
-        // Read persistence file into in-mem map. This map now contains
-        // all images deployed to the device (through an IoT Edge deployment).
-        // If persistence file cannot be read we will return a new map so
-        // new persistence file will be created.
-        let iotedge_images_map = match get_images_with_timestamp(guard.image_use_filepath.clone()) {
-            Ok(map) => map,
+        let image_age_cleanup_threshold =
+            older_than.unwrap_or_else(|| self.inner.settings.image_age_cleanup_threshold());
+
+        let res = if dry_run {
+            self.read(|table| {
+                let (images_to_delete, _) = process_state(
+                    last_used(&read_images(table)?),
+                    in_use_image_ids,
+                    image_age_cleanup_threshold,
+                )?;
+
+                Ok(images_to_delete)
+            })
+        } else {
+            self.write(|table| {
+                let mut images = read_images(table)?;
+
+                let (images_to_delete, carry_over) = process_state(
+                    last_used(&images),
+                    in_use_image_ids,
+                    image_age_cleanup_threshold,
+                )?;
+
+                // images that are in use were last used now
+                for (image_id, last_used) in carry_over {
+                    let last_used = from_epoch(last_used);
+                    if let Some(image_use) = images
+                        .get_mut(&image_id)
+                        .filter(|image_use| image_use.last_used != last_used)
+                    {
+                        image_use.last_used = last_used;
+                        write_image(table, image_use)?;
+                    }
+                }
+
+                for image_id in images_to_delete.keys() {
+                    table.remove(image_id.as_str()).map_err(store_error)?;
+                }
+
+                Ok(images_to_delete)
+            })
+        };
+
+        // If the store cannot be read or updated, no changes are made and no images are
+        // pruned until the next run.
+        match res {
+            Ok(images_to_delete) => Ok(images_to_delete),
             Err(e) => {
-                drop(guard);
                 log::warn!(
-                    "Could not read image garbage collection data. Image garbage collection will not prune any images. {e}"
+                    "Could not update image garbage collection data. Image garbage collection will not prune any images. {e}"
                 );
-                return Ok(HashMap::new());
+                Ok(HashMap::new())
             }
-        };
+        }
+    }
 
-        /* ============================== */
+    /// <summary>
+    /// This method is called when the disk is running out of space. It returns the images
+    /// deployed through IoT Edge that are not in `in_use_image_ids`, least recently used first,
+    /// regardless of their age. Unlike `prune_images_from_file`, it does not update the
+    /// store; call `forget_image` for each image that is actually removed.
+    pub fn least_recently_used(
+        &self,
+        in_use_image_ids: &HashSet<String>,
+    ) -> Result<Vec<String>, Error> {
+        self.read(|table| Ok(lru_order(last_used(&read_images(table)?), in_use_image_ids)))
+    }
 
-        // process maps
-        let (images_to_delete, carry_over) = process_state(
-            iotedge_images_map,
-            in_use_image_ids,
-            settings.image_age_cleanup_threshold(),
-        )?;
+    /// <summary>
+    /// This method removes `image_id` from the store after the image has been deleted outside
+    /// of a scheduled run.
+    pub fn forget_image(&self, image_id: &str) -> Result<(), Error> {
+        self.write(|table| {
+            table.remove(image_id).map_err(store_error)?;
+            Ok(())
+        })
+    }
 
-        /* ============================== */
+    /// <summary>
+    /// This method returns the images tracked for image garbage collection, ordered by ID.
+    pub fn images(&self) -> Result<Vec<ImageUse>, Error> {
+        let mut images: Vec<ImageUse> = self.read(read_images)?.into_values().collect();
+        images.sort_by(|a, b| a.id.cmp(&b.id));
 
-        // write previously removed entries back to file
-        if let Err(e) = write_images_with_timestamp(
-            &carry_over,
-            guard.tmp_filepath.clone(),
-            guard.image_use_filepath.clone(),
-        ) {
-            log::warn!(
-                "Failed to update image auto pruning persistence file. File will be updated on next scheduled run. {e}"
-            );
+        Ok(images)
+    }
+
+    // Imports the image use that earlier versions kept in a flat file, and removes the file.
+    fn import_legacy_file(&self, homedir: &Path) -> Result<(), Error> {
+        let image_use_filepath = homedir.join(IMAGE_USE_FILENAME);
+        if !image_use_filepath.exists() {
+            return Ok(());
         }
 
-        /* ============================== */
+        let legacy_images = get_images_with_timestamp(&image_use_filepath)?;
+
+        self.write(|table| {
+            for (image_id, last_used) in legacy_images {
+                if read_image(table, &image_id)?.is_none() {
+                    write_image(table, &ImageUse::new(image_id, from_epoch(last_used)))?;
+                }
+            }
+
+            Ok(())
+        })?;
+
+        log::info!(
+            "Imported image garbage collection data from {}",
+            image_use_filepath.display()
+        );
+
+        for filepath in [image_use_filepath, homedir.join(TMP_FILENAME)] {
+            match fs::remove_file(&filepath) {
+                Ok(()) => {}
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                Err(err) => {
+                    return Err(Error::FileOperation(format!(
+                        "Could not remove {}: {err}",
+                        filepath.display()
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    // Runs `f` on the image table in a read transaction.
+    fn read<T>(
+        &self,
+        f: impl FnOnce(&redb::ReadOnlyTable<&'static str, &'static str>) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let txn = self.inner.db.begin_read().map_err(store_error)?;
+        let table = txn.open_table(IMAGES).map_err(store_error)?;
+
+        f(&table)
+    }
+
+    // Runs `f` on the image table in a write transaction, which is only committed if `f`
+    // succeeds.
+    fn write<T>(
+        &self,
+        f: impl FnOnce(&mut ImageTable<'_>) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        let txn = self.inner.db.begin_write().map_err(store_error)?;
+
+        let result = {
+            let mut table = txn.open_table(IMAGES).map_err(store_error)?;
+            f(&mut table)?
+        };
 
-        drop(guard);
+        txn.commit().map_err(store_error)?;
 
-        // these are the images we need to prune; file has already been updated
-        Ok(images_to_delete)
+        Ok(result)
     }
 }
 
 /* ===================================== HELPER METHODS ==================================== */
 
-fn get_images_with_timestamp(
-    image_use_filepath: String,
-) -> Result<HashMap<String, Duration>, Error> {
-    if !std::path::Path::new(&image_use_filepath).exists() {
-        log::info!(
-            "Image garbage collection data file not found; creating file at: {}",
-            image_use_filepath.as_str(),
-        );
-        let _file = fs::File::create(image_use_filepath.clone()).map_err(Error::CreateFile)?;
+fn store_error(err: impl Into<redb::Error>) -> Error {
+    Error::ImageUseStore(err.into())
+}
+
+fn read_image(
+    table: &impl ReadableTable<&'static str, &'static str>,
+    image_id: &str,
+) -> Result<Option<ImageUse>, Error> {
+    table
+        .get(image_id)
+        .map_err(store_error)?
+        .map(|image_use| serde_json::from_str(image_use.value()).map_err(Error::ImageUseRecord))
+        .transpose()
+}
+
+fn read_images(
+    table: &impl ReadableTable<&'static str, &'static str>,
+) -> Result<HashMap<String, ImageUse>, Error> {
+    let mut images = HashMap::new();
+
+    for entry in table.iter().map_err(store_error)? {
+        let (image_id, image_use) = entry.map_err(store_error)?;
+        let image_use: ImageUse =
+            serde_json::from_str(image_use.value()).map_err(Error::ImageUseRecord)?;
+
+        images.insert(image_id.value().to_string(), image_use);
     }
 
+    Ok(images)
+}
+
+fn write_image(table: &mut ImageTable<'_>, image_use: &ImageUse) -> Result<(), Error> {
+    let value = serde_json::to_string(image_use).map_err(Error::ImageUseRecord)?;
+
+    table
+        .insert(image_use.id.as_str(), value.as_str())
+        .map_err(store_error)?;
+
+    Ok(())
+}
+
+// The time each image was last used (in epoch), which garbage collection decides on.
+fn last_used(images: &HashMap<String, ImageUse>) -> HashMap<String, Duration> {
+    images
+        .iter()
+        .map(|(image_id, image_use)| {
+            let last_used = u64::try_from(image_use.last_used.timestamp()).unwrap_or_default();
+            (image_id.clone(), Duration::from_secs(last_used))
+        })
+        .collect()
+}
+
+fn from_epoch(time: Duration) -> DateTime<Utc> {
+    i64::try_from(time.as_secs())
+        .ok()
+        .and_then(|secs| DateTime::from_timestamp(secs, 0))
+        .unwrap_or(DateTime::<Utc>::UNIX_EPOCH)
+}
+
+// Reads the flat file that image use was kept in before the store.
+fn get_images_with_timestamp(
+    image_use_filepath: &Path,
+) -> Result<HashMap<String, Duration>, Error> {
     let contents = match fs::read_to_string(image_use_filepath) {
         Ok(ct) => ct,
         Err(e) => {
@@ -184,13 +360,12 @@ fn get_images_with_timestamp(
 
     // TL;DR: this dumps pruning data into the image_map, where
     // Key: Image hash, Value: Timestamp when image was last used (in epoch)
-    let contents = contents
-        .lines()
-        .map(|line| line.split(' ').collect::<Vec<&str>>())
-        .map(|vec| (vec[0].to_string(), vec[1]));
-    for (k, v) in contents {
+    for line in contents.lines() {
+        let Some((k, v)) = line.split_once(' ') else {
+            continue;
+        };
         image_map.insert(
-            k,
+            k.to_string(),
             Duration::from_secs(v.parse::<u64>().map_err(Error::ParseIntError)?),
         );
     }
@@ -198,38 +373,6 @@ fn get_images_with_timestamp(
     Ok(image_map)
 }
 
-fn write_images_with_timestamp(
-    state_to_persist: &HashMap<String, Duration>,
-    temp_file: String,
-    image_use_filepath: String,
-) -> Result<(), Error> {
-    // write to a temp file and then rename/overwrite to image persistence file (to prevent file write failures or corruption)
-    let mut file = std::fs::File::create(temp_file.clone()).map_err(Error::CreateFile)?;
-
-    for (key, value) in state_to_persist {
-        let image_details = format!("{key} {}\n", value.as_secs());
-        let res = write!(file, "{image_details}");
-        if res.is_err() {
-            let msg = format!(
-                "Could not write image:{key} with timestamp:{} to store",
-                value.as_secs(),
-            );
-            return Err(Error::FileOperation(msg));
-        }
-    }
-
-    match fs::rename(temp_file, image_use_filepath) {
-        Ok(()) => {}
-        Err(err) => {
-            return Err(Error::FileOperation(format!(
-                "Could not update garbage collection data {err}"
-            )));
-        }
-    }
-
-    Ok(())
-}
-
 // This method separates out the images to be deleted from the images not to be deleted,
 // and returns those as a tuple: (images to be deleted, images to be written back to file)
 // It takes as input all the images present on the device (that we know about through an
@@ -275,6 +418,22 @@ fn process_state(
     Ok((iotedge_images_map, carry_over))
 }
 
+// This method orders the images that are not in use by the time they were last used, oldest
+// first. Images last used at the same time are ordered by ID so that the order is stable.
+fn lru_order(
+    iotedge_images_map: HashMap<String, Duration>,
+    in_use_image_ids: &HashSet<String>,
+) -> Vec<String> {
+    let mut unused: Vec<(Duration, String)> = iotedge_images_map
+        .into_iter()
+        .filter(|(image_id, _)| !in_use_image_ids.contains(image_id))
+        .map(|(image_id, last_used)| (last_used, image_id))
+        .collect();
+    unused.sort();
+
+    unused.into_iter().map(|(_, image_id)| image_id).collect()
+}
+
 #[cfg(test)]
 mod tests {
     use std::{
@@ -289,14 +448,10 @@ mod tests {
     use serial_test::serial;
 
     use crate::{
-        ImagePruneData,
-        image_prune_data::{
-            IMAGE_USE_FILENAME, TMP_FILENAME, get_images_with_timestamp, process_state,
-        },
+        ImagePruneData, ImageUseDetails,
+        image_prune_data::{IMAGE_USE_FILENAME, TMP_FILENAME, lru_order, process_state},
     };
 
-    use super::write_images_with_timestamp;
-
     const TEST_FILE_DIR: &str = "test-data";
 
     /* =============================================================== PUBLIC API TESTS ============================================================ */
@@ -322,25 +477,24 @@ mod tests {
 
         // write new image
         image_use_data
-            .record_image_use_timestamp(
+            .record_image_use(
                 "sha256:a4d112e0884bd2ba078ab8222e099bc989cc65cd433dfbb74d6de7cee188g4g7",
+                ImageUseDetails {
+                    tag: Some("mcr.microsoft.com/azureiotedge-simulated-temperature-sensor:1.0"),
+                    size: Some(1024),
+                    ..Default::default()
+                },
             )
             .unwrap();
-        let images = get_images_with_timestamp(
-            test_file_dir
-                .join(IMAGE_USE_FILENAME)
-                .into_os_string()
-                .into_string()
-                .unwrap(),
-        )
-        .unwrap();
+        let images = image_use_data.images().unwrap();
 
-        assert!(images.contains_key(
-            "sha256:a4d112e0884bd2ba078ab8222e099bc989cc65cd433dfbb74d6de7cee188g4g7"
-        ));
         assert!(images.len() == 1);
-        let old_time =
-            images.get("sha256:a4d112e0884bd2ba078ab8222e099bc989cc65cd433dfbb74d6de7cee188g4g7");
+        let old_image = &images[0];
+        assert_eq!(
+            "sha256:a4d112e0884bd2ba078ab8222e099bc989cc65cd433dfbb74d6de7cee188g4g7",
+            old_image.id
+        );
+        assert_eq!(old_image.first_used, old_image.last_used);
 
         unsafe {
             sleep(1);
@@ -348,28 +502,36 @@ mod tests {
 
         // update existing image
         image_use_data
-            .record_image_use_timestamp(
+            .record_image_use(
                 "sha256:a4d112e0884bd2ba078ab8222e099bc989cc65cd433dfbb74d6de7cee188g4g7",
+                ImageUseDetails {
+                    module: Some("SimulatedTemperatureSensor"),
+                    ..Default::default()
+                },
             )
             .unwrap();
-        let new_images = get_images_with_timestamp(
-            test_file_dir
-                .join(IMAGE_USE_FILENAME)
-                .into_os_string()
-                .into_string()
-                .unwrap(),
-        )
-        .unwrap();
-        assert!(new_images.contains_key(
-            "sha256:a4d112e0884bd2ba078ab8222e099bc989cc65cd433dfbb74d6de7cee188g4g7"
-        ));
+        let new_images = image_use_data.images().unwrap();
         assert!(new_images.len() == 1);
-        let new_time = new_images
-            .get("sha256:a4d112e0884bd2ba078ab8222e099bc989cc65cd433dfbb74d6de7cee188g4g7");
+        let new_image = &new_images[0];
+
+        assert!(old_image.last_used < new_image.last_used);
+        assert_eq!(old_image.first_used, new_image.first_used);
+        assert_eq!(Some(1024), new_image.size);
+        assert!(
+            new_image
+                .tags
+                .contains("mcr.microsoft.com/azureiotedge-simulated-temperature-sensor:1.0")
+        );
+        assert!(new_image.modules.contains("SimulatedTemperatureSensor"));
 
-        assert!(old_time < new_time);
+        // records survive a restart
+        drop(image_use_data);
+        let image_use_data =
+            ImagePruneData::new(&test_file_dir, ImagePruneSettings::default()).unwrap();
+        assert_eq!(new_images, image_use_data.images().unwrap());
 
         // cleanup
+        drop(image_use_data);
         std::fs::remove_dir_all(test_file_dir).unwrap();
     }
 
@@ -413,19 +575,7 @@ mod tests {
             time,
         );
 
-        let _write = write_images_with_timestamp(
-            &image_map,
-            test_file_dir
-                .join(TMP_FILENAME)
-                .into_os_string()
-                .into_string()
-                .unwrap(),
-            test_file_dir
-                .join(IMAGE_USE_FILENAME)
-                .into_os_string()
-                .into_string()
-                .unwrap(),
-        );
+        write_legacy_file(&test_file_dir, &image_map);
 
         let curr_time = (Utc::now().hour() * 60 + Utc::now().minute()).into();
         let settings = ImagePruneSettings::new(
@@ -454,49 +604,38 @@ mod tests {
 
         // image prune enabled, remove stuff
         let images_to_delete = image_use_data
-            .prune_images_from_file(in_use_image_ids)
+            .prune_images_from_file(in_use_image_ids.clone(), None, true)
             .unwrap();
         assert!(images_to_delete.len() == 2);
 
-        // cleanup
-        std::fs::remove_dir_all(test_file_dir).unwrap();
-    }
-
-    /* =============================================================== MORE TESTS ============================================================ */
+        // a dry run does not stop tracking the images, and a longer minimum age keeps them
+        let images_to_delete = image_use_data
+            .prune_images_from_file(
+                in_use_image_ids.clone(),
+                Some(Duration::from_mins(1)),
+                false,
+            )
+            .unwrap();
+        assert!(images_to_delete.is_empty());
 
-    #[test]
-    #[serial]
-    fn test_file_rename_succeeds() {
-        //setup
-        let test_file_dir = std::env::current_dir().unwrap().join(TEST_FILE_DIR);
-        if test_file_dir.is_dir() {
-            std::fs::remove_dir_all(test_file_dir.clone()).unwrap();
-        }
-        std::fs::create_dir(Path::new(&test_file_dir)).unwrap();
+        let images_to_delete = image_use_data
+            .prune_images_from_file(in_use_image_ids, None, false)
+            .unwrap();
+        assert!(images_to_delete.len() == 2);
 
-        let result = write_images_with_timestamp(
-            &HashMap::new(),
-            test_file_dir
-                .join(TMP_FILENAME)
-                .into_os_string()
-                .into_string()
-                .unwrap(),
-            test_file_dir
-                .join(IMAGE_USE_FILENAME)
-                .into_os_string()
-                .into_string()
-                .unwrap(),
-        );
-        assert!(result.is_ok());
-        assert!(std::path::Path::new(&test_file_dir.join(IMAGE_USE_FILENAME)).exists());
+        // deleted images are no longer tracked
+        assert!(image_use_data.images().unwrap().len() == 4);
 
         // cleanup
+        drop(image_use_data);
         std::fs::remove_dir_all(test_file_dir).unwrap();
     }
 
+    /* =============================================================== MORE TESTS ============================================================ */
+
     #[test]
     #[serial]
-    fn test_get_write_images_with_timestamp() {
+    fn test_import_legacy_file() {
         // setup
         let test_file_dir = std::env::current_dir().unwrap().join(TEST_FILE_DIR);
         if test_file_dir.is_dir() {
@@ -504,44 +643,37 @@ mod tests {
         }
         std::fs::create_dir(Path::new(&test_file_dir)).unwrap();
 
-        let current_time = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .expect("Could not get EPOCH time");
+        let time = Duration::from_secs(1_700_000_000);
 
         let mut hash_map: HashMap<String, Duration> = HashMap::new();
-        hash_map.insert("test1".to_string(), current_time);
-        hash_map.insert("test2".to_string(), current_time);
-        hash_map.insert("test3".to_string(), current_time);
-
-        let result = write_images_with_timestamp(
-            &hash_map,
-            test_file_dir
-                .join(TMP_FILENAME)
-                .into_os_string()
-                .into_string()
-                .unwrap(),
-            test_file_dir
-                .join(IMAGE_USE_FILENAME)
-                .into_os_string()
-                .into_string()
-                .unwrap(),
-        );
-        assert!(result.is_ok());
-        // assert file not empty, verify file write
-        let result_map: HashMap<String, Duration> = get_images_with_timestamp(
-            test_file_dir
-                .join(IMAGE_USE_FILENAME)
-                .into_os_string()
-                .into_string()
-                .unwrap(),
-        )
-        .unwrap();
-        assert!(result_map.len() == 3);
-        assert!(result_map.contains_key(&"test1".to_string()));
-        assert!(result_map.contains_key(&"test2".to_string()));
-        assert!(result_map.contains_key(&"test3".to_string()));
+        hash_map.insert("test1".to_string(), time);
+        hash_map.insert("test2".to_string(), time);
+        hash_map.insert("test3".to_string(), time);
+        write_legacy_file(&test_file_dir, &hash_map);
+        std::fs::write(test_file_dir.join(TMP_FILENAME), "").unwrap();
+
+        let image_use_data =
+            ImagePruneData::new(&test_file_dir, ImagePruneSettings::default()).unwrap();
+
+        // the images are imported with the time they were last used, and the files are removed
+        let images = image_use_data.images().unwrap();
+        assert_eq!(
+            vec!["test1", "test2", "test3"],
+            images
+                .iter()
+                .map(|image| image.id.as_str())
+                .collect::<Vec<_>>()
+        );
+        assert!(
+            images
+                .iter()
+                .all(|image| image.last_used.timestamp() == 1_700_000_000)
+        );
+        assert!(!test_file_dir.join(IMAGE_USE_FILENAME).exists());
+        assert!(!test_file_dir.join(TMP_FILENAME).exists());
 
         // cleanup
+        drop(image_use_data);
         std::fs::remove_dir_all(test_file_dir).unwrap();
     }
 
@@ -617,4 +749,42 @@ mod tests {
         assert!(to_delete.len() == 3);
         assert!(carry_over.len() == 4);
     }
+
+    #[test]
+    fn test_lru_order() {
+        let time = Duration::from_secs(1_000_000);
+
+        let mut all_iotedge_images: HashMap<String, Duration> = HashMap::new();
+        all_iotedge_images.insert("sha256:newest".to_string(), time);
+        all_iotedge_images.insert(
+            "sha256:oldest".to_string(),
+            time - Duration::from_hours(24 * 3),
+        );
+        all_iotedge_images.insert(
+            "sha256:in-use".to_string(),
+            time - Duration::from_hours(24 * 5),
+        );
+        all_iotedge_images.insert("sha256:b".to_string(), time - Duration::from_hours(24));
+        all_iotedge_images.insert("sha256:a".to_string(), time - Duration::from_hours(24));
+
+        let mut images_being_used: HashSet<String> = HashSet::new();
+        images_being_used.insert("sha256:in-use".to_string());
+        images_being_used.insert("sha256:not-deployed-by-iotedge".to_string());
+
+        assert_eq!(
+            vec!["sha256:oldest", "sha256:a", "sha256:b", "sha256:newest"],
+            lru_order(all_iotedge_images, &images_being_used)
+        );
+    }
+
+    fn write_legacy_file(dir: &Path, image_map: &HashMap<String, Duration>) {
+        use std::fmt::Write as _;
+
+        let mut contents = String::new();
+        for (image_id, last_used) in image_map {
+            writeln!(contents, "{image_id} {}", last_used.as_secs()).unwrap();
+        }
+
+        std::fs::write(dir.join(IMAGE_USE_FILENAME), contents).unwrap();
+    }
 }
@@ -1,18 +1,28 @@
 // Copyright (c) Microsoft. All rights reserved.
 
+mod content_trust;
 mod error;
+mod events;
 mod image_prune_data;
+mod metrics;
 mod module;
+mod policy;
+mod pull;
+mod quota;
 mod runtime;
 
+pub use content_trust::{HttpRegistry, ImageReference, ImageVerifier, Registry, Signature};
 pub use error::Error;
-pub use image_prune_data::ImagePruneData;
+pub use image_prune_data::{ImagePruneData, ImageUseDetails};
 pub use module::{DockerModule, MODULE_TYPE};
-pub use runtime::{DockerModuleRuntime, init_client};
+pub use policy::check_container_policy;
+pub use pull::pull_with_retries;
+pub use quota::{DEFAULT_CPU_PERIOD, apply_resource_quotas};
+pub use runtime::{DockerModuleRuntime, init_client, restrict_create_options};
 
 use tokio::sync::mpsc::UnboundedSender;
 
-use edgelet_core::{ModuleAction, ModuleRuntime};
+use edgelet_core::{ModuleAction, ModuleRuntime, RestartTracker};
 use edgelet_settings::RuntimeSettings;
 
 #[async_trait::async_trait]
@@ -25,5 +35,6 @@ pub trait MakeModuleRuntime {
         settings: &Self::Settings,
         create_socket_channel: UnboundedSender<ModuleAction>,
         image_use_data: ImagePruneData,
+        restart_tracker: RestartTracker,
     ) -> anyhow::Result<Self::ModuleRuntime>;
 }
@@ -0,0 +1,177 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use docker::models::{ContainerCpuStats, ContainerStatsResponse};
+use edgelet_core::ModuleMetrics;
+
+/// Converts the statistics that Docker reports for a module's container into module metrics,
+/// computing CPU and memory usage the same way as `docker stats`.
+#[allow(clippy::cast_precision_loss)]
+pub(crate) fn module_metrics(name: &str, stats: &ContainerStatsResponse) -> ModuleMetrics {
+    let total_usage = |cpu: &ContainerCpuStats| {
+        cpu.cpu_usage
+            .as_ref()
+            .and_then(|usage| usage.total_usage)
+            .unwrap_or_default()
+    };
+
+    let cpu = stats.cpu_stats.clone().unwrap_or_default();
+    let cpu_usage_seconds = total_usage(&cpu) as f64 / 1e9;
+
+    // One-shot statistics have no previous sample, so there is no interval to measure over.
+    let cpu_percent = stats.precpu_stats.as_ref().and_then(|precpu| {
+        let cpu_delta = total_usage(&cpu).checked_sub(total_usage(precpu))?;
+        let system_delta = cpu
+            .system_cpu_usage?
+            .checked_sub(precpu.system_cpu_usage.filter(|usage| *usage > 0)?)?;
+        let online_cpus = cpu.online_cpus.map(u64::from).or_else(|| {
+            cpu.cpu_usage
+                .as_ref()
+                .and_then(|usage| usage.percpu_usage.as_ref())
+                .map(|percpu| percpu.len() as u64)
+        })?;
+
+        (system_delta > 0)
+            .then(|| cpu_delta as f64 / system_delta as f64 * online_cpus as f64 * 100.0)
+    });
+
+    let memory = stats.memory_stats.clone().unwrap_or_default();
+    let usage = memory.usage.unwrap_or_default();
+
+    // Page cache that can be reclaimed is not counted, which is "total_inactive_file" on
+    // cgroup v1 and "inactive_file" on cgroup v2.
+    let inactive_file = memory
+        .stats
+        .get("total_inactive_file")
+        .or_else(|| memory.stats.get("inactive_file"))
+        .copied()
+        .unwrap_or_default();
+
+    let mut metrics = ModuleMetrics::new(name.to_owned())
+        .with_cpu(cpu_usage_seconds, cpu_percent)
+        .with_memory(usage.saturating_sub(inactive_file), memory.limit);
+
+    if let Some(networks) = &stats.networks {
+        let (rx, tx) = networks.values().fold((0, 0), |(rx, tx), network| {
+            (
+                rx + network.rx_bytes.unwrap_or_default(),
+                tx + network.tx_bytes.unwrap_or_default(),
+            )
+        });
+
+        metrics = metrics.with_network(rx, tx);
+    }
+
+    if let Some(entries) = stats
+        .blkio_stats
+        .as_ref()
+        .and_then(|blkio| blkio.io_service_bytes_recursive.as_ref())
+    {
+        let total = |op: &str| {
+            entries
+                .iter()
+                .filter(|entry| {
+                    entry
+                        .op
+                        .as_deref()
+                        .is_some_and(|entry_op| entry_op.eq_ignore_ascii_case(op))
+                })
+                .filter_map(|entry| entry.value)
+                .sum()
+        };
+
+        metrics = metrics.with_block_io(total("read"), total("write"));
+    }
+
+    metrics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::module_metrics;
+
+    #[test]
+    fn convert_stats() {
+        let stats = serde_json::from_value(serde_json::json!({
+            "name": "/tempSensor",
+            "cpu_stats": {
+                "cpu_usage": { "total_usage": 3_000_000_000_u64 },
+                "system_cpu_usage": 20_000_000_000_u64,
+                "online_cpus": 2,
+            },
+            "precpu_stats": {
+                "cpu_usage": { "total_usage": 2_000_000_000_u64 },
+                "system_cpu_usage": 10_000_000_000_u64,
+            },
+            "memory_stats": {
+                "usage": 1000,
+                "limit": 4000,
+                "stats": { "inactive_file": 200 },
+            },
+            "networks": {
+                "eth0": { "rx_bytes": 10, "tx_bytes": 20 },
+                "eth1": { "rx_bytes": 1, "tx_bytes": 2 },
+            },
+            "blkio_stats": {
+                "io_service_bytes_recursive": [
+                    { "major": 8, "minor": 0, "op": "read", "value": 100 },
+                    { "major": 8, "minor": 0, "op": "write", "value": 50 },
+                    { "major": 8, "minor": 16, "op": "Read", "value": 5 },
+                ],
+            },
+        }))
+        .unwrap();
+
+        let metrics = module_metrics("tempSensor", &stats);
+
+        assert_eq!("tempSensor", metrics.name());
+        assert!((metrics.cpu_usage_seconds() - 3.0).abs() < f64::EPSILON);
+        assert!((metrics.cpu_percent().unwrap() - 20.0).abs() < f64::EPSILON);
+        assert_eq!(800, metrics.memory_usage_bytes());
+        assert_eq!(Some(4000), metrics.memory_limit_bytes());
+        assert_eq!(Some(11), metrics.network_rx_bytes());
+        assert_eq!(Some(22), metrics.network_tx_bytes());
+        assert_eq!(Some(105), metrics.block_read_bytes());
+        assert_eq!(Some(50), metrics.block_write_bytes());
+    }
+
+    #[test]
+    fn convert_stopped_container() {
+        // Docker reports mostly empty statistics for containers that are not running.
+        let stats = serde_json::from_value(serde_json::json!({
+            "name": "/tempSensor",
+            "cpu_stats": { "cpu_usage": { "total_usage": 0 } },
+            "precpu_stats": { "cpu_usage": { "total_usage": 0 } },
+            "memory_stats": {},
+        }))
+        .unwrap();
+
+        let metrics = module_metrics("tempSensor", &stats);
+
+        assert_eq!(None, metrics.cpu_percent());
+        assert_eq!(0, metrics.memory_usage_bytes());
+        assert_eq!(None, metrics.network_rx_bytes());
+        assert_eq!(None, metrics.block_read_bytes());
+    }
+
+    #[test]
+    fn convert_one_shot_stats() {
+        // Docker leaves the previous sample empty when asked for one-shot statistics.
+        let stats = serde_json::from_value(serde_json::json!({
+            "name": "/tempSensor",
+            "cpu_stats": {
+                "cpu_usage": { "total_usage": 3_000_000_000_u64 },
+                "system_cpu_usage": 20_000_000_000_u64,
+                "online_cpus": 2,
+            },
+            "precpu_stats": { "cpu_usage": { "total_usage": 0 } },
+            "memory_stats": { "usage": 1000 },
+        }))
+        .unwrap();
+
+        let metrics = module_metrics("tempSensor", &stats);
+
+        assert!((metrics.cpu_usage_seconds() - 3.0).abs() < f64::EPSILON);
+        assert_eq!(None, metrics.cpu_percent());
+        assert_eq!(1000, metrics.memory_usage_bytes());
+    }
+}
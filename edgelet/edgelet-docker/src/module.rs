@@ -4,8 +4,10 @@ use anyhow::Context;
 use hyper_util::client::legacy::connect::Connect;
 
 use docker::apis::{DockerApi, DockerApiClient};
-use docker::models::ContainerInspectResponseState;
-use edgelet_core::{Module, ModuleOperation, ModuleRuntimeState, ModuleStatus};
+use docker::models::{ContainerInspectResponseState, Health};
+use edgelet_core::{
+    HealthStatus, Module, ModuleHealth, ModuleOperation, ModuleRuntimeState, ModuleStatus,
+};
 use edgelet_settings::DockerConfig;
 use edgelet_utils::ensure_not_empty;
 
@@ -52,18 +54,46 @@ fn status_from_exit_code(exit_code: Option<i64>) -> Option<ModuleStatus> {
     })
 }
 
+fn module_health(health: Health) -> Option<ModuleHealth> {
+    // Docker reports "none" for containers without a health check.
+    let status = match health.status.as_deref()? {
+        "starting" => HealthStatus::Starting,
+        "healthy" => HealthStatus::Healthy,
+        "unhealthy" => HealthStatus::Unhealthy,
+        _ => return None,
+    };
+
+    let failing_streak = health
+        .failing_streak
+        .and_then(|streak| u32::try_from(streak).ok())
+        .unwrap_or_default();
+
+    let last_output = health
+        .log
+        .and_then(|log| log.into_iter().last())
+        .and_then(|result| result.output)
+        .map(|output| output.trim_end().to_owned());
+
+    Some(ModuleHealth::new(status, failing_streak, last_output))
+}
+
 pub fn runtime_state(
     id: Option<String>,
     response_state: Option<ContainerInspectResponseState>,
 ) -> ModuleRuntimeState {
     response_state.map_or_else(ModuleRuntimeState::default, |state| {
+        let health = state.health.and_then(module_health);
+
         let status = state
             .status
             .and_then(|status| match &*status {
                 "created" | "paused" | "restarting" => Some(ModuleStatus::Stopped),
                 "removing" | "exited" => status_from_exit_code(state.exit_code),
                 "dead" => Some(ModuleStatus::Dead),
-                "running" => Some(ModuleStatus::Running),
+                "running" => match health.as_ref().map(ModuleHealth::status) {
+                    Some(HealthStatus::Unhealthy) => Some(ModuleStatus::Unhealthy),
+                    _ => Some(ModuleStatus::Running),
+                },
                 _ => None,
             })
             .unwrap_or_default();
@@ -84,6 +114,7 @@ pub fn runtime_state(
             )
             .with_image_id(id)
             .with_pid(state.pid)
+            .with_health(health)
     })
 }
 
@@ -117,3 +148,78 @@ where
         Ok(runtime_state(inspect.id, inspect.state))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use docker::models::HealthcheckResult;
+
+    use super::*;
+
+    fn running(health: Option<Health>) -> ContainerInspectResponseState {
+        ContainerInspectResponseState {
+            status: Some("running".to_owned()),
+            pid: Some(1234),
+            health,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn runtime_state_without_health_check() {
+        let state = runtime_state(None, Some(running(None)));
+        assert_eq!(state.status(), &ModuleStatus::Running);
+        assert_eq!(state.health(), None);
+
+        let health = Health {
+            status: Some("none".to_owned()),
+            ..Default::default()
+        };
+        let state = runtime_state(None, Some(running(Some(health))));
+        assert_eq!(state.status(), &ModuleStatus::Running);
+        assert_eq!(state.health(), None);
+    }
+
+    #[test]
+    fn runtime_state_unhealthy() {
+        let health = Health {
+            status: Some("unhealthy".to_owned()),
+            failing_streak: Some(3),
+            log: Some(vec![
+                HealthcheckResult {
+                    exit_code: Some(0),
+                    output: Some("ok\n".to_owned()),
+                    ..Default::default()
+                },
+                HealthcheckResult {
+                    exit_code: Some(1),
+                    output: Some("connection refused\n".to_owned()),
+                    ..Default::default()
+                },
+            ]),
+        };
+
+        let state = runtime_state(None, Some(running(Some(health))));
+        assert_eq!(state.status(), &ModuleStatus::Unhealthy);
+
+        let health = state.health().unwrap();
+        assert_eq!(health.status(), HealthStatus::Unhealthy);
+        assert_eq!(health.failing_streak(), 3);
+        assert_eq!(health.last_output(), Some("connection refused"));
+    }
+
+    #[test]
+    fn runtime_state_healthy() {
+        let health = Health {
+            status: Some("healthy".to_owned()),
+            failing_streak: Some(0),
+            log: None,
+        };
+
+        let state = runtime_state(None, Some(running(Some(health))));
+        assert_eq!(state.status(), &ModuleStatus::Running);
+        assert_eq!(
+            state.health().map(ModuleHealth::status),
+            Some(HealthStatus::Healthy)
+        );
+    }
+}
@@ -0,0 +1,949 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! Evaluation of the `[container_policy]` rules against module create options.
+
+use docker::models::ContainerCreateBody;
+use edgelet_core::{PolicyRule, PolicyViolation, PolicyViolations};
+use edgelet_settings::base::policy::{AllowDeny, ContainerPolicy, PolicyRules};
+
+/// The registry of images that do not name one.
+const DEFAULT_REGISTRY: &str = "docker.io";
+
+/// The profile that is in effect when create options do not select one.
+const DEFAULT_PROFILE: &str = "default";
+
+/// The prefix of a `NetworkMode`, `PidMode` or `IpcMode` that joins the namespace of another
+/// container, like `container:<id>`.
+const CONTAINER_NAMESPACE_PREFIX: &str = "container:";
+
+/// The `CapAdd` entry that adds every capability.
+const ALL_CAPABILITIES: &str = "ALL";
+
+/// The name that stands for a seccomp profile given inline as JSON.
+const INLINE_PROFILE: &str = "inline";
+
+/// Checks the image and create options of module `name` against `policy`.
+pub fn check_container_policy(
+    policy: &ContainerPolicy,
+    name: &str,
+    image: &str,
+    create_options: &ContainerCreateBody,
+) -> Result<(), PolicyViolations> {
+    if policy.is_default() {
+        return Ok(());
+    }
+
+    let violations = violations(&policy.rules_for(name), name, image, create_options);
+    if violations.is_empty() {
+        return Ok(());
+    }
+
+    for violation in &violations {
+        log::warn!("Container policy: module {name}: {violation}");
+    }
+
+    Err(PolicyViolations::new(name.to_owned(), violations))
+}
+
+fn violations(
+    rules: &PolicyRules,
+    name: &str,
+    image: &str,
+    create_options: &ContainerCreateBody,
+) -> Vec<PolicyViolation> {
+    let mut violations = vec![];
+
+    let host_config = create_options.host_config.clone().unwrap_or_default();
+    let other_str = |key: &str| {
+        host_config
+            .other_properties
+            .get(key)
+            .and_then(serde_json::Value::as_str)
+            .map(ToOwned::to_owned)
+    };
+
+    if host_config.privileged == Some(true) && rules.privileged == Some(false) {
+        violations.push(PolicyViolation::new(
+            name.to_owned(),
+            PolicyRule::Privileged,
+            None,
+        ));
+    }
+
+    // A module that joins the namespace of another container shares whatever that container
+    // uses, which may be the host's, so it is held to the same rule.
+    let namespaces = [
+        (PolicyRule::HostNetwork, rules.host_network, "NetworkMode"),
+        (PolicyRule::HostPid, rules.host_pid, "PidMode"),
+        (PolicyRule::HostIpc, rules.host_ipc, "IpcMode"),
+        (PolicyRule::HostUserns, rules.host_userns, "UsernsMode"),
+        (
+            PolicyRule::HostCgroupns,
+            rules.host_cgroupns,
+            "CgroupnsMode",
+        ),
+    ];
+    for (rule, allowed, key) in namespaces {
+        if allowed != Some(false) {
+            continue;
+        }
+
+        match other_str(key) {
+            Some(mode) if mode == "host" => {
+                violations.push(PolicyViolation::new(name.to_owned(), rule, None));
+            }
+            Some(mode) if mode.starts_with(CONTAINER_NAMESPACE_PREFIX) => {
+                violations.push(PolicyViolation::new(name.to_owned(), rule, Some(mode)));
+            }
+            _ => {}
+        }
+    }
+
+    // Volumes from another container bring along its binds, which are checked against that
+    // container's rules rather than this module's, so they are held to the bind mount rule.
+    if rules.bind_mounts.is_some() {
+        for container in volumes_from(&host_config) {
+            violations.push(PolicyViolation::new(
+                name.to_owned(),
+                PolicyRule::VolumesFrom,
+                Some(container),
+            ));
+        }
+    }
+
+    let mut check =
+        |rule, list: Option<&AllowDeny>, value: String, allows: fn(&AllowDeny, &str) -> bool| {
+            if let Some(list) = list
+                && !allows(list, &value)
+            {
+                violations.push(PolicyViolation::new(name.to_owned(), rule, Some(value)));
+            }
+        };
+
+    for path in bind_mount_sources(&host_config) {
+        check(
+            PolicyRule::BindMount,
+            rules.bind_mounts.as_ref(),
+            path,
+            path_allowed,
+        );
+    }
+
+    for path in device_paths(&host_config) {
+        check(
+            PolicyRule::Device,
+            rules.devices.as_ref(),
+            path,
+            path_allowed,
+        );
+    }
+
+    for rule in device_cgroup_rules(&host_config) {
+        check(
+            PolicyRule::DeviceCgroupRule,
+            rules.device_cgroup_rules.as_ref(),
+            rule,
+            |list, rule| list.allows(rule, |entry, rule| normalize_whitespace(entry) == rule),
+        );
+    }
+
+    for capability in host_config.cap_add.iter().flatten() {
+        check(
+            PolicyRule::Capability,
+            rules.capabilities.as_ref(),
+            normalize_capability(capability),
+            capability_allowed,
+        );
+    }
+
+    let security_opt: Vec<String> = host_config
+        .other_properties
+        .get("SecurityOpt")
+        .and_then(|opts| serde_json::from_value(opts.clone()).ok())
+        .unwrap_or_default();
+
+    for profile in security_profiles(&security_opt, "seccomp") {
+        check(
+            PolicyRule::SeccompProfile,
+            rules.seccomp_profiles.as_ref(),
+            seccomp_profile_name(profile),
+            seccomp_profile_allowed,
+        );
+    }
+    for profile in security_profiles(&security_opt, "apparmor") {
+        check(
+            PolicyRule::AppArmorProfile,
+            rules.apparmor_profiles.as_ref(),
+            profile,
+            |list, value| list.allows(value, |entry, value| entry == value),
+        );
+    }
+
+    check(
+        PolicyRule::Registry,
+        rules.registries.as_ref(),
+        registry(image).to_owned(),
+        |list, value| list.allows(value, str::eq_ignore_ascii_case),
+    );
+
+    violations
+}
+
+/// Host paths of bind mounts, and of local volumes that bind a host directory. Binds whose
+/// source is not an absolute path are named volumes.
+fn bind_mount_sources(host_config: &docker::models::HostConfig) -> Vec<String> {
+    let binds = host_config
+        .binds
+        .iter()
+        .flatten()
+        .filter_map(|bind| bind.split(':').next())
+        .filter(|source| source.starts_with('/'))
+        .map(ToOwned::to_owned);
+
+    let mounts = host_config
+        .mounts
+        .iter()
+        .flatten()
+        .filter(|mount| mount.r#type.as_deref().is_none_or(|t| t == "bind"))
+        .filter_map(|mount| mount.source.clone());
+
+    let volumes = host_config
+        .mounts
+        .iter()
+        .flatten()
+        .filter(|mount| mount.r#type.as_deref() == Some("volume"))
+        .filter_map(|mount| local_volume_bind(mount.other_properties.get("VolumeOptions")?));
+
+    binds.chain(mounts).chain(volumes).collect()
+}
+
+/// The host directory that a local volume binds, like one created with
+/// `--opt type=none --opt o=bind --opt device=/host/path`.
+fn local_volume_bind(volume_options: &serde_json::Value) -> Option<String> {
+    let driver_config = volume_options.get("DriverConfig")?;
+
+    let driver = driver_config
+        .get("Name")
+        .and_then(serde_json::Value::as_str)
+        .unwrap_or("local");
+    if !driver.is_empty() && driver != "local" {
+        return None;
+    }
+
+    let options = driver_config.get("Options")?;
+    let binds = options
+        .get("o")?
+        .as_str()?
+        .split(',')
+        .any(|option| matches!(option.trim(), "bind" | "rbind"));
+    if !binds {
+        return None;
+    }
+
+    options.get("device")?.as_str().map(ToOwned::to_owned)
+}
+
+/// The containers named by `VolumesFrom`, like `edgeHub` or `edgeHub:ro`.
+fn volumes_from(host_config: &docker::models::HostConfig) -> Vec<String> {
+    host_config
+        .other_properties
+        .get("VolumesFrom")
+        .and_then(serde_json::Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(serde_json::Value::as_str)
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+fn device_paths(host_config: &docker::models::HostConfig) -> Vec<String> {
+    host_config
+        .other_properties
+        .get("Devices")
+        .and_then(serde_json::Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|device| device.get("PathOnHost")?.as_str())
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+fn device_cgroup_rules(host_config: &docker::models::HostConfig) -> Vec<String> {
+    host_config
+        .other_properties
+        .get("DeviceCgroupRules")
+        .and_then(serde_json::Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(serde_json::Value::as_str)
+        .map(normalize_whitespace)
+        .collect()
+}
+
+fn normalize_whitespace(value: &str) -> String {
+    value.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The profiles selected by `SecurityOpt` entries such as `seccomp=unconfined`, or `default` if
+/// there are none. Docker also accepts `:` as the separator.
+fn security_profiles(security_opt: &[String], kind: &str) -> Vec<String> {
+    let profiles: Vec<_> = security_opt
+        .iter()
+        .filter_map(|opt| {
+            let (key, value) = opt.split_once(['=', ':'])?;
+            (key == kind).then(|| value.to_owned())
+        })
+        .collect();
+
+    if profiles.is_empty() {
+        vec![DEFAULT_PROFILE.to_owned()]
+    } else {
+        profiles
+    }
+}
+
+/// The name that seccomp profile `profile` is checked by. A profile given inline as JSON, which
+/// is how the Docker CLI passes a profile file, is checked as `inline`.
+fn seccomp_profile_name(profile: String) -> String {
+    if profile.trim_start().starts_with('{') {
+        INLINE_PROFILE.to_owned()
+    } else {
+        profile
+    }
+}
+
+/// Whether `list` allows seccomp profile `profile`, which is named. An inline profile can hold
+/// any rules, so it is only allowed when the allow list names `inline` itself.
+fn seccomp_profile_allowed(list: &AllowDeny, profile: &str) -> bool {
+    if profile == INLINE_PROFILE
+        && !list
+            .allow
+            .iter()
+            .flatten()
+            .any(|entry| entry == INLINE_PROFILE)
+    {
+        return false;
+    }
+
+    list.allows(profile, |entry, profile| entry == profile)
+}
+
+/// The registry part of an image reference, following Docker's rules for when the first path
+/// component is a registry host.
+fn registry(image: &str) -> &str {
+    match image.split_once('/') {
+        Some((host, _)) if host.contains(['.', ':']) || host == "localhost" => host,
+        _ => DEFAULT_REGISTRY,
+    }
+}
+
+/// Whether `list` allows host path `path`. Paths are compared after resolving `.`, `..` and
+/// symlinks, and a path that is relative, leaves `/` or is a dangling symlink is never allowed.
+fn path_allowed(list: &AllowDeny, path: &str) -> bool {
+    host_path(path).is_some_and(|path| list.allows(&path, path_matches))
+}
+
+/// Whether `path`, which is resolved, is `entry` or below it.
+fn path_matches(entry: &str, path: &str) -> bool {
+    let Some(entry) = host_path(entry).or_else(|| clean_path(entry)) else {
+        return false;
+    };
+
+    entry == "/"
+        || path == entry
+        || path
+            .strip_prefix(&entry)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Resolves `.`, `..`, repeated separators and symlinks in absolute path `path`. The runtime
+/// creates a missing bind source, so symlinks are resolved in the part of the path that exists
+/// and the rest is appended as is. `None` if the path is relative, `..` goes above `/`, or the
+/// path goes through a symlink whose target does not exist.
+fn host_path(path: &str) -> Option<String> {
+    let path = clean_path(path)?;
+
+    let mut existing = std::path::Path::new(&path);
+    let mut missing = vec![];
+    let resolved = loop {
+        match std::fs::canonicalize(existing) {
+            Ok(resolved) => break resolved,
+            // A dangling symlink would be followed when the runtime creates the missing path.
+            Err(_) if existing.symlink_metadata().is_ok() => return None,
+            Err(_) => {
+                missing.push(existing.file_name()?);
+                existing = existing.parent()?;
+            }
+        }
+    };
+
+    let resolved = missing
+        .into_iter()
+        .rev()
+        .fold(resolved, |resolved, component| resolved.join(component));
+    resolved.to_str().map(ToOwned::to_owned)
+}
+
+/// Resolves `.`, `..` and repeated separators in absolute path `path` without touching the
+/// filesystem. `None` if the path is relative or `..` goes above `/`.
+fn clean_path(path: &str) -> Option<String> {
+    let path = path.strip_prefix('/')?;
+
+    let mut components = vec![];
+    for component in path.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                components.pop()?;
+            }
+            component => components.push(component),
+        }
+    }
+
+    Some(format!("/{}", components.join("/")))
+}
+
+fn normalize_capability(capability: &str) -> String {
+    let capability = capability.to_ascii_uppercase();
+
+    match capability.strip_prefix("CAP_") {
+        Some(capability) => capability.to_owned(),
+        None => capability,
+    }
+}
+
+/// Whether `list` allows `capability`, which is normalized. `ALL` adds every capability, so it
+/// is only allowed when no capability is denied and the allow list covers `ALL` itself.
+fn capability_allowed(list: &AllowDeny, capability: &str) -> bool {
+    if capability == ALL_CAPABILITIES && !list.deny.is_empty() {
+        return false;
+    }
+
+    list.allows(capability, capability_matches)
+}
+
+fn capability_matches(entry: &str, capability: &str) -> bool {
+    let entry = normalize_capability(entry);
+
+    entry == ALL_CAPABILITIES || entry == capability
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use docker::models::HostConfig;
+
+    use super::*;
+
+    fn allow(values: &[&str]) -> AllowDeny {
+        AllowDeny {
+            allow: Some(values.iter().map(|&v| v.to_owned()).collect()),
+            deny: vec![],
+        }
+    }
+
+    fn deny(values: &[&str]) -> AllowDeny {
+        AllowDeny {
+            allow: None,
+            deny: values.iter().map(|&v| v.to_owned()).collect(),
+        }
+    }
+
+    fn create_options(host_config: HostConfig) -> ContainerCreateBody {
+        ContainerCreateBody {
+            host_config: Some(host_config),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn no_policy_allows_everything() {
+        let create_options = create_options(HostConfig {
+            privileged: Some(true),
+            binds: Some(vec!["/:/host".to_owned()]),
+            ..Default::default()
+        });
+
+        check_container_policy(
+            &ContainerPolicy::default(),
+            "m",
+            "example.com/m:1.0",
+            &create_options,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn violations_are_reported() {
+        let rules = PolicyRules {
+            privileged: Some(false),
+            host_network: Some(false),
+            bind_mounts: Some(allow(&["/var/lib/data"])),
+            devices: Some(allow(&["/dev/ttyUSB0"])),
+            capabilities: Some(allow(&["NET_ADMIN"])),
+            seccomp_profiles: Some(deny(&["unconfined"])),
+            registries: Some(allow(&["mcr.microsoft.com"])),
+            ..Default::default()
+        };
+        let policy = ContainerPolicy::new(rules, BTreeMap::default());
+
+        let mut host_config = HostConfig {
+            privileged: Some(true),
+            binds: Some(vec![
+                "/var/lib/data/m:/data".to_owned(),
+                "/var/lib/database:/db".to_owned(),
+                "volume:/volume".to_owned(),
+            ]),
+            cap_add: Some(vec!["CAP_NET_ADMIN".to_owned(), "SYS_ADMIN".to_owned()]),
+            ..Default::default()
+        };
+        host_config.other_properties.insert(
+            "Devices".to_owned(),
+            serde_json::json!([{ "PathOnHost": "/dev/ttyUSB0" }, { "PathOnHost": "/dev/mem" }]),
+        );
+        host_config.other_properties.insert(
+            "SecurityOpt".to_owned(),
+            serde_json::json!(["seccomp=unconfined"]),
+        );
+
+        let err = check_container_policy(
+            &policy,
+            "m",
+            "example.com/m:1.0",
+            &create_options(host_config),
+        )
+        .unwrap_err();
+
+        assert_eq!(err.module(), "m");
+        let violations: Vec<_> = err
+            .violations()
+            .iter()
+            .map(|violation| (violation.rule(), violation.value()))
+            .collect();
+        assert_eq!(
+            violations,
+            [
+                (PolicyRule::Privileged, None),
+                (PolicyRule::BindMount, Some("/var/lib/database")),
+                (PolicyRule::Device, Some("/dev/mem")),
+                (PolicyRule::Capability, Some("SYS_ADMIN")),
+                (PolicyRule::SeccompProfile, Some("unconfined")),
+                (PolicyRule::Registry, Some("example.com")),
+            ]
+        );
+        assert!(err.to_string().starts_with(
+            "module \"m\" violates the container policy: privileged mode is not allowed; bind mount \"/var/lib/database\" is not allowed;"
+        ));
+    }
+
+    #[test]
+    fn exceptions_replace_rules() {
+        let rules = PolicyRules {
+            host_network: Some(false),
+            ..Default::default()
+        };
+        let exception = PolicyRules {
+            host_network: Some(true),
+            ..Default::default()
+        };
+        let policy = ContainerPolicy::new(
+            rules,
+            [("edgeHub".to_owned(), exception)].into_iter().collect(),
+        );
+
+        let mut host_config = HostConfig::default();
+        host_config
+            .other_properties
+            .insert("NetworkMode".to_owned(), serde_json::json!("host"));
+        let create_options = create_options(host_config);
+
+        check_container_policy(&policy, "edgeHub", "mcr.microsoft.com/hub", &create_options)
+            .unwrap();
+        check_container_policy(&policy, "m", "mcr.microsoft.com/m", &create_options).unwrap_err();
+    }
+
+    #[test]
+    fn container_namespaces() {
+        let rules = PolicyRules {
+            host_network: Some(false),
+            host_pid: Some(false),
+            host_userns: Some(false),
+            host_cgroupns: Some(false),
+            ..Default::default()
+        };
+        let policy = ContainerPolicy::new(rules, BTreeMap::default());
+
+        let host_config: HostConfig = serde_json::from_value(serde_json::json!({
+            "NetworkMode": "container:edgeHub",
+            "PidMode": "host",
+            "IpcMode": "container:edgeHub",
+            "UsernsMode": "host",
+            "CgroupnsMode": "host",
+        }))
+        .unwrap();
+
+        let err = check_container_policy(
+            &policy,
+            "m",
+            "example.com/m:1.0",
+            &create_options(host_config),
+        )
+        .unwrap_err();
+
+        let violations: Vec<_> = err
+            .violations()
+            .iter()
+            .map(|violation| (violation.rule(), violation.value()))
+            .collect();
+        assert_eq!(
+            violations,
+            [
+                (PolicyRule::HostNetwork, Some("container:edgeHub")),
+                (PolicyRule::HostPid, None),
+                (PolicyRule::HostUserns, None),
+                (PolicyRule::HostCgroupns, None),
+            ]
+        );
+
+        let host_config: HostConfig = serde_json::from_value(serde_json::json!({
+            "NetworkMode": "azure-iot-edge",
+            "UsernsMode": "",
+            "CgroupnsMode": "private",
+        }))
+        .unwrap();
+        check_container_policy(
+            &policy,
+            "m",
+            "example.com/m:1.0",
+            &create_options(host_config),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn paths_are_cleaned() {
+        let rules = PolicyRules {
+            bind_mounts: Some(AllowDeny {
+                allow: Some(vec!["/var/lib/data".to_owned(), "/var".to_owned()]),
+                deny: vec!["/var/run/docker.sock".to_owned()],
+            }),
+            devices: Some(deny(&["/etc"])),
+            ..Default::default()
+        };
+        let policy = ContainerPolicy::new(rules, BTreeMap::default());
+
+        let host_config: HostConfig = serde_json::from_value(serde_json::json!({
+            "Binds": [
+                "/var/lib/data/./m:/data",
+                "/var/lib/data/../../../etc:/etc",
+                "/var/lib/data/../../run//docker.sock:/docker.sock",
+                "/../var/lib/data:/root",
+            ],
+            "Mounts": [{ "Type": "bind", "Source": "var/lib/data", "Target": "/data" }],
+            "Devices": [{ "PathOnHost": "/dev/../etc/shadow" }],
+        }))
+        .unwrap();
+
+        let err = check_container_policy(
+            &policy,
+            "m",
+            "example.com/m:1.0",
+            &create_options(host_config),
+        )
+        .unwrap_err();
+
+        let violations: Vec<_> = err
+            .violations()
+            .iter()
+            .map(|violation| (violation.rule(), violation.value()))
+            .collect();
+        assert_eq!(
+            violations,
+            [
+                (PolicyRule::BindMount, Some("/var/lib/data/../../../etc")),
+                (
+                    PolicyRule::BindMount,
+                    Some("/var/lib/data/../../run//docker.sock")
+                ),
+                (PolicyRule::BindMount, Some("/../var/lib/data")),
+                (PolicyRule::BindMount, Some("var/lib/data")),
+                (PolicyRule::Device, Some("/dev/../etc/shadow")),
+            ]
+        );
+
+        assert_eq!(
+            clean_path("/var//lib/./data/"),
+            Some("/var/lib/data".to_owned())
+        );
+        assert_eq!(clean_path("/var/.."), Some("/".to_owned()));
+        assert_eq!(clean_path("/.."), None);
+        assert_eq!(clean_path("data"), None);
+    }
+
+    #[test]
+    fn symlinks_are_resolved() {
+        let dir = std::env::temp_dir().join(format!("edgelet-policy-{}", std::process::id()));
+        let data = dir.join("data");
+        std::fs::create_dir_all(&data).unwrap();
+        std::fs::create_dir_all(dir.join("secret")).unwrap();
+        std::os::unix::fs::symlink(dir.join("secret"), data.join("escape")).unwrap();
+        std::os::unix::fs::symlink(dir.join("missing"), data.join("dangling")).unwrap();
+        let dir = dir.to_str().unwrap();
+
+        let rules = PolicyRules {
+            bind_mounts: Some(allow(&[&format!("{dir}/data")])),
+            ..Default::default()
+        };
+        let policy = ContainerPolicy::new(rules, BTreeMap::default());
+
+        let host_config: HostConfig = serde_json::from_value(serde_json::json!({
+            "Binds": [
+                format!("{dir}/data/m:/data"),
+                format!("{dir}/data/escape:/escape"),
+                format!("{dir}/data/escape/m:/escape"),
+                format!("{dir}/data/dangling/m:/dangling"),
+            ],
+        }))
+        .unwrap();
+
+        let err = check_container_policy(
+            &policy,
+            "m",
+            "example.com/m:1.0",
+            &create_options(host_config),
+        )
+        .unwrap_err();
+
+        let violations: Vec<_> = err
+            .violations()
+            .iter()
+            .map(|violation| violation.value().unwrap().to_owned())
+            .collect();
+        assert_eq!(
+            violations,
+            [
+                format!("{dir}/data/escape"),
+                format!("{dir}/data/escape/m"),
+                format!("{dir}/data/dangling/m"),
+            ]
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn volumes_from() {
+        let rules = PolicyRules {
+            bind_mounts: Some(allow(&["/var/lib/data"])),
+            ..Default::default()
+        };
+        let policy = ContainerPolicy::new(rules, BTreeMap::default());
+
+        let host_config: HostConfig = serde_json::from_value(serde_json::json!({
+            "VolumesFrom": ["edgeHub:ro"],
+        }))
+        .unwrap();
+        let create_options = create_options(host_config);
+
+        let err =
+            check_container_policy(&policy, "m", "example.com/m:1.0", &create_options).unwrap_err();
+        let violations: Vec<_> = err
+            .violations()
+            .iter()
+            .map(|violation| (violation.rule(), violation.value()))
+            .collect();
+        assert_eq!(violations, [(PolicyRule::VolumesFrom, Some("edgeHub:ro"))]);
+
+        check_container_policy(
+            &ContainerPolicy::new(
+                PolicyRules {
+                    privileged: Some(false),
+                    ..Default::default()
+                },
+                BTreeMap::default(),
+            ),
+            "m",
+            "example.com/m:1.0",
+            &create_options,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn bind_volumes_and_device_cgroup_rules() {
+        let rules = PolicyRules {
+            bind_mounts: Some(allow(&["/var/lib/data"])),
+            device_cgroup_rules: Some(allow(&["c 188:* rmw"])),
+            ..Default::default()
+        };
+        let policy = ContainerPolicy::new(rules, BTreeMap::default());
+
+        let volume = |driver: &str, options: serde_json::Value| {
+            serde_json::json!({
+                "Type": "volume",
+                "Source": "data",
+                "Target": "/data",
+                "VolumeOptions": { "DriverConfig": { "Name": driver, "Options": options } },
+            })
+        };
+
+        let host_config: HostConfig = serde_json::from_value(serde_json::json!({
+            "Mounts": [
+                volume("local", serde_json::json!({ "type": "none", "o": "bind", "device": "/etc" })),
+                volume("", serde_json::json!({ "o": "ro,rbind", "device": "/var/lib/data/m" })),
+                volume("local", serde_json::json!({ "type": "tmpfs", "o": "size=100m", "device": "tmpfs" })),
+            ],
+            "DeviceCgroupRules": ["c  188:*  rmw", "a *:* rwm"],
+        }))
+        .unwrap();
+
+        let err = check_container_policy(
+            &policy,
+            "m",
+            "example.com/m:1.0",
+            &create_options(host_config),
+        )
+        .unwrap_err();
+
+        let violations: Vec<_> = err
+            .violations()
+            .iter()
+            .map(|violation| (violation.rule(), violation.value()))
+            .collect();
+        assert_eq!(
+            violations,
+            [
+                (PolicyRule::BindMount, Some("/etc")),
+                (PolicyRule::DeviceCgroupRule, Some("a *:* rwm")),
+            ]
+        );
+    }
+
+    #[test]
+    fn all_capabilities() {
+        let check = |capabilities: AllowDeny, cap_add: &[&str]| {
+            let rules = PolicyRules {
+                capabilities: Some(capabilities),
+                ..Default::default()
+            };
+            let policy = ContainerPolicy::new(rules, BTreeMap::default());
+            let host_config = HostConfig {
+                cap_add: Some(cap_add.iter().map(|&c| c.to_owned()).collect()),
+                ..Default::default()
+            };
+
+            check_container_policy(
+                &policy,
+                "m",
+                "example.com/m:1.0",
+                &create_options(host_config),
+            )
+            .map_err(|err| {
+                err.violations()
+                    .iter()
+                    .map(|violation| violation.value().unwrap().to_owned())
+                    .collect::<Vec<_>>()
+            })
+        };
+
+        assert_eq!(
+            check(deny(&["SYS_ADMIN"]), &["ALL"]),
+            Err(vec!["ALL".to_owned()])
+        );
+        assert_eq!(
+            check(deny(&["SYS_ADMIN"]), &["cap_all", "NET_ADMIN"]),
+            Err(vec!["ALL".to_owned()])
+        );
+        assert_eq!(
+            check(allow(&["NET_ADMIN"]), &["ALL"]),
+            Err(vec!["ALL".to_owned()])
+        );
+        assert_eq!(check(allow(&["ALL"]), &["ALL", "SYS_ADMIN"]), Ok(()));
+        assert_eq!(
+            check(deny(&["ALL"]), &["NET_ADMIN"]),
+            Err(vec!["NET_ADMIN".to_owned()])
+        );
+    }
+
+    #[test]
+    fn image_registry() {
+        assert_eq!(registry("ubuntu"), "docker.io");
+        assert_eq!(registry("library/ubuntu:22.04"), "docker.io");
+        assert_eq!(
+            registry("mcr.microsoft.com/azureiotedge-agent:1.5"),
+            "mcr.microsoft.com"
+        );
+        assert_eq!(registry("localhost:5000/m"), "localhost:5000");
+        assert_eq!(registry("localhost/m"), "localhost");
+    }
+
+    #[test]
+    fn every_security_profile_is_checked() {
+        let opts = ["label=disable".to_owned(), "apparmor:unconfined".to_owned()];
+
+        assert_eq!(security_profiles(&opts, "apparmor"), ["unconfined"]);
+        assert_eq!(security_profiles(&opts, "seccomp"), ["default"]);
+
+        let check = |rules: PolicyRules, security_opt: &[&str]| {
+            let policy = ContainerPolicy::new(rules, BTreeMap::default());
+            let mut host_config = HostConfig::default();
+            host_config
+                .other_properties
+                .insert("SecurityOpt".to_owned(), serde_json::json!(security_opt));
+
+            check_container_policy(
+                &policy,
+                "m",
+                "example.com/m:1.0",
+                &create_options(host_config),
+            )
+            .map_err(|err| {
+                err.violations()
+                    .iter()
+                    .map(|violation| (violation.rule(), violation.value().unwrap().to_owned()))
+                    .collect::<Vec<_>>()
+            })
+        };
+        let seccomp = |list| PolicyRules {
+            seccomp_profiles: Some(list),
+            ..Default::default()
+        };
+
+        // Every entry is checked, not just the first.
+        let apparmor = PolicyRules {
+            apparmor_profiles: Some(deny(&["unconfined"])),
+            ..Default::default()
+        };
+        assert_eq!(
+            check(
+                apparmor,
+                &["apparmor=docker-default", "apparmor=unconfined"]
+            ),
+            Err(vec![(PolicyRule::AppArmorProfile, "unconfined".to_owned())])
+        );
+        assert_eq!(
+            check(
+                seccomp(deny(&["unconfined"])),
+                &["seccomp=default", "seccomp:unconfined"]
+            ),
+            Err(vec![(PolicyRule::SeccompProfile, "unconfined".to_owned())])
+        );
+
+        // Inline profiles are only allowed when the allow list names them.
+        let inline = r#"seccomp={"defaultAction":"SCMP_ACT_ALLOW"}"#;
+        assert_eq!(
+            check(seccomp(deny(&["unconfined"])), &[inline]),
+            Err(vec![(PolicyRule::SeccompProfile, "inline".to_owned())])
+        );
+        assert_eq!(
+            check(seccomp(allow(&["default"])), &[inline]),
+            Err(vec![(PolicyRule::SeccompProfile, "inline".to_owned())])
+        );
+        assert_eq!(
+            check(seccomp(allow(&["default", "inline"])), &[inline]),
+            Ok(())
+        );
+        assert_eq!(check(PolicyRules::default(), &[inline]), Ok(()));
+    }
+}
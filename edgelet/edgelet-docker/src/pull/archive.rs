@@ -0,0 +1,229 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! Streaming of a tar archive, in the format that `/images/load` reads, into a request body.
+
+use std::convert::Infallible;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use hyper::body::{Bytes, Frame};
+use tokio::io::AsyncReadExt;
+use tokio::sync::mpsc;
+
+const BLOCK_SIZE: usize = 512;
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// The largest size that fits the octal size field of a tar header.
+const MAX_OCTAL_SIZE: u64 = 0o777_7777_7777;
+
+pub(crate) enum Content {
+    Bytes(Bytes),
+    File(PathBuf),
+}
+
+pub(crate) struct Entry {
+    pub(crate) name: String,
+    pub(crate) content: Content,
+}
+
+/// A request body fed by the chunks sent to a channel. The body ends when the sender is dropped.
+pub(crate) struct ChannelBody(pub(crate) mpsc::Receiver<Bytes>);
+
+impl hyper::body::Body for ChannelBody {
+    type Data = Bytes;
+    type Error = Infallible;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Bytes>, Infallible>>> {
+        self.get_mut()
+            .0
+            .poll_recv(cx)
+            .map(|chunk| chunk.map(|chunk| Ok(Frame::data(chunk))))
+    }
+}
+
+/// Writes a tar archive of `entries` to `sender`. Returns `false` if the receiver went away
+/// before the archive was complete.
+pub(crate) async fn write(
+    entries: Vec<Entry>,
+    sender: mpsc::Sender<Bytes>,
+) -> anyhow::Result<bool> {
+    for entry in entries {
+        let size = match &entry.content {
+            Content::Bytes(bytes) => bytes.len() as u64,
+            Content::File(path) => tokio::fs::metadata(path).await?.len(),
+        };
+
+        if sender
+            .send(Bytes::copy_from_slice(&header(&entry.name, size)?))
+            .await
+            .is_err()
+        {
+            return Ok(false);
+        }
+
+        match entry.content {
+            Content::Bytes(bytes) => {
+                if sender.send(bytes).await.is_err() {
+                    return Ok(false);
+                }
+            }
+
+            Content::File(path) => {
+                if !send_file(&path, size, &sender).await? {
+                    return Ok(false);
+                }
+            }
+        }
+
+        let padding = padding(size);
+        if padding > 0 && sender.send(Bytes::from(vec![0; padding])).await.is_err() {
+            return Ok(false);
+        }
+    }
+
+    // An archive ends with two empty blocks.
+    Ok(sender
+        .send(Bytes::from(vec![0; 2 * BLOCK_SIZE]))
+        .await
+        .is_ok())
+}
+
+/// Sends the first `size` bytes of the file at `path` to `sender`. Returns `false` if the
+/// receiver went away before all of them were sent.
+pub(crate) async fn send_file(
+    path: &Path,
+    size: u64,
+    sender: &mpsc::Sender<Bytes>,
+) -> anyhow::Result<bool> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut remaining = size;
+
+    while remaining > 0 {
+        let mut chunk = vec![0; CHUNK_SIZE];
+        let read = file.read(&mut chunk).await?;
+        anyhow::ensure!(read > 0, "{} shrank while it was read", path.display());
+
+        chunk.truncate(read.min(usize::try_from(remaining).unwrap_or(usize::MAX)));
+        remaining -= chunk.len() as u64;
+
+        if sender.send(Bytes::from(chunk)).await.is_err() {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+/// The ustar header of a regular file.
+fn header(name: &str, size: u64) -> anyhow::Result<[u8; BLOCK_SIZE]> {
+    anyhow::ensure!(name.len() < 100, "archive entry name {name} is too long");
+
+    let mut header = [0; BLOCK_SIZE];
+
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    header[100..108].copy_from_slice(b"0000644\0");
+    header[108..116].copy_from_slice(b"0000000\0");
+    header[116..124].copy_from_slice(b"0000000\0");
+
+    if size <= MAX_OCTAL_SIZE {
+        header[124..136].copy_from_slice(format!("{size:011o}\0").as_bytes());
+    } else {
+        // Larger sizes are written in base-256, marked by the high bit of the first byte.
+        header[124] = 0x80;
+        header[128..136].copy_from_slice(&size.to_be_bytes());
+    }
+
+    header[136..148].copy_from_slice(b"00000000000\0");
+    header[156] = b'0';
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+
+    // The checksum is computed with its own field filled with spaces.
+    header[148..156].copy_from_slice(b"        ");
+    let checksum: u32 = header.iter().map(|&b| u32::from(b)).sum();
+    header[148..156].copy_from_slice(format!("{checksum:06o}\0 ").as_bytes());
+
+    Ok(header)
+}
+
+fn padding(size: u64) -> usize {
+    let block_size = BLOCK_SIZE as u64;
+    usize::try_from((block_size - size % block_size) % block_size).expect("less than a block")
+}
+
+#[cfg(test)]
+mod tests {
+    use hyper::body::Bytes;
+    use tokio::sync::mpsc;
+
+    use super::{BLOCK_SIZE, Content, Entry, header, write};
+
+    #[test]
+    fn headers() {
+        let header = header("manifest.json", 1234).unwrap();
+
+        assert_eq!(&header[..14], b"manifest.json\0");
+        assert_eq!(&header[124..136], b"00000002322\0");
+        assert_eq!(header[156], b'0');
+        assert_eq!(&header[257..263], b"ustar\0");
+
+        let checksum = std::str::from_utf8(&header[148..154]).unwrap();
+        let checksum = u32::from_str_radix(checksum, 8).unwrap();
+        let expected: u32 = header
+            .iter()
+            .enumerate()
+            .map(|(i, &b)| {
+                if (148..156).contains(&i) {
+                    32
+                } else {
+                    u32::from(b)
+                }
+            })
+            .sum();
+        assert_eq!(expected, checksum);
+
+        let header = super::header("layer.tar", 10 << 30).unwrap();
+        assert_eq!(header[124], 0x80);
+        assert_eq!(&header[128..136], &(10_u64 << 30).to_be_bytes());
+
+        super::header(&"a".repeat(100), 0).unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn archive() {
+        let (sender, mut receiver) = mpsc::channel(16);
+
+        let entries = vec![
+            Entry {
+                name: "a.json".to_owned(),
+                content: Content::Bytes(Bytes::from_static(b"{}")),
+            },
+            Entry {
+                name: "b.tar".to_owned(),
+                content: Content::Bytes(Bytes::from(vec![1; BLOCK_SIZE])),
+            },
+        ];
+        assert!(write(entries, sender).await.unwrap());
+
+        let mut archive = vec![];
+        while let Some(chunk) = receiver.recv().await {
+            archive.extend_from_slice(&chunk);
+        }
+
+        // Header and padded content of each entry, then two empty blocks.
+        assert_eq!(archive.len(), 6 * BLOCK_SIZE);
+        assert_eq!(&archive[..6], b"a.json");
+        assert_eq!(&archive[BLOCK_SIZE..BLOCK_SIZE + 3], b"{}\0");
+        assert_eq!(&archive[2 * BLOCK_SIZE..2 * BLOCK_SIZE + 5], b"b.tar");
+        assert!(
+            archive[3 * BLOCK_SIZE..4 * BLOCK_SIZE]
+                .iter()
+                .all(|&b| b == 1)
+        );
+        assert!(archive[4 * BLOCK_SIZE..].iter().all(|&b| b == 0));
+    }
+}
@@ -0,0 +1,121 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! Offline bundles: images in a `docker save` tarball or in an OCI image layout directory, which
+//! are loaded into Docker without contacting a registry.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use http_body_util::combinators::BoxBody;
+use hyper_util::client::legacy::connect::Connect;
+
+use docker::apis::{DockerApi, DockerApiClient};
+
+use super::archive::{self, ChannelBody, Content, Entry};
+
+/// The file that marks a directory as an OCI image layout.
+const OCI_LAYOUT_FILE: &str = "oci-layout";
+
+/// Loads the images of the bundle at `path`. Returns their names, or their IDs for images
+/// without a name.
+pub(crate) async fn load<C>(client: &DockerApiClient<C>, path: &Path) -> anyhow::Result<Vec<String>>
+where
+    C: Clone + Connect + Send + Sync + 'static,
+{
+    let metadata = tokio::fs::metadata(path)
+        .await
+        .with_context(|| format!("could not read bundle {}", path.display()))?;
+
+    let (sender, receiver) = tokio::sync::mpsc::channel(8);
+
+    let write = async {
+        if metadata.is_dir() {
+            // Docker reads an OCI image layout from a tarball of the directory.
+            archive::write(layout_entries(path).await?, sender).await
+        } else {
+            archive::send_file(path, metadata.len(), &sender).await
+        }
+    };
+
+    let (written, loaded) = tokio::join!(
+        write,
+        client.image_load(true, BoxBody::new(ChannelBody(receiver))),
+    );
+    let written = written.with_context(|| format!("could not read bundle {}", path.display()))?;
+    let loaded = loaded.with_context(|| format!("could not load bundle {}", path.display()))?;
+    anyhow::ensure!(written, "container runtime stopped reading the bundle");
+    anyhow::ensure!(
+        !loaded.is_empty(),
+        "bundle {} has no images",
+        path.display()
+    );
+
+    Ok(loaded)
+}
+
+/// The files of the OCI image layout at `path`, named relative to it.
+async fn layout_entries(path: &Path) -> anyhow::Result<Vec<Entry>> {
+    anyhow::ensure!(
+        tokio::fs::try_exists(path.join(OCI_LAYOUT_FILE)).await?,
+        "{} is neither a tarball nor an OCI image layout",
+        path.display()
+    );
+
+    let mut entries = vec![];
+    let mut directories = vec![PathBuf::new()];
+
+    while let Some(directory) = directories.pop() {
+        let mut read_dir = tokio::fs::read_dir(path.join(&directory)).await?;
+
+        while let Some(dir_entry) = read_dir.next_entry().await? {
+            let name = directory.join(dir_entry.file_name());
+
+            if dir_entry.file_type().await?.is_dir() {
+                directories.push(name);
+            } else {
+                let name = name
+                    .to_str()
+                    .ok_or_else(|| anyhow::anyhow!("{} is not valid UTF-8", name.display()))?
+                    .to_owned();
+
+                entries.push(Entry {
+                    name,
+                    content: Content::File(dir_entry.path()),
+                });
+            }
+        }
+    }
+
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::layout_entries;
+
+    #[tokio::test]
+    async fn layout() {
+        let dir = std::env::temp_dir().join(format!("edgelet-bundle-{}", std::process::id()));
+        let blobs = dir.join("blobs").join("sha256");
+        std::fs::create_dir_all(&blobs).unwrap();
+        std::fs::write(dir.join("oci-layout"), r#"{"imageLayoutVersion":"1.0.0"}"#).unwrap();
+        std::fs::write(dir.join("index.json"), "{}").unwrap();
+        std::fs::write(blobs.join("a1b2"), "layer").unwrap();
+
+        let names: Vec<_> = layout_entries(&dir)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.name)
+            .collect();
+        assert_eq!(names, ["blobs/sha256/a1b2", "index.json", "oci-layout"]);
+
+        // Directories that are not OCI image layouts are refused.
+        std::fs::remove_file(dir.join("oci-layout")).unwrap();
+        layout_entries(&dir).await.unwrap_err();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
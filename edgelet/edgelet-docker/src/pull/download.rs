@@ -0,0 +1,521 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! Pulls that edged downloads itself, at a limited rate, and then loads into Docker. Docker
+//! offers no way to limit the bandwidth of its own pulls.
+//!
+//! Blobs are downloaded to a staging directory named after the manifest digest. A blob that
+//! was only partly downloaded when a pull failed is resumed with a range request on the next
+//! attempt, and the staging directory is removed once the image is loaded.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::Context;
+use http_body_util::BodyExt as _;
+use http_body_util::combinators::BoxBody;
+use hyper::body::Bytes;
+use hyper_util::client::legacy::connect::Connect;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::time::Instant;
+
+use docker::apis::{DockerApi, DockerApiClient};
+use docker::models::AuthConfig;
+use edgelet_core::{PullProgress, PullProgressBus, PullStatus};
+
+use super::archive::{self, ChannelBody, Content, Entry};
+use crate::ImageReference;
+use crate::content_trust::registry::{HttpRegistry, Session};
+
+/// A download that receives nothing for this long is abandoned, so that it can be tried again.
+const STALL_TIMEOUT: Duration = Duration::from_mins(1);
+
+/// How often the progress of a layer download is reported.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How far a download may fall behind the rate limit and then catch up at full speed.
+const MAX_BURST: Duration = Duration::from_secs(1);
+
+#[derive(serde::Deserialize)]
+struct Index {
+    manifests: Vec<IndexEntry>,
+}
+
+#[derive(serde::Deserialize)]
+struct IndexEntry {
+    digest: String,
+    platform: Option<Platform>,
+}
+
+#[derive(serde::Deserialize)]
+struct Platform {
+    os: String,
+    architecture: String,
+    variant: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct ImageManifest {
+    config: Descriptor,
+    layers: Vec<Descriptor>,
+}
+
+#[derive(serde::Deserialize)]
+struct Descriptor {
+    digest: String,
+    size: Option<u64>,
+}
+
+/// Limits the rate at which bytes are consumed.
+struct RateLimiter {
+    bytes_per_second: u64,
+    start: Instant,
+    bytes: u64,
+}
+
+impl RateLimiter {
+    fn new(bytes_per_second: u64) -> Self {
+        RateLimiter {
+            bytes_per_second,
+            start: Instant::now(),
+            bytes: 0,
+        }
+    }
+
+    /// How long it should take to consume the bytes consumed so far.
+    fn due(&self) -> Duration {
+        let nanos =
+            u128::from(self.bytes) * 1_000_000_000 / u128::from(self.bytes_per_second.max(1));
+        Duration::from_nanos(u64::try_from(nanos).unwrap_or(u64::MAX))
+    }
+
+    /// Consumes `bytes`, waiting as long as needed to stay within the rate.
+    async fn consume(&mut self, bytes: u64) {
+        // Time spent not downloading, e.g. while waiting for a response, does not build up
+        // an allowance for a burst later.
+        if self.start.elapsed() > self.due() + MAX_BURST {
+            self.start = Instant::now();
+            self.bytes = 0;
+        }
+
+        self.bytes += bytes;
+
+        if let Some(wait) = self.due().checked_sub(self.start.elapsed()) {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Downloads `source` at no more than `max_bandwidth` bytes per second, and loads it into
+/// Docker tagged as `image`. `source` is `image` itself, or `image` pinned to a digest.
+/// Returns the ID of the loaded image.
+pub(crate) async fn pull<C>(
+    client: &DockerApiClient<C>,
+    image: &str,
+    source: &str,
+    auth: Option<&AuthConfig>,
+    max_bandwidth: u64,
+    staging_root: &Path,
+    progress: &PullProgressBus,
+) -> anyhow::Result<String>
+where
+    C: Clone + Connect + Send + Sync + 'static,
+{
+    let reference = ImageReference::parse(source).map_err(anyhow::Error::msg)?;
+    let registry = HttpRegistry::new()?;
+    let mut session = registry.session(&reference, auth);
+
+    let manifest = image_manifest(&mut session, &reference).await?;
+    let manifest_digest = manifest.0;
+    let (config, layers) = (manifest.1.config, manifest.1.layers);
+
+    let staging = staging_root.join(manifest_digest.trim_start_matches("sha256:"));
+    tokio::fs::create_dir_all(&staging)
+        .await
+        .with_context(|| format!("could not create {}", staging.display()))?;
+
+    let mut download = Download {
+        session,
+        limiter: RateLimiter::new(max_bandwidth),
+        progress,
+        image,
+    };
+
+    let config_path = staging.join(file_name(&config.digest, "json"));
+    download
+        .blob(&config.digest, config.size, &config_path, None)
+        .await?;
+
+    let mut entries = vec![];
+    let mut layer_names = vec![];
+
+    for layer in &layers {
+        let name = file_name(&layer.digest, "tar");
+        let path = staging.join(&name);
+
+        download
+            .blob(
+                &layer.digest,
+                layer.size,
+                &path,
+                Some(short_id(&layer.digest)),
+            )
+            .await?;
+
+        layer_names.push(name.clone());
+        entries.push(Entry {
+            name,
+            content: Content::File(path),
+        });
+    }
+
+    let repo_tags: Vec<String> = ImageReference::parse(image)
+        .ok()
+        .and_then(|reference| {
+            let tag = reference.tag()?;
+            Some(format!("{}:{tag}", reference.name()))
+        })
+        .into_iter()
+        .collect();
+    let archive_manifest = serde_json::json!([{
+        "Config": file_name(&config.digest, "json"),
+        "RepoTags": repo_tags,
+        "Layers": layer_names,
+    }]);
+
+    entries.insert(
+        0,
+        Entry {
+            name: "manifest.json".to_owned(),
+            content: Content::Bytes(serde_json::to_vec(&archive_manifest)?.into()),
+        },
+    );
+    entries.insert(
+        1,
+        Entry {
+            name: file_name(&config.digest, "json"),
+            content: Content::File(config_path),
+        },
+    );
+
+    for layer in &layers {
+        progress.report(
+            PullProgress::new(image, PullStatus::Extracting)
+                .with_layer(Some(short_id(&layer.digest))),
+        );
+    }
+
+    let (sender, receiver) = tokio::sync::mpsc::channel(8);
+    let (written, loaded) = tokio::join!(
+        archive::write(entries, sender),
+        client.image_load(true, BoxBody::new(ChannelBody(receiver))),
+    );
+    let written = written.context("could not read downloaded image")?;
+    loaded.context("could not load downloaded image")?;
+    anyhow::ensure!(written, "container runtime stopped reading the image");
+
+    for layer in &layers {
+        progress.report(
+            PullProgress::new(image, PullStatus::Extracted)
+                .with_layer(Some(short_id(&layer.digest))),
+        );
+    }
+
+    if let Err(err) = tokio::fs::remove_dir_all(&staging).await {
+        log::warn!("Could not remove {}: {err}", staging.display());
+    }
+
+    Ok(config.digest)
+}
+
+/// Fetches the manifest of `reference` for this device's platform. Returns its digest along
+/// with the manifest.
+async fn image_manifest(
+    session: &mut Session<'_>,
+    reference: &ImageReference,
+) -> anyhow::Result<(String, ImageManifest)> {
+    let manifest = session.manifest(reference.manifest_reference()).await?;
+
+    if let Some(digest) = reference.digest() {
+        anyhow::ensure!(
+            manifest.digest == digest,
+            "registry served digest {} for {reference}",
+            manifest.digest
+        );
+    }
+
+    let manifest = match serde_json::from_slice::<Index>(&manifest.body) {
+        Ok(index) => {
+            let digest = select_platform(&index)
+                .ok_or_else(|| anyhow::anyhow!("{reference} has no image for this platform"))?;
+            session.manifest(digest).await?
+        }
+        Err(_) => manifest,
+    };
+
+    let image_manifest = serde_json::from_slice(&manifest.body).with_context(|| {
+        format!(
+            "unsupported manifest type {}",
+            manifest.media_type.as_deref().unwrap_or("unknown")
+        )
+    })?;
+
+    Ok((manifest.digest, image_manifest))
+}
+
+/// Picks the manifest for the platform edged runs on from an index.
+fn select_platform(index: &Index) -> Option<&str> {
+    let (architecture, variant) = match std::env::consts::ARCH {
+        "x86_64" => ("amd64", None),
+        "aarch64" => ("arm64", None),
+        "arm" => ("arm", Some("v7")),
+        other => (other, None),
+    };
+
+    let candidates: Vec<_> = index
+        .manifests
+        .iter()
+        .filter(|entry| {
+            entry.platform.as_ref().is_some_and(|platform| {
+                platform.os == "linux" && platform.architecture == architecture
+            })
+        })
+        .collect();
+
+    candidates
+        .iter()
+        .find(|entry| {
+            variant.is_none()
+                || entry
+                    .platform
+                    .as_ref()
+                    .is_some_and(|platform| platform.variant.as_deref() == variant)
+        })
+        .or_else(|| candidates.first())
+        .map(|entry| entry.digest.as_str())
+}
+
+fn file_name(digest: &str, extension: &str) -> String {
+    format!("{}.{extension}", digest.trim_start_matches("sha256:"))
+}
+
+/// The abbreviated digest that Docker uses to identify layers in its progress.
+fn short_id(digest: &str) -> String {
+    digest
+        .trim_start_matches("sha256:")
+        .chars()
+        .take(12)
+        .collect()
+}
+
+struct Download<'a> {
+    session: Session<'a>,
+    limiter: RateLimiter,
+    progress: &'a PullProgressBus,
+    image: &'a str,
+}
+
+impl Download<'_> {
+    /// Downloads blob `digest` to `path`, resuming an earlier partial download.
+    async fn blob(
+        &mut self,
+        digest: &str,
+        size: Option<u64>,
+        path: &Path,
+        layer: Option<String>,
+    ) -> anyhow::Result<()> {
+        // Blobs are moved to their path only once they are complete and verified.
+        if tokio::fs::try_exists(path).await? {
+            self.report(PullStatus::AlreadyExists, layer, size, size);
+            return Ok(());
+        }
+
+        let partial = partial_path(path);
+        let (mut hasher, mut offset) = hash_file(&partial).await?;
+
+        // An earlier attempt may have downloaded all of the blob and failed just after.
+        if size.is_none_or(|size| offset < size) {
+            (hasher, offset) = self
+                .download(digest, size, &partial, hasher, offset, layer.clone())
+                .await?;
+        }
+
+        let actual = format!("sha256:{}", hex::encode(hasher.finish()));
+        if actual != digest {
+            // The partial download is useless, so start over on the next attempt.
+            let _ = tokio::fs::remove_file(&partial).await;
+            anyhow::bail!("downloaded blob has digest {actual}, expected {digest}");
+        }
+
+        tokio::fs::rename(&partial, path).await?;
+        self.report(PullStatus::Downloaded, layer, Some(offset), size);
+
+        Ok(())
+    }
+
+    /// Appends blob `digest` to `partial` from byte `offset`, or from its start if the registry
+    /// cannot resume. Returns the hash and the size of the whole download.
+    async fn download(
+        &mut self,
+        digest: &str,
+        size: Option<u64>,
+        partial: &Path,
+        mut hasher: openssl::sha::Sha256,
+        mut offset: u64,
+        layer: Option<String>,
+    ) -> anyhow::Result<(openssl::sha::Sha256, u64)> {
+        let (mut body, resumed) = self.session.blob(digest, offset).await?;
+
+        let mut file = if resumed {
+            tokio::fs::OpenOptions::new()
+                .append(true)
+                .open(partial)
+                .await?
+        } else {
+            hasher = openssl::sha::Sha256::new();
+            offset = 0;
+            tokio::fs::File::create(partial).await?
+        };
+
+        let mut last_report = Instant::now();
+        self.report(PullStatus::Downloading, layer.clone(), Some(offset), size);
+
+        loop {
+            let frame = tokio::time::timeout(STALL_TIMEOUT, body.frame())
+                .await
+                .with_context(|| format!("download of {digest} stalled"))?;
+            let Some(frame) = frame else {
+                break;
+            };
+            let Ok(data) = frame
+                .with_context(|| format!("download of {digest} failed"))?
+                .into_data()
+            else {
+                continue;
+            };
+
+            self.limiter.consume(data.len() as u64).await;
+
+            hasher.update(&data);
+            file.write_all(&data).await?;
+            offset += data.len() as u64;
+
+            if last_report.elapsed() >= PROGRESS_INTERVAL {
+                last_report = Instant::now();
+                self.report(PullStatus::Downloading, layer.clone(), Some(offset), size);
+            }
+        }
+
+        file.sync_all().await?;
+
+        Ok((hasher, offset))
+    }
+
+    fn report(
+        &self,
+        status: PullStatus,
+        layer: Option<String>,
+        current: Option<u64>,
+        total: Option<u64>,
+    ) {
+        // Blobs that are not layers, i.e. the image config, are not worth reporting.
+        if layer.is_some() {
+            self.progress.report(
+                PullProgress::new(self.image, status)
+                    .with_layer(layer)
+                    .with_bytes(current, total),
+            );
+        }
+    }
+}
+
+fn partial_path(path: &Path) -> PathBuf {
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".partial");
+    partial.into()
+}
+
+/// Hashes what an earlier attempt downloaded to `path`. Returns the hasher and the number of
+/// bytes hashed, which is 0 if there is no such file.
+async fn hash_file(path: &Path) -> anyhow::Result<(openssl::sha::Sha256, u64)> {
+    let mut hasher = openssl::sha::Sha256::new();
+
+    let mut file = match tokio::fs::File::open(path).await {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok((hasher, 0)),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut len = 0;
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let read = file.read(&mut buf).await?;
+        if read == 0 {
+            break;
+        }
+
+        hasher.update(&buf[..read]);
+        len += read as u64;
+    }
+
+    Ok((hasher, len))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Index, RateLimiter, select_platform, short_id};
+
+    #[test]
+    fn platform() {
+        let index: Index = serde_json::from_value(serde_json::json!({
+            "manifests": [
+                { "digest": "sha256:amd64", "platform": { "os": "linux", "architecture": "amd64" } },
+                { "digest": "sha256:arm64", "platform": { "os": "linux", "architecture": "arm64", "variant": "v8" } },
+                { "digest": "sha256:armv6", "platform": { "os": "linux", "architecture": "arm", "variant": "v6" } },
+                { "digest": "sha256:armv7", "platform": { "os": "linux", "architecture": "arm", "variant": "v7" } },
+                { "digest": "sha256:windows", "platform": { "os": "windows", "architecture": "amd64" } },
+            ],
+        }))
+        .unwrap();
+
+        let expected = match std::env::consts::ARCH {
+            "x86_64" => Some("sha256:amd64"),
+            "aarch64" => Some("sha256:arm64"),
+            "arm" => Some("sha256:armv7"),
+            _ => None,
+        };
+        assert_eq!(expected, select_platform(&index));
+
+        let index: Index = serde_json::from_value(serde_json::json!({
+            "manifests": [
+                { "digest": "sha256:windows", "platform": { "os": "windows", "architecture": "amd64" } },
+            ],
+        }))
+        .unwrap();
+        assert_eq!(None, select_platform(&index));
+    }
+
+    #[test]
+    fn layer_id() {
+        assert_eq!(
+            "9f86d081884c",
+            short_id("sha256:9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08")
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn rate_limit() {
+        let mut limiter = RateLimiter::new(1000);
+        let start = tokio::time::Instant::now();
+
+        for _ in 0..10 {
+            limiter.consume(500).await;
+        }
+
+        // 5000 bytes at 1000 bytes per second.
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(4900), "{elapsed:?}");
+        assert!(elapsed <= Duration::from_millis(5100), "{elapsed:?}");
+    }
+}
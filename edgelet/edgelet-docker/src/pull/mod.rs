@@ -0,0 +1,307 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! Image pulls with progress reporting and retries, and images loaded from offline bundles.
+
+mod archive;
+pub(crate) mod bundle;
+pub(crate) mod download;
+
+use std::future::Future;
+
+use anyhow::Context;
+use http_body_util::BodyExt as _;
+use hyper::body::Incoming;
+
+use docker::apis::ApiError;
+use docker::models::CreateImageInfo;
+use edgelet_core::{PullProgress, PullProgressBus, PullStatus};
+use edgelet_settings::base::pull::Settings as PullSettings;
+
+use crate::error::Error;
+
+/// Runs `attempt` until it succeeds, it fails with an error that is not transient, or the
+/// retries allowed by `settings` are used up. The start and the outcome of the pull of `image`
+/// are reported to `progress`.
+pub async fn pull_with_retries<F, Fut, T>(
+    settings: &PullSettings,
+    progress: &PullProgressBus,
+    image: &str,
+    mut attempt: F,
+) -> anyhow::Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = anyhow::Result<T>>,
+{
+    progress.report(PullProgress::new(image, PullStatus::Started));
+
+    let mut retries = 0;
+
+    loop {
+        match attempt().await {
+            Ok(pulled) => {
+                progress.report(PullProgress::new(image, PullStatus::Completed));
+                return Ok(pulled);
+            }
+
+            Err(err) if retries < settings.retries() && is_transient(&err) => {
+                retries += 1;
+                log::warn!(
+                    "Pull of image {image} failed, trying again in {:?} ({retries}/{}): {err:#}",
+                    settings.retry_delay(),
+                    settings.retries()
+                );
+                progress.report(
+                    PullProgress::new(image, PullStatus::Retrying)
+                        .with_message(Some(format!("{err:#}"))),
+                );
+
+                tokio::time::sleep(settings.retry_delay()).await;
+            }
+
+            Err(err) => {
+                progress.report(
+                    PullProgress::new(image, PullStatus::Failed)
+                        .with_message(Some(format!("{err:#}"))),
+                );
+                return Err(err);
+            }
+        }
+    }
+}
+
+/// Whether a failed pull may succeed if tried again. Registries report most permanent failures,
+/// such as a missing image or denied access, in the message rather than with a status code, so
+/// both are considered.
+fn is_transient(err: &anyhow::Error) -> bool {
+    const PERMANENT: &[&str] = &[
+        "unauthorized",
+        "denied",
+        "not found",
+        "manifest unknown",
+        "no such image",
+        "invalid reference",
+        "failed verification",
+        "no space left on device",
+    ];
+
+    if let Some(err) = err.root_cause().downcast_ref::<ApiError>()
+        && err.code.is_client_error()
+    {
+        return false;
+    }
+    if let Some(Error::ImageVerification(..)) = err.root_cause().downcast_ref::<Error>() {
+        return false;
+    }
+
+    let message = format!("{err:#}").to_lowercase();
+    !PERMANENT
+        .iter()
+        .any(|permanent| message.contains(permanent))
+}
+
+/// Reads the progress stream of a Docker pull of `image` to its end, reporting the progress of
+/// each layer.
+pub(crate) async fn follow(
+    mut body: Incoming,
+    image: &str,
+    progress: &PullProgressBus,
+) -> anyhow::Result<()> {
+    let mut pending = Vec::new();
+    let mut finished = false;
+
+    while let Some(frame) = body.frame().await {
+        let frame = frame.context("connection lost during pull")?;
+        let Ok(data) = frame.into_data() else {
+            continue;
+        };
+
+        pending.extend_from_slice(&data);
+
+        while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = pending.drain(..=end).collect();
+            if line.trim_ascii().is_empty() {
+                continue;
+            }
+
+            let info: CreateImageInfo =
+                serde_json::from_slice(&line).context("invalid pull progress")?;
+
+            if let Some(detail) = info.error_detail {
+                return Err(ApiError::from_error_detail(detail).into());
+            }
+
+            // Docker ends a successful pull with a summary, e.g. "Status: Image is up to date".
+            if info
+                .status
+                .as_deref()
+                .is_some_and(|status| status.starts_with("Status:"))
+            {
+                finished = true;
+            }
+
+            if let Some(layer_progress) = layer_progress(image, info) {
+                progress.report(layer_progress);
+            }
+        }
+    }
+
+    anyhow::ensure!(finished, "pull ended before it finished");
+
+    Ok(())
+}
+
+fn layer_progress(image: &str, info: CreateImageInfo) -> Option<PullProgress> {
+    let status = match info.status.as_deref()? {
+        "Downloading" => PullStatus::Downloading,
+        "Download complete" => PullStatus::Downloaded,
+        "Extracting" => PullStatus::Extracting,
+        "Pull complete" => PullStatus::Extracted,
+        "Already exists" => PullStatus::AlreadyExists,
+        _ => return None,
+    };
+
+    let (current, total) = info
+        .progress_detail
+        .map_or((None, None), |detail| (detail.current, detail.total));
+
+    Some(
+        PullProgress::new(image, status)
+            .with_layer(Some(info.id?))
+            .with_bytes(current, total),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use docker::apis::ApiError;
+    use docker::models::CreateImageInfo;
+    use edgelet_core::{PullProgressBus, PullStatus};
+    use edgelet_settings::base::pull::Settings as PullSettings;
+
+    use super::{is_transient, layer_progress, pull_with_retries};
+
+    #[test]
+    fn progress_lines() {
+        let info =
+            |line: serde_json::Value| -> CreateImageInfo { serde_json::from_value(line).unwrap() };
+
+        let progress = layer_progress(
+            "ubuntu:24.04",
+            info(serde_json::json!({
+                "status": "Downloading",
+                "progressDetail": { "current": 1024, "total": 4096 },
+                "progress": "[=====>      ]",
+                "id": "a1b2c3d4e5f6",
+            })),
+        )
+        .unwrap();
+        assert_eq!(PullStatus::Downloading, progress.status());
+        assert_eq!(Some("a1b2c3d4e5f6"), progress.layer());
+        assert_eq!(
+            (Some(1024), Some(4096)),
+            (progress.current(), progress.total())
+        );
+
+        let progress = layer_progress(
+            "ubuntu:24.04",
+            info(serde_json::json!({ "status": "Already exists", "id": "a1b2c3d4e5f6" })),
+        )
+        .unwrap();
+        assert_eq!(PullStatus::AlreadyExists, progress.status());
+
+        // Progress of the whole image is not about a layer.
+        assert!(
+            layer_progress(
+                "ubuntu:24.04",
+                info(serde_json::json!({ "status": "Pulling from library/ubuntu", "id": "24.04" })),
+            )
+            .is_none()
+        );
+        assert!(
+            layer_progress(
+                "ubuntu:24.04",
+                info(
+                    serde_json::json!({ "status": "Status: Image is up to date for ubuntu:24.04" })
+                ),
+            )
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn transient_errors() {
+        assert!(is_transient(&anyhow::anyhow!(
+            "read tcp 10.0.0.2:50312->20.1.2.3:443: read: connection reset by peer"
+        )));
+        assert!(is_transient(&anyhow::anyhow!(ApiError {
+            code: hyper::StatusCode::INTERNAL_SERVER_ERROR,
+            message: "unexpected EOF".to_owned(),
+        })));
+
+        assert!(!is_transient(&anyhow::anyhow!(ApiError {
+            code: hyper::StatusCode::NOT_FOUND,
+            message: "No such image".to_owned(),
+        })));
+        assert!(!is_transient(&anyhow::anyhow!(
+            "pull access denied for contoso/app, repository does not exist"
+        )));
+    }
+
+    #[tokio::test]
+    async fn retries() {
+        let settings = PullSettings::new(None, 2, Duration::ZERO);
+        let progress = PullProgressBus::default();
+        let (_, mut receiver) = progress.subscribe();
+
+        // Succeeds on the last retry.
+        let mut attempts = 0;
+        pull_with_retries(&settings, &progress, "ubuntu:24.04", || {
+            attempts += 1;
+            let result = if attempts < 3 {
+                Err(anyhow::anyhow!("connection reset by peer"))
+            } else {
+                Ok(())
+            };
+            async move { result }
+        })
+        .await
+        .unwrap();
+        assert_eq!(3, attempts);
+
+        let mut statuses = vec![];
+        while let Ok(progress) = receiver.try_recv() {
+            statuses.push(progress.status());
+        }
+        assert_eq!(
+            statuses,
+            [
+                PullStatus::Started,
+                PullStatus::Retrying,
+                PullStatus::Retrying,
+                PullStatus::Completed
+            ]
+        );
+
+        // Gives up after the retries are used up.
+        let mut attempts = 0;
+        pull_with_retries(&settings, &progress, "ubuntu:24.04", || {
+            attempts += 1;
+            async { Err::<(), _>(anyhow::anyhow!("connection reset by peer")) }
+        })
+        .await
+        .unwrap_err();
+        assert_eq!(3, attempts);
+
+        // Permanent failures are not tried again.
+        let mut attempts = 0;
+        pull_with_retries(&settings, &progress, "ubuntu:24.04", || {
+            attempts += 1;
+            async { Err::<(), _>(anyhow::anyhow!("manifest unknown")) }
+        })
+        .await
+        .unwrap_err();
+        assert_eq!(1, attempts);
+    }
+}
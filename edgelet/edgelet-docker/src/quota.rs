@@ -0,0 +1,491 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! Enforcement of the device-wide resource quotas configured in `[resource_quotas]`.
+//!
+//! Limits that a module's create options leave unset are filled in from the configured
+//! defaults, or from the ceilings if there is no default, so a ceiling also applies to modules
+//! that do not ask for a limit at all. Only limits that a module explicitly sets above a ceiling
+//! count as exceeding the quota. An explicitly unlimited value, like a ulimit or PIDs limit of
+//! `-1`, is above every ceiling.
+//!
+//! Docker's `MemorySwap` is the limit of memory and swap together, so the memory ceiling also
+//! applies to it. A module that does not set it gets no swap, instead of Docker's default of as
+//! much swap as memory.
+
+use docker::models::{ContainerCreateBody, HostConfig, ResourcesUlimits};
+use edgelet_settings::base::quota::{QuotaAction, ResourceLimits, ResourceQuotas, Ulimit};
+
+use crate::Error;
+
+/// CFS period that Docker uses when `CpuPeriod` is not set, in microseconds.
+pub const DEFAULT_CPU_PERIOD: i64 = 100_000;
+
+const NANO_CPUS_PER_CPU: f64 = 1_000_000_000.0;
+
+/// Applies `quotas` to the create options of module `name`.
+pub fn apply_resource_quotas(
+    quotas: &ResourceQuotas,
+    name: &str,
+    create_options: &mut ContainerCreateBody,
+) -> Result<(), Error> {
+    if quotas.is_default() {
+        return Ok(());
+    }
+
+    let host_config = create_options.host_config.get_or_insert_default();
+
+    // Only the limits that the module sets itself can exceed the quota. Defaults are checked
+    // against the ceilings when the settings are loaded, and are lowered to them without blame.
+    let exceeded = clamp(quotas.max_limits(), host_config);
+    fill_unset(quotas.default_limits(), host_config);
+    clamp(quotas.max_limits(), host_config);
+    fill_unset(quotas.max_limits(), host_config);
+    fill_unset_memory_swap(quotas.max_limits(), host_config);
+
+    if exceeded.is_empty() {
+        return Ok(());
+    }
+
+    match quotas.on_exceed() {
+        QuotaAction::Clamp => {
+            for limit in &exceeded {
+                log::warn!("Module {name}: {limit}; lowering it to the device maximum");
+            }
+
+            Ok(())
+        }
+        QuotaAction::Reject => Err(Error::ResourceQuotaExceeded(
+            name.to_owned(),
+            exceeded.join("; "),
+        )),
+    }
+}
+
+/// CPUs that the container may use, or `None` if it is not limited.
+#[allow(clippy::cast_precision_loss)]
+fn cpus(host_config: &HostConfig) -> Option<f64> {
+    match (host_config.nano_cpus, host_config.cpu_quota) {
+        (Some(nano_cpus), _) if nano_cpus > 0 => Some(nano_cpus as f64 / NANO_CPUS_PER_CPU),
+        (_, Some(cpu_quota)) if cpu_quota > 0 => {
+            let cpu_period = host_config
+                .cpu_period
+                .filter(|period| *period > 0)
+                .unwrap_or(DEFAULT_CPU_PERIOD);
+
+            Some(cpu_quota as f64 / cpu_period as f64)
+        }
+        _ => None,
+    }
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+fn set_cpus(host_config: &mut HostConfig, cpus: f64) {
+    if host_config.cpu_quota.is_some_and(|cpu_quota| cpu_quota > 0) {
+        let cpu_period = host_config
+            .cpu_period
+            .filter(|period| *period > 0)
+            .unwrap_or(DEFAULT_CPU_PERIOD);
+
+        host_config.cpu_quota = Some((cpus * cpu_period as f64) as i64);
+    } else {
+        host_config.nano_cpus = Some((cpus * NANO_CPUS_PER_CPU) as i64);
+    }
+}
+
+/// Docker treats zero and negative values of these limits as unset.
+fn is_unset(value: Option<i64>) -> bool {
+    value.is_none_or(|value| value <= 0)
+}
+
+fn fill_unset(limits: &ResourceLimits, host_config: &mut HostConfig) {
+    if is_unset(host_config.memory) && limits.memory().is_some() {
+        host_config.memory = limits.memory();
+    }
+
+    if is_unset(host_config.cpu_shares) && limits.cpu_shares().is_some() {
+        host_config.cpu_shares = limits.cpu_shares();
+    }
+
+    if let Some(limit) = limits.cpus()
+        && cpus(host_config).is_none()
+    {
+        set_cpus(host_config, limit);
+    }
+
+    // A negative PIDs limit is unlimited rather than unset.
+    if host_config.pids_limit.is_none_or(|limit| limit == 0) && limits.pids_limit().is_some() {
+        host_config.pids_limit = limits.pids_limit();
+    }
+
+    for (name, limit) in limits.ulimits() {
+        let ulimits = host_config.ulimits.get_or_insert_default();
+
+        if !ulimits
+            .iter()
+            .any(|ulimit| ulimit.name.as_deref() == Some(name))
+        {
+            ulimits.push(ResourcesUlimits {
+                name: Some(name.clone()),
+                soft: Some(limit.soft),
+                hard: Some(limit.hard),
+            });
+        }
+    }
+}
+
+/// Without a `MemorySwap`, Docker allows as much swap as memory on top of the memory limit.
+fn fill_unset_memory_swap(max: &ResourceLimits, host_config: &mut HostConfig) {
+    if max.memory().is_some() && host_config.memory_swap.is_none_or(|swap| swap == 0) {
+        host_config.memory_swap = host_config.memory;
+    }
+}
+
+/// Lowers the limits that are above `max` and describes each of them.
+fn clamp(max: &ResourceLimits, host_config: &mut HostConfig) -> Vec<String> {
+    let mut exceeded = vec![];
+
+    if let (Some(max), Some(memory)) = (max.memory(), host_config.memory)
+        && memory > max
+    {
+        exceeded.push(format!(
+            "memory limit of {memory} bytes exceeds the device maximum of {max} bytes"
+        ));
+        host_config.memory = Some(max);
+    }
+
+    if let (Some(max), Some(memory_swap)) = (max.memory(), host_config.memory_swap)
+        && (memory_swap < 0 || memory_swap > max)
+    {
+        exceeded.push(format!(
+            "memory and swap limit of {} exceeds the device maximum of {max} bytes",
+            describe_limit(memory_swap, "bytes")
+        ));
+        host_config.memory_swap = Some(max);
+    }
+
+    if let (Some(max), Some(cpu_shares)) = (max.cpu_shares(), host_config.cpu_shares)
+        && cpu_shares > max
+    {
+        exceeded.push(format!(
+            "CPU shares of {cpu_shares} exceed the device maximum of {max}"
+        ));
+        host_config.cpu_shares = Some(max);
+    }
+
+    if let (Some(max), Some(cpus)) = (max.cpus(), cpus(host_config))
+        && cpus > max
+    {
+        exceeded.push(format!(
+            "CPU limit of {cpus} CPUs exceeds the device maximum of {max} CPUs"
+        ));
+        set_cpus(host_config, max);
+    }
+
+    if let (Some(max), Some(pids_limit)) = (max.pids_limit(), host_config.pids_limit)
+        && (pids_limit < 0 || pids_limit > max)
+    {
+        exceeded.push(format!(
+            "PIDs limit of {} exceeds the device maximum of {max}",
+            describe_ulimit(Some(pids_limit))
+        ));
+        host_config.pids_limit = Some(max);
+    }
+
+    for ulimit in host_config.ulimits.iter_mut().flatten() {
+        let Some(name) = &ulimit.name else {
+            continue;
+        };
+        let Some(&Ulimit {
+            soft: max_soft,
+            hard: max_hard,
+        }) = max.ulimits().get(name)
+        else {
+            continue;
+        };
+
+        // A negative ulimit is unlimited, and an unset one is up to the runtime.
+        let above = |value: Option<i64>, max| value.is_none_or(|value| value < 0 || value > max);
+        if above(ulimit.soft, max_soft) || above(ulimit.hard, max_hard) {
+            exceeded.push(format!(
+                "{name} ulimit of {}:{} exceeds the device maximum of {max_soft}:{max_hard}",
+                describe_ulimit(ulimit.soft),
+                describe_ulimit(ulimit.hard)
+            ));
+            ulimit.soft = Some(clamp_ulimit(ulimit.soft, max_soft));
+            ulimit.hard = Some(clamp_ulimit(ulimit.hard, max_hard));
+        }
+    }
+
+    exceeded
+}
+
+fn clamp_ulimit(value: Option<i64>, max: i64) -> i64 {
+    match value {
+        Some(value) if (0..=max).contains(&value) => value,
+        _ => max,
+    }
+}
+
+/// Describes a count that is unlimited when negative, like a ulimit or a PIDs limit.
+fn describe_ulimit(value: Option<i64>) -> String {
+    match value {
+        Some(value) if value >= 0 => value.to_string(),
+        Some(_) => "unlimited".to_owned(),
+        None => "unset".to_owned(),
+    }
+}
+
+fn describe_limit(value: i64, unit: &str) -> String {
+    if value < 0 {
+        "unlimited".to_owned()
+    } else {
+        format!("{value} {unit}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_options(host_config: HostConfig) -> ContainerCreateBody {
+        ContainerCreateBody {
+            host_config: Some(host_config),
+            ..Default::default()
+        }
+    }
+
+    fn quotas(on_exceed: QuotaAction) -> ResourceQuotas {
+        ResourceQuotas::new(
+            on_exceed,
+            ResourceLimits::default()
+                .with_memory(Some(256))
+                .with_ulimit(
+                    "nofile".to_owned(),
+                    Ulimit {
+                        soft: 1024,
+                        hard: 1024,
+                    },
+                ),
+            ResourceLimits::default()
+                .with_memory(Some(1024))
+                .with_cpus(Some(1.5))
+                .with_pids_limit(Some(100))
+                .with_ulimit(
+                    "nofile".to_owned(),
+                    Ulimit {
+                        soft: 4096,
+                        hard: 4096,
+                    },
+                ),
+        )
+    }
+
+    #[test]
+    fn no_quotas_leaves_create_options_unchanged() {
+        let mut create_options = ContainerCreateBody::default();
+
+        apply_resource_quotas(&ResourceQuotas::default(), "m", &mut create_options).unwrap();
+
+        assert!(create_options.host_config.is_none());
+    }
+
+    #[test]
+    fn unset_limits_are_filled_in() {
+        let mut create_options = ContainerCreateBody::default();
+
+        apply_resource_quotas(&quotas(QuotaAction::Reject), "m", &mut create_options).unwrap();
+
+        let host_config = create_options.host_config.unwrap();
+        assert_eq!(Some(256), host_config.memory);
+        assert_eq!(Some(1_500_000_000), host_config.nano_cpus);
+        assert_eq!(None, host_config.cpu_quota);
+        assert_eq!(Some(100), host_config.pids_limit);
+        let ulimits = host_config.ulimits.unwrap();
+        assert_eq!(1, ulimits.len());
+        assert_eq!(Some(1024), ulimits[0].soft);
+    }
+
+    #[test]
+    fn limits_within_quota_are_kept() {
+        let mut create_options = create_options(HostConfig {
+            memory: Some(512),
+            cpu_quota: Some(50_000),
+            pids_limit: Some(50),
+            ..Default::default()
+        });
+
+        apply_resource_quotas(&quotas(QuotaAction::Reject), "m", &mut create_options).unwrap();
+
+        let host_config = create_options.host_config.unwrap();
+        assert_eq!(Some(512), host_config.memory);
+        assert_eq!(Some(50_000), host_config.cpu_quota);
+        assert_eq!(None, host_config.nano_cpus);
+        assert_eq!(Some(50), host_config.pids_limit);
+    }
+
+    #[test]
+    fn limits_above_quota_are_clamped() {
+        let mut create_options = create_options(HostConfig {
+            memory: Some(2048),
+            cpu_period: Some(50_000),
+            cpu_quota: Some(200_000),
+            pids_limit: Some(1000),
+            ulimits: Some(vec![ResourcesUlimits {
+                name: Some("nofile".to_owned()),
+                soft: Some(1024),
+                hard: Some(65536),
+            }]),
+            ..Default::default()
+        });
+
+        apply_resource_quotas(&quotas(QuotaAction::Clamp), "m", &mut create_options).unwrap();
+
+        let host_config = create_options.host_config.unwrap();
+        assert_eq!(Some(1024), host_config.memory);
+        assert_eq!(Some(75_000), host_config.cpu_quota);
+        assert_eq!(Some(100), host_config.pids_limit);
+        let ulimits = host_config.ulimits.unwrap();
+        assert_eq!(Some(1024), ulimits[0].soft);
+        assert_eq!(Some(4096), ulimits[0].hard);
+    }
+
+    #[test]
+    fn unlimited_and_unset_ulimits_are_clamped() {
+        let mut options = create_options(HostConfig {
+            ulimits: Some(vec![
+                ResourcesUlimits {
+                    name: Some("nofile".to_owned()),
+                    soft: Some(-1),
+                    hard: Some(-1),
+                },
+                ResourcesUlimits {
+                    name: Some("nofile".to_owned()),
+                    soft: Some(512),
+                    hard: None,
+                },
+            ]),
+            ..Default::default()
+        });
+
+        apply_resource_quotas(&quotas(QuotaAction::Clamp), "m", &mut options).unwrap();
+
+        let ulimits = options.host_config.unwrap().ulimits.unwrap();
+        assert_eq!((Some(4096), Some(4096)), (ulimits[0].soft, ulimits[0].hard));
+        assert_eq!((Some(512), Some(4096)), (ulimits[1].soft, ulimits[1].hard));
+
+        let mut options = create_options(HostConfig {
+            ulimits: Some(vec![ResourcesUlimits {
+                name: Some("nofile".to_owned()),
+                soft: Some(-1),
+                hard: Some(-1),
+            }]),
+            ..Default::default()
+        });
+
+        let err =
+            apply_resource_quotas(&quotas(QuotaAction::Reject), "m", &mut options).unwrap_err();
+
+        assert_eq!(
+            "module \"m\" exceeds the device resource quotas: nofile ulimit of unlimited:unlimited exceeds the device maximum of 4096:4096",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn memory_swap_is_limited_by_memory_quota() {
+        let mut options = ContainerCreateBody::default();
+
+        apply_resource_quotas(&quotas(QuotaAction::Reject), "m", &mut options).unwrap();
+
+        let host_config = options.host_config.unwrap();
+        assert_eq!(Some(256), host_config.memory_swap);
+
+        let mut options = create_options(HostConfig {
+            memory: Some(512),
+            memory_swap: Some(768),
+            ..Default::default()
+        });
+
+        apply_resource_quotas(&quotas(QuotaAction::Reject), "m", &mut options).unwrap();
+
+        assert_eq!(Some(768), options.host_config.unwrap().memory_swap);
+
+        let mut options = create_options(HostConfig {
+            memory: Some(512),
+            memory_swap: Some(-1),
+            ..Default::default()
+        });
+
+        apply_resource_quotas(&quotas(QuotaAction::Clamp), "m", &mut options).unwrap();
+
+        let host_config = options.host_config.unwrap();
+        assert_eq!(Some(512), host_config.memory);
+        assert_eq!(Some(1024), host_config.memory_swap);
+    }
+
+    #[test]
+    fn unlimited_pids_limit_exceeds_quota() {
+        let mut options = create_options(HostConfig {
+            pids_limit: Some(-1),
+            ..Default::default()
+        });
+
+        let err =
+            apply_resource_quotas(&quotas(QuotaAction::Reject), "m", &mut options).unwrap_err();
+
+        assert_eq!(
+            "module \"m\" exceeds the device resource quotas: PIDs limit of unlimited exceeds the device maximum of 100",
+            err.to_string()
+        );
+
+        apply_resource_quotas(&quotas(QuotaAction::Clamp), "m", &mut options).unwrap();
+
+        assert_eq!(Some(100), options.host_config.unwrap().pids_limit);
+
+        // Without a ceiling, an unlimited PIDs limit is kept.
+        let mut options = create_options(HostConfig {
+            pids_limit: Some(-1),
+            ..Default::default()
+        });
+        let quotas = ResourceQuotas::new(
+            QuotaAction::Reject,
+            ResourceLimits::default().with_pids_limit(Some(50)),
+            ResourceLimits::default().with_memory(Some(1024)),
+        );
+
+        apply_resource_quotas(&quotas, "m", &mut options).unwrap();
+
+        assert_eq!(Some(-1), options.host_config.unwrap().pids_limit);
+    }
+
+    #[test]
+    fn defaults_above_quota_are_not_blamed_on_the_module() {
+        let quotas = ResourceQuotas::new(
+            QuotaAction::Reject,
+            ResourceLimits::default().with_memory(Some(2048)),
+            ResourceLimits::default().with_memory(Some(1024)),
+        );
+        let mut options = ContainerCreateBody::default();
+
+        apply_resource_quotas(&quotas, "m", &mut options).unwrap();
+
+        assert_eq!(Some(1024), options.host_config.unwrap().memory);
+    }
+
+    #[test]
+    fn limits_above_quota_are_rejected() {
+        let mut create_options = create_options(HostConfig {
+            memory: Some(2048),
+            nano_cpus: Some(2_000_000_000),
+            ..Default::default()
+        });
+
+        let err = apply_resource_quotas(&quotas(QuotaAction::Reject), "m", &mut create_options)
+            .unwrap_err();
+
+        assert_eq!(
+            "module \"m\" exceeds the device resource quotas: memory limit of 2048 bytes exceeds the device maximum of 1024 bytes; CPU limit of 2 CPUs exceeds the device maximum of 1.5 CPUs",
+            err.to_string()
+        );
+    }
+}
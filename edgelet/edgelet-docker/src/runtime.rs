@@ -1,12 +1,13 @@
 // Copyright (c) Microsoft. All rights reserved.
 
 use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use std::{process, str};
 
 use anyhow::Context;
-use hyper::body::Incoming;
+use http_body_util::BodyExt as _;
 use hyper_util::client::legacy::connect::Connect;
 use sysinfo::{Disks, Process, System};
 use tokio::sync::Mutex;
@@ -16,9 +17,13 @@ use url::Url;
 use docker::apis::{Configuration, DockerApi, DockerApiClient};
 use docker::models::{ContainerCreateBody, ContainerTopResponse, Ipam, NetworkConfig};
 use edgelet_core::{
-    DiskInfo, LogOptions, Module, ModuleAction, ModuleRegistry, ModuleRuntime, ModuleRuntimeState,
-    RegistryOperation, RuntimeOperation, SystemInfo as CoreSystemInfo, SystemResources, UrlExt,
+    DiskInfo, EventStream, ImageUse, LogOptions, LogStream, Module, ModuleAction, ModuleMetrics,
+    ModuleRegistry, ModuleRuntime, ModuleRuntimeState, PullProgressBus, RegistryOperation,
+    RestartTracker, RuntimeOperation, SystemInfo as CoreSystemInfo, SystemResources, UrlExt,
 };
+use edgelet_settings::base::policy::ContainerPolicy;
+use edgelet_settings::base::pull::Settings as PullSettings;
+use edgelet_settings::base::quota::ResourceQuotas;
 use edgelet_settings::{
     DockerConfig, Ipam as CoreIpam, MobyNetwork, ModuleSpec, RuntimeSettings, Settings,
 };
@@ -27,23 +32,43 @@ use http_common::Connector;
 
 use crate::error::Error;
 use crate::module::{DockerModule, MODULE_TYPE as DOCKER_MODULE_TYPE, runtime_state};
-use crate::{ImagePruneData, MakeModuleRuntime};
+use crate::pull::{self, pull_with_retries};
+use crate::{
+    ImagePruneData, ImageReference, ImageUseDetails, ImageVerifier, MakeModuleRuntime,
+    apply_resource_quotas, check_container_policy,
+};
 
 type Deserializer = &'static mut serde_json::Deserializer<serde_json::de::IoRead<std::io::Empty>>;
 
-const OWNER_LABEL_KEY: &str = "net.azure-devices.edge.owner";
-const OWNER_LABEL_VALUE: &str = "Microsoft.Azure.Devices.Edge.Agent";
-const ORIGINAL_IMAGE_LABEL_KEY: &str = "net.azure-devices.edge.original-image";
+pub(crate) const OWNER_LABEL_KEY: &str = "net.azure-devices.edge.owner";
+pub(crate) const OWNER_LABEL_VALUE: &str = "Microsoft.Azure.Devices.Edge.Agent";
+pub(crate) const ORIGINAL_IMAGE_LABEL_KEY: &str = "net.azure-devices.edge.original-image";
 const LABELS: &[&str] = &["net.azure-devices.edge.owner=Microsoft.Azure.Devices.Edge.Agent"];
 
+/// Docker labels the volumes that it creates for containers without a name.
+const ANONYMOUS_VOLUME_LABELS: &[&str] = &["com.docker.volume.anonymous"];
+
+const PULL_DIRECTORY: &str = "pulls";
+
 #[derive(Clone)]
 pub struct DockerModuleRuntime<C> {
     client: DockerApiClient<C>,
     system_resources: Arc<Mutex<System>>,
     create_socket_channel: UnboundedSender<ModuleAction>,
     allow_elevated_docker_permissions: bool,
+    resource_quotas: ResourceQuotas,
+    container_policy: ContainerPolicy,
+    content_trust: Option<ImageVerifier>,
+    image_pull: PullSettings,
+    pull_progress: PullProgressBus,
+    pull_directory: PathBuf,
+    /// IDs of images that edged downloaded and loaded itself, by the digest-pinned reference
+    /// they were pulled as. Docker does not know the digests of loaded images, so it cannot find
+    /// them by such references.
+    loaded_images: Arc<std::sync::Mutex<BTreeMap<String, String>>>,
     additional_info: BTreeMap<String, String>,
     image_use_data: ImagePruneData,
+    restart_tracker: RestartTracker,
 }
 
 fn merge_env(cur_env: Option<&[String]>, new_env: &BTreeMap<String, String>) -> Vec<String> {
@@ -85,10 +110,21 @@ where
 
     async fn pull(&self, config: &Self::Config) -> anyhow::Result<()> {
         let image = config.image().to_owned();
-        let is_content_trust_enabled = false;
 
-        if is_content_trust_enabled {
-            log::info!("Pulling image via digest {image}...");
+        let pinned = match &self.content_trust {
+            Some(content_trust) if content_trust.is_required(&image) => Some(
+                content_trust
+                    .verify(&image, config.auth())
+                    .await
+                    .with_context(|| {
+                        Error::RegistryOperation(RegistryOperation::PullImage(image.clone()))
+                    })?,
+            ),
+            _ => None,
+        };
+
+        if let Some(pinned) = &pinned {
+            log::info!("Pulling image via digest {pinned}...");
         } else {
             log::info!("Pulling image via tag {image}...");
         }
@@ -104,17 +140,70 @@ where
             None => String::new(),
         };
 
-        self.client
-            .image_create(&image, "", "", "", "", "", &creds, "")
-            .await
-            .context(Error::Docker)
-            .map_err(|e| {
-                log::warn!("{e:?}");
-                e
-            })
-            .with_context(|| {
-                Error::RegistryOperation(RegistryOperation::PullImage(image.clone()))
-            })?;
+        let source = pinned.as_ref().unwrap_or(&image);
+        let (image_ref, creds_ref) = (&image, &creds);
+
+        pull_with_retries(
+            &self.image_pull,
+            &self.pull_progress,
+            &image,
+            move || async move {
+                if let Some(max_bandwidth) = self.image_pull.max_bandwidth() {
+                    let image_id = pull::download::pull(
+                        &self.client,
+                        image_ref,
+                        source,
+                        config.auth(),
+                        max_bandwidth,
+                        &self.pull_directory,
+                        &self.pull_progress,
+                    )
+                    .await?;
+
+                    if source.contains('@') {
+                        self.loaded_images
+                            .lock()
+                            .expect("loaded images lock poisoned")
+                            .insert(source.clone(), image_id);
+                    }
+                } else {
+                    let body = self
+                        .client
+                        .image_pull(source, "", "", creds_ref)
+                        .await
+                        .context(Error::Docker)?;
+                    pull::follow(body, image_ref, &self.pull_progress).await?;
+                }
+
+                Ok(())
+            },
+        )
+        .await
+        .map_err(|e| {
+            log::warn!("{e:?}");
+            e
+        })
+        .with_context(|| Error::RegistryOperation(RegistryOperation::PullImage(image.clone())))?;
+
+        // Point the tag at the verified digest, so that the image is found by the name it was
+        // configured with. Images that edged loaded itself are already tagged.
+        if let Some(pinned) = pinned
+            .as_ref()
+            .filter(|_| self.image_pull.max_bandwidth().is_none())
+        {
+            let reference = ImageReference::parse(&image)
+                .map_err(|reason| Error::ImageVerification(image.clone(), reason))?;
+
+            if let Some(tag) = reference.tag() {
+                self.client
+                    .image_tag(pinned, reference.name(), tag)
+                    .await
+                    .context(Error::Docker)
+                    .with_context(|| {
+                        Error::RegistryOperation(RegistryOperation::PullImage(image.clone()))
+                    })?;
+            }
+        }
 
         log::info!("Successfully pulled image {image}");
 
@@ -126,7 +215,15 @@ where
                         "No docker images present on device: {image} was just pulled, but not found on device"
                     );
                 } else if let Some(image_id) = image_name_to_id.get(config.image()) {
-                    self.image_use_data.record_image_use_timestamp(image_id)?;
+                    let sizes = self.image_sizes().await.unwrap_or_default();
+                    self.image_use_data.record_image_use(
+                        image_id,
+                        ImageUseDetails {
+                            tag: Some(config.image()),
+                            module: None,
+                            size: sizes.get(image_id).copied(),
+                        },
+                    )?;
                 } else {
                     log::warn!(
                         "Could not retrieve image id. {image} was not added to image garbage collection list and will not be garbage collected"
@@ -146,6 +243,10 @@ where
             Error::RegistryOperation(RegistryOperation::RemoveImage(name.to_string()))
         })?;
 
+        if let Some(content_trust) = &self.content_trust {
+            content_trust.forget(name);
+        }
+
         self.client
             .image_delete(name, true, false)
             .await
@@ -161,6 +262,138 @@ where
         log::info!("Successfully removed image {name}");
         Ok(())
     }
+
+    async fn image_exists(&self, config: &Self::Config) -> anyhow::Result<bool> {
+        let filters = serde_json::json!({ "reference": [config.image()] }).to_string();
+
+        let images = self
+            .client
+            .images_list(false, &filters, false)
+            .await
+            .context(Error::Docker)
+            .context(Error::RuntimeOperation(RuntimeOperation::ListImages))?;
+
+        Ok(!images.is_empty())
+    }
+
+    async fn load_bundle(&self, config: &Self::Config) -> anyhow::Result<bool> {
+        let image = config.image();
+
+        let Some(bundle) = config.bundle().or_else(|| self.image_pull.bundle(image)) else {
+            return Ok(false);
+        };
+
+        if let Some(content_trust) = &self.content_trust {
+            content_trust.check_bundle_image(image).with_context(|| {
+                Error::RegistryOperation(RegistryOperation::PullImage(image.to_owned()))
+            })?;
+        }
+
+        log::info!(
+            "Loading image {image} from offline bundle {}...",
+            bundle.display()
+        );
+
+        let loaded = self.import(bundle).await?;
+
+        // An image without a name, such as one in an OCI image layout without a reference name,
+        // is given the name it was configured with.
+        if let [id] = &loaded[..]
+            && id.starts_with("sha256:")
+        {
+            let reference = ImageReference::parse(image)
+                .map_err(anyhow::Error::msg)
+                .with_context(|| {
+                    Error::RegistryOperation(RegistryOperation::PullImage(image.to_owned()))
+                })?;
+
+            self.client
+                .image_tag(id, reference.name(), reference.tag().unwrap_or("latest"))
+                .await
+                .context(Error::Docker)
+                .with_context(|| {
+                    Error::RegistryOperation(RegistryOperation::PullImage(image.to_owned()))
+                })?;
+        }
+
+        Ok(true)
+    }
+
+    async fn import(&self, path: &std::path::Path) -> anyhow::Result<Vec<String>> {
+        log::info!("Importing images from {}...", path.display());
+
+        let loaded = pull::bundle::load(&self.client, path)
+            .await
+            .map_err(|e| {
+                log::warn!("{e:?}");
+                e
+            })
+            .with_context(|| {
+                Error::RegistryOperation(RegistryOperation::ImportImage(path.display().to_string()))
+            })?;
+
+        // The names in a bundle are only known once Docker has loaded it, so the images that
+        // content trust keeps out of bundles are untagged again. Images that Docker only knows
+        // by their ID cannot be found by the names that modules use.
+        if let Some(content_trust) = &self.content_trust {
+            let mut rejected = None;
+
+            for image in loaded.iter().filter(|image| !image.starts_with("sha256:")) {
+                content_trust.forget(image);
+
+                if let Err(e) = content_trust.check_bundle_image(image) {
+                    log::warn!("{e}");
+                    if let Err(e) = self.client.image_delete(image, false, false).await {
+                        log::warn!("Could not untag imported image {image}: {e}");
+                    }
+                    rejected.get_or_insert(e);
+                }
+            }
+
+            if let Some(e) = rejected {
+                return Err(e).with_context(|| {
+                    Error::RegistryOperation(RegistryOperation::ImportImage(
+                        path.display().to_string(),
+                    ))
+                });
+            }
+        }
+
+        log::info!("Successfully imported images {}", loaded.join(", "));
+
+        // Imported images are garbage collected like pulled ones once they are no longer used.
+        match self.list_images().await {
+            Ok(image_name_to_id) => {
+                let sizes = self.image_sizes().await.unwrap_or_default();
+                for image in &loaded {
+                    // Bundles may load untagged images, which are listed by their ID.
+                    let (image_id, tag) = match image_name_to_id.get(image) {
+                        Some(image_id) => (image_id.as_str(), Some(image.as_str())),
+                        None => (image.as_str(), None),
+                    };
+                    self.image_use_data.record_image_use(
+                        image_id,
+                        ImageUseDetails {
+                            tag,
+                            module: None,
+                            size: sizes.get(image_id).copied(),
+                        },
+                    )?;
+                }
+            }
+            Err(e) => log::error!("Could not get list of docker images: {e}"),
+        }
+
+        Ok(loaded)
+    }
+
+    async fn image_use(&self) -> anyhow::Result<Vec<ImageUse>> {
+        Ok(self.image_use_data.images()?)
+    }
+
+    fn pull_progress(&self) -> &PullProgressBus {
+        &self.pull_progress
+    }
 }
 
 #[async_trait::async_trait]
@@ -173,11 +406,15 @@ impl MakeModuleRuntime for DockerModuleRuntime<Connector> {
         settings: &Settings,
         create_socket_channel: UnboundedSender<ModuleAction>,
         image_use_data: ImagePruneData,
+        restart_tracker: RestartTracker,
     ) -> anyhow::Result<Self::ModuleRuntime> {
         log::info!("Initializing module runtime...");
 
         let client = init_client(settings.moby_runtime().uri())?;
         create_network_if_missing(settings, &client).await?;
+        let content_trust = ImageVerifier::from_settings(settings)
+            .await
+            .context(Error::Initialization)?;
 
         // to avoid excessive FD usage, we will not allow sysinfo to keep files open.
         sysinfo::set_open_files_limit(0);
@@ -189,8 +426,16 @@ impl MakeModuleRuntime for DockerModuleRuntime<Connector> {
             system_resources: Arc::new(Mutex::new(system_resources)),
             create_socket_channel,
             allow_elevated_docker_permissions: settings.allow_elevated_docker_permissions(),
+            resource_quotas: settings.resource_quotas().clone(),
+            container_policy: settings.container_policy().clone(),
+            content_trust,
+            image_pull: settings.image_pull().clone(),
+            pull_progress: PullProgressBus::default(),
+            pull_directory: settings.homedir().join(PULL_DIRECTORY),
+            loaded_images: Arc::default(),
             additional_info: settings.additional_info().clone(),
             image_use_data,
+            restart_tracker,
         };
 
         Ok(runtime)
@@ -303,6 +548,44 @@ fn get_ipv6_settings(network_configuration: &MobyNetwork) -> (bool, Option<Ipam>
     }
 }
 
+impl<C> DockerModuleRuntime<C>
+where
+    C: Clone + Connect + Send + Sync + 'static,
+{
+    /// Stops a module without recording the stop with the restart tracker, for stops that edged
+    /// makes itself rather than on request.
+    async fn stop_module(
+        &self,
+        id: &str,
+        wait_before_kill: Option<Duration>,
+    ) -> anyhow::Result<()> {
+        log::info!("Stopping module {id}...");
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let wait_timeout = wait_before_kill.map(|s| match s.as_secs() {
+            s if s > i32::MAX as u64 => i32::MAX,
+            s => s as i32,
+        });
+
+        self.create_socket_channel
+            .send(ModuleAction::Stop(id.to_string()))
+            .map_err(|_| {
+                log::error!("Could not notify workload manager, stop of module: {id}");
+                Error::RuntimeOperation(RuntimeOperation::GetModule(id.to_string()))
+            })?;
+
+        self.client
+            .container_stop(id, wait_timeout)
+            .await
+            .context(Error::Docker)
+            .map_err(|e| {
+                log::warn!("{e:?}");
+                e
+            })
+            .with_context(|| Error::RuntimeOperation(RuntimeOperation::StopModule(id.to_owned())))
+    }
+}
+
 #[async_trait::async_trait]
 impl<C> ModuleRuntime for DockerModuleRuntime<C>
 where
@@ -320,22 +603,50 @@ where
             return Err(Error::InvalidModuleType(module.r#type().to_string()).into());
         }
 
-        unset_privileged(
+        check_container_policy(
+            &self.container_policy,
+            module.name(),
+            module.config().image(),
+            module.config().create_options(),
+        )
+        .with_context(|| {
+            Error::RuntimeOperation(RuntimeOperation::CreateModule(module.name().to_string()))
+        })?;
+
+        restrict_create_options(
             self.allow_elevated_docker_permissions,
             module.config_mut().create_options_mut(),
         );
-        drop_unsafe_privileges(
-            self.allow_elevated_docker_permissions,
+        apply_resource_quotas(
+            &self.resource_quotas,
+            module.name(),
             module.config_mut().create_options_mut(),
-        );
+        )?;
 
-        let image = module.config().image().to_owned();
-        let is_content_trust_enabled = false;
+        let mut image = module.config().image().to_owned();
+
+        match &self.content_trust {
+            Some(content_trust) if content_trust.is_required(&image) => {
+                image = content_trust
+                    .pinned(&image, module.config().auth())
+                    .await
+                    .with_context(|| {
+                        Error::RuntimeOperation(RuntimeOperation::CreateModule(
+                            module.name().to_string(),
+                        ))
+                    })?;
+                log::info!("Creating image via digest {image}...");
+            }
+            _ => log::info!("Creating image via tag {image}..."),
+        }
 
-        if is_content_trust_enabled {
-            log::info!("Creating image via digest {}...", &image);
-        } else {
-            log::info!("Creating image via tag {}...", &image);
+        if let Some(image_id) = self
+            .loaded_images
+            .lock()
+            .expect("loaded images lock poisoned")
+            .get(&image)
+        {
+            image.clone_from(image_id);
         }
 
         log::debug!("Creating container {} with image {image}...", module.name());
@@ -352,6 +663,10 @@ where
             ORIGINAL_IMAGE_LABEL_KEY.to_string(),
             module.config().image().to_string(),
         );
+        labels.insert(
+            edgelet_core::restart::RESTART_POLICY_LABEL_KEY.to_string(),
+            module.restart_policy().to_string(),
+        );
 
         // Here we don't add the container to the iot edge docker network as the edge-agent is expected to do that.
         // It contains the logic to add a container to the iot edge network only if a network is not already specified.
@@ -371,12 +686,17 @@ where
         let module_with_details = self.get(module.name()).await?;
 
         // update image use timestamp for image garbage collection job later
-        self.image_use_data.record_image_use_timestamp(
+        self.image_use_data.record_image_use(
             module_with_details
                 .0
                 .config()
                 .image_hash()
                 .ok_or(Error::GetImageId())?,
+            ImageUseDetails {
+                tag: Some(module.config().image()),
+                module: Some(module.name()),
+                size: None,
+            },
         )?;
 
         Ok(())
@@ -436,7 +756,9 @@ where
         let module = DockerModule::new(self.client.clone(), name, config).with_context(|| {
             Error::RuntimeOperation(RuntimeOperation::GetModule(id.to_string()))
         })?;
-        let state = runtime_state(response.id, response.state);
+        let state = self
+            .restart_tracker
+            .annotate(module.name(), runtime_state(response.id, response.state));
 
         Ok((module, state))
     }
@@ -470,38 +792,24 @@ where
                 log::warn!("{e:?}");
                 e
             })
-            .with_context(|| Error::RuntimeOperation(RuntimeOperation::StartModule(id.to_owned())))
+            .with_context(|| {
+                Error::RuntimeOperation(RuntimeOperation::StartModule(id.to_owned()))
+            })?;
+
+        self.restart_tracker.module_started(id);
+
+        Ok(())
     }
 
     async fn stop(&self, id: &str, wait_before_kill: Option<Duration>) -> anyhow::Result<()> {
-        log::info!("Stopping module {id}...");
-
         ensure_not_empty(id).with_context(|| {
             Error::RuntimeOperation(RuntimeOperation::StopModule(id.to_owned()))
         })?;
 
-        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
-        let wait_timeout = wait_before_kill.map(|s| match s.as_secs() {
-            s if s > i32::MAX as u64 => i32::MAX,
-            s => s as i32,
-        });
+        // Record the stop first so that the module is not restarted while it is stopping.
+        self.restart_tracker.module_stopped(id);
 
-        self.create_socket_channel
-            .send(ModuleAction::Stop(id.to_string()))
-            .map_err(|_| {
-                log::error!("Could not notify workload manager, stop of module: {id}");
-                Error::RuntimeOperation(RuntimeOperation::GetModule(id.to_string()))
-            })?;
-
-        self.client
-            .container_stop(id, wait_timeout)
-            .await
-            .context(Error::Docker)
-            .map_err(|e| {
-                log::warn!("{e:?}");
-                e
-            })
-            .with_context(|| Error::RuntimeOperation(RuntimeOperation::StopModule(id.to_owned())))
+        self.stop_module(id, wait_before_kill).await
     }
 
     async fn restart(&self, id: &str) -> anyhow::Result<()> {
@@ -520,7 +828,11 @@ where
             })
             .with_context(|| {
                 Error::RuntimeOperation(RuntimeOperation::RestartModule(id.to_owned()))
-            })
+            })?;
+
+        self.restart_tracker.module_started(id);
+
+        Ok(())
     }
 
     async fn remove(&self, id: &str) -> anyhow::Result<()> {
@@ -554,7 +866,15 @@ where
             })?;
 
         // update image use timestamp for image garbage collection job later
-        self.image_use_data.record_image_use_timestamp(image_id)?;
+        self.image_use_data.record_image_use(
+            image_id,
+            ImageUseDetails {
+                module: Some(id),
+                ..Default::default()
+            },
+        )?;
+
+        self.restart_tracker.module_removed(id);
 
         // Remove the socket to avoid having socket files polluting the home folder.
         self.create_socket_channel
@@ -659,6 +979,43 @@ where
         ))
     }
 
+    async fn module_metrics(&self) -> anyhow::Result<Vec<ModuleMetrics>> {
+        let modules = self.list().await?;
+
+        // One-shot stats do not wait a second for Docker to take a second sample. Without it
+        // there is no CPU percentage, which scrapers can derive from the CPU time instead.
+        let stats = modules
+            .iter()
+            .map(|module| self.client.container_stats(module.name(), false, true));
+        let stats = futures_util::future::join_all(stats).await;
+
+        let mut metrics = Vec::with_capacity(modules.len());
+        for (module, stats) in modules.iter().zip(stats) {
+            let stats = match stats {
+                Ok(stats) => stats,
+                // The module was removed after it was listed.
+                Err(err)
+                    if err
+                        .downcast_ref::<docker::apis::ApiError>()
+                        .is_some_and(|err| err.code == hyper::StatusCode::NOT_FOUND) =>
+                {
+                    continue;
+                }
+                Err(err) => {
+                    return Err(err)
+                        .context(Error::Docker)
+                        .context(Error::RuntimeOperation(RuntimeOperation::GetModuleMetrics));
+                }
+            };
+            let stats = serde_json::from_value(stats)
+                .context(Error::RuntimeOperation(RuntimeOperation::GetModuleMetrics))?;
+
+            metrics.push(crate::metrics::module_metrics(module.name(), &stats));
+        }
+
+        Ok(metrics)
+    }
+
     async fn list(&self) -> anyhow::Result<Vec<Self::Module>> {
         log::debug!("Listing modules...");
 
@@ -755,10 +1112,26 @@ where
         Ok(result)
     }
 
-    async fn logs(&self, id: &str, options: &LogOptions) -> anyhow::Result<Incoming> {
+    async fn image_sizes(&self) -> anyhow::Result<HashMap<String, u64>> {
+        let images = self
+            .client
+            .images_list(false, "", false)
+            .await
+            .context(Error::Docker)
+            .context(Error::RuntimeOperation(RuntimeOperation::ListImages))?;
+
+        let result = images
+            .into_iter()
+            .filter_map(|image| image.size.map(|size| (image.id, size)))
+            .collect();
+        Ok(result)
+    }
+
+    async fn logs(&self, id: &str, options: &LogOptions) -> anyhow::Result<LogStream> {
         log::info!("Getting logs for module {id}...");
 
-        self.client
+        let logs = self
+            .client
             .container_logs(
                 id,
                 options.follow(),
@@ -774,7 +1147,9 @@ where
             .map_err(|e| {
                 log::warn!("{e:?}");
                 e
-            })
+            })?;
+
+        Ok(logs.map_err(Into::into).boxed())
     }
 
     async fn remove_all(&self) -> anyhow::Result<()> {
@@ -799,7 +1174,7 @@ where
         let mut stop = vec![];
 
         for module in &modules {
-            stop.push(self.stop(module.name(), wait_before_kill));
+            stop.push(self.stop_module(module.name(), wait_before_kill));
         }
 
         for result in futures_util::future::join_all(stop).await {
@@ -829,13 +1204,96 @@ where
         Ok(pids)
     }
 
+    async fn data_root(&self) -> anyhow::Result<PathBuf> {
+        let docker_info = self
+            .client
+            .system_info()
+            .await
+            .context(Error::Docker)
+            .context(Error::RuntimeOperation(RuntimeOperation::GetDataRoot))?;
+
+        let data_root = docker_info
+            .docker_root_dir
+            .ok_or_else(|| anyhow::anyhow!("Docker did not report its root directory"))
+            .context(Error::RuntimeOperation(RuntimeOperation::GetDataRoot))?;
+
+        Ok(data_root.into())
+    }
+
+    async fn prune_containers(&self) -> anyhow::Result<u64> {
+        let filters = prune_containers_filters()
+            .context(Error::RuntimeOperation(RuntimeOperation::PruneContainers))?;
+
+        let response = self
+            .client
+            .container_prune(&filters)
+            .await
+            .context(Error::Docker)
+            .map_err(|e| {
+                log::warn!("{e:?}");
+                e
+            })
+            .context(Error::RuntimeOperation(RuntimeOperation::PruneContainers))?;
+
+        Ok(response.space_reclaimed.unwrap_or_default())
+    }
+
+    async fn prune_volumes(&self) -> anyhow::Result<u64> {
+        // Before API v1.42, Docker prunes named volumes too unless they are filtered out, and
+        // named volumes may hold data that the next container to mount them needs.
+        let mut filters = HashMap::new();
+        filters.insert("label", ANONYMOUS_VOLUME_LABELS);
+        let filters = serde_json::to_string(&filters)
+            .context(Error::RuntimeOperation(RuntimeOperation::PruneVolumes))?;
+
+        let response = self
+            .client
+            .volume_prune(&filters)
+            .await
+            .context(Error::Docker)
+            .map_err(|e| {
+                log::warn!("{e:?}");
+                e
+            })
+            .context(Error::RuntimeOperation(RuntimeOperation::PruneVolumes))?;
+
+        Ok(response.space_reclaimed.unwrap_or_default())
+    }
+
+    async fn events(
+        &self,
+        since: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> anyhow::Result<EventStream> {
+        let since = since
+            .as_ref()
+            .map(crate::events::docker_timestamp)
+            .unwrap_or_default();
+
+        let body = self
+            .client
+            .system_events(&since, "", crate::events::EVENT_FILTERS)
+            .await
+            .context(Error::Docker)
+            .context(Error::RuntimeOperation(RuntimeOperation::GetEvents))?;
+
+        Ok(crate::events::stream(body))
+    }
+
     fn registry(&self) -> &Self::ModuleRegistry {
         self
     }
 
     fn error_code(error: &anyhow::Error) -> hyper::StatusCode {
-        if let Some(error) = error.root_cause().downcast_ref::<docker::apis::ApiError>() {
+        let root_cause = error.root_cause();
+
+        if let Some(error) = root_cause.downcast_ref::<docker::apis::ApiError>() {
             error.code
+        } else if let Some(Error::ResourceQuotaExceeded(..)) = root_cause.downcast_ref::<Error>() {
+            hyper::StatusCode::BAD_REQUEST
+        } else if root_cause.is::<edgelet_core::PolicyViolations>() {
+            hyper::StatusCode::FORBIDDEN
+        } else if let Some(Error::ImageVerification(..)) = root_cause.downcast_ref::<Error>() {
+            hyper::StatusCode::FORBIDDEN
         } else {
             hyper::StatusCode::INTERNAL_SERVER_ERROR
         }
@@ -888,6 +1346,17 @@ where
     pids
 }
 
+/// Applies the restrictions on module create options that are in effect when
+/// `allow_elevated_docker_permissions` is false. Runtimes other than Docker that accept
+/// Docker create options should apply these as well.
+pub fn restrict_create_options(
+    allow_elevated_docker_permissions: bool,
+    create_options: &mut ContainerCreateBody,
+) {
+    unset_privileged(allow_elevated_docker_permissions, create_options);
+    drop_unsafe_privileges(allow_elevated_docker_permissions, create_options);
+}
+
 // Disallow adding privileged and other capabilities if allow_elevated_docker_permissions is false
 fn unset_privileged(
     allow_elevated_docker_permissions: bool,
@@ -939,14 +1408,86 @@ fn drop_unsafe_privileges(
     host_config.cap_drop = Some(caps_to_drop);
 }
 
+/// Filters that select containers without the edge owner label. Stopped modules keep their
+/// writable layer so that Edge Agent can start them again, so they are left alone.
+fn prune_containers_filters() -> serde_json::Result<String> {
+    let mut filters = HashMap::new();
+    filters.insert("label!", LABELS);
+    serde_json::to_string(&filters)
+}
+
 #[cfg(test)]
 mod tests {
     use std::process::{Command, Stdio};
 
+    use chrono::Utc;
     use docker::models::HostConfig;
+    use edgelet_core::{ModuleStatus, RestartDecision};
+    use edgelet_settings::base::image::ImagePruneSettings;
+    use edgelet_settings::module::RestartPolicy;
+    use edgelet_settings::watchdog::RestartBackoff;
+    use edgelet_test_utils::JsonConnector;
 
     use super::*;
 
+    #[test]
+    fn prune_containers_skips_modules() {
+        assert_eq!(
+            prune_containers_filters().unwrap(),
+            r#"{"label!":["net.azure-devices.edge.owner=Microsoft.Azure.Devices.Edge.Agent"]}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn only_requested_stops_are_recorded() {
+        let dir = std::env::temp_dir().join(format!("stop_all_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let stopped_path = dir.join("stopped_modules.json");
+
+        // Every request gets the container list back, so the stops themselves fail. The stops are
+        // recorded before they are sent to Docker, which is all this test is about.
+        let containers =
+            r#"[{"Names":["/sensor"],"Image":"sensor:1.0","ImageID":"sha256:1234","Labels":{}}]"#;
+        let (create_socket_channel, _create_socket_receiver) =
+            tokio::sync::mpsc::unbounded_channel();
+        let runtime = DockerModuleRuntime {
+            client: DockerApiClient::new(JsonConnector::ok(containers)),
+            system_resources: Arc::new(Mutex::new(System::new())),
+            create_socket_channel,
+            allow_elevated_docker_permissions: false,
+            resource_quotas: ResourceQuotas::default(),
+            container_policy: ContainerPolicy::default(),
+            content_trust: None,
+            image_pull: PullSettings::default(),
+            pull_progress: PullProgressBus::default(),
+            pull_directory: dir.join("pull"),
+            loaded_images: Arc::default(),
+            additional_info: BTreeMap::new(),
+            image_use_data: ImagePruneData::new(&dir, ImagePruneSettings::default()).unwrap(),
+            restart_tracker: RestartTracker::load(RestartBackoff::default(), stopped_path.clone()),
+        };
+        let stopped = ModuleRuntimeState::default().with_status(ModuleStatus::Stopped);
+
+        // Modules stopped by edged itself are restarted after edged restarts.
+        runtime.stop_all(None).await.unwrap();
+        let tracker = RestartTracker::load(RestartBackoff::default(), stopped_path.clone());
+        assert!(matches!(
+            tracker.evaluate("sensor", RestartPolicy::Always, &stopped, Utc::now()),
+            RestartDecision::Wait(_)
+        ));
+
+        // Modules stopped on request are not.
+        runtime.stop("sensor", None).await.unwrap_err();
+        let tracker = RestartTracker::load(RestartBackoff::default(), stopped_path);
+        assert_eq!(
+            tracker.evaluate("sensor", RestartPolicy::Always, &stopped, Utc::now()),
+            RestartDecision::None
+        );
+
+        drop(runtime);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
     #[test]
     fn parse_top_response_returns_pid_array() {
         let response = ContainerTopResponse {
@@ -0,0 +1,145 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+pub(crate) struct Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    cert_inventory: tokio::sync::mpsc::UnboundedSender<edgelet_core::CertInventoryRequest>,
+    _runtime: std::marker::PhantomData<M>,
+}
+
+const PATH: &str = "/certificates";
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub(crate) struct ListResponse {
+    certificates: Vec<edgelet_core::CertInfo>,
+}
+
+#[async_trait::async_trait]
+impl<M> http_common::server::Route for Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    type ApiVersion = edgelet_http::ApiVersion;
+    fn api_version() -> &'static dyn http_common::DynRangeBounds<Self::ApiVersion> {
+        &((edgelet_http::ApiVersion::V2026_10_18)..)
+    }
+
+    type Service = crate::Service<M>;
+    fn from_uri(
+        service: &Self::Service,
+        path: &str,
+        _query: &[(std::borrow::Cow<'_, str>, std::borrow::Cow<'_, str>)],
+        _extensions: &http::Extensions,
+    ) -> Option<Self> {
+        if path != PATH {
+            return None;
+        }
+
+        Some(Route {
+            cert_inventory: service.cert_inventory.clone(),
+            _runtime: std::marker::PhantomData,
+        })
+    }
+
+    type DeleteBody = serde::de::IgnoredAny;
+
+    async fn get(self) -> http_common::server::RouteResponse {
+        let (response_tx, response_rx) = tokio::sync::oneshot::channel();
+
+        self.cert_inventory
+            .send(response_tx)
+            .map_err(|_| edgelet_http::error::server_error("failed to request certificates"))?;
+
+        let certificates = response_rx
+            .await
+            .map_err(|_| edgelet_http::error::server_error("certificate listing was dropped"))?
+            .map_err(edgelet_http::error::server_error)?;
+
+        let res = ListResponse { certificates };
+        let res = http_common::server::response::json(hyper::StatusCode::OK, &res);
+
+        Ok(res)
+    }
+
+    type PostBody = serde::de::IgnoredAny;
+
+    type PutBody = serde::de::IgnoredAny;
+}
+
+#[cfg(test)]
+mod tests {
+    use http_body_util::BodyExt as _;
+
+    use http_common::server::Route;
+
+    use edgelet_test_utils::{test_route_err, test_route_ok};
+
+    #[test]
+    fn parse_uri() {
+        // Valid URI
+        test_route_ok!(super::PATH);
+
+        // Extra character at beginning of URI
+        test_route_err!(&format!("a{}", super::PATH));
+
+        // Extra character at end of URI
+        test_route_err!(&format!("{}a", super::PATH));
+    }
+
+    #[tokio::test]
+    async fn list_tx_rx() {
+        let runtime = edgelet_test_utils::runtime::Runtime::default();
+        let (service, mut cert_inventory_rx) = crate::Service::new_with_cert_inventory(runtime);
+
+        tokio::spawn(async move {
+            let response = cert_inventory_rx.recv().await.unwrap();
+
+            let cert = edgelet_core::CertInfo {
+                kind: edgelet_core::CertKind::EdgeCa,
+                cert_id: "aziot-edged-ca".to_string(),
+                module_id: None,
+                subject: "CN=aziot-edge CA test-device".to_string(),
+                subject_alt_names: Vec::new(),
+                issuers: vec!["CN=aziot-edge CA test-device".to_string()],
+                key_type: "EC P-256".to_string(),
+                not_before: chrono::DateTime::UNIX_EPOCH,
+                not_after: chrono::DateTime::UNIX_EPOCH,
+            };
+            response.send(Ok(vec![cert])).unwrap();
+
+            let response = cert_inventory_rx.recv().await.unwrap();
+            response.send(Err("certd unavailable".to_string())).unwrap();
+        });
+
+        // The certificates are returned.
+        let route = super::Route::from_uri(
+            &service,
+            super::PATH,
+            &Vec::new(),
+            &edgelet_test_utils::route::extensions(),
+        )
+        .expect("valid route wasn't parsed");
+        let response = route.get().await.unwrap();
+        assert_eq!(hyper::StatusCode::OK, response.status());
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body: super::ListResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(1, body.certificates.len());
+        assert_eq!(edgelet_core::CertKind::EdgeCa, body.certificates[0].kind);
+
+        // Failed listings are server errors.
+        let route = super::Route::from_uri(
+            &service,
+            super::PATH,
+            &Vec::new(),
+            &edgelet_test_utils::route::extensions(),
+        )
+        .expect("valid route wasn't parsed");
+        let response = route.get().await.unwrap_err();
+        assert_eq!(
+            hyper::StatusCode::INTERNAL_SERVER_ERROR,
+            response.status_code
+        );
+    }
+}
@@ -0,0 +1,169 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+pub(crate) struct Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    check_health: edgelet_core::CheckHealth,
+    _runtime: std::marker::PhantomData<M>,
+}
+
+const PATH: &str = "/checks";
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub(crate) struct ListChecksResponse {
+    checks: Vec<edgelet_core::CheckRecord>,
+}
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub(crate) struct ReportChecksRequest {
+    checks: Vec<edgelet_core::CheckReport>,
+}
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub(crate) struct ReportChecksResponse {
+    transitions: Vec<edgelet_core::CheckTransition>,
+}
+
+#[async_trait::async_trait]
+impl<M> http_common::server::Route for Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    type ApiVersion = edgelet_http::ApiVersion;
+    fn api_version() -> &'static dyn http_common::DynRangeBounds<Self::ApiVersion> {
+        &((edgelet_http::ApiVersion::V2026_10_18)..)
+    }
+
+    type Service = crate::Service<M>;
+    fn from_uri(
+        service: &Self::Service,
+        path: &str,
+        _query: &[(std::borrow::Cow<'_, str>, std::borrow::Cow<'_, str>)],
+        _extensions: &http::Extensions,
+    ) -> Option<Self> {
+        if path != PATH {
+            return None;
+        }
+
+        Some(Route {
+            check_health: service.check_health.clone(),
+            _runtime: std::marker::PhantomData,
+        })
+    }
+
+    type DeleteBody = serde::de::IgnoredAny;
+
+    async fn get(self) -> http_common::server::RouteResponse {
+        let res = ListChecksResponse {
+            checks: self.check_health.records(),
+        };
+        let res = http_common::server::response::json(hyper::StatusCode::OK, &res);
+
+        Ok(res)
+    }
+
+    type PostBody = ReportChecksRequest;
+    async fn post(self, body: Option<Self::PostBody>) -> http_common::server::RouteResponse {
+        let Some(body) = body else {
+            return Err(edgelet_http::error::bad_request("missing request body"));
+        };
+
+        let transitions = self.check_health.record(body.checks, chrono::Utc::now());
+
+        let res = ReportChecksResponse { transitions };
+        let res = http_common::server::response::json(hyper::StatusCode::OK, &res);
+
+        Ok(res)
+    }
+
+    type PutBody = serde::de::IgnoredAny;
+}
+
+#[cfg(test)]
+mod tests {
+    use http_body_util::BodyExt as _;
+
+    use http_common::server::Route;
+
+    use edgelet_test_utils::{test_route_err, test_route_ok};
+
+    #[test]
+    fn parse_uri() {
+        // Valid URI
+        test_route_ok!(super::PATH);
+
+        // Extra character at beginning of URI
+        test_route_err!(&format!("a{}", super::PATH));
+
+        // Extra character at end of URI
+        test_route_err!(&format!("{}a", super::PATH));
+    }
+
+    #[tokio::test]
+    async fn report_and_list() {
+        let runtime = edgelet_test_utils::runtime::Runtime::default();
+        let service = crate::Service::new(runtime);
+
+        let report = |status| super::ReportChecksRequest {
+            checks: vec![edgelet_core::CheckReport {
+                id: "container-connect-upstream-mqtt".to_string(),
+                description: "container on the IoT Edge module network can connect to upstream"
+                    .to_string(),
+                status,
+                details: Vec::new(),
+            }],
+        };
+
+        for (status, from) in [
+            (edgelet_core::CheckStatus::Ok, None),
+            (
+                edgelet_core::CheckStatus::Failed,
+                Some(edgelet_core::CheckStatus::Ok),
+            ),
+        ] {
+            let route = super::Route::from_uri(
+                &service,
+                super::PATH,
+                &Vec::new(),
+                &edgelet_test_utils::route::extensions(),
+            )
+            .expect("valid route wasn't parsed");
+            let response = route.post(Some(report(status))).await.unwrap();
+            assert_eq!(hyper::StatusCode::OK, response.status());
+
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let body: super::ReportChecksResponse = serde_json::from_slice(&body).unwrap();
+            assert_eq!(1, body.transitions.len());
+            assert_eq!(from, body.transitions[0].from);
+            assert_eq!(status, body.transitions[0].to);
+        }
+
+        let route = super::Route::from_uri(
+            &service,
+            super::PATH,
+            &Vec::new(),
+            &edgelet_test_utils::route::extensions(),
+        )
+        .expect("valid route wasn't parsed");
+        let response = route.get().await.unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body: super::ListChecksResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(1, body.checks.len());
+        assert_eq!(
+            edgelet_core::CheckStatus::Failed,
+            body.checks[0].report.status
+        );
+        assert_eq!(
+            Some(edgelet_core::CheckStatus::Ok),
+            body.checks[0].previous_status
+        );
+    }
+
+    #[tokio::test]
+    async fn bad_request() {
+        let route = test_route_ok!(super::PATH);
+        let response = route.post(None).await.unwrap_err();
+        assert_eq!(hyper::StatusCode::BAD_REQUEST, response.status_code);
+    }
+}
@@ -0,0 +1,131 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+pub(crate) struct Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    key_rotation: tokio::sync::mpsc::UnboundedSender<edgelet_core::EncryptionKeyRotation>,
+    _runtime: std::marker::PhantomData<M>,
+}
+
+const PATH: &str = "/encryption-key/rotate";
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub(crate) struct RotateResponse {
+    version: u32,
+}
+
+#[async_trait::async_trait]
+impl<M> http_common::server::Route for Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    type ApiVersion = edgelet_http::ApiVersion;
+    fn api_version() -> &'static dyn http_common::DynRangeBounds<Self::ApiVersion> {
+        &((edgelet_http::ApiVersion::V2026_10_18)..)
+    }
+
+    type Service = crate::Service<M>;
+    fn from_uri(
+        service: &Self::Service,
+        path: &str,
+        _query: &[(std::borrow::Cow<'_, str>, std::borrow::Cow<'_, str>)],
+        _extensions: &http::Extensions,
+    ) -> Option<Self> {
+        if path != PATH {
+            return None;
+        }
+
+        Some(Route {
+            key_rotation: service.key_rotation.clone(),
+            _runtime: std::marker::PhantomData,
+        })
+    }
+
+    type DeleteBody = serde::de::IgnoredAny;
+
+    type PostBody = serde::de::IgnoredAny;
+    async fn post(self, _body: Option<Self::PostBody>) -> http_common::server::RouteResponse {
+        let (response_tx, response_rx) = tokio::sync::oneshot::channel();
+
+        self.key_rotation.send(response_tx).map_err(|_| {
+            edgelet_http::error::server_error("failed to request encryption key rotation")
+        })?;
+
+        let version = response_rx
+            .await
+            .map_err(|_| edgelet_http::error::server_error("encryption key rotation was dropped"))?
+            .map_err(edgelet_http::error::server_error)?;
+
+        let res = RotateResponse { version };
+        let res = http_common::server::response::json(hyper::StatusCode::OK, &res);
+
+        Ok(res)
+    }
+
+    type PutBody = serde::de::IgnoredAny;
+}
+
+#[cfg(test)]
+mod tests {
+    use http_body_util::BodyExt as _;
+
+    use http_common::server::Route;
+
+    use edgelet_test_utils::{test_route_err, test_route_ok};
+
+    #[test]
+    fn parse_uri() {
+        // Valid URI
+        test_route_ok!(super::PATH);
+
+        // Extra character at beginning of URI
+        test_route_err!(&format!("a{}", super::PATH));
+
+        // Extra character at end of URI
+        test_route_err!(&format!("{}a", super::PATH));
+    }
+
+    #[tokio::test]
+    async fn rotate_tx_rx() {
+        let runtime = edgelet_test_utils::runtime::Runtime::default();
+        let (service, mut key_rotation_rx) = crate::Service::new_with_key_rotation(runtime);
+
+        tokio::spawn(async move {
+            let response = key_rotation_rx.recv().await.unwrap();
+            response.send(Ok(3)).unwrap();
+
+            let response = key_rotation_rx.recv().await.unwrap();
+            response.send(Err("keyd unavailable".to_string())).unwrap();
+        });
+
+        // The version of the new key is returned.
+        let route = super::Route::from_uri(
+            &service,
+            super::PATH,
+            &Vec::new(),
+            &edgelet_test_utils::route::extensions(),
+        )
+        .expect("valid route wasn't parsed");
+        let response = route.post(None).await.unwrap();
+        assert_eq!(hyper::StatusCode::OK, response.status());
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body: super::RotateResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(3, body.version);
+
+        // Failed rotations are server errors.
+        let route = super::Route::from_uri(
+            &service,
+            super::PATH,
+            &Vec::new(),
+            &edgelet_test_utils::route::extensions(),
+        )
+        .expect("valid route wasn't parsed");
+        let response = route.post(None).await.unwrap_err();
+        assert_eq!(
+            hyper::StatusCode::INTERNAL_SERVER_ERROR,
+            response.status_code
+        );
+    }
+}
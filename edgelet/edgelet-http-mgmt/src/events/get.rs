@@ -0,0 +1,161 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use futures_util::StreamExt;
+use http_body_util::BodyExt;
+
+pub(crate) struct Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    events: edgelet_core::EventBus,
+    _runtime: std::marker::PhantomData<M>,
+
+    since: Option<String>,
+    follow: Option<String>,
+}
+
+const PATH: &str = "/events";
+
+#[async_trait::async_trait]
+impl<M> http_common::server::Route for Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    type ApiVersion = edgelet_http::ApiVersion;
+    fn api_version() -> &'static dyn http_common::DynRangeBounds<Self::ApiVersion> {
+        &((edgelet_http::ApiVersion::V2026_10_18)..)
+    }
+
+    type Service = crate::Service<M>;
+    fn from_uri(
+        service: &Self::Service,
+        path: &str,
+        query: &[(std::borrow::Cow<'_, str>, std::borrow::Cow<'_, str>)],
+        _extensions: &http::Extensions,
+    ) -> Option<Self> {
+        if path != PATH {
+            return None;
+        }
+
+        let since = edgelet_http::find_query("since", query);
+        let follow = edgelet_http::find_query("follow", query);
+
+        Some(Route {
+            events: service.events.clone(),
+            _runtime: std::marker::PhantomData,
+
+            since,
+            follow,
+        })
+    }
+
+    type DeleteBody = serde::de::IgnoredAny;
+
+    async fn get(self) -> http_common::server::RouteResponse {
+        let since = self.since()?;
+        let follow = self.follow()?;
+
+        let (past, receiver) = self.events.subscribe(since);
+
+        let live = if follow {
+            futures_util::stream::unfold(receiver, |mut receiver| async move {
+                loop {
+                    match receiver.recv().await {
+                        Ok(event) => return Some((event, receiver)),
+                        Err(tokio::sync::broadcast::error::RecvError::Lagged(missed)) => {
+                            log::warn!("Event stream client fell behind; {missed} events dropped");
+                        }
+                        Err(tokio::sync::broadcast::error::RecvError::Closed) => return None,
+                    }
+                }
+            })
+            .left_stream()
+        } else {
+            futures_util::stream::empty().right_stream()
+        };
+
+        let body = futures_util::stream::iter(past).chain(live).map(|event| {
+            let mut line = serde_json::to_vec(&event)?;
+            line.push(b'\n');
+
+            Ok::<_, Box<dyn std::error::Error + Send + Sync>>(hyper::body::Frame::data(
+                hyper::body::Bytes::from(line),
+            ))
+        });
+
+        let res = hyper::Response::builder()
+            .status(hyper::StatusCode::OK)
+            .header(hyper::header::CONTENT_TYPE, "application/x-ndjson")
+            .body(http_body_util::StreamBody::new(body).boxed())
+            .expect("cannot fail to build hyper response");
+        Ok(res)
+    }
+
+    type PostBody = serde::de::IgnoredAny;
+
+    type PutBody = serde::de::IgnoredAny;
+}
+
+impl<M> Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    fn since(&self) -> Result<Option<chrono::DateTime<chrono::Utc>>, http_common::server::Error> {
+        let Some(since) = &self.since else {
+            return Ok(None);
+        };
+
+        let since = edgelet_core::parse_since(since)
+            .ok()
+            .and_then(|since| chrono::DateTime::from_timestamp(i64::from(since), 0))
+            .ok_or_else(|| edgelet_http::error::bad_request("invalid parameter: since"))?;
+
+        Ok(Some(since))
+    }
+
+    fn follow(&self) -> Result<bool, http_common::server::Error> {
+        let Some(follow) = &self.follow else {
+            return Ok(false);
+        };
+
+        std::str::FromStr::from_str(follow)
+            .map_err(|_| edgelet_http::error::bad_request("invalid parameter: follow"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use edgelet_test_utils::{test_route_err, test_route_ok};
+
+    #[test]
+    fn parse_uri() {
+        // Valid URI
+        test_route_ok!(super::PATH);
+
+        // Extra character at beginning of URI
+        test_route_err!(&format!("a{}", super::PATH));
+
+        // Extra character at end of URI
+        test_route_err!(&format!("{}a", super::PATH));
+    }
+
+    #[test]
+    fn parse_query() {
+        // Default values when not provided
+        let route = test_route_ok!(super::PATH);
+        assert_eq!(None, route.since().unwrap());
+        assert!(!route.follow().unwrap());
+
+        // Valid values
+        let route = test_route_ok!(super::PATH, ("since", "5"), ("follow", "true"));
+        assert_eq!(5, route.since().unwrap().unwrap().timestamp());
+        assert!(route.follow().unwrap());
+
+        // Invalid values
+        let route = test_route_ok!(super::PATH, ("since", "invalid"));
+        assert!(route.since().is_err());
+
+        let route = test_route_ok!(super::PATH, ("follow", "invalid"));
+        assert!(route.follow().is_err());
+    }
+}
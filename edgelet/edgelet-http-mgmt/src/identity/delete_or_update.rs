@@ -16,6 +16,7 @@ where
     pid: libc::pid_t,
     module_id: String,
     runtime: std::sync::Arc<tokio::sync::Mutex<M>>,
+    events: edgelet_core::EventBus,
 }
 
 #[async_trait::async_trait]
@@ -51,6 +52,7 @@ where
             pid,
             module_id: module_id.into_owned(),
             runtime: service.runtime.clone(),
+            events: service.events.clone(),
         })
     }
 
@@ -60,10 +62,21 @@ where
 
         let client = self.client.lock().await;
 
-        match client.delete_identity(&self.module_id).await {
-            Ok(()) => Ok(http_common::server::response::no_content()),
-            Err(err) => Err(edgelet_http::error::server_error(err.to_string())),
-        }
+        client
+            .delete_identity(&self.module_id)
+            .await
+            .map_err(|err| edgelet_http::error::server_error(err.to_string()))?;
+
+        // The workload API deletes the module's keys when its identity is deleted.
+        self.events.publish(
+            edgelet_core::ModuleEvent::new(
+                std::time::SystemTime::now().into(),
+                edgelet_core::EventKind::IdentityDeleted,
+            )
+            .with_module(Some(self.module_id)),
+        );
+
+        Ok(http_common::server::response::no_content())
     }
 
     type PostBody = serde::de::IgnoredAny;
@@ -149,9 +162,14 @@ mod tests {
         // Delete Identity
         let mut route = test_route_ok!(TEST_PATH);
         route.client = client.clone();
+        let (_, mut events) = route.events.subscribe(None);
 
         route.delete(None).await.unwrap();
 
+        let event = events.try_recv().unwrap();
+        assert_eq!(edgelet_core::EventKind::IdentityDeleted, event.kind());
+        assert_eq!(Some("testModule"), event.module());
+
         // Update Identity should now fail because the Identity was deleted
         let mut route = test_route_ok!(TEST_PATH);
         route.client = client.clone();
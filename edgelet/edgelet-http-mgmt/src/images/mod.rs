@@ -0,0 +1,6 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+pub(super) mod import;
+pub(super) mod prune;
+pub(super) mod pulls;
+pub(super) mod usage;
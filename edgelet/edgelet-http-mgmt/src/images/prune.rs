@@ -0,0 +1,180 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+pub(crate) struct Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    image_prune: tokio::sync::mpsc::UnboundedSender<edgelet_core::ImagePruneRequest>,
+    _runtime: std::marker::PhantomData<M>,
+}
+
+const PATH: &str = "/images/prune";
+
+#[derive(Debug, Default, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PruneRequest {
+    #[serde(default)]
+    dry_run: bool,
+
+    /// Overrides the configured `image_age_cleanup_threshold`, e.g. "2d".
+    #[serde(default, with = "humantime_serde")]
+    older_than: Option<std::time::Duration>,
+}
+
+#[async_trait::async_trait]
+impl<M> http_common::server::Route for Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    type ApiVersion = edgelet_http::ApiVersion;
+    fn api_version() -> &'static dyn http_common::DynRangeBounds<Self::ApiVersion> {
+        &((edgelet_http::ApiVersion::V2026_10_18)..)
+    }
+
+    type Service = crate::Service<M>;
+    fn from_uri(
+        service: &Self::Service,
+        path: &str,
+        _query: &[(std::borrow::Cow<'_, str>, std::borrow::Cow<'_, str>)],
+        _extensions: &http::Extensions,
+    ) -> Option<Self> {
+        if path != PATH {
+            return None;
+        }
+
+        Some(Route {
+            image_prune: service.image_prune.clone(),
+            _runtime: std::marker::PhantomData,
+        })
+    }
+
+    type DeleteBody = serde::de::IgnoredAny;
+
+    type PostBody = PruneRequest;
+    async fn post(self, body: Option<Self::PostBody>) -> http_common::server::RouteResponse {
+        let body = body.unwrap_or_default();
+
+        let (response_tx, response_rx) = tokio::sync::oneshot::channel();
+
+        self.image_prune
+            .send(edgelet_core::ImagePruneRequest {
+                older_than: body.older_than,
+                dry_run: body.dry_run,
+                response: response_tx,
+            })
+            .map_err(|_| edgelet_http::error::server_error("failed to request image pruning"))?;
+
+        let report = response_rx
+            .await
+            .map_err(|_| edgelet_http::error::server_error("image pruning was dropped"))?
+            .map_err(edgelet_http::error::server_error)?;
+
+        let res = http_common::server::response::json(hyper::StatusCode::OK, &report);
+
+        Ok(res)
+    }
+
+    type PutBody = serde::de::IgnoredAny;
+}
+
+#[cfg(test)]
+mod tests {
+    use http_body_util::BodyExt as _;
+
+    use http_common::server::Route;
+
+    use edgelet_test_utils::{test_route_err, test_route_ok};
+
+    #[test]
+    fn parse_uri() {
+        // Valid URI
+        test_route_ok!(super::PATH);
+
+        // Extra character at beginning of URI
+        test_route_err!(&format!("a{}", super::PATH));
+
+        // Extra character at end of URI
+        test_route_err!(&format!("{}a", super::PATH));
+    }
+
+    #[test]
+    fn parse_body() {
+        let body: super::PruneRequest = serde_json::from_str("{}").unwrap();
+        assert!(!body.dry_run);
+        assert_eq!(None, body.older_than);
+
+        let body: super::PruneRequest =
+            serde_json::from_str(r#"{ "dryRun": true, "olderThan": "2d" }"#).unwrap();
+        assert!(body.dry_run);
+        assert_eq!(Some(std::time::Duration::from_hours(48)), body.older_than);
+
+        serde_json::from_str::<super::PruneRequest>(r#"{ "olderThan": "soon" }"#).unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn prune_tx_rx() {
+        let runtime = edgelet_test_utils::runtime::Runtime::default();
+        let (service, mut image_prune_rx) = crate::Service::new_with_image_prune(runtime);
+
+        tokio::spawn(async move {
+            let request = image_prune_rx.recv().await.unwrap();
+            assert!(request.dry_run);
+            assert_eq!(
+                Some(std::time::Duration::from_hours(48)),
+                request.older_than
+            );
+
+            let report = edgelet_core::ImagePruneReport {
+                dry_run: true,
+                images: vec![edgelet_core::PrunedImage {
+                    id: "sha256:unused".to_string(),
+                    size: Some(1024),
+                    last_used: chrono::DateTime::UNIX_EPOCH,
+                    reason: "unused".to_string(),
+                }],
+                space_reclaimed: 1024,
+            };
+            request.response.send(Ok(report)).unwrap();
+
+            let request = image_prune_rx.recv().await.unwrap();
+            request
+                .response
+                .send(Err("runtime unavailable".to_string()))
+                .unwrap();
+        });
+
+        // The images that would be removed are returned.
+        let route = super::Route::from_uri(
+            &service,
+            super::PATH,
+            &Vec::new(),
+            &edgelet_test_utils::route::extensions(),
+        )
+        .expect("valid route wasn't parsed");
+        let body = super::PruneRequest {
+            dry_run: true,
+            older_than: Some(std::time::Duration::from_hours(48)),
+        };
+        let response = route.post(Some(body)).await.unwrap();
+        assert_eq!(hyper::StatusCode::OK, response.status());
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body: edgelet_core::ImagePruneReport = serde_json::from_slice(&body).unwrap();
+        assert_eq!(1024, body.space_reclaimed);
+        assert_eq!("sha256:unused", body.images[0].id);
+
+        // Failed runs are server errors.
+        let route = super::Route::from_uri(
+            &service,
+            super::PATH,
+            &Vec::new(),
+            &edgelet_test_utils::route::extensions(),
+        )
+        .expect("valid route wasn't parsed");
+        let response = route.post(None).await.unwrap_err();
+        assert_eq!(
+            hyper::StatusCode::INTERNAL_SERVER_ERROR,
+            response.status_code
+        );
+    }
+}
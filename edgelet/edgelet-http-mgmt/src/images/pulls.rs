@@ -0,0 +1,146 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use futures_util::StreamExt;
+use http_body_util::BodyExt;
+
+use edgelet_core::{ModuleRegistry, ModuleRuntime};
+
+pub(crate) struct Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    runtime: std::sync::Arc<tokio::sync::Mutex<M>>,
+
+    follow: Option<String>,
+}
+
+const PATH: &str = "/images/pulls";
+
+#[async_trait::async_trait]
+impl<M> http_common::server::Route for Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    type ApiVersion = edgelet_http::ApiVersion;
+    fn api_version() -> &'static dyn http_common::DynRangeBounds<Self::ApiVersion> {
+        &((edgelet_http::ApiVersion::V2026_10_18)..)
+    }
+
+    type Service = crate::Service<M>;
+    fn from_uri(
+        service: &Self::Service,
+        path: &str,
+        query: &[(std::borrow::Cow<'_, str>, std::borrow::Cow<'_, str>)],
+        _extensions: &http::Extensions,
+    ) -> Option<Self> {
+        if path != PATH {
+            return None;
+        }
+
+        let follow = edgelet_http::find_query("follow", query);
+
+        Some(Route {
+            runtime: service.runtime.clone(),
+
+            follow,
+        })
+    }
+
+    type DeleteBody = serde::de::IgnoredAny;
+
+    async fn get(self) -> http_common::server::RouteResponse {
+        let follow = self.follow()?;
+
+        let (current, receiver) = {
+            let runtime = self.runtime.lock().await;
+            runtime.registry().pull_progress().subscribe()
+        };
+
+        let live = if follow {
+            futures_util::stream::unfold(receiver, |mut receiver| async move {
+                loop {
+                    match receiver.recv().await {
+                        Ok(progress) => return Some((progress, receiver)),
+                        Err(tokio::sync::broadcast::error::RecvError::Lagged(missed)) => {
+                            log::warn!(
+                                "Pull progress client fell behind; {missed} updates dropped"
+                            );
+                        }
+                        Err(tokio::sync::broadcast::error::RecvError::Closed) => return None,
+                    }
+                }
+            })
+            .left_stream()
+        } else {
+            futures_util::stream::empty().right_stream()
+        };
+
+        let body = futures_util::stream::iter(current)
+            .chain(live)
+            .map(|progress| {
+                let mut line = serde_json::to_vec(&progress)?;
+                line.push(b'\n');
+
+                Ok::<_, Box<dyn std::error::Error + Send + Sync>>(hyper::body::Frame::data(
+                    hyper::body::Bytes::from(line),
+                ))
+            });
+
+        let res = hyper::Response::builder()
+            .status(hyper::StatusCode::OK)
+            .header(hyper::header::CONTENT_TYPE, "application/x-ndjson")
+            .body(http_body_util::StreamBody::new(body).boxed())
+            .expect("cannot fail to build hyper response");
+        Ok(res)
+    }
+
+    type PostBody = serde::de::IgnoredAny;
+
+    type PutBody = serde::de::IgnoredAny;
+}
+
+impl<M> Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    fn follow(&self) -> Result<bool, http_common::server::Error> {
+        let Some(follow) = &self.follow else {
+            return Ok(false);
+        };
+
+        std::str::FromStr::from_str(follow)
+            .map_err(|_| edgelet_http::error::bad_request("invalid parameter: follow"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use edgelet_test_utils::{test_route_err, test_route_ok};
+
+    #[test]
+    fn parse_uri() {
+        // Valid URI
+        test_route_ok!(super::PATH);
+
+        // Extra character at beginning of URI
+        test_route_err!(&format!("a{}", super::PATH));
+
+        // Extra character at end of URI
+        test_route_err!(&format!("{}a", super::PATH));
+    }
+
+    #[test]
+    fn parse_query() {
+        // Default value when not provided
+        let route = test_route_ok!(super::PATH);
+        assert!(!route.follow().unwrap());
+
+        // Valid value
+        let route = test_route_ok!(super::PATH, ("follow", "true"));
+        assert!(route.follow().unwrap());
+
+        // Invalid value
+        let route = test_route_ok!(super::PATH, ("follow", "invalid"));
+        assert!(route.follow().is_err());
+    }
+}
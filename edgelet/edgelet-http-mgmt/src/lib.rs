@@ -1,7 +1,12 @@
 // Copyright (c) Microsoft. All rights reserved.
 
+mod certs;
+mod checks;
 mod device_actions;
+mod encryption_key;
+mod events;
 mod identity;
+mod images;
 mod module;
 mod system_info;
 
@@ -19,6 +24,11 @@ where
     identity: std::sync::Arc<tokio::sync::Mutex<IdentityClient>>,
     runtime: std::sync::Arc<tokio::sync::Mutex<M>>,
     reprovision: tokio::sync::mpsc::UnboundedSender<edgelet_core::WatchdogAction>,
+    events: edgelet_core::EventBus,
+    key_rotation: tokio::sync::mpsc::UnboundedSender<edgelet_core::EncryptionKeyRotation>,
+    image_prune: tokio::sync::mpsc::UnboundedSender<edgelet_core::ImagePruneRequest>,
+    cert_inventory: tokio::sync::mpsc::UnboundedSender<edgelet_core::CertInventoryRequest>,
+    check_health: edgelet_core::CheckHealth,
 }
 
 impl<M> Service<M>
@@ -30,6 +40,10 @@ where
         identity_socket: &url::Url,
         runtime: M,
         reprovision: tokio::sync::mpsc::UnboundedSender<edgelet_core::WatchdogAction>,
+        events: edgelet_core::EventBus,
+        key_rotation: tokio::sync::mpsc::UnboundedSender<edgelet_core::EncryptionKeyRotation>,
+        image_prune: tokio::sync::mpsc::UnboundedSender<edgelet_core::ImagePruneRequest>,
+        cert_inventory: tokio::sync::mpsc::UnboundedSender<edgelet_core::CertInventoryRequest>,
     ) -> Result<Self, http_common::ConnectorError> {
         let connector = http_common::Connector::new(identity_socket)?;
 
@@ -46,6 +60,11 @@ where
             identity,
             runtime,
             reprovision,
+            events,
+            key_rotation,
+            image_prune,
+            cert_inventory,
+            check_health: edgelet_core::CheckHealth::default(),
         })
     }
 
@@ -63,10 +82,25 @@ where
         let (reprovision_tx, _) =
             tokio::sync::mpsc::unbounded_channel::<edgelet_core::WatchdogAction>();
 
+        // Likewise, key rotation requests fail in tests that don't use new_with_key_rotation,
+        // image prune requests in tests that don't use new_with_image_prune, and certificate
+        // inventory requests in tests that don't use new_with_cert_inventory.
+        let (key_rotation_tx, _) =
+            tokio::sync::mpsc::unbounded_channel::<edgelet_core::EncryptionKeyRotation>();
+        let (image_prune_tx, _) =
+            tokio::sync::mpsc::unbounded_channel::<edgelet_core::ImagePruneRequest>();
+        let (cert_inventory_tx, _) =
+            tokio::sync::mpsc::unbounded_channel::<edgelet_core::CertInventoryRequest>();
+
         Service {
             identity,
             runtime,
             reprovision: reprovision_tx,
+            events: edgelet_core::EventBus::default(),
+            key_rotation: key_rotation_tx,
+            image_prune: image_prune_tx,
+            cert_inventory: cert_inventory_tx,
+            check_health: edgelet_core::CheckHealth::default(),
         }
     }
 
@@ -87,15 +121,96 @@ where
         let (reprovision_tx, reprovision_rx) =
             tokio::sync::mpsc::unbounded_channel::<edgelet_core::WatchdogAction>();
 
+        let (key_rotation_tx, _) =
+            tokio::sync::mpsc::unbounded_channel::<edgelet_core::EncryptionKeyRotation>();
+        let (image_prune_tx, _) =
+            tokio::sync::mpsc::unbounded_channel::<edgelet_core::ImagePruneRequest>();
+        let (cert_inventory_tx, _) =
+            tokio::sync::mpsc::unbounded_channel::<edgelet_core::CertInventoryRequest>();
+
         (
             Service {
                 identity,
                 runtime,
                 reprovision: reprovision_tx,
+                events: edgelet_core::EventBus::default(),
+                key_rotation: key_rotation_tx,
+                image_prune: image_prune_tx,
+                cert_inventory: cert_inventory_tx,
+                check_health: edgelet_core::CheckHealth::default(),
             },
             reprovision_rx,
         )
     }
+
+    // Test constructor that returns the key rotation receiver. Only used by the encryption key
+    // rotation API tests.
+    #[cfg(test)]
+    pub fn new_with_key_rotation(
+        runtime: M,
+    ) -> (
+        Self,
+        tokio::sync::mpsc::UnboundedReceiver<edgelet_core::EncryptionKeyRotation>,
+    ) {
+        let (service, _) = Service::new_with_reprovision(runtime);
+
+        let (key_rotation_tx, key_rotation_rx) =
+            tokio::sync::mpsc::unbounded_channel::<edgelet_core::EncryptionKeyRotation>();
+
+        (
+            Service {
+                key_rotation: key_rotation_tx,
+                ..service
+            },
+            key_rotation_rx,
+        )
+    }
+
+    // Test constructor that returns the image prune receiver. Only used by the image prune
+    // API tests.
+    #[cfg(test)]
+    pub fn new_with_image_prune(
+        runtime: M,
+    ) -> (
+        Self,
+        tokio::sync::mpsc::UnboundedReceiver<edgelet_core::ImagePruneRequest>,
+    ) {
+        let (service, _) = Service::new_with_reprovision(runtime);
+
+        let (image_prune_tx, image_prune_rx) =
+            tokio::sync::mpsc::unbounded_channel::<edgelet_core::ImagePruneRequest>();
+
+        (
+            Service {
+                image_prune: image_prune_tx,
+                ..service
+            },
+            image_prune_rx,
+        )
+    }
+
+    // Test constructor that returns the certificate inventory receiver. Only used by the
+    // certificate list API tests.
+    #[cfg(test)]
+    pub fn new_with_cert_inventory(
+        runtime: M,
+    ) -> (
+        Self,
+        tokio::sync::mpsc::UnboundedReceiver<edgelet_core::CertInventoryRequest>,
+    ) {
+        let (service, _) = Service::new_with_reprovision(runtime);
+
+        let (cert_inventory_tx, cert_inventory_rx) =
+            tokio::sync::mpsc::unbounded_channel::<edgelet_core::CertInventoryRequest>();
+
+        (
+            Service {
+                cert_inventory: cert_inventory_tx,
+                ..service
+            },
+            cert_inventory_rx,
+        )
+    }
 }
 
 http_common::make_service! {
@@ -116,10 +231,24 @@ http_common::make_service! {
         identity::create_or_list::Route<M>,
         identity::delete_or_update::Route<M>,
 
+        images::import::Route<M>,
+        images::prune::Route<M>,
+        images::pulls::Route<M>,
+        images::usage::Route<M>,
+
+        certs::list::Route<M>,
+
         system_info::get::Route<M>,
+        system_info::metrics::Route<M>,
         system_info::resources::Route<M>,
         system_info::support_bundle::Route<M>,
 
         device_actions::reprovision::Route<M>,
+
+        encryption_key::rotate::Route<M>,
+
+        checks::list_or_report::Route<M>,
+
+        events::get::Route<M>,
     ],
 }
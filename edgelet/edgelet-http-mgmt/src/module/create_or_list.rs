@@ -72,7 +72,10 @@ where
 
         let runtime = self.runtime.lock().await;
 
-        super::create_module(&*runtime, body).await?;
+        if let Err(res) = super::create_module(&*runtime, body).await {
+            return res;
+        }
+
         let res = http_common::server::response::json(hyper::StatusCode::CREATED, &details);
 
         Ok(res)
@@ -83,6 +86,10 @@ where
 
 #[cfg(test)]
 mod tests {
+    use http_body_util::BodyExt as _;
+
+    use http_common::server::Route;
+
     use edgelet_test_utils::{test_route_err, test_route_ok};
 
     #[test]
@@ -97,4 +104,34 @@ mod tests {
         // Extra character at end of URI
         test_route_err!(&format!("{}a", super::PATH));
     }
+
+    #[tokio::test]
+    async fn policy_violations() {
+        let route = test_route_ok!(super::PATH);
+        let body = serde_json::from_value(serde_json::json!({
+            "name": "policyViolation",
+            "type": "test",
+            "config": { "settings": {} },
+            "imagePullPolicy": "never",
+        }))
+        .unwrap();
+
+        let response = route.post(Some(body)).await.unwrap();
+        assert_eq!(hyper::StatusCode::FORBIDDEN, response.status());
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            body["violations"],
+            serde_json::json!([
+                { "rule": "privileged", "value": null, "module": "policyViolation" },
+            ])
+        );
+        assert!(
+            body["message"]
+                .as_str()
+                .unwrap()
+                .contains("privileged mode is not allowed")
+        );
+    }
 }
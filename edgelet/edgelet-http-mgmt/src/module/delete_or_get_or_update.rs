@@ -138,7 +138,9 @@ where
             .await
             .map_err(|err| edgelet_http::error::runtime_error(&*runtime, &err))?;
 
-        super::create_module(&*runtime, body.clone()).await?;
+        if let Err(res) = super::create_module(&*runtime, body.clone()).await {
+            return res;
+        }
 
         let details = if start {
             match runtime.start(&self.module).await {
@@ -1,7 +1,5 @@
 // Copyright (c) Microsoft. All rights reserved.
 
-use http_body_util::{BodyExt as _, combinators::BoxBody};
-
 pub(crate) struct Route<M>
 where
     M: edgelet_core::ModuleRuntime + Send + Sync,
@@ -77,7 +75,7 @@ where
         let res = hyper::Response::builder()
             .status(hyper::StatusCode::OK)
             .header(hyper::header::CONTENT_TYPE, "text/plain")
-            .body(BoxBody::new(logs.map_err(Into::into)))
+            .body(logs)
             .expect("cannot fail to build hyper response");
         Ok(res)
     }
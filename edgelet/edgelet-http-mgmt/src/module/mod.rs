@@ -7,29 +7,29 @@ pub(super) mod restart_or_start_or_stop;
 pub(super) mod logs;
 pub(super) mod prepare_update;
 
-use edgelet_core::ModuleRegistry;
-
+/// Creates `module`. A module that breaks the container policy is answered with the rules that
+/// it breaks, which an `Error` cannot carry, so the error is the whole response to send.
 async fn create_module<M>(
     runtime: &M,
     module: edgelet_http::ModuleSpec,
-) -> Result<(), http_common::server::Error>
+) -> Result<(), http_common::server::RouteResponse>
 where
     M: edgelet_core::ModuleRuntime,
     <M as edgelet_core::ModuleRuntime>::Config: serde::de::DeserializeOwned,
 {
-    let module = module
-        .to_runtime_spec::<M>()
-        .map_err(|err| http_common::server::Error {
+    let module = module.to_runtime_spec::<M>().map_err(|err| {
+        Err(http_common::server::Error {
             status_code: http::StatusCode::BAD_REQUEST,
             message: err.into(),
-        })?;
+        })
+    })?;
 
-    pull_image(runtime, &module).await?;
+    pull_image(runtime, &module).await.map_err(Err)?;
 
     runtime
         .create(module)
         .await
-        .map_err(|err| edgelet_http::error::runtime_error(runtime, &err))?;
+        .map_err(|err| edgelet_http::error::runtime_error_response(runtime, &err))?;
 
     Ok(())
 }
@@ -41,23 +41,24 @@ async fn pull_image<M>(
 where
     M: edgelet_core::ModuleRuntime,
 {
-    match module.image_pull_policy() {
-        edgelet_settings::module::ImagePullPolicy::OnCreate => {
-            runtime
-                .registry()
-                .pull(module.config())
-                .await
-                .map_err(|err| edgelet_http::error::runtime_error(runtime, &err))?;
-
-            log::debug!("Successfully pulled new image for module {}", module.name());
-        }
-        edgelet_settings::module::ImagePullPolicy::Never => {
-            log::debug!(
-                "Skipped pulling image for module {} as per pull policy",
-                module.name()
-            );
-        }
+    if module.image_pull_policy() == edgelet_settings::module::ImagePullPolicy::Never {
+        log::debug!(
+            "Skipped pulling image for module {} as per pull policy",
+            module.name()
+        );
+
+        return Ok(());
     }
 
+    edgelet_core::get_image(
+        runtime.registry(),
+        module.config(),
+        module.image_pull_policy(),
+    )
+    .await
+    .map_err(|err| edgelet_http::error::runtime_error(runtime, &err))?;
+
+    log::debug!("Successfully got image for module {}", module.name());
+
     Ok(())
 }
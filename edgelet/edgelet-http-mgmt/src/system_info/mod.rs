@@ -1,5 +1,6 @@
 // Copyright (c) Microsoft. All rights reserved.
 
 pub(super) mod get;
+pub(super) mod metrics;
 pub(super) mod resources;
 pub(super) mod support_bundle;
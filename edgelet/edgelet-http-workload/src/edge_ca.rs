@@ -14,12 +14,59 @@ use test_common::client::KeyClient;
 #[cfg(test)]
 use test_common::client::KeyEngine;
 
+/// The Edge CA certificate replaced by the last renewal. It is served in the trust bundle until
+/// the overlap period ends, so that modules keep trusting peers whose certificates it issued.
+/// Like issued certificates, it is only kept while edged runs.
+#[derive(Clone, Default)]
+pub(crate) struct PreviousEdgeCa(
+    std::sync::Arc<std::sync::Mutex<Option<(Vec<u8>, chrono::DateTime<chrono::Utc>)>>>,
+);
+
+impl PreviousEdgeCa {
+    /// Keeps `cert` until `overlap` after `now`, or until it expires if that is sooner.
+    pub(crate) fn set(
+        &self,
+        cert: &openssl::x509::X509Ref,
+        overlap: std::time::Duration,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<(), openssl::error::ErrorStack> {
+        let expiration = crate::module::cert::renewal::unix_time(cert.not_after())?;
+        let until = chrono::TimeDelta::from_std(overlap)
+            .ok()
+            .and_then(|overlap| now.checked_add_signed(overlap))
+            .map_or(expiration, |until| until.min(expiration));
+
+        let cert = cert.to_pem()?;
+        *self.0.lock().expect("previous Edge CA lock poisoned") = Some((cert, until));
+
+        Ok(())
+    }
+
+    /// The PEM of the previous Edge CA certificate, if the overlap period is not over at `now`.
+    pub(crate) fn get(&self, now: chrono::DateTime<chrono::Utc>) -> Option<Vec<u8>> {
+        let mut previous = self.0.lock().expect("previous Edge CA lock poisoned");
+
+        match &*previous {
+            Some((cert, until)) if now < *until => Some(cert.clone()),
+            Some(_) => {
+                *previous = None;
+
+                None
+            }
+            None => None,
+        }
+    }
+}
+
 pub(crate) struct EdgeCaRenewal {
     rotate_key: bool,
     temp_cert: String,
+    overlap: std::time::Duration,
     cert_client: std::sync::Arc<tokio::sync::Mutex<CertClient>>,
     key_client: std::sync::Arc<tokio::sync::Mutex<KeyClient>>,
     key_connector: http_common::Connector,
+    previous_edge_ca: PreviousEdgeCa,
+    issued_certs: crate::module::cert::renewal::IssuedCerts,
     renewal_tx: tokio::sync::mpsc::UnboundedSender<edgelet_core::WatchdogAction>,
 }
 
@@ -30,6 +77,8 @@ impl EdgeCaRenewal {
         cert_client: std::sync::Arc<tokio::sync::Mutex<CertClient>>,
         key_client: std::sync::Arc<tokio::sync::Mutex<KeyClient>>,
         key_connector: http_common::Connector,
+        previous_edge_ca: PreviousEdgeCa,
+        issued_certs: crate::module::cert::renewal::IssuedCerts,
         renewal_tx: tokio::sync::mpsc::UnboundedSender<edgelet_core::WatchdogAction>,
     ) -> Self {
         let temp_cert = format!("{}-temp", config.edge_ca_cert);
@@ -37,9 +86,12 @@ impl EdgeCaRenewal {
         EdgeCaRenewal {
             rotate_key,
             temp_cert,
+            overlap: config.edge_ca_overlap,
             cert_client,
             key_client,
             key_connector,
+            previous_edge_ca,
+            issued_certs,
             renewal_tx,
         }
     }
@@ -232,7 +284,19 @@ impl cert_renewal::CertInterface for EdgeCaRenewal {
 
         log::info!("Edge CA was renewed");
 
-        // Modules should be restarted so that they request new server certs.
+        let now = chrono::Utc::now();
+
+        if let Err(err) = self
+            .previous_edge_ca
+            .set(&old_cert_chain[0], self.overlap, now)
+        {
+            log::warn!("Failed to keep previous Edge CA in the trust bundle: {err}");
+        }
+
+        // Modules should request new certs. Those that watch for renewals are told now; the
+        // watchdog restarts the others.
+        self.issued_certs.renew_all(now);
+
         if let Err(err) = self
             .renewal_tx
             .send(edgelet_core::WatchdogAction::EdgeCaRenewal)
@@ -244,6 +308,7 @@ impl cert_renewal::CertInterface for EdgeCaRenewal {
     }
 }
 
+/// The private and public keys of a keyd key pair, usable with openssl through the keyd engine.
 pub(crate) fn keys(
     key_connector: http_common::Connector,
     key_handle: &aziot_key_common::KeyHandle,
@@ -268,11 +333,11 @@ pub(crate) fn keys(
 
     let private_key = engine
         .load_private_key(&key_handle)
-        .map_err(|_| "failed to load edge ca private key".to_string())?;
+        .map_err(|_| "failed to load private key".to_string())?;
 
     let public_key = engine
         .load_public_key(&key_handle)
-        .map_err(|_| "failed to load edge ca public key".to_string())?;
+        .map_err(|_| "failed to load public key".to_string())?;
 
     Ok((private_key, public_key))
 }
@@ -339,6 +404,8 @@ mod tests {
             cert_client,
             key_client,
             key_connector,
+            super::PreviousEdgeCa::default(),
+            crate::module::cert::renewal::IssuedCerts::new(80),
             renewal_tx,
         )
     }
@@ -397,4 +464,35 @@ mod tests {
 
         renewal.get_key("test-key").await.unwrap_err();
     }
+
+    #[test]
+    fn previous_edge_ca() {
+        let (cert, _) = test_common::credential::test_certificate("test-ca");
+        let expiration = crate::module::cert::renewal::unix_time(cert.not_after()).unwrap();
+        let now = expiration - chrono::TimeDelta::days(2);
+
+        let previous = super::PreviousEdgeCa::default();
+        assert_eq!(None, previous.get(now));
+
+        // The previous Edge CA is kept for the overlap period.
+        previous
+            .set(&cert, std::time::Duration::from_hours(24), now)
+            .unwrap();
+        assert_eq!(
+            Some(cert.to_pem().unwrap()),
+            previous.get(now + chrono::TimeDelta::hours(23))
+        );
+        assert_eq!(None, previous.get(now + chrono::TimeDelta::hours(24)));
+
+        // It is not kept past its expiration.
+        previous
+            .set(&cert, std::time::Duration::from_hours(24 * 7), now)
+            .unwrap();
+        assert!(
+            previous
+                .get(expiration - chrono::TimeDelta::hours(1))
+                .is_some()
+        );
+        assert_eq!(None, previous.get(expiration));
+    }
 }
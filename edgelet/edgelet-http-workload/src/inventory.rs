@@ -0,0 +1,306 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use edgelet_core::{CertInfo, CertKind};
+
+#[cfg(not(test))]
+use aziot_cert_client_async::Client as CertClient;
+
+#[cfg(test)]
+use test_common::client::CertClient;
+
+use crate::module::cert::csr::CertificateType;
+use crate::module::cert::renewal::{IssuedCerts, unix_time};
+
+/// A certificate to list, by its ID in certd.
+struct Source {
+    kind: CertKind,
+    cert_id: String,
+    module_id: Option<String>,
+
+    /// Whether a missing certificate fails the listing. Optional certificates, like a manifest
+    /// trust bundle, may not exist.
+    required: bool,
+}
+
+/// Lists the Edge CA, the trust bundles, the device identity certificate and the module
+/// certificates issued since edged started. Module certificates are only tracked in memory, so
+/// those issued before edged last restarted are not listed.
+pub(crate) async fn list(
+    cert_client: &tokio::sync::Mutex<CertClient>,
+    config: &crate::WorkloadConfig,
+    issued_certs: &IssuedCerts,
+) -> Result<Vec<CertInfo>, String> {
+    let mut sources = vec![
+        Source {
+            kind: CertKind::EdgeCa,
+            cert_id: config.edge_ca_cert.clone(),
+            module_id: None,
+            required: true,
+        },
+        Source {
+            kind: CertKind::TrustBundle,
+            cert_id: config.trust_bundle.clone(),
+            module_id: None,
+            required: true,
+        },
+        Source {
+            kind: CertKind::ManifestTrustBundle,
+            cert_id: config.manifest_trust_bundle.clone(),
+            module_id: None,
+            required: false,
+        },
+    ];
+
+    if let Some(cert_id) = &config.device_identity_cert {
+        sources.push(Source {
+            kind: CertKind::DeviceIdentity,
+            cert_id: cert_id.clone(),
+            module_id: None,
+            required: false,
+        });
+    }
+
+    // A module certificate may have been deleted with its module since it was issued.
+    sources.extend(
+        issued_certs
+            .list()
+            .into_iter()
+            .map(|(cert_id, module_id, cert_type)| Source {
+                kind: match cert_type {
+                    CertificateType::Server => CertKind::ModuleServer,
+                    CertificateType::Identity => CertKind::ModuleIdentity,
+                },
+                cert_id,
+                module_id: Some(module_id),
+                required: false,
+            }),
+    );
+
+    let cert_client = cert_client.lock().await;
+
+    let mut certs = Vec::new();
+    for source in sources {
+        let pem = match cert_client.get_cert(&source.cert_id).await {
+            Ok(pem) => pem,
+            Err(err) if !source.required && is_not_found(&err) => continue,
+            Err(err) => {
+                return Err(format!(
+                    "failed to get certificate {}: {err}",
+                    source.cert_id
+                ));
+            }
+        };
+
+        let described = describe(&source, &pem)
+            .map_err(|err| format!("failed to parse certificate {}: {err}", source.cert_id))?;
+        certs.extend(described);
+    }
+
+    Ok(certs)
+}
+
+/// Whether certd does not have the certificate. The cert client passes on certd's error message
+/// rather than its status code, so a missing certificate is recognized by its message.
+fn is_not_found(err: &std::io::Error) -> bool {
+    err.kind() == std::io::ErrorKind::NotFound || err.to_string().contains("not found")
+}
+
+/// Describes the certificate in `pem`, whose chain follows it. A trust bundle is a set of
+/// unrelated CA certificates, so each of them is described on its own.
+fn describe(source: &Source, pem: &[u8]) -> Result<Vec<CertInfo>, openssl::error::ErrorStack> {
+    let chain = openssl::x509::X509::stack_from_pem(pem)?;
+
+    if matches!(
+        source.kind,
+        CertKind::TrustBundle | CertKind::ManifestTrustBundle
+    ) {
+        return chain
+            .iter()
+            .map(|cert| cert_info(source, cert, &[]))
+            .collect();
+    }
+
+    match chain.split_first() {
+        Some((cert, rest)) => Ok(vec![cert_info(source, cert, rest)?]),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn cert_info(
+    source: &Source,
+    cert: &openssl::x509::X509Ref,
+    chain: &[openssl::x509::X509],
+) -> Result<CertInfo, openssl::error::ErrorStack> {
+    let issuers = std::iter::once(cert)
+        .chain(chain.iter().map(std::ops::Deref::deref))
+        .map(|cert| name(cert.issuer_name()))
+        .collect();
+
+    let subject_alt_names = cert
+        .subject_alt_names()
+        .map(|names| names.iter().filter_map(general_name).collect())
+        .unwrap_or_default();
+
+    Ok(CertInfo {
+        kind: source.kind,
+        cert_id: source.cert_id.clone(),
+        module_id: source.module_id.clone(),
+        subject: name(cert.subject_name()),
+        subject_alt_names,
+        issuers,
+        key_type: key_type(cert)?,
+        not_before: unix_time(cert.not_before())?,
+        not_after: unix_time(cert.not_after())?,
+    })
+}
+
+/// Formats a name like "CN=edgeHub, O=Contoso".
+fn name(name: &openssl::x509::X509NameRef) -> String {
+    name.entries()
+        .map(|entry| {
+            let key = entry.object().nid().short_name().unwrap_or("?");
+            let value = String::from_utf8_lossy(entry.data().as_slice());
+
+            format!("{key}={value}")
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Formats a subject alternative name like "DNS:edgehub" or "IP:10.0.0.1".
+fn general_name(name: &openssl::x509::GeneralNameRef) -> Option<String> {
+    if let Some(dns) = name.dnsname() {
+        return Some(format!("DNS:{dns}"));
+    }
+
+    if let Some(ip) = name.ipaddress() {
+        let ip = match ip.len() {
+            4 => std::net::IpAddr::from(<[u8; 4]>::try_from(ip).ok()?),
+            16 => std::net::IpAddr::from(<[u8; 16]>::try_from(ip).ok()?),
+            _ => return None,
+        };
+
+        return Some(format!("IP:{ip}"));
+    }
+
+    name.uri()
+        .map(|uri| format!("URI:{uri}"))
+        .or_else(|| name.email().map(|email| format!("email:{email}")))
+}
+
+fn key_type(cert: &openssl::x509::X509Ref) -> Result<String, openssl::error::ErrorStack> {
+    let key = cert.public_key()?;
+
+    let key_type = match key.id() {
+        openssl::pkey::Id::RSA => format!("RSA {}", key.bits()),
+        openssl::pkey::Id::EC => match key.ec_key()?.group().curve_name() {
+            Some(openssl::nid::Nid::X9_62_PRIME256V1) => "EC P-256".to_string(),
+            Some(openssl::nid::Nid::SECP384R1) => "EC P-384".to_string(),
+            Some(openssl::nid::Nid::SECP521R1) => "EC P-521".to_string(),
+            _ => format!("EC {}", key.bits()),
+        },
+        openssl::pkey::Id::ED25519 => "Ed25519".to_string(),
+        _ => format!("unknown {}", key.bits()),
+    };
+
+    Ok(key_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use edgelet_core::CertKind;
+    use edgelet_settings::base::cert::KeyAlgorithm;
+
+    use super::{Source, describe, is_not_found};
+
+    fn cert(
+        common_name: &str,
+        algorithm: KeyAlgorithm,
+        issuer: Option<&openssl::x509::X509Ref>,
+    ) -> openssl::x509::X509 {
+        let keys = crate::module::cert::new_keys(algorithm).unwrap();
+
+        let mut name = openssl::x509::X509Name::builder().unwrap();
+        name.append_entry_by_nid(openssl::nid::Nid::COMMONNAME, common_name)
+            .unwrap();
+        let name = name.build();
+
+        let mut cert = openssl::x509::X509::builder().unwrap();
+        cert.set_subject_name(&name).unwrap();
+        cert.set_issuer_name(issuer.map_or(&*name, |issuer| issuer.subject_name()))
+            .unwrap();
+        cert.set_pubkey(&keys.1).unwrap();
+        cert.set_not_before(&openssl::asn1::Asn1Time::from_unix(1_000_000).unwrap())
+            .unwrap();
+        cert.set_not_after(&openssl::asn1::Asn1Time::from_unix(1_100_000).unwrap())
+            .unwrap();
+
+        let mut names = openssl::x509::extension::SubjectAlternativeName::new();
+        names.dns("edgehub").ip("10.0.0.1");
+        let names = names.build(&cert.x509v3_context(None, None)).unwrap();
+        cert.append_extension(names).unwrap();
+
+        cert.sign(&keys.0, crate::module::cert::signature_digest(&keys.0))
+            .unwrap();
+        cert.build()
+    }
+
+    fn source(kind: CertKind) -> Source {
+        Source {
+            kind,
+            cert_id: "test-cert".to_string(),
+            module_id: None,
+            required: true,
+        }
+    }
+
+    #[test]
+    fn describe_chain() {
+        let root = cert("root", KeyAlgorithm::Rsa2048, None);
+        let edge_ca = cert("edge CA", KeyAlgorithm::EcP384, Some(&root));
+        let server = cert("edgeHub", KeyAlgorithm::EcP256, Some(&edge_ca));
+
+        let mut pem = server.to_pem().unwrap();
+        pem.extend(edge_ca.to_pem().unwrap());
+        pem.extend(root.to_pem().unwrap());
+
+        let certs = describe(&source(CertKind::ModuleServer), &pem).unwrap();
+        assert_eq!(1, certs.len());
+        assert_eq!("CN=edgeHub", certs[0].subject);
+        assert_eq!(
+            vec!["DNS:edgehub".to_string(), "IP:10.0.0.1".to_string()],
+            certs[0].subject_alt_names
+        );
+        assert_eq!(
+            vec![
+                "CN=edge CA".to_string(),
+                "CN=root".to_string(),
+                "CN=root".to_string()
+            ],
+            certs[0].issuers
+        );
+        assert_eq!("EC P-256", certs[0].key_type);
+        assert_eq!(1_100_000, certs[0].not_after.timestamp());
+
+        // Every certificate of a trust bundle is listed.
+        let certs = describe(&source(CertKind::TrustBundle), &pem).unwrap();
+        let subjects: Vec<_> = certs.iter().map(|cert| cert.subject.as_str()).collect();
+        assert_eq!(vec!["CN=edgeHub", "CN=edge CA", "CN=root"], subjects);
+        assert_eq!("EC P-384", certs[1].key_type);
+        assert_eq!("RSA 2048", certs[2].key_type);
+    }
+
+    #[test]
+    fn not_found() {
+        assert!(is_not_found(&std::io::Error::from(
+            std::io::ErrorKind::NotFound
+        )));
+        assert!(is_not_found(&std::io::Error::other(
+            "parameter \"id\" has an invalid value\ncaused by: not found"
+        )));
+        assert!(!is_not_found(&std::io::Error::other("connection refused")));
+        assert!(!is_not_found(&std::io::Error::from(
+            std::io::ErrorKind::PermissionDenied
+        )));
+    }
+}
@@ -1,6 +1,7 @@
 // Copyright (c) Microsoft. All rights reserved.
 
 mod edge_ca;
+mod inventory;
 mod module;
 mod trust_bundle;
 
@@ -18,6 +19,9 @@ use test_common::client::IdentityClient;
 #[cfg(test)]
 use test_common::client::KeyClient;
 
+const MODULE_KEY_INDEX_FILENAME: &str = "module_keys.json";
+const ENCRYPTION_KEYS_FILENAME: &str = "encryption_keys.json";
+
 #[derive(Clone)]
 pub struct Service<M>
 where
@@ -35,6 +39,10 @@ where
     renewal_engine: Option<
         std::sync::Arc<tokio::sync::Mutex<cert_renewal::RenewalEngine<edge_ca::EdgeCaRenewal>>>,
     >,
+    module_keys: std::sync::Arc<tokio::sync::Mutex<module::key::KeyIndex>>,
+    master_keys: std::sync::Arc<tokio::sync::Mutex<module::data::master_key::MasterKeys>>,
+    issued_certs: module::cert::renewal::IssuedCerts,
+    previous_edge_ca: edge_ca::PreviousEdgeCa,
     config: WorkloadConfig,
 }
 
@@ -48,10 +56,11 @@ where
         runtime: M,
         renewal_tx: tokio::sync::mpsc::UnboundedSender<edgelet_core::WatchdogAction>,
         device_info: &aziot_identity_common::AzureIoTSpec,
-    ) -> Result<Self, http_common::ConnectorError> {
+    ) -> Result<Self, String> {
         let endpoints = settings.endpoints();
 
-        let key_connector = http_common::Connector::new(endpoints.aziot_keyd_url())?;
+        let key_connector = http_common::Connector::new(endpoints.aziot_keyd_url())
+            .map_err(|err| format!("invalid keyd endpoint: {err}"))?;
         let key_client = aziot_key_client_async::Client::new(
             aziot_key_common_http::ApiVersion::V2020_09_01,
             key_connector.clone(),
@@ -59,7 +68,8 @@ where
         );
         let key_client = std::sync::Arc::new(tokio::sync::Mutex::new(key_client));
 
-        let cert_connector = http_common::Connector::new(endpoints.aziot_certd_url())?;
+        let cert_connector = http_common::Connector::new(endpoints.aziot_certd_url())
+            .map_err(|err| format!("invalid certd endpoint: {err}"))?;
         let cert_client = aziot_cert_client_async::Client::new(
             aziot_cert_common_http::ApiVersion::V2020_09_01,
             cert_connector,
@@ -67,7 +77,8 @@ where
         );
         let cert_client = std::sync::Arc::new(tokio::sync::Mutex::new(cert_client));
 
-        let identity_connector = http_common::Connector::new(endpoints.aziot_identityd_url())?;
+        let identity_connector = http_common::Connector::new(endpoints.aziot_identityd_url())
+            .map_err(|err| format!("invalid identityd endpoint: {err}"))?;
         let identity_client = aziot_identity_client_async::Client::new(
             aziot_identity_common_http::ApiVersion::V2020_09_01,
             identity_connector,
@@ -78,6 +89,19 @@ where
         let runtime = std::sync::Arc::new(tokio::sync::Mutex::new(runtime));
         let config = WorkloadConfig::new(settings, device_info);
 
+        let module_keys =
+            module::key::KeyIndex::load(settings.homedir().join(MODULE_KEY_INDEX_FILENAME));
+        let module_keys = std::sync::Arc::new(tokio::sync::Mutex::new(module_keys));
+
+        let master_keys = module::data::master_key::MasterKeys::load(
+            settings.homedir().join(ENCRYPTION_KEYS_FILENAME),
+            settings.data_encryption().rotation_grace_period(),
+        )?;
+        let master_keys = std::sync::Arc::new(tokio::sync::Mutex::new(master_keys));
+
+        let issued_certs =
+            module::cert::renewal::IssuedCerts::new(settings.module_certs().renewal_threshold());
+
         let renewal_engine = if config.edge_ca_auto_renew.is_some() {
             let engine = cert_renewal::engine::new();
 
@@ -94,6 +118,10 @@ where
             runtime,
             renewal_tx,
             renewal_engine,
+            module_keys,
+            master_keys,
+            issued_certs,
+            previous_edge_ca: edge_ca::PreviousEdgeCa::default(),
             config,
         })
     }
@@ -161,6 +189,8 @@ where
                 self.cert_client.clone(),
                 self.key_client.clone(),
                 self.key_connector.clone(),
+                self.previous_edge_ca.clone(),
+                self.issued_certs.clone(),
                 self.renewal_tx.clone(),
             );
 
@@ -182,6 +212,78 @@ where
         Ok(())
     }
 
+    /// Fails if the master encryption key versions are missing although keyd has keys that only
+    /// exist once versions were saved, since module data may be encrypted with a later version.
+    pub async fn check_master_keys(&self) -> Result<(), String> {
+        module::data::check_master_keys(&self.key_client, &self.master_keys).await
+    }
+
+    /// Deletes the keys that modules created through the workload API, and stops tracking their
+    /// certificates for renewal, when their identities are deleted. Runs until every clone of
+    /// `events` is dropped.
+    pub async fn forget_deleted_modules(self, events: edgelet_core::EventBus) {
+        let (_, mut receiver) = events.subscribe(None);
+        drop(events);
+
+        loop {
+            match receiver.recv().await {
+                Ok(event) if event.kind() == edgelet_core::EventKind::IdentityDeleted => {
+                    if let Some(module_id) = event.module() {
+                        self.issued_certs.remove_module(module_id);
+
+                        module::key::delete_module_keys(
+                            &self.key_client,
+                            &self.module_keys,
+                            module_id,
+                        )
+                        .await;
+                    }
+                }
+                Ok(_) => {}
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(count)) => {
+                    log::warn!("Missed {count} events while cleaning up after deleted modules");
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }
+
+    /// Rotates the master encryption key on request. Runs until every sender of `requests` is
+    /// dropped.
+    pub async fn rotate_encryption_keys(
+        self,
+        mut requests: tokio::sync::mpsc::UnboundedReceiver<edgelet_core::EncryptionKeyRotation>,
+    ) {
+        while let Some(response) = requests.recv().await {
+            let result = module::data::rotate_master_key(&self.key_client, &self.master_keys).await;
+
+            match &result {
+                Ok(version) => log::info!("Rotated master encryption key to version {version}"),
+                Err(err) => log::warn!("Failed to rotate master encryption key: {err}"),
+            }
+
+            // The requester may have stopped waiting; the rotation has happened regardless.
+            let _ = response.send(result);
+        }
+    }
+
+    /// Lists the certificates on the device on request. Runs until every sender of `requests`
+    /// is dropped.
+    pub async fn list_certs(
+        self,
+        mut requests: tokio::sync::mpsc::UnboundedReceiver<edgelet_core::CertInventoryRequest>,
+    ) {
+        while let Some(response) = requests.recv().await {
+            let result = inventory::list(&self.cert_client, &self.config, &self.issued_certs).await;
+
+            if let Err(err) = &result {
+                log::warn!("Failed to list certificates: {err}");
+            }
+
+            let _ = response.send(result);
+        }
+    }
+
     // Test constructor used to create a test Workload Service.
     #[cfg(test)]
     pub fn new(runtime: M) -> Self {
@@ -200,6 +302,23 @@ where
 
         let runtime = std::sync::Arc::new(tokio::sync::Mutex::new(runtime));
 
+        let module_keys = module::key::KeyIndex::load(std::env::temp_dir().join(format!(
+            "{MODULE_KEY_INDEX_FILENAME}.{}",
+            std::process::id()
+        )));
+        let module_keys = std::sync::Arc::new(tokio::sync::Mutex::new(module_keys));
+
+        let master_keys = module::data::master_key::MasterKeys::load(
+            std::env::temp_dir().join(format!("{ENCRYPTION_KEYS_FILENAME}.{}", std::process::id())),
+            edgelet_settings::base::encryption::DataEncryption::default().rotation_grace_period(),
+        )
+        .unwrap();
+        let master_keys = std::sync::Arc::new(tokio::sync::Mutex::new(master_keys));
+
+        let issued_certs = module::cert::renewal::IssuedCerts::new(
+            edgelet_settings::base::cert::ModuleCerts::default().renewal_threshold(),
+        );
+
         let config = WorkloadConfig {
             hub_name: "test-hub.test.net".to_string(),
             device_id: "test-device".to_string(),
@@ -211,6 +330,10 @@ where
             edge_ca_subject: aziot_certd_config::CertSubject::CommonName(
                 "aziot-edge CA test-device".to_string(),
             ),
+            edge_ca_overlap: edgelet_settings::base::rollover::EdgeCaRollover::default().overlap(),
+            device_identity_cert: None,
+            key_algorithm: edgelet_settings::base::cert::KeyAlgorithm::default(),
+            san_policy: None,
         };
 
         // We won't use the renewal sender, but it must be created to construct the
@@ -227,6 +350,10 @@ where
             runtime,
             renewal_tx,
             renewal_engine: None,
+            module_keys,
+            master_keys,
+            issued_certs,
+            previous_edge_ca: edge_ca::PreviousEdgeCa::default(),
             config,
         }
     }
@@ -242,12 +369,20 @@ http_common::make_service! {
     routes: [
         module::list::Route<M>,
 
+        module::cert::csr::Route<M>,
         module::cert::identity::Route<M>,
+        module::cert::renewal::Route<M>,
         module::cert::server::Route<M>,
 
         module::data::decrypt::Route<M>,
         module::data::encrypt::Route<M>,
         module::data::sign::Route<M>,
+        module::data::verify::Route<M>,
+
+        module::key::create::Route<M>,
+        module::key::get_or_delete::Route<M>,
+        module::key::sign::Route<M>,
+        module::key::verify::Route<M>,
 
         trust_bundle::Route<M>,
     ],
@@ -267,6 +402,13 @@ struct WorkloadConfig {
     edge_ca_key: String,
     edge_ca_auto_renew: Option<cert_renewal::AutoRenewConfig>,
     edge_ca_subject: aziot_certd_config::CertSubject,
+    edge_ca_overlap: std::time::Duration,
+
+    /// The certd ID of the device identity certificate, if the device authenticates with one.
+    device_identity_cert: Option<String>,
+
+    key_algorithm: edgelet_settings::base::cert::KeyAlgorithm,
+    san_policy: Option<edgelet_settings::base::cert::SanPolicy>,
 }
 
 impl WorkloadConfig {
@@ -299,6 +441,16 @@ impl WorkloadConfig {
             aziot_certd_config::CertSubject::CommonName(format!("aziot-edge CA {device_id}"))
         });
 
+        let edge_ca_overlap = settings.edge_ca_rollover().overlap();
+
+        let device_identity_cert = device_info
+            .auth
+            .as_ref()
+            .and_then(|auth| auth.cert_id.clone());
+
+        let key_algorithm = settings.module_certs().key_algorithm();
+        let san_policy = settings.module_certs().san_policy().cloned();
+
         WorkloadConfig {
             hub_name: device_info.hub_name.clone(),
             device_id,
@@ -310,6 +462,12 @@ impl WorkloadConfig {
             edge_ca_key,
             edge_ca_auto_renew,
             edge_ca_subject,
+            edge_ca_overlap,
+
+            device_identity_cert,
+
+            key_algorithm,
+            san_policy,
         }
     }
 }
@@ -344,7 +502,11 @@ mod tests {
                 edge_ca_auto_renew: None,
                 edge_ca_subject: aziot_certd_config::CertSubject::CommonName(
                     "aziot-edge CA test-device".to_string(),
-                )
+                ),
+                edge_ca_overlap: std::time::Duration::from_hours(24),
+                device_identity_cert: None,
+                key_algorithm: edgelet_settings::base::cert::KeyAlgorithm::default(),
+                san_policy: None,
             },
             config
         );
@@ -370,6 +532,15 @@ mod tests {
             )),
             trust_bundle: Some("test-trust-bundle".to_string()),
             manifest_trust_bundle: Some("test-manifest-trust-bundle".to_string()),
+            module_certs: edgelet_settings::base::cert::ModuleCerts::new(
+                edgelet_settings::base::cert::KeyAlgorithm::EcP256,
+            ),
+            data_encryption: edgelet_settings::base::encryption::DataEncryption::default(),
+            edge_ca_rollover: edgelet_settings::base::rollover::EdgeCaRollover::new(
+                true,
+                std::time::Duration::from_hours(2),
+                Vec::new(),
+            ),
         };
 
         // Check that values from settings are used when provided.
@@ -387,7 +558,11 @@ mod tests {
                 edge_ca_auto_renew: None,
                 edge_ca_subject: aziot_certd_config::CertSubject::CommonName(
                     "aziot-edge CA test-device".to_string(),
-                )
+                ),
+                edge_ca_overlap: std::time::Duration::from_hours(2),
+                device_identity_cert: None,
+                key_algorithm: edgelet_settings::base::cert::KeyAlgorithm::EcP256,
+                san_policy: None,
             },
             config
         );
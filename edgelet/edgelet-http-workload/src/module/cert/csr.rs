@@ -0,0 +1,742 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use super::der::{self, Reader};
+
+/// The OIDs of the extensions a module's CSR may request.
+const SUBJECT_ALT_NAME: &[u8] = &[0x55, 0x1d, 0x11];
+const KEY_USAGE: &[u8] = &[0x55, 0x1d, 0x0f];
+const BASIC_CONSTRAINTS: &[u8] = &[0x55, 0x1d, 0x13];
+const EXT_KEY_USAGE: &[u8] = &[0x55, 0x1d, 0x25];
+
+/// The keyCertSign and cRLSign bits of the first byte of a keyUsage.
+const KEY_CERT_SIGN: u8 = 0x04;
+const CRL_SIGN: u8 = 0x02;
+
+/// The OIDs of the extended key usages of server and identity certificates.
+const SERVER_AUTH: &[u8] = &[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x03, 0x01];
+const CLIENT_AUTH: &[u8] = &[0x2b, 0x06, 0x01, 0x05, 0x05, 0x07, 0x03, 0x02];
+
+pub(crate) struct Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    module_id: String,
+    gen_id: String,
+    module_uri: String,
+    pid: libc::pid_t,
+    api: super::CertApi,
+    runtime: std::sync::Arc<tokio::sync::Mutex<M>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum CertificateType {
+    Server,
+    Identity,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub(crate) struct CsrCertificateRequest {
+    /// PEM-encoded CSR, signed by the module's key.
+    csr: String,
+
+    #[serde(rename = "type")]
+    cert_type: CertificateType,
+}
+
+/// The names a CSR asks to be certified.
+#[derive(Debug, Default, PartialEq)]
+struct RequestedNames {
+    common_name: Option<String>,
+    dns: Vec<String>,
+    ip: Vec<std::net::IpAddr>,
+    uri: Vec<String>,
+}
+
+#[async_trait::async_trait]
+impl<M> http_common::server::Route for Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    type ApiVersion = edgelet_http::ApiVersion;
+    fn api_version() -> &'static dyn http_common::DynRangeBounds<Self::ApiVersion> {
+        &((edgelet_http::ApiVersion::V2026_10_18)..)
+    }
+
+    type Service = crate::Service<M>;
+    fn from_uri(
+        service: &Self::Service,
+        path: &str,
+        _query: &[(std::borrow::Cow<'_, str>, std::borrow::Cow<'_, str>)],
+        extensions: &http::Extensions,
+    ) -> Option<Self> {
+        let uri_regex = regex::Regex::new(
+            "^/modules/(?P<moduleId>[^/]+)/genid/(?P<genId>[^/]+)/certificate/csr$",
+        )
+        .expect("hard-coded regex must compile");
+        let captures = uri_regex.captures(path)?;
+
+        let module_id = &captures["moduleId"];
+        let module_id = percent_encoding::percent_decode_str(module_id)
+            .decode_utf8()
+            .ok()?;
+
+        let gen_id = &captures["genId"];
+        let gen_id = percent_encoding::percent_decode_str(gen_id)
+            .decode_utf8()
+            .ok()?;
+
+        let module_uri = format!(
+            "azureiot://{}/devices/{}/modules/{}",
+            service.config.hub_name, service.config.device_id, module_id
+        );
+
+        let pid = extensions.get::<Option<libc::pid_t>>().copied()??;
+
+        let api = super::CertApi::new(
+            service.key_client.clone(),
+            service.cert_client.clone(),
+            service.issued_certs.clone(),
+            &service.config,
+        );
+
+        Some(Route {
+            module_id: module_id.into_owned(),
+            gen_id: gen_id.into_owned(),
+            module_uri,
+            pid,
+            api,
+            runtime: service.runtime.clone(),
+        })
+    }
+
+    type DeleteBody = serde::de::IgnoredAny;
+
+    type PostBody = CsrCertificateRequest;
+    async fn post(self, body: Option<Self::PostBody>) -> http_common::server::RouteResponse {
+        edgelet_http::auth_caller(&self.module_id, self.pid, &self.runtime).await?;
+
+        let Some(body) = body else {
+            return Err(edgelet_http::error::bad_request("missing request body"));
+        };
+
+        let csr = openssl::x509::X509Req::from_pem(body.csr.as_bytes())
+            .map_err(|_| edgelet_http::error::bad_request("invalid csr"))?;
+        let names = requested_names(&csr, body.cert_type).map_err(|message| {
+            http_common::server::Error {
+                status_code: http::StatusCode::BAD_REQUEST,
+                message: format!("invalid csr: {message}").into(),
+            }
+        })?;
+
+        if let Err(message) = self.check_names(&names, body.cert_type) {
+            log::warn!("Rejected csr of module {}: {message}", self.module_id);
+
+            return Err(http_common::server::Error {
+                status_code: http::StatusCode::FORBIDDEN,
+                message: message.into(),
+            });
+        }
+
+        if body.cert_type == CertificateType::Server {
+            for name in names
+                .dns
+                .iter()
+                .cloned()
+                .chain(names.ip.iter().map(ToString::to_string))
+            {
+                self.api.check_san(&self.module_id, &name)?;
+            }
+        }
+
+        let cert_id = match body.cert_type {
+            CertificateType::Server => super::server_cert_id(&self.module_id, &self.gen_id),
+            CertificateType::Identity => super::identity_cert_id(&self.module_id),
+        };
+
+        self.api
+            .sign_csr(
+                &self.module_id,
+                body.cert_type,
+                cert_id,
+                body.csr.as_bytes(),
+            )
+            .await
+    }
+
+    type PutBody = serde::de::IgnoredAny;
+}
+
+impl<M> Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    /// Checks that the module may have a certificate with the requested names. These are the
+    /// names the server and identity certificate routes would have certified.
+    fn check_names(
+        &self,
+        names: &RequestedNames,
+        cert_type: CertificateType,
+    ) -> Result<(), String> {
+        let common_name = names
+            .common_name
+            .as_deref()
+            .ok_or_else(|| "csr has no common name".to_string())?;
+
+        match cert_type {
+            CertificateType::Server => {
+                let module_id = super::cert_module_id(&self.module_id);
+
+                // SANs take precedence over CN, so the CN must also be a SAN.
+                let common_name_san = names.dns.iter().any(|name| name == common_name)
+                    || names.ip.iter().any(|ip| ip.to_string() == common_name);
+                if !common_name_san {
+                    return Err(format!("common name {common_name} is not a SAN"));
+                }
+
+                if let Some(uri) = names.uri.first() {
+                    return Err(format!("URI SAN {uri} is not allowed"));
+                }
+
+                for ip in &names.ip {
+                    if ip.to_string() != common_name {
+                        return Err(format!("IP SAN {ip} is not allowed"));
+                    }
+                }
+
+                for name in &names.dns {
+                    if name != common_name && !name.eq_ignore_ascii_case(module_id) {
+                        return Err(format!("DNS SAN {name} is not allowed"));
+                    }
+                }
+            }
+
+            CertificateType::Identity => {
+                if common_name != self.module_id {
+                    return Err(format!("common name {common_name} is not allowed"));
+                }
+
+                if let Some(name) = names.dns.first() {
+                    return Err(format!("DNS SAN {name} is not allowed"));
+                }
+                if let Some(ip) = names.ip.first() {
+                    return Err(format!("IP SAN {ip} is not allowed"));
+                }
+                for uri in &names.uri {
+                    if uri != &self.module_uri {
+                        return Err(format!("URI SAN {uri} is not allowed"));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Verifies that `csr` is signed by its own key, and reads the names it requests. The subject
+/// may only hold a common name, and only extensions that fit `cert_type` may be requested.
+///
+/// certd copies the CSR's extensions into the certificate, and they cannot be changed without
+/// the module's key. So the CSR must request the extended key usage of `cert_type` and
+/// basicConstraints CA:FALSE, like the certificates of the server and identity routes have.
+fn requested_names(
+    csr: &openssl::x509::X509ReqRef,
+    cert_type: CertificateType,
+) -> Result<RequestedNames, String> {
+    let public_key = csr
+        .public_key()
+        .map_err(|_| "invalid public key".to_string())?;
+    if !csr.verify(&public_key).unwrap_or(false) {
+        return Err("signature does not match public key".to_string());
+    }
+
+    let mut names = RequestedNames::default();
+
+    for entry in csr.subject_name().entries() {
+        if entry.object().nid() != openssl::nid::Nid::COMMONNAME || names.common_name.is_some() {
+            return Err("subject may only have a common name".to_string());
+        }
+
+        // UTF8String and PrintableString common names are UTF-8 as they are encoded.
+        let common_name = std::str::from_utf8(entry.data().as_slice())
+            .map_err(|_| "invalid common name".to_string())?;
+        names.common_name = Some(common_name.to_string());
+    }
+
+    let csr = csr.to_der().map_err(|_| "invalid encoding".to_string())?;
+
+    let mut ext_key_usage = false;
+    let mut basic_constraints = false;
+
+    for extension in der::csr_extensions(&csr)? {
+        match extension.oid {
+            SUBJECT_ALT_NAME => read_subject_alt_names(extension.value, &mut names)?,
+            EXT_KEY_USAGE => {
+                check_ext_key_usage(extension.value, cert_type)?;
+                ext_key_usage = true;
+            }
+            BASIC_CONSTRAINTS => {
+                check_basic_constraints(extension.value)?;
+                basic_constraints = true;
+            }
+            KEY_USAGE => check_key_usage(extension.value)?,
+            _ => return Err("extension is not allowed".to_string()),
+        }
+    }
+
+    if !ext_key_usage {
+        return Err("extended key usage is required".to_string());
+    }
+    if !basic_constraints {
+        return Err("basic constraints CA:FALSE are required".to_string());
+    }
+
+    Ok(names)
+}
+
+fn read_subject_alt_names(value: &[u8], names: &mut RequestedNames) -> Result<(), String> {
+    const DNS_NAME: u8 = 0x82;
+    const URI: u8 = 0x86;
+    const IP_ADDRESS: u8 = 0x87;
+
+    let mut general_names = Reader::new(Reader::new(value).expect(der::SEQUENCE)?);
+
+    while !general_names.is_empty() {
+        match general_names.read()? {
+            (DNS_NAME, name) => names.dns.push(ascii(name)?),
+            (URI, uri) => names.uri.push(ascii(uri)?),
+            (IP_ADDRESS, ip) => {
+                let ip = match ip.len() {
+                    4 => <[u8; 4]>::try_from(ip).map(std::net::IpAddr::from),
+                    _ => <[u8; 16]>::try_from(ip).map(std::net::IpAddr::from),
+                }
+                .map_err(|_| "invalid IP SAN".to_string())?;
+                names.ip.push(ip);
+            }
+            _ => return Err("SAN type is not allowed".to_string()),
+        }
+    }
+
+    Ok(())
+}
+
+fn check_ext_key_usage(value: &[u8], cert_type: CertificateType) -> Result<(), String> {
+    let allowed = match cert_type {
+        CertificateType::Server => SERVER_AUTH,
+        CertificateType::Identity => CLIENT_AUTH,
+    };
+
+    let mut usages = Reader::new(Reader::new(value).expect(der::SEQUENCE)?);
+    if usages.is_empty() {
+        return Err("extended key usage is empty".to_string());
+    }
+    while !usages.is_empty() {
+        if usages.expect(der::OBJECT_IDENTIFIER)? != allowed {
+            return Err("extended key usage is not allowed".to_string());
+        }
+    }
+
+    Ok(())
+}
+
+/// Only allows CA:FALSE, which DER encodes as an empty sequence.
+fn check_basic_constraints(value: &[u8]) -> Result<(), String> {
+    let mut constraints = Reader::new(Reader::new(value).expect(der::SEQUENCE)?);
+
+    if !constraints.is_empty() {
+        let (tag, is_ca) = constraints.read()?;
+        if tag != der::BOOLEAN || is_ca != [0x00] || !constraints.is_empty() {
+            return Err("CA certificates are not allowed".to_string());
+        }
+    }
+
+    Ok(())
+}
+
+/// Rejects key usages that would let the certificate sign other certificates or CRLs.
+fn check_key_usage(value: &[u8]) -> Result<(), String> {
+    let usages = Reader::new(value).expect(der::BIT_STRING)?;
+
+    // The first byte counts the unused bits of the last one.
+    if let [_, first, ..] = usages
+        && first & (KEY_CERT_SIGN | CRL_SIGN) != 0
+    {
+        return Err("key usage is not allowed".to_string());
+    }
+
+    Ok(())
+}
+
+fn ascii(name: &[u8]) -> Result<String, String> {
+    if name.is_ascii() {
+        Ok(String::from_utf8_lossy(name).into_owned())
+    } else {
+        Err("SAN is not ASCII".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use http_body_util::BodyExt as _;
+
+    use http_common::server::Route;
+
+    use edgelet_test_utils::{test_route_err, test_route_ok};
+
+    use super::{CertificateType, CsrCertificateRequest};
+    use crate::module::cert::{KeyAlgorithm, SubjectAltName, new_csr, new_keys};
+
+    const TEST_PATH: &str = "/modules/testModule/genid/1/certificate/csr";
+
+    const MODULE_NAME: &str = "testModule";
+
+    const MODULE_URI: &str = "azureiot://test-hub.test.net/devices/test-device/modules/testModule";
+
+    fn csr(
+        common_name: &str,
+        subject_alt_names: Vec<SubjectAltName>,
+        requested: Vec<openssl::x509::X509Extension>,
+    ) -> String {
+        let keys = new_keys(KeyAlgorithm::EcP256).unwrap();
+
+        let mut subject = openssl::x509::X509Name::builder().unwrap();
+        subject
+            .append_entry_by_nid(openssl::nid::Nid::COMMONNAME, common_name)
+            .unwrap();
+        let subject = subject.build();
+
+        let mut extensions = openssl::stack::Stack::new().unwrap();
+        for extension in requested {
+            extensions.push(extension).unwrap();
+        }
+
+        let csr = new_csr(&subject, keys, subject_alt_names, extensions).unwrap();
+        String::from_utf8(csr).unwrap()
+    }
+
+    fn not_ca() -> openssl::x509::X509Extension {
+        openssl::x509::extension::BasicConstraints::new()
+            .build()
+            .unwrap()
+    }
+
+    fn server_auth() -> Vec<openssl::x509::X509Extension> {
+        vec![
+            openssl::x509::extension::ExtendedKeyUsage::new()
+                .server_auth()
+                .build()
+                .unwrap(),
+            not_ca(),
+        ]
+    }
+
+    fn client_auth() -> Vec<openssl::x509::X509Extension> {
+        vec![
+            openssl::x509::extension::ExtendedKeyUsage::new()
+                .client_auth()
+                .build()
+                .unwrap(),
+            not_ca(),
+        ]
+    }
+
+    async fn post_csr(
+        csr: String,
+        cert_type: CertificateType,
+    ) -> http_common::server::RouteResponse {
+        let route = test_route_ok!(TEST_PATH);
+        {
+            let pid = nix::unistd::getpid().as_raw();
+            let mut runtime = route.runtime.lock().await;
+            runtime.module_auth = std::collections::BTreeMap::new();
+            runtime
+                .module_auth
+                .insert(MODULE_NAME.to_string(), vec![pid]);
+        }
+
+        route
+            .post(Some(CsrCertificateRequest { csr, cert_type }))
+            .await
+    }
+
+    #[test]
+    fn parse_uri() {
+        // Valid URI
+        let route = test_route_ok!(TEST_PATH);
+        assert_eq!(MODULE_NAME, &route.module_id);
+        assert_eq!("1", &route.gen_id);
+        assert_eq!(MODULE_URI, &route.module_uri);
+        assert_eq!(nix::unistd::getpid().as_raw(), route.pid);
+
+        // Missing module ID
+        test_route_err!("/modules//genid/1/certificate/csr");
+
+        // Missing generation ID
+        test_route_err!("/modules/testModule/genid//certificate/csr");
+
+        // Extra character at beginning of URI
+        test_route_err!(&format!("a{TEST_PATH}"));
+
+        // Extra character at end of URI
+        test_route_err!(&format!("{TEST_PATH}a"));
+    }
+
+    #[tokio::test]
+    async fn auth() {
+        async fn post(
+            route: super::Route<edgelet_test_utils::runtime::Runtime>,
+        ) -> http_common::server::RouteResponse {
+            let csr = csr(
+                "testModule.local",
+                vec![SubjectAltName::Dns("testModule.local".to_string())],
+                server_auth(),
+            );
+
+            route
+                .post(Some(CsrCertificateRequest {
+                    csr,
+                    cert_type: CertificateType::Server,
+                }))
+                .await
+        }
+
+        edgelet_test_utils::test_auth_caller!(TEST_PATH, MODULE_NAME, post);
+    }
+
+    #[tokio::test]
+    async fn server() {
+        let request = csr(
+            "testModule.local",
+            vec![
+                SubjectAltName::Dns("testModule.local".to_string()),
+                SubjectAltName::Dns(MODULE_NAME.to_string()),
+            ],
+            server_auth(),
+        );
+        let public_key = openssl::x509::X509Req::from_pem(request.as_bytes())
+            .unwrap()
+            .public_key()
+            .unwrap();
+
+        let response = post_csr(request, CertificateType::Server).await.unwrap();
+        assert_eq!(hyper::StatusCode::CREATED, response.status());
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let response: super::super::CsrCertificateResponse = serde_json::from_slice(&body).unwrap();
+
+        // Only the certificate is returned, for the key in the CSR.
+        let cert = openssl::x509::X509::from_pem(response.certificate.as_bytes()).unwrap();
+        assert!(cert.public_key().unwrap().public_eq(&public_key));
+
+        // Names that belong to other modules are refused.
+        for (common_name, names) in [
+            ("edgeHub", vec![SubjectAltName::Dns("edgeHub".to_string())]),
+            (
+                "testModule.local",
+                vec![
+                    SubjectAltName::Dns("testModule.local".to_string()),
+                    SubjectAltName::Dns("edgeHub".to_string()),
+                ],
+            ),
+        ] {
+            let response = post_csr(
+                csr(common_name, names, server_auth()),
+                CertificateType::Server,
+            )
+            .await
+            .unwrap_err();
+            assert_eq!(hyper::StatusCode::FORBIDDEN, response.status_code);
+        }
+
+        // The CN must be a SAN.
+        let response = post_csr(
+            csr("testModule.local", vec![], server_auth()),
+            CertificateType::Server,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(hyper::StatusCode::FORBIDDEN, response.status_code);
+
+        // Server certificates cannot be used for client authentication.
+        let response = post_csr(
+            csr(
+                "testModule.local",
+                vec![SubjectAltName::Dns("testModule.local".to_string())],
+                client_auth(),
+            ),
+            CertificateType::Server,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(hyper::StatusCode::BAD_REQUEST, response.status_code);
+    }
+
+    #[tokio::test]
+    async fn identity() {
+        let request = csr(
+            MODULE_NAME,
+            vec![SubjectAltName::Uri(MODULE_URI.to_string())],
+            client_auth(),
+        );
+        let response = post_csr(request, CertificateType::Identity).await.unwrap();
+        assert_eq!(hyper::StatusCode::CREATED, response.status());
+
+        // Identity certificates are only for the module's own identity.
+        let request = csr(
+            "edgeHub",
+            vec![SubjectAltName::Uri(MODULE_URI.to_string())],
+            client_auth(),
+        );
+        let response = post_csr(request, CertificateType::Identity)
+            .await
+            .unwrap_err();
+        assert_eq!(hyper::StatusCode::FORBIDDEN, response.status_code);
+
+        let request = csr(
+            MODULE_NAME,
+            vec![SubjectAltName::Uri(
+                "azureiot://test-hub.test.net/devices/test-device/modules/edgeHub".to_string(),
+            )],
+            client_auth(),
+        );
+        let response = post_csr(request, CertificateType::Identity)
+            .await
+            .unwrap_err();
+        assert_eq!(hyper::StatusCode::FORBIDDEN, response.status_code);
+    }
+
+    #[tokio::test]
+    async fn edge_hub() {
+        const PATH: &str = "/modules/$edgeHub/genid/1/certificate/csr";
+        const URI: &str = "azureiot://test-hub.test.net/devices/test-device/modules/$edgeHub";
+
+        // Both certificates of a module like '$edgeHub' are named without the '$'.
+        for (cert_type, request, cert_id) in [
+            (
+                CertificateType::Server,
+                csr(
+                    "edgeHub",
+                    vec![SubjectAltName::Dns("edgeHub".to_string())],
+                    server_auth(),
+                ),
+                "aziot-edged/module/edgeHub:1:server",
+            ),
+            (
+                CertificateType::Identity,
+                csr(
+                    "$edgeHub",
+                    vec![SubjectAltName::Uri(URI.to_string())],
+                    client_auth(),
+                ),
+                "aziot-edged/module/edgeHub:identity",
+            ),
+        ] {
+            let route = test_route_ok!(PATH);
+            {
+                let pid = nix::unistd::getpid().as_raw();
+                let mut runtime = route.runtime.lock().await;
+                runtime.module_auth = std::collections::BTreeMap::new();
+                runtime
+                    .module_auth
+                    .insert("$edgeHub".to_string(), vec![pid]);
+            }
+            let issued_certs = route.api.issued_certs.clone();
+
+            let response = route
+                .post(Some(CsrCertificateRequest {
+                    csr: request,
+                    cert_type,
+                }))
+                .await
+                .unwrap();
+            assert_eq!(hyper::StatusCode::CREATED, response.status());
+
+            assert_eq!(
+                vec![(cert_id.to_string(), "$edgeHub".to_string(), cert_type)],
+                issued_certs.list()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn extensions() {
+        let names = || vec![SubjectAltName::Uri(MODULE_URI.to_string())];
+        let ext_key_usage = || {
+            openssl::x509::extension::ExtendedKeyUsage::new()
+                .client_auth()
+                .build()
+                .unwrap()
+        };
+        let key_usage = |usage: &mut openssl::x509::extension::KeyUsage| usage.build().unwrap();
+
+        // Usages for signing data are allowed.
+        let mut signing = openssl::x509::extension::KeyUsage::new();
+        signing.digital_signature().key_agreement();
+        let request = csr(
+            MODULE_NAME,
+            names(),
+            vec![ext_key_usage(), not_ca(), key_usage(&mut signing)],
+        );
+        post_csr(request, CertificateType::Identity).await.unwrap();
+
+        let mut ca = openssl::x509::extension::BasicConstraints::new();
+        ca.ca();
+        let mut cert_sign = openssl::x509::extension::KeyUsage::new();
+        cert_sign.digital_signature().key_cert_sign();
+        let mut crl_sign = openssl::x509::extension::KeyUsage::new();
+        crl_sign.crl_sign();
+
+        for requested in [
+            // The extended key usage and basic constraints must be requested.
+            vec![not_ca()],
+            vec![ext_key_usage()],
+            // The certificate may not be a CA, or sign certificates or CRLs.
+            vec![ext_key_usage(), ca.build().unwrap()],
+            vec![ext_key_usage(), not_ca(), key_usage(&mut cert_sign)],
+            vec![ext_key_usage(), not_ca(), key_usage(&mut crl_sign)],
+        ] {
+            let response = post_csr(
+                csr(MODULE_NAME, names(), requested),
+                CertificateType::Identity,
+            )
+            .await
+            .unwrap_err();
+            assert_eq!(hyper::StatusCode::BAD_REQUEST, response.status_code);
+        }
+    }
+
+    #[tokio::test]
+    async fn invalid_csr() {
+        let response = post_csr("not a csr".to_string(), CertificateType::Server)
+            .await
+            .unwrap_err();
+        assert_eq!(hyper::StatusCode::BAD_REQUEST, response.status_code);
+
+        // A CSR whose subject has more than a common name.
+        let keys = new_keys(KeyAlgorithm::EcP256).unwrap();
+        let mut subject = openssl::x509::X509Name::builder().unwrap();
+        subject
+            .append_entry_by_nid(openssl::nid::Nid::COMMONNAME, MODULE_NAME)
+            .unwrap();
+        subject
+            .append_entry_by_nid(openssl::nid::Nid::ORGANIZATIONNAME, "Contoso")
+            .unwrap();
+        let subject = subject.build();
+        let request = new_csr(
+            &subject,
+            keys,
+            vec![],
+            openssl::stack::Stack::new().unwrap(),
+        )
+        .unwrap();
+
+        let response = post_csr(
+            String::from_utf8(request).unwrap(),
+            CertificateType::Identity,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(hyper::StatusCode::BAD_REQUEST, response.status_code);
+    }
+}
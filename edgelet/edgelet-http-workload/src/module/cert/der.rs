@@ -0,0 +1,193 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! Just enough DER decoding to read the extensions a CSR requests, which openssl does not expose.
+
+pub(crate) const SEQUENCE: u8 = 0x30;
+const SET: u8 = 0x31;
+pub(crate) const OBJECT_IDENTIFIER: u8 = 0x06;
+pub(crate) const BOOLEAN: u8 = 0x01;
+pub(crate) const BIT_STRING: u8 = 0x03;
+const OCTET_STRING: u8 = 0x04;
+const ATTRIBUTES: u8 = 0xa0;
+
+/// The OID 1.2.840.113549.1.9.14 (PKCS #9 extensionRequest).
+const EXTENSION_REQUEST: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x0e];
+
+/// The DER values in a byte string, read one at a time.
+pub(crate) struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(der: &'a [u8]) -> Self {
+        Reader(der)
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The tag and contents of the next value.
+    pub(crate) fn read(&mut self) -> Result<(u8, &'a [u8]), String> {
+        let [tag, length, rest @ ..] = self.0 else {
+            return Err("truncated value".to_string());
+        };
+
+        let (length, rest) = if length & 0x80 == 0 {
+            (usize::from(*length), rest)
+        } else {
+            let count = usize::from(length & 0x7f);
+            if count == 0 || count > std::mem::size_of::<usize>() || rest.len() < count {
+                return Err("invalid length".to_string());
+            }
+
+            let (length, rest) = rest.split_at(count);
+            let length = length
+                .iter()
+                .fold(0, |length, &b| (length << 8) | usize::from(b));
+            (length, rest)
+        };
+
+        if rest.len() < length {
+            return Err("truncated value".to_string());
+        }
+
+        let (contents, rest) = rest.split_at(length);
+        self.0 = rest;
+
+        Ok((*tag, contents))
+    }
+
+    /// The contents of the next value, which must have the tag `expected`.
+    pub(crate) fn expect(&mut self, expected: u8) -> Result<&'a [u8], String> {
+        match self.read()? {
+            (tag, contents) if tag == expected => Ok(contents),
+            (tag, _) => Err(format!("expected tag {expected:#04x}, found {tag:#04x}")),
+        }
+    }
+}
+
+/// An extension requested by a CSR.
+pub(crate) struct Extension<'a> {
+    /// The DER contents of the extension's OID.
+    pub(crate) oid: &'a [u8],
+    /// The DER encoding of the extension's value.
+    pub(crate) value: &'a [u8],
+}
+
+/// The extensions requested by the DER-encoded CSR `csr`.
+pub(crate) fn csr_extensions(csr: &[u8]) -> Result<Vec<Extension<'_>>, String> {
+    let request = Reader::new(csr).expect(SEQUENCE)?;
+    let mut info = Reader::new(Reader::new(request).expect(SEQUENCE)?);
+
+    // Version, subject and public key.
+    for _ in 0..3 {
+        info.read()?;
+    }
+
+    let mut extensions = vec![];
+
+    if info.is_empty() {
+        return Ok(extensions);
+    }
+
+    let mut attributes = Reader::new(info.expect(ATTRIBUTES)?);
+    while !attributes.is_empty() {
+        let mut attribute = Reader::new(attributes.expect(SEQUENCE)?);
+        if attribute.expect(OBJECT_IDENTIFIER)? != EXTENSION_REQUEST {
+            continue;
+        }
+
+        let mut values = Reader::new(attribute.expect(SET)?);
+        while !values.is_empty() {
+            let mut requested = Reader::new(values.expect(SEQUENCE)?);
+
+            while !requested.is_empty() {
+                let mut extension = Reader::new(requested.expect(SEQUENCE)?);
+                let oid = extension.expect(OBJECT_IDENTIFIER)?;
+
+                let (mut tag, mut value) = extension.read()?;
+                if tag == BOOLEAN {
+                    (tag, value) = extension.read()?;
+                }
+                if tag != OCTET_STRING {
+                    return Err("invalid extension value".to_string());
+                }
+
+                extensions.push(Extension { oid, value });
+            }
+        }
+    }
+
+    Ok(extensions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Reader, csr_extensions};
+
+    #[test]
+    fn lengths() {
+        // Short and long form lengths.
+        let mut reader = Reader::new(&[0x04, 0x01, 0xff, 0x04, 0x81, 0x01, 0xee]);
+        assert_eq!((0x04, &[0xff][..]), reader.read().unwrap());
+        assert_eq!((0x04, &[0xee][..]), reader.read().unwrap());
+        assert!(reader.is_empty());
+
+        Reader::new(&[0x04, 0x02, 0xff]).read().unwrap_err();
+        Reader::new(&[0x04, 0x80]).read().unwrap_err();
+        Reader::new(&[0x04]).read().unwrap_err();
+    }
+
+    #[test]
+    fn extensions() {
+        let keys =
+            crate::module::cert::new_keys(crate::module::cert::KeyAlgorithm::EcP256).unwrap();
+        let subject = openssl::x509::X509Name::builder().unwrap().build();
+
+        let mut extensions = openssl::stack::Stack::new().unwrap();
+        extensions
+            .push(
+                openssl::x509::extension::ExtendedKeyUsage::new()
+                    .server_auth()
+                    .build()
+                    .unwrap(),
+            )
+            .unwrap();
+
+        let csr = crate::module::cert::new_csr(
+            &subject,
+            keys,
+            vec![crate::module::cert::SubjectAltName::Dns(
+                "testModule".to_string(),
+            )],
+            extensions,
+        )
+        .unwrap();
+        let csr = openssl::x509::X509Req::from_pem(&csr)
+            .unwrap()
+            .to_der()
+            .unwrap();
+
+        let oids: Vec<_> = csr_extensions(&csr)
+            .unwrap()
+            .into_iter()
+            .map(|extension| extension.oid.to_vec())
+            .collect();
+        assert_eq!(oids, [vec![0x55, 0x1d, 0x25], vec![0x55, 0x1d, 0x11]]);
+
+        // A CSR without extensions.
+        let keys =
+            crate::module::cert::new_keys(crate::module::cert::KeyAlgorithm::EcP256).unwrap();
+        let csr = crate::module::cert::new_csr(
+            &subject,
+            keys,
+            vec![],
+            openssl::stack::Stack::new().unwrap(),
+        )
+        .unwrap();
+        let csr = openssl::x509::X509Req::from_pem(&csr)
+            .unwrap()
+            .to_der()
+            .unwrap();
+        assert!(csr_extensions(&csr).unwrap().is_empty());
+    }
+}
@@ -11,6 +11,12 @@ where
     runtime: std::sync::Arc<tokio::sync::Mutex<M>>,
 }
 
+#[derive(Debug, serde::Deserialize)]
+pub(crate) struct IdentityCertificateRequest {
+    #[serde(flatten)]
+    key: super::KeyRequest,
+}
+
 #[async_trait::async_trait]
 impl<M> http_common::server::Route for Route<M>
 where
@@ -47,6 +53,7 @@ where
         let api = super::CertApi::new(
             service.key_client.clone(),
             service.cert_client.clone(),
+            service.issued_certs.clone(),
             &service.config,
         );
 
@@ -61,11 +68,11 @@ where
 
     type DeleteBody = serde::de::IgnoredAny;
 
-    type PostBody = serde::de::IgnoredAny;
-    async fn post(self, _body: Option<Self::PostBody>) -> http_common::server::RouteResponse {
+    type PostBody = IdentityCertificateRequest;
+    async fn post(self, body: Option<Self::PostBody>) -> http_common::server::RouteResponse {
         edgelet_http::auth_caller(&self.module_id, self.pid, &self.runtime).await?;
 
-        let cert_id = format!("aziot-edged/module/{}:identity", &self.module_id);
+        let cert_id = super::identity_cert_id(&self.module_id);
 
         let subject_alt_names = vec![super::SubjectAltName::Dns(self.module_uri)];
 
@@ -73,8 +80,19 @@ where
             edgelet_http::error::server_error("failed to set identity csr extensions")
         })?;
 
+        // The request body is optional; without one, the device-wide default key is used.
+        let key = body.map(|body| body.key).unwrap_or_default();
+
         self.api
-            .issue_cert(cert_id, self.module_id, subject_alt_names, csr_extensions)
+            .issue_cert(
+                &self.module_id,
+                super::csr::CertificateType::Identity,
+                cert_id,
+                self.module_id.clone(),
+                subject_alt_names,
+                csr_extensions,
+                &key,
+            )
             .await
     }
 
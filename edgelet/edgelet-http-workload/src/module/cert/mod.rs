@@ -1,6 +1,9 @@
 // Copyright (c) Microsoft. All rights reserved.
 
+pub(crate) mod csr;
+mod der;
 pub(crate) mod identity;
+pub(crate) mod renewal;
 pub(crate) mod server;
 
 use std::error::Error as StdError;
@@ -8,6 +11,8 @@ use std::error::Error as StdError;
 use bytes::Bytes;
 use http_body_util::combinators::BoxBody;
 
+use edgelet_settings::base::cert::{KeyAlgorithm, SanPolicy};
+
 #[cfg(not(test))]
 use aziot_cert_client_async::Client as CertClient;
 #[cfg(not(test))]
@@ -36,44 +41,114 @@ pub(crate) struct CertificateResponse {
     expiration: String,
 }
 
+/// A certificate issued for a module's CSR. The module already holds the private key.
+#[derive(Debug, serde::Serialize)]
+#[cfg_attr(test, derive(serde::Deserialize))]
+pub(crate) struct CsrCertificateResponse {
+    certificate: String,
+    expiration: String,
+}
+
+/// The key a caller asks to be generated for its certificate, e.g. `"keyType": "ec"` and
+/// `"keySize": 384`. The device-wide default is used if no type is given.
+#[derive(Debug, Default, serde::Deserialize)]
+pub(crate) struct KeyRequest {
+    #[serde(rename = "keyType")]
+    key_type: Option<String>,
+
+    #[serde(rename = "keySize")]
+    key_size: Option<u32>,
+}
+
+impl KeyRequest {
+    fn key_algorithm(&self) -> Result<Option<KeyAlgorithm>, http_common::server::Error> {
+        match (&self.key_type, self.key_size) {
+            (Some(key_type), key_size) => KeyAlgorithm::from_type_and_size(key_type, key_size)
+                .map(Some)
+                .map_err(|err| http_common::server::Error {
+                    status_code: http::StatusCode::BAD_REQUEST,
+                    message: err.into(),
+                }),
+            (None, Some(_)) => Err(edgelet_http::error::bad_request("keySize requires keyType")),
+            (None, None) => Ok(None),
+        }
+    }
+}
+
 pub(crate) enum SubjectAltName {
     Dns(String),
     Ip(String),
+    Uri(String),
 }
 
 struct CertApi {
     key_client: std::sync::Arc<tokio::sync::Mutex<KeyClient>>,
     cert_client: std::sync::Arc<tokio::sync::Mutex<CertClient>>,
+    issued_certs: renewal::IssuedCerts,
 
     edge_ca_cert: String,
     edge_ca_key: String,
+    key_algorithm: KeyAlgorithm,
+    san_policy: Option<SanPolicy>,
 }
 
 impl CertApi {
     pub fn new(
         key_client: std::sync::Arc<tokio::sync::Mutex<KeyClient>>,
         cert_client: std::sync::Arc<tokio::sync::Mutex<CertClient>>,
+        issued_certs: renewal::IssuedCerts,
         config: &crate::WorkloadConfig,
     ) -> Self {
         CertApi {
             key_client,
             cert_client,
+            issued_certs,
             edge_ca_cert: config.edge_ca_cert.clone(),
             edge_ca_key: config.edge_ca_key.clone(),
+            key_algorithm: config.key_algorithm,
+            san_policy: config.san_policy.clone(),
+        }
+    }
+
+    /// Checks that the SAN policy allows the module to have `name` in its server certificate.
+    /// Violations are logged, since they may come from a compromised module.
+    pub fn check_san(&self, module_id: &str, name: &str) -> Result<(), http_common::server::Error> {
+        // Server certificates always have the module's own name.
+        if name.eq_ignore_ascii_case(module_id.trim_start_matches('$')) {
+            return Ok(());
+        }
+
+        match &self.san_policy {
+            Some(policy) if !policy.allows(module_id, name) => {
+                log::warn!(
+                    "Module {module_id} requested a certificate for {name}, which its SAN policy does not allow"
+                );
+
+                Err(http_common::server::Error {
+                    status_code: http::StatusCode::FORBIDDEN,
+                    message: format!("SAN {name} is not allowed by the SAN policy").into(),
+                })
+            }
+            _ => Ok(()),
         }
     }
 
     pub async fn issue_cert(
         self,
+        module_id: &str,
+        cert_type: csr::CertificateType,
         cert_id: String,
         common_name: String,
         subject_alt_names: Vec<SubjectAltName>,
         extensions: openssl::stack::Stack<openssl::x509::X509Extension>,
+        key_request: &KeyRequest,
     ) -> Result<
         hyper::Response<BoxBody<Bytes, Box<dyn StdError + Send + Sync>>>,
         http_common::server::Error,
     > {
-        let keys = new_keys()
+        let key_algorithm = key_request.key_algorithm()?.unwrap_or(self.key_algorithm);
+
+        let keys = new_keys(key_algorithm)
             .map_err(|_| edgelet_http::error::server_error("failed to generate csr keys"))?;
         let private_key = key_to_pem(&keys.0);
 
@@ -87,23 +162,38 @@ impl CertApi {
         let csr = new_csr(&subject, keys, subject_alt_names, extensions)
             .map_err(|_| edgelet_http::error::server_error("failed to generate csr"))?;
 
-        let edge_ca_key_handle = {
-            let key_client = self.key_client.lock().await;
+        let cert = self.create_cert(&cert_id, &csr).await?;
+        self.track(cert_id, module_id, cert_type, &cert);
 
-            key_client
-                .load_key_pair(&self.edge_ca_key)
-                .await
-                .map_err(|_| edgelet_http::error::server_error("failed to get edge CA key"))?
+        let expiration = get_expiration(&cert)?;
+
+        let response = CertificateResponse {
+            private_key: PrivateKey::Key { bytes: private_key },
+            certificate: cert,
+            expiration,
         };
+        let response = http_common::server::response::json(hyper::StatusCode::CREATED, &response);
 
-        let cert = self
-            .create_cert(&cert_id, &csr, &edge_ca_key_handle)
-            .await?;
+        Ok(response)
+    }
+
+    /// Issues a certificate for a CSR that the module generated and validated by the caller.
+    pub async fn sign_csr(
+        self,
+        module_id: &str,
+        cert_type: csr::CertificateType,
+        cert_id: String,
+        csr: &[u8],
+    ) -> Result<
+        hyper::Response<BoxBody<Bytes, Box<dyn StdError + Send + Sync>>>,
+        http_common::server::Error,
+    > {
+        let cert = self.create_cert(&cert_id, csr).await?;
+        self.track(cert_id, module_id, cert_type, &cert);
 
         let expiration = get_expiration(&cert)?;
 
-        let response = CertificateResponse {
-            private_key: PrivateKey::Key { bytes: private_key },
+        let response = CsrCertificateResponse {
             certificate: cert,
             expiration,
         };
@@ -112,17 +202,42 @@ impl CertApi {
         Ok(response)
     }
 
+    /// Tracks an issued certificate so that the module can be told when to renew it. Failing
+    /// to track it doesn't fail the request; the module just isn't told.
+    fn track(&self, cert_id: String, module_id: &str, cert_type: csr::CertificateType, cert: &str) {
+        let tracked = openssl::x509::X509::from_pem(cert.as_bytes()).and_then(|cert| {
+            self.issued_certs
+                .insert(cert_id, module_id, cert_type, &cert)
+        });
+
+        if let Err(err) = tracked {
+            log::warn!("Failed to track certificate of module {module_id} for renewal: {err}");
+        }
+    }
+
     async fn create_cert(
         &self,
         cert_id: &str,
         csr: &[u8],
-        edge_ca_key_handle: &aziot_key_common::KeyHandle,
     ) -> Result<String, http_common::server::Error> {
+        let edge_ca_key_handle = {
+            let key_client = self.key_client.lock().await;
+
+            key_client
+                .load_key_pair(&self.edge_ca_key)
+                .await
+                .map_err(|_| edgelet_http::error::server_error("failed to get edge CA key"))?
+        };
+
         let cert = {
             let cert_client = self.cert_client.lock().await;
 
             cert_client
-                .create_cert(cert_id, csr, Some((&self.edge_ca_cert, edge_ca_key_handle)))
+                .create_cert(
+                    cert_id,
+                    csr,
+                    Some((&self.edge_ca_cert, &edge_ca_key_handle)),
+                )
                 .await
                 .map_err(|_| {
                     edgelet_http::error::server_error(format!("failed to create cert {cert_id}"))
@@ -136,15 +251,48 @@ impl CertApi {
     }
 }
 
-fn new_keys() -> Result<
+/// The certd ID of a module's server certificate for generation `gen_id`.
+fn server_cert_id(module_id: &str, gen_id: &str) -> String {
+    format!(
+        "aziot-edged/module/{}:{gen_id}:server",
+        cert_module_id(module_id)
+    )
+}
+
+/// The certd ID of a module's identity certificate.
+fn identity_cert_id(module_id: &str) -> String {
+    format!("aziot-edged/module/{}:identity", cert_module_id(module_id))
+}
+
+/// Removes any leading '$' from modules like '$edgeAgent' and '$edgeHub' for consistency
+/// with previous versions, so that every certificate of a module is named alike.
+fn cert_module_id(module_id: &str) -> &str {
+    module_id.trim_start_matches('$')
+}
+
+pub(crate) fn new_keys(
+    algorithm: KeyAlgorithm,
+) -> Result<
     (
         openssl::pkey::PKey<openssl::pkey::Private>,
         openssl::pkey::PKey<openssl::pkey::Public>,
     ),
     openssl::error::ErrorStack,
 > {
-    let rsa = openssl::rsa::Rsa::generate(2048)?;
-    let private_key = openssl::pkey::PKey::from_rsa(rsa)?;
+    let rsa = |bits| openssl::rsa::Rsa::generate(bits).and_then(openssl::pkey::PKey::from_rsa);
+    let ec = |curve| -> Result<_, openssl::error::ErrorStack> {
+        let group = openssl::ec::EcGroup::from_curve_name(curve)?;
+        openssl::ec::EcKey::generate(&group).and_then(openssl::pkey::PKey::from_ec_key)
+    };
+
+    let private_key = match algorithm {
+        KeyAlgorithm::Rsa2048 => rsa(2048)?,
+        KeyAlgorithm::Rsa3072 => rsa(3072)?,
+        KeyAlgorithm::Rsa4096 => rsa(4096)?,
+        KeyAlgorithm::EcP256 => ec(openssl::nid::Nid::X9_62_PRIME256V1)?,
+        KeyAlgorithm::EcP384 => ec(openssl::nid::Nid::SECP384R1)?,
+        KeyAlgorithm::Ed25519 => openssl::pkey::PKey::generate_ed25519()?,
+    };
 
     let public_key = private_key.public_key_to_pem()?;
     let public_key = openssl::pkey::PKey::public_key_from_pem(&public_key)?;
@@ -177,6 +325,7 @@ pub(crate) fn new_csr(
             match name {
                 SubjectAltName::Dns(name) => names.dns(&name),
                 SubjectAltName::Ip(name) => names.ip(&name),
+                SubjectAltName::Uri(name) => names.uri(&name),
             };
         }
 
@@ -186,13 +335,27 @@ pub(crate) fn new_csr(
 
     csr.add_extensions(&extensions)?;
 
-    csr.sign(&private_key, openssl::hash::MessageDigest::sha256())?;
+    // The certificate issued for the CSR is signed by the Edge CA key with the digest certd
+    // picks for it, whatever the type of the key in the CSR.
+    csr.sign(&private_key, signature_digest(&private_key))?;
 
     let csr = csr.build().to_pem()?;
 
     Ok(csr)
 }
 
+/// The digest to sign with `key`. Ed25519 signs the message itself rather than a digest of it.
+pub(crate) fn signature_digest<T>(key: &openssl::pkey::PKeyRef<T>) -> openssl::hash::MessageDigest
+where
+    T: openssl::pkey::HasPublic,
+{
+    match key.id() {
+        openssl::pkey::Id::ED25519 => openssl::hash::MessageDigest::null(),
+        openssl::pkey::Id::EC if key.bits() > 256 => openssl::hash::MessageDigest::sha384(),
+        _ => openssl::hash::MessageDigest::sha256(),
+    }
+}
+
 fn get_expiration(cert: &str) -> Result<String, http_common::server::Error> {
     let cert = openssl::x509::X509::from_pem(cert.as_bytes())
         .map_err(|_| edgelet_http::error::server_error("failed to parse cert"))?;
@@ -233,9 +396,12 @@ mod tests {
         super::CertApi {
             key_client,
             cert_client,
+            issued_certs: super::renewal::IssuedCerts::new(80),
 
             edge_ca_cert: "test-device-cert".to_string(),
             edge_ca_key: "test-device-key".to_string(),
+            key_algorithm: super::KeyAlgorithm::default(),
+            san_policy: None,
         }
     }
 
@@ -253,11 +419,14 @@ mod tests {
 
         let response = api
             .issue_cert(
+                "testModule",
+                super::csr::CertificateType::Server,
                 "testCertificate".to_string(),
                 "testCertificate".to_string(),
                 // This test won't check these fields, so it doesn't matter what's passed here.
                 vec![],
                 extensions,
+                &super::KeyRequest::default(),
             )
             .await
             .unwrap();
@@ -285,4 +454,78 @@ mod tests {
         // Check certificate is signed by issuer key.
         assert!(cert.verify(&issuer_key).unwrap());
     }
+
+    #[test]
+    fn san_policy() {
+        let mut api = test_api();
+
+        // Without a policy, any name is allowed.
+        assert!(api.check_san("testModule", "edgeHub").is_ok());
+
+        api.san_policy = Some(edgelet_settings::base::cert::SanPolicy::new(
+            vec!["{module}.*".to_string()],
+            std::collections::BTreeMap::new(),
+        ));
+
+        assert!(api.check_san("testModule", "testModule.local").is_ok());
+        assert!(api.check_san("testModule", "testmodule").is_ok());
+
+        let response = api.check_san("testModule", "edgeHub.local").unwrap_err();
+        assert_eq!(hyper::StatusCode::FORBIDDEN, response.status_code);
+    }
+
+    #[test]
+    fn key_algorithms() {
+        for (algorithm, id, bits) in [
+            (super::KeyAlgorithm::Rsa3072, openssl::pkey::Id::RSA, 3072),
+            (super::KeyAlgorithm::EcP256, openssl::pkey::Id::EC, 256),
+            (super::KeyAlgorithm::EcP384, openssl::pkey::Id::EC, 384),
+            (
+                super::KeyAlgorithm::Ed25519,
+                openssl::pkey::Id::ED25519,
+                253,
+            ),
+        ] {
+            let keys = super::new_keys(algorithm).unwrap();
+            assert_eq!(id, keys.0.id());
+            assert_eq!(bits, keys.0.bits());
+
+            let public_key = keys.1.clone();
+            let subject = openssl::x509::X509Name::builder().unwrap().build();
+            let csr = super::new_csr(
+                &subject,
+                keys,
+                vec![],
+                openssl::stack::Stack::new().unwrap(),
+            )
+            .unwrap();
+
+            // The CSR is signed by its own key.
+            let csr = openssl::x509::X509Req::from_pem(&csr).unwrap();
+            assert!(csr.verify(&public_key).unwrap());
+        }
+    }
+
+    #[test]
+    fn key_request() {
+        let request: super::KeyRequest =
+            serde_json::from_value(serde_json::json!({ "keyType": "ec", "keySize": 384 })).unwrap();
+        assert_eq!(
+            Some(super::KeyAlgorithm::EcP384),
+            request.key_algorithm().unwrap()
+        );
+
+        let request: super::KeyRequest = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert_eq!(None, request.key_algorithm().unwrap());
+
+        // A size without a type and unsupported sizes are rejected.
+        let request: super::KeyRequest =
+            serde_json::from_value(serde_json::json!({ "keySize": 256 })).unwrap();
+        request.key_algorithm().unwrap_err();
+
+        let request: super::KeyRequest =
+            serde_json::from_value(serde_json::json!({ "keyType": "rsa", "keySize": 1024 }))
+                .unwrap();
+        request.key_algorithm().unwrap_err();
+    }
 }
@@ -0,0 +1,501 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use http_body_util::BodyExt;
+
+use super::csr::CertificateType;
+
+/// A module certificate issued by edged, tracked until it is due for renewal.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct IssuedCert {
+    module_id: String,
+    cert_type: CertificateType,
+    expiration: DateTime<Utc>,
+    renew_after: DateTime<Utc>,
+    reason: RenewalReason,
+}
+
+/// Why a module should renew a certificate.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum RenewalReason {
+    /// The renewal threshold of the certificate's lifetime has passed.
+    Expiring,
+
+    /// The certificate was issued by an Edge CA certificate that has since been renewed.
+    EdgeCaRenewed,
+}
+
+/// The certificates issued to modules, by certificate ID. Certificates are only tracked while
+/// edged runs; modules are restarted with edged and request new certificates anyway.
+#[derive(Clone)]
+pub(crate) struct IssuedCerts {
+    renewal_threshold: u8,
+    certs: std::sync::Arc<tokio::sync::watch::Sender<BTreeMap<String, IssuedCert>>>,
+}
+
+impl IssuedCerts {
+    pub(crate) fn new(renewal_threshold: u8) -> Self {
+        let (certs, _) = tokio::sync::watch::channel(BTreeMap::new());
+
+        IssuedCerts {
+            renewal_threshold,
+            certs: std::sync::Arc::new(certs),
+        }
+    }
+
+    /// Tracks `cert`, replacing the certificate previously issued with the same ID.
+    pub(super) fn insert(
+        &self,
+        cert_id: String,
+        module_id: &str,
+        cert_type: CertificateType,
+        cert: &openssl::x509::X509Ref,
+    ) -> Result<(), openssl::error::ErrorStack> {
+        let issued = unix_time(cert.not_before())?;
+        let expiration = unix_time(cert.not_after())?;
+
+        let renew_after = issued + (expiration - issued) * i32::from(self.renewal_threshold) / 100;
+
+        let cert = IssuedCert {
+            module_id: module_id.to_string(),
+            cert_type,
+            expiration,
+            renew_after,
+            reason: RenewalReason::Expiring,
+        };
+
+        self.certs.send_modify(|certs| {
+            certs.insert(cert_id, cert);
+        });
+
+        Ok(())
+    }
+
+    /// Makes every tracked certificate due at `now`, so that modules replace the certificates
+    /// issued by the previous Edge CA.
+    pub(crate) fn renew_all(&self, now: DateTime<Utc>) {
+        self.certs.send_if_modified(|certs| {
+            for cert in certs.values_mut() {
+                cert.renew_after = cert.renew_after.min(now);
+                cert.reason = RenewalReason::EdgeCaRenewed;
+            }
+
+            !certs.is_empty()
+        });
+    }
+
+    /// The ID, module and type of every tracked certificate.
+    pub(crate) fn list(&self) -> Vec<(String, String, CertificateType)> {
+        self.certs
+            .borrow()
+            .iter()
+            .map(|(cert_id, cert)| (cert_id.clone(), cert.module_id.clone(), cert.cert_type))
+            .collect()
+    }
+
+    /// Stops tracking the certificates of `module_id`.
+    pub(crate) fn remove_module(&self, module_id: &str) {
+        self.certs.send_if_modified(|certs| {
+            let count = certs.len();
+            certs.retain(|_, cert| !same_module(&cert.module_id, module_id));
+
+            certs.len() != count
+        });
+    }
+}
+
+/// Tells a module that one of its certificates is due for renewal.
+#[derive(Debug, PartialEq, serde::Serialize)]
+pub(crate) struct RenewalNotice {
+    #[serde(rename = "type")]
+    cert_type: CertificateType,
+
+    expiration: String,
+
+    #[serde(rename = "renewAfter")]
+    renew_after: String,
+
+    reason: RenewalReason,
+}
+
+#[derive(Debug, PartialEq)]
+enum Next {
+    /// The certificate with this ID is due and the module has not been told.
+    Due(String, Notified, RenewalNotice),
+
+    /// No certificate is due before this time.
+    At(DateTime<Utc>),
+
+    /// The module has no certificates that will become due.
+    Never,
+}
+
+/// The expiration and renewal reason of a certificate a module was told about.
+type Notified = (DateTime<Utc>, RenewalReason);
+
+/// What to tell `module_id` next. `notified` has what the module was told about each
+/// certificate, so that a certificate re-issued with the same ID, or due again because the
+/// Edge CA was renewed, is notified again.
+fn next(
+    certs: &BTreeMap<String, IssuedCert>,
+    module_id: &str,
+    notified: &BTreeMap<String, Notified>,
+    now: DateTime<Utc>,
+) -> Next {
+    let mut pending = certs.iter().filter(|(cert_id, cert)| {
+        same_module(&cert.module_id, module_id)
+            && notified.get(*cert_id) != Some(&(cert.expiration, cert.reason))
+    });
+
+    if let Some((cert_id, cert)) = pending.clone().find(|(_, cert)| cert.renew_after <= now) {
+        let notice = RenewalNotice {
+            cert_type: cert.cert_type,
+            expiration: cert.expiration.to_rfc3339(),
+            renew_after: cert.renew_after.to_rfc3339(),
+            reason: cert.reason,
+        };
+
+        return Next::Due(cert_id.clone(), (cert.expiration, cert.reason), notice);
+    }
+
+    pending
+        .map(|(_, cert)| cert.renew_after)
+        .min()
+        .map_or(Next::Never, Next::At)
+}
+
+/// Names of modules like '$edgeHub' are used without the '$' in some certificate IDs.
+fn same_module(a: &str, b: &str) -> bool {
+    a.trim_start_matches('$') == b.trim_start_matches('$')
+}
+
+pub(crate) fn unix_time(
+    time: &openssl::asn1::Asn1TimeRef,
+) -> Result<DateTime<Utc>, openssl::error::ErrorStack> {
+    let diff = openssl::asn1::Asn1Time::from_unix(0)?.diff(time)?;
+    let secs = i64::from(diff.days) * 86400 + i64::from(diff.secs);
+
+    Ok(DateTime::from_timestamp(secs, 0).unwrap_or(DateTime::<Utc>::MAX_UTC))
+}
+
+pub(crate) struct Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    issued_certs: IssuedCerts,
+    module_id: String,
+    pid: libc::pid_t,
+    runtime: std::sync::Arc<tokio::sync::Mutex<M>>,
+}
+
+#[async_trait::async_trait]
+impl<M> http_common::server::Route for Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    type ApiVersion = edgelet_http::ApiVersion;
+    fn api_version() -> &'static dyn http_common::DynRangeBounds<Self::ApiVersion> {
+        &((edgelet_http::ApiVersion::V2026_10_18)..)
+    }
+
+    type Service = crate::Service<M>;
+    fn from_uri(
+        service: &Self::Service,
+        path: &str,
+        _query: &[(std::borrow::Cow<'_, str>, std::borrow::Cow<'_, str>)],
+        extensions: &http::Extensions,
+    ) -> Option<Self> {
+        let uri_regex = regex::Regex::new(
+            "^/modules/(?P<moduleId>[^/]+)/genid/(?P<genId>[^/]+)/certificate/renewal$",
+        )
+        .expect("hard-coded regex must compile");
+        let captures = uri_regex.captures(path)?;
+
+        let module_id = &captures["moduleId"];
+        let module_id = percent_encoding::percent_decode_str(module_id)
+            .decode_utf8()
+            .ok()?;
+
+        let pid = extensions.get::<Option<libc::pid_t>>().copied()??;
+
+        Some(Route {
+            issued_certs: service.issued_certs.clone(),
+            module_id: module_id.into_owned(),
+            pid,
+            runtime: service.runtime.clone(),
+        })
+    }
+
+    type DeleteBody = serde::de::IgnoredAny;
+
+    async fn get(self) -> http_common::server::RouteResponse {
+        edgelet_http::auth_caller(&self.module_id, self.pid, &self.runtime).await?;
+
+        let receiver = self.issued_certs.certs.subscribe();
+        let module_id = self.module_id;
+
+        let notices = futures_util::stream::unfold(
+            (receiver, BTreeMap::new()),
+            move |(mut receiver, mut notified)| {
+                let module_id = module_id.clone();
+
+                async move {
+                    loop {
+                        let step = next(
+                            &receiver.borrow_and_update(),
+                            &module_id,
+                            &notified,
+                            Utc::now(),
+                        );
+
+                        let wake = match step {
+                            Next::Due(cert_id, told, notice) => {
+                                notified.insert(cert_id, told);
+
+                                return Some((notice, (receiver, notified)));
+                            }
+                            Next::At(wake) => Some(wake),
+                            Next::Never => None,
+                        };
+
+                        let sleep = async {
+                            match wake {
+                                Some(wake) => {
+                                    let delay = (wake - Utc::now()).to_std().unwrap_or_default();
+                                    tokio::time::sleep(delay).await;
+                                }
+                                None => std::future::pending().await,
+                            }
+                        };
+
+                        tokio::select! {
+                            changed = receiver.changed() => {
+                                if changed.is_err() {
+                                    return None;
+                                }
+                            }
+                            () = sleep => {}
+                        }
+                    }
+                }
+            },
+        );
+
+        let body = notices.map(|notice| {
+            let mut line = serde_json::to_vec(&notice)?;
+            line.push(b'\n');
+
+            Ok::<_, Box<dyn std::error::Error + Send + Sync>>(hyper::body::Frame::data(
+                hyper::body::Bytes::from(line),
+            ))
+        });
+
+        let res = hyper::Response::builder()
+            .status(hyper::StatusCode::OK)
+            .header(hyper::header::CONTENT_TYPE, "application/x-ndjson")
+            .body(http_body_util::StreamBody::new(body).boxed())
+            .expect("cannot fail to build hyper response");
+        Ok(res)
+    }
+
+    type PostBody = serde::de::IgnoredAny;
+
+    type PutBody = serde::de::IgnoredAny;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use chrono::{DateTime, TimeDelta, Utc};
+
+    use http_common::server::Route;
+
+    use edgelet_test_utils::{test_route_err, test_route_ok};
+
+    use super::{CertificateType, IssuedCert, IssuedCerts, Next, RenewalReason};
+
+    const TEST_PATH: &str = "/modules/testModule/genid/1/certificate/renewal";
+
+    #[test]
+    fn parse_uri() {
+        // Valid URI
+        let route = test_route_ok!(TEST_PATH);
+        assert_eq!("testModule", &route.module_id);
+        assert_eq!(nix::unistd::getpid().as_raw(), route.pid);
+
+        // Missing module ID
+        test_route_err!("/modules//genid/1/certificate/renewal");
+
+        // Missing generation ID
+        test_route_err!("/modules/testModule/genid//certificate/renewal");
+
+        // Extra character at beginning of URI
+        test_route_err!(&format!("a{TEST_PATH}"));
+
+        // Extra character at end of URI
+        test_route_err!(&format!("{TEST_PATH}a"));
+    }
+
+    #[tokio::test]
+    async fn auth() {
+        async fn get(
+            route: super::Route<edgelet_test_utils::runtime::Runtime>,
+        ) -> http_common::server::RouteResponse {
+            route.get().await
+        }
+
+        edgelet_test_utils::test_auth_caller!(TEST_PATH, "testModule", get);
+    }
+
+    #[test]
+    fn insert() {
+        let keys =
+            crate::module::cert::new_keys(edgelet_settings::base::cert::KeyAlgorithm::EcP256)
+                .unwrap();
+
+        let mut cert = openssl::x509::X509::builder().unwrap();
+        cert.set_pubkey(&keys.1).unwrap();
+        cert.set_not_before(&openssl::asn1::Asn1Time::from_unix(1_000_000).unwrap())
+            .unwrap();
+        cert.set_not_after(&openssl::asn1::Asn1Time::from_unix(1_100_000).unwrap())
+            .unwrap();
+        cert.sign(&keys.0, openssl::hash::MessageDigest::sha256())
+            .unwrap();
+        let cert = cert.build();
+
+        let issued_certs = IssuedCerts::new(80);
+        issued_certs
+            .insert(
+                "cert".to_string(),
+                "$edgeHub",
+                CertificateType::Server,
+                &cert,
+            )
+            .unwrap();
+
+        assert_eq!(
+            Some(&IssuedCert {
+                module_id: "$edgeHub".to_string(),
+                cert_type: CertificateType::Server,
+                expiration: DateTime::from_timestamp(1_100_000, 0).unwrap(),
+                renew_after: DateTime::from_timestamp(1_080_000, 0).unwrap(),
+                reason: RenewalReason::Expiring,
+            }),
+            issued_certs.certs.borrow().get("cert")
+        );
+
+        // Certificates are forgotten when their module is deleted.
+        issued_certs.remove_module("edgeHub");
+        assert!(issued_certs.certs.borrow().is_empty());
+    }
+
+    #[test]
+    fn next() {
+        let start = DateTime::<Utc>::from_timestamp(1_000_000, 0).unwrap();
+
+        let cert = |module_id: &str, cert_type, renew_after| IssuedCert {
+            module_id: module_id.to_string(),
+            cert_type,
+            expiration: start + TimeDelta::hours(10),
+            renew_after: start + TimeDelta::hours(renew_after),
+            reason: RenewalReason::Expiring,
+        };
+
+        let mut certs = BTreeMap::new();
+        certs.insert(
+            "server".to_string(),
+            cert("testModule", CertificateType::Server, 8),
+        );
+        certs.insert(
+            "identity".to_string(),
+            cert("testModule", CertificateType::Identity, 4),
+        );
+        certs.insert(
+            "other".to_string(),
+            cert("otherModule", CertificateType::Server, 1),
+        );
+
+        let mut notified = BTreeMap::new();
+
+        // Nothing is due before the earliest renewal time of the module's certificates.
+        assert_eq!(
+            Next::At(start + TimeDelta::hours(4)),
+            super::next(&certs, "testModule", &notified, start)
+        );
+
+        let now = start + TimeDelta::hours(5);
+        let Next::Due(cert_id, told, notice) = super::next(&certs, "testModule", &notified, now)
+        else {
+            panic!("identity certificate should be due");
+        };
+        assert_eq!("identity", cert_id);
+        assert_eq!(CertificateType::Identity, notice.cert_type);
+        assert_eq!(RenewalReason::Expiring, notice.reason);
+
+        // A certificate is only notified once.
+        notified.insert(cert_id, told);
+        assert_eq!(
+            Next::At(start + TimeDelta::hours(8)),
+            super::next(&certs, "testModule", &notified, now)
+        );
+
+        // A re-issued certificate is notified again when it becomes due.
+        certs.get_mut("identity").unwrap().expiration = start + TimeDelta::hours(20);
+        assert!(matches!(
+            super::next(&certs, "testModule", &notified, now),
+            Next::Due(..)
+        ));
+
+        assert_eq!(
+            Next::Never,
+            super::next(&certs, "unknownModule", &notified, now)
+        );
+    }
+
+    #[test]
+    fn renew_all() {
+        let start = DateTime::<Utc>::from_timestamp(1_000_000, 0).unwrap();
+
+        let issued_certs = IssuedCerts::new(80);
+        issued_certs.certs.send_modify(|certs| {
+            certs.insert(
+                "server".to_string(),
+                IssuedCert {
+                    module_id: "testModule".to_string(),
+                    cert_type: CertificateType::Server,
+                    expiration: start + TimeDelta::hours(10),
+                    renew_after: start + TimeDelta::hours(8),
+                    reason: RenewalReason::Expiring,
+                },
+            );
+        });
+
+        // The module was already told that its certificate is expiring.
+        let mut notified = BTreeMap::new();
+        notified.insert(
+            "server".to_string(),
+            (start + TimeDelta::hours(10), RenewalReason::Expiring),
+        );
+
+        // Renewing the Edge CA makes the certificate due again, with the new reason.
+        let now = start + TimeDelta::hours(9);
+        issued_certs.renew_all(now);
+
+        let Next::Due(cert_id, _, notice) =
+            super::next(&issued_certs.certs.borrow(), "testModule", &notified, now)
+        else {
+            panic!("server certificate should be due");
+        };
+        assert_eq!("server", cert_id);
+        assert_eq!(RenewalReason::EdgeCaRenewed, notice.reason);
+        assert_eq!(
+            (start + TimeDelta::hours(8)).to_rfc3339(),
+            notice.renew_after
+        );
+    }
+}
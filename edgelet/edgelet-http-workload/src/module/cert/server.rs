@@ -17,6 +17,9 @@ where
 pub(crate) struct ServerCertificateRequest {
     #[serde(rename = "commonName")]
     common_name: String,
+
+    #[serde(flatten)]
+    key: super::KeyRequest,
 }
 
 #[async_trait::async_trait]
@@ -57,6 +60,7 @@ where
         let api = super::CertApi::new(
             service.key_client.clone(),
             service.cert_client.clone(),
+            service.issued_certs.clone(),
             &service.config,
         );
 
@@ -75,16 +79,15 @@ where
     async fn post(self, body: Option<Self::PostBody>) -> http_common::server::RouteResponse {
         edgelet_http::auth_caller(&self.module_id, self.pid, &self.runtime).await?;
 
-        let common_name = match body {
-            Some(body) => body.common_name,
-            None => return Err(edgelet_http::error::bad_request("missing request body")),
+        let Some(ServerCertificateRequest { common_name, key }) = body else {
+            return Err(edgelet_http::error::bad_request("missing request body"));
         };
 
-        // Remove any leading '$' from modules like '$edgeAgent' and '$edgeHub' for consistency
-        // with previous versions.
-        let module_id = self.module_id.trim_start_matches('$');
+        let module_id = super::cert_module_id(&self.module_id);
+
+        let cert_id = super::server_cert_id(&self.module_id, &self.gen_id);
 
-        let cert_id = format!("aziot-edged/module/{}:{}:server", &module_id, &self.gen_id);
+        self.api.check_san(&self.module_id, &common_name)?;
 
         // SANs take precedence over CN. The CN must be in the SAN list to be considered.
         let common_name_san = common_name.clone();
@@ -105,7 +108,15 @@ where
         })?;
 
         self.api
-            .issue_cert(cert_id, common_name, subject_alt_names, csr_extensions)
+            .issue_cert(
+                &self.module_id,
+                super::csr::CertificateType::Server,
+                cert_id,
+                common_name,
+                subject_alt_names,
+                csr_extensions,
+                &key,
+            )
             .await
     }
 
@@ -144,6 +155,7 @@ mod tests {
     ) -> http_common::server::RouteResponse {
         let body = super::ServerCertificateRequest {
             common_name: MODULE_NAME.to_string(),
+            key: crate::module::cert::KeyRequest::default(),
         };
 
         route.post(Some(body)).await
@@ -202,4 +214,42 @@ mod tests {
             assert_eq!(MODULE_NAME.to_lowercase(), name.to_lowercase());
         }
     }
+
+    #[tokio::test]
+    async fn san_policy() {
+        let post = |common_name: &str| {
+            let mut route = edgelet_test_utils::test_route_ok!(TEST_PATH);
+            route.api.san_policy = Some(edgelet_settings::base::cert::SanPolicy::new(
+                vec!["{module}.*".to_string()],
+                std::collections::BTreeMap::new(),
+            ));
+
+            let body = super::ServerCertificateRequest {
+                common_name: common_name.to_string(),
+                key: crate::module::cert::KeyRequest::default(),
+            };
+
+            async move {
+                {
+                    let pid = nix::unistd::getpid().as_raw();
+                    let mut runtime = route.runtime.lock().await;
+                    runtime.module_auth = std::collections::BTreeMap::new();
+                    runtime
+                        .module_auth
+                        .insert(MODULE_NAME.to_string(), vec![pid]);
+                }
+
+                route.post(Some(body)).await
+            }
+        };
+
+        post("testModule.azure-iot-edge").await.unwrap();
+        post(MODULE_NAME).await.unwrap();
+
+        // Names outside the policy, such as those of other modules, are forbidden.
+        for common_name in ["edgeHub", "edgeHub.azure-iot-edge", "10.0.0.1"] {
+            let response = post(common_name).await.unwrap_err();
+            assert_eq!(hyper::StatusCode::FORBIDDEN, response.status_code);
+        }
+    }
 }
@@ -11,6 +11,7 @@ where
     M: edgelet_core::ModuleRuntime + Send + Sync,
 {
     client: std::sync::Arc<tokio::sync::Mutex<KeyClient>>,
+    master_keys: std::sync::Arc<tokio::sync::Mutex<super::master_key::MasterKeys>>,
     module_id: String,
     gen_id: String,
     pid: libc::pid_t,
@@ -67,6 +68,7 @@ where
 
         Some(Route {
             client: service.key_client.clone(),
+            master_keys: service.master_keys.clone(),
             module_id: module_id.into_owned(),
             gen_id: gen_id.into_owned(),
             pid,
@@ -93,10 +95,27 @@ where
         let aad = format!("{}{}", self.module_id, self.gen_id).into_bytes();
         let parameters = aziot_key_common::EncryptMechanism::Aead { iv, aad };
 
+        let (version, ciphertext) = super::master_key::open(&ciphertext);
+
+        if !self
+            .master_keys
+            .lock()
+            .await
+            .can_decrypt(version, chrono::Utc::now())
+        {
+            return Err(http_common::server::Error {
+                status_code: http::StatusCode::BAD_REQUEST,
+                message: format!(
+                    "ciphertext was encrypted with master key version {version}, which has expired"
+                )
+                .into(),
+            });
+        }
+
         let client = self.client.lock().await;
-        let key = super::master_encryption_key(&client).await?;
+        let key = super::encryption_key(&client, version, &self.module_id).await?;
 
-        match client.decrypt(&key, parameters, &ciphertext).await {
+        match client.decrypt(&key, parameters, ciphertext).await {
             Ok(plaintext) => {
                 let engine = base64::engine::general_purpose::STANDARD;
                 let plaintext = base64::Engine::encode(&engine, plaintext);
@@ -202,4 +221,20 @@ mod tests {
         let response: super::DecryptResponse = serde_json::from_slice(&body).unwrap();
         base64::Engine::decode(&engine, response.plaintext).unwrap();
     }
+
+    #[tokio::test]
+    async fn unknown_version() {
+        let engine = base64::engine::general_purpose::STANDARD;
+
+        // Ciphertexts of master key versions that were never current cannot be decrypted.
+        let ciphertext = crate::module::data::master_key::seal(9, b"~");
+        let body = super::DecryptRequest {
+            ciphertext: base64::Engine::encode(&engine, ciphertext),
+            iv: base64::Engine::encode(&engine, "~"),
+        };
+
+        let route = test_route_ok!(TEST_PATH);
+        let response = route.post(Some(body)).await.unwrap_err();
+        assert_eq!(hyper::StatusCode::BAD_REQUEST, response.status_code);
+    }
 }
@@ -11,6 +11,7 @@ where
     M: edgelet_core::ModuleRuntime + Send + Sync,
 {
     client: std::sync::Arc<tokio::sync::Mutex<KeyClient>>,
+    master_keys: std::sync::Arc<tokio::sync::Mutex<super::master_key::MasterKeys>>,
     module_id: String,
     gen_id: String,
     pid: libc::pid_t,
@@ -67,6 +68,7 @@ where
 
         Some(Route {
             client: service.key_client.clone(),
+            master_keys: service.master_keys.clone(),
             module_id: module_id.into_owned(),
             gen_id: gen_id.into_owned(),
             pid,
@@ -93,11 +95,15 @@ where
         let aad = format!("{}{}", self.module_id, self.gen_id).into_bytes();
         let parameters = aziot_key_common::EncryptMechanism::Aead { iv, aad };
 
+        let version = self.master_keys.lock().await.current();
+
         let client = self.client.lock().await;
-        let key = super::master_encryption_key(&client).await?;
+        let key = super::encryption_key(&client, version, &self.module_id).await?;
 
         match client.encrypt(&key, parameters, &plaintext).await {
             Ok(ciphertext) => {
+                let ciphertext = super::master_key::seal(version, &ciphertext);
+
                 let engine = base64::engine::general_purpose::STANDARD;
                 let ciphertext = base64::Engine::encode(&engine, ciphertext);
 
@@ -0,0 +1,297 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! Versions of the master key that module data is encrypted with.
+//!
+//! Version 1 is the key that all modules shared before keys were derived per module. It is
+//! only used to decrypt, and its ciphertexts have no header. Later versions encrypt with a key
+//! derived for each module, and their ciphertexts start with [`HEADER`] and the version.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+
+/// Starts the ciphertexts of versioned keys. keyd's own ciphertexts start with a format
+/// version byte that is never 0xff, so they cannot be mistaken for versioned ones.
+const HEADER: &[u8] = b"\xffiek";
+
+const LEGACY_VERSION: u32 = 1;
+
+/// The keyd ID of the master key `version`.
+pub(super) fn key_id(version: u32) -> String {
+    if version == LEGACY_VERSION {
+        "iotedge_master_encryption_id".to_string()
+    } else {
+        format!("iotedge_master_encryption_id_v{version}")
+    }
+}
+
+pub(super) fn is_legacy(version: u32) -> bool {
+    version == LEGACY_VERSION
+}
+
+/// Prepends the header of key `version` to `ciphertext`.
+pub(super) fn seal(version: u32, ciphertext: &[u8]) -> Vec<u8> {
+    let mut sealed = Vec::with_capacity(HEADER.len() + 4 + ciphertext.len());
+    sealed.extend_from_slice(HEADER);
+    sealed.extend_from_slice(&version.to_be_bytes());
+    sealed.extend_from_slice(ciphertext);
+
+    sealed
+}
+
+/// The key version and keyd ciphertext of `ciphertext`.
+pub(super) fn open(ciphertext: &[u8]) -> (u32, &[u8]) {
+    match ciphertext
+        .strip_prefix(HEADER)
+        .and_then(<[u8]>::split_first_chunk::<4>)
+    {
+        Some((version, rest)) => (u32::from_be_bytes(*version), rest),
+        None => (LEGACY_VERSION, ciphertext),
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+struct Versions {
+    current: u32,
+
+    /// Earlier versions that can still decrypt, with the time they were rotated out. The
+    /// legacy version was replaced by an upgrade rather than a rotation, and has no time
+    /// until the next rotation.
+    previous: BTreeMap<u32, Option<DateTime<Utc>>>,
+}
+
+impl Default for Versions {
+    fn default() -> Self {
+        Versions {
+            current: LEGACY_VERSION + 1,
+            previous: [(LEGACY_VERSION, None)].into(),
+        }
+    }
+}
+
+/// The master key versions, persisted so that rotations survive restarts of edged.
+pub(crate) struct MasterKeys {
+    path: std::path::PathBuf,
+    grace_period: std::time::Duration,
+    versions: Versions,
+
+    /// Whether there were no versions to load, so that the versions are those of an upgrade
+    /// from the legacy key that has not been confirmed and saved yet.
+    upgrade: bool,
+}
+
+impl MasterKeys {
+    /// Reads the versions at `path`. Without a file, the legacy key is replaced by version 2,
+    /// which [`MasterKeys::confirm_upgrade`] must confirm before the keys are used.
+    pub(crate) fn load(
+        path: std::path::PathBuf,
+        grace_period: std::time::Duration,
+    ) -> Result<Self, String> {
+        let (versions, upgrade) = match std::fs::read(&path) {
+            Ok(versions) => (
+                serde_json::from_slice(&versions).map_err(|err| {
+                    format!("invalid encryption key versions {}: {err}", path.display())
+                })?,
+                false,
+            ),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => (Versions::default(), true),
+            Err(err) => {
+                return Err(format!(
+                    "failed to read encryption key versions {}: {err}",
+                    path.display()
+                ));
+            }
+        };
+
+        Ok(MasterKeys {
+            path,
+            grace_period,
+            versions,
+            upgrade,
+        })
+    }
+
+    /// The keyd ID of the key that exists only if the versions were saved before, if they were
+    /// loaded as an upgrade from the legacy key.
+    pub(super) fn upgrade_key_id(&self) -> Option<String> {
+        self.upgrade.then(|| key_id(self.versions.current))
+    }
+
+    /// Saves the versions of an upgrade from the legacy key, once keyd has been found not to
+    /// have the upgrade's key. If it has, the versions were lost rather than never saved, and
+    /// the current version may be a later one, so edged must not start.
+    pub(super) fn confirm_upgrade(&mut self, key_exists: bool) -> Result<(), String> {
+        if !self.upgrade {
+            return Ok(());
+        }
+
+        if key_exists {
+            return Err(format!(
+                "encryption key versions {} are missing, but master encryption key {} exists; \
+                 restore the file to keep module data decryptable",
+                self.path.display(),
+                key_id(self.versions.current),
+            ));
+        }
+
+        save(&self.path, &self.versions).map_err(|err| {
+            format!(
+                "failed to save encryption key versions {}: {err}",
+                self.path.display()
+            )
+        })?;
+        self.upgrade = false;
+
+        Ok(())
+    }
+
+    pub(super) fn current(&self) -> u32 {
+        self.versions.current
+    }
+
+    /// Whether data encrypted with key `version` may be decrypted at `now`.
+    pub(super) fn can_decrypt(&self, version: u32, now: DateTime<Utc>) -> bool {
+        if version == self.versions.current {
+            return true;
+        }
+
+        match self.versions.previous.get(&version) {
+            Some(Some(rotated)) => self.in_grace_period(*rotated, now),
+            Some(None) => true,
+            None => false,
+        }
+    }
+
+    fn in_grace_period(&self, rotated: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        (now - rotated)
+            .to_std()
+            .map_or(true, |elapsed| elapsed < self.grace_period)
+    }
+
+    /// Makes the next version current, and forgets versions whose grace period is over.
+    /// Returns the new version, and the forgotten versions, whose keys can be deleted.
+    pub(super) fn rotate(&mut self, now: DateTime<Utc>) -> std::io::Result<(u32, Vec<u32>)> {
+        let mut previous = std::mem::take(&mut self.versions.previous);
+        previous.insert(self.versions.current, Some(now));
+
+        for rotated in previous.values_mut() {
+            rotated.get_or_insert(now);
+        }
+        let (previous, expired): (BTreeMap<_, _>, BTreeMap<_, _>) =
+            previous.into_iter().partition(|(_, rotated)| {
+                rotated.is_some_and(|rotated| self.in_grace_period(rotated, now))
+            });
+
+        let versions = Versions {
+            current: self.versions.current + 1,
+            previous,
+        };
+        save(&self.path, &versions)?;
+
+        self.versions = versions;
+        self.upgrade = false;
+
+        Ok((self.versions.current, expired.into_keys().collect()))
+    }
+}
+
+fn save(path: &std::path::Path, versions: &Versions) -> std::io::Result<()> {
+    let versions = serde_json::to_vec(versions)?;
+
+    // Replace the file atomically so that a crash never leaves it half-written.
+    let temp_path = path.with_extension("json.tmp");
+    std::fs::write(&temp_path, versions)?;
+    std::fs::rename(&temp_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, TimeDelta, Utc};
+
+    use super::{MasterKeys, key_id, open, seal};
+
+    #[test]
+    fn header() {
+        let sealed = seal(3, b"ciphertext");
+        assert_eq!((3, &b"ciphertext"[..]), open(&sealed));
+
+        // Ciphertexts without a header are from the legacy key.
+        assert_eq!((1, &b"ciphertext"[..]), open(b"ciphertext"));
+        assert_eq!((1, &b"\xffiek\x00"[..]), open(b"\xffiek\x00"));
+
+        assert_eq!("iotedge_master_encryption_id", key_id(1));
+        assert_eq!("iotedge_master_encryption_id_v3", key_id(3));
+    }
+
+    #[test]
+    fn rotate() {
+        let path =
+            std::env::temp_dir().join(format!("encryption_keys_{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let grace_period = std::time::Duration::from_secs(60);
+        let start = DateTime::<Utc>::from_timestamp(1_000_000, 0).unwrap();
+
+        // The legacy key decrypts until the first rotation.
+        let mut keys = MasterKeys::load(path.clone(), grace_period).unwrap();
+        assert_eq!(2, keys.current());
+        assert!(keys.can_decrypt(1, start + TimeDelta::days(365)));
+        assert!(!keys.can_decrypt(3, start));
+
+        assert_eq!((3, vec![]), keys.rotate(start).unwrap());
+        assert!(keys.can_decrypt(1, start + TimeDelta::seconds(59)));
+        assert!(keys.can_decrypt(2, start + TimeDelta::seconds(59)));
+        assert!(!keys.can_decrypt(1, start + TimeDelta::seconds(60)));
+        assert!(!keys.can_decrypt(2, start + TimeDelta::seconds(60)));
+        assert!(keys.can_decrypt(3, start + TimeDelta::days(365)));
+
+        // Rotations are persisted, and versions past their grace period are forgotten.
+        let mut keys = MasterKeys::load(path.clone(), grace_period).unwrap();
+        assert_eq!(3, keys.current());
+        assert_eq!(
+            (4, vec![1, 2]),
+            keys.rotate(start + TimeDelta::seconds(90)).unwrap()
+        );
+        assert_eq!(
+            vec![3],
+            keys.versions.previous.keys().copied().collect::<Vec<_>>()
+        );
+
+        // An invalid file is an error rather than a reset that would lose the current version.
+        std::fs::write(&path, "invalid").unwrap();
+        assert!(MasterKeys::load(path.clone(), grace_period).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn upgrade() {
+        let path = std::env::temp_dir().join(format!(
+            "encryption_keys_upgrade_{}.json",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+
+        let grace_period = std::time::Duration::from_secs(60);
+
+        // Without versions, version 2 is only assumed if keyd does not have it yet.
+        let mut keys = MasterKeys::load(path.clone(), grace_period).unwrap();
+        assert_eq!(
+            Some("iotedge_master_encryption_id_v2".to_string()),
+            keys.upgrade_key_id()
+        );
+        assert!(keys.confirm_upgrade(true).is_err());
+        assert!(!path.exists());
+
+        // A confirmed upgrade is saved.
+        keys.confirm_upgrade(false).unwrap();
+        assert_eq!(None, keys.upgrade_key_id());
+
+        let mut keys = MasterKeys::load(path.clone(), grace_period).unwrap();
+        assert_eq!(None, keys.upgrade_key_id());
+        keys.confirm_upgrade(true).unwrap();
+        assert_eq!(2, keys.current());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -2,7 +2,9 @@
 
 pub(crate) mod decrypt;
 pub(crate) mod encrypt;
+pub(crate) mod master_key;
 pub(crate) mod sign;
+pub(crate) mod verify;
 
 #[cfg(not(test))]
 use aziot_key_client_async::Client as KeyClient;
@@ -10,7 +12,7 @@ use aziot_key_client_async::Client as KeyClient;
 #[cfg(test)]
 use test_common::client::KeyClient;
 
-fn base64_decode(data: String) -> Result<Vec<u8>, http_common::server::Error> {
+pub(super) fn base64_decode(data: String) -> Result<Vec<u8>, http_common::server::Error> {
     let engine = base64::engine::general_purpose::STANDARD;
 
     base64::Engine::decode(&engine, data).map_err(|err| http_common::server::Error {
@@ -19,19 +21,110 @@ fn base64_decode(data: String) -> Result<Vec<u8>, http_common::server::Error> {
     })
 }
 
-async fn master_encryption_key(
+/// The key that encrypts the data of `module_id` with master key `version`. Later versions
+/// derive a key for each module so that one module's key cannot decrypt another's data.
+async fn encryption_key(
     client: &KeyClient,
+    version: u32,
+    module_id: &str,
 ) -> Result<aziot_key_common::KeyHandle, http_common::server::Error> {
+    let master_key = if master_key::is_legacy(version) {
+        client
+            .create_key_if_not_exists(
+                &master_key::key_id(version),
+                aziot_key_common::CreateKeyValue::Generate,
+                &[aziot_key_common::KeyUsage::Encrypt],
+            )
+            .await
+    } else {
+        create_master_key(client, version).await
+    }
+    .map_err(|err| {
+        edgelet_http::error::server_error(format!("unable to load master encryption key: {err}"))
+    })?;
+
+    if master_key::is_legacy(version) {
+        return Ok(master_key);
+    }
+
     client
-        .create_key_if_not_exists(
-            "iotedge_master_encryption_id",
-            aziot_key_common::CreateKeyValue::Generate,
-            &[aziot_key_common::KeyUsage::Encrypt],
-        )
+        .create_derived_key(&master_key, module_id.as_bytes())
         .await
         .map_err(|err| {
             edgelet_http::error::server_error(format!(
-                "unable to load master encryption key: {err}"
+                "unable to derive module encryption key: {err}"
             ))
         })
 }
+
+async fn create_master_key(
+    client: &KeyClient,
+    version: u32,
+) -> std::io::Result<aziot_key_common::KeyHandle> {
+    client
+        .create_key_if_not_exists(
+            &master_key::key_id(version),
+            aziot_key_common::CreateKeyValue::Generate,
+            &[
+                aziot_key_common::KeyUsage::Derive,
+                aziot_key_common::KeyUsage::Encrypt,
+            ],
+        )
+        .await
+}
+
+/// Confirms that master key versions loaded without a versions file are an upgrade from the
+/// legacy key, rather than versions that were lost after keyd had created later keys.
+pub(crate) async fn check_master_keys(
+    client: &tokio::sync::Mutex<KeyClient>,
+    master_keys: &tokio::sync::Mutex<master_key::MasterKeys>,
+) -> Result<(), String> {
+    let mut master_keys = master_keys.lock().await;
+
+    let Some(key_id) = master_keys.upgrade_key_id() else {
+        return Ok(());
+    };
+    let key_exists = client.lock().await.load_key(&key_id).await.is_ok();
+
+    master_keys.confirm_upgrade(key_exists)
+}
+
+/// Creates the next version of the master key and makes it current, and deletes the keys of
+/// versions whose grace period is over. Returns the new version.
+pub(crate) async fn rotate_master_key(
+    client: &tokio::sync::Mutex<KeyClient>,
+    master_keys: &tokio::sync::Mutex<master_key::MasterKeys>,
+) -> Result<u32, String> {
+    let mut master_keys = master_keys.lock().await;
+    let version = master_keys.current() + 1;
+
+    // Create the key before recording the version so that the current version always exists.
+    {
+        let client = client.lock().await;
+
+        create_master_key(&client, version)
+            .await
+            .map_err(|err| format!("failed to create master encryption key: {err}"))?;
+    }
+
+    let (version, expired) = master_keys
+        .rotate(chrono::Utc::now())
+        .map_err(|err| format!("failed to save master encryption key versions: {err}"))?;
+
+    // The expired versions are no longer recorded, so failing to delete their keys only leaves
+    // unused keys in keyd.
+    let client = client.lock().await;
+    for expired in expired {
+        let key_id = master_key::key_id(expired);
+
+        let deleted = match client.load_key(&key_id).await {
+            Ok(key_handle) => client.delete_key(&key_handle).await,
+            Err(err) => Err(err),
+        };
+        if let Err(err) = deleted {
+            log::warn!("Failed to delete expired master encryption key {key_id}: {err}");
+        }
+    }
+
+    Ok(version)
+}
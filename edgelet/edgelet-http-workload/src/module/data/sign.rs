@@ -109,7 +109,7 @@ where
     type PutBody = serde::de::IgnoredAny;
 }
 
-async fn get_module_key(
+pub(super) async fn get_module_key(
     client: std::sync::Arc<tokio::sync::Mutex<IdentityClient>>,
     module_id: &str,
 ) -> Result<aziot_key_common::KeyHandle, http_common::server::Error> {
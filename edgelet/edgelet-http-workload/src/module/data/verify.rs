@@ -0,0 +1,189 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+#[cfg(not(test))]
+use aziot_identity_client_async::Client as IdentityClient;
+#[cfg(not(test))]
+use aziot_key_client_async::Client as KeyClient;
+
+#[cfg(test)]
+use test_common::client::IdentityClient;
+#[cfg(test)]
+use test_common::client::KeyClient;
+
+pub(crate) struct Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    key_client: std::sync::Arc<tokio::sync::Mutex<KeyClient>>,
+    identity_client: std::sync::Arc<tokio::sync::Mutex<IdentityClient>>,
+    module_id: String,
+    pid: libc::pid_t,
+    runtime: std::sync::Arc<tokio::sync::Mutex<M>>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub(crate) struct VerifyRequest {
+    data: String,
+    digest: String,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub(crate) struct VerifyResponse {
+    valid: bool,
+}
+
+#[async_trait::async_trait]
+impl<M> http_common::server::Route for Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    type ApiVersion = edgelet_http::ApiVersion;
+    fn api_version() -> &'static dyn http_common::DynRangeBounds<Self::ApiVersion> {
+        &((edgelet_http::ApiVersion::V2026_10_18)..)
+    }
+
+    type Service = crate::Service<M>;
+    fn from_uri(
+        service: &Self::Service,
+        path: &str,
+        _query: &[(std::borrow::Cow<'_, str>, std::borrow::Cow<'_, str>)],
+        extensions: &http::Extensions,
+    ) -> Option<Self> {
+        let uri_regex =
+            regex::Regex::new("^/modules/(?P<moduleId>[^/]+)/genid/(?P<genId>[^/]+)/verify$")
+                .expect("hard-coded regex must compile");
+        let captures = uri_regex.captures(path)?;
+
+        let module_id = &captures["moduleId"];
+        let module_id = percent_encoding::percent_decode_str(module_id)
+            .decode_utf8()
+            .ok()?;
+
+        let pid = extensions.get::<Option<libc::pid_t>>().copied()??;
+
+        Some(Route {
+            key_client: service.key_client.clone(),
+            identity_client: service.identity_client.clone(),
+            module_id: module_id.into_owned(),
+            pid,
+            runtime: service.runtime.clone(),
+        })
+    }
+
+    type DeleteBody = serde::de::IgnoredAny;
+
+    type PostBody = VerifyRequest;
+    async fn post(self, body: Option<Self::PostBody>) -> http_common::server::RouteResponse {
+        edgelet_http::auth_caller(&self.module_id, self.pid, &self.runtime).await?;
+
+        let (data, digest) = match body {
+            Some(body) => (
+                super::base64_decode(body.data)?,
+                super::base64_decode(body.digest)?,
+            ),
+            None => {
+                return Err(edgelet_http::error::bad_request(
+                    "missing parameter: request body",
+                ));
+            }
+        };
+
+        let module_key = super::sign::get_module_key(self.identity_client, &self.module_id).await?;
+
+        let key_client = self.key_client.lock().await;
+
+        let expected = key_client
+            .sign(
+                &module_key,
+                aziot_key_common::SignMechanism::HmacSha256,
+                &data,
+            )
+            .await
+            .map_err(edgelet_http::error::server_error)?;
+
+        // Compare in constant time so that the digest cannot be guessed byte by byte.
+        let valid = expected.len() == digest.len() && openssl::memcmp::eq(&expected, &digest);
+
+        let res = VerifyResponse { valid };
+        let res = http_common::server::response::json(hyper::StatusCode::OK, &res);
+
+        Ok(res)
+    }
+
+    type PutBody = serde::de::IgnoredAny;
+}
+
+#[cfg(test)]
+mod tests {
+    use http_common::server::Route;
+
+    use edgelet_test_utils::{test_route_err, test_route_ok};
+
+    const TEST_PATH: &str = "/modules/testModule/genid/1/verify";
+
+    #[test]
+    fn parse_uri() {
+        // Valid URI
+        let route = test_route_ok!(TEST_PATH);
+        assert_eq!("testModule", &route.module_id);
+        assert_eq!(nix::unistd::getpid().as_raw(), route.pid);
+
+        // Missing module ID
+        test_route_err!("/modules//genid/1/verify");
+
+        // Missing generation ID
+        test_route_err!("/modules/testModule/genid//verify");
+
+        // Extra character at beginning of URI
+        test_route_err!(&format!("a{TEST_PATH}"));
+
+        // Extra character at end of URI
+        test_route_err!(&format!("{TEST_PATH}a"));
+    }
+
+    #[tokio::test]
+    async fn auth() {
+        async fn post(
+            route: super::Route<edgelet_test_utils::runtime::Runtime>,
+        ) -> http_common::server::RouteResponse {
+            let engine = base64::engine::general_purpose::STANDARD;
+            let body = super::VerifyRequest {
+                data: base64::Engine::encode(&engine, "data"),
+                digest: base64::Engine::encode(&engine, "digest"),
+            };
+
+            route.post(Some(body)).await
+        }
+
+        edgelet_test_utils::test_auth_caller!(TEST_PATH, "testModule", post);
+    }
+
+    #[tokio::test]
+    async fn encoding() {
+        let engine = base64::engine::general_purpose::STANDARD;
+
+        // Body is required
+        let route = test_route_ok!(TEST_PATH);
+        let response = route.post(None).await.unwrap_err();
+        assert_eq!(hyper::StatusCode::BAD_REQUEST, response.status_code);
+
+        // data and digest must be base64-encoded
+        let body = super::VerifyRequest {
+            data: "~".to_string(),
+            digest: base64::Engine::encode(&engine, "digest"),
+        };
+
+        let route = test_route_ok!(TEST_PATH);
+        let response = route.post(Some(body)).await.unwrap_err();
+        assert_eq!(hyper::StatusCode::BAD_REQUEST, response.status_code);
+
+        let body = super::VerifyRequest {
+            data: base64::Engine::encode(&engine, "data"),
+            digest: "~".to_string(),
+        };
+
+        let route = test_route_ok!(TEST_PATH);
+        let response = route.post(Some(body)).await.unwrap_err();
+        assert_eq!(hyper::StatusCode::BAD_REQUEST, response.status_code);
+    }
+}
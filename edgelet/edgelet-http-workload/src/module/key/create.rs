@@ -0,0 +1,201 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+#[cfg(not(test))]
+use aziot_key_client_async::Client as KeyClient;
+
+#[cfg(test)]
+use test_common::client::KeyClient;
+
+pub(crate) struct Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    key_connector: http_common::Connector,
+    key_client: std::sync::Arc<tokio::sync::Mutex<KeyClient>>,
+    module_keys: std::sync::Arc<tokio::sync::Mutex<super::KeyIndex>>,
+    module_id: String,
+    gen_id: String,
+    pid: libc::pid_t,
+    runtime: std::sync::Arc<tokio::sync::Mutex<M>>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub(crate) struct CreateKeyRequest {
+    name: String,
+
+    #[serde(default)]
+    algorithm: super::KeyPairAlgorithm,
+}
+
+#[async_trait::async_trait]
+impl<M> http_common::server::Route for Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    type ApiVersion = edgelet_http::ApiVersion;
+    fn api_version() -> &'static dyn http_common::DynRangeBounds<Self::ApiVersion> {
+        &((edgelet_http::ApiVersion::V2026_10_18)..)
+    }
+
+    type Service = crate::Service<M>;
+    fn from_uri(
+        service: &Self::Service,
+        path: &str,
+        _query: &[(std::borrow::Cow<'_, str>, std::borrow::Cow<'_, str>)],
+        extensions: &http::Extensions,
+    ) -> Option<Self> {
+        let uri_regex =
+            regex::Regex::new("^/modules/(?P<moduleId>[^/]+)/genid/(?P<genId>[^/]+)/keys$")
+                .expect("hard-coded regex must compile");
+        let captures = uri_regex.captures(path)?;
+
+        let module_id = &captures["moduleId"];
+        let module_id = percent_encoding::percent_decode_str(module_id)
+            .decode_utf8()
+            .ok()?;
+
+        let gen_id = &captures["genId"];
+        let gen_id = percent_encoding::percent_decode_str(gen_id)
+            .decode_utf8()
+            .ok()?;
+
+        let pid = extensions.get::<Option<libc::pid_t>>().copied()??;
+
+        Some(Route {
+            key_connector: service.key_connector.clone(),
+            key_client: service.key_client.clone(),
+            module_keys: service.module_keys.clone(),
+            module_id: module_id.into_owned(),
+            gen_id: gen_id.into_owned(),
+            pid,
+            runtime: service.runtime.clone(),
+        })
+    }
+
+    type DeleteBody = serde::de::IgnoredAny;
+
+    type PostBody = CreateKeyRequest;
+    async fn post(self, body: Option<Self::PostBody>) -> http_common::server::RouteResponse {
+        edgelet_http::auth_caller(&self.module_id, self.pid, &self.runtime).await?;
+
+        let body = body
+            .ok_or_else(|| edgelet_http::error::bad_request("missing parameter: request body"))?;
+        let key_id = super::key_id(&self.module_id, &self.gen_id, &body.name)?;
+
+        // Record the key before creating it so that it is never left out of the index.
+        {
+            let mut module_keys = self.module_keys.lock().await;
+
+            module_keys
+                .insert(&self.module_id, &key_id)
+                .map_err(|err| {
+                    edgelet_http::error::server_error(format!(
+                        "failed to update module key index: {err}"
+                    ))
+                })?;
+        }
+
+        let key_handle = {
+            let key_client = self.key_client.lock().await;
+
+            key_client
+                .create_key_pair_if_not_exists(&key_id, Some(body.algorithm.keyd_name()))
+                .await
+                .map_err(|err| {
+                    edgelet_http::error::server_error(format!("failed to create key: {err}"))
+                })?
+        };
+
+        let public_key = super::public_key_pem(self.key_connector, &key_handle)?;
+
+        let res = super::KeyResponse {
+            name: body.name,
+            public_key,
+        };
+        let res = http_common::server::response::json(hyper::StatusCode::CREATED, &res);
+
+        Ok(res)
+    }
+
+    type PutBody = serde::de::IgnoredAny;
+}
+
+#[cfg(test)]
+mod tests {
+    use http_common::server::Route;
+
+    use edgelet_test_utils::{test_route_err, test_route_ok};
+
+    const TEST_PATH: &str = "/modules/testModule/genid/1/keys";
+
+    #[test]
+    fn parse_uri() {
+        // Valid URI
+        let route = test_route_ok!(TEST_PATH);
+        assert_eq!("testModule", &route.module_id);
+        assert_eq!("1", &route.gen_id);
+        assert_eq!(nix::unistd::getpid().as_raw(), route.pid);
+
+        // Missing module ID
+        test_route_err!("/modules//genid/1/keys");
+
+        // Missing generation ID
+        test_route_err!("/modules/testModule/genid//keys");
+
+        // Extra character at beginning of URI
+        test_route_err!(&format!("a{TEST_PATH}"));
+
+        // Extra character at end of URI
+        test_route_err!(&format!("{TEST_PATH}a"));
+    }
+
+    #[tokio::test]
+    async fn auth() {
+        async fn post(
+            route: super::Route<edgelet_test_utils::runtime::Runtime>,
+        ) -> http_common::server::RouteResponse {
+            let body = super::CreateKeyRequest {
+                name: "testKey".to_string(),
+                algorithm: super::super::KeyPairAlgorithm::EcP256,
+            };
+
+            route.post(Some(body)).await
+        }
+
+        edgelet_test_utils::test_auth_caller!(TEST_PATH, "testModule", post);
+    }
+
+    #[tokio::test]
+    async fn body() {
+        // Body is required
+        let route = test_route_ok!(TEST_PATH);
+        let response = route.post(None).await.unwrap_err();
+        assert_eq!(hyper::StatusCode::BAD_REQUEST, response.status_code);
+
+        // Key names are restricted
+        let body = super::CreateKeyRequest {
+            name: "../key".to_string(),
+            algorithm: super::super::KeyPairAlgorithm::EcP256,
+        };
+
+        let route = test_route_ok!(TEST_PATH);
+        let response = route.post(Some(body)).await.unwrap_err();
+        assert_eq!(hyper::StatusCode::BAD_REQUEST, response.status_code);
+
+        // The algorithm defaults to ECDSA P-256
+        let body: super::CreateKeyRequest =
+            serde_json::from_value(serde_json::json!({ "name": "testKey" })).unwrap();
+        assert_eq!(super::super::KeyPairAlgorithm::EcP256, body.algorithm);
+
+        let body: super::CreateKeyRequest = serde_json::from_value(
+            serde_json::json!({ "name": "testKey", "algorithm": "rsa-4096" }),
+        )
+        .unwrap();
+        assert_eq!(super::super::KeyPairAlgorithm::Rsa4096, body.algorithm);
+
+        serde_json::from_value::<super::CreateKeyRequest>(
+            serde_json::json!({ "name": "testKey", "algorithm": "dsa" }),
+        )
+        .unwrap_err();
+    }
+}
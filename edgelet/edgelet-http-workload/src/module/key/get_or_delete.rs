@@ -0,0 +1,190 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+#[cfg(not(test))]
+use aziot_key_client_async::Client as KeyClient;
+
+#[cfg(test)]
+use test_common::client::KeyClient;
+
+pub(crate) struct Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    key_connector: http_common::Connector,
+    key_client: std::sync::Arc<tokio::sync::Mutex<KeyClient>>,
+    module_keys: std::sync::Arc<tokio::sync::Mutex<super::KeyIndex>>,
+    module_id: String,
+    gen_id: String,
+    name: String,
+    pid: libc::pid_t,
+    runtime: std::sync::Arc<tokio::sync::Mutex<M>>,
+}
+
+#[async_trait::async_trait]
+impl<M> http_common::server::Route for Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    type ApiVersion = edgelet_http::ApiVersion;
+    fn api_version() -> &'static dyn http_common::DynRangeBounds<Self::ApiVersion> {
+        &((edgelet_http::ApiVersion::V2026_10_18)..)
+    }
+
+    type Service = crate::Service<M>;
+    fn from_uri(
+        service: &Self::Service,
+        path: &str,
+        _query: &[(std::borrow::Cow<'_, str>, std::borrow::Cow<'_, str>)],
+        extensions: &http::Extensions,
+    ) -> Option<Self> {
+        let uri_regex = regex::Regex::new(
+            "^/modules/(?P<moduleId>[^/]+)/genid/(?P<genId>[^/]+)/keys/(?P<name>[^/]+)$",
+        )
+        .expect("hard-coded regex must compile");
+        let captures = uri_regex.captures(path)?;
+
+        let module_id = &captures["moduleId"];
+        let module_id = percent_encoding::percent_decode_str(module_id)
+            .decode_utf8()
+            .ok()?;
+
+        let gen_id = &captures["genId"];
+        let gen_id = percent_encoding::percent_decode_str(gen_id)
+            .decode_utf8()
+            .ok()?;
+
+        let name = &captures["name"];
+        let name = percent_encoding::percent_decode_str(name)
+            .decode_utf8()
+            .ok()?;
+
+        let pid = extensions.get::<Option<libc::pid_t>>().copied()??;
+
+        Some(Route {
+            key_connector: service.key_connector.clone(),
+            key_client: service.key_client.clone(),
+            module_keys: service.module_keys.clone(),
+            module_id: module_id.into_owned(),
+            gen_id: gen_id.into_owned(),
+            name: name.into_owned(),
+            pid,
+            runtime: service.runtime.clone(),
+        })
+    }
+
+    type DeleteBody = serde::de::IgnoredAny;
+    async fn delete(self, _body: Option<Self::DeleteBody>) -> http_common::server::RouteResponse {
+        edgelet_http::auth_caller(&self.module_id, self.pid, &self.runtime).await?;
+
+        let key_id = super::key_id(&self.module_id, &self.gen_id, &self.name)?;
+        let key_handle = super::load_key_pair(&self.key_client, &key_id).await?;
+
+        {
+            let key_client = self.key_client.lock().await;
+
+            key_client
+                .delete_key_pair(&key_handle)
+                .await
+                .map_err(|err| {
+                    edgelet_http::error::server_error(format!("failed to delete key: {err}"))
+                })?;
+        }
+
+        let mut module_keys = self.module_keys.lock().await;
+
+        module_keys
+            .remove(&self.module_id, &key_id)
+            .map_err(|err| {
+                edgelet_http::error::server_error(format!(
+                    "failed to update module key index: {err}"
+                ))
+            })?;
+
+        Ok(http_common::server::response::no_content())
+    }
+
+    async fn get(self) -> http_common::server::RouteResponse {
+        edgelet_http::auth_caller(&self.module_id, self.pid, &self.runtime).await?;
+
+        let key_id = super::key_id(&self.module_id, &self.gen_id, &self.name)?;
+        let key_handle = super::load_key_pair(&self.key_client, &key_id).await?;
+
+        let public_key = super::public_key_pem(self.key_connector, &key_handle)?;
+
+        let res = super::KeyResponse {
+            name: self.name,
+            public_key,
+        };
+        let res = http_common::server::response::json(hyper::StatusCode::OK, &res);
+
+        Ok(res)
+    }
+
+    type PostBody = serde::de::IgnoredAny;
+
+    type PutBody = serde::de::IgnoredAny;
+}
+
+#[cfg(test)]
+mod tests {
+    use http_common::server::Route;
+
+    use edgelet_test_utils::{test_route_err, test_route_ok};
+
+    const TEST_PATH: &str = "/modules/testModule/genid/1/keys/testKey";
+
+    #[test]
+    fn parse_uri() {
+        // Valid URI
+        let route = test_route_ok!(TEST_PATH);
+        assert_eq!("testModule", &route.module_id);
+        assert_eq!("1", &route.gen_id);
+        assert_eq!("testKey", &route.name);
+        assert_eq!(nix::unistd::getpid().as_raw(), route.pid);
+
+        // Missing module ID
+        test_route_err!("/modules//genid/1/keys/testKey");
+
+        // Missing generation ID
+        test_route_err!("/modules/testModule/genid//keys/testKey");
+
+        // Missing key name
+        test_route_err!("/modules/testModule/genid/1/keys/");
+
+        // Extra character at beginning of URI
+        test_route_err!(&format!("a{TEST_PATH}"));
+
+        // Extra character at end of URI
+        test_route_err!(&format!("{TEST_PATH}/"));
+    }
+
+    #[tokio::test]
+    async fn auth() {
+        async fn get(
+            route: super::Route<edgelet_test_utils::runtime::Runtime>,
+        ) -> http_common::server::RouteResponse {
+            route.get().await
+        }
+
+        async fn delete(
+            route: super::Route<edgelet_test_utils::runtime::Runtime>,
+        ) -> http_common::server::RouteResponse {
+            route.delete(None).await
+        }
+
+        edgelet_test_utils::test_auth_caller!(TEST_PATH, "testModule", get);
+        edgelet_test_utils::test_auth_caller!(TEST_PATH, "testModule", delete);
+    }
+
+    #[tokio::test]
+    async fn invalid_name() {
+        // Names that could not have been created are rejected without a lookup.
+        let route = test_route_ok!("/modules/testModule/genid/1/keys/a%3Ab");
+        let response = route.get().await.unwrap_err();
+        assert_eq!(hyper::StatusCode::BAD_REQUEST, response.status_code);
+
+        let route = test_route_ok!("/modules/testModule/genid/1/keys/a%3Ab");
+        let response = route.delete(None).await.unwrap_err();
+        assert_eq!(hyper::StatusCode::BAD_REQUEST, response.status_code);
+    }
+}
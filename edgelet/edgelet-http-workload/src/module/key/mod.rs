@@ -0,0 +1,265 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! Key pairs that modules create through the workload API.
+//!
+//! The keys live in keyd under IDs scoped to the module's name and generation, so a module can
+//! only use the keys created by the same instance of its identity. Since keyd cannot list keys,
+//! edged keeps an index of the keys each module created so they can be deleted along with the
+//! module's identity.
+
+pub(crate) mod create;
+pub(crate) mod get_or_delete;
+pub(crate) mod sign;
+pub(crate) mod verify;
+
+use std::collections::{BTreeMap, BTreeSet};
+
+#[cfg(not(test))]
+use aziot_key_client_async::Client as KeyClient;
+
+#[cfg(test)]
+use test_common::client::KeyClient;
+
+/// The algorithm of a module key pair.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum KeyPairAlgorithm {
+    /// ECDSA on the NIST P-256 curve.
+    #[default]
+    EcP256,
+    /// ECDSA on the NIST P-384 curve.
+    EcP384,
+    Rsa2048,
+    Rsa4096,
+}
+
+impl KeyPairAlgorithm {
+    /// The algorithm as keyd names it when creating a key pair.
+    fn keyd_name(self) -> &'static str {
+        match self {
+            KeyPairAlgorithm::EcP256 => "ec-p256",
+            KeyPairAlgorithm::EcP384 => "ec-p384",
+            KeyPairAlgorithm::Rsa2048 => "rsa-2048",
+            KeyPairAlgorithm::Rsa4096 => "rsa-4096",
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+pub(crate) struct KeyResponse {
+    name: String,
+
+    #[serde(rename = "publicKey")]
+    public_key: String,
+}
+
+/// The keyd ID of the key `name` of a module. Names and generation IDs may contain letters,
+/// digits, '-', '_' and '.', and are at most 64 characters long, so that neither can change the
+/// scope of the ID.
+fn key_id(module_id: &str, gen_id: &str, name: &str) -> Result<String, http_common::server::Error> {
+    if !valid_component(gen_id) {
+        return Err(edgelet_http::error::bad_request("invalid generation ID"));
+    }
+
+    if !valid_component(name) {
+        return Err(edgelet_http::error::bad_request("invalid key name"));
+    }
+
+    Ok(format!(
+        "aziot-edged/module/{module_id}:{gen_id}:key:{name}"
+    ))
+}
+
+fn valid_component(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= 64
+        && value
+            .bytes()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, b'-' | b'_' | b'.'))
+}
+
+async fn load_key_pair(
+    key_client: &tokio::sync::Mutex<KeyClient>,
+    key_id: &str,
+) -> Result<aziot_key_common::KeyHandle, http_common::server::Error> {
+    let key_client = key_client.lock().await;
+
+    key_client
+        .load_key_pair(key_id)
+        .await
+        .map_err(|_| http_common::server::Error {
+            status_code: http::StatusCode::NOT_FOUND,
+            message: "key not found".into(),
+        })
+}
+
+fn public_key_pem(
+    key_connector: http_common::Connector,
+    key_handle: &aziot_key_common::KeyHandle,
+) -> Result<String, http_common::server::Error> {
+    let (_, public_key) = crate::edge_ca::keys(key_connector, key_handle)
+        .map_err(edgelet_http::error::server_error)?;
+
+    let public_key = public_key
+        .public_key_to_pem()
+        .map_err(|_| edgelet_http::error::server_error("failed to encode public key"))?;
+
+    Ok(String::from_utf8_lossy(&public_key).into_owned())
+}
+
+/// The keys each module has created, persisted so that keys survive restarts of edged.
+pub(crate) struct KeyIndex {
+    path: std::path::PathBuf,
+    modules: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl KeyIndex {
+    /// Reads the index at `path`. A missing or unreadable index is treated as empty.
+    pub(crate) fn load(path: std::path::PathBuf) -> Self {
+        let modules = match std::fs::read(&path) {
+            Ok(index) => serde_json::from_slice(&index).unwrap_or_else(|err| {
+                log::warn!(
+                    "Ignoring invalid module key index {}: {err}",
+                    path.display()
+                );
+
+                BTreeMap::new()
+            }),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(err) => {
+                log::warn!("Failed to read module key index {}: {err}", path.display());
+
+                BTreeMap::new()
+            }
+        };
+
+        KeyIndex { path, modules }
+    }
+
+    fn keys(&self, module_id: &str) -> Vec<String> {
+        self.modules
+            .get(module_id)
+            .map(|keys| keys.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    fn insert(&mut self, module_id: &str, key_id: &str) -> std::io::Result<()> {
+        let keys = self.modules.entry(module_id.to_string()).or_default();
+
+        if keys.insert(key_id.to_string()) {
+            self.save()?;
+        }
+
+        Ok(())
+    }
+
+    fn remove(&mut self, module_id: &str, key_id: &str) -> std::io::Result<()> {
+        let Some(keys) = self.modules.get_mut(module_id) else {
+            return Ok(());
+        };
+
+        if keys.remove(key_id) {
+            if keys.is_empty() {
+                self.modules.remove(module_id);
+            }
+
+            self.save()?;
+        }
+
+        Ok(())
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let index = serde_json::to_vec(&self.modules)?;
+
+        // Replace the index atomically so that a crash never leaves it half-written.
+        let temp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&temp_path, index)?;
+        std::fs::rename(&temp_path, &self.path)
+    }
+}
+
+/// Deletes all keys of the module `module_id` from keyd. Keys that fail to be deleted stay in
+/// the index, and are retried when the module's identity is next deleted.
+pub(crate) async fn delete_module_keys(
+    key_client: &tokio::sync::Mutex<KeyClient>,
+    index: &tokio::sync::Mutex<KeyIndex>,
+    module_id: &str,
+) {
+    let mut index = index.lock().await;
+
+    let keys = index.keys(module_id);
+    if keys.is_empty() {
+        return;
+    }
+
+    for key_id in keys {
+        // A key that cannot be loaded no longer exists.
+        if let Ok(key_handle) = load_key_pair(key_client, &key_id).await {
+            let key_client = key_client.lock().await;
+
+            if let Err(err) = key_client.delete_key_pair(&key_handle).await {
+                log::warn!("Failed to delete key {key_id} of module {module_id}: {err}");
+
+                continue;
+            }
+        }
+
+        if let Err(err) = index.remove(module_id, &key_id) {
+            log::warn!("Failed to update module key index: {err}");
+        }
+    }
+
+    log::info!("Deleted the keys of module {module_id}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KeyIndex, key_id};
+
+    #[test]
+    fn key_names() {
+        assert_eq!(
+            "aziot-edged/module/testModule:1:key:signing-key_1.0",
+            key_id("testModule", "1", "signing-key_1.0").unwrap()
+        );
+
+        for name in ["", "a/b", "a:b", "a b", &"a".repeat(65)] {
+            let response = key_id("testModule", "1", name).unwrap_err();
+            assert_eq!(http::StatusCode::BAD_REQUEST, response.status_code);
+
+            // The generation ID is held to the same rules.
+            let response = key_id("testModule", name, "signing-key").unwrap_err();
+            assert_eq!(http::StatusCode::BAD_REQUEST, response.status_code);
+        }
+    }
+
+    #[test]
+    fn index() {
+        let path = std::env::temp_dir().join(format!("module_keys_{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut index = KeyIndex::load(path.clone());
+        assert!(index.keys("testModule").is_empty());
+
+        index.insert("testModule", "key1").unwrap();
+        index.insert("testModule", "key2").unwrap();
+        index.insert("otherModule", "key3").unwrap();
+        index.remove("testModule", "key1").unwrap();
+
+        // The index is persisted.
+        let mut index = KeyIndex::load(path.clone());
+        assert_eq!(vec!["key2".to_string()], index.keys("testModule"));
+        assert_eq!(vec!["key3".to_string()], index.keys("otherModule"));
+
+        index.remove("testModule", "key2").unwrap();
+        assert!(!index.modules.contains_key("testModule"));
+
+        // An invalid index is treated as empty.
+        std::fs::write(&path, "invalid").unwrap();
+        let index = KeyIndex::load(path.clone());
+        assert!(index.keys("otherModule").is_empty());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,217 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+#[cfg(not(test))]
+use aziot_key_client_async::Client as KeyClient;
+
+#[cfg(test)]
+use test_common::client::KeyClient;
+
+pub(crate) struct Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    key_connector: http_common::Connector,
+    key_client: std::sync::Arc<tokio::sync::Mutex<KeyClient>>,
+    module_id: String,
+    gen_id: String,
+    name: String,
+    pid: libc::pid_t,
+    runtime: std::sync::Arc<tokio::sync::Mutex<M>>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub(crate) struct SignRequest {
+    data: String,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub(crate) struct SignResponse {
+    signature: String,
+}
+
+#[async_trait::async_trait]
+impl<M> http_common::server::Route for Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    type ApiVersion = edgelet_http::ApiVersion;
+    fn api_version() -> &'static dyn http_common::DynRangeBounds<Self::ApiVersion> {
+        &((edgelet_http::ApiVersion::V2026_10_18)..)
+    }
+
+    type Service = crate::Service<M>;
+    fn from_uri(
+        service: &Self::Service,
+        path: &str,
+        _query: &[(std::borrow::Cow<'_, str>, std::borrow::Cow<'_, str>)],
+        extensions: &http::Extensions,
+    ) -> Option<Self> {
+        let uri_regex = regex::Regex::new(
+            "^/modules/(?P<moduleId>[^/]+)/genid/(?P<genId>[^/]+)/keys/(?P<name>[^/]+)/sign$",
+        )
+        .expect("hard-coded regex must compile");
+        let captures = uri_regex.captures(path)?;
+
+        let module_id = &captures["moduleId"];
+        let module_id = percent_encoding::percent_decode_str(module_id)
+            .decode_utf8()
+            .ok()?;
+
+        let gen_id = &captures["genId"];
+        let gen_id = percent_encoding::percent_decode_str(gen_id)
+            .decode_utf8()
+            .ok()?;
+
+        let name = &captures["name"];
+        let name = percent_encoding::percent_decode_str(name)
+            .decode_utf8()
+            .ok()?;
+
+        let pid = extensions.get::<Option<libc::pid_t>>().copied()??;
+
+        Some(Route {
+            key_connector: service.key_connector.clone(),
+            key_client: service.key_client.clone(),
+            module_id: module_id.into_owned(),
+            gen_id: gen_id.into_owned(),
+            name: name.into_owned(),
+            pid,
+            runtime: service.runtime.clone(),
+        })
+    }
+
+    type DeleteBody = serde::de::IgnoredAny;
+
+    type PostBody = SignRequest;
+    async fn post(self, body: Option<Self::PostBody>) -> http_common::server::RouteResponse {
+        edgelet_http::auth_caller(&self.module_id, self.pid, &self.runtime).await?;
+
+        let data = match body {
+            Some(body) => crate::module::data::base64_decode(body.data)?,
+            None => {
+                return Err(edgelet_http::error::bad_request(
+                    "missing parameter: request body",
+                ));
+            }
+        };
+
+        let key_id = super::key_id(&self.module_id, &self.gen_id, &self.name)?;
+        let key_handle = super::load_key_pair(&self.key_client, &key_id).await?;
+
+        let (private_key, _) = crate::edge_ca::keys(self.key_connector, &key_handle)
+            .map_err(edgelet_http::error::server_error)?;
+
+        let signature = sign(&private_key, &data)
+            .map_err(|_| edgelet_http::error::server_error("failed to sign data"))?;
+        let engine = base64::engine::general_purpose::STANDARD;
+        let signature = base64::Engine::encode(&engine, signature);
+
+        let res = SignResponse { signature };
+        let res = http_common::server::response::json(hyper::StatusCode::OK, &res);
+
+        Ok(res)
+    }
+
+    type PutBody = serde::de::IgnoredAny;
+}
+
+fn sign(
+    private_key: &openssl::pkey::PKeyRef<openssl::pkey::Private>,
+    data: &[u8],
+) -> Result<Vec<u8>, openssl::error::ErrorStack> {
+    let digest = crate::module::cert::signature_digest(private_key);
+
+    let mut signer = openssl::sign::Signer::new(digest, private_key)?;
+    signer.update(data)?;
+
+    signer.sign_to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use http_common::server::Route;
+
+    use edgelet_test_utils::{test_route_err, test_route_ok};
+
+    const TEST_PATH: &str = "/modules/testModule/genid/1/keys/testKey/sign";
+
+    #[test]
+    fn parse_uri() {
+        // Valid URI
+        let route = test_route_ok!(TEST_PATH);
+        assert_eq!("testModule", &route.module_id);
+        assert_eq!("1", &route.gen_id);
+        assert_eq!("testKey", &route.name);
+        assert_eq!(nix::unistd::getpid().as_raw(), route.pid);
+
+        // Missing key name
+        test_route_err!("/modules/testModule/genid/1/keys//sign");
+
+        // Extra character at beginning of URI
+        test_route_err!(&format!("a{TEST_PATH}"));
+
+        // Extra character at end of URI
+        test_route_err!(&format!("{TEST_PATH}a"));
+    }
+
+    #[tokio::test]
+    async fn auth() {
+        async fn post(
+            route: super::Route<edgelet_test_utils::runtime::Runtime>,
+        ) -> http_common::server::RouteResponse {
+            let engine = base64::engine::general_purpose::STANDARD;
+            let body = super::SignRequest {
+                data: base64::Engine::encode(&engine, "data"),
+            };
+
+            route.post(Some(body)).await
+        }
+
+        edgelet_test_utils::test_auth_caller!(TEST_PATH, "testModule", post);
+    }
+
+    #[tokio::test]
+    async fn encoding() {
+        // Body is required
+        let route = test_route_ok!(TEST_PATH);
+        let response = route.post(None).await.unwrap_err();
+        assert_eq!(hyper::StatusCode::BAD_REQUEST, response.status_code);
+
+        // data must be base64-encoded
+        let body = super::SignRequest {
+            data: "~".to_string(),
+        };
+
+        let route = test_route_ok!(TEST_PATH);
+        let response = route.post(Some(body)).await.unwrap_err();
+        assert_eq!(hyper::StatusCode::BAD_REQUEST, response.status_code);
+    }
+
+    #[test]
+    fn sign_verify() {
+        for algorithm in [
+            edgelet_settings::base::cert::KeyAlgorithm::EcP256,
+            edgelet_settings::base::cert::KeyAlgorithm::EcP384,
+            edgelet_settings::base::cert::KeyAlgorithm::Rsa2048,
+        ] {
+            let (private_key, public_key) = crate::module::cert::new_keys(algorithm).unwrap();
+
+            let signature = super::sign(&private_key, b"data").unwrap();
+            assert!(super::super::verify::verify(
+                &public_key,
+                b"data",
+                &signature
+            ));
+            assert!(!super::super::verify::verify(
+                &public_key,
+                b"other",
+                &signature
+            ));
+            assert!(!super::super::verify::verify(
+                &public_key,
+                b"data",
+                b"invalid"
+            ));
+        }
+    }
+}
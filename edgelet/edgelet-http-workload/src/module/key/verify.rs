@@ -0,0 +1,205 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+#[cfg(not(test))]
+use aziot_key_client_async::Client as KeyClient;
+
+#[cfg(test)]
+use test_common::client::KeyClient;
+
+pub(crate) struct Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    key_connector: http_common::Connector,
+    key_client: std::sync::Arc<tokio::sync::Mutex<KeyClient>>,
+    module_id: String,
+    gen_id: String,
+    name: String,
+    pid: libc::pid_t,
+    runtime: std::sync::Arc<tokio::sync::Mutex<M>>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub(crate) struct VerifyRequest {
+    data: String,
+    signature: String,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub(crate) struct VerifyResponse {
+    valid: bool,
+}
+
+#[async_trait::async_trait]
+impl<M> http_common::server::Route for Route<M>
+where
+    M: edgelet_core::ModuleRuntime + Send + Sync,
+{
+    type ApiVersion = edgelet_http::ApiVersion;
+    fn api_version() -> &'static dyn http_common::DynRangeBounds<Self::ApiVersion> {
+        &((edgelet_http::ApiVersion::V2026_10_18)..)
+    }
+
+    type Service = crate::Service<M>;
+    fn from_uri(
+        service: &Self::Service,
+        path: &str,
+        _query: &[(std::borrow::Cow<'_, str>, std::borrow::Cow<'_, str>)],
+        extensions: &http::Extensions,
+    ) -> Option<Self> {
+        let uri_regex = regex::Regex::new(
+            "^/modules/(?P<moduleId>[^/]+)/genid/(?P<genId>[^/]+)/keys/(?P<name>[^/]+)/verify$",
+        )
+        .expect("hard-coded regex must compile");
+        let captures = uri_regex.captures(path)?;
+
+        let module_id = &captures["moduleId"];
+        let module_id = percent_encoding::percent_decode_str(module_id)
+            .decode_utf8()
+            .ok()?;
+
+        let gen_id = &captures["genId"];
+        let gen_id = percent_encoding::percent_decode_str(gen_id)
+            .decode_utf8()
+            .ok()?;
+
+        let name = &captures["name"];
+        let name = percent_encoding::percent_decode_str(name)
+            .decode_utf8()
+            .ok()?;
+
+        let pid = extensions.get::<Option<libc::pid_t>>().copied()??;
+
+        Some(Route {
+            key_connector: service.key_connector.clone(),
+            key_client: service.key_client.clone(),
+            module_id: module_id.into_owned(),
+            gen_id: gen_id.into_owned(),
+            name: name.into_owned(),
+            pid,
+            runtime: service.runtime.clone(),
+        })
+    }
+
+    type DeleteBody = serde::de::IgnoredAny;
+
+    type PostBody = VerifyRequest;
+    async fn post(self, body: Option<Self::PostBody>) -> http_common::server::RouteResponse {
+        edgelet_http::auth_caller(&self.module_id, self.pid, &self.runtime).await?;
+
+        let (data, signature) = match body {
+            Some(body) => (
+                crate::module::data::base64_decode(body.data)?,
+                crate::module::data::base64_decode(body.signature)?,
+            ),
+            None => {
+                return Err(edgelet_http::error::bad_request(
+                    "missing parameter: request body",
+                ));
+            }
+        };
+
+        let key_id = super::key_id(&self.module_id, &self.gen_id, &self.name)?;
+        let key_handle = super::load_key_pair(&self.key_client, &key_id).await?;
+
+        let (_, public_key) = crate::edge_ca::keys(self.key_connector, &key_handle)
+            .map_err(edgelet_http::error::server_error)?;
+
+        let res = VerifyResponse {
+            valid: verify(&public_key, &data, &signature),
+        };
+        let res = http_common::server::response::json(hyper::StatusCode::OK, &res);
+
+        Ok(res)
+    }
+
+    type PutBody = serde::de::IgnoredAny;
+}
+
+/// Whether `signature` is a signature of `data` by the private key of `public_key`. Signatures
+/// that openssl fails to parse are invalid.
+pub(super) fn verify(
+    public_key: &openssl::pkey::PKeyRef<openssl::pkey::Public>,
+    data: &[u8],
+    signature: &[u8],
+) -> bool {
+    let digest = crate::module::cert::signature_digest(public_key);
+
+    openssl::sign::Verifier::new(digest, public_key)
+        .and_then(|mut verifier| verifier.verify_oneshot(signature, data))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use http_common::server::Route;
+
+    use edgelet_test_utils::{test_route_err, test_route_ok};
+
+    const TEST_PATH: &str = "/modules/testModule/genid/1/keys/testKey/verify";
+
+    #[test]
+    fn parse_uri() {
+        // Valid URI
+        let route = test_route_ok!(TEST_PATH);
+        assert_eq!("testModule", &route.module_id);
+        assert_eq!("1", &route.gen_id);
+        assert_eq!("testKey", &route.name);
+        assert_eq!(nix::unistd::getpid().as_raw(), route.pid);
+
+        // Missing key name
+        test_route_err!("/modules/testModule/genid/1/keys//verify");
+
+        // Extra character at beginning of URI
+        test_route_err!(&format!("a{TEST_PATH}"));
+
+        // Extra character at end of URI
+        test_route_err!(&format!("{TEST_PATH}a"));
+    }
+
+    #[tokio::test]
+    async fn auth() {
+        async fn post(
+            route: super::Route<edgelet_test_utils::runtime::Runtime>,
+        ) -> http_common::server::RouteResponse {
+            let engine = base64::engine::general_purpose::STANDARD;
+            let body = super::VerifyRequest {
+                data: base64::Engine::encode(&engine, "data"),
+                signature: base64::Engine::encode(&engine, "signature"),
+            };
+
+            route.post(Some(body)).await
+        }
+
+        edgelet_test_utils::test_auth_caller!(TEST_PATH, "testModule", post);
+    }
+
+    #[tokio::test]
+    async fn encoding() {
+        let engine = base64::engine::general_purpose::STANDARD;
+
+        // Body is required
+        let route = test_route_ok!(TEST_PATH);
+        let response = route.post(None).await.unwrap_err();
+        assert_eq!(hyper::StatusCode::BAD_REQUEST, response.status_code);
+
+        // data and signature must be base64-encoded
+        let body = super::VerifyRequest {
+            data: "~".to_string(),
+            signature: base64::Engine::encode(&engine, "signature"),
+        };
+
+        let route = test_route_ok!(TEST_PATH);
+        let response = route.post(Some(body)).await.unwrap_err();
+        assert_eq!(hyper::StatusCode::BAD_REQUEST, response.status_code);
+
+        let body = super::VerifyRequest {
+            data: base64::Engine::encode(&engine, "data"),
+            signature: "~".to_string(),
+        };
+
+        let route = test_route_ok!(TEST_PATH);
+        let response = route.post(Some(body)).await.unwrap_err();
+        assert_eq!(hyper::StatusCode::BAD_REQUEST, response.status_code);
+    }
+}
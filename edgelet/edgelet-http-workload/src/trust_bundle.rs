@@ -13,6 +13,7 @@ where
     client: std::sync::Arc<tokio::sync::Mutex<CertClient>>,
     trust_bundle: String,
     optional: bool,
+    previous_edge_ca: Option<crate::edge_ca::PreviousEdgeCa>,
     _runtime: std::marker::PhantomData<M>,
 }
 
@@ -43,9 +44,16 @@ where
         _extensions: &http::Extensions,
     ) -> Option<Self> {
         // The default trust bundle is required, but the manifest trust bundle is optional.
-        let (trust_bundle, optional) = match path {
-            TRUST_BUNDLE_PATH => (service.config.trust_bundle.clone(), false),
-            MANIFEST_TRUST_BUNDLE_PATH => (service.config.manifest_trust_bundle.clone(), true),
+        // Only the default trust bundle carries the previous Edge CA after a renewal.
+        let (trust_bundle, optional, previous_edge_ca) = match path {
+            TRUST_BUNDLE_PATH => (
+                service.config.trust_bundle.clone(),
+                false,
+                Some(service.previous_edge_ca.clone()),
+            ),
+            MANIFEST_TRUST_BUNDLE_PATH => {
+                (service.config.manifest_trust_bundle.clone(), true, None)
+            }
             _ => return None,
         };
 
@@ -53,6 +61,7 @@ where
             client: service.cert_client.clone(),
             trust_bundle,
             optional,
+            previous_edge_ca,
             _runtime: std::marker::PhantomData,
         })
     }
@@ -71,7 +80,7 @@ where
                     message: format!("certificate {:?} not found", self.trust_bundle).into(),
                 });
 
-        let certificate = match (certificate, self.optional) {
+        let mut certificate = match (certificate, self.optional) {
             (Ok(certificate), _) => std::str::from_utf8(&certificate)
                 .map_err(|err| {
                     edgelet_http::error::server_error(format!("could not parse certificate: {err}"))
@@ -85,6 +94,24 @@ where
             }
         };
 
+        // Certificates issued by the previous Edge CA stay trusted until the overlap period after
+        // its renewal is over.
+        let previous_edge_ca = self
+            .previous_edge_ca
+            .and_then(|previous| previous.get(chrono::Utc::now()));
+
+        if let Some(previous_edge_ca) = previous_edge_ca {
+            let previous_edge_ca = String::from_utf8_lossy(&previous_edge_ca);
+
+            if !certificate.contains(&*previous_edge_ca) {
+                if !certificate.is_empty() && !certificate.ends_with('\n') {
+                    certificate.push('\n');
+                }
+
+                certificate.push_str(&previous_edge_ca);
+            }
+        }
+
         let res = TrustBundleResponse { certificate };
         let res = http_common::server::response::json(hyper::StatusCode::OK, &res);
 
@@ -110,10 +137,12 @@ mod tests {
         let route = test_route_ok!(super::TRUST_BUNDLE_PATH);
         assert_eq!("test-trust-bundle", route.trust_bundle);
         assert!(!route.optional);
+        assert!(route.previous_edge_ca.is_some());
 
         let route = test_route_ok!(super::MANIFEST_TRUST_BUNDLE_PATH);
         assert_eq!("test-manifest-trust-bundle", route.trust_bundle);
         assert!(route.optional);
+        assert!(route.previous_edge_ca.is_none());
 
         // Extra character at beginning of URI
         test_route_err!(&format!("a{}", super::TRUST_BUNDLE_PATH));
@@ -172,4 +201,43 @@ mod tests {
         let trust_bundle: super::TrustBundleResponse = serde_json::from_slice(&body).unwrap();
         assert_eq!(String::new(), trust_bundle.certificate);
     }
+
+    #[tokio::test]
+    async fn previous_edge_ca() {
+        let mut certs = std::collections::BTreeMap::new();
+        certs.insert(
+            "test-trust-bundle".to_string(),
+            "TRUST_BUNDLE".as_bytes().to_owned(),
+        );
+
+        let (previous, _) = test_common::credential::test_certificate("test-ca");
+        let previous_edge_ca = crate::edge_ca::PreviousEdgeCa::default();
+        previous_edge_ca
+            .set(
+                &previous,
+                std::time::Duration::from_hours(24),
+                chrono::Utc::now(),
+            )
+            .unwrap();
+
+        let previous = String::from_utf8(previous.to_pem().unwrap()).unwrap();
+
+        // The previous Edge CA is added to the trust bundle during the overlap period.
+        let mut route = test_route_ok!(super::TRUST_BUNDLE_PATH);
+        route.previous_edge_ca = Some(previous_edge_ca);
+
+        {
+            let mut client = route.client.lock().await;
+            client.certs = tokio::sync::Mutex::new(std::cell::RefCell::new(certs));
+        }
+
+        let response = route.get().await.unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let trust_bundle: super::TrustBundleResponse = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(
+            format!("TRUST_BUNDLE\n{previous}"),
+            trust_bundle.certificate
+        );
+    }
 }
@@ -2,7 +2,7 @@
 
 use std::borrow::Cow;
 
-use http_common::server::Error;
+use http_common::server::{Error, RouteResponse};
 
 pub const FORBIDDEN: Error = Error {
     status_code: http::StatusCode::FORBIDDEN,
@@ -16,15 +16,59 @@ pub const fn bad_request(message: &'static str) -> Error {
     }
 }
 
-/// Produce an HTTP error response provided a runtime-dependent error.
+/// Produce an HTTP error response provided a runtime-dependent error. Client errors carry
+/// their causes, which tell the caller what to change, like the container policy rules that a
+/// module breaks.
 #[allow(clippy::module_name_repetitions)]
 pub fn runtime_error<M>(_runtime: &M, error: &anyhow::Error) -> http_common::server::Error
 where
     M: edgelet_core::ModuleRuntime,
 {
+    let status_code = <M as edgelet_core::ModuleRuntime>::error_code(error);
+    let message = if status_code.is_client_error() {
+        format!("{error:#}")
+    } else {
+        error.to_string()
+    };
+
     http_common::server::Error {
-        status_code: <M as edgelet_core::ModuleRuntime>::error_code(error),
-        message: Cow::Owned(error.to_string()),
+        status_code,
+        message: Cow::Owned(message),
+    }
+}
+
+/// The body of the response to a request to create a module that breaks the container policy.
+/// It lists each violation next to the message.
+#[derive(Debug, serde::Serialize)]
+#[allow(clippy::module_name_repetitions)]
+pub struct PolicyErrorBody<'a> {
+    pub message: String,
+    pub violations: &'a [edgelet_core::PolicyViolation],
+}
+
+/// Produce the HTTP response for a runtime-dependent error. An `Error` carries only a message,
+/// so a client error caused by the container policy is answered here with its violations. Any
+/// other error is returned as `runtime_error` would produce it.
+#[allow(clippy::module_name_repetitions)]
+pub fn runtime_error_response<M>(runtime: &M, error: &anyhow::Error) -> RouteResponse
+where
+    M: edgelet_core::ModuleRuntime,
+{
+    let status_code = <M as edgelet_core::ModuleRuntime>::error_code(error);
+    let violations = error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<edgelet_core::PolicyViolations>());
+
+    match violations {
+        Some(violations) if status_code.is_client_error() => {
+            let body = PolicyErrorBody {
+                message: format!("{error:#}"),
+                violations: violations.violations(),
+            };
+
+            Ok(http_common::server::response::json(status_code, &body))
+        }
+        _ => Err(runtime_error(runtime, error)),
     }
 }
 
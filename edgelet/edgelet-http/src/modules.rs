@@ -8,6 +8,9 @@ pub struct ModuleSpec {
 
     #[serde(rename = "imagePullPolicy", skip_serializing_if = "Option::is_none")]
     image_pull_policy: Option<String>,
+
+    #[serde(rename = "restartPolicy", skip_serializing_if = "Option::is_none")]
+    restart_policy: Option<String>,
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -50,6 +53,15 @@ pub struct ModuleStatus {
     pub exit_status: Option<ExitStatus>,
 
     pub runtime_status: RuntimeStatus,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restart_count: Option<u32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_retry_time: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub health: Option<ModuleHealth>,
 }
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -60,6 +72,16 @@ pub struct ExitStatus {
     pub status_code: String,
 }
 
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(test, derive(Eq, PartialEq))]
+#[serde(rename_all = "camelCase")]
+pub struct ModuleHealth {
+    pub status: String,
+    pub failing_streak: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_output: Option<String>,
+}
+
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 #[cfg_attr(test, derive(Eq, PartialEq))]
 pub struct RuntimeStatus {
@@ -99,7 +121,14 @@ impl ModuleSpec {
             None => edgelet_settings::module::ImagePullPolicy::default(),
         };
 
+        let restart_policy = match self.restart_policy {
+            Some(policy) => std::str::FromStr::from_str(&policy)
+                .map_err(|_| "invalid restartPolicy".to_string())?,
+            None => edgelet_settings::module::RestartPolicy::default(),
+        };
+
         edgelet_settings::ModuleSpec::new(self.name, self.r#type, config, env, image_pull_policy)
+            .map(|spec| spec.with_restart_policy(restart_policy))
     }
 }
 
@@ -134,6 +163,9 @@ impl ModuleDetails {
                     status: status.to_string(),
                     description: None,
                 },
+                restart_count: None,
+                next_retry_time: None,
+                health: None,
             },
         }
     }
@@ -176,6 +208,18 @@ impl std::convert::From<edgelet_core::ModuleRuntimeState> for ModuleStatus {
             None
         };
 
+        let restart_count = Some(state.restart_count()).filter(|count| *count > 0);
+
+        let next_retry_time = state
+            .next_retry_at()
+            .map(|time| time.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true));
+
+        let health = state.health().map(|health| ModuleHealth {
+            status: health.status().to_string(),
+            failing_streak: health.failing_streak(),
+            last_output: health.last_output().map(ToOwned::to_owned),
+        });
+
         ModuleStatus {
             start_time,
             exit_status,
@@ -183,6 +227,9 @@ impl std::convert::From<edgelet_core::ModuleRuntimeState> for ModuleStatus {
                 status: state.status().to_string(),
                 description: None, // The description was only ever `state.status().to_owned()` anyway.
             },
+            restart_count,
+            next_retry_time,
+            health,
         }
     }
 }
@@ -208,6 +255,7 @@ mod tests {
                 }]),
             },
             image_pull_policy: None,
+            restart_policy: Some("on-failure".to_string()),
         };
 
         let runtime_spec: edgelet_settings::ModuleSpec<edgelet_settings::DockerConfig> =
@@ -227,6 +275,10 @@ mod tests {
             edgelet_settings::module::ImagePullPolicy::default(),
             runtime_spec.image_pull_policy()
         );
+        assert_eq!(
+            edgelet_settings::module::RestartPolicy::OnFailure,
+            runtime_spec.restart_policy()
+        );
         assert_eq!(expected_env, runtime_spec.env().clone());
 
         let runtime_config = runtime_spec.config();
@@ -251,7 +303,10 @@ mod tests {
                 runtime_status: super::RuntimeStatus {
                     status: "running".to_string(),
                     description: None,
-                }
+                },
+                restart_count: None,
+                next_retry_time: None,
+                health: None,
             },
             status.into()
         );
@@ -273,7 +328,68 @@ mod tests {
                 runtime_status: super::RuntimeStatus {
                     status: "stopped".to_string(),
                     description: None,
-                }
+                },
+                restart_count: None,
+                next_retry_time: None,
+                health: None,
+            },
+            status.into()
+        );
+
+        // Failed module waiting to be restarted
+        let status = ModuleRuntimeState::default()
+            .with_status(edgelet_core::ModuleStatus::Failed)
+            .with_started_at(Some(timestamp))
+            .with_finished_at(Some(timestamp))
+            .with_exit_code(Some(1))
+            .with_restart_count(3)
+            .with_next_retry_at(Some(timestamp));
+
+        assert_eq!(
+            super::ModuleStatus {
+                start_time: Some(timestamp.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true)),
+                exit_status: Some(super::ExitStatus {
+                    exit_time: timestamp.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true),
+                    status_code: "1".to_string(),
+                }),
+                runtime_status: super::RuntimeStatus {
+                    status: "failed".to_string(),
+                    description: None,
+                },
+                restart_count: Some(3),
+                next_retry_time: Some(
+                    timestamp.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true)
+                ),
+                health: None,
+            },
+            status.into()
+        );
+
+        // Unhealthy module
+        let status = ModuleRuntimeState::default()
+            .with_status(edgelet_core::ModuleStatus::Unhealthy)
+            .with_started_at(Some(timestamp))
+            .with_health(Some(edgelet_core::ModuleHealth::new(
+                edgelet_core::HealthStatus::Unhealthy,
+                4,
+                Some("timed out".to_string()),
+            )));
+
+        assert_eq!(
+            super::ModuleStatus {
+                start_time: Some(timestamp.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true)),
+                exit_status: None,
+                runtime_status: super::RuntimeStatus {
+                    status: "unhealthy".to_string(),
+                    description: None,
+                },
+                restart_count: None,
+                next_retry_time: None,
+                health: Some(super::ModuleHealth {
+                    status: "unhealthy".to_string(),
+                    failing_streak: 4,
+                    last_output: Some("timed out".to_string()),
+                }),
             },
             status.into()
         );
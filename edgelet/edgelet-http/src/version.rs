@@ -10,6 +10,7 @@ pub enum ApiVersion {
     V2020_07_07,
     V2021_12_07,
     V2022_08_03,
+    V2026_10_18,
 }
 
 impl std::fmt::Display for ApiVersion {
@@ -22,6 +23,7 @@ impl std::fmt::Display for ApiVersion {
             ApiVersion::V2020_07_07 => "2020-07-07",
             ApiVersion::V2021_12_07 => "2021-12-07",
             ApiVersion::V2022_08_03 => "2022-08-03",
+            ApiVersion::V2026_10_18 => "2026-10-18",
         })
     }
 }
@@ -38,6 +40,7 @@ impl std::str::FromStr for ApiVersion {
             "2020-07-07" => Ok(ApiVersion::V2020_07_07),
             "2021-12-07" => Ok(ApiVersion::V2021_12_07),
             "2022-08-03" => Ok(ApiVersion::V2022_08_03),
+            "2026-10-18" => Ok(ApiVersion::V2026_10_18),
             _ => Err(()),
         }
     }
@@ -79,6 +82,10 @@ mod tests {
             ApiVersion::V2022_08_03,
             ApiVersion::from_str("2022-08-03").unwrap()
         );
+        assert_eq!(
+            ApiVersion::V2026_10_18,
+            ApiVersion::from_str("2026-10-18").unwrap()
+        );
 
         assert!(ApiVersion::from_str("1900-01-01").is_err());
     }
@@ -1,11 +1,15 @@
 // Copyright (c) Microsoft. All rights reserved.
 
+use std::path::Path;
 use std::{collections::HashSet, time::Duration};
 
-use chrono::Timelike;
-use edgelet_core::{ModuleRegistry, ModuleRuntime};
+use chrono::{DateTime, Timelike, Utc};
+use edgelet_core::{
+    ImagePruneReport, ImagePruneRequest, ModuleRegistry, ModuleRuntime, PrunedImage,
+};
 use edgelet_docker::ImagePruneData;
-use edgelet_settings::base::image::ImagePruneSettings;
+use edgelet_settings::DockerConfig;
+use edgelet_settings::base::image::{DiskPressureSettings, ImagePruneSettings};
 
 use crate::error::ImageCleanupError;
 
@@ -20,49 +24,81 @@ const TOTAL_MINS_IN_DAY: u64 = 1440;
 ///   After waking up, it'll try to get the bootstrap image ID [if it doesn't
 ///   already have it from a previous run], and then calls remove_unused_images()
 ///   Finally, it puts itself back to sleep till it's time for the next run.
+/// - If disk pressure settings are present, it also checks the disk holding the runtime's
+///   data in between runs [see disk_pressure_collect()].
+/// - Whether or not GC is enabled, it runs on demand for each of `requests`
+///   [see on_demand_collect()].
 pub async fn image_garbage_collect(
     edge_agent_bootstrap: String,
     settings: ImagePruneSettings,
-    runtime: &edgelet_docker::DockerModuleRuntime<http_common::Connector>,
+    runtime: &impl ModuleRuntime<Config = DockerConfig>,
     image_use_data: ImagePruneData,
+    requests: tokio::sync::mpsc::UnboundedReceiver<ImagePruneRequest>,
 ) -> Result<(), ImageCleanupError> {
     log::info!("Starting image garbage collection task...");
 
-    if !settings.is_enabled() {
-        return std::future::pending().await;
-    }
+    let scheduled = async {
+        if !settings.is_enabled() {
+            return std::future::pending().await;
+        }
+
+        let Some(disk_pressure) = settings.disk_pressure() else {
+            return scheduled_collect(&edge_agent_bootstrap, &settings, runtime, &image_use_data)
+                .await;
+        };
+
+        validate_disk_pressure(disk_pressure)?;
+
+        tokio::try_join!(
+            scheduled_collect(&edge_agent_bootstrap, &settings, runtime, &image_use_data),
+            disk_pressure_collect(
+                &edge_agent_bootstrap,
+                disk_pressure,
+                runtime,
+                &image_use_data
+            ),
+        )?;
+
+        Ok::<(), ImageCleanupError>(())
+    };
+
+    tokio::try_join!(
+        scheduled,
+        on_demand_collect(
+            &edge_agent_bootstrap,
+            &settings,
+            runtime,
+            &image_use_data,
+            requests
+        ),
+    )?;
+
+    Ok(())
+}
 
+async fn scheduled_collect(
+    edge_agent_bootstrap: &str,
+    settings: &ImagePruneSettings,
+    runtime: &impl ModuleRuntime<Config = DockerConfig>,
+    image_use_data: &ImagePruneData,
+) -> Result<(), ImageCleanupError> {
     let cleanup_time_in_mins = &mut settings.cleanup_time();
 
     let diff_in_secs: u64 = get_sleep_time_mins(*cleanup_time_in_mins) * 60;
     tokio::time::sleep(Duration::from_secs(diff_in_secs)).await;
 
-    let mut bootstrap_image_id_option = None;
-    let mut is_bootstrap_image_deleted: bool = false;
+    let mut bootstrap_image = BootstrapImage::new(edge_agent_bootstrap);
 
     loop {
-        // Try to get the bootstrap image id if we failed on the last(/all previous) run(s)
-        if bootstrap_image_id_option.is_none() {
-            // bootstrap edge agent image should never be deleted
-            if let Ok((id_option, is_image_deleted)) =
-                get_bootstrap_image_id(runtime, edge_agent_bootstrap.clone()).await
-            {
-                is_bootstrap_image_deleted = is_image_deleted;
-                if !is_image_deleted {
-                    bootstrap_image_id_option = id_option;
-                }
-            } else {
-                log::error!("Could not get bootstrap image id");
-            }
-        }
+        if bootstrap_image.refresh(runtime).await {
+            log::info!("Image Garbage Collection starting scheduled run");
 
-        if bootstrap_image_id_option.is_some()
-            || (bootstrap_image_id_option.is_none() && is_bootstrap_image_deleted)
-        {
             remove_unused_images(
                 runtime,
                 image_use_data.clone(),
-                bootstrap_image_id_option.clone(),
+                bootstrap_image.id.clone(),
+                settings.image_age_cleanup_threshold(),
+                false,
             )
             .await?;
         }
@@ -77,14 +113,219 @@ pub async fn image_garbage_collect(
     }
 }
 
+/// <summary>
+/// This method runs image garbage collection for each request from the management API, and
+/// sends back the images that were (or, for a dry run, would be) removed. A failed run is
+/// reported to the caller rather than stopping image garbage collection.
+async fn on_demand_collect(
+    edge_agent_bootstrap: &str,
+    settings: &ImagePruneSettings,
+    runtime: &impl ModuleRuntime<Config = DockerConfig>,
+    image_use_data: &ImagePruneData,
+    mut requests: tokio::sync::mpsc::UnboundedReceiver<ImagePruneRequest>,
+) -> Result<(), ImageCleanupError> {
+    let mut bootstrap_image = BootstrapImage::new(edge_agent_bootstrap);
+
+    while let Some(request) = requests.recv().await {
+        let report = if bootstrap_image.refresh(runtime).await {
+            log::info!(
+                "Image Garbage Collection starting on-demand run{}",
+                if request.dry_run { " (dry run)" } else { "" }
+            );
+
+            remove_unused_images(
+                runtime,
+                image_use_data.clone(),
+                bootstrap_image.id.clone(),
+                request
+                    .older_than
+                    .unwrap_or_else(|| settings.image_age_cleanup_threshold()),
+                request.dry_run,
+            )
+            .await
+            .map_err(|e| e.to_string())
+        } else {
+            Err("could not get the ID of the bootstrap Edge Agent image".to_string())
+        };
+
+        // The caller may have gone away; the run is done either way.
+        let _ = request.response.send(report);
+    }
+
+    // The management API has stopped, so there will be no more requests.
+    std::future::pending().await
+}
+
+/// <summary>
+/// This method is the controller loop for image garbage collection under disk pressure.
+/// Every 'check interval', it gets the usage of the disk holding the runtime's data. Above
+/// the 'high water mark', it removes unused images, least recently used first, until usage
+/// falls to the 'low water mark' [see relieve_disk_pressure()].
+async fn disk_pressure_collect(
+    edge_agent_bootstrap: &str,
+    settings: &DiskPressureSettings,
+    runtime: &impl ModuleRuntime<Config = DockerConfig>,
+    image_use_data: &ImagePruneData,
+) -> Result<(), ImageCleanupError> {
+    let mut bootstrap_image = BootstrapImage::new(edge_agent_bootstrap);
+
+    let mut check_timer = tokio::time::interval(settings.check_interval());
+    check_timer.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        check_timer.tick().await;
+
+        // The runtime may be briefly unavailable; this and any other failure below is retried
+        // on the next check instead of stopping garbage collection.
+        let data_root = match runtime.data_root().await {
+            Ok(data_root) => data_root,
+            Err(e) => {
+                log::warn!("Could not get the data root of the container runtime: {e}");
+                continue;
+            }
+        };
+
+        let Some(used) = disk_usage(&data_root) else {
+            log::warn!("Could not get disk usage of {}", data_root.display());
+            continue;
+        };
+
+        if used < settings.high_water_mark() || !bootstrap_image.refresh(runtime).await {
+            continue;
+        }
+
+        log::info!(
+            "Disk holding {} is {used}% full; removing unused images",
+            data_root.display()
+        );
+
+        if let Err(e) = relieve_disk_pressure(
+            settings,
+            runtime,
+            image_use_data,
+            &data_root,
+            bootstrap_image.id.clone(),
+        )
+        .await
+        {
+            log::warn!("Could not relieve disk pressure: {e}");
+        }
+    }
+}
+
+async fn relieve_disk_pressure(
+    settings: &DiskPressureSettings,
+    runtime: &impl ModuleRuntime<Config = DockerConfig>,
+    image_use_data: &ImagePruneData,
+    data_root: &Path,
+    bootstrap_image_id_option: Option<String>,
+) -> Result<(), ImageCleanupError> {
+    // Stopped containers keep their images in use, so they are removed first.
+    if settings.remove_stopped_containers() {
+        match runtime.prune_containers().await {
+            Ok(reclaimed) => log::info!("Removed stopped containers, reclaiming {reclaimed} bytes"),
+            Err(e) => log::warn!("Could not remove stopped containers: {e}"),
+        }
+    }
+
+    if settings.remove_dangling_volumes() {
+        match runtime.prune_volumes().await {
+            Ok(reclaimed) => log::info!("Removed dangling volumes, reclaiming {reclaimed} bytes"),
+            Err(e) => log::warn!("Could not remove dangling volumes: {e}"),
+        }
+    }
+
+    let in_use_image_ids = in_use_image_ids(runtime, bootstrap_image_id_option).await?;
+    let images = image_use_data
+        .least_recently_used(&in_use_image_ids)
+        .map_err(ImageCleanupError::PruneImages)?;
+
+    for image_id in images {
+        if disk_usage(data_root).is_some_and(|used| used <= settings.low_water_mark()) {
+            break;
+        }
+
+        if let Err(e) = runtime.registry().remove(&image_id).await {
+            log::error!("Could not delete image {image_id} : {e}");
+            continue;
+        }
+
+        if let Err(e) = image_use_data.forget_image(&image_id) {
+            log::warn!("Could not stop tracking deleted image {image_id} : {e}");
+        }
+    }
+
+    if let Some(used) = disk_usage(data_root).filter(|used| *used > settings.low_water_mark()) {
+        log::warn!(
+            "Disk holding {} is still {used}% full after removing unused images",
+            data_root.display()
+        );
+    }
+
+    Ok(())
+}
+
+// Deletes the images that have not been used for `older_than`, or with `dry_run`, only finds
+// them. Returns the images that were (or would be) deleted.
 async fn remove_unused_images(
-    runtime: &edgelet_docker::DockerModuleRuntime<http_common::Connector>,
+    runtime: &impl ModuleRuntime<Config = DockerConfig>,
     image_use_data: ImagePruneData,
     bootstrap_image_id_option: Option<String>,
-) -> Result<(), ImageCleanupError> {
-    log::info!("Image Garbage Collection starting scheduled run");
+    older_than: Duration,
+    dry_run: bool,
+) -> Result<ImagePruneReport, ImageCleanupError> {
+    let in_use_image_ids = in_use_image_ids(runtime, bootstrap_image_id_option).await?;
+
+    let image_map = image_use_data
+        .prune_images_from_file(in_use_image_ids, Some(older_than), dry_run)
+        .map_err(ImageCleanupError::PruneImages)?;
+
+    // sizes are only reported, so images are still deleted without them
+    let image_sizes = runtime.image_sizes().await.unwrap_or_else(|e| {
+        log::warn!("Could not get image sizes: {e}");
+        Default::default()
+    });
 
-    let bootstrap_img_id = bootstrap_image_id_option.clone().unwrap_or_default();
+    let mut report = ImagePruneReport {
+        dry_run,
+        ..Default::default()
+    };
+
+    // delete images
+    for (key, last_used) in image_map {
+        let removed = if dry_run {
+            Ok(())
+        } else {
+            runtime.registry().remove(&key).await
+        };
+        if let Err(e) = removed {
+            log::error!("Could not delete image {key} : {e}");
+            continue;
+        }
+
+        let size = image_sizes.get(&key).copied();
+        report.space_reclaimed += size.unwrap_or_default();
+        report
+            .images
+            .push(pruned_image(key, size, last_used, older_than));
+    }
+
+    report
+        .images
+        .sort_by(|a, b| a.last_used.cmp(&b.last_used).then_with(|| a.id.cmp(&b.id)));
+
+    Ok(report)
+}
+
+/* ================================================ HELPER METHODS ================================================ */
+
+// This is a helper method that gets the IDs of the images used by modules, along with the
+// bootstrap edge agent image ID [if it is known].
+async fn in_use_image_ids(
+    runtime: &impl ModuleRuntime<Config = DockerConfig>,
+    bootstrap_image_id_option: Option<String>,
+) -> Result<HashSet<String>, ImageCleanupError> {
+    let bootstrap_img_id = bootstrap_image_id_option.unwrap_or_default();
 
     // track images associated with extant containers
     let modules = ModuleRuntime::list_with_details(runtime)
@@ -105,21 +346,46 @@ async fn remove_unused_images(
         in_use_image_ids.insert(id.to_string());
     }
 
-    let image_map = image_use_data
-        .prune_images_from_file(in_use_image_ids)
-        .map_err(ImageCleanupError::PruneImages)?;
+    Ok(in_use_image_ids)
+}
 
-    // delete images
-    for key in image_map.keys() {
-        if let Err(e) = ModuleRegistry::remove(runtime, key).await {
-            log::error!("Could not delete image {key} : {e}");
+// The bootstrap edge agent image, which is never deleted. Its ID is looked up again on each run
+// until it has been found.
+struct BootstrapImage {
+    name: String,
+    id: Option<String>,
+    is_deleted: bool,
+}
+
+impl BootstrapImage {
+    fn new(name: &str) -> Self {
+        BootstrapImage {
+            name: name.to_string(),
+            id: None,
+            is_deleted: false,
         }
     }
 
-    Ok(())
-}
+    // Returns whether images can be pruned, i.e. whether the bootstrap image ID is known or the
+    // bootstrap image is known to be absent.
+    async fn refresh(&mut self, runtime: &impl ModuleRuntime<Config = DockerConfig>) -> bool {
+        // Try to get the bootstrap image id if we failed on the last(/all previous) run(s)
+        if self.id.is_none() {
+            if let Ok((id_option, is_image_deleted)) =
+                get_bootstrap_image_id(runtime, self.name.clone()).await
+            {
+                self.is_deleted = is_image_deleted;
+                if !is_image_deleted {
+                    self.id = id_option;
+                }
+            } else {
+                log::error!("Could not get bootstrap image id");
+            }
+        }
 
-/* ================================================ HELPER METHODS ================================================ */
+        self.id.is_some() || self.is_deleted
+    }
+}
 
 // This is a helper method that gets the imageID of the bootstrap edge agent, if it is present on the box.
 // This image is used as a fallback in certain scenarios and we have to make sure that we never delete it
@@ -134,7 +400,7 @@ async fn remove_unused_images(
 //                                                     do not prune images to ensure EA bootstrap isn't deleted
 
 async fn get_bootstrap_image_id(
-    runtime: &edgelet_docker::DockerModuleRuntime<http_common::Connector>,
+    runtime: &impl ModuleRuntime<Config = DockerConfig>,
     edge_agent_bootstrap: String,
 ) -> Result<(Option<String>, bool), ImageCleanupError> {
     let image_name_to_id = ModuleRuntime::list_images(runtime)
@@ -156,6 +422,81 @@ async fn get_bootstrap_image_id(
     ))
 }
 
+// Describes an image deleted because it was last used at `last_used` (in epoch), more than
+// `older_than` ago.
+fn pruned_image(
+    id: String,
+    size: Option<u64>,
+    last_used: Duration,
+    older_than: Duration,
+) -> PrunedImage {
+    let last_used = i64::try_from(last_used.as_secs())
+        .ok()
+        .and_then(|secs| DateTime::from_timestamp(secs, 0))
+        .unwrap_or(DateTime::<Utc>::UNIX_EPOCH);
+
+    PrunedImage {
+        id,
+        size,
+        last_used,
+        reason: format!(
+            "not used by any module since {}, which is more than {} ago",
+            last_used.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            humantime::format_duration(older_than)
+        ),
+    }
+}
+
+fn validate_disk_pressure(settings: &DiskPressureSettings) -> Result<(), ImageCleanupError> {
+    let (high, low) = (settings.high_water_mark(), settings.low_water_mark());
+
+    if settings.check_interval().is_zero() {
+        return Err(ImageCleanupError::InvalidConfiguration(
+            "disk pressure check_interval must not be zero".to_string(),
+        ));
+    }
+
+    if high > 100 || low >= high {
+        return Err(ImageCleanupError::InvalidConfiguration(format!(
+            "disk pressure low_water_mark ({low}) must be below high_water_mark ({high}), which must be at most 100"
+        )));
+    }
+
+    Ok(())
+}
+
+// Returns the percentage of the disk holding `path` that is in use.
+fn disk_usage(path: &Path) -> Option<u8> {
+    let disks = sysinfo::Disks::new_with_refreshed_list();
+
+    used_percent(
+        disks.list().iter().map(|disk| {
+            (
+                disk.mount_point(),
+                disk.available_space(),
+                disk.total_space(),
+            )
+        }),
+        path,
+    )
+}
+
+// Finds the disk holding `path` among `disks` of (mount point, available bytes, total bytes),
+// which is the one with the longest mount point that `path` is under.
+fn used_percent<'a>(
+    disks: impl IntoIterator<Item = (&'a Path, u64, u64)>,
+    path: &Path,
+) -> Option<u8> {
+    let (_, available, total) = disks
+        .into_iter()
+        .filter(|(mount_point, _, total)| path.starts_with(mount_point) && *total > 0)
+        .max_by_key(|(mount_point, _, _)| mount_point.as_os_str().len())?;
+
+    let used = u128::from(total.saturating_sub(available)) * 100 / u128::from(total);
+
+    u8::try_from(used).ok()
+}
+
 fn get_sleep_time_mins(cleanup_mins: u64) -> u64 {
     let current_hour = chrono::Local::now().hour();
     let current_minute = chrono::Local::now().minute();
@@ -170,8 +511,11 @@ fn get_sleep_time_mins(cleanup_mins: u64) -> u64 {
 
 #[cfg(test)]
 mod tests {
-    use super::get_sleep_time_mins;
+    use super::{get_sleep_time_mins, pruned_image, used_percent, validate_disk_pressure};
     use chrono::Timelike;
+    use edgelet_settings::base::image::DiskPressureSettings;
+    use std::path::Path;
+    use std::time::Duration;
 
     const TOTAL_MINS_IN_DAY: u64 = 1440;
 
@@ -193,4 +537,59 @@ mod tests {
 
         assert!(answer == result);
     }
+
+    #[test]
+    fn test_pruned_image() {
+        let image = pruned_image(
+            "sha256:unused".to_string(),
+            Some(1024),
+            Duration::from_secs(1_700_000_000),
+            Duration::from_hours(48),
+        );
+
+        assert_eq!("sha256:unused", image.id);
+        assert_eq!(Some(1024), image.size);
+        assert_eq!(1_700_000_000, image.last_used.timestamp());
+        assert_eq!(
+            "not used by any module since 2023-11-14T22:13:20Z, which is more than 2days ago",
+            image.reason
+        );
+    }
+
+    #[test]
+    fn test_used_percent() {
+        let disks = [
+            (Path::new("/"), 50, 100),
+            (Path::new("/var/lib/docker"), 10, 200),
+            (Path::new("/var/lib/docker-other"), 200, 200),
+            (Path::new("/mnt/empty"), 0, 0),
+        ];
+
+        // The disk with the longest mount point holding the path is used.
+        assert_eq!(
+            Some(95),
+            used_percent(disks, Path::new("/var/lib/docker/overlay2"))
+        );
+        assert_eq!(Some(50), used_percent(disks, Path::new("/var/lib")));
+
+        // Disks without a size are ignored.
+        assert_eq!(Some(50), used_percent(disks, Path::new("/mnt/empty")));
+        assert_eq!(None, used_percent([], Path::new("/var/lib/docker")));
+    }
+
+    #[test]
+    fn test_validate_disk_pressure() {
+        let settings =
+            |high, low| DiskPressureSettings::new(high, low, Duration::from_mins(5), false, false);
+
+        assert!(validate_disk_pressure(&DiskPressureSettings::default()).is_ok());
+        assert!(validate_disk_pressure(&settings(100, 0)).is_ok());
+
+        assert!(validate_disk_pressure(&settings(80, 80)).is_err());
+        assert!(validate_disk_pressure(&settings(70, 80)).is_err());
+        assert!(validate_disk_pressure(&settings(101, 80)).is_err());
+
+        let no_interval = DiskPressureSettings::new(85, 75, Duration::ZERO, false, false);
+        assert!(validate_disk_pressure(&no_interval).is_err());
+    }
 }
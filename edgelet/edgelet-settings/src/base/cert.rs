@@ -0,0 +1,331 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+
+/// How edged issues server and identity certificates to modules.
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ModuleCerts {
+    /// Algorithm of the keys generated for module certificates that do not request one.
+    #[serde(default)]
+    key_algorithm: KeyAlgorithm,
+
+    /// The names modules may have in their server certificates. Modules may request any name
+    /// if unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    san_policy: Option<SanPolicy>,
+
+    /// The percentage of a certificate's lifetime after which modules are told to renew it.
+    #[serde(
+        default = "default_renewal_threshold",
+        deserialize_with = "deserialize_renewal_threshold"
+    )]
+    renewal_threshold: u8,
+}
+
+fn default_renewal_threshold() -> u8 {
+    80
+}
+
+fn deserialize_renewal_threshold<'de, D>(deserializer: D) -> Result<u8, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let threshold: u8 = serde::Deserialize::deserialize(deserializer)?;
+
+    if (1..100).contains(&threshold) {
+        Ok(threshold)
+    } else {
+        Err(serde::de::Error::custom(
+            "renewal_threshold must be between 1 and 99",
+        ))
+    }
+}
+
+impl Default for ModuleCerts {
+    fn default() -> Self {
+        ModuleCerts::new(KeyAlgorithm::default())
+    }
+}
+
+impl ModuleCerts {
+    pub fn new(key_algorithm: KeyAlgorithm) -> Self {
+        ModuleCerts {
+            key_algorithm,
+            san_policy: None,
+            renewal_threshold: default_renewal_threshold(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_san_policy(mut self, san_policy: Option<SanPolicy>) -> Self {
+        self.san_policy = san_policy;
+        self
+    }
+
+    pub fn key_algorithm(&self) -> KeyAlgorithm {
+        self.key_algorithm
+    }
+
+    pub fn san_policy(&self) -> Option<&SanPolicy> {
+        self.san_policy.as_ref()
+    }
+
+    pub fn renewal_threshold(&self) -> u8 {
+        self.renewal_threshold
+    }
+
+    pub fn is_default(&self) -> bool {
+        self == &ModuleCerts::default()
+    }
+}
+
+/// The DNS names and IP addresses modules may have in their server certificates.
+///
+/// A DNS pattern is a name in which `{module}` stands for the module's name and `*` for one or
+/// more characters other than `.`, e.g. `{module}.*` for the module's name on any network. An IP
+/// pattern is an address or a CIDR block, e.g. `10.0.0.0/8`. Names are compared ignoring case.
+#[derive(Clone, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct SanPolicy {
+    /// Patterns that apply to all modules.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    default: Vec<String>,
+
+    /// Additional patterns for individual modules, by module name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    modules: BTreeMap<String, Vec<String>>,
+}
+
+impl SanPolicy {
+    pub fn new(default: Vec<String>, modules: BTreeMap<String, Vec<String>>) -> Self {
+        SanPolicy { default, modules }
+    }
+
+    /// Whether the module `module_id` may have `name` in its server certificate.
+    pub fn allows(&self, module_id: &str, name: &str) -> bool {
+        // Names of modules like '$edgeHub' are used without the '$' in certificates.
+        let module_id = module_id.trim_start_matches('$');
+
+        let module_patterns = self
+            .modules
+            .iter()
+            .filter(|(module, _)| module.trim_start_matches('$') == module_id)
+            .flat_map(|(_, patterns)| patterns);
+
+        let mut patterns = self.default.iter().chain(module_patterns);
+
+        if let Ok(ip) = name.parse::<IpAddr>() {
+            return patterns.any(|pattern| ip_matches(pattern, ip));
+        }
+
+        let name = name.to_lowercase();
+        let module_id = module_id.to_lowercase();
+        patterns.any(|pattern| {
+            let pattern = glob(&pattern.to_lowercase(), &module_id);
+            glob_matches(&pattern, name.as_bytes())
+        })
+    }
+}
+
+fn ip_matches(pattern: &str, ip: IpAddr) -> bool {
+    let (address, prefix) = match pattern.split_once('/') {
+        Some((address, prefix)) => (address, prefix.parse::<u32>().ok()),
+        None => (pattern, None),
+    };
+
+    let Ok(address) = address.parse::<IpAddr>() else {
+        return false;
+    };
+
+    let (address, ip, bits) = match (address, ip) {
+        (IpAddr::V4(address), IpAddr::V4(ip)) => (
+            u128::from(address.to_bits()) << 96,
+            u128::from(ip.to_bits()) << 96,
+            32,
+        ),
+        (IpAddr::V6(address), IpAddr::V6(ip)) => (address.to_bits(), ip.to_bits(), 128),
+        _ => return false,
+    };
+
+    let prefix = prefix.unwrap_or(bits);
+    if prefix > bits {
+        return false;
+    }
+
+    let mask = u128::MAX.checked_shl(128 - prefix).unwrap_or(0);
+    address & mask == ip & mask
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum GlobToken {
+    Byte(u8),
+    /// One or more characters other than `.`.
+    Any,
+}
+
+/// Parses SAN pattern `pattern`, substituting `module_id` for `{module}`. The module's name is
+/// taken literally, so a `*` in it is not a wildcard.
+fn glob(pattern: &str, module_id: &str) -> Vec<GlobToken> {
+    let mut tokens = vec![];
+
+    for (i, part) in pattern.split("{module}").enumerate() {
+        if i > 0 {
+            tokens.extend(module_id.bytes().map(GlobToken::Byte));
+        }
+
+        tokens.extend(part.bytes().map(|c| match c {
+            b'*' => GlobToken::Any,
+            c => GlobToken::Byte(c),
+        }));
+    }
+
+    tokens
+}
+
+/// Whether `name` matches `pattern`. A wildcard covers part of a single label, so it never
+/// matches an empty label or crosses a `.`.
+fn glob_matches(pattern: &[GlobToken], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((GlobToken::Any, rest)) => {
+            let label = name.iter().position(|&c| c == b'.').unwrap_or(name.len());
+            (1..=label).any(|skip| glob_matches(rest, &name[skip..]))
+        }
+        Some((GlobToken::Byte(c), rest)) => name
+            .split_first()
+            .is_some_and(|(n, name)| n == c && glob_matches(rest, name)),
+    }
+}
+
+/// The type and size of a key generated for a module certificate.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeyAlgorithm {
+    #[default]
+    Rsa2048,
+    Rsa3072,
+    Rsa4096,
+    /// ECDSA on the NIST P-256 curve.
+    EcP256,
+    /// ECDSA on the NIST P-384 curve.
+    EcP384,
+    Ed25519,
+}
+
+impl KeyAlgorithm {
+    /// The algorithm of a key type, e.g. "ec", and size in bits. A type without a size gets its
+    /// smallest supported size.
+    pub fn from_type_and_size(key_type: &str, size: Option<u32>) -> Result<Self, String> {
+        match (key_type.to_lowercase().as_str(), size) {
+            ("rsa", None | Some(2048)) => Ok(KeyAlgorithm::Rsa2048),
+            ("rsa", Some(3072)) => Ok(KeyAlgorithm::Rsa3072),
+            ("rsa", Some(4096)) => Ok(KeyAlgorithm::Rsa4096),
+            ("ec", None | Some(256)) => Ok(KeyAlgorithm::EcP256),
+            ("ec", Some(384)) => Ok(KeyAlgorithm::EcP384),
+            ("ed25519", None | Some(256)) => Ok(KeyAlgorithm::Ed25519),
+            (key_type @ ("rsa" | "ec" | "ed25519"), Some(size)) => {
+                Err(format!("Unsupported size {size} for key type {key_type}"))
+            }
+            _ => Err(format!("Unsupported key type {key_type}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KeyAlgorithm, ModuleCerts, SanPolicy};
+
+    #[test]
+    fn deserialize() {
+        let settings: ModuleCerts = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert!(settings.is_default());
+        assert_eq!(KeyAlgorithm::Rsa2048, settings.key_algorithm());
+
+        let settings: ModuleCerts =
+            serde_json::from_value(serde_json::json!({ "key_algorithm": "ec-p256" })).unwrap();
+        assert_eq!(KeyAlgorithm::EcP256, settings.key_algorithm());
+
+        serde_json::from_value::<ModuleCerts>(serde_json::json!({ "key_algorithm": "dsa" }))
+            .unwrap_err();
+    }
+
+    #[test]
+    fn renewal_threshold() {
+        let settings: ModuleCerts = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert_eq!(80, settings.renewal_threshold());
+
+        let settings: ModuleCerts =
+            serde_json::from_value(serde_json::json!({ "renewal_threshold": 50 })).unwrap();
+        assert_eq!(50, settings.renewal_threshold());
+
+        serde_json::from_value::<ModuleCerts>(serde_json::json!({ "renewal_threshold": 0 }))
+            .unwrap_err();
+        serde_json::from_value::<ModuleCerts>(serde_json::json!({ "renewal_threshold": 100 }))
+            .unwrap_err();
+    }
+
+    #[test]
+    fn type_and_size() {
+        assert_eq!(
+            Ok(KeyAlgorithm::Rsa2048),
+            KeyAlgorithm::from_type_and_size("rsa", None)
+        );
+        assert_eq!(
+            Ok(KeyAlgorithm::Rsa4096),
+            KeyAlgorithm::from_type_and_size("RSA", Some(4096))
+        );
+        assert_eq!(
+            Ok(KeyAlgorithm::EcP384),
+            KeyAlgorithm::from_type_and_size("ec", Some(384))
+        );
+        assert_eq!(
+            Ok(KeyAlgorithm::Ed25519),
+            KeyAlgorithm::from_type_and_size("ed25519", None)
+        );
+
+        KeyAlgorithm::from_type_and_size("rsa", Some(1024)).unwrap_err();
+        KeyAlgorithm::from_type_and_size("ec", Some(521)).unwrap_err();
+        KeyAlgorithm::from_type_and_size("dsa", None).unwrap_err();
+    }
+
+    #[test]
+    fn san_policy() {
+        let settings: ModuleCerts = serde_json::from_value(serde_json::json!({
+            "san_policy": {
+                "default": ["{module}", "{module}.*"],
+                "modules": { "$edgeHub": ["gateway.contoso.com", "10.0.0.0/8", "fd00::1"] },
+            },
+        }))
+        .unwrap();
+        let policy: &SanPolicy = settings.san_policy().unwrap();
+
+        assert!(policy.allows("sensor", "sensor"));
+        assert!(policy.allows("sensor", "SENSOR.azure-iot-edge"));
+        assert!(policy.allows("$edgeHub", "edgehub"));
+        assert!(policy.allows("$edgeHub", "gateway.contoso.com"));
+        assert!(policy.allows("edgeHub", "10.1.2.3"));
+        assert!(policy.allows("$edgeHub", "fd00::1"));
+
+        // Names of other modules, and names beyond the module's network.
+        assert!(!policy.allows("sensor", "edgehub"));
+        assert!(!policy.allows("sensor", "gateway.contoso.com"));
+        assert!(!policy.allows("sensor", "sensor.azure-iot-edge.contoso.com"));
+        assert!(!policy.allows("sensor", "10.1.2.3"));
+        assert!(!policy.allows("$edgeHub", "11.1.2.3"));
+        assert!(!policy.allows("$edgeHub", "fd00::2"));
+
+        // A wildcard stands for at least one character of a single label.
+        assert!(!policy.allows("sensor", "sensor."));
+        assert!(!policy.allows("sensor", "sensor..net"));
+
+        // A '*' in a module's name is not a wildcard.
+        assert!(policy.allows("sensor*", "sensor*"));
+        assert!(policy.allows("sensor*", "sensor*.net"));
+        assert!(!policy.allows("sensor*", "sensor1"));
+        assert!(!policy.allows("sensor*", "sensor1.net"));
+
+        // Modules may request any name without a policy.
+        let settings: ModuleCerts = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert!(settings.san_policy().is_none());
+    }
+}
@@ -0,0 +1,64 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::time::Duration;
+
+/// How the workload API encrypts data for modules.
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct DataEncryption {
+    /// How long data encrypted with a master key can still be decrypted after the key is rotated.
+    /// Modules must re-encrypt their data within this window.
+    #[serde(default = "default_grace_period", with = "humantime_serde")]
+    rotation_grace_period: Duration,
+}
+
+fn default_grace_period() -> Duration {
+    Duration::from_hours(24 * 30)
+}
+
+impl Default for DataEncryption {
+    fn default() -> Self {
+        DataEncryption {
+            rotation_grace_period: default_grace_period(),
+        }
+    }
+}
+
+impl DataEncryption {
+    pub fn new(rotation_grace_period: Duration) -> Self {
+        DataEncryption {
+            rotation_grace_period,
+        }
+    }
+
+    pub fn rotation_grace_period(&self) -> Duration {
+        self.rotation_grace_period
+    }
+
+    pub fn is_default(&self) -> bool {
+        self == &DataEncryption::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::DataEncryption;
+
+    #[test]
+    fn deserialize() {
+        let settings: DataEncryption = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert!(settings.is_default());
+        assert_eq!(
+            Duration::from_hours(24 * 30),
+            settings.rotation_grace_period()
+        );
+
+        let settings: DataEncryption =
+            serde_json::from_value(serde_json::json!({ "rotation_grace_period": "7d" })).unwrap();
+        assert_eq!(
+            Duration::from_hours(24 * 7),
+            settings.rotation_grace_period()
+        );
+    }
+}
@@ -26,6 +26,9 @@ pub struct ImagePruneSettings {
     // is image garbage collection enabled
     #[serde(default = "default_enabled")]
     enabled: bool,
+    /// frees disk space between scheduled runs when the disk fills up
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    disk_pressure: Option<DiskPressureSettings>,
 }
 
 impl ImagePruneSettings {
@@ -40,9 +43,16 @@ impl ImagePruneSettings {
             image_age_cleanup_threshold,
             cleanup_time,
             enabled,
+            disk_pressure: None,
         }
     }
 
+    #[must_use]
+    pub fn with_disk_pressure(mut self, disk_pressure: DiskPressureSettings) -> ImagePruneSettings {
+        self.disk_pressure = Some(disk_pressure);
+        self
+    }
+
     pub fn cleanup_recurrence(&self) -> Duration {
         self.cleanup_recurrence
     }
@@ -59,6 +69,10 @@ impl ImagePruneSettings {
         self.enabled
     }
 
+    pub fn disk_pressure(&self) -> Option<&DiskPressureSettings> {
+        self.disk_pressure.as_ref()
+    }
+
     pub fn is_default(value: &Self) -> bool {
         value == &Self::default()
     }
@@ -136,6 +150,91 @@ impl Default for ImagePruneSettings {
             image_age_cleanup_threshold: default_image_age_cleanup_threshold(),
             cleanup_time: 0,
             enabled: default_enabled(),
+            disk_pressure: None,
         }
     }
 }
+
+/// Options for removing unused images, least recently used first, when the disk that holds the
+/// container runtime's data fills up.
+#[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+pub struct DiskPressureSettings {
+    /// percentage of the disk in use above which images are removed
+    #[serde(default = "default_high_water_mark")]
+    high_water_mark: u8,
+    /// percentage of the disk in use at which removal stops
+    #[serde(default = "default_low_water_mark")]
+    low_water_mark: u8,
+    /// how often disk usage is checked
+    #[serde(default = "default_check_interval", with = "humantime_serde")]
+    check_interval: Duration,
+    /// whether stopped containers that IoT Edge did not create are removed first
+    #[serde(default)]
+    remove_stopped_containers: bool,
+    /// whether anonymous volumes that no container uses are removed first
+    #[serde(default)]
+    remove_dangling_volumes: bool,
+}
+
+impl DiskPressureSettings {
+    pub fn new(
+        high_water_mark: u8,
+        low_water_mark: u8,
+        check_interval: Duration,
+        remove_stopped_containers: bool,
+        remove_dangling_volumes: bool,
+    ) -> DiskPressureSettings {
+        DiskPressureSettings {
+            high_water_mark,
+            low_water_mark,
+            check_interval,
+            remove_stopped_containers,
+            remove_dangling_volumes,
+        }
+    }
+
+    pub fn high_water_mark(&self) -> u8 {
+        self.high_water_mark
+    }
+
+    pub fn low_water_mark(&self) -> u8 {
+        self.low_water_mark
+    }
+
+    pub fn check_interval(&self) -> Duration {
+        self.check_interval
+    }
+
+    pub fn remove_stopped_containers(&self) -> bool {
+        self.remove_stopped_containers
+    }
+
+    pub fn remove_dangling_volumes(&self) -> bool {
+        self.remove_dangling_volumes
+    }
+}
+
+impl Default for DiskPressureSettings {
+    fn default() -> Self {
+        DiskPressureSettings {
+            high_water_mark: default_high_water_mark(),
+            low_water_mark: default_low_water_mark(),
+            check_interval: default_check_interval(),
+            remove_stopped_containers: false,
+            remove_dangling_volumes: false,
+        }
+    }
+}
+
+fn default_high_water_mark() -> u8 {
+    85
+}
+
+fn default_low_water_mark() -> u8 {
+    75
+}
+
+// 5 minutes
+fn default_check_interval() -> Duration {
+    Duration::from_mins(5)
+}
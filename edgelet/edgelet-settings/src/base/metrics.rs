@@ -0,0 +1,45 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+/// Settings of the optional listener that serves module metrics in the Prometheus text format.
+#[derive(Clone, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Settings {
+    /// Address to listen on, e.g. `127.0.0.1:9601`. No listener is started if unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    listen: Option<std::net::SocketAddr>,
+}
+
+impl Settings {
+    pub fn new(listen: Option<std::net::SocketAddr>) -> Self {
+        Settings { listen }
+    }
+
+    pub fn listen(&self) -> Option<std::net::SocketAddr> {
+        self.listen
+    }
+
+    pub fn is_default(&self) -> bool {
+        self == &Settings::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Settings;
+
+    #[test]
+    fn deserialize() {
+        let settings: Settings = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert!(settings.is_default());
+        assert_eq!(None, settings.listen());
+
+        let settings: Settings =
+            serde_json::from_value(serde_json::json!({ "listen": "127.0.0.1:9601" })).unwrap();
+        assert_eq!(
+            Some(std::net::SocketAddr::from(([127, 0, 0, 1], 9601))),
+            settings.listen()
+        );
+
+        serde_json::from_value::<Settings>(serde_json::json!({ "listen": "localhost" }))
+            .unwrap_err();
+    }
+}
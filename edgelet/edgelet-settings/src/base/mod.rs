@@ -1,8 +1,15 @@
 // Copyright (c) Microsoft. All rights reserved.
 
 pub mod aziot;
+pub mod cert;
+pub mod encryption;
 pub mod image;
+pub mod metrics;
 pub mod module;
+pub mod policy;
+pub mod pull;
+pub mod quota;
+pub mod rollover;
 pub mod uri;
 pub mod watchdog;
 
@@ -40,6 +47,20 @@ pub trait RuntimeSettings {
     fn additional_info(&self) -> &std::collections::BTreeMap<String, String>;
 
     fn image_garbage_collection(&self) -> &image::ImagePruneSettings;
+
+    fn metrics(&self) -> &metrics::Settings;
+
+    fn resource_quotas(&self) -> &quota::ResourceQuotas;
+
+    fn container_policy(&self) -> &policy::ContainerPolicy;
+
+    fn image_pull(&self) -> &pull::Settings;
+
+    fn module_certs(&self) -> &cert::ModuleCerts;
+
+    fn data_encryption(&self) -> &encryption::DataEncryption;
+
+    fn edge_ca_rollover(&self) -> &rollover::EdgeCaRollover;
 }
 
 #[derive(Clone, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
@@ -130,6 +151,30 @@ pub struct Settings<ModuleConfig> {
 
     #[serde(default, skip_serializing_if = "image::ImagePruneSettings::is_default")]
     pub image_garbage_collection: image::ImagePruneSettings,
+
+    #[serde(default, skip_serializing_if = "metrics::Settings::is_default")]
+    pub metrics: metrics::Settings,
+
+    #[serde(default, skip_serializing_if = "quota::ResourceQuotas::is_default")]
+    pub resource_quotas: quota::ResourceQuotas,
+
+    #[serde(default, skip_serializing_if = "policy::ContainerPolicy::is_default")]
+    pub container_policy: policy::ContainerPolicy,
+
+    #[serde(default, skip_serializing_if = "pull::Settings::is_default")]
+    pub image_pull: pull::Settings,
+
+    #[serde(default, skip_serializing_if = "cert::ModuleCerts::is_default")]
+    pub module_certs: cert::ModuleCerts,
+
+    #[serde(
+        default,
+        skip_serializing_if = "encryption::DataEncryption::is_default"
+    )]
+    pub data_encryption: encryption::DataEncryption,
+
+    #[serde(default, skip_serializing_if = "rollover::EdgeCaRollover::is_default")]
+    pub edge_ca_rollover: rollover::EdgeCaRollover,
 }
 
 pub(crate) fn default_allow_elevated_docker_permissions() -> bool {
@@ -214,4 +259,32 @@ impl<T: Clone> RuntimeSettings for Settings<T> {
     fn image_garbage_collection(&self) -> &image::ImagePruneSettings {
         &self.image_garbage_collection
     }
+
+    fn metrics(&self) -> &metrics::Settings {
+        &self.metrics
+    }
+
+    fn resource_quotas(&self) -> &quota::ResourceQuotas {
+        &self.resource_quotas
+    }
+
+    fn container_policy(&self) -> &policy::ContainerPolicy {
+        &self.container_policy
+    }
+
+    fn image_pull(&self) -> &pull::Settings {
+        &self.image_pull
+    }
+
+    fn module_certs(&self) -> &cert::ModuleCerts {
+        &self.module_certs
+    }
+
+    fn data_encryption(&self) -> &encryption::DataEncryption {
+        &self.data_encryption
+    }
+
+    fn edge_ca_rollover(&self) -> &rollover::EdgeCaRollover {
+        &self.edge_ca_rollover
+    }
 }
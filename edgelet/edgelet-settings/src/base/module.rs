@@ -9,6 +9,13 @@ pub struct Settings<ModuleConfig> {
     #[serde(default, rename = "imagePullPolicy")]
     image_pull_policy: ImagePullPolicy,
 
+    #[serde(
+        default,
+        rename = "restartPolicy",
+        skip_serializing_if = "RestartPolicy::is_default"
+    )]
+    restart_policy: RestartPolicy,
+
     config: ModuleConfig,
 
     #[serde(default)]
@@ -26,6 +33,7 @@ where
             config: self.config.clone(),
             env: self.env.clone(),
             image_pull_policy: self.image_pull_policy,
+            restart_policy: self.restart_policy,
         }
     }
 }
@@ -50,6 +58,7 @@ impl<ModuleConfig> Settings<ModuleConfig> {
             name,
             r#type,
             image_pull_policy,
+            restart_policy: RestartPolicy::default(),
             config,
             env,
         })
@@ -85,6 +94,16 @@ impl<ModuleConfig> Settings<ModuleConfig> {
         self
     }
 
+    pub fn restart_policy(&self) -> RestartPolicy {
+        self.restart_policy
+    }
+
+    #[must_use]
+    pub fn with_restart_policy(mut self, restart_policy: RestartPolicy) -> Self {
+        self.restart_policy = restart_policy;
+        self
+    }
+
     pub fn config(&self) -> &ModuleConfig {
         &self.config
     }
@@ -118,12 +137,23 @@ impl<ModuleConfig> Settings<ModuleConfig> {
     }
 }
 
+/// Determines how edged gets the image of a module before creating it.
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum ImagePullPolicy {
+    /// Pull the image every time the module is created.
     #[serde(rename = "on-create")]
     #[default]
     OnCreate,
+    /// Use the image if it is already present, or else load it from the module's offline
+    /// bundle. Pull it only if neither has it.
+    #[serde(rename = "prefer-local")]
+    PreferLocal,
+    /// Use the image if it is already present, or else load it from the module's offline
+    /// bundle. Never pull it.
+    #[serde(rename = "offline-bundle")]
+    OfflineBundle,
+    /// Never get the image; it must already be present.
     Never,
 }
 
@@ -133,8 +163,59 @@ impl std::str::FromStr for ImagePullPolicy {
     fn from_str(s: &str) -> Result<ImagePullPolicy, Self::Err> {
         match s.to_lowercase().as_str() {
             "on-create" => Ok(ImagePullPolicy::OnCreate),
+            "prefer-local" => Ok(ImagePullPolicy::PreferLocal),
+            "offline-bundle" => Ok(ImagePullPolicy::OfflineBundle),
             "never" => Ok(ImagePullPolicy::Never),
             _ => Err(format!("Unsupported image pull policy {s}")),
         }
     }
 }
+
+/// Determines when edged restarts a module that is no longer running.
+///
+/// A module that was stopped through the management API is not restarted, regardless of its
+/// policy, until it is started again. This holds across restarts of edged.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RestartPolicy {
+    /// Restart the module whenever it exits.
+    Always,
+    /// Restart the module only if it exits with a non-zero exit code.
+    OnFailure,
+    /// Restart the module if it fails or is reported unhealthy.
+    OnUnhealthy,
+    /// Never restart the module. Any restart policy of the container runtime still applies.
+    #[default]
+    Never,
+}
+
+impl RestartPolicy {
+    pub fn is_default(value: &Self) -> bool {
+        value == &Self::default()
+    }
+}
+
+impl std::fmt::Display for RestartPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            RestartPolicy::Always => "always",
+            RestartPolicy::OnFailure => "on-failure",
+            RestartPolicy::OnUnhealthy => "on-unhealthy",
+            RestartPolicy::Never => "never",
+        })
+    }
+}
+
+impl std::str::FromStr for RestartPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<RestartPolicy, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "always" => Ok(RestartPolicy::Always),
+            "on-failure" => Ok(RestartPolicy::OnFailure),
+            "on-unhealthy" => Ok(RestartPolicy::OnUnhealthy),
+            "never" => Ok(RestartPolicy::Never),
+            _ => Err(format!("Unsupported restart policy {s}")),
+        }
+    }
+}
@@ -0,0 +1,198 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+/// Rules on what module create options may ask for, applied to every module that is created.
+#[derive(Clone, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ContainerPolicy {
+    #[serde(flatten)]
+    rules: PolicyRules,
+
+    /// Per-module rules. A rule set here replaces the corresponding device-wide rule for that
+    /// module only.
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    exceptions: std::collections::BTreeMap<String, PolicyRules>,
+}
+
+impl ContainerPolicy {
+    pub fn new(
+        rules: PolicyRules,
+        exceptions: std::collections::BTreeMap<String, PolicyRules>,
+    ) -> Self {
+        ContainerPolicy { rules, exceptions }
+    }
+
+    /// The rules that apply to module `name`.
+    pub fn rules_for(&self, name: &str) -> PolicyRules {
+        match self.exceptions.get(name) {
+            Some(exception) => self.rules.clone().overridden_by(exception.clone()),
+            None => self.rules.clone(),
+        }
+    }
+
+    pub fn is_default(&self) -> bool {
+        self == &ContainerPolicy::default()
+    }
+}
+
+/// Each rule is unrestricted when unset.
+#[derive(Clone, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct PolicyRules {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub privileged: Option<bool>,
+
+    /// Whether the host network may be used. Joining the network of another container with
+    /// `container:<id>` is held to the same rule.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host_network: Option<bool>,
+
+    /// Like `host_network`, for the PID namespace.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host_pid: Option<bool>,
+
+    /// Like `host_network`, for the IPC namespace.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host_ipc: Option<bool>,
+
+    /// Whether the host user namespace may be used.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host_userns: Option<bool>,
+
+    /// Whether the host cgroup namespace may be used.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host_cgroupns: Option<bool>,
+
+    /// Host paths that may be bind-mounted. A path also covers everything below it. Paths are
+    /// compared after symlinks are resolved, so a link inside an allowed directory does not reach
+    /// outside it. Links are resolved when the module is created, so a link swapped in afterwards
+    /// is not seen; do not allow directories that untrusted modules can write to. When set,
+    /// `VolumesFrom` is not allowed, since it would bring along another container's binds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bind_mounts: Option<AllowDeny>,
+
+    /// Host device paths that may be mapped. A path also covers everything below it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub devices: Option<AllowDeny>,
+
+    /// Device cgroup rules that may be added, like `c 189:* rmw`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub device_cgroup_rules: Option<AllowDeny>,
+
+    /// Capabilities that may be added, with or without the `CAP_` prefix. `ALL` stands for every
+    /// capability, so adding it is only allowed when nothing is denied and `allow` lists `ALL`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub capabilities: Option<AllowDeny>,
+
+    /// Seccomp profiles that may be used. `default` is the runtime's default profile. A profile
+    /// given inline as JSON is named `inline`, and is only allowed when `allow` lists `inline`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seccomp_profiles: Option<AllowDeny>,
+
+    /// AppArmor profiles that may be used. `default` is the runtime's default profile.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub apparmor_profiles: Option<AllowDeny>,
+
+    /// Registries that module images may come from, e.g. `mcr.microsoft.com`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registries: Option<AllowDeny>,
+}
+
+impl PolicyRules {
+    #[must_use]
+    fn overridden_by(self, other: PolicyRules) -> Self {
+        PolicyRules {
+            privileged: other.privileged.or(self.privileged),
+            host_network: other.host_network.or(self.host_network),
+            host_pid: other.host_pid.or(self.host_pid),
+            host_ipc: other.host_ipc.or(self.host_ipc),
+            host_userns: other.host_userns.or(self.host_userns),
+            host_cgroupns: other.host_cgroupns.or(self.host_cgroupns),
+            bind_mounts: other.bind_mounts.or(self.bind_mounts),
+            devices: other.devices.or(self.devices),
+            device_cgroup_rules: other.device_cgroup_rules.or(self.device_cgroup_rules),
+            capabilities: other.capabilities.or(self.capabilities),
+            seccomp_profiles: other.seccomp_profiles.or(self.seccomp_profiles),
+            apparmor_profiles: other.apparmor_profiles.or(self.apparmor_profiles),
+            registries: other.registries.or(self.registries),
+        }
+    }
+}
+
+/// A value is allowed if it is in `allow`, or `allow` is unset, and it is not in `deny`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct AllowDeny {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow: Option<Vec<String>>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deny: Vec<String>,
+}
+
+impl AllowDeny {
+    /// Whether `value` is allowed, where `matches(entry, value)` tells whether a list entry
+    /// covers the value.
+    pub fn allows(&self, value: &str, matches: impl Fn(&str, &str) -> bool) -> bool {
+        let allowed = self
+            .allow
+            .as_ref()
+            .is_none_or(|allow| allow.iter().any(|entry| matches(entry, value)));
+
+        allowed && !self.deny.iter().any(|entry| matches(entry, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AllowDeny, ContainerPolicy};
+
+    #[test]
+    fn deserialize() {
+        let policy: ContainerPolicy = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert!(policy.is_default());
+
+        let policy: ContainerPolicy = serde_json::from_value(serde_json::json!({
+            "privileged": false,
+            "bind_mounts": { "allow": ["/var/lib/data"], "deny": ["/var/lib/data/secret"] },
+            "registries": { "allow": ["mcr.microsoft.com"] },
+            "exceptions": {
+                "edgeHub": {
+                    "privileged": true,
+                    "bind_mounts": { "deny": [] },
+                },
+            },
+        }))
+        .unwrap();
+
+        let rules = policy.rules_for("tempSensor");
+        assert_eq!(Some(false), rules.privileged);
+        assert_eq!(
+            Some(vec!["/var/lib/data".to_owned()]),
+            rules.bind_mounts.unwrap().allow
+        );
+
+        let rules = policy.rules_for("edgeHub");
+        assert_eq!(Some(true), rules.privileged);
+        assert_eq!(Some(AllowDeny::default()), rules.bind_mounts);
+        assert!(rules.registries.is_some());
+    }
+
+    #[test]
+    fn allow_deny() {
+        let eq = |entry: &str, value: &str| entry == value;
+
+        assert!(AllowDeny::default().allows("a", eq));
+
+        let list = AllowDeny {
+            allow: Some(vec!["a".to_owned(), "b".to_owned()]),
+            deny: vec!["b".to_owned()],
+        };
+        assert!(list.allows("a", eq));
+        assert!(!list.allows("b", eq));
+        assert!(!list.allows("c", eq));
+
+        let list = AllowDeny {
+            allow: None,
+            deny: vec!["b".to_owned()],
+        };
+        assert!(list.allows("c", eq));
+        assert!(!list.allows("b", eq));
+    }
+}
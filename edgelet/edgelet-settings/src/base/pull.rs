@@ -0,0 +1,119 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// How module images are pulled.
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Settings {
+    /// Download rate limit in bytes per second, shared by all layers of a pull. Pulls are not
+    /// limited if unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_bandwidth: Option<u64>,
+
+    /// How many times a pull that failed with a transient error, such as a dropped connection,
+    /// is tried again. Layers that were already downloaded are not downloaded again.
+    #[serde(default = "default_retries")]
+    retries: u32,
+
+    /// Delay before a failed pull is tried again.
+    #[serde(default = "default_retry_delay", with = "humantime_serde")]
+    retry_delay: Duration,
+
+    /// Offline bundles by image name. A bundle is a `docker save` tarball or an OCI image layout
+    /// directory that holds the image, for modules whose pull policy allows loading it.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    bundles: BTreeMap<String, PathBuf>,
+}
+
+fn default_retries() -> u32 {
+    5
+}
+
+fn default_retry_delay() -> Duration {
+    Duration::from_secs(10)
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            max_bandwidth: None,
+            retries: default_retries(),
+            retry_delay: default_retry_delay(),
+            bundles: BTreeMap::new(),
+        }
+    }
+}
+
+impl Settings {
+    pub fn new(max_bandwidth: Option<u64>, retries: u32, retry_delay: Duration) -> Self {
+        Settings {
+            max_bandwidth,
+            retries,
+            retry_delay,
+            bundles: BTreeMap::new(),
+        }
+    }
+
+    pub fn max_bandwidth(&self) -> Option<u64> {
+        self.max_bandwidth
+            .filter(|max_bandwidth| *max_bandwidth > 0)
+    }
+
+    pub fn retries(&self) -> u32 {
+        self.retries
+    }
+
+    pub fn retry_delay(&self) -> Duration {
+        self.retry_delay
+    }
+
+    pub fn bundle(&self, image: &str) -> Option<&Path> {
+        self.bundles.get(image).map(PathBuf::as_path)
+    }
+
+    pub fn is_default(&self) -> bool {
+        self == &Settings::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::Settings;
+
+    #[test]
+    fn deserialize() {
+        let settings: Settings = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert!(settings.is_default());
+        assert_eq!(None, settings.max_bandwidth());
+        assert_eq!(5, settings.retries());
+
+        let settings: Settings = serde_json::from_value(serde_json::json!({
+            "max_bandwidth": 131_072,
+            "retries": 0,
+            "retry_delay": "1m",
+        }))
+        .unwrap();
+        assert_eq!(Some(131_072), settings.max_bandwidth());
+        assert_eq!(0, settings.retries());
+        assert_eq!(Duration::from_mins(1), settings.retry_delay());
+
+        let settings: Settings =
+            serde_json::from_value(serde_json::json!({ "max_bandwidth": 0 })).unwrap();
+        assert_eq!(None, settings.max_bandwidth());
+
+        let settings: Settings = serde_json::from_value(serde_json::json!({
+            "bundles": { "contoso.azurecr.io/app:1.0": "/var/lib/bundles/app.tar" },
+        }))
+        .unwrap();
+        assert!(!settings.is_default());
+        assert_eq!(
+            Some(std::path::Path::new("/var/lib/bundles/app.tar")),
+            settings.bundle("contoso.azurecr.io/app:1.0")
+        );
+        assert_eq!(None, settings.bundle("contoso.azurecr.io/app:2.0"));
+    }
+}
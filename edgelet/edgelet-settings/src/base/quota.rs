@@ -0,0 +1,317 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+/// Device-wide resource policy applied to every module that is created.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize)]
+pub struct ResourceQuotas {
+    /// What to do when a module asks for more than `max` allows.
+    #[serde(default, skip_serializing_if = "QuotaAction::is_default")]
+    on_exceed: QuotaAction,
+
+    /// Limits given to modules whose create options do not set them.
+    #[serde(default, skip_serializing_if = "ResourceLimits::is_default")]
+    default: ResourceLimits,
+
+    /// Largest limits that a module may ask for.
+    #[serde(default, skip_serializing_if = "ResourceLimits::is_default")]
+    max: ResourceLimits,
+}
+
+impl ResourceQuotas {
+    pub fn new(on_exceed: QuotaAction, default: ResourceLimits, max: ResourceLimits) -> Self {
+        ResourceQuotas {
+            on_exceed,
+            default,
+            max,
+        }
+    }
+
+    pub fn on_exceed(&self) -> QuotaAction {
+        self.on_exceed
+    }
+
+    pub fn default_limits(&self) -> &ResourceLimits {
+        &self.default
+    }
+
+    pub fn max_limits(&self) -> &ResourceLimits {
+        &self.max
+    }
+
+    pub fn is_default(&self) -> bool {
+        self == &ResourceQuotas::default()
+    }
+
+    /// Checks that each limit is valid and that no default is above its ceiling, which would
+    /// otherwise be blamed on every module that leaves the limit unset.
+    fn validate(&self) -> Result<(), String> {
+        fn above<T: PartialOrd>(default: Option<T>, max: Option<T>) -> bool {
+            matches!((default, max), (Some(default), Some(max)) if default > max)
+        }
+
+        self.default.validate("default")?;
+        self.max.validate("max")?;
+
+        let ulimits_above = self.default.ulimits.iter().map(|(name, default)| {
+            let above = self
+                .max
+                .ulimits
+                .get(name)
+                .is_some_and(|max| default.soft > max.soft || default.hard > max.hard);
+            (name.as_str(), above)
+        });
+
+        let mut limits = [
+            ("memory", above(self.default.memory, self.max.memory)),
+            (
+                "cpu_shares",
+                above(self.default.cpu_shares, self.max.cpu_shares),
+            ),
+            ("cpus", above(self.default.cpus, self.max.cpus)),
+            (
+                "pids_limit",
+                above(self.default.pids_limit, self.max.pids_limit),
+            ),
+        ]
+        .into_iter()
+        .chain(ulimits_above);
+
+        match limits.find(|(_, above)| *above) {
+            Some((limit, _)) => Err(format!("default {limit} is above the max {limit}")),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for ResourceQuotas {
+    fn deserialize<D>(de: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(serde::Deserialize)]
+        struct Inner {
+            #[serde(default)]
+            on_exceed: QuotaAction,
+            #[serde(default)]
+            default: ResourceLimits,
+            #[serde(default)]
+            max: ResourceLimits,
+        }
+
+        let Inner {
+            on_exceed,
+            default,
+            max,
+        } = Inner::deserialize(de)?;
+        let quotas = ResourceQuotas {
+            on_exceed,
+            default,
+            max,
+        };
+        quotas
+            .validate()
+            .map_err(<D::Error as serde::de::Error>::custom)?;
+
+        Ok(quotas)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuotaAction {
+    /// Lower the module's limits to the ceiling and log a warning.
+    #[default]
+    Clamp,
+
+    /// Refuse to create the module.
+    Reject,
+}
+
+impl QuotaAction {
+    pub fn is_default(&self) -> bool {
+        self == &QuotaAction::default()
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct ResourceLimits {
+    /// Memory limit in bytes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    memory: Option<i64>,
+
+    /// Relative CPU weight.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cpu_shares: Option<i64>,
+
+    /// Number of CPUs, which may be fractional. Corresponds to a CPU quota.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cpus: Option<f64>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pids_limit: Option<i64>,
+
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    ulimits: std::collections::BTreeMap<String, Ulimit>,
+}
+
+impl ResourceLimits {
+    pub fn memory(&self) -> Option<i64> {
+        self.memory
+    }
+
+    #[must_use]
+    pub fn with_memory(mut self, memory: Option<i64>) -> Self {
+        self.memory = memory;
+        self
+    }
+
+    pub fn cpu_shares(&self) -> Option<i64> {
+        self.cpu_shares
+    }
+
+    #[must_use]
+    pub fn with_cpu_shares(mut self, cpu_shares: Option<i64>) -> Self {
+        self.cpu_shares = cpu_shares;
+        self
+    }
+
+    pub fn cpus(&self) -> Option<f64> {
+        self.cpus
+    }
+
+    #[must_use]
+    pub fn with_cpus(mut self, cpus: Option<f64>) -> Self {
+        self.cpus = cpus;
+        self
+    }
+
+    pub fn pids_limit(&self) -> Option<i64> {
+        self.pids_limit
+    }
+
+    #[must_use]
+    pub fn with_pids_limit(mut self, pids_limit: Option<i64>) -> Self {
+        self.pids_limit = pids_limit;
+        self
+    }
+
+    pub fn ulimits(&self) -> &std::collections::BTreeMap<String, Ulimit> {
+        &self.ulimits
+    }
+
+    #[must_use]
+    pub fn with_ulimit(mut self, name: String, ulimit: Ulimit) -> Self {
+        self.ulimits.insert(name, ulimit);
+        self
+    }
+
+    pub fn is_default(&self) -> bool {
+        self == &ResourceLimits::default()
+    }
+
+    fn validate(&self, kind: &str) -> Result<(), String> {
+        if let Some(cpus) = self.cpus
+            && (!cpus.is_finite() || cpus <= 0.0)
+        {
+            return Err(format!("{kind} cpus must be greater than 0"));
+        }
+
+        for (name, ulimit) in &self.ulimits {
+            if ulimit.soft > ulimit.hard {
+                return Err(format!(
+                    "{kind} {name} ulimit has a soft limit above its hard limit"
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Ulimit {
+    pub soft: i64,
+    pub hard: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{QuotaAction, ResourceQuotas, Ulimit};
+
+    #[test]
+    fn deserialize() {
+        let quotas: ResourceQuotas = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert!(quotas.is_default());
+        assert_eq!(QuotaAction::Clamp, quotas.on_exceed());
+
+        let quotas: ResourceQuotas = serde_json::from_value(serde_json::json!({
+            "on_exceed": "reject",
+            "default": {
+                "memory": 268_435_456,
+                "pids_limit": 256,
+            },
+            "max": {
+                "cpus": 1.5,
+                "ulimits": {
+                    "nofile": { "soft": 1024, "hard": 4096 },
+                },
+            },
+        }))
+        .unwrap();
+        assert_eq!(QuotaAction::Reject, quotas.on_exceed());
+        assert_eq!(Some(268_435_456), quotas.default_limits().memory());
+        assert_eq!(Some(256), quotas.default_limits().pids_limit());
+        assert_eq!(None, quotas.default_limits().cpus());
+        assert_eq!(Some(1.5), quotas.max_limits().cpus());
+        assert_eq!(
+            Some(&Ulimit {
+                soft: 1024,
+                hard: 4096
+            }),
+            quotas.max_limits().ulimits().get("nofile")
+        );
+
+        serde_json::from_value::<ResourceQuotas>(serde_json::json!({ "on_exceed": "ignore" }))
+            .unwrap_err();
+    }
+
+    #[test]
+    fn invalid_limits_are_rejected() {
+        let err = |quotas: serde_json::Value| {
+            serde_json::from_value::<ResourceQuotas>(quotas)
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(
+            "default memory is above the max memory",
+            err(serde_json::json!({
+                "on_exceed": "reject",
+                "default": { "memory": 2048, "pids_limit": 10 },
+                "max": { "memory": 1024, "pids_limit": 100 },
+            }))
+        );
+        assert_eq!(
+            "default nofile is above the max nofile",
+            err(serde_json::json!({
+                "default": { "ulimits": { "nofile": { "soft": 1024, "hard": 8192 } } },
+                "max": { "ulimits": { "nofile": { "soft": 4096, "hard": 4096 } } },
+            }))
+        );
+        assert_eq!(
+            "max cpus must be greater than 0",
+            err(serde_json::json!({ "max": { "cpus": 0.0 } }))
+        );
+        assert_eq!(
+            "default nofile ulimit has a soft limit above its hard limit",
+            err(serde_json::json!({
+                "default": { "ulimits": { "nofile": { "soft": 4096, "hard": 1024 } } },
+            }))
+        );
+
+        serde_json::from_value::<ResourceQuotas>(serde_json::json!({
+            "default": { "memory": 1024, "cpus": 0.5 },
+            "max": { "memory": 1024, "cpus": 1.5 },
+        }))
+        .unwrap();
+    }
+}
@@ -0,0 +1,86 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::time::Duration;
+
+/// How modules move to a renewed Edge CA certificate.
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct EdgeCaRollover {
+    /// Whether modules are told to request new certificates instead of all being restarted.
+    #[serde(default)]
+    live_reload: bool,
+
+    /// How long the previous Edge CA certificate stays in the trust bundle after a renewal, so
+    /// that certificates it issued are still trusted until their modules replace them.
+    #[serde(default = "default_overlap", with = "humantime_serde")]
+    overlap: Duration,
+
+    /// Modules that cannot reload their certificates. With live reload, only these modules are
+    /// restarted, one at a time.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    restart_modules: Vec<String>,
+}
+
+fn default_overlap() -> Duration {
+    Duration::from_hours(24)
+}
+
+impl Default for EdgeCaRollover {
+    fn default() -> Self {
+        EdgeCaRollover {
+            live_reload: false,
+            overlap: default_overlap(),
+            restart_modules: Vec::new(),
+        }
+    }
+}
+
+impl EdgeCaRollover {
+    pub fn new(live_reload: bool, overlap: Duration, restart_modules: Vec<String>) -> Self {
+        EdgeCaRollover {
+            live_reload,
+            overlap,
+            restart_modules,
+        }
+    }
+
+    pub fn live_reload(&self) -> bool {
+        self.live_reload
+    }
+
+    pub fn overlap(&self) -> Duration {
+        self.overlap
+    }
+
+    pub fn restart_modules(&self) -> &[String] {
+        &self.restart_modules
+    }
+
+    pub fn is_default(&self) -> bool {
+        self == &EdgeCaRollover::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::EdgeCaRollover;
+
+    #[test]
+    fn deserialize() {
+        let settings: EdgeCaRollover = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert!(settings.is_default());
+        assert!(!settings.live_reload());
+        assert_eq!(Duration::from_hours(24), settings.overlap());
+
+        let settings: EdgeCaRollover = serde_json::from_value(serde_json::json!({
+            "live_reload": true,
+            "overlap": "2h",
+            "restart_modules": ["legacyModule"],
+        }))
+        .unwrap();
+        assert!(settings.live_reload());
+        assert_eq!(Duration::from_hours(2), settings.overlap());
+        assert_eq!(["legacyModule".to_string()], settings.restart_modules());
+    }
+}
@@ -1,20 +1,108 @@
 // Copyright (c) Microsoft. All rights reserved.
 
 use std::convert::TryInto;
+use std::time::Duration;
 
 #[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
 pub struct Settings {
     #[serde(default)]
     pub max_retries: MaxRetries,
+
+    #[serde(default, skip_serializing_if = "RestartBackoff::is_default")]
+    pub restart_backoff: RestartBackoff,
 }
 
 impl Settings {
     pub fn max_retries(&self) -> MaxRetries {
         self.max_retries
     }
+
+    pub fn restart_backoff(&self) -> &RestartBackoff {
+        &self.restart_backoff
+    }
+}
+
+/// Back-off applied by edged when restarting modules according to their restart policy.
+///
+/// The delay before the n-th consecutive restart is `initial_delay * 2^(n - 1)`, capped at
+/// `max_delay`. A module that stays up for `reset_after` has its restart count reset.
+#[derive(Clone, Debug, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct RestartBackoff {
+    #[serde(default = "default_initial_delay", with = "humantime_serde")]
+    initial_delay: Duration,
+
+    #[serde(default = "default_max_delay", with = "humantime_serde")]
+    max_delay: Duration,
+
+    #[serde(default)]
+    max_restarts: MaxRetries,
+
+    #[serde(default = "default_reset_after", with = "humantime_serde")]
+    reset_after: Duration,
+}
+
+impl RestartBackoff {
+    pub fn new(
+        initial_delay: Duration,
+        max_delay: Duration,
+        max_restarts: MaxRetries,
+        reset_after: Duration,
+    ) -> Self {
+        RestartBackoff {
+            initial_delay,
+            max_delay,
+            max_restarts,
+            reset_after,
+        }
+    }
+
+    pub fn initial_delay(&self) -> Duration {
+        self.initial_delay
+    }
+
+    pub fn max_delay(&self) -> Duration {
+        self.max_delay
+    }
+
+    pub fn max_restarts(&self) -> MaxRetries {
+        self.max_restarts
+    }
+
+    pub fn reset_after(&self) -> Duration {
+        self.reset_after
+    }
+
+    pub fn is_default(value: &Self) -> bool {
+        value == &Self::default()
+    }
+}
+
+impl Default for RestartBackoff {
+    fn default() -> Self {
+        RestartBackoff {
+            initial_delay: default_initial_delay(),
+            max_delay: default_max_delay(),
+            max_restarts: MaxRetries::default(),
+            reset_after: default_reset_after(),
+        }
+    }
 }
 
-#[derive(Clone, Copy, Debug, Default)]
+fn default_initial_delay() -> Duration {
+    Duration::from_secs(10)
+}
+
+// 5 minutes
+fn default_max_delay() -> Duration {
+    Duration::from_mins(5)
+}
+
+// 10 minutes
+fn default_reset_after() -> Duration {
+    Duration::from_mins(10)
+}
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
 pub enum MaxRetries {
     #[default]
     Infinite,
@@ -136,4 +224,27 @@ mod tests {
         assert!(max_retries == 10);
         assert!(max_retries < 11);
     }
+
+    #[test]
+    fn restart_backoff() {
+        let settings: super::Settings = serde_json::from_value(serde_json::json!({
+            "max_retries": 3,
+            "restart_backoff": {
+                "initial_delay": "5s",
+                "max_restarts": 8,
+            },
+        }))
+        .unwrap();
+
+        let backoff = settings.restart_backoff();
+        assert_eq!(backoff.initial_delay(), std::time::Duration::from_secs(5));
+        assert_eq!(backoff.max_delay(), std::time::Duration::from_mins(5));
+        assert_eq!(backoff.max_restarts(), super::MaxRetries::Num(8));
+        assert_eq!(backoff.reset_after(), std::time::Duration::from_mins(10));
+
+        let settings: super::Settings = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert!(super::RestartBackoff::is_default(
+            settings.restart_backoff()
+        ));
+    }
 }
@@ -19,6 +19,11 @@ pub struct DockerConfig {
     #[serde(skip_serializing_if = "Option::is_none")]
     auth: Option<docker::models::AuthConfig>,
 
+    /// A `docker save` tarball or an OCI image layout directory on the device that holds the
+    /// image, for use when the image cannot be pulled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bundle: Option<std::path::PathBuf>,
+
     #[serde(
         default = "crate::base::default_allow_elevated_docker_permissions",
         skip_serializing
@@ -44,6 +49,7 @@ impl DockerConfig {
             create_options,
             digest,
             auth,
+            bundle: None,
             allow_elevated_docker_permissions,
         })
     }
@@ -103,6 +109,16 @@ impl DockerConfig {
         self
     }
 
+    pub fn bundle(&self) -> Option<&std::path::Path> {
+        self.bundle.as_deref()
+    }
+
+    #[must_use]
+    pub fn with_bundle(mut self, bundle: std::path::PathBuf) -> Self {
+        self.bundle = Some(bundle);
+        self
+    }
+
     pub fn allow_elevated_docker_permissions(&self) -> bool {
         self.allow_elevated_docker_permissions
     }
@@ -267,6 +283,20 @@ mod tests {
         });
         let config = serde_json::from_str::<DockerConfig>(&input_json.to_string()).unwrap();
         assert_eq!(config.image, "ubuntu");
+        assert_eq!(None, config.bundle());
+    }
+
+    #[test]
+    fn docker_config_deser_bundle() {
+        let input_json = json!({
+            "image": "contoso.azurecr.io/app:1.0",
+            "bundle": "/var/lib/bundles/app.tar"
+        });
+        let config = serde_json::from_str::<DockerConfig>(&input_json.to_string()).unwrap();
+        assert_eq!(
+            Some(std::path::Path::new("/var/lib/bundles/app.tar")),
+            config.bundle()
+        );
     }
 
     #[test]
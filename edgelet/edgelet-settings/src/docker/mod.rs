@@ -11,7 +11,13 @@ pub struct Settings {
     #[serde(flatten)]
     pub base: crate::base::Settings<config::DockerConfig>,
 
+    #[serde(default, skip_serializing_if = "runtime::RuntimeBackend::is_default")]
+    pub runtime_backend: runtime::RuntimeBackend,
+
     pub moby_runtime: runtime::MobyRuntime,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cri_runtime: Option<runtime::CriRuntime>,
 }
 
 pub const CONFIG_FILE_DEFAULT: &str = "/etc/aziot/edged/config.toml";
@@ -33,15 +39,29 @@ impl Settings {
         let mut settings: Settings =
             config_common::read_config(config_path, Some(config_directory_path))?;
 
+        if settings.runtime_backend == runtime::RuntimeBackend::Cri
+            && settings.cri_runtime.is_none()
+        {
+            return Err("runtime_backend is \"cri\" but [cri_runtime] is not configured".into());
+        }
+
         init::agent_spec(&mut settings)?;
 
         Ok(settings)
     }
 
+    pub fn runtime_backend(&self) -> runtime::RuntimeBackend {
+        self.runtime_backend
+    }
+
     pub fn moby_runtime(&self) -> &runtime::MobyRuntime {
         &self.moby_runtime
     }
 
+    pub fn cri_runtime(&self) -> Option<&runtime::CriRuntime> {
+        self.cri_runtime.as_ref()
+    }
+
     #[must_use]
     pub fn agent_upstream_resolve(mut self, parent_hostname: &str) -> Self {
         crate::RuntimeSettings::agent_mut(&mut self)
@@ -130,6 +150,34 @@ impl crate::RuntimeSettings for Settings {
     fn image_garbage_collection(&self) -> &crate::base::image::ImagePruneSettings {
         self.base.image_garbage_collection()
     }
+
+    fn metrics(&self) -> &crate::base::metrics::Settings {
+        self.base.metrics()
+    }
+
+    fn resource_quotas(&self) -> &crate::base::quota::ResourceQuotas {
+        self.base.resource_quotas()
+    }
+
+    fn container_policy(&self) -> &crate::base::policy::ContainerPolicy {
+        self.base.container_policy()
+    }
+
+    fn image_pull(&self) -> &crate::base::pull::Settings {
+        self.base.image_pull()
+    }
+
+    fn module_certs(&self) -> &crate::base::cert::ModuleCerts {
+        self.base.module_certs()
+    }
+
+    fn data_encryption(&self) -> &crate::base::encryption::DataEncryption {
+        self.base.data_encryption()
+    }
+
+    fn edge_ca_rollover(&self) -> &crate::base::rollover::EdgeCaRollover {
+        self.base.edge_ca_rollover()
+    }
 }
 
 #[cfg(test)]
@@ -155,6 +203,8 @@ mod tests {
     static GOOD_SETTINGS_CONTENT_TRUST: &str = "test-files/sample_settings_content_trust.toml";
     static GOOD_SETTINGS_NETWORK: &str = "test-files/sample_settings.network.toml";
     static GOOD_SETTINGS_IMAGE_GC: &str = "test-files/sample_settings_image_gc.toml";
+    static GOOD_SETTINGS_CRI: &str = "test-files/sample_settings_cri.toml";
+    static BAD_SETTINGS_CRI: &str = "test-files/bad_settings_cri.toml";
 
     #[test]
     fn err_no_file() {
@@ -387,4 +437,62 @@ mod tests {
         let settings = Settings::new();
         assert!(settings.is_err());
     }
+
+    #[test]
+    fn runtime_backend_default() {
+        let _env_lock = ENV_LOCK.lock().expect("env lock poisoned");
+
+        unsafe {
+            std::env::set_var("AZIOT_EDGED_CONFIG", GOOD_SETTINGS);
+            std::env::set_var("AZIOT_EDGED_CONFIG_DIR", CONFIG_DIR);
+        }
+
+        let settings = Settings::new().unwrap();
+        assert_eq!(
+            settings.runtime_backend(),
+            crate::docker::runtime::RuntimeBackend::Moby
+        );
+        assert!(settings.cri_runtime().is_none());
+    }
+
+    #[test]
+    fn cri_runtime() {
+        let _env_lock = ENV_LOCK.lock().expect("env lock poisoned");
+
+        unsafe {
+            std::env::set_var("AZIOT_EDGED_CONFIG", GOOD_SETTINGS_CRI);
+            std::env::set_var("AZIOT_EDGED_CONFIG_DIR", CONFIG_DIR);
+        }
+
+        let settings = Settings::new().unwrap();
+        assert_eq!(
+            settings.runtime_backend(),
+            crate::docker::runtime::RuntimeBackend::Cri
+        );
+
+        let cri_runtime = settings.cri_runtime().unwrap();
+        assert_eq!(
+            cri_runtime.uri().as_str(),
+            "unix:///run/containerd/containerd.sock"
+        );
+        assert_eq!(cri_runtime.image_uri(), cri_runtime.uri());
+        assert_eq!(cri_runtime.pod_namespace(), DEFAULT_NETWORKID);
+        assert_eq!(
+            cri_runtime.log_directory(),
+            std::path::Path::new("/var/log/aziot/edged/pods")
+        );
+    }
+
+    #[test]
+    fn cri_runtime_missing() {
+        let _env_lock = ENV_LOCK.lock().expect("env lock poisoned");
+
+        unsafe {
+            std::env::set_var("AZIOT_EDGED_CONFIG", BAD_SETTINGS_CRI);
+            std::env::set_var("AZIOT_EDGED_CONFIG_DIR", CONFIG_DIR);
+        }
+
+        let settings = Settings::new();
+        assert!(settings.is_err());
+    }
 }
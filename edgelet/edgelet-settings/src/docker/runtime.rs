@@ -34,3 +34,86 @@ impl ContentTrust {
         self.ca_certs.as_ref()
     }
 }
+
+/// The container runtime that aziot-edged uses to run modules.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuntimeBackend {
+    /// Moby (Docker Engine API), configured by `[moby_runtime]`.
+    #[default]
+    Moby,
+
+    /// Any CRI endpoint, such as containerd's CRI plugin, configured by `[cri_runtime]`.
+    Cri,
+}
+
+impl RuntimeBackend {
+    pub fn is_default(&self) -> bool {
+        self == &RuntimeBackend::default()
+    }
+}
+
+impl std::fmt::Display for RuntimeBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuntimeBackend::Moby => f.write_str("moby"),
+            RuntimeBackend::Cri => f.write_str("cri"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct CriRuntime {
+    /// gRPC endpoint of the CRI runtime service.
+    pub uri: url::Url,
+
+    /// gRPC endpoint of the CRI image service. Defaults to `uri`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_uri: Option<url::Url>,
+
+    /// Namespace of the pod sandboxes created for modules.
+    #[serde(default = "default_pod_namespace")]
+    pub pod_namespace: String,
+
+    /// Directory in which the runtime writes module logs.
+    #[serde(default = "default_log_directory")]
+    pub log_directory: std::path::PathBuf,
+
+    /// Directory under which named volumes in module create options are created.
+    #[serde(default = "default_volume_directory")]
+    pub volume_directory: std::path::PathBuf,
+}
+
+fn default_pod_namespace() -> String {
+    crate::DEFAULT_NETWORKID.to_string()
+}
+
+fn default_log_directory() -> std::path::PathBuf {
+    "/var/log/aziot/edged/pods".into()
+}
+
+fn default_volume_directory() -> std::path::PathBuf {
+    "/var/lib/aziot/edged/volumes".into()
+}
+
+impl CriRuntime {
+    pub fn uri(&self) -> &url::Url {
+        &self.uri
+    }
+
+    pub fn image_uri(&self) -> &url::Url {
+        self.image_uri.as_ref().unwrap_or(&self.uri)
+    }
+
+    pub fn pod_namespace(&self) -> &str {
+        &self.pod_namespace
+    }
+
+    pub fn log_directory(&self) -> &std::path::Path {
+        &self.log_directory
+    }
+
+    pub fn volume_directory(&self) -> &std::path::Path {
+        &self.volume_directory
+    }
+}
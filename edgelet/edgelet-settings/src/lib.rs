@@ -13,7 +13,7 @@ pub use crate::docker::{
     CONFIG_FILE_DEFAULT, Settings,
     config::{DockerConfig, UPSTREAM_PARENT_KEYWORD},
     network::{Ipam, MobyNetwork},
-    runtime::{ContentTrust, MobyRuntime},
+    runtime::{ContentTrust, CriRuntime, MobyRuntime, RuntimeBackend},
 };
 
 /// ID of the device CA cert in certd and private key in keyd.
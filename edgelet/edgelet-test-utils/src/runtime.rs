@@ -1,6 +1,5 @@
 // Copyright (c) Microsoft. All rights reserved.
 
-use hyper::body::Incoming;
 
 #[derive(Clone, Default, serde::Deserialize, serde::Serialize)]
 pub struct Config {}
@@ -60,6 +59,26 @@ impl edgelet_core::ModuleRegistry for ModuleRegistry {
     async fn remove(&self, _name: &str) -> anyhow::Result<()> {
         unimplemented!()
     }
+
+    async fn image_exists(&self, _config: &Self::Config) -> anyhow::Result<bool> {
+        unimplemented!()
+    }
+
+    async fn load_bundle(&self, _config: &Self::Config) -> anyhow::Result<bool> {
+        unimplemented!()
+    }
+
+    async fn import(&self, _path: &std::path::Path) -> anyhow::Result<Vec<String>> {
+        unimplemented!()
+    }
+
+    async fn image_use(&self) -> anyhow::Result<Vec<edgelet_core::ImageUse>> {
+        unimplemented!()
+    }
+
+    fn pull_progress(&self) -> &edgelet_core::PullProgressBus {
+        unimplemented!()
+    }
 }
 
 pub struct Runtime {
@@ -103,15 +122,37 @@ impl edgelet_core::ModuleRuntime for Runtime {
         }
     }
 
-    // The functions below aren't used in tests.
-
     async fn create(
         &self,
-        _module: edgelet_settings::ModuleSpec<Self::Config>,
+        module: edgelet_settings::ModuleSpec<Self::Config>,
     ) -> anyhow::Result<()> {
-        unimplemented!()
+        if module.name() == "policyViolation" {
+            let violation = edgelet_core::PolicyViolation::new(
+                module.name().to_string(),
+                edgelet_core::PolicyRule::Privileged,
+                None,
+            );
+            let violations =
+                edgelet_core::PolicyViolations::new(module.name().to_string(), vec![violation]);
+
+            Err(anyhow::Error::new(violations).context(
+                edgelet_core::RuntimeOperation::CreateModule(module.name().to_string()),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn error_code(error: &anyhow::Error) -> hyper::StatusCode {
+        if error.root_cause().is::<edgelet_core::PolicyViolations>() {
+            hyper::StatusCode::FORBIDDEN
+        } else {
+            hyper::StatusCode::INTERNAL_SERVER_ERROR
+        }
     }
 
+    // The functions below aren't used in tests.
+
     async fn get(
         &self,
         _id: &str,
@@ -147,6 +188,10 @@ impl edgelet_core::ModuleRuntime for Runtime {
         unimplemented!()
     }
 
+    async fn module_metrics(&self) -> anyhow::Result<Vec<edgelet_core::ModuleMetrics>> {
+        unimplemented!()
+    }
+
     async fn list(&self) -> anyhow::Result<Vec<Self::Module>> {
         unimplemented!()
     }
@@ -161,11 +206,15 @@ impl edgelet_core::ModuleRuntime for Runtime {
         unimplemented!()
     }
 
+    async fn image_sizes(&self) -> anyhow::Result<std::collections::HashMap<String, u64>> {
+        unimplemented!()
+    }
+
     async fn logs(
         &self,
         _id: &str,
         _options: &edgelet_core::LogOptions,
-    ) -> anyhow::Result<Incoming> {
+    ) -> anyhow::Result<edgelet_core::LogStream> {
         unimplemented!()
     }
 
@@ -177,11 +226,26 @@ impl edgelet_core::ModuleRuntime for Runtime {
         unimplemented!()
     }
 
-    fn registry(&self) -> &Self::ModuleRegistry {
+    async fn data_root(&self) -> anyhow::Result<std::path::PathBuf> {
         unimplemented!()
     }
 
-    fn error_code(_error: &anyhow::Error) -> hyper::StatusCode {
+    async fn prune_containers(&self) -> anyhow::Result<u64> {
+        unimplemented!()
+    }
+
+    async fn prune_volumes(&self) -> anyhow::Result<u64> {
+        unimplemented!()
+    }
+
+    async fn events(
+        &self,
+        _since: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> anyhow::Result<edgelet_core::EventStream> {
+        unimplemented!()
+    }
+
+    fn registry(&self) -> &Self::ModuleRegistry {
         unimplemented!()
     }
 }
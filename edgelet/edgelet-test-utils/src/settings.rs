@@ -9,6 +9,10 @@ pub struct Settings {
 
     pub trust_bundle: Option<String>,
     pub manifest_trust_bundle: Option<String>,
+
+    pub module_certs: edgelet_settings::base::cert::ModuleCerts,
+    pub data_encryption: edgelet_settings::base::encryption::DataEncryption,
+    pub edge_ca_rollover: edgelet_settings::base::rollover::EdgeCaRollover,
 }
 
 impl edgelet_settings::RuntimeSettings for Settings {
@@ -38,6 +42,18 @@ impl edgelet_settings::RuntimeSettings for Settings {
         self.manifest_trust_bundle.as_deref()
     }
 
+    fn module_certs(&self) -> &edgelet_settings::base::cert::ModuleCerts {
+        &self.module_certs
+    }
+
+    fn data_encryption(&self) -> &edgelet_settings::base::encryption::DataEncryption {
+        &self.data_encryption
+    }
+
+    fn edge_ca_rollover(&self) -> &edgelet_settings::base::rollover::EdgeCaRollover {
+        &self.edge_ca_rollover
+    }
+
     // The functions below aren't used in tests.
 
     fn hostname(&self) -> &str {
@@ -91,4 +107,20 @@ impl edgelet_settings::RuntimeSettings for Settings {
     fn image_garbage_collection(&self) -> &edgelet_settings::base::image::ImagePruneSettings {
         unimplemented!()
     }
+
+    fn metrics(&self) -> &edgelet_settings::base::metrics::Settings {
+        unimplemented!()
+    }
+
+    fn resource_quotas(&self) -> &edgelet_settings::base::quota::ResourceQuotas {
+        unimplemented!()
+    }
+
+    fn container_policy(&self) -> &edgelet_settings::base::policy::ContainerPolicy {
+        unimplemented!()
+    }
+
+    fn image_pull(&self) -> &edgelet_settings::base::pull::Settings {
+        unimplemented!()
+    }
 }
@@ -0,0 +1,122 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::io::Write;
+use std::time::Duration;
+
+use anyhow::Context;
+use chrono::{DateTime, SecondsFormat, Utc};
+use tabwriter::TabWriter;
+
+use edgelet_core::CertInfo;
+
+use crate::error::Error;
+use crate::{MgmtClient, OutputFormat};
+
+pub struct Certs<W> {
+    client: MgmtClient,
+    output: W,
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry<'a> {
+    #[serde(flatten)]
+    cert: &'a CertInfo,
+    expires_soon: bool,
+}
+
+impl<W> Certs<W>
+where
+    W: Write,
+{
+    pub fn new(client: MgmtClient, output: W) -> Self {
+        Certs { client, output }
+    }
+
+    /// Lists the certificates on the device, and flags the ones that have expired or expire
+    /// within `warning_threshold`.
+    pub async fn list(
+        mut self,
+        warning_threshold: Duration,
+        output_format: OutputFormat,
+    ) -> anyhow::Result<()> {
+        let mut certs = self.client.list_certificates().await?;
+        certs.sort_by(|a, b| {
+            (a.kind, &a.module_id, a.not_after).cmp(&(b.kind, &b.module_id, b.not_after))
+        });
+
+        let now = Utc::now();
+        let entries: Vec<_> = certs
+            .iter()
+            .map(|cert| Entry {
+                cert,
+                expires_soon: cert.expires_within(warning_threshold, now),
+            })
+            .collect();
+
+        match output_format {
+            OutputFormat::Json => {
+                serde_json::to_writer_pretty(&mut self.output, &entries)
+                    .context(Error::WriteToStdout)?;
+                writeln!(self.output).context(Error::WriteToStdout)?;
+            }
+
+            OutputFormat::Text => {
+                let mut w = TabWriter::new(&mut self.output).minwidth(10);
+                writeln!(
+                    w,
+                    "KIND\tMODULE\tSUBJECT\tSANS\tISSUERS\tKEY\tEXPIRES\tSTATUS"
+                )
+                .context(Error::WriteToStdout)?;
+                for entry in &entries {
+                    let cert = entry.cert;
+                    writeln!(
+                        w,
+                        "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                        cert.kind,
+                        cert.module_id.as_deref().unwrap_or("-"),
+                        cert.subject,
+                        join_or_dash(&cert.subject_alt_names, ","),
+                        join_or_dash(&cert.issuers, " <- "),
+                        cert.key_type,
+                        cert.not_after.to_rfc3339_opts(SecondsFormat::Secs, true),
+                        status(cert.not_after, entry.expires_soon, now),
+                    )
+                    .context(Error::WriteToStdout)?;
+                }
+                w.flush().context(Error::WriteToStdout)?;
+
+                let expiring = entries.iter().filter(|entry| entry.expires_soon).count();
+                if expiring > 0 {
+                    writeln!(
+                        self.output,
+                        "\n{expiring} of {} certificates have expired or expire within {}",
+                        entries.len(),
+                        humantime::format_duration(warning_threshold)
+                    )
+                    .context(Error::WriteToStdout)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn status(not_after: DateTime<Utc>, expires_soon: bool, now: DateTime<Utc>) -> &'static str {
+    if not_after <= now {
+        "expired"
+    } else if expires_soon {
+        "expiring"
+    } else {
+        "ok"
+    }
+}
+
+fn join_or_dash(values: &[String], separator: &str) -> String {
+    if values.is_empty() {
+        "-".to_string()
+    } else {
+        values.join(separator)
+    }
+}
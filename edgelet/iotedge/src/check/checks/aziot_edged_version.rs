@@ -20,7 +20,7 @@ pub(crate) struct AziotEdgedVersion {
 
 #[async_trait::async_trait]
 impl Checker for AziotEdgedVersion {
-    fn meta(&self) -> CheckerMeta {
+    fn meta(&self) -> CheckerMeta<'_> {
         CheckerMeta {
             id: "aziot-edge-version",
             description: "aziot-edge package is up-to-date",
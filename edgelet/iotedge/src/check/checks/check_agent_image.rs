@@ -10,7 +10,7 @@ pub(crate) struct CheckAgentImage {}
 
 #[async_trait::async_trait]
 impl Checker for CheckAgentImage {
-    fn meta(&self) -> CheckerMeta {
+    fn meta(&self) -> CheckerMeta<'_> {
         CheckerMeta {
             id: "check-agent-image",
             description: "Agent image is valid and can be pulled from upstream",
@@ -41,7 +41,7 @@ impl CheckAgentImage {
             return Ok(CheckResult::Skipped);
         };
 
-        let Some(docker_host_arg) = &check.docker_host_arg else {
+        let Some(docker) = &check.docker else {
             return Ok(CheckResult::Skipped);
         };
 
@@ -59,44 +59,16 @@ impl CheckAgentImage {
             }
         }
 
-        let server_address = settings
-            .agent()
-            .config()
-            .auth()
-            .and_then(|auth_config| auth_config.server_address.as_ref());
-
-        if let (Some(username), Some(password), Some(server_address)) = (
-            &settings
-                .agent()
-                .config()
-                .auth()
-                .and_then(|auth_config| auth_config.username.as_ref()),
-            &settings
-                .agent()
-                .config()
-                .auth()
-                .and_then(|auth_config| auth_config.password.as_ref()),
-            server_address,
-        ) {
-            super::docker(
-                docker_host_arg,
-                vec![
-                    "login",
-                    server_address,
-                    "-p",
-                    password,
-                    "--username",
-                    username,
-                ],
-            )
-            .await
-            .map_err(|(_, err)| err)
-            .context(format!("Failed to login to {server_address}"))?;
-        }
+        let x_registry_auth = match settings.agent().config().auth() {
+            Some(auth) => {
+                let auth = serde_json::to_string(auth).context("Failed to encode credentials")?;
+                base64::Engine::encode(&base64::engine::general_purpose::URL_SAFE, auth)
+            }
+            None => String::new(),
+        };
 
-        super::docker(docker_host_arg, vec!["pull", &agent_image])
+        super::pull_image(docker, &agent_image, &x_registry_auth)
             .await
-            .map_err(|(_, err)| err)
             .context("Failed to get edge Agent image")?;
 
         Ok(CheckResult::Ok)
@@ -1,6 +1,3 @@
-use std::borrow::Cow;
-use std::ffi::{OsStr, OsString};
-
 use anyhow::{Context, anyhow};
 
 use edgelet_core::{self, UrlExt};
@@ -16,7 +13,7 @@ pub(crate) struct ConnectManagementUri {
 
 #[async_trait::async_trait]
 impl Checker for ConnectManagementUri {
-    fn meta(&self) -> CheckerMeta {
+    fn meta(&self) -> CheckerMeta<'_> {
         CheckerMeta {
             id: "connect-management-uri",
             description: "configuration has correct URIs for daemon mgmt endpoint",
@@ -36,7 +33,7 @@ impl ConnectManagementUri {
             return Ok(CheckResult::Skipped);
         };
 
-        let Some(docker_host_arg) = &check.docker_host_arg else {
+        let Some(docker) = &check.docker else {
             return Ok(CheckResult::Skipped);
         };
 
@@ -58,15 +55,23 @@ impl ConnectManagementUri {
         self.connect_management_uri = Some(format!("{connect_management_uri}"));
         self.listen_management_uri = Some(format!("{listen_management_uri}"));
 
-        let mut args: Vec<Cow<'_, OsStr>> = vec![
-            Cow::Borrowed(OsStr::new("run")),
-            Cow::Borrowed(OsStr::new("--rm")),
-        ];
-
-        for (name, value) in settings.agent().env() {
-            args.push(Cow::Borrowed(OsStr::new("-e")));
-            args.push(Cow::Owned(format!("{name}={value}").into()));
-        }
+        let mut container = super::diagnostics_container(
+            diagnostics_image_name,
+            [
+                "edge-agent".to_owned(),
+                "--management-uri".to_owned(),
+                connect_management_uri.to_string(),
+            ],
+        );
+
+        container.env = Some(
+            settings
+                .agent()
+                .env()
+                .iter()
+                .map(|(name, value)| format!("{name}={value}"))
+                .collect(),
+        );
 
         match (
             connect_management_uri.scheme(),
@@ -75,8 +80,6 @@ impl ConnectManagementUri {
             ("http", "http") => (),
 
             ("unix", "unix" | "fd") => {
-                args.push(Cow::Borrowed(OsStr::new("-v")));
-
                 let socket_path = connect_management_uri.to_uds_file_path().context(
                     "Could not parse connect.management_uri: does not represent a valid file path",
                 )?;
@@ -85,7 +88,8 @@ impl ConnectManagementUri {
                     anyhow!("Could not parse connect.management_uri: file path is not valid utf-8")
                 })?;
 
-                args.push(Cow::Owned(format!("{socket_path}:{socket_path}").into()));
+                container.host_config.get_or_insert_default().binds =
+                    Some(vec![format!("{socket_path}:{socket_path}")]);
             }
 
             (scheme1, scheme2) if scheme1 != scheme2 => {
@@ -101,19 +105,8 @@ impl ConnectManagementUri {
             }
         }
 
-        args.extend(vec![
-            Cow::Borrowed(OsStr::new(&diagnostics_image_name)),
-            Cow::Borrowed(OsStr::new("dotnet")),
-            Cow::Borrowed(OsStr::new("IotedgeDiagnosticsDotnet.dll")),
-            Cow::Borrowed(OsStr::new("edge-agent")),
-            Cow::Borrowed(OsStr::new("--management-uri")),
-            Cow::Owned(OsString::from(connect_management_uri.to_string())),
-        ]);
-
-        match super::docker(docker_host_arg, args).await {
-            Ok(_) => Ok(CheckResult::Ok),
-            Err((Some(stderr), err)) => Err(err.context(stderr)),
-            Err((None, err)) => Err(err.context("Could not spawn docker process")),
-        }
+        super::run_container(docker, container).await?;
+
+        Ok(CheckResult::Ok)
     }
 }
@@ -1,3 +1,4 @@
+use docker::DockerApi;
 use edgelet_settings::RuntimeSettings;
 
 use serde::Deserialize;
@@ -64,7 +65,7 @@ pub(crate) struct ContainerConnectUpstream {
 
 #[async_trait::async_trait]
 impl Checker for ContainerConnectUpstream {
-    fn meta(&self) -> CheckerMeta {
+    fn meta(&self) -> CheckerMeta<'_> {
         CheckerMeta {
             id: self.id,
             description: self.description,
@@ -82,7 +83,7 @@ impl ContainerConnectUpstream {
             return CheckResult::Skipped;
         };
 
-        let Some(docker_host_arg) = &check.docker_host_arg else {
+        let Some(docker) = &check.docker else {
             return CheckResult::Skipped;
         };
 
@@ -108,17 +109,16 @@ impl ContainerConnectUpstream {
 
         self.upstream_hostname = Some(upstream_hostname.clone());
 
-        let upstream_protocol =
-            get_env_from_container(docker_host_arg, "edgeAgent", "UpstreamProtocol")
-                .await
-                .unwrap_or(
-                    // We should default to AMQP with fallback to AMQPWS.
-                    if self.upstream_port == UpstreamProtocolPort::Https {
-                        UpstreamProtocol::AmqpWs
-                    } else {
-                        UpstreamProtocol::Amqp
-                    },
-                );
+        let upstream_protocol = get_env_from_container(docker, "edgeAgent", "UpstreamProtocol")
+            .await
+            .unwrap_or(
+                // We should default to AMQP with fallback to AMQPWS.
+                if self.upstream_port == UpstreamProtocolPort::Https {
+                    UpstreamProtocol::AmqpWs
+                } else {
+                    UpstreamProtocol::Amqp
+                },
+            );
 
         let should_skip_instead = should_skip_instead(self.upstream_port, upstream_protocol);
 
@@ -129,29 +129,10 @@ impl ContainerConnectUpstream {
         let network_name = settings.moby_runtime().network().name();
         self.network_name = Some(network_name.to_owned());
 
-        let mut args = vec!["run", "--rm"];
-
         let port = self.upstream_port.as_port().to_string();
 
-        if self.use_container_runtime_network {
-            args.extend(["--network", network_name]);
-        }
-
-        if check.parent_hostname.is_some() {
-            args.extend(["-v", &map_volume]);
-        }
-
         self.diagnostics_image_name = Some(check.diagnostics_image_name.clone());
-        args.extend([
-            &diagnostics_image_name,
-            "dotnet",
-            "IotedgeDiagnosticsDotnet.dll",
-            "upstream",
-            "--hostname",
-            upstream_hostname,
-            "--port",
-            &port,
-        ]);
+        let mut args = vec!["upstream", "--hostname", upstream_hostname, "--port", &port];
 
         if check.parent_hostname.is_some() {
             args.extend(["--isNested", "true"]);
@@ -169,7 +150,20 @@ impl ContainerConnectUpstream {
             return CheckResult::SkippedDueTo("not required in this configuration".into());
         }
 
-        if let Err((_, err)) = super::docker(docker_host_arg, args).await {
+        let mut container = super::diagnostics_container(diagnostics_image_name, args);
+        let host_config = container.host_config.get_or_insert_default();
+
+        if self.use_container_runtime_network {
+            host_config
+                .other_properties
+                .insert("NetworkMode".to_owned(), network_name.into());
+        }
+
+        if check.parent_hostname.is_some() {
+            host_config.binds = Some(vec![map_volume]);
+        }
+
+        if let Err(err) = super::run_container(docker, container).await {
             let err = err.context(format!(
                 "Container on the {} network could not connect to {}:{}",
                 if self.use_container_runtime_network {
@@ -214,35 +208,19 @@ enum UpstreamProtocol {
 }
 
 async fn get_env_from_container(
-    docker_host_arg: &str,
+    docker: &super::DockerClient,
     name: &str,
     env_var_name: &str,
 ) -> Option<UpstreamProtocol> {
-    let shell_var = to_shell_var(env_var_name);
-    let command = format!("echo {shell_var}");
-    super::docker(docker_host_arg, &["exec", name, "/bin/sh", "-c", &command])
-        .await
-        .map_err(|(_, err)| err)
-        .and_then(|output| {
-            let mut s = String::from_utf8(output)?;
-            // Remove newline
-            if s.ends_with('\n') {
-                s.pop();
-            }
-            Ok(s)
-        })
-        .and_then(|string| {
-            let string = to_serde_enum(string);
-            let up = serde_json::from_str::<UpstreamProtocol>(&string)?;
-            Ok(up)
-        })
-        .ok()
-}
-
-fn to_shell_var(val: impl Into<String>) -> String {
-    let dollar = String::from("$");
-    let val_str = val.into();
-    dollar + &val_str
+    let inspect_result = docker.container_inspect(name, false).await.ok()?;
+    let prefix = format!("{env_var_name}=");
+    let value = inspect_result
+        .config?
+        .env?
+        .into_iter()
+        .find_map(|env| env.strip_prefix(&prefix).map(ToOwned::to_owned))?;
+
+    serde_json::from_str::<UpstreamProtocol>(&to_serde_enum(value)).ok()
 }
 
 fn to_serde_enum(val: impl Into<String>) -> String {
@@ -12,7 +12,7 @@ pub(crate) struct ContainerEngineDns {
 
 #[async_trait::async_trait]
 impl Checker for ContainerEngineDns {
-    fn meta(&self) -> CheckerMeta {
+    fn meta(&self) -> CheckerMeta<'_> {
         CheckerMeta {
             id: "container-engine-dns",
             description: "DNS server",
@@ -1,16 +1,19 @@
-use crate::check::{Check, CheckResult, Checker, CheckerMeta};
+use anyhow::Context;
+
+use docker::{DockerApi, DockerApiClient};
+use http_common::Connector;
 
-use anyhow::anyhow;
+use crate::check::{Check, CheckResult, Checker, CheckerMeta};
 
 #[derive(Default, serde::Serialize)]
 pub(crate) struct ContainerEngineInstalled {
-    docker_host_arg: Option<String>,
+    docker_uri: Option<String>,
     docker_server_version: Option<String>,
 }
 
 #[async_trait::async_trait]
 impl Checker for ContainerEngineInstalled {
-    fn meta(&self) -> CheckerMeta {
+    fn meta(&self) -> CheckerMeta<'_> {
         CheckerMeta {
             id: "container-engine-uri",
             description: "container engine is installed and functional",
@@ -32,38 +35,22 @@ impl ContainerEngineInstalled {
 
         let uri = settings.moby_runtime().uri();
 
-        let docker_host_arg = match uri.scheme() {
-            "unix" => uri.to_string(),
-
-            "npipe" => {
-                let mut uri = uri.to_string();
-                uri.replace_range(0.."npipe://".len(), "npipe:////");
-                uri
-            }
-
-            scheme => {
-                return Err(anyhow!(
-                    "Could not communicate with container engine at {uri}. The scheme {scheme} is invalid."
-                ));
-            }
-        };
+        let connector = Connector::new(uri)
+            .with_context(|| format!("Could not communicate with container engine at {uri}."))?;
+        let docker = DockerApiClient::new(connector);
 
-        let output = super::docker(
-            &docker_host_arg,
-            &["version", "--format", "{{.Server.Version}}"],
-        )
-        .await;
-        let output = match output {
-            Ok(output) => output,
-            Err((message, err)) => {
+        let info = match docker.system_info().await {
+            Ok(info) => info,
+            Err(err) => {
                 let mut error_message = format!(
                     "Could not communicate with container engine at {uri}.\n\
                      Please check your moby-engine installation and ensure the service is running.",
                 );
 
-                if let Some(message) = message
-                    && message.contains("Got permission denied")
-                {
+                if err.chain().any(|err| {
+                    err.downcast_ref::<std::io::Error>()
+                        .is_some_and(|err| err.kind() == std::io::ErrorKind::PermissionDenied)
+                }) {
                     error_message += "\nYou might need to run this command as root.";
                     return Ok(CheckResult::Fatal(err.context(error_message)));
                 }
@@ -72,15 +59,15 @@ impl ContainerEngineInstalled {
             }
         };
 
-        check.docker_host_arg = Some(docker_host_arg);
+        check.docker = Some(docker);
 
-        check.docker_server_version = Some(String::from_utf8_lossy(&output).trim().to_owned());
+        check.docker_server_version = info.server_version;
         check
             .additional_info
             .docker_version
             .clone_from(&check.docker_server_version);
 
-        self.docker_host_arg.clone_from(&check.docker_host_arg);
+        self.docker_uri = Some(uri.to_string());
         self.docker_server_version
             .clone_from(&check.docker_server_version);
 
@@ -14,7 +14,7 @@ pub(crate) struct ContainerEngineIPv6 {
 
 #[async_trait::async_trait]
 impl Checker for ContainerEngineIPv6 {
-    fn meta(&self) -> CheckerMeta {
+    fn meta(&self) -> CheckerMeta<'_> {
         CheckerMeta {
             id: "container-engine-ipv6",
             description: "IPv6 network configuration",
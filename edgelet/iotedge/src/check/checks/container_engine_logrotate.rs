@@ -11,7 +11,7 @@ pub(crate) struct ContainerEngineLogrotate {
 
 #[async_trait::async_trait]
 impl Checker for ContainerEngineLogrotate {
-    fn meta(&self) -> CheckerMeta {
+    fn meta(&self) -> CheckerMeta<'_> {
         CheckerMeta {
             id: "container-engine-logrotate",
             description: "production readiness: logs policy",
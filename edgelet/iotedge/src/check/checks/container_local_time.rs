@@ -13,7 +13,7 @@ pub(crate) struct ContainerLocalTime {
 
 #[async_trait::async_trait]
 impl Checker for ContainerLocalTime {
-    fn meta(&self) -> CheckerMeta {
+    fn meta(&self) -> CheckerMeta<'_> {
         CheckerMeta {
             id: "container-local-time",
             description: "container time is close to host time",
@@ -29,7 +29,7 @@ impl Checker for ContainerLocalTime {
 
 impl ContainerLocalTime {
     async fn inner_execute(&mut self, check: &mut Check) -> anyhow::Result<CheckResult> {
-        let Some(docker_host_arg) = &check.docker_host_arg else {
+        let Some(docker) = &check.docker else {
             return Ok(CheckResult::Skipped);
         };
 
@@ -45,19 +45,11 @@ impl ContainerLocalTime {
             check.diagnostics_image_name.clone()
         };
 
-        let output = super::docker(
-            docker_host_arg,
-            vec![
-                "run",
-                "--rm",
-                &diagnostics_image_name,
-                "dotnet",
-                "IotedgeDiagnosticsDotnet.dll",
-                "local-time",
-            ],
+        let output = super::run_container(
+            docker,
+            super::diagnostics_container(diagnostics_image_name, ["local-time"]),
         )
         .await
-        .map_err(|(_, err)| err)
         .context("Could not query local time inside container")?;
 
         let output = std::str::from_utf8(&output)
@@ -11,7 +11,7 @@ pub(crate) struct ContainerResolveParentHostname {}
 
 #[async_trait::async_trait]
 impl Checker for ContainerResolveParentHostname {
-    fn meta(&self) -> CheckerMeta {
+    fn meta(&self) -> CheckerMeta<'_> {
         CheckerMeta {
             id: "container-resolve-parent-hostname",
             description: "parent hostname is resolvable from inside container",
@@ -55,36 +55,25 @@ impl ContainerResolveParentHostname {
             check.diagnostics_image_name.clone()
         };
 
-        let Some(docker_host_arg) = &check.docker_host_arg else {
+        let Some(docker) = &check.docker else {
             return Ok(CheckResult::Skipped);
         };
 
-        let mut args = vec!["run".to_owned(), "--rm".to_owned()];
+        let mut container = super::diagnostics_container(
+            diagnostics_image_name,
+            ["parent-hostname", "--parent-hostname", &parent_hostname],
+        );
 
-        for host in settings
+        container.host_config.get_or_insert_default().extra_hosts = settings
             .agent()
             .config()
             .create_options()
             .host_config
             .as_ref()
-            .and_then(|host_config| host_config.extra_hosts.as_deref())
-            .unwrap_or_default()
-        {
-            args.push(format!("--add-host={host}"));
-        }
-
-        args.extend(vec![
-            diagnostics_image_name,
-            "dotnet".to_owned(),
-            "IotedgeDiagnosticsDotnet.dll".to_owned(),
-            "parent-hostname".to_owned(),
-            "--parent-hostname".to_owned(),
-            parent_hostname.clone(),
-        ]);
+            .and_then(|host_config| host_config.extra_hosts.clone());
 
-        super::docker(docker_host_arg, args)
+        super::run_container(docker, container)
             .await
-            .map_err(|(_, err)| err)
             .context(format!(
                 "Failed to resolve parent hostname {parent_hostname}"
             ))?;
@@ -12,6 +12,7 @@ mod parent_hostname;
 mod proxy_settings;
 mod storage_mounted_from_host;
 mod up_to_date_config;
+mod user_defined;
 mod well_formed_config;
 
 pub(crate) use self::aziot_edged_version::AziotEdgedVersion;
@@ -28,40 +29,157 @@ pub(crate) use self::parent_hostname::ParentHostname;
 pub(crate) use self::proxy_settings::ProxySettings;
 pub(crate) use self::storage_mounted_from_host::{EdgeAgentStorageMounted, EdgeHubStorageMounted};
 pub(crate) use self::up_to_date_config::UpToDateConfig;
+pub(crate) use self::user_defined::user_defined_checks;
 pub(crate) use self::well_formed_config::WellFormedConfig;
 
-use std::ffi::OsStr;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use anyhow::{Context, anyhow};
+use http_body_util::BodyExt;
+
+use docker::apis::ApiError;
+use docker::models::{ContainerCreateBody, CreateImageInfo};
+use docker::{DockerApi, DockerApiClient};
+use http_common::Connector;
 
 use super::Checker;
 
-pub(crate) async fn docker<I>(
-    docker_host_arg: &str,
-    args: I,
-) -> Result<Vec<u8>, (Option<String>, anyhow::Error)>
+pub(crate) type DockerClient = DockerApiClient<Connector>;
+
+/// A container that runs the diagnostics image with `args`.
+pub(crate) fn diagnostics_container<I>(image: String, args: I) -> ContainerCreateBody
 where
     I: IntoIterator,
-    <I as IntoIterator>::Item: AsRef<OsStr>,
+    <I as IntoIterator>::Item: Into<String>,
 {
-    let mut process = tokio::process::Command::new("docker");
-    process.arg("-H");
-    process.arg(docker_host_arg);
+    let cmd = ["dotnet", "IotedgeDiagnosticsDotnet.dll"]
+        .into_iter()
+        .map(Into::into)
+        .chain(args.into_iter().map(Into::into))
+        .collect();
+
+    ContainerCreateBody {
+        image: Some(image),
+        cmd: Some(cmd),
+        ..Default::default()
+    }
+}
 
-    process.args(args);
+/// Runs a container to completion like `docker run --rm` does, pulling its image if it is
+/// missing, and returns its stdout.
+pub(crate) async fn run_container(
+    docker: &DockerClient,
+    container: ContainerCreateBody,
+) -> anyhow::Result<Vec<u8>> {
+    let image = container.image.clone().unwrap_or_default();
 
-    let output = process.output().await.map_err(|err| {
-        (
-            None,
-            anyhow::Error::from(err).context(format!("could not run {process:?}")),
-        )
-    })?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
-        let err = anyhow::anyhow!("docker returned {}, stderr = {stderr}", output.status);
-        return Err((Some(stderr), err));
+    let created = match docker.container_create("", "", container.clone()).await {
+        Err(err) if is_not_found(&err) => {
+            pull_image(docker, &image, "").await?;
+            docker.container_create("", "", container).await
+        }
+        created => created,
+    }
+    .with_context(|| format!("could not create container from {image}"))?;
+
+    let output = run_created_container(docker, &created.id).await;
+
+    // The container is removed even if it failed, and failing to remove it does not fail the check.
+    let _ = docker
+        .container_delete(&created.id, true, true, false)
+        .await;
+
+    output
+}
+
+async fn run_created_container(docker: &DockerClient, id: &str) -> anyhow::Result<Vec<u8>> {
+    // Attach before starting the container so that none of its output is missed.
+    let output = docker
+        .container_attach(id, true, true, true)
+        .await
+        .context("could not attach to container")?;
+    docker
+        .container_start(id, "")
+        .await
+        .context("could not start container")?;
+
+    // The stream ends when the container exits.
+    let output = output
+        .collect()
+        .await
+        .context("could not read container output")?
+        .to_bytes();
+    let (stdout, stderr) = demultiplex(&output);
+
+    let exit = docker
+        .container_wait(id, "not-running")
+        .await
+        .context("could not wait for container to exit")?;
+    if exit.status_code != 0 {
+        let stderr = String::from_utf8_lossy(&stderr);
+        return Err(anyhow!(
+            "container exited with {}, stderr = {stderr}",
+            exit.status_code
+        ));
+    }
+
+    Ok(stdout)
+}
+
+/// Pulls an image like `docker pull` does. `x_registry_auth` is the base64-encoded JSON of the
+/// registry credentials, or empty.
+pub(crate) async fn pull_image(
+    docker: &DockerClient,
+    image: &str,
+    x_registry_auth: &str,
+) -> anyhow::Result<()> {
+    let progress = docker
+        .image_pull(image, "", "", x_registry_auth)
+        .await
+        .with_context(|| format!("could not pull {image}"))?;
+    let progress = progress
+        .collect()
+        .await
+        .with_context(|| format!("could not pull {image}"))?
+        .to_bytes();
+
+    for info in serde_json::Deserializer::from_slice(&progress).into_iter::<CreateImageInfo>() {
+        if let Some(detail) = info?.error_detail {
+            return Err(anyhow!(ApiError::from_error_detail(detail)))
+                .with_context(|| format!("could not pull {image}"));
+        }
     }
 
-    Ok(output.stdout)
+    Ok(())
+}
+
+pub(crate) fn is_not_found(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<ApiError>()
+        .is_some_and(|err| err.code == hyper::StatusCode::NOT_FOUND)
+}
+
+/// Splits the output of a container without a TTY into its stdout and stderr. Each frame is an
+/// 8-byte header (stream type, 3 padding bytes, big-endian payload length) and the payload.
+fn demultiplex(mut output: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let mut stdout = Vec::new();
+    let mut stderr = Vec::new();
+
+    while let Some((header, rest)) = output.split_first_chunk::<8>() {
+        let len = u32::from_be_bytes([header[4], header[5], header[6], header[7]]);
+        let len = usize::try_from(len).unwrap_or(usize::MAX).min(rest.len());
+        let (payload, rest) = rest.split_at(len);
+
+        if header[0] == 2 {
+            stderr.extend_from_slice(payload);
+        } else {
+            stdout.extend_from_slice(payload);
+        }
+
+        output = rest;
+    }
+
+    (stdout, stderr)
 }
 
 // built-in checks, as opposed to those that are deferred to `aziot check`
@@ -95,3 +213,42 @@ pub(crate) fn built_in_checks() -> [(&'static str, Vec<Box<dyn Checker>>); 2] {
         }),
     ]
 }
+
+/// The built-in checks, followed by the user-defined checks in `checks_dir` if there are any.
+pub(crate) fn all_checks(checks_dir: &Path) -> Vec<(&'static str, Vec<Box<dyn Checker>>)> {
+    let mut sections: Vec<_> = built_in_checks().into();
+
+    let built_in_ids: BTreeSet<_> = sections
+        .iter()
+        .flat_map(|(_, checks)| checks.iter().map(|check| check.meta().id))
+        .collect();
+    let user_checks = user_defined_checks(checks_dir, &built_in_ids);
+    if !user_checks.is_empty() {
+        sections.push(("User-defined checks", user_checks));
+    }
+
+    sections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::demultiplex;
+
+    #[test]
+    fn demultiplex_output() {
+        let mut output = Vec::new();
+        for (stream, payload) in [(1, "12"), (2, "warning\n"), (1, "34\n")] {
+            output.extend_from_slice(&[stream, 0, 0, 0]);
+            output.extend_from_slice(&u32::try_from(payload.len()).unwrap().to_be_bytes());
+            output.extend_from_slice(payload.as_bytes());
+        }
+
+        let (stdout, stderr) = demultiplex(&output);
+        assert_eq!(b"1234\n".as_slice(), stdout);
+        assert_eq!(b"warning\n".as_slice(), stderr);
+
+        // A truncated frame is kept rather than dropped.
+        let (stdout, _) = demultiplex(&output[..output.len() - 1]);
+        assert_eq!(b"1234".as_slice(), stdout);
+    }
+}
@@ -11,7 +11,7 @@ pub(crate) struct ParentHostname {
 
 #[async_trait::async_trait]
 impl Checker for ParentHostname {
-    fn meta(&self) -> CheckerMeta {
+    fn meta(&self) -> CheckerMeta<'_> {
         CheckerMeta {
             id: "parent_hostname",
             description: "configuration has correct parent_hostname",
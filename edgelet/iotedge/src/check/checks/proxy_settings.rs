@@ -5,7 +5,7 @@ pub(crate) struct ProxySettings {}
 
 #[async_trait::async_trait]
 impl Checker for ProxySettings {
-    fn meta(&self) -> CheckerMeta {
+    fn meta(&self) -> CheckerMeta<'_> {
         CheckerMeta {
             id: "proxy-settings",
             description: "proxy settings are consistent in aziot-edged, aziot-identityd, moby daemon and config.toml",
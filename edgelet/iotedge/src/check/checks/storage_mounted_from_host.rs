@@ -8,6 +8,8 @@ use std::{
 use anyhow::Context;
 use regex::Regex;
 
+use docker::DockerApi;
+
 use crate::check::{Check, CheckResult, Checker, CheckerMeta};
 
 #[derive(Default, serde::Serialize)]
@@ -18,7 +20,7 @@ pub(crate) struct EdgeAgentStorageMounted {
 
 #[async_trait::async_trait]
 impl Checker for EdgeAgentStorageMounted {
-    fn meta(&self) -> CheckerMeta {
+    fn meta(&self) -> CheckerMeta<'_> {
         CheckerMeta {
             id: "edge-agent-storage-mounted-from-host",
             description: "production readiness: Edge Agent's storage directory is persisted on the host filesystem",
@@ -46,7 +48,7 @@ pub struct EdgeHubStorageMounted {
 
 #[async_trait::async_trait]
 impl Checker for EdgeHubStorageMounted {
-    fn meta(&self) -> CheckerMeta {
+    fn meta(&self) -> CheckerMeta<'_> {
         CheckerMeta {
             id: "edge-hub-storage-mounted-from-host",
             description: "production readiness: Edge Hub's storage directory is persisted on the host filesystem",
@@ -78,11 +80,14 @@ async fn storage_mounted_from_host<'a>(
             .expect("This hard-coded regex is expected to be valid.")
     });
 
-    let Some(docker_host_arg) = &check.docker_host_arg else {
+    let Some(docker) = &check.docker else {
         return Ok(CheckResult::Skipped);
     };
 
-    let inspect_result = inspect_container(docker_host_arg, container_name).await?;
+    let inspect_result = docker
+        .container_inspect(container_name, false)
+        .await
+        .with_context(|| format!("Could not check current state of {container_name} container"))?;
 
     let temp_dir = inspect_result
         .config
@@ -137,19 +142,3 @@ async fn storage_mounted_from_host<'a>(
 
     Ok(CheckResult::Ok)
 }
-
-async fn inspect_container(
-    docker_host_arg: &str,
-    name: &str,
-) -> anyhow::Result<docker::models::ContainerInspectResponse> {
-    super::docker(docker_host_arg, &["inspect", name])
-        .await
-        .map_err(|(_, err)| err)
-        .and_then(|output| {
-            let (inspect_result,): (docker::models::ContainerInspectResponse,) =
-                serde_json::from_slice(&output)
-                    .context("Could not parse result of docker inspect")?;
-            Ok(inspect_result)
-        })
-        .with_context(|| format!("Could not check current state of {name} container"))
-}
@@ -7,7 +7,7 @@ pub(crate) struct UpToDateConfig {}
 
 #[async_trait::async_trait]
 impl Checker for UpToDateConfig {
-    fn meta(&self) -> CheckerMeta {
+    fn meta(&self) -> CheckerMeta<'_> {
         CheckerMeta {
             id: "config-up-to-date",
             description: "configuration up-to-date with config.toml",
@@ -0,0 +1,521 @@
+//! Checks that operators declare in TOML files, so that site-specific checks run alongside the
+//! built-in ones without changes to this tool.
+//!
+//! Each `*.toml` file in the checks directory holds any number of checks:
+//!
+//! ```toml
+//! [[check]]
+//! id = "plc-gateway-reachable"
+//! description = "PLC gateway accepts connections"
+//! severity = "warning"
+//! kind = "tcp"
+//! host = "10.0.0.5"
+//! port = 502
+//! ```
+
+use std::collections::BTreeSet;
+use std::io::ErrorKind;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, anyhow};
+
+use docker::models::ContainerCreateBody;
+use edgelet_core::{ModuleRuntime, ModuleStatus};
+use edgelet_settings::RuntimeSettings;
+
+use crate::MgmtClient;
+use crate::check::{Check, CheckResult, Checker, CheckerMeta};
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, serde::Deserialize)]
+struct ChecksFile {
+    #[serde(default, rename = "check")]
+    checks: Vec<Definition>,
+}
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+struct Definition {
+    id: String,
+    description: String,
+
+    #[serde(default)]
+    severity: Severity,
+
+    #[serde(flatten)]
+    probe: Probe,
+}
+
+/// How a failed check is reported. Warnings fail `iotedge check` only with `--warnings-as-errors`.
+#[derive(Clone, Copy, Debug, Default, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Severity {
+    Warning,
+    #[default]
+    Error,
+}
+
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Probe {
+    /// Runs `command` in a container of `image`, the diagnostics image by default, and fails if
+    /// it exits with an error.
+    Container {
+        command: Vec<String>,
+        image: Option<String>,
+        network: Option<String>,
+    },
+
+    /// Connects to `host:port`, and completes a TLS handshake with it if `tls` is set.
+    Tcp {
+        host: String,
+        port: u16,
+        #[serde(default)]
+        tls: bool,
+    },
+
+    /// Asserts that `path` exists, or not, and if it exists that its permissions are `mode`,
+    /// given in octal like "600".
+    File {
+        path: PathBuf,
+        #[serde(default = "default_exists")]
+        exists: bool,
+        mode: Option<String>,
+    },
+
+    /// Asserts that the module `name` has `status`.
+    Module {
+        name: String,
+        #[serde(default = "default_status")]
+        status: ModuleStatus,
+    },
+}
+
+fn default_exists() -> bool {
+    true
+}
+
+fn default_status() -> ModuleStatus {
+    ModuleStatus::Running
+}
+
+#[derive(serde::Serialize)]
+pub(crate) struct UserDefinedCheck {
+    file: PathBuf,
+    #[serde(flatten)]
+    definition: Definition,
+    output: Option<String>,
+}
+
+#[async_trait::async_trait]
+impl Checker for UserDefinedCheck {
+    fn meta(&self) -> CheckerMeta<'_> {
+        CheckerMeta {
+            id: &self.definition.id,
+            description: &self.definition.description,
+        }
+    }
+
+    async fn execute(&mut self, check: &mut Check) -> CheckResult {
+        match self.inner_execute(check).await {
+            Ok(result) => result,
+            Err(err) => match self.definition.severity {
+                Severity::Warning => CheckResult::Warning(err),
+                Severity::Error => CheckResult::Failed(err),
+            },
+        }
+    }
+}
+
+impl UserDefinedCheck {
+    async fn inner_execute(&mut self, check: &mut Check) -> anyhow::Result<CheckResult> {
+        match &self.definition.probe {
+            Probe::Container {
+                command,
+                image,
+                network,
+            } => {
+                let Some(docker) = &check.docker else {
+                    return Ok(CheckResult::Skipped);
+                };
+
+                let image = image.clone().unwrap_or_else(|| {
+                    if check
+                        .diagnostics_image_name
+                        .starts_with("/azureiotedge-diagnostics:")
+                    {
+                        check.parent_hostname.as_ref().map_or_else(
+                            || format!("mcr.microsoft.com{}", check.diagnostics_image_name),
+                            |upstream_hostname| {
+                                format!("{upstream_hostname}{}", check.diagnostics_image_name)
+                            },
+                        )
+                    } else {
+                        check.diagnostics_image_name.clone()
+                    }
+                });
+
+                let mut container = ContainerCreateBody {
+                    image: Some(image),
+                    cmd: Some(command.clone()),
+                    ..Default::default()
+                };
+                if let Some(network) = network {
+                    container
+                        .host_config
+                        .get_or_insert_default()
+                        .other_properties
+                        .insert("NetworkMode".to_owned(), network.clone().into());
+                }
+
+                let output = super::run_container(docker, container)
+                    .await
+                    .with_context(|| format!("{command:?} failed in container"))?;
+                self.output = Some(String::from_utf8_lossy(&output).trim_end().to_owned());
+            }
+
+            Probe::Tcp { host, port, tls } => {
+                let (host, port, tls) = (host.clone(), *port, *tls);
+                tokio::task::spawn_blocking(move || probe_tcp(&host, port, tls))
+                    .await
+                    .context("TCP probe panicked")??;
+            }
+
+            Probe::File { path, exists, mode } => {
+                let metadata = match std::fs::metadata(path) {
+                    Ok(metadata) => Some(metadata),
+                    Err(err) if err.kind() == ErrorKind::NotFound => None,
+                    Err(err) => {
+                        return Err(anyhow::Error::from(err)
+                            .context(format!("could not read metadata of {}", path.display())));
+                    }
+                };
+
+                match (metadata, *exists) {
+                    (None, true) => return Err(anyhow!("{} does not exist", path.display())),
+                    (Some(_), false) => return Err(anyhow!("{} exists", path.display())),
+                    (Some(metadata), true) => {
+                        let actual = metadata.permissions().mode() & 0o7777;
+                        self.output = Some(format!("{actual:o}"));
+
+                        if let Some(mode) = mode {
+                            let expected = parse_mode(mode)?;
+                            if actual != expected {
+                                return Err(anyhow!(
+                                    "{} has permissions {actual:o}, expected {expected:o}",
+                                    path.display()
+                                ));
+                            }
+                        }
+                    }
+                    (None, false) => (),
+                }
+            }
+
+            Probe::Module { name, status } => {
+                let Some(settings) = &check.settings else {
+                    return Ok(CheckResult::Skipped);
+                };
+
+                let client = MgmtClient::new(settings.connect().management_uri())?;
+                let modules = client
+                    .list()
+                    .await
+                    .context("could not list modules from aziot-edged")?;
+
+                let actual = modules
+                    .iter()
+                    .find(|module| &module.details.name == name)
+                    .map(|module| module.details.status.runtime_status.status.clone())
+                    .ok_or_else(|| anyhow!("module {name} is not deployed"))?;
+                self.output = Some(actual.clone());
+
+                if actual != status.to_string() {
+                    return Err(anyhow!("module {name} is {actual}, expected {status}"));
+                }
+            }
+        }
+
+        Ok(CheckResult::Ok)
+    }
+}
+
+/// Stands in for a checks file that could not be used, so that the problem is reported like a
+/// failed check rather than the file being ignored.
+#[derive(serde::Serialize)]
+pub(crate) struct InvalidChecksFile {
+    #[serde(skip)]
+    id: String,
+    #[serde(skip)]
+    description: String,
+    file: PathBuf,
+    error: String,
+}
+
+#[async_trait::async_trait]
+impl Checker for InvalidChecksFile {
+    fn meta(&self) -> CheckerMeta<'_> {
+        CheckerMeta {
+            id: &self.id,
+            description: &self.description,
+        }
+    }
+
+    async fn execute(&mut self, _check: &mut Check) -> CheckResult {
+        CheckResult::Failed(anyhow!("{}", self.error).context(format!(
+            "could not load user-defined checks from {}",
+            self.file.display()
+        )))
+    }
+}
+
+/// Loads the checks of every `*.toml` file in `dir`, in file name order. `reserved_ids` are the
+/// IDs of the built-in checks, which user-defined checks may not reuse.
+pub(crate) fn user_defined_checks(
+    dir: &Path,
+    reserved_ids: &BTreeSet<&str>,
+) -> Vec<Box<dyn Checker>> {
+    // Most devices have no user-defined checks, so a missing directory is not an error.
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<_> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .collect();
+    files.sort();
+
+    let mut ids: BTreeSet<String> = reserved_ids.iter().map(|&id| id.to_owned()).collect();
+    let mut checks: Vec<Box<dyn Checker>> = Vec::new();
+
+    for file in files {
+        match load_file(&file, &ids) {
+            Ok(definitions) => {
+                for definition in definitions {
+                    ids.insert(definition.id.clone());
+                    checks.push(Box::new(UserDefinedCheck {
+                        file: file.clone(),
+                        definition,
+                        output: None,
+                    }));
+                }
+            }
+            Err(err) => {
+                let file_name = file
+                    .file_name()
+                    .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+                checks.push(Box::new(InvalidChecksFile {
+                    id: format!("checks.d/{file_name}"),
+                    description: format!("user-defined checks in {file_name} are valid"),
+                    file,
+                    error: format!("{err:#}"),
+                }));
+            }
+        }
+    }
+
+    checks
+}
+
+fn load_file(file: &Path, ids: &BTreeSet<String>) -> anyhow::Result<Vec<Definition>> {
+    let contents = std::fs::read_to_string(file).context("could not read file")?;
+    let ChecksFile { checks } = toml::from_str(&contents).context("could not parse file")?;
+
+    let mut file_ids = BTreeSet::new();
+    for definition in &checks {
+        if definition.id.is_empty() || definition.id.contains(char::is_whitespace) {
+            return Err(anyhow!(
+                "check ID {:?} must be non-empty and contain no whitespace, so that it can be passed to --dont-run",
+                definition.id
+            ));
+        }
+
+        if ids.contains(&definition.id) || !file_ids.insert(definition.id.as_str()) {
+            return Err(anyhow!("check ID {} is already used", definition.id));
+        }
+
+        match &definition.probe {
+            Probe::Container { command, .. } if command.is_empty() => {
+                return Err(anyhow!("check {} has an empty command", definition.id));
+            }
+            Probe::File {
+                mode: Some(mode), ..
+            } => {
+                parse_mode(mode).with_context(|| format!("check {} is invalid", definition.id))?;
+            }
+            _ => (),
+        }
+    }
+
+    Ok(checks)
+}
+
+fn parse_mode(mode: &str) -> anyhow::Result<u32> {
+    u32::from_str_radix(mode.trim_start_matches("0o"), 8)
+        .ok()
+        .filter(|mode| *mode <= 0o7777)
+        .ok_or_else(|| anyhow!("{mode:?} is not an octal file mode"))
+}
+
+fn probe_tcp(host: &str, port: u16, tls: bool) -> anyhow::Result<()> {
+    let addrs = (host, port)
+        .to_socket_addrs()
+        .with_context(|| format!("could not resolve {host}"))?;
+
+    let mut stream = Err(anyhow!("{host} resolved to no addresses"));
+    for addr in addrs {
+        stream = TcpStream::connect_timeout(&addr, PROBE_TIMEOUT)
+            .with_context(|| format!("could not connect to {addr}"));
+        if stream.is_ok() {
+            break;
+        }
+    }
+    let stream = stream?;
+
+    if tls {
+        stream
+            .set_read_timeout(Some(PROBE_TIMEOUT))
+            .context("could not set socket timeout")?;
+        stream
+            .set_write_timeout(Some(PROBE_TIMEOUT))
+            .context("could not set socket timeout")?;
+
+        let connector = openssl::ssl::SslConnector::builder(openssl::ssl::SslMethod::tls())
+            .context("could not create TLS connector")?
+            .build();
+        connector
+            .connect(host, stream)
+            .with_context(|| format!("TLS handshake with {host}:{port} failed"))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::{Probe, Severity, load_file, parse_mode};
+
+    fn write_checks(name: &str, contents: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("iotedge-user-checks-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join(name);
+        std::fs::write(&file, contents).unwrap();
+        file
+    }
+
+    #[test]
+    fn load_checks() {
+        let file = write_checks(
+            "valid.toml",
+            r#"
+            [[check]]
+            id = "plc-gateway-reachable"
+            description = "PLC gateway accepts connections"
+            severity = "warning"
+            kind = "tcp"
+            host = "10.0.0.5"
+            port = 502
+
+            [[check]]
+            id = "opcua-cert-private"
+            description = "OPC UA client key is private"
+            kind = "file"
+            path = "/var/lib/opcua/client.key"
+            mode = "600"
+
+            [[check]]
+            id = "opcua-running"
+            description = "OPC UA publisher is running"
+            kind = "module"
+            name = "OPCPublisher"
+            "#,
+        );
+
+        let checks = load_file(&file, &BTreeSet::new()).unwrap();
+        assert_eq!(3, checks.len());
+
+        assert!(matches!(checks[0].severity, Severity::Warning));
+        assert!(matches!(
+            &checks[0].probe,
+            Probe::Tcp { host, port: 502, tls: false } if host == "10.0.0.5"
+        ));
+
+        assert!(matches!(checks[1].severity, Severity::Error));
+        assert!(matches!(
+            &checks[1].probe,
+            Probe::File { exists: true, mode: Some(mode), .. } if mode == "600"
+        ));
+
+        assert!(matches!(
+            &checks[2].probe,
+            Probe::Module {
+                status: edgelet_core::ModuleStatus::Running,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn reject_invalid_checks() {
+        let duplicate = r#"
+            [[check]]
+            id = "same"
+            description = "first"
+            kind = "file"
+            path = "/tmp"
+
+            [[check]]
+            id = "same"
+            description = "second"
+            kind = "file"
+            path = "/tmp"
+            "#;
+        let file = write_checks("duplicate.toml", duplicate);
+        load_file(&file, &BTreeSet::new()).unwrap_err();
+
+        let built_in = r#"
+            [[check]]
+            id = "config-toml-well-formed"
+            description = "shadows a built-in check"
+            kind = "file"
+            path = "/tmp"
+            "#;
+        let file = write_checks("built_in.toml", built_in);
+        let reserved = BTreeSet::from(["config-toml-well-formed".to_owned()]);
+        load_file(&file, &reserved).unwrap_err();
+
+        let empty_command = r#"
+            [[check]]
+            id = "empty-command"
+            description = "runs nothing"
+            kind = "container"
+            command = []
+            "#;
+        let file = write_checks("empty_command.toml", empty_command);
+        load_file(&file, &BTreeSet::new()).unwrap_err();
+
+        let unknown_kind = r#"
+            [[check]]
+            id = "unknown-kind"
+            description = "probes something unsupported"
+            kind = "udp"
+            "#;
+        let file = write_checks("unknown_kind.toml", unknown_kind);
+        load_file(&file, &BTreeSet::new()).unwrap_err();
+    }
+
+    #[test]
+    fn file_modes() {
+        assert_eq!(0o600, parse_mode("600").unwrap());
+        assert_eq!(0o755, parse_mode("0755").unwrap());
+        assert_eq!(0o640, parse_mode("0o640").unwrap());
+        parse_mode("rw-------").unwrap_err();
+        parse_mode("77777").unwrap_err();
+    }
+}
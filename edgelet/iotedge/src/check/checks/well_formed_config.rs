@@ -10,7 +10,7 @@ pub(crate) struct WellFormedConfig {}
 
 #[async_trait::async_trait]
 impl Checker for WellFormedConfig {
-    fn meta(&self) -> CheckerMeta {
+    fn meta(&self) -> CheckerMeta<'_> {
         CheckerMeta {
             id: "aziot-edged-config-well-formed",
             description: "aziot-edged configuration is well-formed",
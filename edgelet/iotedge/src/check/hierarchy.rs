@@ -0,0 +1,926 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+//! `iotedge check --hierarchy` walks the chain of parents of a nested device. Every layer's API
+//! proxy describes its device at `/iotedge/hierarchy` and forwards each `/upstream` suffix one
+//! layer up, so the whole chain is reachable through the nearest parent, like `$upstream` images.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+use std::net::IpAddr;
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{Context, anyhow};
+use bytes::Bytes;
+use edgelet_http::ApiVersion;
+use http_body_util::{BodyExt as _, Empty};
+use hyper::StatusCode;
+use hyper_util::client::legacy::{Client, connect::Connect};
+use hyper_util::rt::TokioExecutor;
+use openssl::x509::X509;
+
+use crate::config::super_config;
+use crate::error::Error;
+
+use super::OutputFormat;
+use super::stdout::Stdout;
+
+const HIERARCHY_PATH: &str = "/iotedge/hierarchy";
+const UPSTREAM_SEGMENT: &str = "/upstream";
+
+/// Each API proxy serves the certificate that it presents to its child under the hierarchy path.
+const CERTIFICATE_SEGMENT: &str = "/certificate";
+
+/// The port of the parent's API proxy in the `$upstream` convention.
+const UPSTREAM_PORT: u16 = 443;
+
+/// ISA-95 networks have five layers, and the API proxy verifies certificate chains up to seven
+/// deep, so a longer chain is a loop or a misconfiguration.
+const MAX_DEPTH: usize = 8;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const CERTIFICATE_EXPIRY_WARNING_DAYS: u32 = 7;
+
+/// What a layer's API proxy reports about its device.
+#[derive(Debug, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Layer {
+    device_id: String,
+    hostname: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    parent_hostname: Option<String>,
+    #[serde(default)]
+    versions: BTreeMap<String, String>,
+}
+
+impl Layer {
+    fn parent(&self) -> Option<&str> {
+        self.parent_hostname
+            .as_deref()
+            .filter(|hostname| !hostname.is_empty())
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Certificate {
+    subject: String,
+    issuer: String,
+    not_after: String,
+}
+
+/// One ancestor of this device. The first hop is the parent.
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Hop {
+    /// The hostname that the child of this hop connects to.
+    hostname: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    layer: Option<Layer>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    certificate: Option<Certificate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    registry_reachable: Option<bool>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl Hop {
+    fn new(hostname: String) -> Self {
+        Hop {
+            hostname,
+            layer: None,
+            certificate: None,
+            registry_reachable: None,
+            warnings: Vec::new(),
+            error: None,
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Report {
+    parent_hostname: String,
+    hops: Vec<Hop>,
+}
+
+impl Report {
+    fn first_broken(&self) -> Option<usize> {
+        self.hops.iter().position(|hop| hop.error.is_some())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum HopStatus {
+    Ok,
+    Warning,
+    Error,
+}
+
+/// Walks the parents of the device configured in `config_file` up to the top layer and prints
+/// the chain as a tree, or as JSON.
+pub(super) async fn run(config_file: &Path, output_format: OutputFormat) -> anyhow::Result<()> {
+    let config = std::fs::read_to_string(config_file)
+        .with_context(|| format!("could not read config file {}", config_file.display()))?;
+    let config: super_config::Config = toml::from_str(&config)
+        .with_context(|| format!("could not parse config file {}", config_file.display()))?;
+
+    let parent_hostname = config.aziot.parent_hostname.ok_or_else(|| {
+        anyhow!(
+            "{} does not set parent_hostname, so this device is not part of a nested hierarchy",
+            config_file.display()
+        )
+    })?;
+
+    let trusted_certs = match &config.trust_bundle_cert {
+        Some(uri) => load_trust_bundle(uri)?,
+        None => Vec::new(),
+    };
+
+    let identity = load_client_identity(config.edge_ca.as_ref())?;
+    let connector = http_common::MaybeProxyConnector::new(
+        None,
+        identity
+            .as_ref()
+            .map(|(cert, key)| (cert.as_slice(), key.as_slice())),
+        &trusted_certs,
+    )
+    .context("could not initialize HTTP connector")?;
+    let client: Client<_, Empty<Bytes>> = Client::builder(TokioExecutor::new()).build(connector);
+
+    let base = format!("https://{parent_hostname}:{UPSTREAM_PORT}");
+    let report = walk(&client, &base, &parent_hostname, &trusted_certs).await;
+
+    print_report(&report, output_format)?;
+
+    if report.first_broken().is_some() {
+        Err(Error::Diagnostics.into())
+    } else {
+        Ok(())
+    }
+}
+
+/// Queries `base` for each layer in turn, adding one `/upstream` per layer, until a layer has no
+/// parent or a hop is broken. The certificate of every hop is validated against `trusted_certs`.
+async fn walk<C>(
+    client: &Client<C, Empty<Bytes>>,
+    base: &str,
+    parent_hostname: &str,
+    trusted_certs: &[X509],
+) -> Report
+where
+    C: Connect + Clone + Send + Sync + 'static,
+{
+    let mut report = Report {
+        parent_hostname: parent_hostname.to_owned(),
+        hops: Vec::new(),
+    };
+    let mut seen = BTreeSet::new();
+    let mut next = Some(parent_hostname.to_owned());
+
+    while let Some(hostname) = next.take() {
+        let depth = report.hops.len();
+        let child = report
+            .hops
+            .last()
+            .and_then(|hop| hop.layer.as_ref())
+            .map(|layer| layer.hostname.clone());
+
+        let mut hop = Hop::new(hostname);
+
+        if depth == MAX_DEPTH {
+            hop.error = Some(format!(
+                "the hierarchy is deeper than {MAX_DEPTH} layers, which the API proxy cannot verify"
+            ));
+            report.hops.push(hop);
+            break;
+        }
+
+        let uri = format!("{base}{HIERARCHY_PATH}{}", UPSTREAM_SEGMENT.repeat(depth));
+        match get(client, &uri).await {
+            Ok((StatusCode::OK, body)) => match serde_json::from_slice::<Layer>(&body) {
+                Ok(layer) => {
+                    if seen.insert(layer.hostname.to_lowercase()) {
+                        if !layer.hostname.eq_ignore_ascii_case(&hop.hostname) {
+                            hop.warnings.push(format!(
+                                "{} reports its hostname as {}, which its certificate is issued for",
+                                hop.hostname, layer.hostname
+                            ));
+                        }
+
+                        if let Some(child_layer) =
+                            report.hops.last().and_then(|child| child.layer.as_ref())
+                            && let (Some(version), Some(child_version)) = (
+                                workload_api_version(&layer),
+                                workload_api_version(child_layer),
+                            )
+                            && version < child_version
+                        {
+                            hop.warnings.push(format!(
+                                "{} runs an older workload API ({version}) than its child {} ({child_version}); parents should be updated before their children",
+                                layer.hostname, child_layer.hostname
+                            ));
+                        }
+
+                        next = layer.parent().map(ToOwned::to_owned);
+                    } else {
+                        hop.error = Some(format!(
+                            "{} is already in the hierarchy, so the parents form a loop",
+                            layer.hostname
+                        ));
+                    }
+                    hop.layer = Some(layer);
+                }
+                Err(err) => {
+                    hop.error = Some(format!(
+                        "{} answered {HIERARCHY_PATH} with an invalid description: {err}",
+                        hop.hostname
+                    ));
+                }
+            },
+            Ok((status, _)) => {
+                hop.error = Some(status_error(status, child.as_deref(), &hop.hostname));
+            }
+            Err(err) => hop.error = Some(format!("could not query {}: {err:#}", hop.hostname)),
+        }
+
+        // Images that use `$upstream` are pulled through the parent's API proxy.
+        if depth == 0 && hop.error.is_none() {
+            match get(client, &format!("{base}/v2/")).await {
+                Ok((StatusCode::OK | StatusCode::UNAUTHORIZED, _)) => {
+                    hop.registry_reachable = Some(true);
+                }
+                Ok((status, _)) => {
+                    hop.registry_reachable = Some(false);
+                    hop.warnings.push(format!(
+                        "images from $upstream cannot be pulled: the registry endpoint of {} answered HTTP {status}",
+                        hop.hostname
+                    ));
+                }
+                Err(err) => {
+                    hop.registry_reachable = Some(false);
+                    hop.warnings
+                        .push(format!("images from $upstream cannot be pulled: {err:#}"));
+                }
+            }
+        }
+
+        // A certificate that this device does not trust is reported, but the API proxies may
+        // still trust it, so the walk goes on.
+        let broken = hop.error.is_some();
+        if !broken {
+            check_certificate(client, &uri, &mut hop, trusted_certs).await;
+        }
+
+        report.hops.push(hop);
+        if broken {
+            break;
+        }
+    }
+
+    report
+}
+
+/// The workload API version of `layer`. Versions that this tool does not know are not compared.
+fn workload_api_version(layer: &Layer) -> Option<ApiVersion> {
+    layer.versions.get("workloadApi")?.parse().ok()
+}
+
+/// Fetches the certificate that the API proxy at `uri` presents to its child, and records it and
+/// any problem with it on `hop`.
+async fn check_certificate<C>(
+    client: &Client<C, Empty<Bytes>>,
+    uri: &str,
+    hop: &mut Hop,
+    trusted_certs: &[X509],
+) where
+    C: Connect + Clone + Send + Sync + 'static,
+{
+    let chain = match get(client, &format!("{uri}{CERTIFICATE_SEGMENT}")).await {
+        Ok((StatusCode::OK, body)) => X509::stack_from_pem(&body).map_err(anyhow::Error::from),
+        Ok((status @ StatusCode::NOT_FOUND, _)) => {
+            hop.warnings.push(format!(
+                "the API proxy of {} does not serve its certificate (HTTP {status}); its configuration may predate it",
+                hop.hostname
+            ));
+            return;
+        }
+        Ok((status, _)) => Err(anyhow!("HTTP {status}")),
+        Err(err) => Err(err),
+    };
+
+    if let Err(err) = chain.and_then(|chain| validate_certificate(hop, &chain, trusted_certs)) {
+        hop.warnings.push(format!(
+            "could not check the certificate of {}: {err:#}",
+            hop.hostname
+        ));
+    }
+}
+
+/// Checks that `chain` chains to the system roots and `trusted_certs`, is issued for the hostname
+/// of `hop` and does not expire soon.
+fn validate_certificate(
+    hop: &mut Hop,
+    chain: &[X509],
+    trusted_certs: &[X509],
+) -> anyhow::Result<()> {
+    let (cert, intermediates) = chain
+        .split_first()
+        .ok_or_else(|| anyhow!("the response has no certificate"))?;
+
+    let mut store = openssl::x509::store::X509StoreBuilder::new()?;
+    store
+        .set_default_paths()
+        .context("could not load the system roots")?;
+    for trusted_cert in trusted_certs {
+        store
+            .add_cert(trusted_cert.clone())
+            .context("could not add trust bundle certificate")?;
+    }
+    let mut param = openssl::x509::verify::X509VerifyParam::new()?;
+    match hop.hostname.parse::<IpAddr>() {
+        Ok(ip) => param.set_ip(ip)?,
+        Err(_) => param.set_host(&hop.hostname)?,
+    }
+    store.set_param(&param)?;
+    let store = store.build();
+
+    let mut untrusted = openssl::stack::Stack::new()?;
+    for intermediate in intermediates {
+        untrusted.push(intermediate.clone())?;
+    }
+
+    let mut context = openssl::x509::X509StoreContext::new()?;
+    let invalid = context.init(&store, cert, &untrusted, |context| {
+        Ok((!context.verify_cert()?).then(|| context.error()))
+    })?;
+
+    let certificate = Certificate {
+        subject: name_to_string(cert.subject_name()),
+        issuer: name_to_string(cert.issuer_name()),
+        not_after: cert.not_after().to_string(),
+    };
+
+    if let Some(invalid) = invalid {
+        hop.error.get_or_insert_with(|| {
+            format!(
+                "the certificate of {} is not valid for this device: {invalid}",
+                hop.hostname
+            )
+        });
+    } else {
+        let soon = openssl::asn1::Asn1Time::days_from_now(CERTIFICATE_EXPIRY_WARNING_DAYS)
+            .context("could not compute certificate expiry")?;
+        if cert
+            .not_after()
+            .compare(&soon)
+            .context("could not compare certificate expiry")?
+            .is_lt()
+        {
+            hop.warnings.push(format!(
+                "the certificate of {} expires within {CERTIFICATE_EXPIRY_WARNING_DAYS} days, on {}",
+                hop.hostname, certificate.not_after,
+            ));
+        }
+    }
+
+    hop.certificate = Some(certificate);
+
+    Ok(())
+}
+
+fn status_error(status: StatusCode, child: Option<&str>, hostname: &str) -> String {
+    match (status, child) {
+        (StatusCode::BAD_GATEWAY | StatusCode::GATEWAY_TIMEOUT, Some(child)) => format!(
+            "{child} could not reach or verify the certificate of its parent {hostname} (HTTP {status})"
+        ),
+        (StatusCode::FORBIDDEN, Some(child)) => format!(
+            "{hostname} did not accept the client certificate of {child} (HTTP {status}); the API proxy of {child} may predate client certificates, or its identity certificate does not chain to the trust bundle of {hostname}"
+        ),
+        (StatusCode::FORBIDDEN, None) => format!(
+            "{hostname} did not accept the client certificate of this device (HTTP {status}); this check presents the Edge CA certificate, so [edge_ca] must be preloaded from files and chain to the trust bundle of {hostname}"
+        ),
+        (StatusCode::NOT_FOUND, _) => format!(
+            "the API proxy of {} does not serve {HIERARCHY_PATH} (HTTP {status}); its configuration may predate it",
+            child.unwrap_or(hostname)
+        ),
+        _ => format!("{hostname} answered HTTP {status}"),
+    }
+}
+
+async fn get<C>(client: &Client<C, Empty<Bytes>>, uri: &str) -> anyhow::Result<(StatusCode, Bytes)>
+where
+    C: Connect + Clone + Send + Sync + 'static,
+{
+    let uri: hyper::Uri = uri.parse().with_context(|| format!("invalid URI {uri}"))?;
+
+    let res = tokio::time::timeout(REQUEST_TIMEOUT, client.get(uri))
+        .await
+        .context("request timed out")?
+        .context("request failed")?;
+    let status = res.status();
+    let body = res
+        .into_body()
+        .collect()
+        .await
+        .context("could not read response")?
+        .to_bytes();
+
+    Ok((status, body))
+}
+
+fn load_trust_bundle(uri: &url::Url) -> anyhow::Result<Vec<X509>> {
+    let path = uri
+        .to_file_path()
+        .map_err(|()| anyhow!("unsupported trust_bundle_cert URI {uri}"))?;
+    let pem = std::fs::read(&path)
+        .with_context(|| format!("could not read trust bundle {}", path.display()))?;
+
+    X509::stack_from_pem(&pem)
+        .with_context(|| format!("could not parse trust bundle {}", path.display()))
+}
+
+/// The API proxies only describe their device to clients whose certificate chains to the trust
+/// bundle of the hierarchy. Only an Edge CA that is preloaded from files can be presented here;
+/// other Edge CAs are kept by the certificate and key services.
+fn load_client_identity(
+    edge_ca: Option<&super_config::EdgeCa>,
+) -> anyhow::Result<Option<(Vec<u8>, Vec<u8>)>> {
+    let Some(super_config::EdgeCa::Preloaded { cert, pk }) = edge_ca else {
+        return Ok(None);
+    };
+    let (Ok(cert_path), Ok(pk_path)) = (cert.to_file_path(), pk.to_file_path()) else {
+        return Ok(None);
+    };
+
+    let cert = std::fs::read(&cert_path)
+        .with_context(|| format!("could not read Edge CA certificate {}", cert_path.display()))?;
+    let pk = std::fs::read(&pk_path)
+        .with_context(|| format!("could not read Edge CA private key {}", pk_path.display()))?;
+
+    Ok(Some((cert, pk)))
+}
+
+fn name_to_string(name: &openssl::x509::X509NameRef) -> String {
+    name.entries()
+        .map(|entry| {
+            let key = entry.object().nid().short_name().unwrap_or("?");
+            let value = String::from_utf8_lossy(entry.data().as_slice());
+            format!("{key}={value}")
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn print_report(report: &Report, output_format: OutputFormat) -> anyhow::Result<()> {
+    if output_format == OutputFormat::Json {
+        serde_json::to_writer(std::io::stdout(), report).context(Error::WriteToStdout)?;
+        println!();
+        return Ok(());
+    }
+
+    let mut stdout = Stdout::new(output_format);
+
+    println!("this device");
+    for (status, text) in render(report) {
+        let write_hop = |stdout: &mut dyn Write| write!(stdout, "{text}");
+        match status {
+            HopStatus::Ok => stdout.write_success(write_hop),
+            HopStatus::Warning => stdout.write_warning(write_hop),
+            HopStatus::Error => stdout.write_error(write_hop),
+        }
+    }
+
+    Ok(())
+}
+
+/// Renders each hop as a branch of the tree, nested under its child.
+fn render(report: &Report) -> Vec<(HopStatus, String)> {
+    report
+        .hops
+        .iter()
+        .enumerate()
+        .map(|(depth, hop)| {
+            let indent = "    ".repeat(depth);
+            let details = format!("{indent}    ");
+
+            let (status, symbol) = if hop.error.is_some() {
+                (HopStatus::Error, '\u{00d7}')
+            } else if hop.warnings.is_empty() {
+                (HopStatus::Ok, '\u{221a}')
+            } else {
+                (HopStatus::Warning, '\u{203c}')
+            };
+
+            let header = format!("{indent}\u{2514}\u{2500}\u{2500} {symbol} {}", hop.hostname);
+            let mut lines = vec![match &hop.layer {
+                Some(layer) => format!("{header} (device {})", layer.device_id),
+                None => header,
+            }];
+
+            if let Some(layer) = &hop.layer
+                && !layer.versions.is_empty()
+            {
+                let versions: Vec<_> = layer
+                    .versions
+                    .iter()
+                    .map(|(component, version)| format!("{component} {version}"))
+                    .collect();
+                lines.push(format!("{details}versions: {}", versions.join(", ")));
+            }
+            if let Some(certificate) = &hop.certificate {
+                lines.push(format!(
+                    "{details}certificate: {}, issued by {}, expires {}",
+                    certificate.subject, certificate.issuer, certificate.not_after
+                ));
+            }
+            if hop.registry_reachable == Some(true) {
+                lines.push(format!("{details}registry: reachable through $upstream"));
+            }
+            for warning in &hop.warnings {
+                lines.push(format!("{details}{warning}"));
+            }
+            if let Some(error) = &hop.error {
+                lines.push(format!("{details}{error}"));
+            }
+
+            let mut text = lines.join("\n");
+            text.push('\n');
+
+            (status, text)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use http_body_util::{Empty, Full};
+    use hyper::{Request, Response, StatusCode};
+    use hyper_util::client::legacy::{Client, connect::HttpConnector};
+    use hyper_util::rt::TokioExecutor;
+    use openssl::pkey::{PKey, Private};
+    use openssl::x509::X509;
+
+    use super::{CERTIFICATE_SEGMENT, HIERARCHY_PATH, HopStatus, UPSTREAM_SEGMENT, render, walk};
+
+    /// Serves the layers above a device the way a chain of API proxies would: layer `n` answers
+    /// after `n` `/upstream` segments, and a request past `broken_after` gets the 502 that the API
+    /// proxy of that layer returns when it cannot reach its parent. A `null` layer gets the 403
+    /// that an API proxy returns to a client without a verified certificate. Layer `n` presents
+    /// `certificates[n]`, if any.
+    async fn mock_parent(
+        layers: Vec<serde_json::Value>,
+        certificates: Vec<X509>,
+        broken_after: Option<usize>,
+    ) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            loop {
+                let (stream, _) = listener.accept().await.unwrap();
+                let layers = layers.clone();
+                let certificates = certificates.clone();
+                let service =
+                    hyper::service::service_fn(move |req: Request<hyper::body::Incoming>| {
+                        let response =
+                            respond(&layers, &certificates, broken_after, req.uri().path());
+                        async move { Ok::<_, std::convert::Infallible>(response) }
+                    });
+
+                tokio::spawn(
+                    hyper::server::conn::http1::Builder::new()
+                        .serve_connection(hyper_util::rt::TokioIo::new(stream), service),
+                );
+            }
+        });
+
+        format!("http://{addr}")
+    }
+
+    fn respond(
+        layers: &[serde_json::Value],
+        certificates: &[X509],
+        broken_after: Option<usize>,
+        path: &str,
+    ) -> Response<Full<Bytes>> {
+        let status = |status| {
+            Response::builder()
+                .status(status)
+                .body(Full::default())
+                .unwrap()
+        };
+
+        if path == "/v2/" {
+            return status(StatusCode::UNAUTHORIZED);
+        }
+
+        let Some(upstream) = path.strip_prefix(HIERARCHY_PATH) else {
+            return status(StatusCode::NOT_FOUND);
+        };
+        let (upstream, certificate) = match upstream.strip_suffix(CERTIFICATE_SEGMENT) {
+            Some(upstream) => (upstream, true),
+            None => (upstream, false),
+        };
+        let depth = upstream.matches(UPSTREAM_SEGMENT).count();
+
+        if broken_after.is_some_and(|broken| depth > broken) {
+            return status(StatusCode::BAD_GATEWAY);
+        }
+
+        match layers.get(depth) {
+            Some(serde_json::Value::Null) => status(StatusCode::FORBIDDEN),
+            Some(_) if certificate => match certificates.get(depth) {
+                Some(certificate) => {
+                    Response::new(Full::new(Bytes::from(certificate.to_pem().unwrap())))
+                }
+                None => status(StatusCode::NOT_FOUND),
+            },
+            Some(layer) => Response::new(Full::new(Bytes::from(layer.to_string()))),
+            None => status(StatusCode::NOT_FOUND),
+        }
+    }
+
+    fn layer(hostname: &str, parent_hostname: Option<&str>) -> serde_json::Value {
+        let mut layer = serde_json::json!({
+            "deviceId": hostname.split('.').next().unwrap(),
+            "hostname": hostname,
+            "versions": { "workloadApi": "2022-08-03" },
+        });
+        if let Some(parent_hostname) = parent_hostname {
+            layer["parentHostname"] = parent_hostname.into();
+        }
+        layer
+    }
+
+    fn client() -> Client<HttpConnector, Empty<Bytes>> {
+        Client::builder(TokioExecutor::new()).build(HttpConnector::new())
+    }
+
+    fn test_ca() -> (X509, PKey<Private>) {
+        issue(None, "hierarchy test CA", 30)
+    }
+
+    /// Issues a certificate for `hostname` that is valid for `days`, signed by `ca`, or a
+    /// self-signed CA certificate if there is no `ca`.
+    fn issue(
+        ca: Option<&(X509, PKey<Private>)>,
+        hostname: &str,
+        days: u32,
+    ) -> (X509, PKey<Private>) {
+        let group =
+            openssl::ec::EcGroup::from_curve_name(openssl::nid::Nid::X9_62_PRIME256V1).unwrap();
+        let key = PKey::from_ec_key(openssl::ec::EcKey::generate(&group).unwrap()).unwrap();
+
+        let mut name = openssl::x509::X509NameBuilder::new().unwrap();
+        name.append_entry_by_text("CN", hostname).unwrap();
+        let name = name.build();
+
+        let mut builder = X509::builder().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder
+            .set_not_before(&openssl::asn1::Asn1Time::days_from_now(0).unwrap())
+            .unwrap();
+        builder
+            .set_not_after(&openssl::asn1::Asn1Time::days_from_now(days).unwrap())
+            .unwrap();
+        builder.set_pubkey(&key).unwrap();
+
+        if let Some((ca_cert, ca_key)) = ca {
+            builder.set_issuer_name(ca_cert.subject_name()).unwrap();
+            let san = openssl::x509::extension::SubjectAlternativeName::new()
+                .dns(hostname)
+                .build(&builder.x509v3_context(Some(ca_cert), None))
+                .unwrap();
+            builder.append_extension(san).unwrap();
+            builder
+                .sign(ca_key, openssl::hash::MessageDigest::sha256())
+                .unwrap();
+        } else {
+            builder.set_issuer_name(&name).unwrap();
+            builder
+                .append_extension(
+                    openssl::x509::extension::BasicConstraints::new()
+                        .critical()
+                        .ca()
+                        .build()
+                        .unwrap(),
+                )
+                .unwrap();
+            builder
+                .sign(&key, openssl::hash::MessageDigest::sha256())
+                .unwrap();
+        }
+
+        (builder.build(), key)
+    }
+
+    /// Certificates that `ca` issued for the hostname of each layer.
+    fn certificates(ca: &(X509, PKey<Private>), layers: &[serde_json::Value]) -> Vec<X509> {
+        layers
+            .iter()
+            .map(|layer| issue(Some(ca), layer["hostname"].as_str().unwrap(), 30).0)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn walks_to_top_layer() {
+        let ca = test_ca();
+        let layers = vec![
+            layer("l4.contoso.com", Some("l5.contoso.com")),
+            layer("l5.contoso.com", None),
+        ];
+        let base = mock_parent(layers.clone(), certificates(&ca, &layers), None).await;
+
+        let report = walk(&client(), &base, "l4.contoso.com", &[ca.0]).await;
+
+        assert_eq!(report.first_broken(), None);
+        assert_eq!(report.hops.len(), 2);
+        assert_eq!(report.hops[0].registry_reachable, Some(true));
+        assert!(report.hops.iter().all(|hop| hop.warnings.is_empty()));
+        assert!(report.hops.iter().all(|hop| hop.certificate.is_some()));
+        assert_eq!(
+            report.hops[1].layer.as_ref().unwrap().device_id,
+            "l5".to_owned()
+        );
+    }
+
+    #[tokio::test]
+    async fn stops_at_first_broken_hop() {
+        let ca = test_ca();
+        let layers = vec![
+            layer("l3.contoso.com", Some("l4.contoso.com")),
+            layer("l4.contoso.com", Some("l5.contoso.com")),
+            layer("l5.contoso.com", None),
+        ];
+        let base = mock_parent(layers.clone(), certificates(&ca, &layers), Some(1)).await;
+
+        let report = walk(&client(), &base, "l3.contoso.com", &[ca.0]).await;
+
+        assert_eq!(report.first_broken(), Some(2));
+        assert_eq!(report.hops.len(), 3);
+        assert_eq!(report.hops[2].hostname, "l5.contoso.com");
+        assert_eq!(
+            report.hops[2].error.as_deref(),
+            Some(
+                "l4.contoso.com could not reach or verify the certificate of its parent l5.contoso.com (HTTP 502 Bad Gateway)"
+            )
+        );
+
+        let rendered = render(&report);
+        assert_eq!(rendered[0].0, HopStatus::Ok);
+        assert_eq!(rendered[2].0, HopStatus::Error);
+        assert!(
+            rendered[2]
+                .1
+                .starts_with("        \u{2514}\u{2500}\u{2500} \u{00d7} l5.contoso.com\n")
+        );
+    }
+
+    #[tokio::test]
+    async fn detects_loops() {
+        let ca = test_ca();
+        let layers = vec![
+            layer("l4.contoso.com", Some("l5.contoso.com")),
+            layer("l5.contoso.com", Some("l4.contoso.com")),
+            layer("l4.contoso.com", Some("l5.contoso.com")),
+        ];
+        let base = mock_parent(layers.clone(), certificates(&ca, &layers), None).await;
+
+        let report = walk(&client(), &base, "l4.contoso.com", &[ca.0]).await;
+
+        assert_eq!(report.first_broken(), Some(2));
+        assert_eq!(
+            report.hops[2].error.as_deref(),
+            Some("l4.contoso.com is already in the hierarchy, so the parents form a loop")
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_refused_client_certificates() {
+        let ca = test_ca();
+        let base = mock_parent(vec![serde_json::Value::Null], Vec::new(), None).await;
+
+        let report = walk(
+            &client(),
+            &base,
+            "l4.contoso.com",
+            std::slice::from_ref(&ca.0),
+        )
+        .await;
+
+        assert_eq!(report.first_broken(), Some(0));
+        assert_eq!(
+            report.hops[0].error.as_deref(),
+            Some(
+                "l4.contoso.com did not accept the client certificate of this device (HTTP 403 Forbidden); this check presents the Edge CA certificate, so [edge_ca] must be preloaded from files and chain to the trust bundle of l4.contoso.com"
+            )
+        );
+
+        let layers = vec![
+            layer("l4.contoso.com", Some("l5.contoso.com")),
+            serde_json::Value::Null,
+        ];
+        let certificates = certificates(&ca, &layers[..1]);
+        let base = mock_parent(layers, certificates, None).await;
+
+        let report = walk(&client(), &base, "l4.contoso.com", &[ca.0]).await;
+
+        assert_eq!(report.first_broken(), Some(1));
+        assert_eq!(
+            report.hops[1].error.as_deref(),
+            Some(
+                "l5.contoso.com did not accept the client certificate of l4.contoso.com (HTTP 403 Forbidden); the API proxy of l4.contoso.com may predate client certificates, or its identity certificate does not chain to the trust bundle of l5.contoso.com"
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn checks_certificate_of_every_hop() {
+        let ca = test_ca();
+        let other_ca = issue(None, "other CA", 30);
+        let layers = vec![
+            layer("l3.contoso.com", Some("l4.contoso.com")),
+            layer("l4.contoso.com", Some("l5.contoso.com")),
+            layer("l5.contoso.com", Some("l6.contoso.com")),
+            layer("l6.contoso.com", None),
+        ];
+        let certificates = vec![
+            issue(Some(&ca), "l3.contoso.com", 30).0,
+            issue(Some(&ca), "l4.contoso.com", 3).0,
+            issue(Some(&other_ca), "l5.contoso.com", 30).0,
+            issue(Some(&ca), "l5.contoso.com", 30).0,
+        ];
+        let base = mock_parent(layers, certificates, None).await;
+
+        let report = walk(&client(), &base, "l3.contoso.com", &[ca.0]).await;
+
+        // Certificate problems do not stop the walk: the API proxies may trust what this device
+        // does not.
+        assert_eq!(report.hops.len(), 4);
+        assert_eq!(report.first_broken(), Some(2));
+
+        assert!(report.hops[0].error.is_none() && report.hops[0].warnings.is_empty());
+
+        assert!(report.hops[1].error.is_none());
+        assert_eq!(report.hops[1].warnings.len(), 1);
+        assert!(
+            report.hops[1].warnings[0]
+                .starts_with("the certificate of l4.contoso.com expires within 7 days, on ")
+        );
+
+        assert_eq!(
+            report.hops[2].error.as_deref(),
+            Some(
+                "the certificate of l5.contoso.com is not valid for this device: unable to get local issuer certificate"
+            )
+        );
+        assert!(report.hops[2].certificate.is_some());
+
+        assert!(
+            report.hops[3]
+                .error
+                .as_deref()
+                .unwrap()
+                .starts_with("the certificate of l6.contoso.com is not valid for this device: ")
+        );
+    }
+
+    #[tokio::test]
+    async fn warns_about_older_workload_api_in_parent() {
+        let ca = test_ca();
+        let mut parent = layer("l4.contoso.com", None);
+        parent["versions"]["workloadApi"] = "2019-01-30".into();
+        let mut unknown = layer("l4.contoso.com", None);
+        unknown["versions"]["workloadApi"] = "2099-01-01".into();
+
+        for (parent, warning) in [
+            (
+                parent,
+                Some(
+                    "l4.contoso.com runs an older workload API (2019-01-30) than its child l3.contoso.com (2022-08-03); parents should be updated before their children",
+                ),
+            ),
+            (unknown, None),
+        ] {
+            let layers = vec![layer("l3.contoso.com", Some("l4.contoso.com")), parent];
+            let base = mock_parent(layers.clone(), certificates(&ca, &layers), None).await;
+
+            let report = walk(
+                &client(),
+                &base,
+                "l3.contoso.com",
+                std::slice::from_ref(&ca.0),
+            )
+            .await;
+
+            assert_eq!(report.first_broken(), None);
+            assert_eq!(report.hops[1].warnings.first().map(String::as_str), warning);
+        }
+    }
+}
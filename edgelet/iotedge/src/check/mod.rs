@@ -2,11 +2,12 @@
 
 use std::collections::{BTreeMap, BTreeSet};
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 use anyhow::Context;
 
+use edgelet_core::{CheckReport, CheckStatus};
 use edgelet_settings::{RuntimeSettings, Settings};
 
 use aziotctl_common::{
@@ -14,6 +15,7 @@ use aziotctl_common::{
     CheckResultsSerializable, CheckerMetaSerializable,
 };
 
+use crate::MgmtClient;
 use crate::error::Error;
 
 mod additional_info;
@@ -29,6 +31,8 @@ use shared::{CheckResult, Checker, CheckerMeta};
 
 mod checks;
 
+mod hierarchy;
+
 pub struct Check {
     container_engine_config_path: PathBuf,
     diagnostics_image_name: String,
@@ -40,9 +44,13 @@ pub struct Check {
     verbose: bool,
     warnings_as_errors: bool,
     aziot_bin: std::ffi::OsString,
+    checks_dir: PathBuf,
 
     additional_info: AdditionalInfo,
 
+    // The results of the last run, for `watch` to report
+    reports: Vec<CheckReport>,
+
     // These optional fields are populated by the checks
     aziot_edge_proxy: Option<String>,
     aziot_identity_proxy: Option<String>,
@@ -50,7 +58,7 @@ pub struct Check {
     proxy_uri: Option<String>,       // populated by `aziot check`
     parent_hostname: Option<String>, // populated by `aziot check`
     settings: Option<Settings>,
-    docker_host_arg: Option<String>,
+    docker: Option<checks::DockerClient>,
     docker_proxy: Option<String>,
     docker_server_version: Option<String>,
 }
@@ -73,6 +81,7 @@ impl Check {
         verbose: bool,
         warnings_as_errors: bool,
         aziot_bin: std::ffi::OsString,
+        checks_dir: PathBuf,
         iothub_hostname: Option<String>,
         proxy_uri: Option<String>,
     ) -> Check {
@@ -87,22 +96,25 @@ impl Check {
             verbose,
             warnings_as_errors,
             aziot_bin,
+            checks_dir,
 
             additional_info: AdditionalInfo::new(),
 
+            reports: Vec::new(),
+
             aziot_edge_proxy: get_local_service_proxy_setting("aziot-edged.service"),
             aziot_identity_proxy: get_local_service_proxy_setting("aziot-identityd.service"),
             iothub_hostname,
             proxy_uri: get_proxy_uri(proxy_uri),
             parent_hostname: None,
             settings: None,
-            docker_host_arg: None,
+            docker: None,
             docker_proxy: get_local_service_proxy_setting("docker"),
             docker_server_version: None,
         }
     }
 
-    pub async fn print_list(aziot_bin: &str) -> anyhow::Result<()> {
+    pub async fn print_list(aziot_bin: &str, checks_dir: &Path) -> anyhow::Result<()> {
         let mut all_checks: Vec<(String, Vec<CheckerMetaSerializable>)> = Vec::new();
 
         // get all the aziot checks by shelling-out to aziot
@@ -141,10 +153,10 @@ impl Check {
             }
         }
 
-        // get all the built-in checks
+        // get all the built-in and user-defined checks
         {
-            let built_in_checks = checks::built_in_checks();
-            let checks = built_in_checks.iter().map(|(section_name, checks)| {
+            let iotedge_checks = checks::all_checks(checks_dir);
+            let checks = iotedge_checks.iter().map(|(section_name, checks)| {
                 (
                     (*section_name).to_string(),
                     checks
@@ -231,12 +243,21 @@ impl Check {
         let mut num_fatal = 0_usize;
         let mut num_errors = 0_usize;
 
+        let mut reports = Vec::new();
+
         let mut output_check =
             |check: CheckOutput, verbose: bool, warnings_as_errors: bool| -> anyhow::Result<bool> {
                 if num_fatal > 0 {
                     return Ok(true);
                 }
 
+                reports.push(check_report(
+                    &check.id,
+                    &check.description,
+                    &check.result,
+                    warnings_as_errors,
+                ));
+
                 let CheckOutput {
                     id: check_id,
                     description: check_name,
@@ -549,8 +570,8 @@ impl Check {
             }
         }
 
-        // run the built-in checks
-        'outer: for (section_name, section_checks) in &mut checks::built_in_checks() {
+        // run the built-in checks, then the user-defined ones
+        'outer: for (section_name, section_checks) in &mut checks::all_checks(&self.checks_dir) {
             self.output_section(section_name);
 
             for check in section_checks {
@@ -573,6 +594,8 @@ impl Check {
             }
         }
 
+        self.reports = reports;
+
         stdout.write_success(|stdout| {
             writeln!(stdout, "{num_successful} check(s) succeeded.")?;
             Ok(())
@@ -639,6 +662,81 @@ impl Check {
 
         result
     }
+
+    /// Walks the parents of this nested device up to the top layer of the hierarchy, and reports
+    /// the first hop that is broken. `config_file` is the IoT Edge system configuration that
+    /// names the parent and the trust bundle.
+    pub async fn hierarchy(&self, config_file: &Path) -> anyhow::Result<()> {
+        hierarchy::run(config_file, self.output_format).await
+    }
+
+    /// Runs a new check from `new_check` every `interval`, and reports the results of each run to
+    /// aziot-edged, which keeps the latest result of every check for monitoring.
+    pub async fn watch(
+        mut new_check: impl FnMut() -> Check,
+        interval: std::time::Duration,
+        client: &MgmtClient,
+    ) -> anyhow::Result<()> {
+        loop {
+            let mut check = new_check();
+
+            // Failed checks are reported like any other result, so they don't end the watch.
+            if let Err(err) = check.execute().await
+                && !matches!(err.downcast_ref::<Error>(), Some(Error::Diagnostics))
+            {
+                eprintln!("Could not run checks: {err:#}");
+            }
+
+            match client
+                .report_checks(std::mem::take(&mut check.reports))
+                .await
+            {
+                Ok(transitions) => {
+                    if check.output_format == OutputFormat::Text {
+                        for transition in transitions {
+                            println!("{transition}");
+                        }
+                    }
+                }
+                Err(err) => eprintln!("Could not report check results to aziot-edged: {err:#}"),
+            }
+
+            tokio::time::sleep(interval).await;
+        }
+    }
+}
+
+fn check_report(
+    id: &str,
+    description: &str,
+    result: &CheckResult,
+    warnings_as_errors: bool,
+) -> CheckReport {
+    let (status, details) = match result {
+        CheckResult::Ok => (CheckStatus::Ok, Vec::new()),
+        CheckResult::Warning(warning) if !warnings_as_errors => (
+            CheckStatus::Warning,
+            warning.chain().map(ToString::to_string).collect(),
+        ),
+        CheckResult::Ignored => (CheckStatus::Ignored, Vec::new()),
+        CheckResult::Skipped => (CheckStatus::Skipped, Vec::new()),
+        CheckResult::SkippedDueTo(reason) => (CheckStatus::Skipped, vec![reason.clone()]),
+        CheckResult::Fatal(err) => (
+            CheckStatus::Fatal,
+            err.chain().map(ToString::to_string).collect(),
+        ),
+        CheckResult::Warning(err) | CheckResult::Failed(err) => (
+            CheckStatus::Failed,
+            err.chain().map(ToString::to_string).collect(),
+        ),
+    };
+
+    CheckReport {
+        id: id.to_owned(),
+        description: description.to_owned(),
+        status,
+        details,
+    }
 }
 
 fn get_proxy_uri(arg: Option<String>) -> Option<String> {
@@ -798,6 +896,7 @@ mod tests {
             false,                // unused for this test
             false,                // unused for this test
             "".into(),            // unused for this test
+            "checks.d".into(),    // unused for this test
             None,                 // unused for this test
             None,                 // unused for this test
         );
@@ -874,7 +973,8 @@ mod tests {
                 super::OutputFormat::Text, // unused for this test
                 false,
                 false,
-                "".into(), // unused for this test
+                "".into(),         // unused for this test
+                "checks.d".into(), // unused for this test
                 None,
                 None,
             );
@@ -913,7 +1013,8 @@ mod tests {
             super::OutputFormat::Text, // unused for this test
             false,
             false,
-            "".into(), // unused for this test
+            "".into(),         // unused for this test
+            "checks.d".into(), // unused for this test
             None,
             None,
         );
@@ -1,16 +1,16 @@
 use crate::check::Check;
 
 #[derive(Debug, Copy, Clone, serde::Serialize)]
-pub struct CheckerMeta {
+pub struct CheckerMeta<'a> {
     /// Unique human-readable identifier for the check.
-    pub id: &'static str,
+    pub id: &'a str,
     /// A brief description of what this check does.
-    pub description: &'static str,
+    pub description: &'a str,
 }
 
 #[async_trait::async_trait]
 pub trait Checker: erased_serde::Serialize {
-    fn meta(&self) -> CheckerMeta;
+    fn meta(&self) -> CheckerMeta<'_>;
 
     async fn execute(&mut self, shared: &mut Check) -> CheckResult;
 }
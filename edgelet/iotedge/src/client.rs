@@ -2,12 +2,13 @@ use std::{collections::HashMap, time::Duration};
 
 use anyhow::Context;
 use bytes::Bytes;
-use http_body_util::Empty;
-use hyper::{Uri, body::Incoming};
+use http_body_util::{BodyExt as _, Empty};
+use hyper::Uri;
 use url::Url;
 
 use edgelet_core::{
-    LogOptions, Module, ModuleRegistry, ModuleRuntime, ModuleRuntimeState, SystemInfo,
+    CertInfo, CheckReport, CheckTransition, EventStream, ImagePruneReport, LogOptions, LogStream,
+    Module, ModuleMetrics, ModuleRegistry, ModuleRuntime, ModuleRuntimeState, SystemInfo,
     SystemResources, UrlExt,
 };
 use edgelet_http::{ListModulesResponse, ModuleDetails};
@@ -17,6 +18,11 @@ use http_common::{Connector, ErrorBody, HttpRequest};
 use crate::error::Error;
 
 const API_VERSION: &str = "2020-07-07";
+const CERTS_API_VERSION: &str = "2026-10-18";
+const CHECKS_API_VERSION: &str = "2026-10-18";
+const ENCRYPTION_KEY_API_VERSION: &str = "2026-10-18";
+const EVENTS_API_VERSION: &str = "2026-10-18";
+const IMAGES_API_VERSION: &str = "2026-10-18";
 
 #[derive(serde::Serialize, Clone)]
 pub struct MgmtConfig {}
@@ -54,6 +60,160 @@ impl MgmtClient {
 
         Ok(uri)
     }
+
+    /// Gets the module events recorded since `since` as newline-delimited JSON. If `follow` is
+    /// set, the stream stays open and new events are appended as they occur.
+    pub async fn events(&self, since: Option<&str>, follow: bool) -> anyhow::Result<LogStream> {
+        let uri = {
+            let mut query = ::url::form_urlencoded::Serializer::new(String::new());
+            query
+                .append_pair("api-version", EVENTS_API_VERSION)
+                .append_pair("follow", &follow.to_string());
+            if let Some(since) = since {
+                query.append_pair("since", since);
+            }
+            let query = query.finish();
+            self.get_uri(&format!("/events?{query}"))?
+        };
+
+        let req = hyper::Request::builder()
+            .method(hyper::Method::GET)
+            .uri(uri)
+            .body(Empty::<Bytes>::new())
+            .expect("could not build hyper::Request");
+        let client = self.connector.clone().into_client();
+        let resp = client.request(req).await.context(Error::ModuleRuntime)?;
+
+        let (hyper::http::response::Parts { status, .. }, body) = resp.into_parts();
+        if status.is_success() {
+            Ok(body.map_err(Into::into).boxed())
+        } else {
+            Err(Error::Misc(format!("Bad status code when calling events: {status}")).into())
+        }
+    }
+
+    /// Gets the progress of the image pulls in progress as newline-delimited JSON. If `follow`
+    /// is set, the stream stays open and progress is appended as it is reported.
+    pub async fn pulls(&self, follow: bool) -> anyhow::Result<LogStream> {
+        let uri = {
+            let query = ::url::form_urlencoded::Serializer::new(String::new())
+                .append_pair("api-version", IMAGES_API_VERSION)
+                .append_pair("follow", &follow.to_string())
+                .finish();
+            self.get_uri(&format!("/images/pulls?{query}"))?
+        };
+
+        let req = hyper::Request::builder()
+            .method(hyper::Method::GET)
+            .uri(uri)
+            .body(Empty::<Bytes>::new())
+            .expect("could not build hyper::Request");
+        let client = self.connector.clone().into_client();
+        let resp = client.request(req).await.context(Error::ModuleRuntime)?;
+
+        let (hyper::http::response::Parts { status, .. }, body) = resp.into_parts();
+        if status.is_success() {
+            Ok(body.map_err(Into::into).boxed())
+        } else {
+            Err(Error::Misc(format!("Bad status code when calling pulls: {status}")).into())
+        }
+    }
+
+    /// Rotates the master key of the workload encrypt API. Returns the version of the new key.
+    pub async fn rotate_encryption_key(&self) -> anyhow::Result<u32> {
+        #[derive(serde::Deserialize)]
+        struct RotateResponse {
+            version: u32,
+        }
+
+        let uri = self.get_uri(&format!(
+            "/encryption-key/rotate?api-version={ENCRYPTION_KEY_API_VERSION}"
+        ))?;
+
+        let request: HttpRequest<(), _> = HttpRequest::post(self.connector.clone(), &uri, None);
+
+        let response = request
+            .json_response()
+            .await
+            .context(Error::ModuleRuntime)?;
+        let response = response
+            .parse_expect_ok::<RotateResponse, ErrorBody<'_>>()
+            .context(Error::ModuleRuntime)?;
+
+        Ok(response.version)
+    }
+
+    /// Removes the images that no module has used for `older_than`, or for the configured
+    /// threshold if unset. With `dry_run`, only reports the images that would be removed.
+    pub async fn prune_images(
+        &self,
+        older_than: Option<&str>,
+        dry_run: bool,
+    ) -> anyhow::Result<ImagePruneReport> {
+        let uri = self.get_uri(&format!("/images/prune?api-version={IMAGES_API_VERSION}"))?;
+        let body = serde_json::json!({ "dryRun": dry_run, "olderThan": older_than });
+
+        let request = HttpRequest::post(self.connector.clone(), &uri, Some(body));
+
+        let response = request
+            .json_response()
+            .await
+            .context(Error::ModuleRuntime)?;
+        let response = response
+            .parse_expect_ok::<ImagePruneReport, ErrorBody<'_>>()
+            .context(Error::ModuleRuntime)?;
+
+        Ok(response)
+    }
+
+    /// Lists the certificates of aziot-edged, the device identity and the modules.
+    pub async fn list_certificates(&self) -> anyhow::Result<Vec<CertInfo>> {
+        #[derive(serde::Deserialize)]
+        struct ListCertificatesResponse {
+            certificates: Vec<CertInfo>,
+        }
+
+        let uri = self.get_uri(&format!("/certificates?api-version={CERTS_API_VERSION}"))?;
+
+        let request: HttpRequest<(), _> = HttpRequest::get(self.connector.clone(), &uri);
+
+        let response = request
+            .json_response()
+            .await
+            .context(Error::ModuleRuntime)?;
+        let response = response
+            .parse_expect_ok::<ListCertificatesResponse, ErrorBody<'_>>()
+            .context(Error::ModuleRuntime)?;
+
+        Ok(response.certificates)
+    }
+
+    /// Reports the results of an `iotedge check` run, and returns the checks whose status
+    /// changed since the last run that was reported.
+    pub async fn report_checks(
+        &self,
+        checks: Vec<CheckReport>,
+    ) -> anyhow::Result<Vec<CheckTransition>> {
+        #[derive(serde::Deserialize)]
+        struct ReportChecksResponse {
+            transitions: Vec<CheckTransition>,
+        }
+
+        let uri = self.get_uri(&format!("/checks?api-version={CHECKS_API_VERSION}"))?;
+        let body = serde_json::json!({ "checks": checks });
+
+        let request = HttpRequest::post(self.connector.clone(), &uri, Some(body));
+
+        let response = request
+            .json_response()
+            .await
+            .context(Error::ModuleRuntime)?;
+        let response = response
+            .parse_expect_ok::<ReportChecksResponse, ErrorBody<'_>>()
+            .context(Error::ModuleRuntime)?;
+
+        Ok(response.transitions)
+    }
 }
 
 #[async_trait::async_trait]
@@ -123,7 +283,11 @@ impl ModuleRuntime for MgmtClient {
         unimplemented!()
     }
 
-    async fn logs(&self, id: &str, options: &LogOptions) -> anyhow::Result<Incoming> {
+    async fn image_sizes(&self) -> anyhow::Result<HashMap<String, u64>> {
+        unimplemented!()
+    }
+
+    async fn logs(&self, id: &str, options: &LogOptions) -> anyhow::Result<LogStream> {
         let uri = {
             let mut query = ::url::form_urlencoded::Serializer::new(String::new());
             query
@@ -150,7 +314,7 @@ impl ModuleRuntime for MgmtClient {
 
         let (hyper::http::response::Parts { status, .. }, body) = resp.into_parts();
         if status.is_success() {
-            Ok(body)
+            Ok(body.map_err(Into::into).boxed())
         } else {
             Err(Error::Misc(format!("Bad status code when calling logs: {status}")).into())
         }
@@ -174,6 +338,9 @@ impl ModuleRuntime for MgmtClient {
     async fn system_resources(&self) -> anyhow::Result<SystemResources> {
         unimplemented!()
     }
+    async fn module_metrics(&self) -> anyhow::Result<Vec<ModuleMetrics>> {
+        unimplemented!()
+    }
     async fn remove_all(&self) -> anyhow::Result<()> {
         unimplemented!()
     }
@@ -183,6 +350,21 @@ impl ModuleRuntime for MgmtClient {
     async fn module_top(&self, _id: &str) -> anyhow::Result<Vec<i32>> {
         unimplemented!()
     }
+    async fn data_root(&self) -> anyhow::Result<std::path::PathBuf> {
+        unimplemented!()
+    }
+    async fn prune_containers(&self) -> anyhow::Result<u64> {
+        unimplemented!()
+    }
+    async fn prune_volumes(&self) -> anyhow::Result<u64> {
+        unimplemented!()
+    }
+    async fn events(
+        &self,
+        _since: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> anyhow::Result<EventStream> {
+        unimplemented!()
+    }
 
     fn registry(&self) -> &Self::ModuleRegistry {
         unimplemented!()
@@ -223,6 +405,61 @@ impl ModuleRegistry for MgmtClient {
     async fn remove(&self, _name: &str) -> anyhow::Result<()> {
         Ok(())
     }
+
+    async fn image_exists(&self, _config: &Self::Config) -> anyhow::Result<bool> {
+        unimplemented!()
+    }
+
+    async fn load_bundle(&self, _config: &Self::Config) -> anyhow::Result<bool> {
+        unimplemented!()
+    }
+
+    async fn import(&self, path: &std::path::Path) -> anyhow::Result<Vec<String>> {
+        #[derive(serde::Deserialize)]
+        struct ImportResponse {
+            images: Vec<String>,
+        }
+
+        let uri = self.get_uri(&format!("/images/import?api-version={IMAGES_API_VERSION}"))?;
+        let body = serde_json::json!({ "path": path });
+
+        let request = HttpRequest::post(self.connector.clone(), &uri, Some(body));
+
+        let response = request
+            .json_response()
+            .await
+            .context(Error::ModuleRuntime)?;
+        let response = response
+            .parse_expect_ok::<ImportResponse, ErrorBody<'_>>()
+            .context(Error::ModuleRuntime)?;
+
+        Ok(response.images)
+    }
+
+    async fn image_use(&self) -> anyhow::Result<Vec<edgelet_core::ImageUse>> {
+        #[derive(serde::Deserialize)]
+        struct ImageUseResponse {
+            images: Vec<edgelet_core::ImageUse>,
+        }
+
+        let uri = self.get_uri(&format!("/images/usage?api-version={IMAGES_API_VERSION}"))?;
+
+        let request: HttpRequest<(), _> = HttpRequest::get(self.connector.clone(), &uri);
+
+        let response = request
+            .json_response()
+            .await
+            .context(Error::ModuleRuntime)?;
+        let response = response
+            .parse_expect_ok::<ImageUseResponse, ErrorBody<'_>>()
+            .context(Error::ModuleRuntime)?;
+
+        Ok(response.images)
+    }
+
+    fn pull_progress(&self) -> &edgelet_core::PullProgressBus {
+        unimplemented!()
+    }
 }
 
 impl MgmtModule {
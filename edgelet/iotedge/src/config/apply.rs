@@ -227,6 +227,15 @@ async fn execute_inner(
         edge_ca,
         moby_runtime,
         image_garbage_collection,
+        metrics,
+        resource_quotas,
+        container_policy,
+        image_pull,
+        module_certs,
+        data_encryption,
+        edge_ca_rollover,
+        runtime_backend,
+        cri_runtime,
     } = toml::from_str(config).map_err(|err| format!("could not parse config file: {err}"))?;
 
     let aziotctl_common::config::apply::RunOutput {
@@ -238,46 +247,54 @@ async fn execute_inner(
     } = aziotctl_common::config::apply::run(aziot, aziotcs_uid, aziotid_uid)
         .map_err(|err| format!("{err:?}"))?;
 
-    let old_identityd_path = Path::new("/etc/aziot/identityd/config.d/00-super.toml");
-    if let Ok(old_identity_config) = std::fs::read(old_identityd_path) {
-        let old_identity_config = std::str::from_utf8(&old_identity_config)
-            .map_err(|err| format!("error parsing config: {err}"))?;
-
-        if let Ok(aziot_identityd_config::Settings { hostname, .. }) =
-            toml::from_str(old_identity_config)
-        {
-            let new_hostname = &identityd_config.hostname;
-            let moby_runtime = &moby_runtime;
-            let uri = &moby_runtime.uri;
-
-            let client = DockerApiClient::new(
-                Connector::new(uri)
-                    .map_err(|err| format!("Failed to make docker client: {err}"))?,
-            );
+    if runtime_backend == edgelet_settings::RuntimeBackend::Cri && cri_runtime.is_none() {
+        return Err("runtime_backend is \"cri\" but [cri_runtime] is not configured".into());
+    }
 
-            let mut filters = HashMap::new();
-            filters.insert("label", LABELS);
-            let filters = serde_json::to_string(&filters).map_err(|err| format!("{err:?}"))?;
-
-            let containers = client
-                .container_list(
-                    true,  /*all*/
-                    0,     /*limit*/
-                    false, /*size*/
-                    &filters,
-                )
-                .await
-                .map_err(|err| format!("{err:?}"))?;
-            if &hostname != new_hostname && !containers.is_empty() {
-                return Err(format!("Cannot apply config because the hostname in the config {} is different from the previous hostname {}. To update the hostname, run the following command which deletes all IoT Edge modules and reapplies the configuration. Or, revert the hostname change in the config.toml file.
+    // The hostname check below lists containers through the Moby API, so it only applies
+    // when Moby is the module runtime.
+    if runtime_backend == edgelet_settings::RuntimeBackend::Moby {
+        let old_identityd_path = Path::new("/etc/aziot/identityd/config.d/00-super.toml");
+        if let Ok(old_identity_config) = std::fs::read(old_identityd_path) {
+            let old_identity_config = std::str::from_utf8(&old_identity_config)
+                .map_err(|err| format!("error parsing config: {err}"))?;
+
+            if let Ok(aziot_identityd_config::Settings { hostname, .. }) =
+                toml::from_str(old_identity_config)
+            {
+                let new_hostname = &identityd_config.hostname;
+                let moby_runtime = &moby_runtime;
+                let uri = &moby_runtime.uri;
+
+                let client = DockerApiClient::new(
+                    Connector::new(uri)
+                        .map_err(|err| format!("Failed to make docker client: {err}"))?,
+                );
+
+                let mut filters = HashMap::new();
+                filters.insert("label", LABELS);
+                let filters = serde_json::to_string(&filters).map_err(|err| format!("{err:?}"))?;
+
+                let containers = client
+                    .container_list(
+                        true,  /*all*/
+                        0,     /*limit*/
+                        false, /*size*/
+                        &filters,
+                    )
+                    .await
+                    .map_err(|err| format!("{err:?}"))?;
+                if &hostname != new_hostname && !containers.is_empty() {
+                    return Err(format!("Cannot apply config because the hostname in the config {} is different from the previous hostname {}. To update the hostname, run the following command which deletes all IoT Edge modules and reapplies the configuration. Or, revert the hostname change in the config.toml file.
                     sudo iotedge system stop && sudo docker rm -f $(sudo docker ps -aq -f \"label=net.azure-devices.edge.owner=Microsoft.Azure.Devices.Edge.Agent\") && sudo iotedge config apply
                 Warning: Data stored in the modules is lost when above command is executed.", &hostname, &new_hostname).into());
+                }
+            } else {
+                println!("Warning: the previous identity config file is unreadable");
             }
         } else {
             println!("Warning: the previous identity config file is unreadable");
         }
-    } else {
-        println!("Warning: the previous identity config file is unreadable");
     }
     let mut iotedge_authorized_certs = vec![
         edgelet_settings::AZIOT_EDGED_CA_ALIAS.to_owned(),
@@ -561,8 +578,24 @@ async fn execute_inner(
             endpoints: Default::default(),
 
             image_garbage_collection,
+
+            metrics,
+
+            resource_quotas,
+
+            container_policy,
+
+            image_pull,
+
+            module_certs,
+
+            data_encryption,
+
+            edge_ca_rollover,
         },
 
+        runtime_backend,
+
         moby_runtime: {
             let super_config::MobyRuntime {
                 uri,
@@ -602,6 +635,8 @@ async fn execute_inner(
                     .transpose()?,
             }
         },
+
+        cri_runtime,
     };
 
     let header = String::from(
@@ -505,6 +505,7 @@ fn execute_inner(
                         edgelet_settings::watchdog::MaxRetries::Num(num)
                     }
                 },
+                restart_backoff: edgelet_settings::watchdog::RestartBackoff::default(),
             }
         },
 
@@ -589,6 +590,15 @@ fn execute_inner(
             }
         },
         image_garbage_collection: ImagePruneSettings::default(),
+        metrics: Default::default(),
+        resource_quotas: Default::default(),
+        container_policy: Default::default(),
+        image_pull: Default::default(),
+        module_certs: Default::default(),
+        data_encryption: Default::default(),
+        edge_ca_rollover: Default::default(),
+        runtime_backend: Default::default(),
+        cri_runtime: None,
     };
 
     let config =
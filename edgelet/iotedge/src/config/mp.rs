@@ -96,6 +96,20 @@ To reconfigure IoT Edge, run:
         moby_runtime: Default::default(),
 
         image_garbage_collection: Default::default(),
+
+        metrics: Default::default(),
+
+        resource_quotas: Default::default(),
+
+        container_policy: Default::default(),
+
+        image_pull: Default::default(),
+        module_certs: Default::default(),
+        data_encryption: Default::default(),
+        edge_ca_rollover: Default::default(),
+
+        runtime_backend: Default::default(),
+        cri_runtime: None,
     };
     let config = toml::to_string(&config)
         .map_err(|err| format!("could not serialize system config: {err}"))?;
@@ -61,6 +61,57 @@ pub struct Config {
 
     #[serde(default, skip_serializing_if = "image::ImagePruneSettings::is_default")]
     pub image_garbage_collection: image::ImagePruneSettings,
+
+    #[serde(
+        default,
+        skip_serializing_if = "edgelet_settings::base::metrics::Settings::is_default"
+    )]
+    pub metrics: edgelet_settings::base::metrics::Settings,
+
+    #[serde(
+        default,
+        skip_serializing_if = "edgelet_settings::base::quota::ResourceQuotas::is_default"
+    )]
+    pub resource_quotas: edgelet_settings::base::quota::ResourceQuotas,
+
+    #[serde(
+        default,
+        skip_serializing_if = "edgelet_settings::base::policy::ContainerPolicy::is_default"
+    )]
+    pub container_policy: edgelet_settings::base::policy::ContainerPolicy,
+
+    #[serde(
+        default,
+        skip_serializing_if = "edgelet_settings::base::pull::Settings::is_default"
+    )]
+    pub image_pull: edgelet_settings::base::pull::Settings,
+
+    #[serde(
+        default,
+        skip_serializing_if = "edgelet_settings::base::cert::ModuleCerts::is_default"
+    )]
+    pub module_certs: edgelet_settings::base::cert::ModuleCerts,
+
+    #[serde(
+        default,
+        skip_serializing_if = "edgelet_settings::base::encryption::DataEncryption::is_default"
+    )]
+    pub data_encryption: edgelet_settings::base::encryption::DataEncryption,
+
+    #[serde(
+        default,
+        skip_serializing_if = "edgelet_settings::base::rollover::EdgeCaRollover::is_default"
+    )]
+    pub edge_ca_rollover: edgelet_settings::base::rollover::EdgeCaRollover,
+
+    #[serde(
+        default,
+        skip_serializing_if = "edgelet_settings::RuntimeBackend::is_default"
+    )]
+    pub runtime_backend: edgelet_settings::RuntimeBackend,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cri_runtime: Option<edgelet_settings::CriRuntime>,
 }
 
 pub fn default_agent() -> edgelet_settings::ModuleSpec<edgelet_settings::DockerConfig> {
@@ -0,0 +1,36 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::io::Write;
+
+use anyhow::Context;
+
+use crate::MgmtClient;
+use crate::error::Error;
+
+pub struct EncryptionKey<W> {
+    client: MgmtClient,
+    output: W,
+}
+
+impl<W> EncryptionKey<W>
+where
+    W: Write,
+{
+    pub fn new(client: MgmtClient, output: W) -> Self {
+        EncryptionKey { client, output }
+    }
+
+    /// Makes a new master key current for the workload encrypt API. Data encrypted with the
+    /// previous keys can still be decrypted until the rotation grace period ends.
+    pub async fn rotate(mut self) -> anyhow::Result<()> {
+        let version = self.client.rotate_encryption_key().await?;
+
+        writeln!(
+            self.output,
+            "Rotated master encryption key to version {version}"
+        )
+        .context(Error::WriteToStdout)?;
+
+        Ok(())
+    }
+}
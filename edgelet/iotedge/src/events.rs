@@ -0,0 +1,80 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::io::Write;
+
+use anyhow::Context;
+use http_body_util::BodyExt as _;
+
+use edgelet_core::ModuleEvent;
+
+use crate::MgmtClient;
+use crate::error::Error;
+
+pub struct Events<W> {
+    client: MgmtClient,
+    since: Option<i32>,
+    follow: bool,
+    output: W,
+}
+
+impl<W> Events<W>
+where
+    W: Write,
+{
+    pub fn new(client: MgmtClient, since: Option<i32>, follow: bool, output: W) -> Self {
+        Events {
+            client,
+            since,
+            follow,
+            output,
+        }
+    }
+
+    pub async fn execute(mut self) -> anyhow::Result<()> {
+        let since = self.since.map(|since| since.to_string());
+        let mut body = self.client.events(since.as_deref(), self.follow).await?;
+
+        let mut pending = Vec::new();
+
+        while let Some(frame) = body.frame().await {
+            let frame = frame
+                .map_err(|err| anyhow::anyhow!(err))
+                .context(Error::ModuleRuntime)?;
+            let Ok(data) = frame.into_data() else {
+                continue;
+            };
+
+            pending.extend_from_slice(&data);
+
+            while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = pending.drain(..=end).collect();
+                let event: ModuleEvent =
+                    serde_json::from_slice(&line).context(Error::ModuleRuntime)?;
+
+                writeln!(self.output, "{}", format_event(&event)).context(Error::WriteToStdout)?;
+                self.output.flush().context(Error::WriteToStdout)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn format_event(event: &ModuleEvent) -> String {
+    let mut fields = vec![
+        event
+            .time()
+            .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        format!("{:<15}", event.kind().to_string()),
+        event.module().unwrap_or("-").to_owned(),
+    ];
+
+    if let Some(image) = event.image() {
+        fields.push(format!("image={image}"));
+    }
+    if let Some(exit_code) = event.exit_code() {
+        fields.push(format!("exitCode={exit_code}"));
+    }
+
+    fields.join(" ")
+}
@@ -0,0 +1,120 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::Context;
+use byte_unit::{Byte, UnitType};
+use chrono::SecondsFormat;
+use tabwriter::TabWriter;
+
+use edgelet_core::ModuleRegistry;
+
+use crate::MgmtClient;
+use crate::error::Error;
+
+pub struct Image<W> {
+    client: MgmtClient,
+    output: W,
+}
+
+impl<W> Image<W>
+where
+    W: Write,
+{
+    pub fn new(client: MgmtClient, output: W) -> Self {
+        Image { client, output }
+    }
+
+    /// Loads the images of a `docker save` tarball or an OCI image layout directory into the
+    /// container runtime, where image garbage collection tracks them like pulled images.
+    pub async fn import(mut self, path: &Path) -> anyhow::Result<()> {
+        // aziot-edged reads the bundle, so it needs a path that does not depend on the current
+        // directory of this process.
+        let path = std::fs::canonicalize(path)
+            .with_context(|| format!("could not find {}", path.display()))?;
+
+        let images = self.client.import(&path).await?;
+
+        for image in images {
+            writeln!(self.output, "Imported {image}").context(Error::WriteToStdout)?;
+        }
+
+        Ok(())
+    }
+
+    /// Lists the images that image garbage collection tracks, with the names and modules they
+    /// were used as and when.
+    pub async fn list(self) -> anyhow::Result<()> {
+        let images = self.client.image_use().await?;
+
+        let mut w = TabWriter::new(self.output).minwidth(15);
+        writeln!(w, "IMAGE ID\tTAGS\tMODULES\tFIRST USED\tLAST USED\tSIZE")
+            .context(Error::WriteToStdout)?;
+        for image in images {
+            writeln!(
+                w,
+                "{}\t{}\t{}\t{}\t{}\t{}",
+                image.id,
+                join_or_dash(&image.tags),
+                join_or_dash(&image.modules),
+                image.first_used.to_rfc3339_opts(SecondsFormat::Secs, true),
+                image.last_used.to_rfc3339_opts(SecondsFormat::Secs, true),
+                image.size.map_or_else(|| "-".to_string(), pretty_bytes),
+            )
+            .context(Error::WriteToStdout)?;
+        }
+        w.flush().context(Error::WriteToStdout)?;
+
+        Ok(())
+    }
+
+    /// Removes the images that no module has used for `older_than`, such as "2d", or for the
+    /// configured `image_age_cleanup_threshold` if unset.
+    pub async fn prune(mut self, older_than: Option<&str>, dry_run: bool) -> anyhow::Result<()> {
+        let report = self.client.prune_images(older_than, dry_run).await?;
+
+        let verb = if dry_run { "Would remove" } else { "Removed" };
+
+        for image in &report.images {
+            let size = image
+                .size
+                .map_or_else(|| "unknown size".to_string(), pretty_bytes);
+            writeln!(
+                self.output,
+                "{verb} {} ({size}): {}",
+                image.id, image.reason
+            )
+            .context(Error::WriteToStdout)?;
+        }
+
+        writeln!(
+            self.output,
+            "{verb} {} images, freeing up to {}",
+            report.images.len(),
+            pretty_bytes(report.space_reclaimed)
+        )
+        .context(Error::WriteToStdout)?;
+
+        Ok(())
+    }
+}
+
+fn join_or_dash(values: &std::collections::BTreeSet<String>) -> String {
+    if values.is_empty() {
+        "-".to_string()
+    } else {
+        values
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+fn pretty_bytes(bytes: u64) -> String {
+    format!(
+        "{:.2}",
+        Byte::from_u64(bytes).get_appropriate_unit(UnitType::Binary)
+    )
+}
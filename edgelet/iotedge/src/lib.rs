@@ -2,22 +2,32 @@
 
 use serde::Deserialize;
 
+mod certs;
 mod check;
 mod client;
 pub mod config;
+mod encryption_key;
 mod error;
+mod events;
+mod image;
 mod list;
 mod logs;
+mod pulls;
 mod restart;
 mod support_bundle;
 mod system;
 mod version;
 
+pub use crate::certs::Certs;
 pub use crate::check::{Check, OutputFormat};
 pub use crate::client::{MgmtClient, MgmtModule};
+pub use crate::encryption_key::EncryptionKey;
 pub use crate::error::{Error, FetchLatestVersionsReason};
+pub use crate::events::Events;
+pub use crate::image::Image;
 pub use crate::list::List;
 pub use crate::logs::Logs;
+pub use crate::pulls::Pulls;
 pub use crate::restart::Restart;
 pub use crate::support_bundle::SupportBundleCommand;
 pub use crate::system::System;
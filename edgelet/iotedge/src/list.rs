@@ -67,6 +67,24 @@ where
 }
 
 fn humanize_status(status: &ModuleStatus) -> String {
+    let mut description = humanize_runtime_status(status);
+
+    if let Some(next_retry_time) = &status.next_retry_time
+        && let Ok(time) = DateTime::parse_from_rfc3339(next_retry_time)
+    {
+        description += &format!(", restarting {}", format_time(time, Tense::Future));
+    }
+
+    match status.restart_count {
+        Some(1) => description += " (restarted 1 time)",
+        Some(count) => description += &format!(" (restarted {count} times)"),
+        None => (),
+    }
+
+    description
+}
+
+fn humanize_runtime_status(status: &ModuleStatus) -> String {
     let status_enum = status.runtime_status.status.parse().unwrap_or_default();
     match status_enum {
         ModuleStatusEnum::Unknown => "Unknown".to_string(),
@@ -101,6 +119,23 @@ fn humanize_status(status: &ModuleStatus) -> String {
 
             "Up".to_string()
         }
+        ModuleStatusEnum::Unhealthy => {
+            let failing_streak = status
+                .health
+                .as_ref()
+                .map_or(0, |health| health.failing_streak);
+
+            if let Some(start_time) = &status.start_time
+                && let Ok(time) = DateTime::parse_from_rfc3339(start_time)
+            {
+                return format!(
+                    "Up {} (unhealthy, {failing_streak} failed checks)",
+                    format_time(time, Tense::Present)
+                );
+            }
+
+            format!("Up (unhealthy, {failing_streak} failed checks)")
+        }
     }
 }
 
@@ -108,7 +143,7 @@ fn format_time<Tz>(time: DateTime<Tz>, tense: Tense) -> String
 where
     Tz: TimeZone,
 {
-    let ht = HumanTime::from(Utc::now().signed_duration_since(time));
+    let ht = HumanTime::from(Utc::now().signed_duration_since(time).abs());
     if ht <= HumanTime::from(Duration::seconds(20)) {
         ht.to_text_en(Accuracy::Precise, tense)
     } else {
@@ -14,8 +14,8 @@ use edgelet_core::{LogOptions, LogTail, parse_since};
 use support_bundle::OutputLocation;
 
 use iotedge::{
-    Check, Error, List, Logs, MgmtClient, OutputFormat, Restart, SupportBundleCommand, System,
-    Version,
+    Certs, Check, EncryptionKey, Error, Events, Image, List, Logs, MgmtClient, OutputFormat, Pulls,
+    Restart, SupportBundleCommand, System, Version,
 };
 
 #[tokio::main]
@@ -50,6 +50,14 @@ async fn run() -> anyhow::Result<()> {
         edgelet_core::version().replace('~', "-")
     );
 
+    let checks_dir_arg = Arg::new("checks-dir")
+        .long("checks-dir")
+        .value_name("DIR")
+        .help("Sets the directory of TOML files that define additional checks.")
+        .num_args(1)
+        .value_parser(clap::value_parser!(PathBuf))
+        .default_value("/etc/aziot/edged/checks.d");
+
     let default_support_bundle_name = format!(
         "support_bundle{}.zip",
         chrono::Utc::now().format("_%Y_%m_%d_%H_%M_%S_%Z")
@@ -91,6 +99,7 @@ async fn run() -> anyhow::Result<()> {
                         .num_args(1)
                         .default_value(&default_diagnostics_image_name),
                 )
+                .arg(checks_dir_arg.clone())
                 .arg(
                     Arg::new("dont-run")
                         .long("dont-run")
@@ -166,9 +175,50 @@ async fn run() -> anyhow::Result<()> {
                         .value_name("WARNINGS_AS_ERRORS")
                         .num_args(0)
                         .help("Treats warnings as errors. Thus 'iotedge check' will exit with non-zero code if it encounters warnings.")
+                )
+                .arg(
+                    Arg::new("watch")
+                        .long("watch")
+                        .num_args(0)
+                        .help("Keeps running the checks every --interval and reports their results to aziot-edged, which serves the latest result of each check on the management API.")
+                )
+                .arg(
+                    Arg::new("interval")
+                        .long("interval")
+                        .value_name("DURATION")
+                        .help("Sets how often --watch runs the checks, like \"5m\" or \"1h\".")
+                        .num_args(1)
+                        .value_parser(|s: &str| match humantime::parse_duration(s) {
+                            Ok(interval) if interval.is_zero() => {
+                                Err("interval must be greater than zero".to_owned())
+                            }
+                            result => result.map_err(|err| err.to_string()),
+                        })
+                        .default_value("5m"),
+                )
+                .arg(
+                    Arg::new("hierarchy")
+                        .long("hierarchy")
+                        .num_args(0)
+                        .conflicts_with("watch")
+                        .help("Instead of the checks, walks the parents of this nested device up to the top layer through their API proxies, and reports their versions, certificates and connectivity as a tree.")
+                )
+                .arg(
+                    Arg::new("config-file")
+                        .short('c')
+                        .long("config-file")
+                        .value_name("FILE")
+                        .help("The path of the IoT Edge system configuration file that --hierarchy reads the parent hostname, trust bundle and Edge CA from")
+                        .num_args(1)
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .default_value("/etc/aziot/config.toml"),
                 ),
         )
-        .subcommand(Command::new("check-list").about("List the checks that are run for 'iotedge check'"))
+        .subcommand(
+            Command::new("check-list")
+                .about("List the checks that are run for 'iotedge check'")
+                .arg(checks_dir_arg),
+        )
         .subcommand(
             Command::new("config")
                 .about("Manage Azure IoT Edge system configuration.")
@@ -301,6 +351,110 @@ async fn run() -> anyhow::Result<()> {
                         .help("Follow output log"),
                 ),
         )
+        .subcommand(
+            Command::new("encryption-key")
+                .about("Manage the master key that modules encrypt data with")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommand(
+                    Command::new("rotate")
+                        .about("Encrypt module data with a new master key; data encrypted with the previous keys can still be decrypted for the rotation grace period"),
+                ),
+        )
+        .subcommand(
+            Command::new("events")
+                .about("Show module lifecycle events")
+                .arg(
+                    Arg::new("since")
+                        .help("Only return events since this time, as a duration (1 day, 90 minutes, 2 days 3 hours 2 minutes), rfc3339 timestamp, or UNIX timestamp")
+                        .long("since")
+                        .num_args(1)
+                        .value_name("DURATION or TIMESTAMP"),
+                )
+                .arg(
+                    Arg::new("follow")
+                        .short('f')
+                        .long("follow")
+                        .num_args(0)
+                        .help("Keep printing new events as they occur"),
+                ),
+        )
+        .subcommand(
+            Command::new("image")
+                .about("Manage module images")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommand(
+                    Command::new("import")
+                        .about("Load images from a 'docker save' tarball or an OCI image layout directory")
+                        .arg(
+                            Arg::new("path")
+                                .help("Path of the tarball or directory")
+                                .required(true)
+                                .value_parser(clap::value_parser!(PathBuf)),
+                        ),
+                )
+                .subcommand(
+                    Command::new("list")
+                        .about("List the images that image garbage collection tracks, with the modules that used them"),
+                )
+                .subcommand(
+                    Command::new("prune")
+                        .about("Remove images that no module has used for a while")
+                        .arg(
+                            Arg::new("dry-run")
+                                .long("dry-run")
+                                .num_args(0)
+                                .help("Only show the images that would be removed"),
+                        )
+                        .arg(
+                            Arg::new("older-than")
+                                .long("older-than")
+                                .value_name("DURATION")
+                                .help("Remove images unused for at least this long, like \"2d\" or \"12h\". Defaults to image_age_cleanup_threshold in config.toml"),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("pulls")
+                .about("Show the progress of image pulls")
+                .arg(
+                    Arg::new("follow")
+                        .short('f')
+                        .long("follow")
+                        .num_args(0)
+                        .help("Keep printing progress as it is reported"),
+                ),
+        )
+        .subcommand(
+            Command::new("certs")
+                .about("Inspect the certificates of IoT Edge")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommand(
+                    Command::new("list")
+                        .about("List the Edge CA, trust bundle, device identity and module certificates, with their expiry. Module certificates issued before aziot-edged last restarted are not listed.")
+                        .arg(
+                            Arg::new("output")
+                                .long("output")
+                                .short('o')
+                                .value_name("FORMAT")
+                                .help("Output format")
+                                .num_args(1)
+                                .value_parser(["json", "text"])
+                                .default_value("text"),
+                        )
+                        .arg(
+                            Arg::new("warning-threshold")
+                                .long("warning-threshold")
+                                .value_name("DURATION")
+                                .help("Flags certificates that expire within this long, like \"30d\" or \"12h\"")
+                                .num_args(1)
+                                .value_parser(humantime::parse_duration)
+                                .default_value("30d"),
+                        ),
+                ),
+        )
         .subcommand(
             Command::new("system")
                 .about("Manage system services for IoT Edge.")
@@ -414,40 +568,66 @@ async fn run() -> anyhow::Result<()> {
         .expect("Command::subcommand_required was set, but ArgMatches::subcommand was None")
     {
         ("check", args) => {
-            let mut check = Check::new(
-                args.get_one::<PathBuf>("container-engine-config-file")
-                    .expect("arg has a default value")
-                    .into(),
-                args.get_one::<String>("diagnostics-image-name")
-                    .expect("arg has a default value")
-                    .clone(),
-                args.get_many::<String>("dont-run")
-                    .into_iter()
-                    .flatten()
-                    .cloned()
-                    .collect(),
-                args.get_one::<String>("expected-aziot-edged-version")
-                    .cloned(),
-                args.get_one::<String>("expected-aziot-version").cloned(),
-                args.get_one::<PathBuf>("aziot-edged")
-                    .expect("arg has a default value")
-                    .into(),
-                args.get_one::<String>("output")
-                    .map(|arg| match &**arg {
-                        "json" => OutputFormat::Json,
-                        "text" => OutputFormat::Text,
-                        _ => unreachable!(),
-                    })
-                    .expect("arg has a default value"),
-                args.get_flag("verbose"),
-                args.get_flag("warnings-as-errors"),
-                aziot_bin.into(),
-                args.get_one::<String>("iothub-hostname").cloned(),
-                args.get_one::<String>("proxy-uri").cloned(),
-            );
-            check.execute().await
+            let new_check = || {
+                Check::new(
+                    args.get_one::<PathBuf>("container-engine-config-file")
+                        .expect("arg has a default value")
+                        .into(),
+                    args.get_one::<String>("diagnostics-image-name")
+                        .expect("arg has a default value")
+                        .clone(),
+                    args.get_many::<String>("dont-run")
+                        .into_iter()
+                        .flatten()
+                        .cloned()
+                        .collect(),
+                    args.get_one::<String>("expected-aziot-edged-version")
+                        .cloned(),
+                    args.get_one::<String>("expected-aziot-version").cloned(),
+                    args.get_one::<PathBuf>("aziot-edged")
+                        .expect("arg has a default value")
+                        .into(),
+                    args.get_one::<String>("output")
+                        .map(|arg| match &**arg {
+                            "json" => OutputFormat::Json,
+                            "text" => OutputFormat::Text,
+                            _ => unreachable!(),
+                        })
+                        .expect("arg has a default value"),
+                    args.get_flag("verbose"),
+                    args.get_flag("warnings-as-errors"),
+                    aziot_bin.into(),
+                    args.get_one::<PathBuf>("checks-dir")
+                        .expect("arg has a default value")
+                        .clone(),
+                    args.get_one::<String>("iothub-hostname").cloned(),
+                    args.get_one::<String>("proxy-uri").cloned(),
+                )
+            };
+
+            if args.get_flag("hierarchy") {
+                let config_file = args
+                    .get_one::<PathBuf>("config-file")
+                    .expect("arg has a default value");
+
+                new_check().hierarchy(config_file).await
+            } else if args.get_flag("watch") {
+                let interval = *args
+                    .get_one::<std::time::Duration>("interval")
+                    .expect("arg has a default value");
+
+                Check::watch(new_check, interval, &runtime()?).await
+            } else {
+                new_check().execute().await
+            }
+        }
+        ("check-list", args) => {
+            let checks_dir = args
+                .get_one::<PathBuf>("checks-dir")
+                .expect("arg has a default value");
+
+            Check::print_list(aziot_bin, checks_dir).await
         }
-        ("check-list", _) => Check::print_list(aziot_bin).await,
         ("config", args) => {
             match args
                 .subcommand()
@@ -542,6 +722,82 @@ async fn run() -> anyhow::Result<()> {
 
             Logs::new(id, options, runtime()?).execute().await
         }
+        ("encryption-key", args) => match args
+            .subcommand()
+            .expect("Command::subcommand_required was set, but ArgMatches::subcommand was None")
+        {
+            ("rotate", _) => EncryptionKey::new(runtime()?, io::stdout()).rotate().await,
+            (command, _) => {
+                eprintln!("Unknown encryption-key subcommand: {command}");
+                std::process::exit(1);
+            }
+        },
+        ("events", args) => {
+            let since = args
+                .get_one::<String>("since")
+                .map(|s| parse_since(s))
+                .transpose()
+                .context(Error::BadSinceParameter)?;
+            let follow = args.get_flag("follow");
+
+            Events::new(runtime()?, since, follow, io::stdout())
+                .execute()
+                .await
+        }
+        ("image", args) => match args
+            .subcommand()
+            .expect("Command::subcommand_required was set, but ArgMatches::subcommand was None")
+        {
+            ("import", args) => {
+                let path = args.get_one::<PathBuf>("path").expect("arg is required");
+
+                Image::new(runtime()?, io::stdout()).import(path).await
+            }
+            ("list", _) => Image::new(runtime()?, io::stdout()).list().await,
+            ("prune", args) => {
+                let older_than = args.get_one::<String>("older-than");
+                let dry_run = args.get_flag("dry-run");
+
+                Image::new(runtime()?, io::stdout())
+                    .prune(older_than.map(String::as_str), dry_run)
+                    .await
+            }
+            (command, _) => {
+                eprintln!("Unknown image subcommand: {command}");
+                std::process::exit(1);
+            }
+        },
+        ("pulls", args) => {
+            let follow = args.get_flag("follow");
+
+            Pulls::new(runtime()?, follow, io::stdout()).execute().await
+        }
+        ("certs", args) => match args
+            .subcommand()
+            .expect("Command::subcommand_required was set, but ArgMatches::subcommand was None")
+        {
+            ("list", args) => {
+                let warning_threshold = *args
+                    .get_one::<std::time::Duration>("warning-threshold")
+                    .expect("arg has a default value");
+                let output_format = args
+                    .get_one::<String>("output")
+                    .map(|arg| match &**arg {
+                        "json" => OutputFormat::Json,
+                        "text" => OutputFormat::Text,
+                        _ => unreachable!(),
+                    })
+                    .expect("arg has a default value");
+
+                Certs::new(runtime()?, io::stdout())
+                    .list(warning_threshold, output_format)
+                    .await
+            }
+            (command, _) => {
+                eprintln!("Unknown certs subcommand: {command}");
+                std::process::exit(1);
+            }
+        },
         ("system", args) => (match args
             .subcommand()
             .expect("Command::subcommand_required was set, but ArgMatches::subcommand was None")
@@ -0,0 +1,81 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::io::Write;
+
+use anyhow::Context;
+use http_body_util::BodyExt as _;
+
+use edgelet_core::PullProgress;
+
+use crate::MgmtClient;
+use crate::error::Error;
+
+pub struct Pulls<W> {
+    client: MgmtClient,
+    follow: bool,
+    output: W,
+}
+
+impl<W> Pulls<W>
+where
+    W: Write,
+{
+    pub fn new(client: MgmtClient, follow: bool, output: W) -> Self {
+        Pulls {
+            client,
+            follow,
+            output,
+        }
+    }
+
+    pub async fn execute(mut self) -> anyhow::Result<()> {
+        let mut body = self.client.pulls(self.follow).await?;
+
+        let mut pending = Vec::new();
+
+        while let Some(frame) = body.frame().await {
+            let frame = frame
+                .map_err(|err| anyhow::anyhow!(err))
+                .context(Error::ModuleRuntime)?;
+            let Ok(data) = frame.into_data() else {
+                continue;
+            };
+
+            pending.extend_from_slice(&data);
+
+            while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = pending.drain(..=end).collect();
+                let progress: PullProgress =
+                    serde_json::from_slice(&line).context(Error::ModuleRuntime)?;
+
+                writeln!(self.output, "{}", format_progress(&progress))
+                    .context(Error::WriteToStdout)?;
+                self.output.flush().context(Error::WriteToStdout)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn format_progress(progress: &PullProgress) -> String {
+    let mut fields = vec![
+        progress
+            .time()
+            .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        progress.image().to_owned(),
+        format!("{:<12}", progress.layer().unwrap_or("-")),
+        format!("{:<14}", progress.status().to_string()),
+    ];
+
+    match (progress.current(), progress.total()) {
+        (Some(current), Some(total)) => fields.push(format!("{current}/{total} bytes")),
+        (Some(current), None) => fields.push(format!("{current} bytes")),
+        _ => (),
+    }
+    if let Some(message) = progress.message() {
+        fields.push(message.to_owned());
+    }
+
+    fields.join(" ")
+}
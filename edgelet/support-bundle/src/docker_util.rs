@@ -0,0 +1,139 @@
+// Copyright (c) Microsoft. All rights reserved.
+
+use std::io::{Seek, Write};
+
+use anyhow::Context;
+use zip::{ZipWriter, write::FileOptions};
+
+use docker::apis::ApiError;
+use docker::{DockerApi, DockerApiClient};
+use http_common::Connector;
+
+use crate::error::Error;
+use crate::shell_util::print_verbose;
+
+const DEFAULT_DOCKER_HOST: &str = "unix:///var/run/docker.sock";
+
+/// A client for the container engine that the docker CLI would use: the one at `DOCKER_HOST`, or
+/// the default socket.
+pub fn docker_client() -> anyhow::Result<DockerApiClient<Connector>> {
+    let host = std::env::var("DOCKER_HOST").unwrap_or_else(|_| DEFAULT_DOCKER_HOST.to_owned());
+    let host = url::Url::parse(&host).with_context(|| format!("invalid DOCKER_HOST {host}"))?;
+    let connector = Connector::new(&host).context(Error::SupportBundle)?;
+
+    Ok(DockerApiClient::new(connector))
+}
+
+pub async fn write_inspect<W>(
+    docker: &DockerApiClient<Connector>,
+    module_name: &str,
+    zip_writer: &mut ZipWriter<W>,
+    file_options: &FileOptions<'_, ()>,
+    verbose: bool,
+) -> anyhow::Result<()>
+where
+    W: Write + Seek,
+{
+    print_verbose(format!("Running docker inspect for {module_name}"), verbose);
+
+    let inspect = docker.container_inspect_raw(module_name, false).await;
+    let (file_name, output) = to_file("inspect", module_name, inspect)?;
+
+    zip_writer
+        .start_file(file_name, *file_options)
+        .context(Error::SupportBundle)?;
+
+    zip_writer
+        .write_all(&output)
+        .context(Error::SupportBundle)?;
+
+    print_verbose(format!("Got docker inspect for {module_name}"), verbose);
+
+    Ok(())
+}
+
+pub async fn get_docker_networks(
+    docker: &DockerApiClient<Connector>,
+) -> Result<Vec<String>, Error> {
+    let result = match docker.network_list("").await {
+        Ok(networks) => networks
+            .into_iter()
+            .filter_map(|network| network.name)
+            .collect(),
+        Err(err) => {
+            println!("Could not find network names: {err}");
+            vec![edgelet_settings::DEFAULT_NETWORKID.to_owned()]
+        }
+    };
+
+    Ok(result)
+}
+
+pub async fn write_network_inspect<W>(
+    docker: &DockerApiClient<Connector>,
+    network_name: &str,
+    zip_writer: &mut ZipWriter<W>,
+    file_options: &FileOptions<'_, ()>,
+    verbose: bool,
+) -> anyhow::Result<()>
+where
+    W: Write + Seek,
+{
+    print_verbose(
+        format!("Running docker network inspect for {network_name}"),
+        verbose,
+    );
+
+    let inspect = docker.network_inspect(network_name, true).await;
+    let (file_name, output) = to_file("network", network_name, inspect)?;
+
+    zip_writer
+        .start_file(file_name, *file_options)
+        .context(Error::SupportBundle)?;
+
+    zip_writer
+        .write_all(&output)
+        .context(Error::SupportBundle)?;
+
+    print_verbose(
+        format!("Got docker network inspect for {network_name}"),
+        verbose,
+    );
+    Ok(())
+}
+
+/// The name and contents of the bundle file for an inspect of `name`. Results are written as an
+/// array like `docker inspect` prints them, errors from docker as `<name>_err.json`, and failures
+/// to reach docker as `<name>_err_docker.txt`.
+fn to_file(
+    directory: &str,
+    name: &str,
+    inspect: anyhow::Result<serde_json::Value>,
+) -> anyhow::Result<(String, Vec<u8>)> {
+    let file = match inspect {
+        Ok(inspect) => (
+            format!("{directory}/{name}.json"),
+            serde_json::to_vec_pretty(&[inspect]).context(Error::SupportBundle)?,
+        ),
+        Err(err) => {
+            if let Some(err) = err.downcast_ref::<ApiError>() {
+                (
+                    format!("{directory}/{name}_err.json"),
+                    serde_json::to_vec_pretty(&serde_json::json!({ "message": err.message }))
+                        .context(Error::SupportBundle)?,
+                )
+            } else {
+                let err_message = format!("{err:?}");
+                println!(
+                    "Could not reach docker. Including error in bundle.\nError message: {err_message}"
+                );
+                (
+                    format!("{directory}/{name}_err_docker.txt"),
+                    err_message.into_bytes(),
+                )
+            }
+        }
+    };
+
+    Ok(file)
+}
@@ -1,5 +1,6 @@
 // Copyright (c) Microsoft. All rights reserved.
 
+mod docker_util;
 mod error;
 mod runtime_util;
 mod shell_util;
@@ -5,7 +5,7 @@ use std::io::{self, Write};
 use anyhow::Context;
 use http_body::Body as _;
 
-use edgelet_core::{LogOptions, Module, ModuleRuntime};
+use edgelet_core::{LogOptions, Module, ModuleRegistry, ModuleRuntime};
 
 use crate::error::Error;
 
@@ -30,6 +30,31 @@ pub async fn get_modules(runtime: &impl ModuleRuntime, include_ms_only: bool) ->
     }
 }
 
+/// Writes the images that image garbage collection tracks as JSON, or nothing if they could
+/// not be queried.
+///
+/// # Errors
+///
+/// Will return `Err` if unable to write the images
+pub async fn write_image_use(
+    runtime: &impl ModuleRuntime,
+    writer: &mut (impl Write + Send),
+) -> anyhow::Result<()> {
+    // From iotedge, this goes through the management socket like the module list does.
+    let images = tokio::time::timeout(
+        std::time::Duration::from_secs(30),
+        runtime.registry().image_use(),
+    );
+
+    if let Ok(Ok(images)) = images.await {
+        serde_json::to_writer_pretty(writer, &images).context(Error::Write)?;
+    } else {
+        println!("Warning: Unable to call management socket. Image use not available.");
+    }
+
+    Ok(())
+}
+
 /// # Errors
 ///
 /// Will return `Err` if docker is unable to fetch logs
@@ -49,6 +74,7 @@ pub async fn write_logs(
         futures_util::future::poll_fn(|cx| std::pin::Pin::new(&mut logs).poll_frame(cx)).await
     {
         let bytes = frame
+            .map_err(|err| anyhow::anyhow!(err))
             .context(Error::Write)?
             .into_data()
             .map_err(|_| io::Error::from(io::ErrorKind::UnexpectedEof))
@@ -45,126 +45,6 @@ pub async fn write_check(
     Ok(())
 }
 
-pub async fn write_inspect<W>(
-    module_name: &str,
-    zip_writer: &mut ZipWriter<W>,
-    file_options: &FileOptions<'_, ()>,
-    verbose: bool,
-) -> anyhow::Result<()>
-where
-    W: Write + Seek,
-{
-    print_verbose(format!("Running docker inspect for {module_name}"), verbose);
-
-    let mut inspect = Command::new("docker");
-    inspect.arg("inspect").arg(module_name);
-    let inspect = inspect.output().await;
-
-    let (file_name, output) = if let Ok(result) = inspect {
-        if result.status.success() {
-            (format!("inspect/{module_name}.json"), result.stdout)
-        } else {
-            (format!("inspect/{module_name}_err.json"), result.stderr)
-        }
-    } else {
-        let err_message = inspect.err().unwrap().to_string();
-        println!(
-            "Could not reach docker. Including error in bundle.\nError message: {err_message}"
-        );
-        (
-            format!("inspect/{module_name}_err_docker.txt"),
-            err_message.as_bytes().to_vec(),
-        )
-    };
-
-    zip_writer
-        .start_file(file_name, *file_options)
-        .context(Error::SupportBundle)?;
-
-    zip_writer
-        .write_all(&output)
-        .context(Error::SupportBundle)?;
-
-    print_verbose(format!("Got docker inspect for {module_name}"), verbose);
-
-    Ok(())
-}
-
-pub async fn get_docker_networks() -> Result<Vec<String>, Error> {
-    let mut inspect = Command::new("docker");
-    inspect.args(["network", "ls"]);
-    inspect.args(["--format", "{{.Name}}"]);
-    let inspect = inspect.output().await;
-
-    let result = if let Ok(result) = inspect {
-        if result.status.success() {
-            String::from_utf8_lossy(&result.stdout).to_string()
-        } else {
-            println!(
-                "Could not find network names: {}",
-                String::from_utf8_lossy(&result.stderr),
-            );
-            edgelet_settings::DEFAULT_NETWORKID.to_owned()
-        }
-    } else {
-        println!("Could not find network names: {}", inspect.err().unwrap());
-        edgelet_settings::DEFAULT_NETWORKID.to_owned()
-    };
-
-    let result = result.lines().map(String::from).collect();
-    Ok(result)
-}
-
-pub async fn write_network_inspect<W>(
-    network_name: &str,
-    zip_writer: &mut ZipWriter<W>,
-    file_options: &FileOptions<'_, ()>,
-    verbose: bool,
-) -> anyhow::Result<()>
-where
-    W: Write + Seek,
-{
-    print_verbose(
-        format!("Running docker network inspect for {network_name}"),
-        verbose,
-    );
-    let mut inspect = Command::new("docker");
-
-    inspect.args(["network", "inspect", network_name, "-v"]);
-    let inspect = inspect.output().await;
-
-    let (file_name, output) = if let Ok(result) = inspect {
-        if result.status.success() {
-            (format!("network/{network_name}.json"), result.stdout)
-        } else {
-            (format!("network/{network_name}_err.json"), result.stderr)
-        }
-    } else {
-        let err_message = inspect.err().unwrap().to_string();
-        println!(
-            "Could not reach docker. Including error in bundle.\nError message: {err_message}",
-        );
-        (
-            format!("network/{network_name}_err_docker.txt"),
-            err_message.as_bytes().to_vec(),
-        )
-    };
-
-    zip_writer
-        .start_file(file_name, *file_options)
-        .context(Error::SupportBundle)?;
-
-    zip_writer
-        .write_all(&output)
-        .context(Error::SupportBundle)?;
-
-    print_verbose(
-        format!("Got docker network inspect for {network_name}"),
-        verbose,
-    );
-    Ok(())
-}
-
 pub async fn write_system_log<W>(
     name: &str,
     unit: &str,
@@ -226,7 +106,7 @@ where
     Ok(())
 }
 
-fn print_verbose<S>(message: S, verbose: bool)
+pub(crate) fn print_verbose<S>(message: S, verbose: bool)
 where
     S: std::fmt::Display,
 {
@@ -9,11 +9,12 @@ use zip::{CompressionMethod, ZipWriter, write::FileOptions};
 
 use edgelet_core::{LogOptions, ModuleRuntime};
 
-use crate::error::Error;
-use crate::runtime_util::{get_modules, write_logs};
-use crate::shell_util::{
-    get_docker_networks, write_check, write_inspect, write_network_inspect, write_system_log,
+use crate::docker_util::{
+    docker_client, get_docker_networks, write_inspect, write_network_inspect,
 };
+use crate::error::Error;
+use crate::runtime_util::{get_modules, write_image_use, write_logs};
+use crate::shell_util::{write_check, write_system_log};
 
 #[cfg(not(feature = "snapctl"))]
 const SYSTEM_MODULES: &[(&str, &str)] = &[
@@ -104,6 +105,8 @@ where
         .context(Error::SupportBundle)?;
     write_check(&mut zip_writer, iothub_hostname, verbose).await?;
 
+    let docker = docker_client()?;
+
     // Get all modules
     for module_name in get_modules(runtime, include_ms_only).await {
         // Write module logs
@@ -113,12 +116,32 @@ where
         write_logs(runtime, &module_name, &log_options, &mut zip_writer).await?;
 
         // write module inspect
-        write_inspect(&module_name, &mut zip_writer, &file_options, verbose).await?;
+        write_inspect(
+            &docker,
+            &module_name,
+            &mut zip_writer,
+            &file_options,
+            verbose,
+        )
+        .await?;
     }
 
+    // Get the images tracked for image garbage collection
+    zip_writer
+        .start_file("image_use.json", file_options)
+        .context(Error::SupportBundle)?;
+    write_image_use(runtime, &mut zip_writer).await?;
+
     // Get all docker network inspects
-    for network_name in get_docker_networks().await? {
-        write_network_inspect(&network_name, &mut zip_writer, &file_options, verbose).await?;
+    for network_name in get_docker_networks(&docker).await? {
+        write_network_inspect(
+            &docker,
+            &network_name,
+            &mut zip_writer,
+            &file_options,
+            verbose,
+        )
+        .await?;
     }
 
     // Get logs for system modules